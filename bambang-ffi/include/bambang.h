@@ -0,0 +1,133 @@
+#ifndef BAMBANG_H
+#define BAMBANG_H
+
+/* Generated by cbindgen from bambang-ffi/src/lib.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ Outcome of a call
+ */
+typedef enum BambangStatus {
+  BAMBANG_STATUS_OK = 0,
+  /*
+   The database refused the call; `bambang_last_error` says why
+   */
+  BAMBANG_STATUS_ERROR = 1,
+  /*
+   A required pointer was NULL, a string was not valid UTF-8 or a scan filter was not
+   understood
+   */
+  BAMBANG_STATUS_INVALID_ARGUMENT = 2,
+  /*
+   The call panicked. It may have stopped part way through a change, so the handle
+   should be closed.
+   */
+  BAMBANG_STATUS_PANIC = 3,
+  /*
+   `bambang_scan_next` returned a row
+   */
+  BAMBANG_STATUS_ROW = 100,
+  /*
+   `bambang_scan_next` has no rows left
+   */
+  BAMBANG_STATUS_DONE = 101,
+} BambangStatus;
+
+/*
+ A scan in progress, reading the table's pages as `bambang_scan_next` asks for rows
+ */
+typedef struct BambangCursor BambangCursor;
+
+/*
+ An open database
+ */
+typedef struct BambangHandle BambangHandle;
+
+/*
+ Open the database at `path`, creating it if it does not exist. Returns NULL on
+ failure, with the reason in `bambang_last_error(NULL)`.
+
+ # Safety
+ `path` must be NULL or a NUL-terminated string.
+ */
+struct BambangHandle *bambang_open(const char *path);
+
+/*
+ Flush and close the database, freeing `handle` even if that fails, in which case the
+ reason is in `bambang_last_error(NULL)`. Closing NULL does nothing.
+
+ # Safety
+ `handle` must be NULL or come from `bambang_open`, not be closed already and have no
+ cursors left.
+ */
+enum BambangStatus bambang_close(struct BambangHandle *handle);
+
+/*
+ Run one SQL statement. Rows a SELECT returns are discarded; read rows with
+ `bambang_scan_begin`.
+
+ # Safety
+ `handle` must be NULL or an open handle, and `sql` NULL or a NUL-terminated string.
+ */
+enum BambangStatus bambang_exec(struct BambangHandle *handle, const char *sql);
+
+/*
+ Start a scan of `table`. `predicate_json` is NULL or a JSON object such as
+ `{"where": "age >= 18"}` whose optional condition takes the syntax of a SQL WHERE
+ clause; without one every row is returned. Returns NULL on failure, with the reason
+ in `bambang_last_error(handle)`.
+
+ The cursor reads the table a page at a time as rows are asked for, so changes made
+ through the handle while it is open may or may not show up in it.
+
+ # Safety
+ `handle` must be NULL or an open handle, and `table` and `predicate_json` NULL or
+ NUL-terminated strings.
+ */
+struct BambangCursor *bambang_scan_begin(struct BambangHandle *handle,
+                                         const char *table,
+                                         const char *predicate_json);
+
+/*
+ Advance `cursor`. Returns `BAMBANG_STATUS_ROW` with the row as a JSON object keyed by
+ column name in `*out_row_json`, to be freed with `bambang_free_string`, or
+ `BAMBANG_STATUS_DONE` with `*out_row_json` set to NULL once the rows run out.
+
+ # Safety
+ `cursor` must be NULL or a cursor not yet ended, and `out_row_json` NULL or valid for
+ writing a pointer.
+ */
+enum BambangStatus bambang_scan_next(struct BambangCursor *cursor, char **out_row_json);
+
+/*
+ Free a cursor from `bambang_scan_begin`, with any rows it has not returned. Ending
+ NULL does nothing.
+
+ # Safety
+ `cursor` must be NULL or come from `bambang_scan_begin` and not be ended already.
+ */
+void bambang_scan_end(struct BambangCursor *cursor);
+
+/*
+ Why the last call on `handle` failed, or NULL if it succeeded. With a NULL handle,
+ why the last `bambang_open` or `bambang_close` on this thread failed.
+
+ # Safety
+ `handle` must be NULL or an open handle.
+ */
+const char *bambang_last_error(const struct BambangHandle *handle);
+
+/*
+ Free a string returned by this library. Freeing NULL does nothing.
+
+ # Safety
+ `text` must be NULL or a string from `bambang_scan_next` not freed already.
+ */
+void bambang_free_string(char *text);
+
+#endif  /* BAMBANG_H */
@@ -0,0 +1,353 @@
+//! C interface to bambang, built as the shared library `libbambang_ffi`.
+//!
+//! `include/bambang.h` declares everything here; regenerate it from this directory with
+//! `cbindgen --config cbindgen.toml --output include/bambang.h` after changing the API.
+//!
+//! Ownership rules:
+//! - A handle from `bambang_open` is freed by `bambang_close`, and a cursor from
+//!   `bambang_scan_begin` by `bambang_scan_end`. Cursors must be ended before their
+//!   handle is closed.
+//! - Rows from `bambang_scan_next` belong to the caller, who frees them with
+//!   `bambang_free_string`.
+//! - Messages from `bambang_last_error` stay owned by the library and are valid until the
+//!   next call on the same handle (or, for a NULL handle, the next open or close on the
+//!   same thread).
+//!
+//! A handle may be used from any thread but only from one at a time. No call unwinds
+//! into the caller: a panic is caught at the boundary and reported as
+//! `BAMBANG_STATUS_PANIC`.
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::{CStr, CString, c_char},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use bambang::{
+    executor::{predicate::Predicate, scan::Scanner, statement::execute_statement},
+    storage::{
+        export::{JsonLayout, JsonRowWriter},
+        schema::TableSchema,
+        storage_manager::StorageManager,
+    },
+    types::{error::DatabaseError, row::Row},
+};
+use serde::Deserialize;
+
+/// Outcome of a call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BambangStatus {
+    Ok = 0,
+    /// The database refused the call; `bambang_last_error` says why
+    Error = 1,
+    /// A required pointer was NULL, a string was not valid UTF-8 or a scan filter was not
+    /// understood
+    InvalidArgument = 2,
+    /// The call panicked. It may have stopped part way through a change, so the handle
+    /// should be closed.
+    Panic = 3,
+    /// `bambang_scan_next` returned a row
+    Row = 100,
+    /// `bambang_scan_next` has no rows left
+    Done = 101,
+}
+
+/// An open database
+pub struct BambangHandle {
+    storage: StorageManager,
+    last_error: Option<CString>,
+}
+
+/// A scan in progress, reading the table's pages as `bambang_scan_next` asks for rows
+pub struct BambangCursor {
+    /// Handle the scan's errors are recorded on
+    handle: *mut BambangHandle,
+    schema: TableSchema,
+    scanner: Box<dyn Scanner>,
+}
+
+/// Filter passed to `bambang_scan_begin`, e.g. `{"where": "age >= 18 AND name LIKE 'A%'"}`.
+/// The condition takes the syntax of `Predicate::parse`; without one every row matches.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScanFilter {
+    #[serde(rename = "where")]
+    condition: Option<String>,
+}
+
+thread_local! {
+    /// Why the last `bambang_open` or `bambang_close` on this thread failed
+    static THREAD_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Why a call failed, before it is reported as a status and message
+enum Failure {
+    Database(DatabaseError),
+    InvalidArgument(String),
+    Panic(String),
+}
+
+impl Failure {
+    fn status(&self) -> BambangStatus {
+        match self {
+            Failure::Database(_) => BambangStatus::Error,
+            Failure::InvalidArgument(_) => BambangStatus::InvalidArgument,
+            Failure::Panic(_) => BambangStatus::Panic,
+        }
+    }
+
+    fn message(&self) -> CString {
+        let message = match self {
+            Failure::Database(e) => e.to_string(),
+            Failure::InvalidArgument(reason) => reason.clone(),
+            Failure::Panic(reason) => format!("panicked: {}", reason),
+        };
+        c_string(message)
+    }
+}
+
+impl From<DatabaseError> for Failure {
+    fn from(e: DatabaseError) -> Self {
+        Failure::Database(e)
+    }
+}
+
+impl BambangHandle {
+    /// Keep the message of a failed call for `bambang_last_error`, or clear the previous
+    /// one if the call succeeded
+    fn record<T>(&mut self, result: Result<T, Failure>) -> Result<T, BambangStatus> {
+        match result {
+            Ok(value) => {
+                self.last_error = None;
+                Ok(value)
+            }
+            Err(failure) => {
+                self.last_error = Some(failure.message());
+                Err(failure.status())
+            }
+        }
+    }
+}
+
+/// Run `f`, turning a panic into a failure instead of unwinding across the C boundary
+fn guarded<T>(f: impl FnOnce() -> Result<T, Failure>) -> Result<T, Failure> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| Err(Failure::Panic(panic_message(payload))))
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown cause".to_string()
+    }
+}
+
+/// `text` as a C string, leaving out any NUL bytes it holds
+fn c_string(text: String) -> CString {
+    CString::new(text.replace('\0', "")).unwrap_or_default()
+}
+
+fn set_thread_error<T>(result: &Result<T, Failure>) -> BambangStatus {
+    let (status, message) = match result {
+        Ok(_) => (BambangStatus::Ok, None),
+        Err(failure) => (failure.status(), Some(failure.message())),
+    };
+    THREAD_ERROR.with(|error| *error.borrow_mut() = message);
+    status
+}
+
+/// Borrow the string argument `name` as UTF-8
+///
+/// # Safety
+/// `value` must be NULL or point to a NUL-terminated string that outlives the call.
+unsafe fn str_arg<'a>(name: &str, value: *const c_char) -> Result<&'a str, Failure> {
+    if value.is_null() {
+        return Err(Failure::InvalidArgument(format!("{} is NULL", name)));
+    }
+    unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .map_err(|e| Failure::InvalidArgument(format!("{} is not valid UTF-8: {}", name, e)))
+}
+
+fn parse_scan_filter(json: &str) -> Result<Option<Predicate>, Failure> {
+    let filter: ScanFilter = serde_json::from_str(json)
+        .map_err(|e| Failure::InvalidArgument(format!("predicate_json is not a scan filter: {}", e)))?;
+    Ok(filter.condition.as_deref().map(Predicate::parse).transpose()?)
+}
+
+/// A row as a JSON object keyed by column name, written as `JsonRowWriter` exports it
+fn row_json(schema: &TableSchema, row: &Row) -> Result<CString, Failure> {
+    let mut buffer = Vec::new();
+    let mut writer = JsonRowWriter::begin_with_layout(&mut buffer, schema, JsonLayout::Lines)?;
+    writer.write_row(row)?;
+    writer.finish()?;
+    // The line break ending the row
+    buffer.pop();
+    Ok(c_string(String::from_utf8_lossy(&buffer).into_owned()))
+}
+
+/// Open the database at `path`, creating it if it does not exist. Returns NULL on
+/// failure, with the reason in `bambang_last_error(NULL)`.
+///
+/// # Safety
+/// `path` must be NULL or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bambang_open(path: *const c_char) -> *mut BambangHandle {
+    let opened = guarded(|| {
+        let path = unsafe { str_arg("path", path) }?;
+        Ok(StorageManager::new(path)?)
+    });
+    set_thread_error(&opened);
+    match opened {
+        Ok(storage) => Box::into_raw(Box::new(BambangHandle { storage, last_error: None })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Flush and close the database, freeing `handle` even if that fails, in which case the
+/// reason is in `bambang_last_error(NULL)`. Closing NULL does nothing.
+///
+/// # Safety
+/// `handle` must be NULL or come from `bambang_open`, not be closed already and have no
+/// cursors left.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bambang_close(handle: *mut BambangHandle) -> BambangStatus {
+    if handle.is_null() {
+        return BambangStatus::Ok;
+    }
+    let handle = unsafe { Box::from_raw(handle) };
+    set_thread_error(&guarded(move || Ok(handle.storage.close()?)))
+}
+
+/// Run one SQL statement. Rows a SELECT returns are discarded; read rows with
+/// `bambang_scan_begin`.
+///
+/// # Safety
+/// `handle` must be NULL or an open handle, and `sql` NULL or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bambang_exec(handle: *mut BambangHandle, sql: *const c_char) -> BambangStatus {
+    let Some(handle) = (unsafe { handle.as_mut() }) else {
+        return BambangStatus::InvalidArgument;
+    };
+    let result = guarded(|| {
+        let sql = unsafe { str_arg("sql", sql) }?;
+        execute_statement(&mut handle.storage, sql)?;
+        Ok(())
+    });
+    match handle.record(result) {
+        Ok(()) => BambangStatus::Ok,
+        Err(status) => status,
+    }
+}
+
+/// Start a scan of `table`. `predicate_json` is NULL or a JSON object such as
+/// `{"where": "age >= 18"}` whose optional condition takes the syntax of a SQL WHERE
+/// clause; without one every row is returned. Returns NULL on failure, with the reason
+/// in `bambang_last_error(handle)`.
+///
+/// The cursor reads the table a page at a time as rows are asked for, so changes made
+/// through the handle while it is open may or may not show up in it.
+///
+/// # Safety
+/// `handle` must be NULL or an open handle, and `table` and `predicate_json` NULL or
+/// NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bambang_scan_begin(
+    handle: *mut BambangHandle,
+    table: *const c_char,
+    predicate_json: *const c_char,
+) -> *mut BambangCursor {
+    let handle_ptr = handle;
+    let Some(handle) = (unsafe { handle.as_mut() }) else {
+        return ptr::null_mut();
+    };
+    let result = guarded(|| {
+        let table = unsafe { str_arg("table", table) }?;
+        let predicate = if predicate_json.is_null() {
+            None
+        } else {
+            parse_scan_filter(unsafe { str_arg("predicate_json", predicate_json) }?)?
+        };
+        let schema = handle
+            .storage
+            .load_table_schema(table)?
+            .ok_or_else(|| DatabaseError::TableNotFound { name: table.to_string() })?
+            .clone();
+        let scanner = handle.storage.create_filtered_scanner(table, predicate)?;
+        Ok(BambangCursor { handle: handle_ptr, schema, scanner })
+    });
+    match handle.record(result) {
+        Ok(cursor) => Box::into_raw(Box::new(cursor)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Advance `cursor`. Returns `BAMBANG_STATUS_ROW` with the row as a JSON object keyed by
+/// column name in `*out_row_json`, to be freed with `bambang_free_string`, or
+/// `BAMBANG_STATUS_DONE` with `*out_row_json` set to NULL once the rows run out.
+///
+/// # Safety
+/// `cursor` must be NULL or a cursor not yet ended, and `out_row_json` NULL or valid for
+/// writing a pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bambang_scan_next(cursor: *mut BambangCursor, out_row_json: *mut *mut c_char) -> BambangStatus {
+    let Some(cursor) = (unsafe { cursor.as_mut() }) else {
+        return BambangStatus::InvalidArgument;
+    };
+    let result = guarded(|| {
+        if out_row_json.is_null() {
+            return Err(Failure::InvalidArgument("out_row_json is NULL".to_string()));
+        }
+        unsafe { *out_row_json = ptr::null_mut() };
+        let Some(row) = cursor.scanner.scan()? else {
+            return Ok(BambangStatus::Done);
+        };
+        let json = row_json(&cursor.schema, &row)?;
+        unsafe { *out_row_json = json.into_raw() };
+        Ok(BambangStatus::Row)
+    });
+    match unsafe { &mut *cursor.handle }.record(result) {
+        Ok(status) | Err(status) => status,
+    }
+}
+
+/// Free a cursor from `bambang_scan_begin`, with any rows it has not returned. Ending
+/// NULL does nothing.
+///
+/// # Safety
+/// `cursor` must be NULL or come from `bambang_scan_begin` and not be ended already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bambang_scan_end(cursor: *mut BambangCursor) {
+    if !cursor.is_null() {
+        drop(unsafe { Box::from_raw(cursor) });
+    }
+}
+
+/// Why the last call on `handle` failed, or NULL if it succeeded. With a NULL handle,
+/// why the last `bambang_open` or `bambang_close` on this thread failed.
+///
+/// # Safety
+/// `handle` must be NULL or an open handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bambang_last_error(handle: *const BambangHandle) -> *const c_char {
+    match unsafe { handle.as_ref() } {
+        Some(handle) => handle.last_error.as_ref().map_or(ptr::null(), |message| message.as_ptr()),
+        None => THREAD_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr())),
+    }
+}
+
+/// Free a string returned by this library. Freeing NULL does nothing.
+///
+/// # Safety
+/// `text` must be NULL or a string from `bambang_scan_next` not freed already.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bambang_free_string(text: *mut c_char) {
+    if !text.is_null() {
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
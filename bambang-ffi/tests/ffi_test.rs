@@ -0,0 +1,214 @@
+use std::{
+    ffi::{CStr, CString, c_char},
+    fs, ptr,
+};
+
+use bambang::utils::mock::create_temp_db_path_with_prefix;
+// Links the library whose symbols the declarations below resolve to
+use bambang_ffi as _;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+enum Status {
+    Ok = 0,
+    Error = 1,
+    InvalidArgument = 2,
+    Panic = 3,
+    Row = 100,
+    Done = 101,
+}
+
+#[repr(C)]
+struct Handle {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct Cursor {
+    _private: [u8; 0],
+}
+
+// The functions as a C caller sees them, declared as in include/bambang.h
+unsafe extern "C" {
+    fn bambang_open(path: *const c_char) -> *mut Handle;
+    fn bambang_close(handle: *mut Handle) -> Status;
+    fn bambang_exec(handle: *mut Handle, sql: *const c_char) -> Status;
+    fn bambang_scan_begin(handle: *mut Handle, table: *const c_char, predicate_json: *const c_char) -> *mut Cursor;
+    fn bambang_scan_next(cursor: *mut Cursor, out_row_json: *mut *mut c_char) -> Status;
+    fn bambang_scan_end(cursor: *mut Cursor);
+    fn bambang_last_error(handle: *const Handle) -> *const c_char;
+    fn bambang_free_string(text: *mut c_char);
+}
+
+fn c(text: &str) -> CString {
+    CString::new(text).unwrap()
+}
+
+fn last_error(handle: *const Handle) -> Option<String> {
+    let message = unsafe { bambang_last_error(handle) };
+    (!message.is_null()).then(|| unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned())
+}
+
+fn exec(handle: *mut Handle, sql: &str) -> Status {
+    let sql = c(sql);
+    unsafe { bambang_exec(handle, sql.as_ptr()) }
+}
+
+/// Every row of `table` matching `filter` as JSON, freeing each string the library returns
+fn scan(handle: *mut Handle, table: &str, filter: Option<&str>) -> Result<Vec<String>, String> {
+    let table = c(table);
+    let filter = filter.map(c);
+    let cursor = unsafe { bambang_scan_begin(handle, table.as_ptr(), filter.as_ref().map_or(ptr::null(), |f| f.as_ptr())) };
+    if cursor.is_null() {
+        return Err(last_error(handle).unwrap());
+    }
+    let mut rows = Vec::new();
+    loop {
+        let mut row: *mut c_char = ptr::null_mut();
+        match unsafe { bambang_scan_next(cursor, &mut row) } {
+            Status::Row => {
+                rows.push(unsafe { CStr::from_ptr(row) }.to_str().unwrap().to_string());
+                unsafe { bambang_free_string(row) };
+            }
+            Status::Done => {
+                assert!(row.is_null());
+                break;
+            }
+            status => panic!("scan_next returned {:?}: {:?}", status, last_error(handle)),
+        }
+    }
+    unsafe { bambang_scan_end(cursor) };
+    Ok(rows)
+}
+
+#[test]
+fn test_open_create_insert_scan_and_close_through_the_c_abi() {
+    let path = create_temp_db_path_with_prefix("ffi");
+    let c_path = c(path.to_str().unwrap());
+    let handle = unsafe { bambang_open(c_path.as_ptr()) };
+    assert!(!handle.is_null(), "{:?}", last_error(ptr::null()));
+
+    assert_eq!(exec(handle, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, age INTEGER)"), Status::Ok);
+    assert_eq!(exec(handle, "INSERT INTO users VALUES (1, 'Ann', 34), (2, 'Bo', 17), (3, NULL, 52)"), Status::Ok);
+    assert_eq!(last_error(handle), None);
+
+    assert_eq!(
+        scan(handle, "users", None).unwrap(),
+        vec![
+            r#"{"id": 1, "name": "Ann", "age": 34}"#,
+            r#"{"id": 2, "name": "Bo", "age": 17}"#,
+            r#"{"id": 3, "name": null, "age": 52}"#,
+        ]
+    );
+    assert_eq!(
+        scan(handle, "users", Some(r#"{"where": "age >= 18 AND name IS NOT NULL"}"#)).unwrap(),
+        vec![r#"{"id": 1, "name": "Ann", "age": 34}"#]
+    );
+    assert_eq!(scan(handle, "users", Some("{}")).unwrap().len(), 3);
+
+    // An unfinished cursor can be ended, and the data outlives the handle
+    let table = c("users");
+    let cursor = unsafe { bambang_scan_begin(handle, table.as_ptr(), ptr::null()) };
+    unsafe { bambang_scan_end(cursor) };
+    assert_eq!(unsafe { bambang_close(handle) }, Status::Ok);
+    assert_eq!(last_error(ptr::null()), None);
+
+    let handle = unsafe { bambang_open(c_path.as_ptr()) };
+    assert_eq!(scan(handle, "users", None).unwrap().len(), 3);
+    assert_eq!(unsafe { bambang_close(handle) }, Status::Ok);
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_errors_are_reported_through_status_and_last_error() {
+    let path = create_temp_db_path_with_prefix("ffi_errors");
+    let c_path = c(path.to_str().unwrap());
+    let handle = unsafe { bambang_open(c_path.as_ptr()) };
+    assert_eq!(exec(handle, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)"), Status::Ok);
+
+    // Database errors keep their message until the next call succeeds
+    assert_eq!(exec(handle, "SELEKT 1"), Status::Error);
+    assert!(last_error(handle).unwrap().contains("parse"), "{:?}", last_error(handle));
+    assert_eq!(exec(handle, "INSERT INTO users VALUES (1, NULL)"), Status::Error);
+    assert!(last_error(handle).unwrap().contains("name"), "{:?}", last_error(handle));
+    assert_eq!(exec(handle, "INSERT INTO users VALUES (1, 'Ann')"), Status::Ok);
+    assert_eq!(last_error(handle), None);
+
+    assert!(scan(handle, "missing", None).unwrap_err().contains("missing"));
+    assert!(scan(handle, "users", Some(r#"{"where": "nope = 1"}"#)).unwrap_err().contains("nope"));
+    assert!(scan(handle, "users", Some(r#"{"select": "id"}"#)).unwrap_err().contains("predicate_json"));
+    assert!(scan(handle, "users", Some("id = 1")).unwrap_err().contains("predicate_json"));
+
+    // Strings are checked before use
+    let invalid_utf8 = CString::new(vec![b'S', 0xff, b'L']).unwrap();
+    assert_eq!(unsafe { bambang_exec(handle, invalid_utf8.as_ptr()) }, Status::InvalidArgument);
+    assert!(last_error(handle).unwrap().contains("UTF-8"));
+    assert_eq!(unsafe { bambang_exec(handle, ptr::null()) }, Status::InvalidArgument);
+    assert_eq!(unsafe { bambang_exec(ptr::null_mut(), ptr::null()) }, Status::InvalidArgument);
+    assert!(unsafe { bambang_scan_begin(ptr::null_mut(), ptr::null(), ptr::null()) }.is_null());
+    assert_eq!(unsafe { bambang_scan_next(ptr::null_mut(), ptr::null_mut()) }, Status::InvalidArgument);
+    let table = c("users");
+    let cursor = unsafe { bambang_scan_begin(handle, table.as_ptr(), ptr::null()) };
+    assert_eq!(unsafe { bambang_scan_next(cursor, ptr::null_mut()) }, Status::InvalidArgument);
+    assert!(last_error(handle).unwrap().contains("out_row_json"));
+    unsafe { bambang_scan_end(cursor) };
+    assert_eq!(unsafe { bambang_close(handle) }, Status::Ok);
+
+    // A failed open has no handle, so its reason is kept for the thread
+    let directory = c(std::env::temp_dir().to_str().unwrap());
+    assert!(unsafe { bambang_open(directory.as_ptr()) }.is_null());
+    assert!(last_error(ptr::null()).is_some());
+    assert!(unsafe { bambang_open(ptr::null()) }.is_null());
+    assert_eq!(last_error(ptr::null()).unwrap(), "path is NULL");
+    unsafe {
+        bambang_free_string(ptr::null_mut());
+        bambang_scan_end(ptr::null_mut());
+        assert_eq!(bambang_close(ptr::null_mut()), Status::Ok);
+    }
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_cursor_reads_pages_as_rows_are_asked_for() {
+    let path = create_temp_db_path_with_prefix("ffi_live_cursor");
+    let c_path = c(path.to_str().unwrap());
+    let handle = unsafe { bambang_open(c_path.as_ptr()) };
+    assert_eq!(exec(handle, "CREATE TABLE items (id INTEGER PRIMARY KEY, padding TEXT)"), Status::Ok);
+    let padding = "x".repeat(200);
+    for chunk in (1..=2000).collect::<Vec<i64>>().chunks(100) {
+        let values: Vec<String> = chunk.iter().map(|id| format!("({}, '{}')", id, padding)).collect();
+        assert_eq!(exec(handle, &format!("INSERT INTO items VALUES {}", values.join(", "))), Status::Ok);
+    }
+
+    // A row stored after the first one is read still reaches the cursor, whose last
+    // pages are only read once it gets there
+    let table = c("items");
+    let cursor = unsafe { bambang_scan_begin(handle, table.as_ptr(), ptr::null()) };
+    assert!(!cursor.is_null(), "{:?}", last_error(handle));
+    let mut count = 0;
+    let mut row: *mut c_char = ptr::null_mut();
+    assert_eq!(unsafe { bambang_scan_next(cursor, &mut row) }, Status::Row);
+    unsafe { bambang_free_string(row) };
+    count += 1;
+    assert_eq!(exec(handle, &format!("INSERT INTO items VALUES (2001, '{}')", padding)), Status::Ok);
+    let mut last = String::new();
+    loop {
+        let mut row: *mut c_char = ptr::null_mut();
+        match unsafe { bambang_scan_next(cursor, &mut row) } {
+            Status::Row => {
+                last = unsafe { CStr::from_ptr(row) }.to_str().unwrap().to_string();
+                unsafe { bambang_free_string(row) };
+                count += 1;
+            }
+            Status::Done => break,
+            status => panic!("scan_next returned {:?}: {:?}", status, last_error(handle)),
+        }
+    }
+    unsafe { bambang_scan_end(cursor) };
+    assert_eq!(count, 2001);
+    assert!(last.starts_with(r#"{"id": 2001,"#), "{}", last);
+
+    assert_eq!(unsafe { bambang_close(handle) }, Status::Ok);
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,218 @@
+use bambang::{
+    storage::{
+        bplus_tree::BPlusTree,
+        options::{StorageManagerOptions, SyncMode},
+        schema::TableKind,
+        storage_manager::StorageManager,
+    },
+    types::{
+        page::{Page, PageType},
+        row::Row,
+        value::Value,
+    },
+    utils::mock::create_temp_db_path_with_prefix,
+};
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+const ROWS_PER_ITERATION: usize = 200;
+
+fn small_row(id: usize) -> Row {
+    Row::new(vec![Value::Integer(id as i64), Value::Text(format!("user_{}", id))])
+}
+
+fn benchmark_small_row_inserts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small_row_insert");
+    group.throughput(Throughput::Elements(ROWS_PER_ITERATION as u64));
+
+    for (label, partial) in [("partial_page_writes", true), ("full_page_writes", false)] {
+        let path = create_temp_db_path_with_prefix("bench_insert");
+        let options = StorageManagerOptions::new().with_partial_page_writes(partial);
+        let mut storage = StorageManager::open_with_options(&path, options).unwrap();
+        storage
+            .create_table("users", "CREATE TABLE users(id INTEGER, name TEXT)")
+            .unwrap();
+
+        let mut next_id = 0;
+        let before = storage.io_stats();
+        group.bench_function(BenchmarkId::from_parameter(label), |b| {
+            b.iter(|| {
+                for _ in 0..ROWS_PER_ITERATION {
+                    storage.insert_into_table("users", small_row(next_id)).unwrap();
+                    next_id += 1;
+                }
+            });
+        });
+        let after = storage.io_stats();
+        eprintln!(
+            "{}: {} rows, {} bytes written ({:.0} per row), {} full / {} partial page writes",
+            label,
+            next_id,
+            after.bytes_written - before.bytes_written,
+            (after.bytes_written - before.bytes_written) as f64 / next_id.max(1) as f64,
+            after.full_page_writes - before.full_page_writes,
+            after.partial_page_writes - before.partial_page_writes,
+        );
+        drop(storage);
+        let _ = std::fs::remove_file(&path);
+    }
+    group.finish();
+}
+
+fn empty_users_table() -> (std::path::PathBuf, StorageManager) {
+    let path = create_temp_db_path_with_prefix("bench_batch_insert");
+    let mut storage = StorageManager::new(&path).unwrap();
+    storage
+        .create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)")
+        .unwrap();
+    (path, storage)
+}
+
+/// Rows in shuffled key order, so neither path gets to append at the end of the tree
+fn shuffled_rows(count: usize) -> Vec<Row> {
+    (0..count).map(|i| small_row(i * 7919 % count)).collect()
+}
+
+/// A batch loaded through `insert_batch_into_table` against the same rows inserted one
+/// at a time, each paying its own descent, page rewrite and flush
+fn benchmark_batch_inserts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_insert");
+    group.sample_size(10);
+
+    for count in [10_000, 50_000] {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("row_at_a_time", count), &count, |b, &count| {
+            b.iter_batched(
+                || (empty_users_table(), shuffled_rows(count)),
+                |((path, mut storage), rows)| {
+                    for row in rows {
+                        storage.insert_into_table("users", row).unwrap();
+                    }
+                    drop(storage);
+                    let _ = std::fs::remove_file(&path);
+                },
+                BatchSize::PerIteration,
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("bulk_load", count), &count, |b, &count| {
+            b.iter_batched(
+                || (empty_users_table(), shuffled_rows(count)),
+                |((path, mut storage), rows)| {
+                    storage.insert_batch_into_table("users", rows).unwrap();
+                    drop(storage);
+                    let _ = std::fs::remove_file(&path);
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+/// The same batch made durable row by row under `SyncMode::Always` and with a single
+/// sync under `SyncMode::OnBatch`; `Off` shows what the syncs themselves cost
+fn benchmark_durable_batch_inserts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("durable_batch_insert");
+    group.sample_size(10);
+    let count = 1_000;
+    group.throughput(Throughput::Elements(count as u64));
+
+    for (label, mode) in [("always", SyncMode::Always), ("on_batch", SyncMode::OnBatch), ("off", SyncMode::Off)] {
+        group.bench_function(BenchmarkId::from_parameter(label), |b| {
+            b.iter_batched(
+                || {
+                    let path = create_temp_db_path_with_prefix("bench_durable_insert");
+                    let options = StorageManagerOptions::new().with_sync_mode(mode);
+                    let mut storage = StorageManager::open_with_options(&path, options).unwrap();
+                    storage
+                        .create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)")
+                        .unwrap();
+                    (path, storage, shuffled_rows(count))
+                },
+                |(path, mut storage, rows)| {
+                    storage.insert_batch_into_table("users", rows).unwrap();
+                    drop(storage);
+                    let _ = std::fs::remove_file(&path);
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+/// A file holding nothing but an empty root leaf
+fn empty_tree_file() -> (std::path::PathBuf, std::fs::File) {
+    let path = create_temp_db_path_with_prefix("bench_btree_insert");
+    std::fs::write(&path, Page::new(1, PageType::LeafTable).to_bytes().unwrap()).unwrap();
+    let file = std::fs::OpenOptions::new().read(true).write(true).open(&path).unwrap();
+    (path, file)
+}
+
+/// Sequential keys straight into a `BPlusTree`, where the cost is the descent and the
+/// leaf rewrite rather than the storage manager's bookkeeping
+fn benchmark_btree_sequential_inserts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("btree_sequential_insert");
+    group.sample_size(10);
+    let count = 10_000;
+    group.throughput(Throughput::Elements(count as u64));
+    group.bench_function(BenchmarkId::from_parameter(count), |b| {
+        b.iter_batched(
+            empty_tree_file,
+            |(path, file)| {
+                let mut btree = BPlusTree::new(file, 1).unwrap();
+                for id in 0..count {
+                    btree.insert(small_row(id), None).unwrap();
+                }
+                drop(btree);
+                let _ = std::fs::remove_file(&path);
+            },
+            BatchSize::PerIteration,
+        );
+    });
+    group.finish();
+}
+
+fn event_row(id: usize) -> Row {
+    Row::new(vec![Value::Timestamp(id as i64 * 1000), Value::Text(format!("event_{}", id))])
+}
+
+/// The same batch of timestamped events into a B+ tree table and into an append log,
+/// which never descends or splits
+fn benchmark_append_log_inserts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("append_log_insert");
+    group.sample_size(10);
+    let count = 10_000;
+    group.throughput(Throughput::Elements(count as u64));
+
+    for (label, kind) in [("btree", TableKind::BTree), ("append_log", TableKind::AppendLog)] {
+        group.bench_function(BenchmarkId::from_parameter(label), |b| {
+            b.iter_batched(
+                || {
+                    let path = create_temp_db_path_with_prefix("bench_append_log");
+                    let mut storage = StorageManager::new(&path).unwrap();
+                    storage
+                        .create_table_with_kind("events", "CREATE TABLE events(at TIMESTAMP, name TEXT)", kind)
+                        .unwrap();
+                    (path, storage, (0..count).map(event_row).collect::<Vec<_>>())
+                },
+                |(path, mut storage, rows)| {
+                    storage.insert_batch_into_table("events", rows).unwrap();
+                    drop(storage);
+                    let _ = std::fs::remove_file(&path);
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_small_row_inserts,
+    benchmark_batch_inserts,
+    benchmark_durable_batch_inserts,
+    benchmark_btree_sequential_inserts,
+    benchmark_append_log_inserts
+);
+criterion_main!(benches);
@@ -0,0 +1,94 @@
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use bambang::{
+    storage::{page_offset_with_size, storage_manager::StorageManager},
+    types::{
+        page::{Page, PageType},
+        row::Row,
+        value::Value,
+    },
+    utils::mock::create_temp_db_path_with_prefix,
+};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+const TABLE_ROWS: i64 = 100_000;
+const LOOKUPS_PER_ITERATION: i64 = 100;
+
+fn users_table(path: &Path) -> StorageManager {
+    let mut storage = StorageManager::new(path).unwrap();
+    storage
+        .create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT, email TEXT)")
+        .unwrap();
+    let rows = (0..TABLE_ROWS)
+        .map(|id| {
+            Row::new(vec![
+                Value::Integer(id),
+                Value::Text(format!("user_{}", id)),
+                Value::Text(format!("user{}@example.com", id)),
+            ])
+        })
+        .collect();
+    storage.insert_batch_into_table("users", rows).unwrap();
+    storage
+}
+
+/// Clear the sorted flag of every B+ tree page, as a version that did not keep cells in
+/// key order would have written them, so lookups take the linear path
+fn strip_sorted_flags(path: &Path, page_count: u64, page_size: usize) {
+    let mut file = OpenOptions::new().read(true).write(true).open(path).unwrap();
+    let mut buffer = vec![0u8; page_size];
+    for page_id in 1..=page_count {
+        let offset = page_offset_with_size(page_id, page_size);
+        file.seek(SeekFrom::Start(offset)).unwrap();
+        file.read_exact(&mut buffer).unwrap();
+        let Ok(mut page) = Page::from_bytes(&buffer) else {
+            continue;
+        };
+        if !matches!(page.page_type, PageType::LeafTable | PageType::InteriorTable) {
+            continue;
+        }
+        page.sorted = false;
+        page.update_checksum();
+        file.seek(SeekFrom::Start(offset)).unwrap();
+        file.write_all(&page.to_bytes().unwrap()).unwrap();
+    }
+}
+
+/// Primary key lookups spread over a 100k-row table, binary searching each page against
+/// the same file with every page searched slot by slot
+fn benchmark_point_lookups(c: &mut Criterion) {
+    let mut group = c.benchmark_group("point_lookup");
+    group.sample_size(20);
+    group.throughput(Throughput::Elements(LOOKUPS_PER_ITERATION as u64));
+
+    let path = create_temp_db_path_with_prefix("bench_point_lookup");
+    let storage = users_table(&path);
+    let (page_count, page_size) = (storage.db_info.page_count, storage.page_size());
+    drop(storage);
+
+    for label in ["binary_search", "linear"] {
+        if label == "linear" {
+            strip_sorted_flags(&path, page_count, page_size);
+        }
+        let storage = StorageManager::new(&path).unwrap();
+        let mut next = 0;
+        group.bench_function(BenchmarkId::new(label, TABLE_ROWS), |b| {
+            b.iter(|| {
+                for _ in 0..LOOKUPS_PER_ITERATION {
+                    let id = next * 7919 % TABLE_ROWS;
+                    next += 1;
+                    assert!(storage.get_by_primary_key("users", &[Value::Integer(id)]).unwrap().is_some());
+                }
+            });
+        });
+    }
+    let _ = std::fs::remove_file(&path);
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_point_lookups);
+criterion_main!(benches);
@@ -1,17 +1,25 @@
 use bambang::{
+    executor::{scan::{BatchPolicy, LimitScanner, Scanner}, sequential_scan::SequentialScanner},
     storage::storage_manager::StorageManager,
     types::error::DatabaseError,
     utils::mock::TempDatabase,
 };
 use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+#[allow(dead_code)]
 mod utils;
-use utils::benchmark_helpers::
-    measure_scan_operation
-;
+use utils::benchmark_helpers::{
+    measure_batch_scan_operation, measure_reset_rescan_operation, measure_scan_operation,
+};
 use utils::data_generator::{DataGenerator, RowType};
 
 const DATASET_SIZES: &[usize] = &[50, 100];
 const ROW_TYPES: &[RowType] = &[RowType::Small, RowType::Medium, RowType::Large];
+const BATCH_POLICIES: &[BatchPolicy] = &[
+    BatchPolicy::Fixed(1),
+    BatchPolicy::Fixed(32),
+    BatchPolicy::Fixed(500),
+    BatchPolicy::Auto,
+];
 
 fn setup_test_table(
     storage: &mut StorageManager,
@@ -43,24 +51,129 @@ fn benchmark_sequential_scan_throughput(c: &mut Criterion) {
     for &dataset_size in DATASET_SIZES {
         for &row_type in ROW_TYPES {
             let mut temp_db = TempDatabase::with_prefix("bench_throughput");
-            let mut storage = temp_db.create_storage_manager().unwrap();
-            setup_test_table(&mut storage, "test_table", dataset_size, row_type).unwrap();
+            let storage = temp_db.create_storage_manager().unwrap();
+            setup_test_table(storage, "test_table", dataset_size, row_type).unwrap();
 
             let benchmark_id =
                 BenchmarkId::from_parameter(format!("{}_{:?}", dataset_size, row_type));
             group.throughput(Throughput::Elements(dataset_size as u64));
 
             group.bench_with_input(benchmark_id, &(dataset_size, row_type), |b, &(size, _)| {
-                b.iter(|| measure_scan_operation(&storage, "test_table", size).unwrap());
+                b.iter(|| measure_scan_operation(storage, "test_table", size).unwrap());
+            });
+        }
+    }
+    group.finish();
+}
+
+fn benchmark_batch_scan_policies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_scan_policy");
+    let dataset_size = *DATASET_SIZES.last().unwrap();
+
+    for &row_type in ROW_TYPES {
+        let mut temp_db = TempDatabase::with_prefix("bench_batch_policy");
+        let storage = temp_db.create_storage_manager().unwrap();
+        setup_test_table(storage, "test_table", dataset_size, row_type).unwrap();
+        group.throughput(Throughput::Elements(dataset_size as u64));
+
+        for &policy in BATCH_POLICIES {
+            let benchmark_id = BenchmarkId::new(format!("{:?}", row_type), format!("{:?}", policy));
+            group.bench_with_input(benchmark_id, &policy, |b, &policy| {
+                b.iter(|| {
+                    measure_batch_scan_operation(storage, "test_table", policy, dataset_size).unwrap()
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+fn benchmark_reset_after_root_split(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reset_after_root_split");
+
+    for &row_type in ROW_TYPES {
+        let mut temp_db = TempDatabase::with_prefix("bench_reset_growth");
+        let storage = temp_db.create_storage_manager().unwrap();
+        setup_test_table(storage, "test_table", 1, row_type).unwrap();
+
+        // Start a scan, then grow the table until its root moves
+        let mut scanner = SequentialScanner::new(storage, "test_table".to_string(), None).unwrap();
+        scanner.scan().unwrap();
+        let original_root = storage.table_roots["test_table"];
+        let data_generator = DataGenerator::new();
+        let mut row_count = 1;
+        while storage.table_roots["test_table"] == original_root
+            || row_count < *DATASET_SIZES.last().unwrap()
+        {
+            row_count += 1;
+            let row = data_generator.generate_row(row_count as i64, row_type);
+            storage.insert_into_table("test_table", row).unwrap();
+        }
+
+        group.throughput(Throughput::Elements(row_count as u64));
+        group.bench_function(BenchmarkId::from_parameter(format!("{:?}", row_type)), |b| {
+            b.iter(|| measure_reset_rescan_operation(&mut scanner, row_count).unwrap());
+        });
+    }
+    group.finish();
+}
+
+fn benchmark_count_rows(c: &mut Criterion) {
+    let mut group = c.benchmark_group("count_rows");
+
+    for &dataset_size in DATASET_SIZES {
+        for &row_type in ROW_TYPES {
+            let mut temp_db = TempDatabase::with_prefix("bench_count_rows");
+            let storage = temp_db.create_storage_manager().unwrap();
+            setup_test_table(storage, "test_table", dataset_size, row_type).unwrap();
+            group.throughput(Throughput::Elements(dataset_size as u64));
+
+            let parameter = format!("{}_{:?}", dataset_size, row_type);
+            group.bench_function(BenchmarkId::new("scan_table_len", &parameter), |b| {
+                b.iter(|| {
+                    let count = storage.scan_table("test_table", None).unwrap().len();
+                    assert_eq!(count, dataset_size);
+                });
+            });
+            group.bench_function(BenchmarkId::new("count_rows", &parameter), |b| {
+                b.iter(|| {
+                    let count = storage.count_rows("test_table", None).unwrap();
+                    assert_eq!(count, dataset_size);
+                });
             });
         }
     }
     group.finish();
 }
 
+fn benchmark_limited_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("limited_scan");
+
+    // A small limit should cost the same regardless of table size
+    for &dataset_size in &[100, 2_000] {
+        let mut temp_db = TempDatabase::with_prefix("bench_limited_scan");
+        let storage = temp_db.create_storage_manager().unwrap();
+        setup_test_table(storage, "test_table", dataset_size, RowType::Medium).unwrap();
+        group.throughput(Throughput::Elements(10));
+
+        group.bench_function(BenchmarkId::from_parameter(dataset_size), |b| {
+            b.iter(|| {
+                let scanner = SequentialScanner::new(storage, "test_table".to_string(), None).unwrap();
+                let mut limited = LimitScanner::new(scanner, 10, 0);
+                assert_eq!(limited.scan_batch(100).unwrap().len(), 10);
+            });
+        });
+    }
+    group.finish();
+}
+
 criterion_group!(
     benches,
     benchmark_sequential_scan_throughput,
+    benchmark_batch_scan_policies,
+    benchmark_reset_after_root_split,
+    benchmark_count_rows,
+    benchmark_limited_scan,
 );
 
 criterion_main!(benches);
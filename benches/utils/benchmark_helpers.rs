@@ -1,6 +1,9 @@
 use std::time::{Duration, Instant};
 use bambang::{
-    executor::{scan::Scanner, sequential_scan::SequentialScanner},
+    executor::{
+        scan::{BatchPolicy, Scanner},
+        sequential_scan::SequentialScanner,
+    },
     storage::storage_manager::StorageManager,
     types::error::DatabaseError,
     utils::mock::TempDatabase,
@@ -71,15 +74,19 @@ pub fn measure_scan_operation(
 pub fn measure_batch_scan_operation(
     storage: &StorageManager,
     table_name: &str,
-    batch_size: usize,
+    policy: BatchPolicy,
     expected_count: usize,
 ) -> Result<Duration, DatabaseError> {
-    let mut scanner = SequentialScanner::new(storage, table_name.to_string(), Some(batch_size))?;
+    let batch_size = match policy {
+        BatchPolicy::Fixed(batch_size) => Some(batch_size),
+        BatchPolicy::Auto => None,
+    };
+    let mut scanner = SequentialScanner::new(storage, table_name.to_string(), batch_size)?;
     
     let start = Instant::now();
     let mut total_rows = 0;
     loop {
-        let batch = scanner.scan_batch(batch_size)?;
+        let batch = scanner.scan_batch_with_policy(policy)?;
         if batch.is_empty() {
             break;
         }
@@ -110,4 +117,20 @@ pub fn measure_reset_operation(
     let reset_duration = start.elapsed();
     
     Ok(reset_duration)
-}
\ No newline at end of file
+}
+/// Measure a reset followed by a full rescan, checking the rescan sees every current row
+pub fn measure_reset_rescan_operation(
+    scanner: &mut SequentialScanner,
+    expected_count: usize,
+) -> Result<Duration, DatabaseError> {
+    let start = Instant::now();
+    scanner.reset()?;
+    let mut count = 0;
+    while scanner.scan()?.is_some() {
+        count += 1;
+    }
+    let duration = start.elapsed();
+
+    assert_eq!(count, expected_count);
+    Ok(duration)
+}
@@ -1,6 +1,6 @@
 use std::time::{Duration, Instant};
 use memory_stats::memory_stats;
-use sysinfo::{System, Process, Pid};
+use sysinfo::{System, Pid};
 
 #[derive(Debug, Clone)]
 pub struct BenchmarkMetrics {
@@ -99,10 +99,10 @@ impl MetricsCollector {
 
     pub fn increment_rows(&mut self, count: usize) {
         self.rows_processed += count;
-        if self.rows_processed % 1000 == 0 {
-            if let Some(current_memory) = memory_stats().map(|m| m.physical_mem) {
-                self.peak_memory = self.peak_memory.max(current_memory);
-            }
+        if self.rows_processed.is_multiple_of(1000)
+            && let Some(current_memory) = memory_stats().map(|m| m.physical_mem)
+        {
+            self.peak_memory = self.peak_memory.max(current_memory);
         }
     }
 
@@ -2,7 +2,7 @@ use super::data_generator::{DataGenerator, RowType};
 use bambang::{
     executor::sequential_scan::SequentialScanner,
     storage::storage_manager::StorageManager,
-    types::{error::DatabaseError, row::Row},
+    types::error::DatabaseError,
     utils::mock::TempDatabase,
 };
 
@@ -62,20 +62,20 @@ impl TestEnvironment {
     }
 
     pub fn setup_data(&mut self) -> Result<(), DatabaseError> {
-        let mut storage = self.temp_db.create_storage_manager().map_err(|e| DatabaseError::Io(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
+        let storage = self.temp_db.create_storage_manager().map_err(|e| DatabaseError::Io(std::io::Error::other(e.to_string())))?;
         let data_generator = DataGenerator::new();
         match &self.config.scenario {
             TestScenario::SingleRowScan { dataset_size, row_type }
             | TestScenario::BatchScan { dataset_size, row_type, .. }
             | TestScenario::FullTableScan { dataset_size, row_type }
             | TestScenario::ResetAndRescan { dataset_size, row_type, .. } => {
-                Self::setup_uniform_data(&mut storage, &self.config.table_name, *dataset_size, *row_type, &data_generator)?
+                Self::setup_uniform_data(storage, &self.config.table_name, *dataset_size, *row_type, &data_generator)?
             }
             TestScenario::MixedDataTypes { dataset_size } => {
-                Self::setup_mixed_data(&mut storage, &self.config.table_name, *dataset_size, &data_generator)?
+                Self::setup_mixed_data(storage, &self.config.table_name, *dataset_size, &data_generator)?
             }
             TestScenario::MemoryStress { dataset_size } => {
-                Self::setup_uniform_data(&mut storage, &self.config.table_name, *dataset_size, RowType::Large, &data_generator)?
+                Self::setup_uniform_data(storage, &self.config.table_name, *dataset_size, RowType::Large, &data_generator)?
             }
         }
         Ok(())
@@ -106,8 +106,8 @@ impl TestEnvironment {
     }
 
     pub fn create_scanner(&mut self, batch_size: Option<usize>) -> Result<SequentialScanner, DatabaseError> {
-        let storage = self.temp_db.create_storage_manager().map_err(|e| DatabaseError::Io(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
-        SequentialScanner::new(&storage, self.config.table_name.clone(), batch_size)
+        let storage = self.temp_db.create_storage_manager().map_err(|e| DatabaseError::Io(std::io::Error::other(e.to_string())))?;
+        SequentialScanner::new(storage, self.config.table_name.clone(), batch_size)
     }
 
     pub fn execute_scenario<F, R>(&self, mut executor: F) -> Result<R, DatabaseError>
@@ -196,6 +196,8 @@ impl TestScenario {
 
 #[cfg(test)]
 mod tests {
+    // The bench target runs without the test harness, so these only compile under `cargo test`
+    #[allow(unused_imports)]
     use super::*;
 
     #[test]
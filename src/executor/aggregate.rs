@@ -0,0 +1,340 @@
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use crate::{
+    executor::{predicate::Predicate, scan::Scanner, subquery::value_total_cmp},
+    storage::{schema::TableSchema, storage_manager::StorageManager},
+    types::{
+        error::DatabaseError,
+        row::Row,
+        value::{GroupKey, Value},
+    },
+};
+
+/// Aggregate functions over a single column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggFunc {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
+}
+
+impl fmt::Display for AggFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AggFunc::Sum => "SUM",
+            AggFunc::Avg => "AVG",
+            AggFunc::Min => "MIN",
+            AggFunc::Max => "MAX",
+            AggFunc::Count => "COUNT",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One aggregate of an `AggregateSpec`; a `None` column is `COUNT(*)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregate {
+    pub func: AggFunc,
+    pub column: Option<String>,
+}
+
+impl Aggregate {
+    pub fn new(func: AggFunc, column: &str) -> Self {
+        Self {
+            func,
+            column: Some(column.to_string()),
+        }
+    }
+
+    pub fn count_star() -> Self {
+        Self {
+            func: AggFunc::Count,
+            column: None,
+        }
+    }
+
+    pub fn count(column: &str) -> Self {
+        Self::new(AggFunc::Count, column)
+    }
+
+    pub fn sum(column: &str) -> Self {
+        Self::new(AggFunc::Sum, column)
+    }
+
+    pub fn avg(column: &str) -> Self {
+        Self::new(AggFunc::Avg, column)
+    }
+
+    pub fn min(column: &str) -> Self {
+        Self::new(AggFunc::Min, column)
+    }
+
+    pub fn max(column: &str) -> Self {
+        Self::new(AggFunc::Max, column)
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.func, self.column.as_deref().unwrap_or("*"))
+    }
+}
+
+/// The aggregates computed in one pass; results come back in the same order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AggregateSpec {
+    pub aggregates: Vec<Aggregate>,
+}
+
+impl AggregateSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_aggregate(mut self, aggregate: Aggregate) -> Self {
+        self.aggregates.push(aggregate);
+        self
+    }
+}
+
+/// Running state of one aggregate. NULLs are ignored by every function; `count_row`
+/// counts a row regardless of its values, as in `COUNT(*)`.
+#[derive(Debug, Clone)]
+pub struct Accumulator {
+    func: AggFunc,
+    count: i64,
+    /// Exact sum while every value seen is an integer and the sum fits in an i64
+    integer_sum: Option<i64>,
+    real_sum: f64,
+    extreme: Option<Value>,
+}
+
+impl Accumulator {
+    pub fn new(func: AggFunc) -> Self {
+        Self {
+            func,
+            count: 0,
+            integer_sum: Some(0),
+            real_sum: 0.0,
+            extreme: None,
+        }
+    }
+
+    pub fn update(&mut self, value: &Value) -> Result<(), DatabaseError> {
+        if value.is_null() {
+            return Ok(());
+        }
+        self.count += 1;
+        match self.func {
+            AggFunc::Count => {}
+            AggFunc::Sum | AggFunc::Avg => {
+                let number = match value {
+                    Value::Text(_) | Value::Blob(_) => None,
+                    _ => value.coerce_to_number(),
+                }
+                .ok_or_else(|| DatabaseError::TypeMismatch {
+                    expected: "numeric value".to_string(),
+                    actual: value.data_type().to_string(),
+                })?;
+                self.integer_sum = match (self.integer_sum, value) {
+                    (Some(sum), Value::Integer(i)) => sum.checked_add(*i),
+                    _ => None,
+                };
+                self.real_sum += number;
+            }
+            AggFunc::Min | AggFunc::Max => {
+                let wanted = if self.func == AggFunc::Min {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+                let replace = self
+                    .extreme
+                    .as_ref()
+                    .is_none_or(|current| value_total_cmp(value, current) == wanted);
+                if replace {
+                    self.extreme = Some(value.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn count_row(&mut self) {
+        self.count += 1;
+    }
+
+    /// Final value: COUNT is an integer, AVG a real, SUM an integer when every input was
+    /// one and the total fits, else a real. Empty input gives NULL except for COUNT.
+    pub fn finish(self) -> Value {
+        match self.func {
+            AggFunc::Count => Value::Integer(self.count),
+            _ if self.count == 0 => Value::Null,
+            // An integer total that overflowed is kept as a real rather than wrapping
+            AggFunc::Sum => match self.integer_sum {
+                Some(sum) => Value::Integer(sum),
+                None => Value::Real(self.real_sum),
+            },
+            AggFunc::Avg => Value::Real(self.real_sum / self.count as f64),
+            AggFunc::Min | AggFunc::Max => self.extreme.unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// Computes the aggregates of an `AggregateSpec` over a stream of rows in one pass.
+/// Filter the input by wrapping the scanner in a `FilterScanner`.
+pub struct AggregateExecutor {
+    aggregates: Vec<Aggregate>,
+    positions: Vec<Option<usize>>,
+    accumulators: Vec<Accumulator>,
+}
+
+impl AggregateExecutor {
+    pub fn new(schema: &TableSchema, spec: AggregateSpec) -> Result<Self, DatabaseError> {
+        let positions = spec
+            .aggregates
+            .iter()
+            .map(|aggregate| match &aggregate.column {
+                None if aggregate.func == AggFunc::Count => Ok(None),
+                None => Err(DatabaseError::ExecutionError {
+                    details: format!("{} needs a column", aggregate.func),
+                }),
+                Some(column) => schema.get_column_index(column).map(Some).ok_or_else(|| {
+                    DatabaseError::ColumnNotFound {
+                        name: column.clone(),
+                        table: schema.table_name.clone(),
+                    }
+                }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let accumulators = spec.aggregates.iter().map(|aggregate| Accumulator::new(aggregate.func)).collect();
+        Ok(Self {
+            aggregates: spec.aggregates,
+            positions,
+            accumulators,
+        })
+    }
+
+    pub fn update(&mut self, row: &Row) -> Result<(), DatabaseError> {
+        for ((aggregate, position), accumulator) in self
+            .aggregates
+            .iter()
+            .zip(&self.positions)
+            .zip(&mut self.accumulators)
+        {
+            let Some(position) = position else {
+                accumulator.count_row();
+                continue;
+            };
+            let value = row.values.get(*position).unwrap_or(&Value::Null);
+            accumulator.update(value).map_err(|e| match e {
+                DatabaseError::TypeMismatch { actual, .. } => DatabaseError::ExecutionError {
+                    details: format!("Cannot compute {}: {} values are not numeric", aggregate, actual),
+                },
+                e => e,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Feed every remaining row of `scanner` and return the results
+    pub fn execute<S: Scanner>(mut self, scanner: &mut S) -> Result<Vec<Value>, DatabaseError> {
+        while let Some(row) = scanner.scan()? {
+            self.update(&row)?;
+        }
+        Ok(self.finish())
+    }
+
+    pub fn finish(self) -> Vec<Value> {
+        self.accumulators.into_iter().map(Accumulator::finish).collect()
+    }
+}
+
+/// Compute `func` over `column` for the rows matching `predicate`. `column` may be `*`
+/// for `AggFunc::Count`, counting rows rather than non-NULL values.
+pub fn aggregate(
+    storage: &StorageManager,
+    table_name: &str,
+    column: &str,
+    func: AggFunc,
+    predicate: Option<Predicate>,
+) -> Result<Value, DatabaseError> {
+    let aggregate = match column {
+        "*" if func == AggFunc::Count => Aggregate::count_star(),
+        _ => Aggregate::new(func, column),
+    };
+    let mut values = storage.aggregate(table_name, predicate, AggregateSpec::new().with_aggregate(aggregate))?;
+    Ok(values.pop().unwrap_or(Value::Null))
+}
+
+/// Compute `agg` (a column, or `*` for COUNT) separately for each distinct combination of
+/// `group_cols` among the rows matching `predicate`. Group keys are compared as the
+/// storage manager's `ValueComparison::group_key` makes them: NULLs form one group, and
+/// `1` and `1.0` share one unless the comparison is Strict. Groups come back ordered by
+/// their key values as `value_total_cmp` orders them.
+pub fn group_by(
+    storage: &StorageManager,
+    table_name: &str,
+    group_cols: Vec<String>,
+    agg: (String, AggFunc),
+    predicate: Option<Predicate>,
+) -> Result<Vec<(Vec<Value>, Value)>, DatabaseError> {
+    let schema = storage
+        .load_table_schema(table_name)?
+        .ok_or_else(|| DatabaseError::TableNotFound {
+            name: table_name.to_string(),
+        })?;
+    let column_index = |column: &str| {
+        schema
+            .get_column_index(column)
+            .ok_or_else(|| DatabaseError::ColumnNotFound {
+                name: column.to_string(),
+                table: table_name.to_string(),
+            })
+    };
+    let group_positions = group_cols
+        .iter()
+        .map(|column| column_index(column))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (agg_column, func) = agg;
+    let agg_position = match agg_column.as_str() {
+        "*" if func == AggFunc::Count => None,
+        column => Some(column_index(column)?),
+    };
+
+    let comparison = storage.options.value_comparison;
+    let init: Result<HashMap<Vec<GroupKey>, Accumulator>, DatabaseError> = Ok(HashMap::new());
+    let groups = storage.fold_rows(table_name, predicate, init, |groups, row| {
+        let mut groups = groups?;
+        let key = group_positions
+            .iter()
+            .map(|&position| comparison.group_key(row.values.get(position).cloned().unwrap_or(Value::Null)))
+            .collect();
+        let accumulator = groups.entry(key).or_insert_with(|| Accumulator::new(func));
+        match agg_position {
+            Some(position) => accumulator.update(row.values.get(position).unwrap_or(&Value::Null))?,
+            None => accumulator.count_row(),
+        }
+        Ok(groups)
+    })??;
+
+    let mut results: Vec<(Vec<Value>, Value)> = groups
+        .into_iter()
+        .map(|(key, accumulator)| {
+            (
+                key.into_iter().map(GroupKey::into_inner).collect(),
+                accumulator.finish(),
+            )
+        })
+        .collect();
+    results.sort_by(|(a, _), (b, _)| {
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| value_total_cmp(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+    Ok(results)
+}
@@ -1,13 +1,7 @@
-use std::{
-    fs::OpenOptions,
-    path::PathBuf,
-};
-
 use crate::{
     storage::{
         storage_manager::StorageManager,
-        schema::{TableSchema, ColumnSchema},
-        BAMBANG_HEADER_SIZE,
+        schema::{TableSchema, ColumnSchema, ForeignKey, MAX_COLUMNS},
     },
     types::{
         error::DatabaseError,
@@ -27,30 +21,28 @@ pub trait TableCreator {
 }
 
 /// Table creator implementation that handles table creation operations
-pub struct CreateTableExecutor {
-    db_file_path: PathBuf,
-    extras: Option<u64>,
-}
+#[derive(Default)]
+pub struct CreateTableExecutor;
 
 impl CreateTableExecutor {
     /// Create a new CreateTableExecutor
-    pub fn new(storage_manager: &StorageManager) -> Result<Self, DatabaseError> {
-        let db_file_path = storage_manager.db_info.path.clone();
-        let extras = Some(BAMBANG_HEADER_SIZE as u64);
-
-        Ok(Self {
-            db_file_path,
-            extras,
-        })
+    pub fn new() -> Self {
+        Self
     }
 
     /// Validate column definitions
-    fn validate_columns(&self, columns: &[ColumnSchema]) -> Result<(), DatabaseError> {
+    pub fn validate_columns(&self, columns: &[ColumnSchema]) -> Result<(), DatabaseError> {
         if columns.is_empty() {
             return Err(DatabaseError::InvalidData {
                 details: "Table must have at least one column".to_string(),
             });
         }
+        if columns.len() > MAX_COLUMNS {
+            return Err(DatabaseError::TooManyColumns {
+                count: columns.len(),
+                max: MAX_COLUMNS,
+            });
+        }
 
         // Check for duplicate column names
         let mut column_names = std::collections::HashSet::new();
@@ -87,16 +79,54 @@ impl CreateTableExecutor {
         let primary_key_count = columns.iter().filter(|c| c.primary_key).count();
         if primary_key_count > 1 {
             return Err(DatabaseError::InvalidData {
-                details: "Table can have at most one primary key column".to_string(),
+                details: "Table can have at most one primary key column; declare a composite key as PRIMARY KEY (a, b)".to_string(),
             });
         }
 
+        // A CHECK may name any column of the table, including ones declared after its own
+        for column in columns {
+            let Some(check) = &column.check else {
+                continue;
+            };
+            if let Some(unknown) = check
+                .get_referenced_columns()
+                .into_iter()
+                .find(|name| !column_names.contains(name))
+            {
+                return Err(DatabaseError::InvalidData {
+                    details: format!("CHECK on column {} names unknown column {}", column.name, unknown),
+                });
+            }
+        }
+
         Ok(())
     }
 
-    /// Allocate a new page for the table
-    fn allocate_table_page(&self, storage_manager: &mut StorageManager) -> Result<PageId, DatabaseError> {
-        storage_manager.allocate_new_page(PageType::LeafTable)
+    /// Validate a table-level PRIMARY KEY given as column positions in key order. Such a
+    /// key cannot be combined with a column declared PRIMARY KEY.
+    pub fn validate_primary_key(&self, columns: &[ColumnSchema], primary_key: &[usize]) -> Result<(), DatabaseError> {
+        if primary_key.is_empty() {
+            return Ok(());
+        }
+        if columns.iter().any(|column| column.primary_key) {
+            return Err(DatabaseError::InvalidData {
+                details: "Table can have at most one primary key".to_string(),
+            });
+        }
+        let mut seen = std::collections::HashSet::new();
+        for position in primary_key {
+            if !columns.iter().any(|column| column.position == *position) {
+                return Err(DatabaseError::InvalidData {
+                    details: format!("Primary key refers to missing column position {}", position),
+                });
+            }
+            if !seen.insert(position) {
+                return Err(DatabaseError::InvalidData {
+                    details: format!("Primary key lists column position {} twice", position),
+                });
+            }
+        }
+        Ok(())
     }
 
     /// Create table schema and validate it
@@ -113,7 +143,7 @@ impl CreateTableExecutor {
 }
 
 impl TableCreator for CreateTableExecutor {
-    fn create_table(&mut self, table_name: String, columns: Vec<ColumnSchema>, sql: String) -> Result<PageId, DatabaseError> {
+    fn create_table(&mut self, _table_name: String, _columns: Vec<ColumnSchema>, _sql: String) -> Result<PageId, DatabaseError> {
         // Note: We need a mutable reference to StorageManager to allocate pages and add schemas
         // This is a limitation of the current design - we'll need to refactor this
         // For now, we'll return an error indicating this needs to be handled differently
@@ -216,6 +246,33 @@ impl StorageManager {
         table_name: String,
         columns: Vec<ColumnSchema>,
         sql: String,
+    ) -> Result<PageId, DatabaseError> {
+        self.create_table_with_primary_key(table_name, columns, Vec::new(), sql)
+    }
+
+    /// Create a table whose PRIMARY KEY is the columns at `primary_key`, in that order.
+    /// With more than one the table is keyed on the tuple of their values and only the
+    /// tuple has to be unique. An empty `primary_key` leaves the key to the columns.
+    pub fn create_table_with_primary_key(
+        &mut self,
+        table_name: String,
+        columns: Vec<ColumnSchema>,
+        primary_key: Vec<usize>,
+        sql: String,
+    ) -> Result<PageId, DatabaseError> {
+        self.create_table_with_constraints(table_name, columns, primary_key, Vec::new(), sql)
+    }
+
+    /// Create a table with a PRIMARY KEY as `create_table_with_primary_key` does, whose
+    /// `foreign_keys` each reference a PRIMARY KEY or UNIQUE column of an existing table or
+    /// of the new one
+    pub fn create_table_with_constraints(
+        &mut self,
+        table_name: String,
+        columns: Vec<ColumnSchema>,
+        primary_key: Vec<usize>,
+        foreign_keys: Vec<ForeignKey>,
+        sql: String,
     ) -> Result<PageId, DatabaseError> {
         // Check if table already exists
         if self.table_exists(&table_name) {
@@ -225,16 +282,23 @@ impl StorageManager {
         }
 
         // Create executor for validation
-        let executor = CreateTableExecutor::new(self)?;
+        let executor = CreateTableExecutor::new();
         
         // Validate columns
         executor.validate_columns(&columns)?;
+        executor.validate_primary_key(&columns, &primary_key)?;
+
+        // Create table schema, whose references are checked before any page is allocated
+        let mut table_schema = executor.create_table_schema(table_name.clone(), columns, 0, sql)?;
+        if !primary_key.is_empty() {
+            table_schema = table_schema.with_primary_key(primary_key);
+        }
+        table_schema = table_schema.with_foreign_keys(foreign_keys);
+        self.validate_foreign_keys(&table_schema)?;
 
         // Allocate new page for the table
         let root_page_id = self.allocate_new_page(PageType::LeafTable)?;
-
-        // Create table schema
-        let table_schema = executor.create_table_schema(table_name.clone(), columns, root_page_id, sql)?;
+        table_schema.root_page_id = root_page_id;
 
         // Add schema to storage manager
         self.add_table_schema(table_schema)?;
@@ -242,6 +306,49 @@ impl StorageManager {
         Ok(root_page_id)
     }
 
+    /// Check that every foreign key of `schema` is on one of its columns, at most one per
+    /// column, and references a PRIMARY KEY or UNIQUE column, so a value matches at most
+    /// one row. A table may reference itself.
+    pub(crate) fn validate_foreign_keys(&self, schema: &TableSchema) -> Result<(), DatabaseError> {
+        let mut referencing = std::collections::HashSet::new();
+        for foreign_key in &schema.foreign_keys {
+            if schema.get_column(&foreign_key.column).is_none() {
+                return Err(DatabaseError::ColumnNotFound {
+                    name: foreign_key.column.clone(),
+                    table: schema.table_name.clone(),
+                });
+            }
+            if !referencing.insert(&foreign_key.column) {
+                return Err(DatabaseError::InvalidData {
+                    details: format!("Column {} has more than one FOREIGN KEY", foreign_key.column),
+                });
+            }
+            let parent = if foreign_key.ref_table == schema.table_name {
+                schema
+            } else {
+                self.load_table_schema(&foreign_key.ref_table)?
+                    .ok_or_else(|| DatabaseError::TableNotFound {
+                        name: foreign_key.ref_table.clone(),
+                    })?
+            };
+            let position = parent.get_column_index(&foreign_key.ref_column).ok_or_else(|| {
+                DatabaseError::ColumnNotFound {
+                    name: foreign_key.ref_column.clone(),
+                    table: foreign_key.ref_table.clone(),
+                }
+            })?;
+            if parent.primary_key != [position] && !parent.columns[position].unique {
+                return Err(DatabaseError::InvalidData {
+                    details: format!(
+                        "FOREIGN KEY on {} must reference a PRIMARY KEY or UNIQUE column, not {}.{}",
+                        foreign_key.column, foreign_key.ref_table, foreign_key.ref_column
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Create a table using the builder pattern
     pub fn create_table_with_builder(
         &mut self,
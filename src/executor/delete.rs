@@ -0,0 +1,53 @@
+use crate::{
+    executor::predicate::Predicate,
+    storage::storage_manager::StorageManager,
+    types::error::DatabaseError,
+};
+
+/// Trait for deleting rows from database tables
+pub trait Deleter {
+    /// Delete every row matching the predicate, returning how many were removed
+    fn delete_where(&mut self, predicate: Predicate) -> Result<usize, DatabaseError>;
+
+    /// Get the table name this deleter operates on
+    fn table_name(&self) -> &str;
+}
+
+/// `DELETE FROM table WHERE predicate`. Matching rows are located by a full scan before
+/// any is removed, so deleting never disturbs the scan that finds them. Rows are removed
+/// by position rather than by key, leaving other rows with the same key in place.
+pub struct DeleteExecutor<'a> {
+    storage: &'a mut StorageManager,
+    table_name: String,
+}
+
+impl<'a> DeleteExecutor<'a> {
+    pub fn new(storage: &'a mut StorageManager, table_name: String) -> Result<Self, DatabaseError> {
+        if storage.load_table_schema(&table_name)?.is_none() {
+            return Err(DatabaseError::TableNotFound { name: table_name });
+        }
+        Ok(Self { storage, table_name })
+    }
+
+    /// Delete every row of the table
+    pub fn delete_all(&mut self) -> Result<usize, DatabaseError> {
+        self.storage.delete_from_table(&self.table_name, None)
+    }
+}
+
+impl Deleter for DeleteExecutor<'_> {
+    fn delete_where(&mut self, predicate: Predicate) -> Result<usize, DatabaseError> {
+        let schema = self
+            .storage
+            .load_table_schema(&self.table_name)?
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: self.table_name.clone(),
+            })?;
+        predicate.validate_against_schema(schema)?;
+        self.storage.delete_from_table(&self.table_name, Some(predicate))
+    }
+
+    fn table_name(&self) -> &str {
+        &self.table_name
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+};
+
+use crate::{
+    executor::scan::{Scanner, collect_batch},
+    types::{error::DatabaseError, row::Row, value::Value},
+};
+
+/// Suppress duplicate rows of the inner scanner, optionally comparing only a subset of
+/// columns. Rows are compared by their serialized values, so values must match in type as
+/// well as value: `Integer(1)` and `Real(1.0)` are distinct, while NULLs equal each other.
+/// Output rows hold only the compared values and carry no rowid, since each may stand for
+/// several stored rows.
+///
+/// By default the rows seen so far are kept in memory and output follows first occurrence.
+/// With `with_spill` memory stays within a budget: the input is consumed up front into
+/// sorted runs on disk, which are merged on output, so rows come out ordered by their
+/// serialized form instead.
+pub struct DistinctExecutor<S: Scanner> {
+    scanner: S,
+    projection: Option<Vec<usize>>,
+    spill_budget: Option<usize>,
+    state: DistinctState,
+}
+
+enum DistinctState {
+    Streaming(HashSet<Vec<u8>>),
+    Merging(RunMerger),
+}
+
+impl<S: Scanner> DistinctExecutor<S> {
+    pub fn new(scanner: S) -> Self {
+        Self {
+            scanner,
+            projection: None,
+            spill_budget: None,
+            state: DistinctState::Streaming(HashSet::new()),
+        }
+    }
+
+    /// Compare and return only the columns at `indices`
+    pub fn with_projection(mut self, indices: Vec<usize>) -> Self {
+        self.projection = Some(indices);
+        self
+    }
+
+    /// Buffer at most about `memory_budget` bytes of rows before writing a sorted run
+    pub fn with_spill(mut self, memory_budget: usize) -> Self {
+        self.spill_budget = Some(memory_budget);
+        self
+    }
+
+    pub fn into_inner(self) -> S {
+        self.scanner
+    }
+
+    /// Serialized compared values of `row`, without its rowid
+    fn key(&self, row: Row) -> Vec<u8> {
+        let values = match &self.projection {
+            Some(indices) => indices
+                .iter()
+                .map(|&index| row.values.get(index).cloned().unwrap_or(Value::Null))
+                .collect(),
+            None => row.values,
+        };
+        Row::new(values).to_bytes()
+    }
+
+    /// Consume the whole input into deduplicated sorted runs, spilling each one that
+    /// outgrows the budget
+    fn build_runs(&mut self, memory_budget: usize) -> Result<RunMerger, DatabaseError> {
+        let mut runs = Vec::new();
+        let mut buffer: Vec<Vec<u8>> = Vec::new();
+        let mut buffered_bytes = 0;
+        while let Some(row) = self.scanner.scan()? {
+            let key = self.key(row);
+            buffered_bytes += key.len();
+            buffer.push(key);
+            if buffered_bytes > memory_budget {
+                runs.push(RunSource::File(write_run(std::mem::take(&mut buffer))?));
+                buffered_bytes = 0;
+            }
+        }
+        buffer.sort_unstable();
+        buffer.dedup();
+        runs.push(RunSource::Memory(buffer.into_iter()));
+        RunMerger::new(runs)
+    }
+}
+
+impl<S: Scanner> Scanner for DistinctExecutor<S> {
+    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+        if let (Some(memory_budget), DistinctState::Streaming(_)) = (self.spill_budget, &self.state) {
+            self.state = DistinctState::Merging(self.build_runs(memory_budget)?);
+        }
+        match &mut self.state {
+            DistinctState::Merging(merger) => merger.next_key()?.map(|key| Row::from_bytes(&key)).transpose(),
+            DistinctState::Streaming(_) => {
+                while let Some(row) = self.scanner.scan()? {
+                    let key = self.key(row);
+                    let DistinctState::Streaming(seen) = &mut self.state else {
+                        unreachable!("state only changes above");
+                    };
+                    if !seen.contains(&key) {
+                        let row = Row::from_bytes(&key)?;
+                        seen.insert(key);
+                        return Ok(Some(row));
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
+        collect_batch(self, batch_size)
+    }
+
+    fn reset(&mut self) -> Result<(), DatabaseError> {
+        self.state = DistinctState::Streaming(HashSet::new());
+        self.scanner.reset()
+    }
+}
+
+enum RunSource {
+    Memory(std::vec::IntoIter<Vec<u8>>),
+    File(BufReader<File>),
+}
+
+impl RunSource {
+    fn next_key(&mut self) -> Result<Option<Vec<u8>>, DatabaseError> {
+        match self {
+            RunSource::Memory(keys) => Ok(keys.next()),
+            RunSource::File(reader) => read_key(reader),
+        }
+    }
+}
+
+/// K-way merge of sorted runs that skips keys equal to the previous one
+struct RunMerger {
+    runs: Vec<RunSource>,
+    heads: Vec<Option<Vec<u8>>>,
+    last: Option<Vec<u8>>,
+}
+
+impl RunMerger {
+    fn new(mut runs: Vec<RunSource>) -> Result<Self, DatabaseError> {
+        let heads = runs.iter_mut().map(RunSource::next_key).collect::<Result<_, _>>()?;
+        Ok(Self { runs, heads, last: None })
+    }
+
+    fn next_key(&mut self) -> Result<Option<Vec<u8>>, DatabaseError> {
+        loop {
+            let next = self
+                .heads
+                .iter()
+                .enumerate()
+                .filter_map(|(i, head)| head.as_ref().map(|key| (i, key)))
+                .min_by(|(_, a), (_, b)| a.cmp(b))
+                .map(|(i, _)| i);
+            let Some(run) = next else {
+                return Ok(None);
+            };
+            let key = self.heads[run].take().expect("selected run has a head key");
+            self.heads[run] = self.runs[run].next_key()?;
+            if self.last.as_ref() != Some(&key) {
+                self.last = Some(key.clone());
+                return Ok(Some(key));
+            }
+        }
+    }
+}
+
+fn write_run(mut keys: Vec<Vec<u8>>) -> Result<BufReader<File>, DatabaseError> {
+    keys.sort_unstable();
+    keys.dedup();
+    let mut file = tempfile::tempfile()?;
+    {
+        let mut writer = BufWriter::new(&mut file);
+        for key in &keys {
+            writer.write_all(&(key.len() as u32).to_le_bytes())?;
+            writer.write_all(key)?;
+        }
+        writer.flush()?;
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok(BufReader::new(file))
+}
+
+fn read_key<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>, DatabaseError> {
+    let mut len_bytes = [0u8; 4];
+    match reader.read_exact(&mut len_bytes) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let mut key = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+    reader.read_exact(&mut key)?;
+    Ok(Some(key))
+}
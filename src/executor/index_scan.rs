@@ -0,0 +1,289 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    executor::{
+        metrics::{CollectMetrics, OperatorMetrics},
+        scan::{Scanner, collect_batch},
+    },
+    planner::types::SortOrder,
+    storage::{
+        BAMBANG_HEADER_SIZE, bplus_tree::BPlusTree, index::TableIndex, io_stats::IoCounters, page_store,
+        storage_manager::StorageManager,
+    },
+    types::{PageId, error::DatabaseError, page::Page, row::Row, value::Value},
+};
+
+/// Return a table's rows already ordered by one column, walking the leaves of a B+ tree
+/// keyed on it instead of sorting: the table's own tree for its key, or a secondary
+/// index whose entries point back at the rows. Descending orders walk the leaves right to
+/// left through their previous-leaf links. Leaf pages are read one at a time, so only
+/// the rows of the current leaf are held.
+///
+/// A secondary index has no entries for NULL, so an index scan returns only the rows
+/// whose column is set. Rows that tie on the column come out in key order.
+pub struct IndexScanner {
+    table_name: String,
+    /// The index walked, with the position of its column, or `None` for the table's key
+    index: Option<(TableIndex, usize)>,
+    order: SortOrder,
+    db_file_path: PathBuf,
+    page_size: usize,
+    table_root_page_id: PageId,
+    key_columns: Vec<usize>,
+    io_counters: Arc<IoCounters>,
+    extras: Option<u64>,
+    table_btree: BPlusTree,
+    /// The tree whose leaves are walked, `None` while walking the table's own tree
+    index_btree: Option<BPlusTree>,
+    /// Next leaf to read, or `None` once the walk has ended
+    next_leaf: Option<PageId>,
+    started: bool,
+    /// Index entries read but not yet resolved, held back while the next leaf may
+    /// continue their run of equal values
+    pending: Vec<(Value, Value)>,
+    rows: VecDeque<Row>,
+    leaves_read: u64,
+    rows_out: u64,
+    elapsed: Duration,
+}
+
+impl IndexScanner {
+    /// Scan `table_name` in `order` of `column`, through the index on it, or of the
+    /// table's key when `column` is `None`
+    pub fn new(
+        storage_manager: &StorageManager,
+        table_name: &str,
+        column: Option<&str>,
+        order: SortOrder,
+    ) -> Result<Self, DatabaseError> {
+        let table_root_page_id = storage_manager
+            .table_roots
+            .get(table_name)
+            .copied()
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        let key_columns = storage_manager
+            .load_table_schema(table_name)?
+            .map(|schema| schema.key_columns().to_vec())
+            .unwrap_or_default();
+        let index = match column {
+            Some(column) => Some(
+                storage_manager
+                    .table_indexes(table_name)?
+                    .into_iter()
+                    .find(|(index, _)| index.column_name == column)
+                    .ok_or_else(|| DatabaseError::IndexNotFound {
+                        table: table_name.to_string(),
+                        column: column.to_string(),
+                    })?,
+            ),
+            None => None,
+        };
+        let db_file_path = storage_manager.db_info.path.clone();
+        let page_size = storage_manager.page_size();
+        let io_counters = storage_manager.io_counters.clone();
+        let open = |root_page_id, key_columns| {
+            open_btree(&db_file_path, page_size, &io_counters, root_page_id, key_columns)
+        };
+        let table_btree = open(table_root_page_id, key_columns.clone())?;
+        let index_btree = index.as_ref().map(|(index, _)| open(index.root_page_id, Vec::new())).transpose()?;
+        Ok(Self {
+            table_name: table_name.to_string(),
+            index,
+            order,
+            db_file_path,
+            page_size,
+            table_root_page_id,
+            key_columns,
+            io_counters,
+            extras: Some(BAMBANG_HEADER_SIZE as u64),
+            table_btree,
+            index_btree,
+            next_leaf: None,
+            started: false,
+            pending: Vec::new(),
+            rows: VecDeque::new(),
+            leaves_read: 0,
+            rows_out: 0,
+            elapsed: Duration::ZERO,
+        })
+    }
+
+    fn open_btree(&self, root_page_id: PageId, key_columns: Vec<usize>) -> Result<BPlusTree, DatabaseError> {
+        open_btree(&self.db_file_path, self.page_size, &self.io_counters, root_page_id, key_columns)
+    }
+
+    /// The tree whose leaves are walked
+    fn walked_btree(&mut self) -> &mut BPlusTree {
+        self.index_btree.as_mut().unwrap_or(&mut self.table_btree)
+    }
+
+    fn next_row(&mut self) -> Result<Option<Row>, DatabaseError> {
+        if !self.started {
+            self.started = true;
+            let extras = self.extras;
+            let order = self.order.clone();
+            let btree = self.walked_btree();
+            self.next_leaf = Some(match order {
+                SortOrder::Ascending => btree.first_leaf_page_id(extras)?,
+                SortOrder::Descending => btree.last_leaf_page_id(extras)?,
+            });
+        }
+        while self.rows.is_empty() {
+            let Some(page_id) = self.next_leaf else {
+                if self.pending.is_empty() {
+                    return Ok(None);
+                }
+                let entries = std::mem::take(&mut self.pending);
+                self.resolve_entries(entries)?;
+                continue;
+            };
+            self.read_leaf(page_id)?;
+        }
+        self.rows_out += 1;
+        Ok(self.rows.pop_front())
+    }
+
+    /// Order the live cells of the leaf `page_id` and queue its rows, or its index
+    /// entries, then move on to the leaf beside it
+    fn read_leaf(&mut self, page_id: PageId) -> Result<(), DatabaseError> {
+        let extras = self.extras;
+        let btree = self.walked_btree();
+        let page: Page = btree.load_page(page_id, extras)?.clone();
+        let mut cells = Vec::new();
+        for slot_index in 0..page.slot_directory.slots.len() {
+            if let Some(row) = btree.leaf_row(&page, slot_index, extras)? {
+                cells.push(row);
+            }
+        }
+        self.leaves_read += 1;
+        self.next_leaf = match self.order {
+            SortOrder::Ascending => page.next_leaf_page_id,
+            SortOrder::Descending => page.prev_leaf_page_id,
+        };
+
+        if self.index.is_none() {
+            let mut keyed: Vec<(Value, Row)> = cells.into_iter().map(|row| (row.key(&self.key_columns), row)).collect();
+            keyed.sort_by(|a, b| self.ordered(a.0.total_cmp(&b.0)));
+            self.rows.extend(keyed.into_iter().map(|(_, row)| row));
+            return Ok(());
+        }
+
+        let mut entries: Vec<(Value, Value)> = cells
+            .into_iter()
+            .filter_map(|entry| {
+                let mut values = entry.values.into_iter();
+                Some((values.next()?, values.next()?))
+            })
+            .collect();
+        entries.sort_by(|a, b| self.ordered(a.0.total_cmp(&b.0).then_with(|| a.1.total_cmp(&b.1))));
+        self.pending.extend(entries);
+        // Entries equal to the last value may continue on the next leaf, out of key order
+        let Some((last, _)) = self.pending.last() else {
+            return Ok(());
+        };
+        let complete = self.pending.partition_point(|(value, _)| !value.total_cmp(last).is_eq());
+        let entries: Vec<(Value, Value)> = self.pending.drain(..complete).collect();
+        self.resolve_entries(entries)
+    }
+
+    /// Look up the rows behind index entries, in the entries' order. Entries of one value
+    /// that repeat a key are read once, as several stored rows may share a key.
+    fn resolve_entries(&mut self, mut entries: Vec<(Value, Value)>) -> Result<(), DatabaseError> {
+        let Some((_, position)) = &self.index else {
+            return Ok(());
+        };
+        let position = *position;
+        for run in entries.chunk_by_mut(|a, b| a.0.total_cmp(&b.0).is_eq()) {
+            run.sort_by(|a, b| self.ordered(a.1.total_cmp(&b.1)));
+            let mut previous: Option<&Value> = None;
+            for (value, key) in run.iter() {
+                if previous.is_some_and(|previous| previous.total_cmp(key).is_eq()) {
+                    continue;
+                }
+                previous = Some(key);
+                let rows = self.table_btree.search_all(key, self.extras)?;
+                self.rows.extend(
+                    rows.into_iter()
+                        .filter(|row| row.values.get(position).is_some_and(|stored| stored.total_cmp(value).is_eq())),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// `ordering` of ascending values, turned around for a descending scan
+    fn ordered(&self, ordering: std::cmp::Ordering) -> std::cmp::Ordering {
+        match self.order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+}
+
+fn open_btree(
+    db_file_path: &Path,
+    page_size: usize,
+    io_counters: &Arc<IoCounters>,
+    root_page_id: PageId,
+    key_columns: Vec<usize>,
+) -> Result<BPlusTree, DatabaseError> {
+    let file = page_store::open_store(db_file_path, None, page_size)?;
+    Ok(
+        BPlusTree::new_with_page_size(file, root_page_id, Some(BAMBANG_HEADER_SIZE as u64), page_size)?
+            .with_io_counters(io_counters.clone())
+            .with_key_columns(key_columns),
+    )
+}
+
+impl Scanner for IndexScanner {
+    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+        let started = Instant::now();
+        let row = self.next_row();
+        self.elapsed += started.elapsed();
+        row
+    }
+
+    fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
+        collect_batch(self, batch_size)
+    }
+
+    fn reset(&mut self) -> Result<(), DatabaseError> {
+        self.table_btree = self.open_btree(self.table_root_page_id, self.key_columns.clone())?;
+        self.index_btree = match &self.index {
+            Some((index, _)) => Some(self.open_btree(index.root_page_id, Vec::new())?),
+            None => None,
+        };
+        self.started = false;
+        self.next_leaf = None;
+        self.pending.clear();
+        self.rows.clear();
+        Ok(())
+    }
+}
+
+impl CollectMetrics for IndexScanner {
+    fn collect_metrics(&self) -> OperatorMetrics {
+        let direction = match self.order {
+            SortOrder::Ascending => "",
+            SortOrder::Descending => " Backward",
+        };
+        let tree = match &self.index {
+            Some((index, _)) => index.name.clone(),
+            None => "key".to_string(),
+        };
+        OperatorMetrics::new(format!(
+            "Index Scan{} using {} on {}, sort elided (index order)",
+            direction, tree, self.table_name
+        ))
+        .with_rows(self.rows_out, self.rows_out)
+        .with_detail("pages read", self.leaves_read)
+        .with_elapsed(self.elapsed)
+    }
+}
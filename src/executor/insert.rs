@@ -1,14 +1,18 @@
-use std::{
-    fs::{File, OpenOptions},
-    path::PathBuf,
-};
+use std::{cmp::Ordering, collections::HashSet, path::PathBuf, sync::Arc};
 
 use crate::{
-    storage::{bplus_tree::BPlusTree, storage_manager::StorageManager, BAMBANG_HEADER_SIZE},
+    storage::{
+        bplus_tree::BPlusTree, index::TableIndex, io_stats::IoCounters, journal::RollbackJournal,
+        page_store::{self, IoLog, PageStore}, schema::TableSchema, storage_manager::StorageManager,
+        BAMBANG_HEADER_SIZE,
+    },
     types::{
         error::DatabaseError,
+        page::PageType,
         row::Row,
+        value::{Value, ValueComparison},
         PageId,
+        RowId,
     },
 };
 
@@ -30,6 +34,29 @@ pub struct TableInserter {
     root_page_id: PageId,
     db_file_path: PathBuf,
     extras: Option<u64>,
+    /// Name and position of every UNIQUE column and of a single PRIMARY KEY column
+    unique_columns: Vec<(String, usize)>,
+    /// Positions of a composite PRIMARY KEY's columns, in key order, which the B+ tree
+    /// keys rows on; empty when rows are keyed on their first value
+    key_columns: Vec<usize>,
+    /// Composite key columns as named in errors, like `(a, b)`
+    key_name: String,
+    /// Decides whether a new unique value collides with a stored one of another type
+    value_comparison: ValueComparison,
+    /// The table's schema when it has CHECK constraints to evaluate rows against
+    check_schema: Option<TableSchema>,
+    /// Secondary indexes on the table and the position of the column each one covers.
+    /// Roots move here as index trees split.
+    indexes: Vec<(TableIndex, usize)>,
+    /// How full `insert_batch` packs the pages it writes
+    bulk_load_fill_factor: f64,
+    next_row_id: RowId,
+    io_counters: Arc<IoCounters>,
+    partial_page_writes: bool,
+    paranoid_checks: bool,
+    page_size: usize,
+    journal: Option<Arc<RollbackJournal>>,
+    io_log: Option<IoLog>,
 }
 
 impl TableInserter {
@@ -46,17 +73,74 @@ impl TableInserter {
                 name: table_name.clone(),
             })?;
 
+        let next_row_id = storage_manager.next_row_id(&table_name)?;
         let db_file_path = storage_manager.db_info.path.clone();
         let extras = Some(BAMBANG_HEADER_SIZE as u64);
+        let schema = storage_manager.load_table_schema(&table_name)?;
+        // Columns of a composite key are only unique together
+        let unique_columns = schema
+            .as_ref()
+            .map(|schema| {
+                schema
+                    .columns
+                    .iter()
+                    .filter(|column| column.unique || (column.primary_key && schema.key_columns().is_empty()))
+                    .map(|column| (column.name.clone(), column.position))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let key_columns = schema
+            .as_ref()
+            .map(|schema| schema.key_columns().to_vec())
+            .unwrap_or_default();
+        let key_name = schema
+            .as_ref()
+            .map(|schema| {
+                let names: Vec<&str> = schema
+                    .primary_key_columns()
+                    .iter()
+                    .map(|column| column.name.as_str())
+                    .collect();
+                format!("({})", names.join(", "))
+            })
+            .unwrap_or_default();
+        let check_schema = schema.filter(|schema| schema.has_checks()).cloned();
+        let indexes = storage_manager.table_indexes(&table_name)?;
 
         Ok(Self {
             table_name,
             root_page_id,
             db_file_path,
             extras,
+            unique_columns,
+            key_columns,
+            key_name,
+            value_comparison: storage_manager.options.value_comparison,
+            check_schema,
+            indexes,
+            bulk_load_fill_factor: storage_manager.options.bulk_load_fill_factor,
+            next_row_id,
+            io_counters: storage_manager.io_counters.clone(),
+            partial_page_writes: storage_manager.options.partial_page_writes,
+            paranoid_checks: storage_manager.options.paranoid_checks,
+            page_size: storage_manager.page_size(),
+            journal: storage_manager.journal.clone(),
+            io_log: storage_manager.options.io_log.clone(),
         })
     }
 
+    /// Insert a row unless it would violate a PRIMARY KEY or UNIQUE constraint. Returns
+    /// whether the row was inserted.
+    pub fn insert_or_ignore(&mut self, row: Row) -> Result<bool, DatabaseError> {
+        let mut btree = self.create_btree()?;
+        let mut index_btrees = self.create_index_btrees()?;
+        match self.insert_checked(&mut btree, &mut index_btrees, row) {
+            Ok(()) => Ok(true),
+            Err(DatabaseError::UniqueConstraintViolation { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Update the root page ID for this table (used when B+ tree splits cause root changes)
     pub fn update_root_page_id(&mut self, new_root_page_id: PageId) {
         self.root_page_id = new_root_page_id;
@@ -67,19 +151,507 @@ impl TableInserter {
         self.root_page_id
     }
 
+    /// Rowid the next inserted row without one will receive
+    pub fn next_row_id(&self) -> RowId {
+        self.next_row_id
+    }
+
+    /// The table's secondary indexes with their current roots
+    pub fn indexes(&self) -> impl Iterator<Item = &TableIndex> {
+        self.indexes.iter().map(|(index, _)| index)
+    }
+
     /// Open the database file for writing
-    fn open_db_file(&self) -> Result<File, DatabaseError> {
-        OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(&self.db_file_path)
-            .map_err(DatabaseError::from)
+    fn open_db_file(&self) -> Result<Box<dyn PageStore>, DatabaseError> {
+        page_store::open_store(&self.db_file_path, self.io_log.as_ref(), self.page_size)
     }
 
     /// Create a B+ tree instance for this table
     fn create_btree(&self) -> Result<BPlusTree, DatabaseError> {
+        self.open_btree(self.root_page_id)
+    }
+
+    /// One B+ tree per secondary index, in the order of `indexes`
+    fn create_index_btrees(&self) -> Result<Vec<BPlusTree>, DatabaseError> {
+        self.indexes
+            .iter()
+            .map(|(index, _)| self.open_btree(index.root_page_id))
+            .collect()
+    }
+
+    fn open_btree(&self, root_page_id: PageId) -> Result<BPlusTree, DatabaseError> {
         let file = self.open_db_file()?;
-        BPlusTree::new_with_extras(file, self.root_page_id, self.extras)
+        Ok(BPlusTree::new_with_page_size(file, root_page_id, self.extras, self.page_size)?
+            .with_io_counters(self.io_counters.clone())
+            .with_partial_writes(self.partial_page_writes)
+            .with_paranoid_checks(self.paranoid_checks)
+            .with_journal(self.journal.clone())
+            .with_key_columns(self.key_columns.clone()))
+    }
+
+    /// Whether the unique column at `position` is the one the B+ tree keys rows on, so a
+    /// point lookup finds its stored value
+    fn is_tree_key(&self, position: usize) -> bool {
+        self.key_columns.is_empty() && position == 0
+    }
+
+    /// The error for a row whose composite key is already taken
+    fn key_violation(&self, row: &Row) -> DatabaseError {
+        let values: Vec<String> = self
+            .key_columns
+            .iter()
+            .map(|position| row.values.get(*position).unwrap_or(&Value::Null).to_string())
+            .collect();
+        DatabaseError::UniqueConstraintViolation {
+            table: self.table_name.clone(),
+            column: self.key_name.clone(),
+            value: Value::Text(format!("({})", values.join(", "))),
+        }
+    }
+
+    /// Insert `row` after checking that none of its unique values are already taken, then
+    /// add its entries to `index_btrees`. Rows without a rowid are stamped with the next
+    /// one; explicit rowids move the counter past them.
+    fn insert_checked(
+        &mut self,
+        btree: &mut BPlusTree,
+        index_btrees: &mut [BPlusTree],
+        mut row: Row,
+    ) -> Result<(), DatabaseError> {
+        self.check_nan(&row)?;
+        self.check_constraints(&row)?;
+
+        if !self.key_columns.is_empty() && btree.search(&row.key(&self.key_columns), self.extras)?.is_some() {
+            return Err(self.key_violation(&row));
+        }
+
+        // NULLs never conflict, matching SQL semantics
+        let probes: Vec<(usize, &Value)> = self
+            .unique_columns
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (_, position))| {
+                row.values
+                    .get(*position)
+                    .filter(|value| !value.is_null())
+                    .map(|value| (index, value))
+            })
+            .collect();
+
+        if let Some(index) = self.find_conflict(btree, &probes)? {
+            let (column, position) = &self.unique_columns[index];
+            return Err(DatabaseError::UniqueConstraintViolation {
+                table: self.table_name.clone(),
+                column: column.clone(),
+                value: row.values[*position].clone(),
+            });
+        }
+
+        self.stamp_row_id(&mut row);
+
+        let index_entries: Vec<Option<Row>> = self
+            .indexes
+            .iter()
+            .map(|(_, position)| TableIndex::entry_for(&row, *position, &self.key_columns))
+            .collect();
+        if let Some(new_root_page_id) = btree.insert(row, self.extras)? {
+            self.update_root_page_id(new_root_page_id);
+        }
+        for ((index, _), (index_btree, entry)) in self
+            .indexes
+            .iter_mut()
+            .zip(index_btrees.iter_mut().zip(index_entries))
+        {
+            if let Some(entry) = entry
+                && let Some(new_root_page_id) = index_btree.insert(entry, self.extras)?
+            {
+                index.root_page_id = new_root_page_id;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject NaN where it would become a key: NaN equals nothing, not even itself, so
+    /// uniqueness could not be enforced for it and an index entry could never be found
+    fn check_nan(&self, row: &Row) -> Result<(), DatabaseError> {
+        if let Some((column, _)) = self
+            .unique_columns
+            .iter()
+            .find(|(_, position)| row.values.get(*position).is_some_and(Value::is_nan))
+        {
+            return Err(DatabaseError::InvalidData {
+                details: format!("NaN cannot be stored in UNIQUE column {}.{}", self.table_name, column),
+            });
+        }
+        if let Some((index, _)) = self
+            .indexes
+            .iter()
+            .find(|(_, position)| row.values.get(*position).is_some_and(Value::is_nan))
+        {
+            return Err(DatabaseError::InvalidData {
+                details: format!("NaN cannot be stored in indexed column {}.{}", self.table_name, index.column_name),
+            });
+        }
+        let key_columns: &[usize] = if self.key_columns.is_empty() { &[0] } else { &self.key_columns };
+        if key_columns.iter().any(|position| row.values.get(*position).is_some_and(Value::is_nan)) {
+            return Err(DatabaseError::InvalidData {
+                details: "NaN cannot be used as a key".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Reject a row that makes one of the table's CHECK constraints false
+    fn check_constraints(&self, row: &Row) -> Result<(), DatabaseError> {
+        match &self.check_schema {
+            Some(schema) => schema.check_row(row),
+            None => Ok(()),
+        }
+    }
+
+    /// Give a row without a rowid the next one; an explicit rowid moves the counter past it
+    fn stamp_row_id(&mut self, row: &mut Row) {
+        let row_id = *row.row_id.get_or_insert(self.next_row_id);
+        self.next_row_id = self.next_row_id.max(row_id + 1);
+    }
+
+    /// Whether the batch's non-NULL values in each unique column are all of one kind.
+    /// Sorted by `total_cmp`, such values sit next to every value they equal.
+    fn is_uniform(&self, rows: &[Row]) -> bool {
+        self.unique_columns.iter().all(|(_, position)| {
+            let mut kinds = rows
+                .iter()
+                .filter_map(|row| row.values.get(*position))
+                .filter(|value| !value.is_null())
+                .map(value_kind);
+            kinds.next().is_none_or(|first| kinds.all(|kind| kind == first))
+        })
+    }
+
+    /// Check a batch the way inserting its rows one at a time would, against stored rows
+    /// and the rows ahead of each one. Returns the rows before the first that would fail,
+    /// stamped with rowids, and that row's error. Needs an `is_uniform` batch.
+    fn check_batch(
+        &mut self,
+        btree: &mut BPlusTree,
+        mut rows: Vec<Row>,
+    ) -> Result<(Vec<Row>, Option<DatabaseError>), DatabaseError> {
+        let mut failure = None;
+        let mut limit = rows.len();
+        if let Some((row, error)) = rows
+            .iter()
+            .enumerate()
+            .find_map(|(row, values)| {
+                self.check_nan(values)
+                    .and_then(|()| self.check_constraints(values))
+                    .err()
+                    .map(|error| (row, error))
+            })
+        {
+            limit = row;
+            failure = Some(error);
+        }
+
+        // Per row, the unique column it collides on, the first in `find_conflict` order
+        let probe_order = |index: usize| if self.is_tree_key(self.unique_columns[index].1) { 0 } else { index + 1 };
+        let mut conflicts: Vec<Option<usize>> = vec![None; limit];
+        let mut mark = |row: usize, index: usize| {
+            let conflict = &mut conflicts[row];
+            if conflict.is_none_or(|current| probe_order(index) < probe_order(current)) {
+                *conflict = Some(index);
+            }
+        };
+
+        // A taken composite key is reported ahead of any column of the row
+        let mut key_conflicts = vec![false; limit];
+        if !self.key_columns.is_empty() {
+            let mut keys: Vec<(Value, usize)> = rows[..limit]
+                .iter()
+                .enumerate()
+                .map(|(row, values)| (values.key(&self.key_columns), row))
+                .collect();
+            keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for pair in keys.windows(2) {
+                if pair[0].0 == pair[1].0 {
+                    key_conflicts[pair[1].1] = true;
+                }
+            }
+            for (key, row) in &keys {
+                if btree.search(key, self.extras)?.is_some() {
+                    key_conflicts[*row] = true;
+                }
+            }
+        }
+
+        let mut scan_columns = Vec::new();
+        for (index, (_, position)) in self.unique_columns.iter().enumerate() {
+            let mut values: Vec<(&Value, usize)> = rows[..limit]
+                .iter()
+                .enumerate()
+                .filter_map(|(row, values)| {
+                    values.values.get(*position).filter(|value| !value.is_null()).map(|value| (value, row))
+                })
+                .collect();
+            // Stable, so equal values stay in row order
+            values.sort_by(|a, b| a.0.total_cmp(b.0));
+            for run in values.chunk_by(|a, b| a.0.total_cmp(b.0) == Ordering::Equal) {
+                for (offset, (value, row)) in run.iter().enumerate() {
+                    if run[..offset].iter().any(|(earlier, _)| self.value_comparison.equals(earlier, value)) {
+                        mark(*row, index);
+                    }
+                }
+            }
+
+            if self.is_tree_key(*position) {
+                for (value, row) in &values {
+                    let stored = btree.search(value, self.extras)?;
+                    if stored.is_some_and(|stored| {
+                        stored.values.first().is_some_and(|key| self.value_comparison.equals(key, value))
+                    }) {
+                        mark(*row, index);
+                    }
+                }
+            } else if !values.is_empty() {
+                scan_columns.push((index, *position, values));
+            }
+        }
+
+        // Columns other than the key share one walk of the leaf chain
+        if !scan_columns.is_empty() {
+            let mut next_page_id = Some(btree.first_leaf_page_id(self.extras)?);
+            while let Some(page_id) = next_page_id {
+                let page = btree.load_page(page_id, self.extras)?.clone();
+                if page.page_type != PageType::LeafTable {
+                    break;
+                }
+                for i in 0..page.slot_directory.slots.len() {
+                    let Some(stored) = btree.leaf_row(&page, i, self.extras)? else {
+                        continue;
+                    };
+                    for (index, position, values) in &scan_columns {
+                        let Some(stored_value) = stored.values.get(*position).filter(|value| !value.is_null()) else {
+                            continue;
+                        };
+                        // A stored value of another kind may equal batch values anywhere in
+                        // their order, so it is compared against all of them
+                        let candidates = if value_kind(values[0].0) == value_kind(stored_value) {
+                            let start = values.partition_point(|(value, _)| value.total_cmp(stored_value) == Ordering::Less);
+                            let end = values.partition_point(|(value, _)| value.total_cmp(stored_value) != Ordering::Greater);
+                            &values[start..end]
+                        } else {
+                            &values[..]
+                        };
+                        for (value, row) in candidates {
+                            if self.value_comparison.equals(stored_value, value) {
+                                mark(*row, *index);
+                            }
+                        }
+                    }
+                }
+                next_page_id = page.next_leaf_page_id;
+            }
+        }
+
+        if let Some(row) = (0..limit).find(|&row| key_conflicts[row] || conflicts[row].is_some()) {
+            failure = Some(match conflicts[row] {
+                Some(index) if !key_conflicts[row] => {
+                    let (column, position) = &self.unique_columns[index];
+                    DatabaseError::UniqueConstraintViolation {
+                        table: self.table_name.clone(),
+                        column: column.clone(),
+                        value: rows[row].values[*position].clone(),
+                    }
+                }
+                _ => self.key_violation(&rows[row]),
+            });
+            limit = row;
+        }
+        rows.truncate(limit);
+        for row in &mut rows {
+            self.stamp_row_id(row);
+        }
+        Ok((rows, failure))
+    }
+
+    /// Return the index into `unique_columns` of the first probed value already stored.
+    /// The B+ tree key column is checked with a point lookup; other columns share a single
+    /// walk of the leaf chain until they have indexes of their own.
+    fn find_conflict(
+        &self,
+        btree: &mut BPlusTree,
+        probes: &[(usize, &Value)],
+    ) -> Result<Option<usize>, DatabaseError> {
+        let mut scan_probes = Vec::new();
+        for &(index, value) in probes {
+            if self.is_tree_key(self.unique_columns[index].1) {
+                let stored = btree.search(value, self.extras)?;
+                if stored.is_some_and(|row| {
+                    row.values
+                        .first()
+                        .is_some_and(|key| self.value_comparison.equals(key, value))
+                }) {
+                    return Ok(Some(index));
+                }
+            } else {
+                scan_probes.push((index, value));
+            }
+        }
+        if scan_probes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut next_page_id = Some(btree.first_leaf_page_id(self.extras)?);
+        while let Some(page_id) = next_page_id {
+            let page = btree.load_page(page_id, self.extras)?.clone();
+            if page.page_type != PageType::LeafTable {
+                break;
+            }
+            for slot_index in 0..page.slot_directory.slots.len() {
+                let Some(row) = btree.leaf_row(&page, slot_index, self.extras)? else {
+                    continue;
+                };
+                for &(index, value) in &scan_probes {
+                    if row
+                        .values
+                        .get(self.unique_columns[index].1)
+                        .is_some_and(|stored| self.value_comparison.equals(stored, value))
+                    {
+                        return Ok(Some(index));
+                    }
+                }
+            }
+            next_page_id = page.next_leaf_page_id;
+        }
+        Ok(None)
+    }
+
+    /// Check `rows`, which are about to replace the stored rows in `replaced`, as inserting
+    /// them would: against every stored row left in place and against each other.
+    /// `replaced` gives where each replaced row is stored and its current values, in the
+    /// order of `rows`. Nothing is written.
+    pub fn check_rewrites(
+        &self,
+        btree: &mut BPlusTree,
+        replaced: &[(PageId, usize, Row)],
+        rows: &[Row],
+    ) -> Result<(), DatabaseError> {
+        for row in rows {
+            self.check_nan(row)?;
+        }
+
+        if !self.key_columns.is_empty() {
+            let mut keys: Vec<(Value, usize)> =
+                rows.iter().enumerate().map(|(row, values)| (values.key(&self.key_columns), row)).collect();
+            keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+            if let Some(pair) = keys.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(self.key_violation(&rows[pair[1].1]));
+            }
+            // A key held by a replaced row is free to take
+            let mut old_keys: Vec<Value> = replaced.iter().map(|(_, _, row)| row.key(&self.key_columns)).collect();
+            old_keys.sort_by(|a, b| a.total_cmp(b));
+            for (key, row) in &keys {
+                if old_keys.binary_search_by(|old| old.total_cmp(key)).is_err()
+                    && btree.search(key, self.extras)?.is_some()
+                {
+                    return Err(self.key_violation(&rows[*row]));
+                }
+            }
+        }
+
+        let violation = |index: usize, row: usize| {
+            let (column, position) = &self.unique_columns[index];
+            DatabaseError::UniqueConstraintViolation {
+                table: self.table_name.clone(),
+                column: column.clone(),
+                value: rows[row].values[*position].clone(),
+            }
+        };
+        let mut scan_columns = Vec::new();
+        for (index, (_, position)) in self.unique_columns.iter().enumerate() {
+            // NULLs never conflict, matching SQL semantics
+            let mut values: Vec<(&Value, usize)> = rows
+                .iter()
+                .enumerate()
+                .filter_map(|(row, values)| {
+                    values.values.get(*position).filter(|value| !value.is_null()).map(|value| (value, row))
+                })
+                .collect();
+            values.sort_by(|a, b| a.0.total_cmp(b.0));
+            if let Some(row) = self.first_duplicate(&values) {
+                return Err(violation(index, row));
+            }
+
+            // Only a changed value can collide with a row left in place
+            let changed: Vec<(&Value, usize)> = values
+                .into_iter()
+                .filter(|(value, row)| {
+                    replaced[*row].2.values.get(*position).is_none_or(|old| !self.value_comparison.equals(old, value))
+                })
+                .collect();
+            if self.is_tree_key(*position) {
+                let mut old_keys: Vec<&Value> = replaced.iter().filter_map(|(_, _, row)| row.values.first()).collect();
+                old_keys.sort_by(|a, b| a.total_cmp(b));
+                for (value, row) in &changed {
+                    let stored = btree.search(value, self.extras)?;
+                    if let Some(key) = stored.as_ref().and_then(|stored| stored.values.first())
+                        && self.value_comparison.equals(key, value)
+                        && old_keys.binary_search_by(|old| old.total_cmp(key)).is_err()
+                    {
+                        return Err(violation(index, *row));
+                    }
+                }
+            } else if !changed.is_empty() {
+                scan_columns.push((index, *position, changed));
+            }
+        }
+        if scan_columns.is_empty() {
+            return Ok(());
+        }
+
+        let replaced_cells: HashSet<(PageId, usize)> =
+            replaced.iter().map(|(page_id, slot_index, _)| (*page_id, *slot_index)).collect();
+        let mut next_page_id = Some(btree.first_leaf_page_id(self.extras)?);
+        while let Some(page_id) = next_page_id {
+            let page = btree.load_page(page_id, self.extras)?.clone();
+            if page.page_type != PageType::LeafTable {
+                break;
+            }
+            for slot_index in 0..page.slot_directory.slots.len() {
+                if replaced_cells.contains(&(page_id, slot_index)) {
+                    continue;
+                }
+                let Some(stored) = btree.leaf_row(&page, slot_index, self.extras)? else {
+                    continue;
+                };
+                for (index, position, values) in &scan_columns {
+                    let Some(stored_value) = stored.values.get(*position).filter(|value| !value.is_null()) else {
+                        continue;
+                    };
+                    if let Some((_, row)) = values.iter().find(|(value, _)| self.value_comparison.equals(stored_value, value)) {
+                        return Err(violation(*index, *row));
+                    }
+                }
+            }
+            next_page_id = page.next_leaf_page_id;
+        }
+        Ok(())
+    }
+
+    /// The row of the first value in `values`, sorted by `total_cmp`, that equals an earlier
+    /// one. Values of one kind that are equal sit next to each other; a mix of kinds may
+    /// equal each other anywhere in that order, so every pair is compared.
+    fn first_duplicate(&self, values: &[(&Value, usize)]) -> Option<usize> {
+        let uniform = values.windows(2).all(|pair| value_kind(pair[0].0) == value_kind(pair[1].0));
+        let mut duplicates = values.iter().enumerate().filter(|(offset, (value, _))| {
+            let earlier = if uniform {
+                let start = values[..*offset].partition_point(|(earlier, _)| earlier.total_cmp(value) == Ordering::Less);
+                &values[start..*offset]
+            } else {
+                &values[..*offset]
+            };
+            earlier.iter().any(|(earlier, _)| self.value_comparison.equals(earlier, value))
+        });
+        duplicates.next().map(|(_, (_, row))| *row)
     }
 }
 
@@ -93,15 +665,10 @@ impl Inserter for TableInserter {
             });
         }
 
-        // Create B+ tree instance and perform insertion
+        // Create B+ tree instance and perform insertion, tracking root page changes
         let mut btree = self.create_btree()?;
-        
-        // Insert the row and handle potential root page changes
-        if let Some(new_root_page_id) = btree.insert(row, self.extras)? {
-            self.update_root_page_id(new_root_page_id);
-        }
-
-        Ok(())
+        let mut index_btrees = self.create_index_btrees()?;
+        self.insert_checked(&mut btree, &mut index_btrees, row)
     }
 
     fn insert_batch(&mut self, rows: Vec<Row>) -> Result<(), DatabaseError> {
@@ -119,27 +686,43 @@ impl Inserter for TableInserter {
             }
         }
 
-        // Create B+ tree instance once for the entire batch
+        // Create B+ tree instances once for the entire batch
         let mut btree = self.create_btree()?;
-        
-        // Insert all rows in the batch
-        for (index, row) in rows.into_iter().enumerate() {
-            match btree.insert(row, self.extras) {
-                Ok(Some(new_root_page_id)) => {
-                    self.update_root_page_id(new_root_page_id);
-                }
-                Ok(None) => {
-                    // Normal insertion, no root change
-                }
-                Err(e) => {
-                    return Err(DatabaseError::CorruptedDatabase {
-                        reason: format!("Failed to insert row at index {}: {}", index, e),
-                    });
-                }
+        let mut index_btrees = self.create_index_btrees()?;
+
+        // Mixed kinds of unique values might not sort next to the values they equal, so
+        // such a batch is checked and inserted row by row
+        if !self.is_uniform(&rows) {
+            for row in rows {
+                self.insert_checked(&mut btree, &mut index_btrees, row)?;
             }
+            return Ok(());
         }
 
-        Ok(())
+        // Rows before a failure stay inserted, as if the batch were inserted row by row
+        let (rows, failure) = self.check_batch(&mut btree, rows)?;
+        let index_entries: Vec<Vec<Row>> = self
+            .indexes
+            .iter()
+            .map(|(_, position)| {
+                rows.iter()
+                    .filter_map(|row| TableIndex::entry_for(row, *position, &self.key_columns))
+                    .collect()
+            })
+            .collect();
+        if let Some(new_root_page_id) = btree.bulk_insert(rows, self.bulk_load_fill_factor, self.extras)? {
+            self.update_root_page_id(new_root_page_id);
+        }
+        for ((index, _), (index_btree, entries)) in self
+            .indexes
+            .iter_mut()
+            .zip(index_btrees.iter_mut().zip(index_entries))
+        {
+            if let Some(new_root_page_id) = index_btree.bulk_insert(entries, self.bulk_load_fill_factor, self.extras)? {
+                index.root_page_id = new_root_page_id;
+            }
+        }
+        failure.map_or(Ok(()), Err)
     }
 
     fn table_name(&self) -> &str {
@@ -172,3 +755,16 @@ impl<I: Inserter> InsertIterator<I> {
         self.inserter.table_name()
     }
 }
+
+/// Values of one kind compare consistently under `total_cmp`; numbers of every type are
+/// one kind
+fn value_kind(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Integer(_) | Value::Real(_) | Value::Decimal(_) => 1,
+        Value::Boolean(_) => 2,
+        Value::Timestamp(_) => 3,
+        Value::Text(_) => 4,
+        Value::Blob(_) => 5,
+    }
+}
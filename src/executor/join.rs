@@ -0,0 +1,136 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    executor::{
+        metrics::{CollectMetrics, OperatorMetrics},
+        scan::{Scanner, collect_batch},
+    },
+    types::{
+        error::DatabaseError,
+        row::Row,
+        value::{HashableValue, Value},
+    },
+};
+
+/// Inner equi-join pairing each row of `probe` with every row of `build` whose key
+/// column equals its own. The build side is read into a hash table on the first `scan`,
+/// then probe rows are streamed through it. Output rows hold the probe row's values
+/// followed by the build row's and carry no rowid. Keys compare as `HashableValue`s, so
+/// `1` matches `1.0`; NULL keys match nothing, as in SQL.
+pub struct HashJoinExecutor<P: Scanner, B: Scanner> {
+    probe: P,
+    build: B,
+    probe_key: usize,
+    build_key: usize,
+    /// Join condition, shown in metrics
+    condition: String,
+    table: Option<HashMap<HashableValue, Vec<Row>>>,
+    /// Joined rows of the current probe row not yet returned
+    pending: VecDeque<Row>,
+    build_rows: u64,
+    probe_rows: u64,
+    rows_out: u64,
+    elapsed: Duration,
+}
+
+impl<P: Scanner, B: Scanner> HashJoinExecutor<P, B> {
+    /// Join on `probe.values[probe_key] = build.values[build_key]`
+    pub fn new(probe: P, build: B, probe_key: usize, build_key: usize) -> Self {
+        Self {
+            probe,
+            build,
+            probe_key,
+            build_key,
+            condition: format!("#{} = #{}", probe_key, build_key),
+            table: None,
+            pending: VecDeque::new(),
+            build_rows: 0,
+            probe_rows: 0,
+            rows_out: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Describe the join condition as `condition` in metrics, e.g. `u.id = o.user_id`
+    pub fn with_condition(mut self, condition: impl Into<String>) -> Self {
+        self.condition = condition.into();
+        self
+    }
+
+    fn build_table(&mut self) -> Result<HashMap<HashableValue, Vec<Row>>, DatabaseError> {
+        let mut table: HashMap<HashableValue, Vec<Row>> = HashMap::new();
+        while let Some(row) = self.build.scan()? {
+            self.build_rows += 1;
+            let key = row.values.get(self.build_key).cloned().unwrap_or(Value::Null);
+            if !key.is_null() {
+                table.entry(HashableValue(key)).or_default().push(row);
+            }
+        }
+        Ok(table)
+    }
+
+    fn next_row(&mut self) -> Result<Option<Row>, DatabaseError> {
+        if self.table.is_none() {
+            self.table = Some(self.build_table()?);
+        }
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                self.rows_out += 1;
+                return Ok(Some(row));
+            }
+            let Some(probe_row) = self.probe.scan()? else {
+                return Ok(None);
+            };
+            self.probe_rows += 1;
+            let key = probe_row.values.get(self.probe_key).cloned().unwrap_or(Value::Null);
+            if key.is_null() {
+                continue;
+            }
+            let matches = self.table.as_ref().and_then(|table| table.get(&HashableValue(key)));
+            for build_row in matches.into_iter().flatten() {
+                let mut values = probe_row.values.clone();
+                values.extend(build_row.values.iter().cloned());
+                self.pending.push_back(Row::new(values));
+            }
+        }
+    }
+}
+
+impl<P: Scanner, B: Scanner> Scanner for HashJoinExecutor<P, B> {
+    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+        let started = Instant::now();
+        let row = self.next_row();
+        self.elapsed += started.elapsed();
+        row
+    }
+
+    fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
+        collect_batch(self, batch_size)
+    }
+
+    /// Rewind both inputs; the hash table is rebuilt, picking up changes to the build side
+    fn reset(&mut self) -> Result<(), DatabaseError> {
+        self.table = None;
+        self.pending.clear();
+        self.probe.reset()?;
+        self.build.reset()
+    }
+}
+
+impl<P: Scanner + CollectMetrics, B: Scanner + CollectMetrics> CollectMetrics for HashJoinExecutor<P, B> {
+    /// Rows in counts both sides; the probe input is listed first
+    fn collect_metrics(&self) -> OperatorMetrics {
+        let buckets = self.table.as_ref().map_or(0, HashMap::len);
+        OperatorMetrics::new(format!("Hash Join: {}", self.condition))
+            .with_rows(self.probe_rows + self.build_rows, self.rows_out)
+            .with_detail("build rows", self.build_rows)
+            .with_detail("buckets", buckets)
+            .with_detail("probe rows", self.probe_rows)
+            .with_elapsed(self.elapsed)
+            .with_child(self.probe.collect_metrics())
+            .with_child(self.build.collect_metrics())
+    }
+}
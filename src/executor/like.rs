@@ -0,0 +1,87 @@
+use crate::types::error::DatabaseError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    /// `%`: any run of characters, including none
+    AnyRun,
+    /// `_`: exactly one character
+    AnyChar,
+    Literal(char),
+}
+
+/// Match `text` against a SQL LIKE `pattern`, where `%` matches any run of characters
+/// and `_` exactly one. A character preceded by `escape` matches itself, so with
+/// `escape = Some('\\')` the pattern `50\%` matches only the text `50%`. Characters are
+/// compared by Unicode scalar value, folded to lowercase when `case_insensitive`.
+///
+/// A pattern ending in the escape character has nothing to escape and is an error.
+pub fn like_match(
+    text: &str,
+    pattern: &str,
+    escape: Option<char>,
+    case_insensitive: bool,
+) -> Result<bool, DatabaseError> {
+    let tokens = tokenize(pattern, escape)?;
+    let text: Vec<char> = text.chars().collect();
+    let chars_equal = |a: char, b: char| {
+        a == b || (case_insensitive && a.to_lowercase().eq(b.to_lowercase()))
+    };
+
+    // Two pointers with backtracking to the most recent `%`, which may only ever need
+    // to swallow one more character: linear in the common case, O(n * m) at worst
+    let (mut t, mut p) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match tokens.get(p) {
+            Some(Token::AnyChar) => {
+                t += 1;
+                p += 1;
+            }
+            Some(Token::Literal(c)) if chars_equal(*c, text[t]) => {
+                t += 1;
+                p += 1;
+            }
+            Some(Token::AnyRun) => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            _ => match backtrack {
+                Some((run_p, run_t)) => {
+                    backtrack = Some((run_p, run_t + 1));
+                    p = run_p + 1;
+                    t = run_t + 1;
+                }
+                None => return Ok(false),
+            },
+        }
+    }
+    Ok(tokens[p..].iter().all(|token| *token == Token::AnyRun))
+}
+
+fn tokenize(pattern: &str, escape: Option<char>) -> Result<Vec<Token>, DatabaseError> {
+    let mut tokens = Vec::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let token = if Some(c) == escape {
+            match chars.next() {
+                Some(escaped) => Token::Literal(escaped),
+                None => {
+                    return Err(DatabaseError::ExecutionError {
+                        details: format!("LIKE pattern '{}' ends with its escape character", pattern),
+                    });
+                }
+            }
+        } else {
+            match c {
+                '%' => Token::AnyRun,
+                '_' => Token::AnyChar,
+                c => Token::Literal(c),
+            }
+        };
+        // Consecutive `%` match the same as one
+        if !(token == Token::AnyRun && tokens.last() == Some(&Token::AnyRun)) {
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
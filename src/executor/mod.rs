@@ -1,7 +1,16 @@
+pub mod aggregate;
 pub mod create_table;
 pub mod delete;
+pub mod distinct;
+pub mod index_scan;
 pub mod insert;
 pub mod join;
+pub mod like;
+pub mod metrics;
 pub mod predicate;
+pub mod predicate_parser;
 pub mod scan;
 pub mod sequential_scan;
+pub mod sort;
+pub mod statement;
+pub mod subquery;
@@ -1,11 +1,17 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, fmt, sync::Arc};
 
 use crate::{
-    storage::schema::TableSchema,
+    executor::{
+        like::like_match,
+        predicate_parser::{is_keyword, parse_predicate},
+        subquery::ValueSet,
+    },
+    storage::schema::{ColumnSource, PseudoColumn, TableSchema},
     types::{
+        RowId,
         error::DatabaseError,
         row::Row,
-        value::{DataType, Value},
+        value::{Value, ValueComparison},
     },
 };
 
@@ -20,12 +26,77 @@ pub enum ComparisonOp {
     GreaterThanOrEqual,
     IsNull,
     IsNotNull,
-    Like,
-    NotLike,
+    /// SQL LIKE against a text pattern, optionally with an ESCAPE character
+    Like { escape: Option<char> },
+    NotLike { escape: Option<char> },
+    /// Case-insensitive LIKE
+    ILike { escape: Option<char> },
     In,
     NotIn,
 }
 
+/// Truth value of a predicate under SQL's three-valued logic, where comparing with NULL
+/// is `Unknown`. AND, OR and NOT follow Kleene logic, e.g. `Unknown AND False` is `False`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriBool {
+    True,
+    False,
+    Unknown,
+}
+
+impl TriBool {
+    pub fn and(self, other: TriBool) -> TriBool {
+        match (self, other) {
+            (TriBool::False, _) | (_, TriBool::False) => TriBool::False,
+            (TriBool::True, TriBool::True) => TriBool::True,
+            _ => TriBool::Unknown,
+        }
+    }
+
+    pub fn or(self, other: TriBool) -> TriBool {
+        match (self, other) {
+            (TriBool::True, _) | (_, TriBool::True) => TriBool::True,
+            (TriBool::False, TriBool::False) => TriBool::False,
+            _ => TriBool::Unknown,
+        }
+    }
+
+    /// Whether a row with this result passes a filter
+    pub fn is_true(self) -> bool {
+        self == TriBool::True
+    }
+}
+
+impl std::ops::Not for TriBool {
+    type Output = TriBool;
+
+    fn not(self) -> Self::Output {
+        match self {
+            TriBool::True => TriBool::False,
+            TriBool::False => TriBool::True,
+            TriBool::Unknown => TriBool::Unknown,
+        }
+    }
+}
+
+impl From<bool> for TriBool {
+    fn from(value: bool) -> Self {
+        if value { TriBool::True } else { TriBool::False }
+    }
+}
+
+/// How predicates treat NULL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PredicateMode {
+    /// Comparisons are plain booleans: `NULL = NULL` is true, `NULL > 1` false, and
+    /// `NOT (NULL > 1)` true
+    #[default]
+    Legacy,
+    /// SQL three-valued logic: comparisons with NULL are unknown, stay unknown under NOT,
+    /// and filter the row out
+    Sql,
+}
+
 /// Logical operators for combining predicates
 #[derive(Debug, Clone, PartialEq)]
 pub enum LogicalOp {
@@ -34,6 +105,106 @@ pub enum LogicalOp {
     Not,
 }
 
+/// Arithmetic operators usable in expressions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithmeticOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// A scalar expression over the columns of a row
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Column(String),
+    Literal(Value),
+    BinaryOp {
+        op: ArithmeticOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+}
+
+impl Expr {
+    pub fn column(name: String) -> Self {
+        Self::Column(name)
+    }
+
+    pub fn literal(value: Value) -> Self {
+        Self::Literal(value)
+    }
+
+    pub fn binary(op: ArithmeticOp, left: Expr, right: Expr) -> Self {
+        Self::BinaryOp {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Compute the expression for a row. NULL operands yield NULL, and so does dividing
+    /// by zero, as in SQLite; integer operands stay integers unless the result
+    /// overflows, everything else is computed as a real.
+    pub fn evaluate(&self, row: &Row, schema: &TableSchema) -> Result<Value, DatabaseError> {
+        match self {
+            Expr::Column(column_name) => {
+                Predicate::column_value(row, schema, column_name).map(Cow::into_owned)
+            }
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::BinaryOp { op, left, right } => {
+                let left = left.evaluate(row, schema)?;
+                let right = right.evaluate(row, schema)?;
+                Self::apply(*op, &left, &right)
+            }
+        }
+    }
+
+    fn apply(op: ArithmeticOp, left: &Value, right: &Value) -> Result<Value, DatabaseError> {
+        if left.is_null() || right.is_null() {
+            return Ok(Value::Null);
+        }
+        if let (Value::Integer(a), Value::Integer(b)) = (left, right) {
+            let exact = match op {
+                ArithmeticOp::Add => a.checked_add(*b),
+                ArithmeticOp::Sub => a.checked_sub(*b),
+                ArithmeticOp::Mul => a.checked_mul(*b),
+                ArithmeticOp::Div if *b == 0 => return Ok(Value::Null),
+                ArithmeticOp::Div if a % b == 0 => a.checked_div(*b),
+                ArithmeticOp::Div => None,
+            };
+            if let Some(result) = exact {
+                return Ok(Value::Integer(result));
+            }
+        }
+        let operand = |value: &Value| {
+            value.coerce_to_number().ok_or_else(|| DatabaseError::TypeMismatch {
+                expected: "numeric operand".to_string(),
+                actual: value.data_type().to_string(),
+            })
+        };
+        let (a, b) = (operand(left)?, operand(right)?);
+        Ok(Value::Real(match op {
+            ArithmeticOp::Add => a + b,
+            ArithmeticOp::Sub => a - b,
+            ArithmeticOp::Mul => a * b,
+            ArithmeticOp::Div if b == 0.0 => return Ok(Value::Null),
+            ArithmeticOp::Div => a / b,
+        }))
+    }
+
+    fn collect_columns(&self, columns: &mut Vec<String>) {
+        match self {
+            Expr::Column(column_name) => columns.push(column_name.clone()),
+            Expr::Literal(_) => {}
+            Expr::BinaryOp { left, right, .. } => {
+                left.collect_columns(columns);
+                right.collect_columns(columns);
+            }
+        }
+    }
+}
+
 /// A predicate expression for filtering rows
 #[derive(Debug, Clone, PartialEq)]
 pub enum Predicate {
@@ -43,12 +214,38 @@ pub enum Predicate {
         op: ComparisonOp,
         value: Value,
     },
+    /// Comparison between two expressions, e.g. `price > quantity * 2`
+    ExprComparison {
+        left: Expr,
+        op: ComparisonOp,
+        right: Expr,
+    },
+    /// column_name [NOT] BETWEEN low AND high, bounds included
+    Between {
+        column_name: String,
+        low: Expr,
+        high: Expr,
+        negated: bool,
+    },
     /// Column comparison with multiple values (for IN/NOT IN)
     InList {
         column_name: String,
         values: Vec<Value>,
         negated: bool,
     },
+    /// Membership in another table's column: column_name [NOT] IN (SELECT other_column FROM other_table)
+    InTable {
+        column_name: String,
+        other_table: String,
+        other_column: String,
+        negated: bool,
+    },
+    /// Resolved form of `InTable`, holding the materialized values of the other column
+    InValueSet {
+        column_name: String,
+        values: Arc<ValueSet>,
+        negated: bool,
+    },
     /// Logical combination of predicates
     Logical {
         op: LogicalOp,
@@ -116,6 +313,72 @@ impl Predicate {
         }
     }
 
+    /// Create a LIKE predicate, e.g. `'a%b_'`
+    pub fn like(column_name: String, pattern: String) -> Self {
+        Self::Comparison {
+            column_name,
+            op: ComparisonOp::Like { escape: None },
+            value: Value::Text(pattern),
+        }
+    }
+
+    /// Create a LIKE predicate where `escape` makes the character after it literal
+    pub fn like_with_escape(column_name: String, pattern: String, escape: char) -> Self {
+        Self::Comparison {
+            column_name,
+            op: ComparisonOp::Like { escape: Some(escape) },
+            value: Value::Text(pattern),
+        }
+    }
+
+    /// Create a NOT LIKE predicate
+    pub fn not_like(column_name: String, pattern: String) -> Self {
+        Self::Comparison {
+            column_name,
+            op: ComparisonOp::NotLike { escape: None },
+            value: Value::Text(pattern),
+        }
+    }
+
+    /// Create a case-insensitive LIKE predicate
+    pub fn ilike(column_name: String, pattern: String) -> Self {
+        Self::Comparison {
+            column_name,
+            op: ComparisonOp::ILike { escape: None },
+            value: Value::Text(pattern),
+        }
+    }
+
+    /// Create a comparison between two expressions
+    pub fn compare_exprs(left: Expr, op: ComparisonOp, right: Expr) -> Self {
+        Self::ExprComparison { left, op, right }
+    }
+
+    /// Create a BETWEEN predicate with constant bounds
+    pub fn between(column_name: String, low: Value, high: Value) -> Self {
+        Self::between_exprs(column_name, Expr::Literal(low), Expr::Literal(high))
+    }
+
+    /// Create a NOT BETWEEN predicate with constant bounds
+    pub fn not_between(column_name: String, low: Value, high: Value) -> Self {
+        Self::Between {
+            column_name,
+            low: Expr::Literal(low),
+            high: Expr::Literal(high),
+            negated: true,
+        }
+    }
+
+    /// Create a BETWEEN predicate whose bounds are computed per row
+    pub fn between_exprs(column_name: String, low: Expr, high: Expr) -> Self {
+        Self::Between {
+            column_name,
+            low,
+            high,
+            negated: false,
+        }
+    }
+
     /// Create an IS NULL predicate
     pub fn is_null(column_name: String) -> Self {
         Self::Comparison {
@@ -152,6 +415,26 @@ impl Predicate {
         }
     }
 
+    /// Create an IN predicate against the values of another table's column
+    pub fn in_table(column_name: String, other_table: String, other_column: String) -> Self {
+        Self::InTable {
+            column_name,
+            other_table,
+            other_column,
+            negated: false,
+        }
+    }
+
+    /// Create a NOT IN predicate against the values of another table's column
+    pub fn not_in_table(column_name: String, other_table: String, other_column: String) -> Self {
+        Self::InTable {
+            column_name,
+            other_table,
+            other_column,
+            negated: true,
+        }
+    }
+
     /// Create an AND predicate
     pub fn and(left: Predicate, right: Predicate) -> Self {
         Self::Logical {
@@ -170,184 +453,214 @@ impl Predicate {
         }
     }
 
-    /// Create a NOT predicate
-    pub fn not(predicate: Predicate) -> Self {
-        Self::Logical {
-            op: LogicalOp::Not,
-            left: Box::new(predicate),
-            right: None,
-        }
-    }
-
     /// Evaluate the predicate against a row using the table schema
     pub fn evaluate(&self, row: &Row, schema: &TableSchema) -> Result<bool, DatabaseError> {
-        match self {
+        self.evaluate_with(row, schema, ValueComparison::Coercive)
+    }
+
+    /// Evaluate the predicate, relating values of different types according to `mode`
+    pub fn evaluate_with(
+        &self,
+        row: &Row,
+        schema: &TableSchema,
+        mode: ValueComparison,
+    ) -> Result<bool, DatabaseError> {
+        self.evaluate_in(row, schema, mode, PredicateMode::Legacy)
+    }
+
+    /// Whether the row passes the filter under `predicate_mode`: only rows the predicate
+    /// is true for do, never those it is false or unknown for
+    pub fn evaluate_in(
+        &self,
+        row: &Row,
+        schema: &TableSchema,
+        mode: ValueComparison,
+        predicate_mode: PredicateMode,
+    ) -> Result<bool, DatabaseError> {
+        Ok(self.evaluate_tri(row, schema, mode, predicate_mode)?.is_true())
+    }
+
+    /// Evaluate the predicate to a truth value. Under `PredicateMode::Legacy` the result
+    /// is never `Unknown`.
+    pub fn evaluate_tri(
+        &self,
+        row: &Row,
+        schema: &TableSchema,
+        mode: ValueComparison,
+        predicate_mode: PredicateMode,
+    ) -> Result<TriBool, DatabaseError> {
+        let result = match self {
             Predicate::Comparison { column_name, op, value } => {
-                let column_index = schema.get_column_index(column_name)
-                    .ok_or_else(|| DatabaseError::ColumnNotFound {
-                        name: column_name.clone(),
-                        table: schema.table_name.clone(),
-                    })?;
-
-                if column_index >= row.values.len() {
-                    return Err(DatabaseError::ColumnIndexOutOfBounds { index: column_index });
+                let row_value = Self::column_value(row, schema, column_name)?;
+                let row_value = row_value.as_ref();
+                self.compare_values(row_value, op, value, mode, predicate_mode)?
+            }
+            Predicate::ExprComparison { left, op, right } => {
+                let left = left.evaluate(row, schema)?;
+                let right = right.evaluate(row, schema)?;
+                // A computed NULL is unknown, so only the null checks can match it
+                if (left.is_null() || right.is_null())
+                    && !matches!(op, ComparisonOp::IsNull | ComparisonOp::IsNotNull)
+                {
+                    TriBool::Unknown
+                } else {
+                    self.compare_values(&left, op, &right, mode, predicate_mode)?
                 }
-
-                let row_value = &row.values[column_index];
-                self.compare_values(row_value, op, value)
+            }
+            Predicate::Between { column_name, low, high, negated } => {
+                // Like `x >= low AND x <= high`, so a NULL operand or bound is unknown
+                let row_value = Self::column_value(row, schema, column_name)?;
+                let row_value = row_value.as_ref();
+                let within = |op: ComparisonOp, bound: &Expr| {
+                    let bound = bound.evaluate(row, schema)?;
+                    if row_value.is_null() || bound.is_null() {
+                        return Ok(TriBool::Unknown);
+                    }
+                    self.compare_values(row_value, &op, &bound, mode, predicate_mode)
+                };
+                let between = within(ComparisonOp::GreaterThanOrEqual, low)?
+                    .and(within(ComparisonOp::LessThanOrEqual, high)?);
+                if *negated { !between } else { between }
             }
             Predicate::InList { column_name, values, negated } => {
-                let column_index = schema.get_column_index(column_name)
-                    .ok_or_else(|| DatabaseError::ColumnNotFound {
-                        name: column_name.clone(),
-                        table: schema.table_name.clone(),
-                    })?;
-
-                if column_index >= row.values.len() {
-                    return Err(DatabaseError::ColumnIndexOutOfBounds { index: column_index });
-                }
-
-                let row_value = &row.values[column_index];
-                let in_list = values.iter().any(|v| self.values_equal(row_value, v));
-                Ok(if *negated { !in_list } else { in_list })
+                let row_value = Self::column_value(row, schema, column_name)?;
+                let row_value = row_value.as_ref();
+                let in_list = match predicate_mode {
+                    PredicateMode::Legacy => TriBool::from(values.iter().any(|v| mode.equals(row_value, v))),
+                    // x IN (a, b) is x = a OR x = b
+                    PredicateMode::Sql if row_value.is_null() => TriBool::Unknown,
+                    PredicateMode::Sql if values.iter().any(|v| !v.is_null() && mode.equals(row_value, v)) => {
+                        TriBool::True
+                    }
+                    PredicateMode::Sql if values.iter().any(Value::is_null) => TriBool::Unknown,
+                    PredicateMode::Sql => TriBool::False,
+                };
+                if *negated { !in_list } else { in_list }
+            }
+            Predicate::InTable { other_table, .. } => {
+                return Err(DatabaseError::ExecutionError {
+                    details: format!(
+                        "IN predicate on table '{}' must be resolved before evaluation",
+                        other_table
+                    ),
+                });
+            }
+            Predicate::InValueSet { column_name, values, negated } => {
+                // SQL semantics in either mode: a NULL operand, or a miss against a set
+                // containing NULL, is unknown and filters the row out in either form
+                let row_value = Self::column_value(row, schema, column_name)?;
+                let row_value = row_value.as_ref();
+                let found = if row_value.is_null() {
+                    TriBool::Unknown
+                } else if values.contains(row_value)? {
+                    TriBool::True
+                } else if values.contains_null() {
+                    TriBool::Unknown
+                } else {
+                    TriBool::False
+                };
+                if *negated { !found } else { found }
             }
             Predicate::Logical { op, left, right } => {
+                let left_result = left.evaluate_tri(row, schema, mode, predicate_mode)?;
+                let right_operand = |name: &str| {
+                    right.as_ref().ok_or_else(|| DatabaseError::ExecutionError {
+                        details: format!("{} operator requires two operands", name),
+                    })
+                };
                 match op {
+                    // Short-circuit once the result cannot change
+                    LogicalOp::And if left_result == TriBool::False => TriBool::False,
                     LogicalOp::And => {
-                        let left_result = left.evaluate(row, schema)?;
-                        if !left_result {
-                            return Ok(false); // Short-circuit evaluation
-                        }
-                        if let Some(right_pred) = right {
-                            right_pred.evaluate(row, schema)
-                        } else {
-                            Err(DatabaseError::ExecutionError {
-                                details: "AND operator requires two operands".to_string(),
-                            })
-                        }
+                        left_result.and(right_operand("AND")?.evaluate_tri(row, schema, mode, predicate_mode)?)
                     }
+                    LogicalOp::Or if left_result == TriBool::True => TriBool::True,
                     LogicalOp::Or => {
-                        let left_result = left.evaluate(row, schema)?;
-                        if left_result {
-                            return Ok(true); // Short-circuit evaluation
-                        }
-                        if let Some(right_pred) = right {
-                            right_pred.evaluate(row, schema)
-                        } else {
-                            Err(DatabaseError::ExecutionError {
-                                details: "OR operator requires two operands".to_string(),
-                            })
-                        }
-                    }
-                    LogicalOp::Not => {
-                        let result = left.evaluate(row, schema)?;
-                        Ok(!result)
+                        left_result.or(right_operand("OR")?.evaluate_tri(row, schema, mode, predicate_mode)?)
                     }
+                    LogicalOp::Not => !left_result,
                 }
             }
-            Predicate::True => Ok(true),
-            Predicate::False => Ok(false),
+            Predicate::True => TriBool::True,
+            Predicate::False => TriBool::False,
+        };
+        // Legacy evaluation settles every sub-predicate to true or false, so NOT of a
+        // comparison with NULL is true
+        Ok(match (predicate_mode, result) {
+            (PredicateMode::Legacy, TriBool::Unknown) => TriBool::False,
+            _ => result,
+        })
+    }
+
+    /// Value of the column or pseudo-column `column_name` in `row`
+    fn column_value<'a>(row: &'a Row, schema: &TableSchema, column_name: &str) -> Result<Cow<'a, Value>, DatabaseError> {
+        let source = schema.column_source(column_name)
+            .ok_or_else(|| DatabaseError::ColumnNotFound {
+                name: column_name.to_string(),
+                table: schema.table_name.clone(),
+            })?;
+        match source {
+            ColumnSource::Position(index) => row.values
+                .get(index)
+                .map(Cow::Borrowed)
+                .ok_or(DatabaseError::ColumnIndexOutOfBounds { index }),
+            ColumnSource::Pseudo(pseudo) => Ok(Cow::Owned(pseudo.value(row))),
         }
     }
 
-    /// Compare two values using the specified operator
-    fn compare_values(&self, left: &Value, op: &ComparisonOp, right: &Value) -> Result<bool, DatabaseError> {
-        match op {
-            ComparisonOp::Equal => Ok(self.values_equal(left, right)),
-            ComparisonOp::NotEqual => Ok(!self.values_equal(left, right)),
-            ComparisonOp::LessThan => {
-                match left.partial_cmp(right) {
-                    Some(std::cmp::Ordering::Less) => Ok(true),
-                    Some(_) => Ok(false),
-                    None => Ok(false), // Incomparable types
-                }
-            }
-            ComparisonOp::LessThanOrEqual => {
-                match left.partial_cmp(right) {
-                    Some(std::cmp::Ordering::Less | std::cmp::Ordering::Equal) => Ok(true),
-                    Some(_) => Ok(false),
-                    None => Ok(false),
-                }
-            }
-            ComparisonOp::GreaterThan => {
-                match left.partial_cmp(right) {
-                    Some(std::cmp::Ordering::Greater) => Ok(true),
-                    Some(_) => Ok(false),
-                    None => Ok(false),
-                }
-            }
+    /// Compare two values using the specified operator. Under `PredicateMode::Sql` any
+    /// comparison with NULL other than IS [NOT] NULL is unknown.
+    fn compare_values(
+        &self,
+        left: &Value,
+        op: &ComparisonOp,
+        right: &Value,
+        mode: ValueComparison,
+        predicate_mode: PredicateMode,
+    ) -> Result<TriBool, DatabaseError> {
+        if predicate_mode == PredicateMode::Sql
+            && (left.is_null() || right.is_null())
+            && !matches!(op, ComparisonOp::IsNull | ComparisonOp::IsNotNull)
+        {
+            return Ok(TriBool::Unknown);
+        }
+        let ordered = |accept: &[std::cmp::Ordering]| {
+            // Incomparable types satisfy no ordering
+            mode.compare(left, right).is_some_and(|ordering| accept.contains(&ordering))
+        };
+        let result = match op {
+            ComparisonOp::Equal => mode.equals(left, right),
+            ComparisonOp::NotEqual => !mode.equals(left, right),
+            ComparisonOp::LessThan => ordered(&[std::cmp::Ordering::Less]),
+            ComparisonOp::LessThanOrEqual => ordered(&[std::cmp::Ordering::Less, std::cmp::Ordering::Equal]),
+            ComparisonOp::GreaterThan => ordered(&[std::cmp::Ordering::Greater]),
             ComparisonOp::GreaterThanOrEqual => {
-                match left.partial_cmp(right) {
-                    Some(std::cmp::Ordering::Greater | std::cmp::Ordering::Equal) => Ok(true),
-                    Some(_) => Ok(false),
-                    None => Ok(false),
-                }
+                ordered(&[std::cmp::Ordering::Greater, std::cmp::Ordering::Equal])
             }
-            ComparisonOp::IsNull => Ok(matches!(left, Value::Null)),
-            ComparisonOp::IsNotNull => Ok(!matches!(left, Value::Null)),
-            ComparisonOp::Like => {
+            ComparisonOp::IsNull => matches!(left, Value::Null),
+            ComparisonOp::IsNotNull => !matches!(left, Value::Null),
+            // LIKE only relates text to text: anything else matches in neither form
+            ComparisonOp::Like { escape } | ComparisonOp::ILike { escape } => {
                 match (left, right) {
                     (Value::Text(text), Value::Text(pattern)) => {
-                        Ok(self.like_match(text, pattern))
+                        like_match(text, pattern, *escape, matches!(op, ComparisonOp::ILike { .. }))?
                     }
-                    _ => Ok(false),
+                    _ => false,
                 }
             }
-            ComparisonOp::NotLike => {
+            ComparisonOp::NotLike { escape } => {
                 match (left, right) {
-                    (Value::Text(text), Value::Text(pattern)) => {
-                        Ok(!self.like_match(text, pattern))
-                    }
-                    _ => Ok(true),
+                    (Value::Text(text), Value::Text(pattern)) => !like_match(text, pattern, *escape, false)?,
+                    _ => false,
                 }
             }
             ComparisonOp::In | ComparisonOp::NotIn => {
-                Err(DatabaseError::ExecutionError {
+                return Err(DatabaseError::ExecutionError {
                     details: "IN/NOT IN should be handled by InList predicate".to_string(),
-                })
+                });
             }
-        }
-    }
-
-    /// Check if two values are equal (with type coercion)
-    fn values_equal(&self, left: &Value, right: &Value) -> bool {
-        left == right
-    }
-
-    /// Simple LIKE pattern matching (supports % and _ wildcards)
-    fn like_match(&self, text: &str, pattern: &str) -> bool {
-        let regex_pattern = pattern
-            .replace('%', ".*")
-            .replace('_', ".");
-        
-        // Simple regex-like matching without external dependencies
-        self.simple_pattern_match(text, &regex_pattern)
-    }
-
-    /// Simple pattern matching implementation
-    fn simple_pattern_match(&self, text: &str, pattern: &str) -> bool {
-        // For now, implement basic pattern matching
-        // This is a simplified version - a full implementation would use proper regex
-        if pattern == ".*" {
-            return true;
-        }
-        
-        if pattern.starts_with(".*") && pattern.ends_with(".*") {
-            let middle = &pattern[2..pattern.len()-2];
-            return text.contains(middle);
-        }
-        
-        if pattern.starts_with(".*") {
-            let suffix = &pattern[2..];
-            return text.ends_with(suffix);
-        }
-        
-        if pattern.ends_with(".*") {
-            let prefix = &pattern[..pattern.len()-2];
-            return text.starts_with(prefix);
-        }
-        
-        text == pattern
+        };
+        Ok(TriBool::from(result))
     }
 
     /// Get all column names referenced in this predicate
@@ -365,7 +678,18 @@ impl Predicate {
             Predicate::Comparison { column_name, .. } => {
                 columns.push(column_name.clone());
             }
-            Predicate::InList { column_name, .. } => {
+            Predicate::ExprComparison { left, right, .. } => {
+                left.collect_columns(columns);
+                right.collect_columns(columns);
+            }
+            Predicate::Between { column_name, low, high, .. } => {
+                columns.push(column_name.clone());
+                low.collect_columns(columns);
+                high.collect_columns(columns);
+            }
+            Predicate::InList { column_name, .. }
+            | Predicate::InTable { column_name, .. }
+            | Predicate::InValueSet { column_name, .. } => {
                 columns.push(column_name.clone());
             }
             Predicate::Logical { left, right, .. } => {
@@ -378,11 +702,43 @@ impl Predicate {
         }
     }
 
-    /// Validate that all referenced columns exist in the schema
+    /// Rowids every matching row has one of, when a conjunct compares `_rowid` with
+    /// integer literals through `=` or `IN`. Rows with other rowids cannot match, so the
+    /// listed ones can be fetched directly instead of scanning for them.
+    pub fn target_row_ids(&self, schema: &TableSchema) -> Option<Vec<RowId>> {
+        let is_row_id = |column_name: &str| {
+            schema.column_source(column_name) == Some(ColumnSource::Pseudo(PseudoColumn::RowId))
+        };
+        // A NULL or non-integer literal may equal rows no rowid identifies, so those
+        // leave the predicate to a scan
+        let row_ids = |values: &[Value]| {
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Integer(id) => Some(RowId::try_from(*id).ok()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|ids| ids.into_iter().flatten().collect())
+        };
+        match self {
+            Predicate::Comparison { column_name, op: ComparisonOp::Equal, value } if is_row_id(column_name) => {
+                row_ids(std::slice::from_ref(value))
+            }
+            Predicate::InList { column_name, values, negated: false } if is_row_id(column_name) => row_ids(values),
+            Predicate::Logical { op: LogicalOp::And, left, right: Some(right) } => {
+                left.target_row_ids(schema).or_else(|| right.target_row_ids(schema))
+            }
+            _ => None,
+        }
+    }
+
+    /// Validate that all referenced columns exist in the schema, as columns or
+    /// pseudo-columns
     pub fn validate_against_schema(&self, schema: &TableSchema) -> Result<(), DatabaseError> {
         let referenced_columns = self.get_referenced_columns();
         for column_name in referenced_columns {
-            if schema.get_column(&column_name).is_none() {
+            if schema.column_source(&column_name).is_none() {
                 return Err(DatabaseError::ColumnNotFound {
                     name: column_name,
                     table: schema.table_name.clone(),
@@ -393,6 +749,224 @@ impl Predicate {
     }
 }
 
+impl std::ops::Not for Predicate {
+    type Output = Predicate;
+
+    /// Create a NOT predicate
+    fn not(self) -> Self::Output {
+        Predicate::Logical {
+            op: LogicalOp::Not,
+            left: Box::new(self),
+            right: None,
+        }
+    }
+}
+
+/// Write `name` as a column reference, double-quoting it unless it is a plain word
+fn write_identifier(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    let plain = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !is_keyword(name);
+    if plain { write!(f, "{}", name) } else { write!(f, "\"{}\"", name.replace('"', "\"\"")) }
+}
+
+/// Write `value` as a literal that `Predicate::parse` reads back as the same value.
+/// TIMESTAMP and DECIMAL values have no literal syntax and are written as numbers.
+fn write_literal(f: &mut fmt::Formatter<'_>, value: &Value) -> fmt::Result {
+    match value {
+        Value::Text(text) => write!(f, "'{}'", text.replace('\'', "''")),
+        Value::Real(real) => write!(f, "{:?}", real),
+        Value::Blob(bytes) => {
+            write!(f, "X'")?;
+            for byte in bytes {
+                write!(f, "{:02X}", byte)?;
+            }
+            write!(f, "'")
+        }
+        Value::Timestamp(ts) => write!(f, "{}", ts),
+        other => write!(f, "{}", other),
+    }
+}
+
+fn write_like(f: &mut fmt::Formatter<'_>, keyword: &str, pattern: &Value, escape: &Option<char>) -> fmt::Result {
+    write!(f, " {} ", keyword)?;
+    write_literal(f, pattern)?;
+    if let Some(escape) = escape {
+        write!(f, " ESCAPE '{}'", escape.to_string().replace('\'', "''"))?;
+    }
+    Ok(())
+}
+
+fn write_values(f: &mut fmt::Formatter<'_>, values: &[Value]) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write_literal(f, value)?;
+    }
+    write!(f, ")")
+}
+
+impl fmt::Display for ComparisonOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ComparisonOp::Equal => "=",
+            ComparisonOp::NotEqual => "!=",
+            ComparisonOp::LessThan => "<",
+            ComparisonOp::LessThanOrEqual => "<=",
+            ComparisonOp::GreaterThan => ">",
+            ComparisonOp::GreaterThanOrEqual => ">=",
+            ComparisonOp::IsNull => "IS NULL",
+            ComparisonOp::IsNotNull => "IS NOT NULL",
+            ComparisonOp::Like { .. } => "LIKE",
+            ComparisonOp::NotLike { .. } => "NOT LIKE",
+            ComparisonOp::ILike { .. } => "ILIKE",
+            ComparisonOp::In => "IN",
+            ComparisonOp::NotIn => "NOT IN",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::BinaryOp { op: ArithmeticOp::Add | ArithmeticOp::Sub, .. } => 1,
+            Expr::BinaryOp { .. } => 2,
+            _ => 3,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    /// Operands are parenthesized only where precedence or left associativity needs it
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Column(name) => write_identifier(f, name),
+            Expr::Literal(value) => write_literal(f, value),
+            Expr::BinaryOp { op, left, right } => {
+                let precedence = self.precedence();
+                if left.precedence() < precedence {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+                let symbol = match op {
+                    ArithmeticOp::Add => "+",
+                    ArithmeticOp::Sub => "-",
+                    ArithmeticOp::Mul => "*",
+                    ArithmeticOp::Div => "/",
+                };
+                if right.precedence() <= precedence {
+                    write!(f, " {} ({})", symbol, right)
+                } else {
+                    write!(f, " {} {}", symbol, right)
+                }
+            }
+        }
+    }
+}
+
+impl Predicate {
+    /// Parse a SQL WHERE condition such as `age >= 18 AND (name LIKE 'A%' OR email IS NULL)`.
+    /// Supports comparisons between columns, literals and arithmetic, AND/OR/NOT,
+    /// parentheses, [NOT] IN lists and subqueries, [NOT] BETWEEN, IS [NOT] NULL and
+    /// [NOT] LIKE/ILIKE with ESCAPE. Keywords are case-insensitive; strings are
+    /// single-quoted with `''` for a quote, and column names that clash with a keyword
+    /// are double-quoted. Displaying a predicate gives text that parses back to it.
+    pub fn parse(input: &str) -> Result<Self, DatabaseError> {
+        parse_predicate(input)
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Predicate::Logical { op: LogicalOp::Or, .. } => 1,
+            Predicate::Logical { op: LogicalOp::And, .. } => 2,
+            Predicate::Logical { op: LogicalOp::Not, .. } => 3,
+            _ => 4,
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    /// SQL text for the predicate. A resolved `InValueSet` has no literal form and is
+    /// written with a placeholder in place of its values.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Predicate::Comparison { column_name, op, value } => {
+                write_identifier(f, column_name)?;
+                match op {
+                    ComparisonOp::IsNull | ComparisonOp::IsNotNull => write!(f, " {}", op),
+                    ComparisonOp::Like { escape } | ComparisonOp::NotLike { escape } | ComparisonOp::ILike { escape } => {
+                        write_like(f, &op.to_string(), value, escape)
+                    }
+                    ComparisonOp::In | ComparisonOp::NotIn => {
+                        write!(f, " {} ", op)?;
+                        write_values(f, std::slice::from_ref(value))
+                    }
+                    _ => {
+                        write!(f, " {} ", op)?;
+                        write_literal(f, value)
+                    }
+                }
+            }
+            Predicate::ExprComparison { left, op, right } => match op {
+                ComparisonOp::IsNull | ComparisonOp::IsNotNull => write!(f, "{} {}", left, op),
+                _ => write!(f, "{} {} {}", left, op, right),
+            },
+            Predicate::Between { column_name, low, high, negated } => {
+                write_identifier(f, column_name)?;
+                let not = if *negated { " NOT" } else { "" };
+                write!(f, "{} BETWEEN {} AND {}", not, low, high)
+            }
+            Predicate::InList { column_name, values, negated } => {
+                write_identifier(f, column_name)?;
+                write!(f, "{} IN ", if *negated { " NOT" } else { "" })?;
+                write_values(f, values)
+            }
+            Predicate::InTable { column_name, other_table, other_column, negated } => {
+                write_identifier(f, column_name)?;
+                write!(f, "{} IN (SELECT ", if *negated { " NOT" } else { "" })?;
+                write_identifier(f, other_column)?;
+                write!(f, " FROM ")?;
+                write_identifier(f, other_table)?;
+                write!(f, ")")
+            }
+            Predicate::InValueSet { column_name, values, negated } => {
+                write_identifier(f, column_name)?;
+                write!(f, "{} IN (<{} values>)", if *negated { " NOT" } else { "" }, values.len())
+            }
+            Predicate::Logical { op, left, right } => {
+                let precedence = self.precedence();
+                let keyword = match op {
+                    LogicalOp::And => "AND",
+                    LogicalOp::Or => "OR",
+                    LogicalOp::Not => {
+                        return if left.precedence() < precedence {
+                            write!(f, "NOT ({})", left)
+                        } else {
+                            write!(f, "NOT {}", left)
+                        };
+                    }
+                };
+                if left.precedence() < precedence {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+                match right {
+                    Some(right) if right.precedence() <= precedence => write!(f, " {} ({})", keyword, right),
+                    Some(right) => write!(f, " {} {}", keyword, right),
+                    None => Ok(()),
+                }
+            }
+            Predicate::True => write!(f, "TRUE"),
+            Predicate::False => write!(f, "FALSE"),
+        }
+    }
+}
+
 /// Builder for creating complex predicates
 pub struct PredicateBuilder {
     predicate: Option<Predicate>,
@@ -451,12 +1025,49 @@ impl PredicateBuilder {
         self
     }
 
+    pub fn between(mut self, column_name: String, low: Value, high: Value) -> Self {
+        let pred = Predicate::between(column_name, low, high);
+        self.predicate = Some(self.combine_with_and(pred));
+        self
+    }
+
+    /// AND in a comparison between two expressions
+    pub fn expr(mut self, left: Expr, op: ComparisonOp, right: Expr) -> Self {
+        let pred = Predicate::compare_exprs(left, op, right);
+        self.predicate = Some(self.combine_with_and(pred));
+        self
+    }
+
+    pub fn like(mut self, column_name: String, pattern: String) -> Self {
+        let pred = Predicate::like(column_name, pattern);
+        self.predicate = Some(self.combine_with_and(pred));
+        self
+    }
+
+    pub fn ilike(mut self, column_name: String, pattern: String) -> Self {
+        let pred = Predicate::ilike(column_name, pattern);
+        self.predicate = Some(self.combine_with_and(pred));
+        self
+    }
+
     pub fn in_list(mut self, column_name: String, values: Vec<Value>) -> Self {
         let pred = Predicate::in_list(column_name, values);
         self.predicate = Some(self.combine_with_and(pred));
         self
     }
 
+    pub fn in_table(mut self, column_name: String, other_table: String, other_column: String) -> Self {
+        let pred = Predicate::in_table(column_name, other_table, other_column);
+        self.predicate = Some(self.combine_with_and(pred));
+        self
+    }
+
+    pub fn not_in_table(mut self, column_name: String, other_table: String, other_column: String) -> Self {
+        let pred = Predicate::not_in_table(column_name, other_table, other_column);
+        self.predicate = Some(self.combine_with_and(pred));
+        self
+    }
+
     pub fn or(mut self, other_predicate: Predicate) -> Self {
         self.predicate = Some(match self.predicate {
             Some(existing) => Predicate::or(existing, other_predicate),
@@ -0,0 +1,510 @@
+use crate::{
+    executor::predicate::{ArithmeticOp, ComparisonOp, Expr, Predicate},
+    types::{error::DatabaseError, value::Value},
+};
+
+/// Words with a meaning in predicate text. A column named like one must be double-quoted.
+pub(crate) const KEYWORDS: &[&str] = &[
+    "AND", "OR", "NOT", "IS", "NULL", "IN", "BETWEEN", "LIKE", "ILIKE", "ESCAPE", "TRUE", "FALSE",
+    "SELECT", "FROM",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    /// Bare word: a keyword or a column name
+    Word(String),
+    /// Double-quoted column name, never a keyword
+    QuotedIdent(String),
+    Number(String),
+    Str(String),
+    Blob(Vec<u8>),
+    Symbol(&'static str),
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    /// Byte offset of the token in the input
+    offset: usize,
+    /// The token as written, for error messages
+    text: String,
+}
+
+const SYMBOLS: &[&str] = &["<=", ">=", "<>", "!=", "=", "<", ">", "+", "-", "*", "/", "(", ")", ","];
+
+fn syntax_error(offset: usize, token: &str, details: impl Into<String>) -> DatabaseError {
+    DatabaseError::PredicateSyntax {
+        offset,
+        token: token.to_string(),
+        details: details.into(),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, DatabaseError> {
+    let mut tokens = Vec::new();
+    let bytes = input.as_bytes();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let c = input[pos..].chars().next().expect("pos is on a char boundary");
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        }
+        let start = pos;
+        let kind = if c == '\'' || c == '"' {
+            let (text, end) = read_quoted(input, start, c)?;
+            pos = end;
+            if c == '\'' { TokenKind::Str(text) } else { TokenKind::QuotedIdent(text) }
+        } else if (c == 'x' || c == 'X') && bytes.get(pos + 1) == Some(&b'\'') {
+            let (hex, end) = read_quoted(input, start + 1, '\'')?;
+            pos = end;
+            TokenKind::Blob(decode_hex(&hex).ok_or_else(|| {
+                syntax_error(start, &input[start..end], "blob literal needs an even number of hex digits")
+            })?)
+        } else if c.is_ascii_digit() || (c == '.' && bytes.get(pos + 1).is_some_and(u8::is_ascii_digit)) {
+            pos = scan_number(bytes, pos);
+            TokenKind::Number(input[start..pos].to_string())
+        } else if c.is_alphabetic() || c == '_' {
+            while let Some(next) = input[pos..].chars().next()
+                && (next.is_alphanumeric() || next == '_')
+            {
+                pos += next.len_utf8();
+            }
+            TokenKind::Word(input[start..pos].to_string())
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| input[pos..].starts_with(**symbol)) {
+            pos += symbol.len();
+            TokenKind::Symbol(symbol)
+        } else {
+            return Err(syntax_error(start, &c.to_string(), "unexpected character"));
+        };
+        tokens.push(Token {
+            kind,
+            offset: start,
+            text: input[start..pos].to_string(),
+        });
+    }
+    tokens.push(Token {
+        kind: TokenKind::End,
+        offset: input.len(),
+        text: "end of input".to_string(),
+    });
+    Ok(tokens)
+}
+
+/// Read a literal opened by `quote` at `start`, where a doubled quote stands for itself.
+/// Returns the unescaped text and the offset just past the closing quote.
+fn read_quoted(input: &str, start: usize, quote: char) -> Result<(String, usize), DatabaseError> {
+    let mut text = String::new();
+    let mut chars = input[start + 1..].char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c != quote {
+            text.push(c);
+        } else if chars.peek().is_some_and(|(_, next)| *next == quote) {
+            text.push(quote);
+            chars.next();
+        } else {
+            return Ok((text, start + 1 + index + 1));
+        }
+    }
+    Err(syntax_error(start, &input[start..], format!("unterminated {} literal", quote)))
+}
+
+fn scan_number(bytes: &[u8], mut pos: usize) -> usize {
+    while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+        pos += 1;
+    }
+    if pos < bytes.len() && (bytes[pos] == b'e' || bytes[pos] == b'E') {
+        let mut exponent = pos + 1;
+        if exponent < bytes.len() && (bytes[exponent] == b'+' || bytes[exponent] == b'-') {
+            exponent += 1;
+        }
+        if exponent < bytes.len() && bytes[exponent].is_ascii_digit() {
+            pos = exponent;
+            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                pos += 1;
+            }
+        }
+    }
+    pos
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Recursive-descent parser over the token list. Precedence from loosest to tightest:
+/// OR, AND, NOT, then a single comparison of arithmetic expressions.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error_here(&self, details: impl Into<String>) -> DatabaseError {
+        let token = self.peek();
+        syntax_error(token.offset, &token.text, details)
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.at_keyword(keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), DatabaseError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error_here(format!("expected {}", keyword)))
+        }
+    }
+
+    fn at_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek().kind, TokenKind::Symbol(found) if found == symbol)
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.at_symbol(symbol);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), DatabaseError> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.error_here(format!("expected '{}'", symbol)))
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Predicate, DatabaseError> {
+        let mut predicate = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            predicate = Predicate::or(predicate, self.parse_and()?);
+        }
+        Ok(predicate)
+    }
+
+    fn parse_and(&mut self) -> Result<Predicate, DatabaseError> {
+        let mut predicate = self.parse_not()?;
+        while self.eat_keyword("AND") {
+            predicate = Predicate::and(predicate, self.parse_not()?);
+        }
+        Ok(predicate)
+    }
+
+    fn parse_not(&mut self) -> Result<Predicate, DatabaseError> {
+        if self.eat_keyword("NOT") {
+            return Ok(!self.parse_not()?);
+        }
+        if !self.at_symbol("(") {
+            return self.parse_comparison();
+        }
+        // Either a parenthesized predicate or an arithmetic group starting a comparison
+        let start = self.pos;
+        self.advance();
+        let grouped = self
+            .parse_or()
+            .and_then(|predicate| self.expect_symbol(")").map(|_| predicate));
+        let grouped_error = match grouped {
+            Ok(predicate) => return Ok(predicate),
+            Err(error) => error,
+        };
+        self.pos = start;
+        // When neither reading works, the one that got further has the useful error
+        self.parse_comparison().map_err(|error| match (&error, &grouped_error) {
+            (
+                DatabaseError::PredicateSyntax { offset, .. },
+                DatabaseError::PredicateSyntax { offset: grouped_offset, .. },
+            ) if grouped_offset > offset => grouped_error,
+            _ => error,
+        })
+    }
+
+    fn parse_comparison(&mut self) -> Result<Predicate, DatabaseError> {
+        let left_token = self.peek().clone();
+        let left = self.parse_expr()?;
+
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            let op = if negated { ComparisonOp::IsNotNull } else { ComparisonOp::IsNull };
+            return Ok(match left {
+                Expr::Column(column_name) => Predicate::Comparison {
+                    column_name,
+                    op,
+                    value: Value::Null,
+                },
+                left => Predicate::compare_exprs(left, op, Expr::Literal(Value::Null)),
+            });
+        }
+
+        let negated = self.eat_keyword("NOT");
+        if negated || self.at_keyword("IN") || self.at_keyword("BETWEEN") || self.at_keyword("LIKE") || self.at_keyword("ILIKE") {
+            let Expr::Column(column_name) = left else {
+                return Err(syntax_error(
+                    left_token.offset,
+                    &left_token.text,
+                    format!("expected a column before {}", self.peek().text.to_uppercase()),
+                ));
+            };
+            return self.parse_column_test(column_name, negated);
+        }
+
+        if let Some(op) = self.comparison_op() {
+            self.advance();
+            let right = self.parse_expr()?;
+            return Ok(match (left, right) {
+                (Expr::Column(column_name), Expr::Literal(value)) => Predicate::Comparison { column_name, op, value },
+                (left, right) => Predicate::compare_exprs(left, op, right),
+            });
+        }
+        match left {
+            Expr::Literal(Value::Boolean(true)) => Ok(Predicate::True),
+            Expr::Literal(Value::Boolean(false)) => Ok(Predicate::False),
+            _ => Err(self.error_here("expected a comparison operator")),
+        }
+    }
+
+    /// The rest of `column [NOT] IN / BETWEEN / LIKE / ILIKE ...` after the optional NOT
+    fn parse_column_test(&mut self, column_name: String, negated: bool) -> Result<Predicate, DatabaseError> {
+        if self.eat_keyword("IN") {
+            self.expect_symbol("(")?;
+            if self.eat_keyword("SELECT") {
+                let other_column = self.parse_identifier()?;
+                self.expect_keyword("FROM")?;
+                let other_table = self.parse_identifier()?;
+                self.expect_symbol(")")?;
+                return Ok(Predicate::InTable {
+                    column_name,
+                    other_table,
+                    other_column,
+                    negated,
+                });
+            }
+            let mut values = Vec::new();
+            if !self.at_symbol(")") {
+                loop {
+                    values.push(self.parse_literal()?);
+                    if !self.eat_symbol(",") {
+                        break;
+                    }
+                }
+            }
+            self.expect_symbol(")")?;
+            return Ok(Predicate::InList {
+                column_name,
+                values,
+                negated,
+            });
+        }
+        if self.eat_keyword("BETWEEN") {
+            let low = self.parse_expr()?;
+            self.expect_keyword("AND")?;
+            let high = self.parse_expr()?;
+            return Ok(Predicate::Between {
+                column_name,
+                low,
+                high,
+                negated,
+            });
+        }
+        let case_insensitive = self.at_keyword("ILIKE");
+        if !(self.eat_keyword("LIKE") || self.eat_keyword("ILIKE")) {
+            return Err(self.error_here("expected IN, BETWEEN, LIKE or ILIKE"));
+        }
+        let pattern = self.parse_string("a LIKE pattern")?;
+        let escape = if self.eat_keyword("ESCAPE") {
+            let escape_token = self.peek().clone();
+            let escape = self.parse_string("an ESCAPE character")?;
+            let mut chars = escape.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => {
+                    return Err(syntax_error(
+                        escape_token.offset,
+                        &escape_token.text,
+                        "ESCAPE takes a single character",
+                    ));
+                }
+            }
+        } else {
+            None
+        };
+        let op = match (case_insensitive, negated) {
+            (true, _) => ComparisonOp::ILike { escape },
+            (false, true) => ComparisonOp::NotLike { escape },
+            (false, false) => ComparisonOp::Like { escape },
+        };
+        let predicate = Predicate::Comparison {
+            column_name,
+            op,
+            value: Value::Text(pattern),
+        };
+        // There is no NOT ILIKE operator; negate the match instead
+        Ok(if case_insensitive && negated { !predicate } else { predicate })
+    }
+
+    fn comparison_op(&self) -> Option<ComparisonOp> {
+        let TokenKind::Symbol(symbol) = self.peek().kind else {
+            return None;
+        };
+        Some(match symbol {
+            "=" => ComparisonOp::Equal,
+            "!=" | "<>" => ComparisonOp::NotEqual,
+            "<" => ComparisonOp::LessThan,
+            "<=" => ComparisonOp::LessThanOrEqual,
+            ">" => ComparisonOp::GreaterThan,
+            ">=" => ComparisonOp::GreaterThanOrEqual,
+            _ => return None,
+        })
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, DatabaseError> {
+        let mut expr = self.parse_term()?;
+        loop {
+            let op = if self.eat_symbol("+") {
+                ArithmeticOp::Add
+            } else if self.eat_symbol("-") {
+                ArithmeticOp::Sub
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::binary(op, expr, self.parse_term()?);
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, DatabaseError> {
+        let mut expr = self.parse_factor()?;
+        loop {
+            let op = if self.eat_symbol("*") {
+                ArithmeticOp::Mul
+            } else if self.eat_symbol("/") {
+                ArithmeticOp::Div
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::binary(op, expr, self.parse_factor()?);
+        }
+    }
+
+    fn parse_factor(&mut self) -> Result<Expr, DatabaseError> {
+        if self.eat_symbol("(") {
+            let expr = self.parse_expr()?;
+            self.expect_symbol(")")?;
+            return Ok(expr);
+        }
+        if self.at_symbol("-") && !matches!(self.tokens[self.pos + 1].kind, TokenKind::Number(_)) {
+            self.advance();
+            let operand = self.parse_factor()?;
+            return Ok(Expr::binary(ArithmeticOp::Sub, Expr::Literal(Value::Integer(0)), operand));
+        }
+        match &self.peek().kind {
+            TokenKind::QuotedIdent(_) => Ok(Expr::Column(self.parse_identifier()?)),
+            TokenKind::Word(word) if !is_keyword(word) => Ok(Expr::Column(self.parse_identifier()?)),
+            _ => Ok(Expr::Literal(self.parse_literal()?)),
+        }
+    }
+
+    fn parse_literal(&mut self) -> Result<Value, DatabaseError> {
+        let negative = self.at_symbol("-");
+        if negative {
+            self.advance();
+        }
+        let token = self.peek().clone();
+        let value = match &token.kind {
+            TokenKind::Number(digits) => {
+                let text = if negative { format!("-{}", digits) } else { digits.clone() };
+                match text.parse::<i64>() {
+                    Ok(integer) => Value::Integer(integer),
+                    Err(_) => Value::Real(text.parse::<f64>().map_err(|_| {
+                        syntax_error(token.offset, &token.text, "malformed number")
+                    })?),
+                }
+            }
+            _ if negative => return Err(self.error_here("expected a number after '-'")),
+            TokenKind::Str(text) => Value::Text(text.clone()),
+            TokenKind::Blob(bytes) => Value::Blob(bytes.clone()),
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("NULL") => Value::Null,
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("TRUE") => Value::Boolean(true),
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("FALSE") => Value::Boolean(false),
+            _ => return Err(self.error_here("expected a value")),
+        };
+        self.advance();
+        Ok(value)
+    }
+
+    fn parse_string(&mut self, what: &str) -> Result<String, DatabaseError> {
+        match &self.peek().kind {
+            TokenKind::Str(text) => {
+                let text = text.clone();
+                self.advance();
+                Ok(text)
+            }
+            _ => Err(self.error_here(format!("expected {} in single quotes", what))),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, DatabaseError> {
+        match &self.peek().kind {
+            TokenKind::QuotedIdent(name) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            TokenKind::Word(word) if !is_keyword(word) => {
+                let name = word.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.error_here("expected a column name")),
+        }
+    }
+}
+
+pub(crate) fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+/// Parse a WHERE-clause condition into a `Predicate`
+pub fn parse_predicate(input: &str) -> Result<Predicate, DatabaseError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+    if parser.peek().kind == TokenKind::End {
+        return Err(parser.error_here("expected a condition"));
+    }
+    let predicate = parser.parse_or()?;
+    if parser.peek().kind != TokenKind::End {
+        return Err(parser.error_here("unexpected token after the condition"));
+    }
+    Ok(predicate)
+}
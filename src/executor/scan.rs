@@ -1,22 +1,112 @@
 use std::{
-    collections::VecDeque,
-    fs::File,
-    io::{Read, Seek, SeekFrom},
+    sync::Arc,
+    time::{Duration, Instant},
 };
+
 use crate::{
-    storage::storage_manager::StorageManager,
+    executor::{
+        metrics::{CollectMetrics, OperatorMetrics},
+        predicate::{Predicate, PredicateMode},
+    },
+    storage::schema::{PseudoColumn, TableSchema},
     types::{
-        PAGE_SIZE, PageId,
+        PageId,
         error::DatabaseError,
-        page::{Page, PageType},
         row::Row,
+        value::{Value, ValueComparison},
     },
 };
 
+/// How many rows a batch scan returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchPolicy {
+    /// Return up to this many rows per batch
+    Fixed(usize),
+    /// Pick the row count from the observed average row size to target a byte budget
+    Auto,
+}
+
+/// What a scanner does when it meets data it cannot decode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnCorruption {
+    /// Abort the scan with the error
+    #[default]
+    Fail,
+    /// Log the failure, skip the row or page, and keep going
+    Skip,
+}
+
+/// One unreadable row or page skipped by a scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorruptionEntry {
+    pub page_id: PageId,
+    /// `None` when the whole page was skipped
+    pub slot_index: Option<usize>,
+    pub error: String,
+}
+
+/// Failures a scan skipped in `OnCorruption::Skip` mode, in the order met
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CorruptionLog {
+    pub entries: Vec<CorruptionEntry>,
+}
+
+impl CorruptionLog {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// Callback invoked for each corruption a scan skips
+pub type CorruptionHook = Arc<dyn Fn(&CorruptionEntry) + Send + Sync>;
+
+/// Behaviour of a scan when it meets corrupt data
+#[derive(Clone, Default)]
+pub struct ScanOptions {
+    pub on_corruption: OnCorruption,
+    pub on_corruption_hook: Option<CorruptionHook>,
+}
+
+impl ScanOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Best-effort reads: skip what cannot be decoded and log it
+    pub fn skip_corrupt() -> Self {
+        Self {
+            on_corruption: OnCorruption::Skip,
+            on_corruption_hook: None,
+        }
+    }
+
+    pub fn with_corruption_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&CorruptionEntry) + Send + Sync + 'static,
+    {
+        self.on_corruption_hook = Some(Arc::new(hook));
+        self
+    }
+}
+
 pub trait Scanner {
     fn scan(&mut self) -> Result<Option<Row>, DatabaseError>;
     fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError>;
+    /// Rewind so the next `scan` starts from the first row of the table's current data
     fn reset(&mut self) -> Result<(), DatabaseError>;
+
+    /// Iterate over the rows converted by `f`, e.g. into a tuple or a domain type
+    fn map_rows<F, T>(self, f: F) -> MappedScanIterator<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Row) -> Result<T, DatabaseError>,
+    {
+        MappedScanIterator::new(self, f)
+    }
 }
 
 
@@ -39,4 +129,346 @@ impl<S: Scanner> Iterator for ScanIterator<S> {
             Err(e) => Some(Err(e)),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Like `ScanIterator`, but yields each row converted by a closure. Rows are scanned one
+/// at a time as the iterator is advanced, so stopping early stops the scan.
+pub struct MappedScanIterator<S: Scanner, F> {
+    scanner: S,
+    f: F,
+}
+
+impl<S: Scanner, F> MappedScanIterator<S, F> {
+    pub fn new(scanner: S, f: F) -> Self {
+        Self { scanner, f }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.scanner
+    }
+}
+
+impl<S: Scanner, F, T> Iterator for MappedScanIterator<S, F>
+where
+    F: FnMut(Row) -> Result<T, DatabaseError>,
+{
+    type Item = Result<T, DatabaseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.scanner.scan() {
+            Ok(Some(row)) => Some((self.f)(row)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Pass through only the rows of the inner scanner that satisfy a predicate
+pub struct FilterScanner<S: Scanner> {
+    scanner: S,
+    predicate: Predicate,
+    schema: TableSchema,
+    value_comparison: ValueComparison,
+    predicate_mode: PredicateMode,
+    rows_in: u64,
+    rows_out: u64,
+    elapsed: Duration,
+}
+
+impl<S: Scanner> FilterScanner<S> {
+    pub fn new(scanner: S, predicate: Predicate, schema: TableSchema) -> Result<Self, DatabaseError> {
+        predicate.validate_against_schema(&schema)?;
+        Ok(Self {
+            scanner,
+            predicate,
+            schema,
+            value_comparison: ValueComparison::Coercive,
+            predicate_mode: PredicateMode::Legacy,
+            rows_in: 0,
+            rows_out: 0,
+            elapsed: Duration::ZERO,
+        })
+    }
+
+    /// Relate values of different types according to `mode` rather than coercively
+    pub fn with_value_comparison(mut self, mode: ValueComparison) -> Self {
+        self.value_comparison = mode;
+        self
+    }
+
+    /// Treat NULL according to `mode`, e.g. `PredicateMode::Sql` for three-valued logic
+    pub fn with_predicate_mode(mut self, mode: PredicateMode) -> Self {
+        self.predicate_mode = mode;
+        self
+    }
+
+    pub fn into_inner(self) -> S {
+        self.scanner
+    }
+}
+
+impl<S: Scanner> FilterScanner<S> {
+    fn next_match(&mut self) -> Result<Option<Row>, DatabaseError> {
+        while let Some(row) = self.scanner.scan()? {
+            self.rows_in += 1;
+            if self
+                .predicate
+                .evaluate_in(&row, &self.schema, self.value_comparison, self.predicate_mode)?
+            {
+                self.rows_out += 1;
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<S: Scanner> Scanner for FilterScanner<S> {
+    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+        let started = Instant::now();
+        let row = self.next_match();
+        self.elapsed += started.elapsed();
+        row
+    }
+
+    fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
+        collect_batch(self, batch_size)
+    }
+
+    fn reset(&mut self) -> Result<(), DatabaseError> {
+        self.scanner.reset()
+    }
+}
+
+impl<S: Scanner + CollectMetrics> CollectMetrics for FilterScanner<S> {
+    /// Every row read is compared once, so comparisons equal rows in
+    fn collect_metrics(&self) -> OperatorMetrics {
+        OperatorMetrics::new(format!("Filter: {}", self.predicate))
+            .with_rows(self.rows_in, self.rows_out)
+            .with_detail("comparisons", self.rows_in)
+            .with_elapsed(self.elapsed)
+            .with_child(self.scanner.collect_metrics())
+    }
+}
+
+/// Skip the first `offset` rows of the inner scanner and stop after `limit` more. The
+/// inner scanner is not asked for rows past the limit, so no further pages are read.
+/// Wrap a `FilterScanner` to count the offset in matching rows.
+pub struct LimitScanner<S: Scanner> {
+    scanner: S,
+    limit: usize,
+    offset: usize,
+    skipped: usize,
+    returned: usize,
+    rows_in: u64,
+    rows_out: u64,
+    elapsed: Duration,
+}
+
+impl<S: Scanner> LimitScanner<S> {
+    pub fn new(scanner: S, limit: usize, offset: usize) -> Self {
+        Self {
+            scanner,
+            limit,
+            offset,
+            skipped: 0,
+            returned: 0,
+            rows_in: 0,
+            rows_out: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.scanner
+    }
+}
+
+impl<S: Scanner> LimitScanner<S> {
+    fn next_row(&mut self) -> Result<Option<Row>, DatabaseError> {
+        if self.returned >= self.limit {
+            return Ok(None);
+        }
+        while self.skipped < self.offset {
+            if self.scanner.scan()?.is_none() {
+                self.returned = self.limit;
+                return Ok(None);
+            }
+            self.rows_in += 1;
+            self.skipped += 1;
+        }
+        let row = self.scanner.scan()?;
+        if row.is_some() {
+            self.rows_in += 1;
+            self.rows_out += 1;
+        }
+        self.returned = if row.is_some() { self.returned + 1 } else { self.limit };
+        Ok(row)
+    }
+}
+
+impl<S: Scanner> Scanner for LimitScanner<S> {
+    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+        let started = Instant::now();
+        let row = self.next_row();
+        self.elapsed += started.elapsed();
+        row
+    }
+
+    fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
+        collect_batch(self, batch_size)
+    }
+
+    fn reset(&mut self) -> Result<(), DatabaseError> {
+        self.skipped = 0;
+        self.returned = 0;
+        self.scanner.reset()
+    }
+}
+
+impl<S: Scanner + CollectMetrics> CollectMetrics for LimitScanner<S> {
+    fn collect_metrics(&self) -> OperatorMetrics {
+        let operator = match self.offset {
+            0 => format!("Limit: {}", self.limit),
+            offset => format!("Limit: {} offset {}", self.limit, offset),
+        };
+        OperatorMetrics::new(operator)
+            .with_rows(self.rows_in, self.rows_out)
+            .with_elapsed(self.elapsed)
+            .with_child(self.scanner.collect_metrics())
+    }
+}
+
+/// Keep only the columns at `indices` of each row of the inner scanner, in that order.
+/// Positions past the end of a row read as NULL.
+pub struct ProjectScanner<S: Scanner> {
+    scanner: S,
+    indices: Vec<usize>,
+    /// Output column names, shown in metrics
+    names: Vec<String>,
+    rows: u64,
+    elapsed: Duration,
+}
+
+impl<S: Scanner> ProjectScanner<S> {
+    pub fn new(scanner: S, indices: Vec<usize>, names: Vec<String>) -> Self {
+        Self {
+            scanner,
+            indices,
+            names,
+            rows: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.scanner
+    }
+}
+
+impl<S: Scanner> Scanner for ProjectScanner<S> {
+    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+        let started = Instant::now();
+        let row = self.scanner.scan().map(|row| {
+            row.map(|row| {
+                let values = self
+                    .indices
+                    .iter()
+                    .map(|&index| row.values.get(index).cloned().unwrap_or(Value::Null))
+                    .collect();
+                Row { row_id: row.row_id, version: row.version, values }
+            })
+        });
+        if let Ok(Some(_)) = row {
+            self.rows += 1;
+        }
+        self.elapsed += started.elapsed();
+        row
+    }
+
+    fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
+        collect_batch(self, batch_size)
+    }
+
+    fn reset(&mut self) -> Result<(), DatabaseError> {
+        self.scanner.reset()
+    }
+}
+
+impl<S: Scanner + CollectMetrics> CollectMetrics for ProjectScanner<S> {
+    fn collect_metrics(&self) -> OperatorMetrics {
+        OperatorMetrics::new(format!("Projection: {}", self.names.join(", ")))
+            .with_rows(self.rows, self.rows)
+            .with_elapsed(self.elapsed)
+            .with_child(self.scanner.collect_metrics())
+    }
+}
+
+/// Append the values of pseudo-columns such as `_rowid` to each row of the inner scanner,
+/// after its first `width` values, so later operators find them by position
+pub struct PseudoColumnScanner<S: Scanner> {
+    scanner: S,
+    width: usize,
+    columns: Vec<PseudoColumn>,
+    rows: u64,
+    elapsed: Duration,
+}
+
+impl<S: Scanner> PseudoColumnScanner<S> {
+    pub fn new(scanner: S, width: usize, columns: Vec<PseudoColumn>) -> Self {
+        Self {
+            scanner,
+            width,
+            columns,
+            rows: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl<S: Scanner> Scanner for PseudoColumnScanner<S> {
+    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+        let started = Instant::now();
+        let row = self.scanner.scan().map(|row| {
+            row.map(|mut row| {
+                row.values.resize(self.width, Value::Null);
+                let metadata: Vec<Value> = self.columns.iter().map(|column| column.value(&row)).collect();
+                row.values.extend(metadata);
+                row
+            })
+        });
+        if let Ok(Some(_)) = row {
+            self.rows += 1;
+        }
+        self.elapsed += started.elapsed();
+        row
+    }
+
+    fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
+        collect_batch(self, batch_size)
+    }
+
+    fn reset(&mut self) -> Result<(), DatabaseError> {
+        self.scanner.reset()
+    }
+}
+
+impl<S: Scanner + CollectMetrics> CollectMetrics for PseudoColumnScanner<S> {
+    fn collect_metrics(&self) -> OperatorMetrics {
+        let names: Vec<&str> = self.columns.iter().map(|column| column.name()).collect();
+        OperatorMetrics::new(format!("Row metadata: {}", names.join(", ")))
+            .with_rows(self.rows, self.rows)
+            .with_elapsed(self.elapsed)
+            .with_child(self.scanner.collect_metrics())
+    }
+}
+
+pub(crate) fn collect_batch<S: Scanner>(scanner: &mut S, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
+    let mut rows = Vec::with_capacity(batch_size.min(1024));
+    while rows.len() < batch_size {
+        match scanner.scan()? {
+            Some(row) => rows.push(row),
+            None => break,
+        }
+    }
+    Ok(rows)
+}
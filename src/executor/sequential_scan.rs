@@ -2,29 +2,95 @@ use std::{
     collections::VecDeque,
     fs::File,
     io::{Read, Seek, SeekFrom},
+    ops::ControlFlow,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    executor::scan::Scanner,
-    storage::storage_manager::StorageManager,
+    executor::{
+        metrics::{CollectMetrics, OperatorMetrics},
+        scan::{BatchPolicy, CorruptionEntry, CorruptionLog, OnCorruption, ScanOptions, Scanner},
+    },
+    storage::{
+        bplus_tree::BPlusTree,
+        header::BambangHeader,
+        schema::TableKind,
+        storage_manager::{StorageManager, walk_schema_rows},
+    },
     types::{
-        PAGE_SIZE, PageId,
+        PageId,
         error::DatabaseError,
-        page::{Page, PageType},
+        page::{OverflowPointer, Page, PageType},
         row::Row,
+        value::Value,
     },
 };
 
+/// Bytes of decoded row data an automatically sized batch aims for
+pub const DEFAULT_BATCH_BYTE_BUDGET: usize = 256 * 1024;
+
+/// Number of automatically sized batches served before the row count is recomputed
+const AUTO_BATCH_REEVALUATE_INTERVAL: usize = 4;
+
+/// Upper bound on the row count of an automatically sized batch
+const MAX_AUTO_BATCH_ROWS: usize = 65_536;
+
+/// Leaf pages read ahead of the one rows are served from, unless configured otherwise
+const DEFAULT_READ_AHEAD_PAGES: usize = 2;
+
+/// Running counters kept by a scanner; they accumulate across resets
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanStats {
+    pub rows_scanned: u64,
+    pub bytes_scanned: u64,
+    /// Leaf pages read in full to serve rows
+    pub pages_read: u64,
+    pub batches: u64,
+    pub last_batch_rows: usize,
+    pub last_batch_bytes: u64,
+    /// Row count currently chosen for `BatchPolicy::Auto`, once one has been computed
+    pub auto_batch_size: Option<usize>,
+    /// Time spent in `scan`, reading pages included
+    pub elapsed: Duration,
+}
+
+impl ScanStats {
+    /// Mean serialized size of the rows decoded so far
+    pub fn average_row_size(&self) -> Option<f64> {
+        if self.rows_scanned == 0 {
+            None
+        } else {
+            Some(self.bytes_scanned as f64 / self.rows_scanned as f64)
+        }
+    }
+}
+
 pub struct SequentialScanner {
     file: File,
     root_page_id: PageId,
     current_page_id: Option<PageId>,
+    /// Full image of the page rows are currently served from, read once per page
+    current_page: Option<Page>,
+    /// Successor of the current page, as recorded when the current page was read
+    next_page_id: Option<PageId>,
     current_slot_index: usize,
     batch_size: usize,
-    read_ahead_pages: VecDeque<Page>,
+    /// Leaf pages following the current one, in chain order, each with the id it was
+    /// read from
+    read_ahead_pages: VecDeque<(PageId, Page)>,
+    read_ahead_limit: usize,
     table_name: String,
+    table_kind: TableKind,
     extras: Option<u64>,
+    page_size: usize,
     is_exhausted: bool,
+    batch_byte_budget: usize,
+    auto_batches_since_evaluation: usize,
+    stats: ScanStats,
+    /// Column positions kept in returned rows, in output order
+    projection: Option<Vec<usize>>,
+    options: ScanOptions,
+    corruption_log: CorruptionLog,
 }
 
 impl SequentialScanner {
@@ -40,6 +106,11 @@ impl SequentialScanner {
             .ok_or_else(|| DatabaseError::TableNotFound {
                 name: table_name.clone(),
             })?;
+        // A schema deferred by a lazy open is parsed now, so a broken one fails the scan
+        let table_kind = storage_manager
+            .load_table_schema(&table_name)?
+            .map(|schema| schema.kind)
+            .unwrap_or_default();
         let file = std::fs::OpenOptions::new()
             .read(true)
             .open(&storage_manager.db_info.path)?;
@@ -48,35 +119,288 @@ impl SequentialScanner {
             file,
             root_page_id,
             current_page_id: None,
+            current_page: None,
+            next_page_id: None,
             current_slot_index: 0,
             batch_size: batch_size.unwrap_or(32),
             read_ahead_pages: VecDeque::new(),
+            read_ahead_limit: DEFAULT_READ_AHEAD_PAGES,
             table_name,
+            table_kind,
             extras,
+            page_size: storage_manager.page_size(),
             is_exhausted: false,
+            batch_byte_budget: DEFAULT_BATCH_BYTE_BUDGET,
+            auto_batches_since_evaluation: 0,
+            stats: ScanStats::default(),
+            projection: None,
+            options: ScanOptions::default(),
+            corruption_log: CorruptionLog::default(),
         })
     }
 
+    /// Create a scanner that returns only the named columns, in the order given
+    pub fn with_projection(
+        storage_manager: &StorageManager,
+        table_name: String,
+        columns: Vec<String>,
+        batch_size: Option<usize>,
+    ) -> Result<Self, DatabaseError> {
+        let schema = storage_manager
+            .load_table_schema(&table_name)?
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.clone(),
+            })?;
+        let indices = columns
+            .iter()
+            .map(|column| {
+                schema
+                    .get_column_index(column)
+                    .ok_or_else(|| DatabaseError::ColumnNotFound {
+                        name: column.clone(),
+                        table: table_name.clone(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut scanner = Self::new(storage_manager, table_name, batch_size)?;
+        scanner.set_projection(indices);
+        Ok(scanner)
+    }
+
+    /// Keep only the given column positions in returned rows. Other columns are skipped
+    /// over without being decoded.
+    pub fn set_projection(&mut self, indices: Vec<usize>) {
+        self.projection = Some(indices);
+    }
+
+    pub fn clear_projection(&mut self) {
+        self.projection = None;
+    }
+
+    pub fn projection(&self) -> Option<&[usize]> {
+        self.projection.as_deref()
+    }
+
+    /// Set the byte budget targeted by `BatchPolicy::Auto`
+    pub fn with_batch_byte_budget(mut self, bytes: usize) -> Self {
+        self.batch_byte_budget = bytes.max(1);
+        self.stats.auto_batch_size = None;
+        self
+    }
+
+    /// Read up to `pages` leaf pages ahead of the one rows are served from; 0 disables
+    /// read-ahead
+    pub fn with_read_ahead(mut self, pages: usize) -> Self {
+        self.read_ahead_limit = pages;
+        self.read_ahead_pages.clear();
+        self
+    }
+
+    /// Choose how the scan reacts to corrupt rows and pages
+    pub fn with_scan_options(mut self, options: ScanOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Rows and pages skipped so far; accumulates across resets like the stats
+    pub fn corruption_log(&self) -> &CorruptionLog {
+        &self.corruption_log
+    }
+
+    pub fn take_corruption_log(&mut self) -> CorruptionLog {
+        std::mem::take(&mut self.corruption_log)
+    }
+
+    /// In skip mode, log `error` and let the scan continue; otherwise hand it back
+    fn skip_corruption(
+        &mut self,
+        page_id: PageId,
+        slot_index: Option<usize>,
+        error: DatabaseError,
+    ) -> Result<(), DatabaseError> {
+        if self.options.on_corruption == OnCorruption::Fail {
+            return Err(error);
+        }
+        let entry = CorruptionEntry {
+            page_id,
+            slot_index,
+            error: error.to_string(),
+        };
+        if let Some(hook) = &self.options.on_corruption_hook {
+            hook(&entry);
+        }
+        self.corruption_log.entries.push(entry);
+        Ok(())
+    }
+
+    pub fn batch_byte_budget(&self) -> usize {
+        self.batch_byte_budget
+    }
+
+    pub fn stats(&self) -> &ScanStats {
+        &self.stats
+    }
+
+    /// Fetch the next batch, sized according to `policy`
+    pub fn scan_batch_with_policy(&mut self, policy: BatchPolicy) -> Result<Vec<Row>, DatabaseError> {
+        match policy {
+            BatchPolicy::Fixed(batch_size) => self.scan_batch(batch_size),
+            BatchPolicy::Auto => {
+                let batch_size = self.auto_batch_size();
+                self.auto_batches_since_evaluation += 1;
+                self.scan_batch(batch_size)
+            }
+        }
+    }
+
+    /// Row count for the next automatic batch, recomputed every few batches from the
+    /// running average row size. Until a row has been seen the configured batch size is used.
+    fn auto_batch_size(&mut self) -> usize {
+        let stale = self.auto_batches_since_evaluation >= AUTO_BATCH_REEVALUATE_INTERVAL;
+        if let Some(batch_size) = self.stats.auto_batch_size
+            && !stale
+        {
+            return batch_size;
+        }
+        let batch_size = match self.stats.average_row_size() {
+            Some(average) => ((self.batch_byte_budget as f64 / average) as usize)
+                .clamp(1, MAX_AUTO_BATCH_ROWS),
+            None => self.batch_size,
+        };
+        if self.stats.rows_scanned > 0 {
+            self.stats.auto_batch_size = Some(batch_size);
+            self.auto_batches_since_evaluation = 0;
+        }
+        batch_size
+    }
+
+    /// Name of the table being scanned
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Root page the scan starts from; refreshed by `reset`
+    pub fn root_page_id(&self) -> PageId {
+        self.root_page_id
+    }
+
+    /// Default number of rows fetched per batch
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Count the live cells across every leaf page, reading only page headers and slot
+    /// directories. Rows are never deserialized and the scan position is left untouched.
+    pub fn count_active_cells(&mut self) -> Result<usize, DatabaseError> {
+        let mut count = 0;
+        let mut next_page_id = self.find_first_leaf()?;
+        while let Some(page_id) = next_page_id {
+            let page = self.load_page_metadata(page_id)?;
+            count += page.active_cell_count();
+            next_page_id = page.next_leaf_page_id;
+        }
+        Ok(count)
+    }
+
+    /// Every live row in descending key order, walking the leaf chain right to left
+    /// through each leaf's previous-leaf link. Rows within a leaf are ordered by their
+    /// first column with `Value::total_cmp`. The forward scan position is left untouched.
+    pub fn scan_reverse(&mut self) -> Result<Vec<Row>, DatabaseError> {
+        if self.table_kind == TableKind::AppendLog {
+            return Err(DatabaseError::AppendLogUnsupported {
+                table: self.table_name.clone(),
+                operation: "reverse scans in key order".to_string(),
+            });
+        }
+        let started = Instant::now();
+        let mut rows = Vec::new();
+        let mut prev_page_id = self.find_last_leaf()?;
+        while let Some(page_id) = prev_page_id {
+            let page = match self.load_full_page(page_id) {
+                Ok(page) => page,
+                Err(error) => {
+                    self.skip_corruption(page_id, None, error)?;
+                    prev_page_id = self
+                        .load_page_metadata(page_id)
+                        .ok()
+                        .and_then(|metadata| metadata.prev_leaf_page_id);
+                    continue;
+                }
+            };
+            self.stats.pages_read += 1;
+            let mut page_rows = Vec::new();
+            for slot_index in 0..page.slot_directory.slots.len() {
+                if page.slot_directory.slots[slot_index].is_deleted() {
+                    continue;
+                }
+                let keyed_row = page
+                    .get_cell(slot_index)
+                    .map(|bytes| Row::from_bytes_prefix(bytes, 1))
+                    .transpose()
+                    .and_then(|key_row| {
+                        let row = self.read_row_from_page(&page, slot_index)?;
+                        let key = key_row.and_then(|key_row| key_row.values.into_iter().next());
+                        Ok((key.unwrap_or(Value::Null), row))
+                    });
+                match keyed_row {
+                    Ok(keyed_row) => page_rows.push(keyed_row),
+                    Err(error) => self.skip_corruption(page_id, Some(slot_index), error)?,
+                }
+            }
+            page_rows.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+            rows.extend(page_rows.into_iter().map(|(_, row)| row));
+            prev_page_id = page.prev_leaf_page_id;
+        }
+        self.stats.elapsed += started.elapsed();
+        Ok(rows)
+    }
+
     fn page_offset(&self, page_id: PageId) -> u64 {
         let header_offset = self
             .extras
             .unwrap_or(crate::storage::BAMBANG_HEADER_SIZE as u64);
-        header_offset + (page_id - 1) * PAGE_SIZE as u64
+        header_offset + (page_id - 1) * self.page_size as u64
+    }
+
+    /// Whether the table has no live rows, reading only the headers of leading leaves
+    /// up to the first one holding a row
+    pub fn is_empty(&mut self) -> Result<bool, DatabaseError> {
+        Ok(self.first_live_leaf()?.is_none())
+    }
+
+    /// The first leaf with a live cell, skipping leaves emptied by deletes. A leaf whose
+    /// header cannot be read is returned as is, for the scan to fail or skip it.
+    fn first_live_leaf(&mut self) -> Result<Option<PageId>, DatabaseError> {
+        let mut next_page_id = self.find_first_leaf()?;
+        while let Some(page_id) = next_page_id {
+            let Ok(page) = self.load_page_metadata(page_id) else {
+                return Ok(Some(page_id));
+            };
+            if page.active_cell_count() > 0 {
+                return Ok(Some(page_id));
+            }
+            next_page_id = page.next_leaf_page_id;
+        }
+        Ok(None)
     }
 
-    fn find_first_leaf(&mut self) -> Result<PageId, DatabaseError> {
+    /// The leftmost leaf, or `None` when the root is an interior page left without
+    /// children, which is an empty tree
+    fn find_first_leaf(&mut self) -> Result<Option<PageId>, DatabaseError> {
         let mut current_page_id = self.root_page_id;
         loop {
             let page = self.load_page_metadata(current_page_id)?;
             match page.page_type {
                 PageType::LeafTable => {
-                    return Ok(current_page_id);
+                    return Ok(Some(current_page_id));
                 }
                 PageType::InteriorTable => {
                     if let Some(first_slot) = page.slot_directory.slots.first() {
                         let child_page_id =
                             self.read_child_page_id_from_slot(current_page_id, first_slot)?;
                         current_page_id = child_page_id;
+                    } else if current_page_id == self.root_page_id {
+                        return Ok(None);
                     } else {
                         return Err(DatabaseError::CorruptedPage {
                             page_id: current_page_id,
@@ -94,6 +418,62 @@ impl SequentialScanner {
         }
     }
 
+    /// The rightmost leaf, reached through each interior page's unbounded child, or
+    /// `None` for an empty tree
+    fn find_last_leaf(&mut self) -> Result<Option<PageId>, DatabaseError> {
+        let mut current_page_id = self.root_page_id;
+        loop {
+            let page = self.load_full_page(current_page_id)?;
+            match page.page_type {
+                PageType::LeafTable => return Ok(Some(current_page_id)),
+                PageType::InteriorTable => {
+                    let mut rightmost: Option<(PageId, Value)> = None;
+                    for slot_index in 0..page.slot_directory.slots.len() {
+                        let Some(entry) = page.get_cell(slot_index) else {
+                            continue;
+                        };
+                        let (child, upper_bound) = Self::parse_interior_entry(current_page_id, entry)?;
+                        // A NULL upper bound marks the unbounded child, which sorts last
+                        let further_right = rightmost.as_ref().is_none_or(|(_, bound)| {
+                            !bound.is_null() && (upper_bound.is_null() || upper_bound.total_cmp(bound).is_gt())
+                        });
+                        if further_right {
+                            rightmost = Some((child, upper_bound));
+                        }
+                    }
+                    match rightmost {
+                        Some((child, _)) => current_page_id = child,
+                        None if current_page_id == self.root_page_id => return Ok(None),
+                        None => {
+                            return Err(DatabaseError::CorruptedPage {
+                                page_id: current_page_id,
+                                reason: "Interior page has no children".to_string(),
+                            });
+                        }
+                    }
+                }
+                _ => {
+                    return Err(DatabaseError::CorruptedPage {
+                        page_id: current_page_id,
+                        reason: "Invalid page type in B+ tree".to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Decode an interior entry: child page id, key length, then the upper bound
+    fn parse_interior_entry(page_id: PageId, entry: &[u8]) -> Result<(PageId, Value), DatabaseError> {
+        let truncated = || DatabaseError::CorruptedPage {
+            page_id,
+            reason: "Interior entry too short".to_string(),
+        };
+        let child = u64::from_le_bytes(entry.get(0..8).ok_or_else(truncated)?.try_into().unwrap());
+        let key_length = u32::from_le_bytes(entry.get(8..12).ok_or_else(truncated)?.try_into().unwrap()) as usize;
+        let key_bytes = entry.get(12..12 + key_length).ok_or_else(truncated)?;
+        Ok((child, Value::from_bytes(key_bytes)?))
+    }
+
     fn load_page_metadata(&mut self, page_id: PageId) -> Result<Page, DatabaseError> {
         let offset = self.page_offset(page_id);
         let mut header_buffer = vec![0u8; crate::types::PAGE_HEADER_SIZE];
@@ -103,7 +483,20 @@ impl SequentialScanner {
         let mut metadata_buffer = vec![0u8; metadata_size];
         self.file.seek(SeekFrom::Start(offset))?;
         self.file.read_exact(&mut metadata_buffer)?;
-        Page::from_header_bytes(&metadata_buffer)
+        Page::from_header_bytes_with_size(&metadata_buffer, self.page_size)
+    }
+
+    fn load_full_page(&mut self, page_id: PageId) -> Result<Page, DatabaseError> {
+        let mut buffer = vec![0u8; self.page_size];
+        self.file.seek(SeekFrom::Start(self.page_offset(page_id)))?;
+        self.file.read_exact(&mut buffer)?;
+        Page::from_bytes(&buffer)
+    }
+
+    /// Start serving rows from `page`, remembering where the chain goes next
+    fn enter_page(&mut self, page: &Page) {
+        self.next_page_id = page.next_leaf_page_id;
+        self.stats.pages_read += 1;
     }
 
     fn read_child_page_id_from_slot(
@@ -119,115 +512,224 @@ impl SequentialScanner {
         Ok(u64::from_le_bytes(page_id_buffer))
     }
 
-    fn read_row_from_slot(
-        &mut self,
-        page_id: PageId,
-        slot: &crate::types::page::SlotEntry,
-    ) -> Result<Row, DatabaseError> {
-        if slot.is_deleted() {
-            return Err(DatabaseError::CorruptedPage {
+    fn read_row_from_page(&mut self, page: &Page, slot_index: usize) -> Result<Row, DatabaseError> {
+        let cell = page
+            .get_cell(slot_index)
+            .ok_or_else(|| DatabaseError::CorruptedPage {
+                page_id: page.page_id,
+                reason: format!("Slot {} does not point at readable cell data", slot_index),
+            })?;
+        let overflow_cell;
+        let row_bytes = if page.slot_directory.slots[slot_index].is_overflow {
+            overflow_cell = self.read_overflow_cell(page.page_id, cell)?;
+            &overflow_cell[..]
+        } else {
+            cell
+        };
+        let row = match &self.projection {
+            Some(indices) => Row::from_bytes_projected(row_bytes, indices)?,
+            None => Row::from_bytes(row_bytes)?,
+        };
+        self.stats.rows_scanned += 1;
+        self.stats.bytes_scanned += row_bytes.len() as u64;
+        Ok(row)
+    }
+
+    /// The cell an overflow pointer on `page_id` stands for, read from its overflow page
+    fn read_overflow_cell(&mut self, page_id: PageId, pointer: &[u8]) -> Result<Vec<u8>, DatabaseError> {
+        let pointer = OverflowPointer::from_bytes(pointer)?;
+        let overflow_page = self.load_full_page(pointer.page_id)?;
+        overflow_page
+            .get_cell(0)
+            .filter(|_| overflow_page.page_type == PageType::OverflowPage)
+            .and_then(|cell| cell.get(..pointer.total_size as usize))
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| DatabaseError::CorruptedPage {
                 page_id,
-                reason: "Attempting to read deleted slot".to_string(),
-            });
-        }
-        let page_offset = self.page_offset(page_id);
-        let slot_offset = page_offset + slot.offset as u64;
-        let data_length = slot.length as usize;
-        let mut row_buffer = vec![0u8; data_length];
-        self.file.seek(SeekFrom::Start(slot_offset))?;
-        self.file.read_exact(&mut row_buffer)?;
-        Row::from_bytes(&row_buffer)
+                reason: format!("Overflow page {} does not hold the cell it is pointed at for", pointer.page_id),
+            })
     }
 
-    fn prefetch_next_page(&mut self, current_page: &Page) -> Result<(), DatabaseError> {
-        if let Some(next_page_id) = current_page.next_leaf_page_id {
-            if self.read_ahead_pages.len() < 2 {
-                let next_page = self.load_page_metadata(next_page_id)?;
-                self.read_ahead_pages.push_back(next_page);
-            }
+    /// Fill the read-ahead queue by following the chain from the current page, or from
+    /// the last page already queued
+    fn prefetch_next_pages(&mut self) -> Result<(), DatabaseError> {
+        while self.read_ahead_pages.len() < self.read_ahead_limit {
+            let next_page_id = match self.read_ahead_pages.back() {
+                Some((_, page)) => page.next_leaf_page_id,
+                None => self.next_page_id,
+            };
+            let Some(next_page_id) = next_page_id else {
+                break;
+            };
+            let next_page = self.load_full_page(next_page_id)?;
+            self.read_ahead_pages.push_back((next_page_id, next_page));
         }
         Ok(())
     }
 
-    fn get_next_page(&mut self) -> Result<Option<(PageId, Page)>, DatabaseError> {
-        // First, try to use prefetched pages
-        if let Some(page) = self.read_ahead_pages.pop_front() {
-            if let Some(page_id) = self.current_page_id {
-                let current_page = self.load_page_metadata(page_id)?;
-                if let Some(next_id) = current_page.next_leaf_page_id {
-                    return Ok(Some((next_id, page)));
+    /// Read the table's current root from its `sqlite_schema` entry, keeping the known root
+    /// when the entry cannot be found. The schema tree is walked from the root the header
+    /// names, as page 1 is an interior page once the schema outgrows it.
+    fn resolve_root_page_id(&mut self) -> Result<PageId, DatabaseError> {
+        let header = BambangHeader::read_from(&mut self.file)?;
+        let first_leaf = BPlusTree::new_with_page_size(
+            self.file.try_clone()?,
+            header.schema_root_page(),
+            self.extras,
+            self.page_size,
+        )?
+        .first_leaf_page_id(self.extras)?;
+        let table_name = self.table_name.clone();
+        let mut root_page_id = self.root_page_id;
+        walk_schema_rows(
+            first_leaf,
+            header.database_size_pages as u64,
+            |page_id| self.load_full_page(page_id),
+            |_, row| {
+                if let [Value::Text(entry_type), Value::Text(name), _, Value::Integer(root), ..] = &row.values[..]
+                    && entry_type == "table"
+                    && *name == table_name
+                {
+                    root_page_id = *root as PageId;
+                    return Ok(ControlFlow::Break(()));
                 }
-            }
-        }
+                Ok(ControlFlow::Continue(()))
+            },
+        )?;
+        Ok(root_page_id)
+    }
 
-        // If no prefetched pages, load the next page directly
-        if let Some(current_id) = self.current_page_id {
-            let current_page = self.load_page_metadata(current_id)?;
-            if let Some(next_id) = current_page.next_leaf_page_id {
-                let next_page = self.load_page_metadata(next_id)?;
-                return Ok(Some((next_id, next_page)));
+    /// Id of the page after the current one. The front of the read-ahead queue becomes
+    /// the current page if it was read from that id; a queue that does not start there
+    /// is stale and dropped, and the caller loads the page directly.
+    fn get_next_page(&mut self) -> Option<PageId> {
+        let next_id = self.next_page_id?;
+        match self.read_ahead_pages.pop_front() {
+            Some((page_id, page)) if page_id == next_id && page.page_id == next_id => {
+                self.enter_page(&page);
+                self.current_page = Some(page);
             }
+            Some(_) => self.read_ahead_pages.clear(),
+            None => {}
         }
-
-        Ok(None)
+        Some(next_id)
     }
-    
 }
 
-impl Scanner for SequentialScanner {
-    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+impl SequentialScanner {
+    fn next_row(&mut self) -> Result<Option<Row>, DatabaseError> {
         if self.is_exhausted {
             return Ok(None);
         }
         if self.current_page_id.is_none() {
-            let first_leaf_id = self.find_first_leaf()?;
+            // An empty table ends here, before any full page is read
+            let Some(first_leaf_id) = self.first_live_leaf()? else {
+                self.is_exhausted = true;
+                return Ok(None);
+            };
             self.current_page_id = Some(first_leaf_id);
             self.current_slot_index = 0;
         }
         loop {
-            if let Some(page_id) = self.current_page_id {
-                let page = self.load_page_metadata(page_id)?;
-                if self.current_slot_index < page.slot_directory.slots.len() {
-                    let slot = &page.slot_directory.slots[self.current_slot_index];
-                    if slot.is_deleted() {
-                        self.current_slot_index += 1;
-                        continue;
-                    }
-                    let row = self.read_row_from_slot(page_id, slot)?;
-                    self.current_slot_index += 1;
-                    // Prefetch next page when we're near the end of current page
-                    if self.current_slot_index >= page.slot_directory.slots.len().saturating_sub(2)
-                    {
-                        let _ = self.prefetch_next_page(&page);
+            let Some(page_id) = self.current_page_id else {
+                self.is_exhausted = true;
+                return Ok(None);
+            };
+            let page = match self.current_page.take() {
+                Some(page) if page.page_id == page_id => page,
+                _ => match self.load_full_page(page_id) {
+                    Ok(page) => {
+                        self.enter_page(&page);
+                        page
                     }
-                    return Ok(Some(row));
-                } else {
-                    if let Some((next_page_id, _)) = self.get_next_page()? {
-                        self.current_page_id = Some(next_page_id);
+                    Err(error) => {
+                        self.skip_corruption(page_id, None, error)?;
+                        // Carry on past the page if its header still names a successor
+                        self.current_page_id = self
+                            .load_page_metadata(page_id)
+                            .ok()
+                            .and_then(|metadata| metadata.next_leaf_page_id);
+                        self.next_page_id = None;
+                        self.read_ahead_pages.clear();
                         self.current_slot_index = 0;
-                    } else {
-                        self.is_exhausted = true;
-                        return Ok(None);
+                        continue;
                     }
+                },
+            };
+            let slot_count = page.slot_directory.slots.len();
+            if self.current_slot_index < slot_count {
+                let slot_index = self.current_slot_index;
+                self.current_slot_index += 1;
+                if page.slot_directory.slots[slot_index].is_deleted() {
+                    self.current_page = Some(page);
+                    continue;
+                }
+                let row = self.read_row_from_page(&page, slot_index);
+                // Prefetch next page when we're near the end of current page
+                if self.current_slot_index >= slot_count.saturating_sub(2) {
+                    // A page that fails to read here is loaded, and reported, when reached
+                    let _ = self.prefetch_next_pages();
                 }
+                self.current_page = Some(page);
+                match row {
+                    Ok(row) => return Ok(Some(row)),
+                    Err(error) => self.skip_corruption(page_id, Some(slot_index), error)?,
+                }
+            } else if let Some(next_page_id) = self.get_next_page() {
+                self.current_page_id = Some(next_page_id);
+                self.current_slot_index = 0;
             } else {
                 self.is_exhausted = true;
                 return Ok(None);
             }
         }
     }
+}
+
+impl CollectMetrics for SequentialScanner {
+    fn collect_metrics(&self) -> OperatorMetrics {
+        OperatorMetrics::new(format!("Seq Scan on {}", self.table_name))
+            .with_rows(self.stats.rows_scanned, self.stats.rows_scanned)
+            .with_detail("pages read", self.stats.pages_read)
+            .with_elapsed(self.stats.elapsed)
+    }
+}
+
+impl Scanner for SequentialScanner {
+    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+        let started = Instant::now();
+        let row = self.next_row();
+        self.stats.elapsed += started.elapsed();
+        row
+    }
+
     fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
-        let mut rows = Vec::with_capacity(batch_size);
+        let bytes_before = self.stats.bytes_scanned;
+        // Settle whether an empty table has any rows before allocating for the batch
+        if self.current_page_id.is_none() && !self.is_exhausted && self.first_live_leaf()?.is_none() {
+            self.is_exhausted = true;
+        }
+        let capacity = if self.is_exhausted { 0 } else { batch_size.min(MAX_AUTO_BATCH_ROWS) };
+        let mut rows = Vec::with_capacity(capacity);
         for _ in 0..batch_size {
             match self.scan()? {
                 Some(row) => rows.push(row),
                 None => break,
             }
         }
+        self.stats.batches += 1;
+        self.stats.last_batch_rows = rows.len();
+        self.stats.last_batch_bytes = self.stats.bytes_scanned - bytes_before;
         Ok(rows)
     }
+    /// Rewind to the start of the table. The root is looked up again, so the rescan is a
+    /// fresh snapshot of the table's current data, including pages split off since the
+    /// scanner was created.
     fn reset(&mut self) -> Result<(), DatabaseError> {
+        self.root_page_id = self.resolve_root_page_id()?;
         self.current_page_id = None;
+        self.current_page = None;
+        self.next_page_id = None;
         self.current_slot_index = 0;
         self.read_ahead_pages.clear();
         self.is_exhausted = false;
@@ -0,0 +1,125 @@
+use std::{
+    cmp::Ordering,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    executor::{
+        metrics::{CollectMetrics, OperatorMetrics},
+        scan::{Scanner, collect_batch},
+        subquery::value_total_cmp,
+    },
+    planner::types::SortOrder,
+    types::{error::DatabaseError, row::Row, value::Value},
+};
+
+/// Order two rows by the columns at `keys`, later keys breaking ties in earlier ones.
+/// Values are ordered as in `value_total_cmp`: NULL lowest, so NULLs come first
+/// ascending and last descending, as in SQLite.
+pub fn compare_rows(a: &Row, b: &Row, keys: &[(usize, SortOrder)]) -> Ordering {
+    keys.iter()
+        .map(|(index, order)| {
+            let ordering = value_total_cmp(
+                a.values.get(*index).unwrap_or(&Value::Null),
+                b.values.get(*index).unwrap_or(&Value::Null),
+            );
+            match order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Return the inner scanner's rows ordered by `compare_rows`. The whole input is read
+/// into memory on the first `scan`; rows that tie on every key keep their input order.
+pub struct SortExecutor<S: Scanner> {
+    scanner: S,
+    keys: Vec<(usize, SortOrder)>,
+    /// Key column names, shown in metrics
+    key_names: Vec<String>,
+    sorted: Option<std::vec::IntoIter<Row>>,
+    rows_in: u64,
+    rows_out: u64,
+    /// Serialized size of the rows held by the last sort
+    memory_bytes: usize,
+    elapsed: Duration,
+}
+
+impl<S: Scanner> SortExecutor<S> {
+    /// Sort by the columns at the positions in `keys`, named `key_names` in metrics
+    pub fn new(scanner: S, keys: Vec<(usize, SortOrder)>, key_names: Vec<String>) -> Self {
+        Self {
+            scanner,
+            keys,
+            key_names,
+            sorted: None,
+            rows_in: 0,
+            rows_out: 0,
+            memory_bytes: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.scanner
+    }
+
+    fn next_row(&mut self) -> Result<Option<Row>, DatabaseError> {
+        if self.sorted.is_none() {
+            let mut rows = Vec::new();
+            while let Some(row) = self.scanner.scan()? {
+                rows.push(row);
+            }
+            self.rows_in += rows.len() as u64;
+            self.memory_bytes = rows.iter().map(|row| row.to_bytes().len()).sum();
+            rows.sort_by(|a, b| compare_rows(a, b, &self.keys));
+            self.sorted = Some(rows.into_iter());
+        }
+        let row = self.sorted.as_mut().and_then(Iterator::next);
+        if row.is_some() {
+            self.rows_out += 1;
+        }
+        Ok(row)
+    }
+}
+
+impl<S: Scanner> Scanner for SortExecutor<S> {
+    fn scan(&mut self) -> Result<Option<Row>, DatabaseError> {
+        let started = Instant::now();
+        let row = self.next_row();
+        self.elapsed += started.elapsed();
+        row
+    }
+
+    fn scan_batch(&mut self, batch_size: usize) -> Result<Vec<Row>, DatabaseError> {
+        collect_batch(self, batch_size)
+    }
+
+    fn reset(&mut self) -> Result<(), DatabaseError> {
+        self.sorted = None;
+        self.scanner.reset()
+    }
+}
+
+impl<S: Scanner + CollectMetrics> CollectMetrics for SortExecutor<S> {
+    fn collect_metrics(&self) -> OperatorMetrics {
+        let keys: Vec<String> = self
+            .key_names
+            .iter()
+            .zip(&self.keys)
+            .map(|(name, (_, order))| match order {
+                SortOrder::Ascending => name.clone(),
+                SortOrder::Descending => format!("{} DESC", name),
+            })
+            .collect();
+        // Sorting never spills yet; the detail keeps the output comparable once it can
+        OperatorMetrics::new(format!("Sort: {}", keys.join(", ")))
+            .with_rows(self.rows_in, self.rows_out)
+            .with_detail("memory", format!("{} bytes", self.memory_bytes))
+            .with_detail("spilled", "no")
+            .with_elapsed(self.elapsed)
+            .with_child(self.scanner.collect_metrics())
+    }
+}
@@ -0,0 +1,626 @@
+use std::time::Instant;
+
+use sqlparser::{
+    ast::{
+        BinaryOperator, Expr, FromTable, GroupByExpr, Ident, JoinConstraint, JoinOperator,
+        ObjectType, Query, SelectItem, SetExpr, Statement, TableFactor, TableObject,
+        TableWithJoins,
+    },
+    dialect::SQLiteDialect,
+    parser::Parser,
+};
+
+use crate::{
+    executor::{
+        delete::{DeleteExecutor, Deleter},
+        index_scan::IndexScanner,
+        join::HashJoinExecutor,
+        metrics::{CollectMetrics, Operator, OperatorMetrics},
+        predicate::Predicate,
+        scan::{FilterScanner, LimitScanner, ProjectScanner, PseudoColumnScanner, Scanner},
+        sort::SortExecutor,
+    },
+    planner::{parser::SqlParser, types::SortOrder},
+    storage::{
+        schema::{ColumnSchema, PseudoColumn, TableSchema},
+        storage_manager::StorageManager,
+        workload::ScanRecord,
+    },
+    types::{error::DatabaseError, row::Row, value::Value},
+};
+
+/// Outcome of one SQL statement run by `execute_statement`
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementResult {
+    TableCreated { table: String },
+    TablesDropped { tables: Vec<String> },
+    RowsInserted { count: usize },
+    RowsDeleted { count: usize },
+    /// Rows of a SELECT, holding the selected columns in the order of `columns`
+    Rows { columns: Vec<String>, rows: Vec<Row> },
+    /// What each operator of an `EXPLAIN ANALYZE`d query did while it ran
+    Explained { plan: OperatorMetrics },
+}
+
+/// Split `input` into statements at each `;` outside a quoted string or identifier,
+/// dropping empty ones, so a failing statement does not stop those after it from being
+/// parsed and run
+pub fn split_statements(input: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, ';') => {
+                statements.push(&input[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(&input[start..]);
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+/// Parse and run a single statement: CREATE TABLE, INSERT INTO ... VALUES,
+/// SELECT cols FROM table [JOIN table ON a = b ...] [WHERE ...] [ORDER BY ...]
+/// [LIMIT n [OFFSET m]], EXPLAIN ANALYZE SELECT ..., DELETE FROM ... [WHERE ...] or
+/// DROP TABLE. WHERE clauses are handed to `Predicate::parse`, so they accept what
+/// the predicate parser does.
+pub fn execute_statement(storage: &mut StorageManager, sql: &str) -> Result<StatementResult, DatabaseError> {
+    let statements = Parser::parse_sql(&SQLiteDialect {}, sql).map_err(|e| DatabaseError::SqlParseError {
+        details: e.to_string(),
+    })?;
+    let statement = match statements.as_slice() {
+        [statement] => statement,
+        _ => return Err(parse_error("expected exactly one statement")),
+    };
+
+    match statement {
+        Statement::CreateTable(_) => {
+            let (table, columns, primary_key, foreign_keys) = SqlParser::new()
+                .parse_create_table(sql)
+                .map_err(|e| parse_error(&e.to_string()))?;
+            storage.create_table_with_constraints(table.clone(), columns, primary_key, foreign_keys, sql.to_string())?;
+            Ok(StatementResult::TableCreated { table })
+        }
+        Statement::Insert(insert) => {
+            let TableObject::TableName(name) = &insert.table else {
+                return Err(unsupported("INSERT into a table function"));
+            };
+            let source = insert.source.as_deref().ok_or_else(|| unsupported("INSERT without VALUES"))?;
+            let SetExpr::Values(values) = source.body.as_ref() else {
+                return Err(unsupported("INSERT from a query"));
+            };
+            let columns: Vec<String> = insert.columns.iter().map(|column| column.value.clone()).collect();
+            insert_values(storage, &name.to_string(), &columns, &values.rows)
+        }
+        Statement::Query(query) => select(storage, query),
+        Statement::Explain { analyze: true, statement, .. } => match statement.as_ref() {
+            Statement::Query(query) => explain_analyze(storage, query),
+            other => Err(unsupported(&format!("EXPLAIN ANALYZE {}", other))),
+        },
+        Statement::Explain { .. } => Err(unsupported("EXPLAIN without ANALYZE")),
+        Statement::Delete(delete) => {
+            let (FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from)) = &delete.from;
+            let table = single_table(from)?;
+            let mut executor = DeleteExecutor::new(storage, table)?;
+            let count = match &delete.selection {
+                Some(selection) => executor.delete_where(where_predicate(selection)?)?,
+                None => executor.delete_all()?,
+            };
+            Ok(StatementResult::RowsDeleted { count })
+        }
+        Statement::Drop { object_type: ObjectType::Table, if_exists, names, .. } => {
+            let mut tables = Vec::with_capacity(names.len());
+            for name in names {
+                let table = name.to_string();
+                if *if_exists && !storage.table_exists(&table) {
+                    continue;
+                }
+                storage.drop_table(&table)?;
+                tables.push(table);
+            }
+            Ok(StatementResult::TablesDropped { tables })
+        }
+        other => Err(unsupported(&other.to_string())),
+    }
+}
+
+fn insert_values(
+    storage: &mut StorageManager,
+    table: &str,
+    columns: &[String],
+    value_rows: &[Vec<Expr>],
+) -> Result<StatementResult, DatabaseError> {
+    let schema = storage.load_table_schema(table)?.ok_or_else(|| DatabaseError::TableNotFound {
+        name: table.to_string(),
+    })?;
+    // Positions the listed columns take in a row, or every column in order
+    let positions = if columns.is_empty() {
+        (0..schema.columns.len()).collect::<Vec<_>>()
+    } else {
+        columns
+            .iter()
+            .map(|column| schema.writable_column(column).map(|column| column.position))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let parser = SqlParser::new();
+    let mut rows = Vec::with_capacity(value_rows.len());
+    for exprs in value_rows {
+        if exprs.len() != positions.len() {
+            return Err(parse_error(&format!(
+                "{} values for {} columns",
+                exprs.len(),
+                positions.len()
+            )));
+        }
+        let mut values = vec![Value::Null; schema.columns.len()];
+        for (&position, expr) in positions.iter().zip(exprs) {
+            values[position] = parser.convert_literal(expr).map_err(|e| parse_error(&e.to_string()))?;
+        }
+        let mut row = Row::new(values);
+        storage.apply_defaults(table, &mut row)?;
+        storage.coerce_row(table, &mut row)?;
+        rows.push(row);
+    }
+    let count = rows.len();
+    storage.insert_batch_into_table(table, rows)?;
+    Ok(StatementResult::RowsInserted { count })
+}
+
+/// A SELECT built into an operator pipeline, not yet run
+struct SelectPlan {
+    operator: Box<dyn Operator>,
+    /// Result header of each output column
+    columns: Vec<String>,
+    /// Table and predicate columns of a filtered single-table scan, logged for the
+    /// index advisor once the pipeline has run
+    filtered_scan: Option<(String, Vec<String>)>,
+}
+
+/// A column of the rows flowing through a SELECT pipeline, named by the table (or its
+/// alias) it comes from
+struct BoundColumn {
+    qualifier: String,
+    table: String,
+    column: ColumnSchema,
+    /// Set for a pseudo-column, whose `column` only names it
+    pseudo: Option<PseudoColumn>,
+}
+
+fn select(storage: &StorageManager, query: &Query) -> Result<StatementResult, DatabaseError> {
+    let mut plan = plan_select(storage, query)?;
+    let mut rows = Vec::new();
+    while let Some(row) = plan.operator.scan()? {
+        rows.push(row);
+    }
+    record_filtered_scan(storage, &plan);
+    Ok(StatementResult::Rows { columns: plan.columns, rows })
+}
+
+/// Run `query` to completion, discarding its rows, and report what each operator did
+fn explain_analyze(storage: &StorageManager, query: &Query) -> Result<StatementResult, DatabaseError> {
+    let mut plan = plan_select(storage, query)?;
+    while plan.operator.scan()?.is_some() {}
+    record_filtered_scan(storage, &plan);
+    Ok(StatementResult::Explained { plan: plan.operator.collect_metrics() })
+}
+
+/// Build the pipeline for `query`: scans, a hash join per JOIN, then filter, sort,
+/// projection and limit, each present only when the query asks for it
+fn plan_select(storage: &StorageManager, query: &Query) -> Result<SelectPlan, DatabaseError> {
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return Err(unsupported(&query.body.to_string()));
+    };
+    let grouped = !matches!(&select.group_by, GroupByExpr::Expressions(exprs, _) if exprs.is_empty());
+    if select.distinct.is_some() || grouped || select.having.is_some() {
+        return Err(unsupported("DISTINCT, GROUP BY or HAVING"));
+    }
+    let TableWithJoins { relation, joins } = match select.from.as_slice() {
+        [from] => from,
+        [] => return Err(parse_error("expected a table after FROM")),
+        _ => return Err(unsupported("queries over several tables without JOIN")),
+    };
+
+    let (table, qualifier) = table_factor(relation)?;
+    let mut bound = bind_table(storage, &table, &qualifier)?;
+    let mut tables = vec![table.clone()];
+    let mut operator: Box<dyn Operator> = Box::new(storage.create_scanner(&table, None)?);
+    for join in joins {
+        let JoinOperator::Inner(JoinConstraint::On(on)) = &join.join_operator else {
+            return Err(unsupported("joins other than INNER JOIN ... ON"));
+        };
+        let (build_table, build_qualifier) = table_factor(&join.relation)?;
+        let build_columns = bind_table(storage, &build_table, &build_qualifier)?;
+        let Expr::BinaryOp { left, op: BinaryOperator::Eq, right } = on else {
+            return Err(unsupported(&format!("the join condition {}", on)));
+        };
+        // Either side of the condition may name the new table
+        let (probe_key, build_key) = match (resolve(&bound, left, &table), resolve(&build_columns, right, &build_table)) {
+            (Ok(probe_key), Ok(build_key)) => (probe_key, build_key),
+            _ => (resolve(&bound, right, &table)?, resolve(&build_columns, left, &build_table)?),
+        };
+        let build: Box<dyn Operator> = Box::new(storage.create_scanner(&build_table, None)?);
+        operator = Box::new(
+            HashJoinExecutor::new(operator, build, probe_key, build_key).with_condition(on.to_string()),
+        );
+        bound.extend(build_columns);
+        tables.push(build_table);
+    }
+
+    // Rows of a single table keep their metadata, so its pseudo-columns can be named too.
+    // They follow the table's columns once `PseudoColumnScanner` appends them.
+    let width = bound.len();
+    if joins.is_empty() {
+        let pseudo_columns: Vec<BoundColumn> = PseudoColumn::ALL
+            .into_iter()
+            .filter(|pseudo| bound.iter().all(|bound| bound.column.name != pseudo.name()))
+            .enumerate()
+            .map(|(i, pseudo)| BoundColumn {
+                qualifier: qualifier.clone(),
+                table: table.clone(),
+                column: ColumnSchema::new(pseudo.name().to_string(), pseudo.data_type(), width + i),
+                pseudo: Some(pseudo),
+            })
+            .collect();
+        bound.extend(pseudo_columns);
+    }
+
+    // Names the predicate sees: bare column names over one table, qualified ones over a join
+    let joined = !joins.is_empty();
+    let names: Vec<String> = bound
+        .iter()
+        .map(|bound| {
+            if joined {
+                format!("{}.{}", bound.qualifier, bound.column.name)
+            } else {
+                bound.column.name.clone()
+            }
+        })
+        .collect();
+
+    let mut filtered_scan = None;
+    let mut filter = None;
+    if let Some(selection) = &select.selection {
+        let mut selection = selection.clone();
+        bind_identifiers(&mut selection, &|expr| Ok(names[resolve(&bound, expr, &table)?].clone()))?;
+        let schema = if joined {
+            TableSchema::new(
+                tables.join(", "),
+                names
+                    .iter()
+                    .zip(&bound)
+                    .enumerate()
+                    .map(|(position, (name, bound))| {
+                        ColumnSchema::new(name.clone(), bound.column.data_type.clone(), position)
+                    })
+                    .collect(),
+                0,
+                String::new(),
+            )
+        } else {
+            storage.load_table_schema(&table)?.cloned().ok_or_else(|| DatabaseError::TableNotFound {
+                name: table.clone(),
+            })?
+        };
+        let predicate = where_predicate(&selection)?;
+        predicate.validate_against_schema(&schema)?;
+        let predicate = storage.resolve_subqueries(&predicate, &schema)?;
+        record_reads(storage, &bound, predicate.get_referenced_columns().iter().filter_map(|column| {
+            names.iter().position(|name| name == column)
+        }));
+        if !joined {
+            filtered_scan = Some((table.clone(), predicate.get_referenced_columns()));
+        }
+        filter = Some((predicate, schema));
+    }
+
+    // Source position, result header and pipeline name of each selected column
+    let mut selected = Vec::new();
+    for item in &select.projection {
+        match item {
+            SelectItem::Wildcard(_) => {
+                selected.extend((0..width).map(|i| (i, bound[i].column.name.clone(), names[i].clone())));
+            }
+            SelectItem::QualifiedWildcard(prefix, _) => {
+                let prefix = prefix.to_string();
+                let before = selected.len();
+                selected.extend(
+                    (0..width)
+                        .filter(|&i| bound[i].qualifier == prefix)
+                        .map(|i| (i, bound[i].column.name.clone(), names[i].clone())),
+                );
+                if selected.len() == before {
+                    return Err(DatabaseError::TableNotFound { name: prefix });
+                }
+            }
+            SelectItem::UnnamedExpr(expr) => {
+                let i = resolve(&bound, expr, &table)?;
+                selected.push((i, bound[i].column.name.clone(), names[i].clone()));
+            }
+            SelectItem::ExprWithAlias { expr, alias } => {
+                let i = resolve(&bound, expr, &table)?;
+                selected.push((i, alias.value.clone(), names[i].clone()));
+            }
+        }
+    }
+
+    let mut sort = None;
+    if let Some(order_by) = &query.order_by {
+        let mut keys = Vec::with_capacity(order_by.exprs.len());
+        let mut key_names = Vec::with_capacity(order_by.exprs.len());
+        for key in &order_by.exprs {
+            if key.nulls_first.is_some() || key.with_fill.is_some() {
+                return Err(unsupported(&format!("ORDER BY {}", key)));
+            }
+            // A bare name may refer to a result column by its alias
+            let alias = match &key.expr {
+                Expr::Identifier(ident) => selected.iter().find(|(_, header, _)| *header == ident.value),
+                _ => None,
+            };
+            let index = match alias {
+                Some((index, ..)) => *index,
+                None => resolve(&bound, &key.expr, &table)?,
+            };
+            let order = match key.asc {
+                Some(false) => SortOrder::Descending,
+                _ => SortOrder::Ascending,
+            };
+            keys.push((index, order));
+            key_names.push(names[index].clone());
+        }
+        sort = Some((keys, key_names));
+    }
+
+    // A single table already stored in the requested order is read in that order instead
+    // of being sorted
+    if !joined
+        && let Some((keys, _)) = &sort
+        && let Some(scanner) = index_order_scanner(storage, &table, keys, width)?
+    {
+        operator = Box::new(scanner);
+        sort = None;
+    }
+    if let Some((predicate, schema)) = filter {
+        operator = Box::new(
+            FilterScanner::new(operator, predicate, schema)?
+                .with_value_comparison(storage.options.value_comparison)
+                .with_predicate_mode(storage.options.predicate_mode),
+        );
+    }
+
+    let sort_indices = sort.iter().flat_map(|(keys, _)| keys.iter().map(|(index, _)| *index));
+    if selected.iter().map(|(index, ..)| *index).chain(sort_indices).any(|index| index >= width) {
+        let pseudo_columns = bound[width..].iter().filter_map(|bound| bound.pseudo).collect();
+        operator = Box::new(PseudoColumnScanner::new(operator, width, pseudo_columns));
+    }
+    if let Some((keys, key_names)) = sort {
+        operator = Box::new(SortExecutor::new(operator, keys, key_names));
+    }
+
+    record_reads(storage, &bound, selected.iter().map(|(index, ..)| *index));
+
+    let indices = selected.iter().map(|(index, ..)| *index).collect();
+    let projected = selected.iter().map(|(_, _, name)| name.clone()).collect();
+    operator = Box::new(ProjectScanner::new(operator, indices, projected));
+
+    let limit = query.limit.as_ref().map(|expr| row_count("LIMIT", expr)).transpose()?;
+    let offset = query.offset.as_ref().map(|offset| row_count("OFFSET", &offset.value)).transpose()?;
+    if limit.is_some() || offset.is_some() {
+        operator = Box::new(LimitScanner::new(operator, limit.unwrap_or(usize::MAX), offset.unwrap_or(0)));
+    }
+
+    Ok(SelectPlan {
+        operator,
+        columns: selected.into_iter().map(|(_, header, _)| header).collect(),
+        filtered_scan,
+    })
+}
+
+/// A scan of `table` returning its rows already ordered by the sort `keys`, if one exists:
+/// the table's own tree when the keys are a prefix of its key in a single direction, or
+/// the index on a lone NOT NULL key column, whose index leaves out no rows
+fn index_order_scanner(
+    storage: &StorageManager,
+    table: &str,
+    keys: &[(usize, SortOrder)],
+    width: usize,
+) -> Result<Option<IndexScanner>, DatabaseError> {
+    let Some((_, order)) = keys.first() else {
+        return Ok(None);
+    };
+    if keys.iter().any(|(index, key_order)| *index >= width || key_order != order) {
+        return Ok(None);
+    }
+    let Some(schema) = storage.load_table_schema(table)? else {
+        return Ok(None);
+    };
+    let key_columns = match schema.key_columns() {
+        [] => &[0][..],
+        key_columns => key_columns,
+    };
+    let positions: Vec<usize> = keys.iter().map(|(index, _)| *index).collect();
+    if key_columns.starts_with(&positions) {
+        return Ok(Some(IndexScanner::new(storage, table, None, order.clone())?));
+    }
+    let [position] = positions[..] else {
+        return Ok(None);
+    };
+    let indexed = storage.table_indexes(table)?.into_iter().find(|(_, indexed)| *indexed == position);
+    match indexed {
+        Some((index, _)) if schema.columns.iter().any(|column| column.position == position && !column.nullable) => {
+            Ok(Some(IndexScanner::new(storage, table, Some(&index.column_name), order.clone())?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Log a filtered single-table SELECT with the workload log, as `scan_table` does for
+/// the scans it filters, so `\advise` sees queries run from the prompt
+fn record_filtered_scan(storage: &StorageManager, plan: &SelectPlan) {
+    let Some((table, columns)) = &plan.filtered_scan else {
+        return;
+    };
+    let metrics = plan.operator.collect_metrics();
+    let mut filter = &metrics;
+    while !filter.operator.starts_with("Filter") {
+        match filter.children.first() {
+            Some(child) => filter = child,
+            None => return,
+        }
+    }
+    let Some(scan) = filter.children.first() else {
+        return;
+    };
+    storage.workload_log().record(ScanRecord {
+        table: table.clone(),
+        columns: columns.clone(),
+        rows_examined: scan.rows_out,
+        rows_returned: filter.rows_out,
+        pages_read: scan.detail("pages read").and_then(|pages| pages.parse().ok()).unwrap_or(0),
+        at: Instant::now(),
+    });
+}
+
+/// Count one read of each of the pipeline columns at `indices` with the column usage
+/// counters, however often the projection or predicate names it
+fn record_reads(storage: &StorageManager, bound: &[BoundColumn], indices: impl IntoIterator<Item = usize>) {
+    let mut read = vec![false; bound.len()];
+    for i in indices {
+        read[i] = true;
+    }
+    for (column, _) in bound.iter().zip(read).filter(|(column, read)| *read && column.pseudo.is_none()) {
+        storage.column_usage_log().record_reads(&column.table, [column.column.position]);
+    }
+}
+
+/// Table named by a FROM or JOIN item, with the name its columns are qualified by
+fn table_factor(relation: &TableFactor) -> Result<(String, String), DatabaseError> {
+    match relation {
+        TableFactor::Table { name, alias, .. } => {
+            let table = name.to_string();
+            let qualifier = alias.as_ref().map_or_else(|| table.clone(), |alias| alias.name.value.clone());
+            Ok((table, qualifier))
+        }
+        other => Err(unsupported(&format!("selecting from {}", other))),
+    }
+}
+
+/// Columns of `table` in row order, qualified by `qualifier`
+fn bind_table(storage: &StorageManager, table: &str, qualifier: &str) -> Result<Vec<BoundColumn>, DatabaseError> {
+    let schema = storage.load_table_schema(table)?.ok_or_else(|| DatabaseError::TableNotFound {
+        name: table.to_string(),
+    })?;
+    let mut columns = schema.columns.clone();
+    columns.sort_by_key(|column| column.position);
+    Ok(columns
+        .into_iter()
+        .map(|column| BoundColumn {
+            qualifier: qualifier.to_string(),
+            table: table.to_string(),
+            column,
+            pseudo: None,
+        })
+        .collect())
+}
+
+/// Position among `columns` of the column `expr` names, as `name` or `qualifier.name`.
+/// A bare name found in more than one table is an error.
+fn resolve(columns: &[BoundColumn], expr: &Expr, table: &str) -> Result<usize, DatabaseError> {
+    let (qualifier, name) = match expr {
+        Expr::Identifier(ident) => (None, &ident.value),
+        Expr::CompoundIdentifier(idents) if idents.len() == 2 => (Some(&idents[0].value), &idents[1].value),
+        _ => return Err(unsupported(&format!("the expression {}", expr))),
+    };
+    let mut matches = columns
+        .iter()
+        .enumerate()
+        .filter(|(_, bound)| bound.column.name == *name && qualifier.is_none_or(|q| bound.qualifier == *q))
+        .map(|(i, _)| i);
+    match (matches.next(), matches.next()) {
+        (Some(i), None) => Ok(i),
+        (Some(_), Some(_)) => Err(parse_error(&format!("ambiguous column name: {}", expr))),
+        (None, _) => Err(DatabaseError::ColumnNotFound {
+            name: name.clone(),
+            table: qualifier.map_or_else(|| table.to_string(), Clone::clone),
+        }),
+    }
+}
+
+/// Replace each column reference in `expr` with the quoted name `bind` gives it, so
+/// qualified references reach `Predicate::parse` as single identifiers. Subqueries are
+/// left alone, as they name another table's columns.
+fn bind_identifiers(
+    expr: &mut Expr,
+    bind: &impl Fn(&Expr) -> Result<String, DatabaseError>,
+) -> Result<(), DatabaseError> {
+    match expr {
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => {
+            *expr = Expr::Identifier(Ident::with_quote('"', bind(expr)?));
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            bind_identifiers(left, bind)?;
+            bind_identifiers(right, bind)?;
+        }
+        Expr::UnaryOp { expr: inner, .. }
+        | Expr::Nested(inner)
+        | Expr::IsNull(inner)
+        | Expr::IsNotNull(inner)
+        | Expr::InSubquery { expr: inner, .. }
+        | Expr::Like { expr: inner, .. }
+        | Expr::ILike { expr: inner, .. } => bind_identifiers(inner, bind)?,
+        Expr::InList { expr: inner, list, .. } => {
+            bind_identifiers(inner, bind)?;
+            for item in list {
+                bind_identifiers(item, bind)?;
+            }
+        }
+        Expr::Between { expr: inner, low, high, .. } => {
+            bind_identifiers(inner, bind)?;
+            bind_identifiers(low, bind)?;
+            bind_identifiers(high, bind)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn row_count(clause: &str, expr: &Expr) -> Result<usize, DatabaseError> {
+    match SqlParser::new().convert_literal(expr) {
+        Ok(Value::Integer(count)) if count >= 0 => Ok(count as usize),
+        _ => Err(parse_error(&format!("{} must be a non-negative integer, got {}", clause, expr))),
+    }
+}
+
+/// Name of the one plain table in a FROM clause
+fn single_table(from: &[TableWithJoins]) -> Result<String, DatabaseError> {
+    match from {
+        [TableWithJoins { relation: TableFactor::Table { name, .. }, joins }] if joins.is_empty() => {
+            Ok(name.to_string())
+        }
+        [] => Err(parse_error("expected a table after FROM")),
+        _ => Err(unsupported("joins and queries over several tables")),
+    }
+}
+
+fn where_predicate(selection: &Expr) -> Result<Predicate, DatabaseError> {
+    Predicate::parse(&selection.to_string())
+}
+
+fn parse_error(details: &str) -> DatabaseError {
+    DatabaseError::SqlParseError {
+        details: details.to_string(),
+    }
+}
+
+fn unsupported(what: &str) -> DatabaseError {
+    parse_error(&format!("unsupported statement: {}", what))
+}
@@ -0,0 +1,246 @@
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    sync::Mutex,
+};
+
+use crate::types::{error::DatabaseError, value::Value};
+
+/// Default number of bytes a materialized value set may buffer before spilling to disk
+pub const DEFAULT_SUBQUERY_MEMORY_BUDGET: usize = 16 * 1024 * 1024;
+
+/// Number of values per block in a spilled set; one key per block is kept in memory
+const SPILL_BLOCK_LEN: usize = 64;
+
+/// Total order used to sort and probe value sets; see `Value::total_cmp`
+pub fn value_total_cmp(a: &Value, b: &Value) -> Ordering {
+    a.total_cmp(b)
+}
+
+/// A deduplicated set of values materialized from another table's column,
+/// used to evaluate `IN (SELECT ...)` style predicates
+#[derive(Debug)]
+pub struct ValueSet {
+    contains_null: bool,
+    storage: ValueSetStorage,
+}
+
+/// First value and byte offset of every block in a spilled set
+type BlockIndex = Vec<(Value, u64)>;
+
+#[derive(Debug)]
+enum ValueSetStorage {
+    InMemory(Vec<Value>),
+    Spilled {
+        file: Mutex<File>,
+        index: BlockIndex,
+        len: usize,
+    },
+}
+
+impl ValueSet {
+    /// Whether the set holds a value equal to `value`. NULL is never a member.
+    pub fn contains(&self, value: &Value) -> Result<bool, DatabaseError> {
+        if value.is_null() {
+            return Ok(false);
+        }
+        match &self.storage {
+            ValueSetStorage::InMemory(values) => Ok(values
+                .binary_search_by(|probe| value_total_cmp(probe, value))
+                .is_ok()),
+            ValueSetStorage::Spilled { file, index, .. } => {
+                let block = index.partition_point(|(first, _)| {
+                    value_total_cmp(first, value) != Ordering::Greater
+                });
+                if block == 0 {
+                    return Ok(false);
+                }
+                let mut file = file.lock().map_err(|_| DatabaseError::ConcurrencyError)?;
+                file.seek(SeekFrom::Start(index[block - 1].1))?;
+                let mut reader = BufReader::new(&mut *file);
+                for _ in 0..SPILL_BLOCK_LEN {
+                    match read_spilled_value(&mut reader)? {
+                        Some(candidate) => match value_total_cmp(&candidate, value) {
+                            Ordering::Less => continue,
+                            Ordering::Equal => return Ok(true),
+                            Ordering::Greater => return Ok(false),
+                        },
+                        None => break,
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Whether the source column contained NULL
+    pub fn contains_null(&self) -> bool {
+        self.contains_null
+    }
+
+    /// Number of distinct non-null values
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            ValueSetStorage::InMemory(values) => values.len(),
+            ValueSetStorage::Spilled { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the set had to be spilled to a temporary file
+    pub fn is_spilled(&self) -> bool {
+        matches!(self.storage, ValueSetStorage::Spilled { .. })
+    }
+}
+
+impl PartialEq for ValueSet {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// Accumulates values for a `ValueSet`, spilling sorted runs to temporary files
+/// once the buffered values exceed the memory budget
+pub struct ValueSetBuilder {
+    memory_budget: usize,
+    buffer: Vec<Value>,
+    buffered_bytes: usize,
+    runs: Vec<File>,
+    contains_null: bool,
+}
+
+impl ValueSetBuilder {
+    pub fn new(memory_budget: usize) -> Self {
+        Self {
+            memory_budget,
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            runs: Vec::new(),
+            contains_null: false,
+        }
+    }
+
+    pub fn push(&mut self, value: Value) -> Result<(), DatabaseError> {
+        if value.is_null() {
+            self.contains_null = true;
+            return Ok(());
+        }
+        self.buffered_bytes += value.serialized_size() + std::mem::size_of::<Value>();
+        self.buffer.push(value);
+        if self.buffered_bytes > self.memory_budget {
+            self.spill_run()?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<ValueSet, DatabaseError> {
+        if self.runs.is_empty() {
+            sort_dedup(&mut self.buffer);
+            return Ok(ValueSet {
+                contains_null: self.contains_null,
+                storage: ValueSetStorage::InMemory(self.buffer),
+            });
+        }
+        self.spill_run()?;
+        let (file, index, len) = merge_runs(std::mem::take(&mut self.runs))?;
+        Ok(ValueSet {
+            contains_null: self.contains_null,
+            storage: ValueSetStorage::Spilled {
+                file: Mutex::new(file),
+                index,
+                len,
+            },
+        })
+    }
+
+    fn spill_run(&mut self) -> Result<(), DatabaseError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        sort_dedup(&mut self.buffer);
+        let mut file = tempfile::tempfile()?;
+        {
+            let mut writer = BufWriter::new(&mut file);
+            for value in self.buffer.drain(..) {
+                write_spilled_value(&mut writer, &value)?;
+            }
+            writer.flush()?;
+        }
+        file.seek(SeekFrom::Start(0))?;
+        self.runs.push(file);
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+}
+
+fn sort_dedup(values: &mut Vec<Value>) {
+    values.sort_by(value_total_cmp);
+    values.dedup_by(|a, b| value_total_cmp(a, b) == Ordering::Equal);
+}
+
+/// K-way merge of sorted runs into a single deduplicated file with a sparse block index
+fn merge_runs(runs: Vec<File>) -> Result<(File, BlockIndex, usize), DatabaseError> {
+    let mut readers: Vec<BufReader<File>> = runs.into_iter().map(BufReader::new).collect();
+    let mut heads = Vec::with_capacity(readers.len());
+    for reader in readers.iter_mut() {
+        heads.push(read_spilled_value(reader)?);
+    }
+
+    let mut output = tempfile::tempfile()?;
+    let mut index = Vec::new();
+    let mut len = 0usize;
+    let mut offset = 0u64;
+    let mut last: Option<Value> = None;
+    {
+        let mut writer = BufWriter::new(&mut output);
+        loop {
+            let next = heads
+                .iter()
+                .enumerate()
+                .filter_map(|(i, head)| head.as_ref().map(|value| (i, value)))
+                .min_by(|(_, a), (_, b)| value_total_cmp(a, b))
+                .map(|(i, _)| i);
+            let Some(run) = next else { break };
+            let value = heads[run].take().expect("selected run has a head value");
+            heads[run] = read_spilled_value(&mut readers[run])?;
+
+            if last
+                .as_ref()
+                .is_some_and(|previous| value_total_cmp(previous, &value) == Ordering::Equal)
+            {
+                continue;
+            }
+            if len.is_multiple_of(SPILL_BLOCK_LEN) {
+                index.push((value.clone(), offset));
+            }
+            offset += write_spilled_value(&mut writer, &value)? as u64;
+            len += 1;
+            last = Some(value);
+        }
+        writer.flush()?;
+    }
+    Ok((output, index, len))
+}
+
+fn write_spilled_value<W: Write>(writer: &mut W, value: &Value) -> Result<usize, DatabaseError> {
+    let bytes = value.to_bytes();
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    Ok(4 + bytes.len())
+}
+
+fn read_spilled_value<R: Read>(reader: &mut R) -> Result<Option<Value>, DatabaseError> {
+    let mut len_bytes = [0u8; 4];
+    match reader.read_exact(&mut len_bytes) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let mut bytes = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+    reader.read_exact(&mut bytes)?;
+    Value::from_bytes(&bytes).map(Some)
+}
@@ -1,66 +1,158 @@
-use std::io::Write;
-
 use bambang::{
     art::welcome_message,
-    executor::scan::Scanner,
-    storage::storage_manager::StorageManager,
-    types::{row::Row, value::Value, error::DatabaseError},
+    executor::{
+        predicate::Predicate,
+        scan::Scanner,
+        statement::{StatementResult, execute_statement, split_statements},
+    },
+    storage::{
+        export::JsonLayout,
+        header::BambangHeader,
+        options::StorageManagerOptions,
+        schema::{ColumnSchema, TableSchema},
+        storage_manager::StorageManager,
+    },
+    types::{page::{TablePageStats, VacuumReport}, row::Row, value::Value, error::DatabaseError},
+    utils::{
+        metrics_exposition::{QueryMetrics, render_metrics, serve_metrics},
+        progress::TerminalProgress,
+        result_format::{OutputMode, ResultFormatter},
+    },
 };
 use rustyline::{DefaultEditor, error::ReadlineError};
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::BufWriter,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
+const USAGE: &str = "Usage: bambang [[--db] <path> | :memory:] [--metrics-listen <addr>]";
 
-fn main() -> Result<(), ReadlineError> {
-    let welcome = welcome_message("BAMBANG DB");
-    println!("{}", welcome);
+/// Database the shell opens, from the command line
+#[derive(Debug)]
+enum DatabaseLocation {
+    /// Deleted on exit; the default when no path is given
+    Memory,
+    File(PathBuf),
+}
 
-    let temp_dir = tempfile::tempdir().map_err(|e| ReadlineError::Io(e))?;
-    let db_path = temp_dir.path().join("bambang.db");
-    let mut storage_manager = StorageManager::new(&db_path).map_err(|e| ReadlineError::Io(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
+/// Options read from the command line
+#[derive(Debug)]
+struct Args {
+    location: DatabaseLocation,
+    /// Address to serve Prometheus metrics on, if any
+    metrics_listen: Option<String>,
+}
 
-    // Create a simple test table
-    storage_manager
-        .create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT)")
-        .map_err(|e| ReadlineError::Io(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
+/// Read `[--db] <path>` and `--metrics-listen <addr>` from the arguments after the program
+/// name. `:memory:` or no path at all opens an ephemeral database.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut location = None;
+    let mut metrics_listen = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let path = match arg.as_str() {
+            "--db" => args.next().ok_or("--db needs a path")?,
+            "--metrics-listen" => {
+                metrics_listen = Some(args.next().ok_or("--metrics-listen needs an address")?);
+                continue;
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option: {}", flag)),
+            _ => arg,
+        };
+        if location.is_some() {
+            return Err(format!("Unexpected argument: {}", path));
+        }
+        location = Some(if path == ":memory:" {
+            DatabaseLocation::Memory
+        } else {
+            DatabaseLocation::File(PathBuf::from(path))
+        });
+    }
+    Ok(Args {
+        location: location.unwrap_or(DatabaseLocation::Memory),
+        metrics_listen,
+    })
+}
 
-    // Insert some test data
-    let test_rows = vec![
-        Row::new(vec![
-            Value::Integer(1),
-            Value::Text("Alice".to_string()),
-            Value::Text("alice@example.com".to_string()),
-        ]),
-        Row::new(vec![
-            Value::Integer(2),
-            Value::Text("Bob".to_string()),
-            Value::Text("bob@example.com".to_string()),
-        ]),
-        Row::new(vec![
-            Value::Integer(3),
-            Value::Text("Charlie".to_string()),
-            Value::Text("charlie@example.com".to_string()),
-        ]),
-    ];
+fn to_readline_error(error: DatabaseError) -> ReadlineError {
+    ReadlineError::Io(std::io::Error::other(error.to_string()))
+}
 
-    for row in test_rows {
-        storage_manager
-            .insert_into_table("users", row)
-            .map_err(|e| ReadlineError::Io(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
-    }
+fn main() -> Result<(), ReadlineError> {
+    let Args { location, metrics_listen } = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
 
-    println!("\n--- Full Table Scan ---");
-    let all_rows = storage_manager.scan_table("users", None).map_err(|e| ReadlineError::Io(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
-    println!("Retrieved {} rows using scan_table()", all_rows.len());
-    for (i, row) in all_rows.iter().enumerate() {
-        println!("Row {}: {:?}", i + 1, row.values);
+    let welcome = welcome_message("BAMBANG DB");
+    println!("{}", welcome);
+
+    // The temporary directory lives until the end of main, taking an ephemeral database
+    // with it
+    let (_temp_dir, db_path, history_path) = match location {
+        DatabaseLocation::Memory => {
+            let temp_dir = tempfile::tempdir().map_err(ReadlineError::Io)?;
+            let db_path = temp_dir.path().join("bambang.db");
+            (Some(temp_dir), db_path, None)
+        }
+        DatabaseLocation::File(db_path) => {
+            let mut history_path = OsString::from(db_path.as_os_str());
+            history_path.push(".history");
+            (None, db_path, Some(PathBuf::from(history_path)))
+        }
+    };
+    let is_new_database = !db_path.exists();
+    let options = StorageManagerOptions::default().with_log(|message| println!("{}", message));
+    let mut storage_manager = StorageManager::open_with_options(&db_path, options).map_err(to_readline_error)?;
+    if is_new_database {
+        add_demo_data(&mut storage_manager).map_err(to_readline_error)?;
     }
 
+    // Scrapes read the exposition rendered after the last statement, never the database
+    let metrics = match metrics_listen {
+        Some(addr) => {
+            let listener = TcpListener::bind(&addr).map_err(ReadlineError::Io)?;
+            println!("Serving metrics on http://{}/metrics", listener.local_addr().map_err(ReadlineError::Io)?);
+            let snapshot = Arc::new(Mutex::new(String::new()));
+            serve_metrics(listener, snapshot.clone());
+            Some(snapshot)
+        }
+        None => None,
+    };
+    let mut query_metrics = QueryMetrics::default();
+    publish_metrics(&mut storage_manager, &query_metrics, metrics.as_deref());
+
     println!("\n--- Interactive Mode ---");
-    println!("Enter SQL-like commands or 'quit' to exit");
-    println!("Available commands:");
-    println!("  scan users - Show all users");
+    println!("Enter SQL statements separated by ';', or 'quit' to exit");
+    println!("Supported SQL: CREATE TABLE, INSERT INTO ... VALUES, SELECT ... FROM ... [JOIN ... ON a = b]");
+    println!("  [WHERE ...] [ORDER BY ...] [LIMIT n [OFFSET m]], EXPLAIN ANALYZE SELECT ...,");
+    println!("  DELETE FROM ... [WHERE ...], DROP TABLE");
+    println!("Other commands:");
+    println!("  scan <table> [WHERE <condition>] - Show a table's rows, e.g. scan users WHERE id > 2");
+    println!("  demo - Run the scanner demo");
+    println!("  \\analyze <statement> - Run a SELECT and show what each operator did");
+    println!("  \\advise - Suggest indexes for the scans run so far");
+    println!("  \\usage <table> - Show how often each column is read and written");
+    println!("  \\import <file> - Import a table from a page image");
+    println!("{}", DOT_COMMANDS);
     println!("  quit - Exit the program");
 
+    let mut formatter = ResultFormatter::default();
     let mut rl = DefaultEditor::new()?;
+    if let Some(history_path) = &history_path
+        && history_path.exists()
+        && let Err(e) = rl.load_history(history_path)
+    {
+        println!("Could not load history from {}: {}", history_path.display(), e);
+    }
     loop {
         let readline = rl.readline("bambang> ");
         match readline {
@@ -77,20 +169,96 @@ fn main() -> Result<(), ReadlineError> {
                     break;
                 }
                 
-                if trimmed.eq_ignore_ascii_case("scan users") {
-                    match storage_manager.scan_table("users", None) {
+                if trimmed.starts_with('.') {
+                    run_dot_command(&mut storage_manager, &mut formatter, trimmed);
+                } else if let Some(scan_args) = strip_prefix_ignore_case(trimmed, "scan ") {
+                    let (table_name, condition) = match scan_args.trim().split_once(char::is_whitespace) {
+                        Some((table_name, rest)) => (table_name, Some(rest.trim())),
+                        None => (scan_args.trim(), None),
+                    };
+                    let predicate = match condition {
+                        Some(rest) => match strip_prefix_ignore_case(rest, "where ") {
+                            Some(condition) => Predicate::parse(condition).map(Some),
+                            None => Err(DatabaseError::PredicateSyntax {
+                                offset: 0,
+                                token: rest.to_string(),
+                                details: "expected WHERE after the table name".to_string(),
+                            }),
+                        },
+                        None => Ok(None),
+                    };
+                    let scanned = predicate.and_then(|predicate| storage_manager.scan_table(table_name, predicate));
+                    match scanned {
                         Ok(rows) => {
-                            println!("Found {} rows:", rows.len());
-                            for (i, row) in rows.iter().enumerate() {
-                                println!("  {}: {:?}", i + 1, row.values);
-                            }
+                            let columns = scan_columns(&storage_manager, table_name, &rows);
+                            print_rows(&formatter, &columns, &rows);
                         }
                         Err(e) => println!("Error scanning table: {}", e),
                     }
+                } else if trimmed.eq_ignore_ascii_case("demo") {
+                    if let Err(e) = demo_scanner_functionality() {
+                        println!("Demo failed: {}", e);
+                    }
+                } else if trimmed.eq_ignore_ascii_case("\\advise") {
+                    let recommendations = storage_manager.recommend_indexes(Duration::from_secs(24 * 60 * 60));
+                    if recommendations.is_empty() {
+                        println!("No index recommendations");
+                    }
+                    for recommendation in recommendations {
+                        println!(
+                            "  {} -- saves ~{:.0} pages/day",
+                            recommendation.create_statement, recommendation.estimated_pages_saved_per_day
+                        );
+                    }
+                } else if let Some(table_name) = trimmed.strip_prefix("\\usage ") {
+                    match storage_manager.column_usage(table_name.trim()) {
+                        Ok(columns) => {
+                            for column in columns {
+                                println!(
+                                    "  {}: {} reads, {} writes, {:.0}% non-NULL",
+                                    column.name,
+                                    column.reads,
+                                    column.writes,
+                                    column.non_null_ratio * 100.0
+                                );
+                            }
+                        }
+                        Err(e) => println!("Error: {}", e),
+                    }
+                } else if let Some(statement) = trimmed.strip_prefix("\\analyze ") {
+                    let explain = format!("EXPLAIN ANALYZE {}", statement.trim().trim_end_matches(';'));
+                    match execute_statement(&mut storage_manager, &explain) {
+                        Ok(result) => print_statement_result(&formatter, &result),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                } else if let Some(image_path) = trimmed.strip_prefix("\\import ") {
+                    let progress = TerminalProgress::stderr();
+                    let imported = std::fs::File::open(image_path.trim())
+                        .map_err(DatabaseError::from)
+                        .and_then(|file| {
+                            let mut reader = std::io::BufReader::new(file);
+                            storage_manager.import_table_pages_with_progress(&mut reader, Some(&progress))
+                        });
+                    progress.finish();
+                    match imported {
+                        Ok(summary) => println!(
+                            "Imported table '{}' ({} pages)",
+                            summary.table_name, summary.page_count
+                        ),
+                        Err(e) => println!("Import failed: {}", e),
+                    }
                 } else {
-                    println!("Unknown command: {}", trimmed);
-                    println!("Available commands: scan users, quit");
+                    for statement in split_statements(trimmed) {
+                        let started = Instant::now();
+                        let result = execute_statement(&mut storage_manager, statement);
+                        query_metrics.record(statement, started.elapsed());
+                        match result {
+                            Ok(result) => print_statement_result(&formatter, &result),
+                            Err(e) => println!("Error: {}", e),
+                        }
+                    }
                 }
+                publish_metrics(&mut storage_manager, &query_metrics, metrics.as_deref());
             }
             Err(ReadlineError::Interrupted) => {
                 println!("CTRL-C");
@@ -106,10 +274,305 @@ fn main() -> Result<(), ReadlineError> {
             }
         }
     }
-    
+
+    if let Some(history_path) = &history_path
+        && let Err(e) = rl.save_history(history_path)
+    {
+        println!("Could not save history to {}: {}", history_path.display(), e);
+    }
+    storage_manager.close().map_err(to_readline_error)?;
     Ok(())
 }
 
+/// Render the metrics into `snapshot` for the metrics endpoint to serve, if there is one
+fn publish_metrics(storage_manager: &mut StorageManager, query_metrics: &QueryMetrics, snapshot: Option<&Mutex<String>>) {
+    let Some(snapshot) = snapshot else {
+        return;
+    };
+    match render_metrics(storage_manager, query_metrics) {
+        Ok(rendered) => {
+            if let Ok(mut snapshot) = snapshot.lock() {
+                *snapshot = rendered;
+            }
+        }
+        Err(e) => println!("Could not render metrics: {}", e),
+    }
+}
+
+/// Create and fill the `users` table a new database starts with
+fn add_demo_data(storage_manager: &mut StorageManager) -> Result<(), DatabaseError> {
+    storage_manager.create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT)")?;
+
+    let test_rows = vec![
+        Row::new(vec![
+            Value::Integer(1),
+            Value::Text("Alice".to_string()),
+            Value::Text("alice@example.com".to_string()),
+        ]),
+        Row::new(vec![
+            Value::Integer(2),
+            Value::Text("Bob".to_string()),
+            Value::Text("bob@example.com".to_string()),
+        ]),
+        Row::new(vec![
+            Value::Integer(3),
+            Value::Text("Charlie".to_string()),
+            Value::Text("charlie@example.com".to_string()),
+        ]),
+    ];
+
+    for row in test_rows {
+        storage_manager.insert_into_table("users", row)?;
+    }
+
+    println!("\n--- Full Table Scan ---");
+    let all_rows = storage_manager.scan_table("users", None)?;
+    println!("Retrieved {} rows using scan_table()", all_rows.len());
+    for (i, row) in all_rows.iter().enumerate() {
+        println!("Row {}: {:?}", i + 1, row.values);
+    }
+    Ok(())
+}
+
+
+fn print_statement_result(formatter: &ResultFormatter, result: &StatementResult) {
+    match result {
+        StatementResult::TableCreated { table } => println!("Created table '{}'", table),
+        StatementResult::TablesDropped { tables } => println!("Dropped {} table(s)", tables.len()),
+        StatementResult::RowsInserted { count } => println!("Inserted {} row(s)", count),
+        StatementResult::RowsDeleted { count } => println!("Deleted {} row(s)", count),
+        StatementResult::Rows { columns, rows } => print_rows(formatter, columns, rows),
+        StatementResult::Explained { plan } => print!("{}", plan.render()),
+    }
+}
+
+/// Print rows in the formatter's mode. Only tables get a row count, so CSV and JSON
+/// output can be copied as is.
+fn print_rows(formatter: &ResultFormatter, columns: &[String], rows: &[Row]) {
+    print!("{}", formatter.format(columns, rows));
+    if formatter.mode == OutputMode::Table {
+        println!("({} row(s))", rows.len());
+    }
+}
+
+/// Headers for a scan of `table`: its schema's column names, or `column1`, `column2`, ...
+/// for a table without a schema
+fn scan_columns(storage: &StorageManager, table: &str, rows: &[Row]) -> Vec<String> {
+    match storage.get_table_schema(table) {
+        Some(schema) => schema.column_names(),
+        None => {
+            let width = rows.iter().map(|row| row.values.len()).max().unwrap_or(0);
+            (1..=width).map(|i| format!("column{}", i)).collect()
+        }
+    }
+}
+
+const DOT_COMMANDS: &str = "  .tables - List the tables
+  .schema [table] - Show the SQL and columns of a table, or of every table
+  .stats [table] - Show how full a table's pages are, or every table's
+  .vacuum [table] - Compact a table's pages and merge underfull ones, or rebuild the whole database file
+  .dbinfo - Show the database header
+  .integrity_check - Check every page of the database file and list the problems found
+  .mode [table|csv|json] - Set how query results are printed, or show the current mode
+  .export <table> <file> [csv|json|jsonl] - Write a table to a file, in the format its extension names by default";
+
+fn run_dot_command(storage: &mut StorageManager, formatter: &mut ResultFormatter, line: &str) {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    let argument = words.next();
+    let tables = match argument {
+        Some(table) => vec![table.to_string()],
+        None => {
+            let mut tables = storage.get_table_names();
+            tables.sort();
+            tables
+        }
+    };
+    match command {
+        ".tables" => tables.iter().for_each(|table| println!("{}", table)),
+        ".schema" => {
+            for table in &tables {
+                match storage.get_table_schema(table) {
+                    Some(schema) => print_schema(schema),
+                    None => println!("Error: Table '{}' not found", table),
+                }
+            }
+        }
+        ".stats" => {
+            for table in &tables {
+                match storage.table_page_stats(table) {
+                    Ok(stats) => print_page_stats(table, &stats),
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+        }
+        ".vacuum" if argument.is_none() => match storage.vacuum() {
+            Ok(report) => println!(
+                "Reclaimed {} bytes, freed {} pages ({} -> {} pages)",
+                report.reclaimed_bytes(),
+                report.freed_pages(),
+                report.pages_before,
+                report.pages_after
+            ),
+            Err(e) => println!("Error: {}", e),
+        },
+        ".vacuum" => {
+            for table in &tables {
+                match storage.vacuum_table(table, true) {
+                    Ok(report) => print_vacuum_report(table, &report),
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+        }
+        ".dbinfo" => print_header(&storage.db_info.header),
+        ".integrity_check" => match storage.check_integrity() {
+            Ok(report) if report.is_ok() => {
+                println!("ok ({} trees, {} pages checked)", report.trees_checked, report.pages_checked)
+            }
+            Ok(report) => report.findings.iter().for_each(|finding| println!("{}", finding)),
+            Err(e) => println!("Error: {}", e),
+        },
+        ".mode" => match argument.map(str::parse::<OutputMode>) {
+            Some(Ok(mode)) => formatter.mode = mode,
+            Some(Err(e)) => println!("Error: {}", e),
+            None => println!("{}", formatter.mode.name()),
+        },
+        ".export" => match (argument, words.next()) {
+            (Some(table), Some(path)) => export_table(storage, table, path, words.next()),
+            _ => println!("Usage: .export <table> <file> [csv|json|jsonl]"),
+        },
+        _ => println!("Unknown command: {}\n{}", command, DOT_COMMANDS),
+    }
+}
+
+/// Write every row of `table` to `path` as CSV, a JSON array or JSON lines, as `format`
+/// or else the file extension says
+fn export_table(storage: &StorageManager, table: &str, path: &str, format: Option<&str>) {
+    let format = format
+        .or_else(|| Path::new(path).extension().and_then(|extension| extension.to_str()))
+        .unwrap_or("csv")
+        .to_ascii_lowercase();
+    let layout = match format.as_str() {
+        "csv" => None,
+        "json" => Some(JsonLayout::Array),
+        "jsonl" | "ndjson" => Some(JsonLayout::Lines),
+        other => {
+            println!("Error: Unknown export format '{}', expected csv, json or jsonl", other);
+            return;
+        }
+    };
+    let exported = File::create(path).map_err(DatabaseError::from).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        match layout {
+            Some(layout) => storage.export_json(table, None, &mut writer, layout),
+            None => storage.export_csv(table, None, &mut writer),
+        }
+    });
+    match exported {
+        Ok(count) => println!("Exported {} row(s) to {}", count, path),
+        Err(e) => println!("Export failed: {}", e),
+    }
+}
+
+fn print_schema(schema: &TableSchema) {
+    println!("{};", schema.sql);
+    let mut columns: Vec<&ColumnSchema> = schema.columns.iter().collect();
+    columns.sort_by_key(|column| column.position);
+    for column in columns {
+        let mut line = format!("  {} {}", column.name, column.data_type);
+        if !column.nullable {
+            line.push_str(" NOT NULL");
+        }
+        if let Some(default) = &column.default_value {
+            line.push_str(&format!(" DEFAULT {}", default));
+        }
+        if column.primary_key && schema.primary_key.len() == 1 {
+            line.push_str(" PRIMARY KEY");
+        }
+        if column.unique {
+            line.push_str(" UNIQUE");
+        }
+        if let Some(check) = &column.check {
+            line.push_str(&format!(" CHECK ({})", check));
+        }
+        if let Some(foreign_key) = schema.foreign_key(&column.name) {
+            line.push_str(&format!(" REFERENCES {}({})", foreign_key.ref_table, foreign_key.ref_column));
+        }
+        println!("{}", line);
+    }
+    if schema.primary_key.len() > 1 {
+        let names: Vec<&str> = schema.primary_key_columns().iter().map(|column| column.name.as_str()).collect();
+        println!("  PRIMARY KEY ({})", names.join(", "));
+    }
+}
+
+fn print_page_stats(table: &str, stats: &TablePageStats) {
+    println!(
+        "{}: {} pages ({} leaf, {} interior), {} slots ({} active, {} deleted)",
+        table,
+        stats.page_count,
+        stats.leaf_pages,
+        stats.interior_pages,
+        stats.total_slots,
+        stats.active_slots,
+        stats.deleted_slots
+    );
+    println!(
+        "  utilization {:.1}%, fragmentation {:.1}%, free {} bytes, wasted {} bytes",
+        stats.utilization_ratio() * 100.0,
+        stats.fragmentation_ratio() * 100.0,
+        stats.free_space,
+        stats.wasted_space
+    );
+}
+
+fn print_vacuum_report(table: &str, report: &VacuumReport) {
+    println!(
+        "{}: reclaimed {} bytes, freed {} pages",
+        table,
+        report.reclaimed_bytes(),
+        report.freed_pages()
+    );
+    print_page_stats("  before", &report.before);
+    print_page_stats("  after", &report.after);
+}
+
+fn print_header(header: &BambangHeader) {
+    let fields: [(&str, String); 22] = [
+        ("magic", String::from_utf8_lossy(&header.magic).trim_end_matches('\0').to_string()),
+        ("page size", header.page_size_bytes().to_string()),
+        ("file format write version", header.file_format_write_version.to_string()),
+        ("file format read version", header.file_format_read_version.to_string()),
+        ("reserved space", header.reserved_space.to_string()),
+        ("max embedded payload fraction", header.max_embedded_payload_fraction.to_string()),
+        ("min embedded payload fraction", header.min_embedded_payload_fraction.to_string()),
+        ("leaf payload fraction", header.leaf_payload_fraction.to_string()),
+        ("file change counter", header.file_change_counter.to_string()),
+        ("database size (pages)", header.database_size_pages.to_string()),
+        ("freelist trunk page", header.freelist_trunk_page.to_string()),
+        ("freelist pages", header.freelist_pages_count.to_string()),
+        ("schema cookie", header.schema_cookie.to_string()),
+        ("schema format", header.schema_format_number.to_string()),
+        ("default page cache size", header.default_page_cache_size.to_string()),
+        ("largest root b-tree page", header.largest_root_btree_page.to_string()),
+        ("text encoding", header.text_encoding.to_string()),
+        ("user version", header.user_version.to_string()),
+        ("incremental vacuum mode", header.incremental_vacuum_mode.to_string()),
+        ("application id", header.application_id.to_string()),
+        ("version valid for", header.version_valid_for.to_string()),
+        ("bambang version", header.bambang_version_number.to_string()),
+    ];
+    for (name, value) in fields {
+        println!("{:<30} {}", name, value);
+    }
+}
+
+/// `line` without `prefix`, matched ignoring ASCII case
+fn strip_prefix_ignore_case<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = line.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix).then(|| &line[prefix.len()..])
+}
+
 fn demo_scanner_functionality() -> Result<(), DatabaseError> {
     println!("\n=== Scanner Functionality Demo ===");
     
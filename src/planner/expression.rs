@@ -263,7 +263,7 @@ impl Expression {
                 !is_non_deterministic && args.iter().all(|arg| arg.is_deterministic())
             }
             Expression::Aggregate { expr, .. } => {
-                expr.as_ref().map_or(true, |e| e.is_deterministic())
+                expr.as_ref().is_none_or(|e| e.is_deterministic())
             }
             Expression::IsNull(expr) | Expression::IsNotNull(expr) => expr.is_deterministic(),
             Expression::In { expr, list, .. } => {
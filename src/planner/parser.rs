@@ -1,15 +1,29 @@
 use crate::{
+    executor::predicate::{LogicalOp, Predicate},
     planner::{error::PlannerError, logical_plan::LogicalPlan},
-    types::value::DataType,
+    storage::schema::{ColumnSchema, ForeignKey},
+    types::value::{DataType, Value},
 };
 use sqlparser::{
-    ast::{DataType as SqlDataType, Statement},
+    ast::{
+        ColumnOption, DataType as SqlDataType, Expr, Ident, ObjectName, ReferentialAction, Statement,
+        TableConstraint, UnaryOperator, Value as SqlValue,
+    },
     dialect::SQLiteDialect,
     parser::Parser,
 };
 
 pub struct SqlParser;
 
+/// A CREATE TABLE statement taken apart by `SqlParser::parse_create_table`
+pub type CreateTableParts = (String, Vec<ColumnSchema>, Vec<usize>, Vec<ForeignKey>);
+
+impl Default for SqlParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SqlParser {
     pub fn new() -> Self {
         Self
@@ -28,25 +42,203 @@ impl SqlParser {
         self.to_plan(&statements[0])
     }
 
-    fn to_plan(&self, statement: &Statement) -> Result<LogicalPlan, PlannerError> {
-        match statement {
-            _ => Err(PlannerError::UnsupportedStatement(format!(
-                "{:?}",
-                statement
-            ))),
+    /// Parse a CREATE TABLE statement into its table name, column schemas, the
+    /// positions named by a table-level `PRIMARY KEY (a, b, ...)` in key order, which is
+    /// empty when the key, if any, is declared on a column, and its foreign keys
+    pub fn parse_create_table(
+        &self,
+        sql: &str,
+    ) -> Result<CreateTableParts, PlannerError> {
+        let dialect = SQLiteDialect {};
+        let statements = Parser::parse_sql(&dialect, sql)?;
+
+        let create = match statements.as_slice() {
+            [Statement::CreateTable(create)] => create,
+            _ => {
+                return Err(PlannerError::InvalidQuery(
+                    "Expected a single CREATE TABLE statement".to_string(),
+                ));
+            }
+        };
+
+        let mut columns = Vec::with_capacity(create.columns.len());
+        let mut foreign_keys = Vec::new();
+        for (position, column_def) in create.columns.iter().enumerate() {
+            let data_type = self.convert_data_type(&column_def.data_type)?;
+            let mut column = ColumnSchema::new(column_def.name.value.clone(), data_type, position);
+
+            for option in &column_def.options {
+                match &option.option {
+                    ColumnOption::NotNull => column = column.not_null(),
+                    ColumnOption::Unique { is_primary: true, .. } => column = column.primary_key(),
+                    ColumnOption::Unique { is_primary: false, .. } => column = column.unique(),
+                    ColumnOption::Default(expr) => {
+                        column = column.with_default(self.convert_literal(expr)?)
+                    }
+                    ColumnOption::Check(expr) => column = column.with_check(self.convert_check(expr)?),
+                    ColumnOption::ForeignKey {
+                        foreign_table,
+                        referred_columns,
+                        on_delete,
+                        on_update,
+                        ..
+                    } => foreign_keys.push(self.convert_foreign_key(
+                        std::slice::from_ref(&column_def.name),
+                        foreign_table,
+                        referred_columns,
+                        [on_delete, on_update],
+                    )?),
+                    _ => {}
+                }
+            }
+            columns.push(column);
+        }
+
+        let mut primary_key = Vec::new();
+        for constraint in &create.constraints {
+            if let TableConstraint::ForeignKey {
+                columns: referencing,
+                foreign_table,
+                referred_columns,
+                on_delete,
+                on_update,
+                ..
+            } = constraint
+            {
+                foreign_keys.push(self.convert_foreign_key(
+                    referencing,
+                    foreign_table,
+                    referred_columns,
+                    [on_delete, on_update],
+                )?);
+                continue;
+            }
+            let TableConstraint::PrimaryKey { columns: key_columns, .. } = constraint else {
+                continue;
+            };
+            if !primary_key.is_empty() {
+                return Err(PlannerError::InvalidQuery(
+                    "Table can have at most one PRIMARY KEY constraint".to_string(),
+                ));
+            }
+            for key_column in key_columns {
+                let position = columns
+                    .iter()
+                    .position(|column| column.name == key_column.value)
+                    .ok_or_else(|| {
+                        PlannerError::InvalidQuery(format!("PRIMARY KEY names unknown column '{}'", key_column.value))
+                    })?;
+                primary_key.push(position);
+            }
         }
+
+        if let Some(foreign_key) = foreign_keys
+            .iter()
+            .find(|foreign_key| !columns.iter().any(|column| column.name == foreign_key.column))
+        {
+            return Err(PlannerError::InvalidQuery(format!(
+                "FOREIGN KEY names unknown column '{}'",
+                foreign_key.column
+            )));
+        }
+
+        Ok((create.name.to_string(), columns, primary_key, foreign_keys))
+    }
+
+    fn to_plan(&self, statement: &Statement) -> Result<LogicalPlan, PlannerError> {
+        Err(PlannerError::UnsupportedStatement(format!(
+            "{:?}",
+            statement
+        )))
     }
 
     fn convert_data_type(&self, sql_type: &SqlDataType) -> Result<DataType, PlannerError> {
         match sql_type {
-            SqlDataType::Integer(_) => Ok(DataType::Integer),
-            SqlDataType::Float(_) => Ok(DataType::Real),
+            SqlDataType::Integer(_)
+            | SqlDataType::Int(_)
+            | SqlDataType::BigInt(_)
+            | SqlDataType::SmallInt(_)
+            | SqlDataType::TinyInt(_) => Ok(DataType::Integer),
+            SqlDataType::Float(_) | SqlDataType::Real | SqlDataType::Double(_) => Ok(DataType::Real),
             SqlDataType::Text => Ok(DataType::Text),
-            SqlDataType::Boolean => Ok(DataType::Boolean),
+            SqlDataType::Boolean | SqlDataType::Bool => Ok(DataType::Boolean),
+            SqlDataType::Blob(_) => Ok(DataType::Blob),
             SqlDataType::Varchar(_) => Ok(DataType::Text),
             SqlDataType::Char(_) => Ok(DataType::Text),
             SqlDataType::Timestamp(_, _) => Ok(DataType::Timestamp),
+            SqlDataType::Datetime(_) => Ok(DataType::Timestamp),
+            SqlDataType::Decimal(_) | SqlDataType::Numeric(_) => Ok(DataType::Decimal),
             _ => Err(PlannerError::UnsupportedDataType(format!("{:?}", sql_type))),
         }
     }
+
+    /// Condition of a `CHECK (...)` constraint, which may not read other tables
+    fn convert_check(&self, expr: &Expr) -> Result<Predicate, PlannerError> {
+        let check = Predicate::parse(&expr.to_string())
+            .map_err(|e| PlannerError::UnsupportedExpression(format!("CHECK ({}): {}", expr, e)))?;
+        if reads_other_tables(&check) {
+            return Err(PlannerError::UnsupportedExpression(format!("subquery in CHECK ({})", expr)));
+        }
+        Ok(check)
+    }
+
+    /// A single-column foreign key. The referenced column has to be named, and only the
+    /// default RESTRICT behaviour is enforced, so actions such as CASCADE are refused.
+    fn convert_foreign_key(
+        &self,
+        columns: &[Ident],
+        foreign_table: &ObjectName,
+        referred_columns: &[Ident],
+        actions: [&Option<ReferentialAction>; 2],
+    ) -> Result<ForeignKey, PlannerError> {
+        let ([column], [ref_column]) = (columns, referred_columns) else {
+            return Err(PlannerError::InvalidQuery(format!(
+                "FOREIGN KEY referencing {} must name one column on each side",
+                foreign_table
+            )));
+        };
+        if let Some(action) = actions
+            .into_iter()
+            .flatten()
+            .find(|action| !matches!(action, ReferentialAction::Restrict | ReferentialAction::NoAction))
+        {
+            return Err(PlannerError::UnsupportedExpression(format!(
+                "{} on FOREIGN KEY referencing {}",
+                action, foreign_table
+            )));
+        }
+        Ok(ForeignKey::new(column.value.clone(), foreign_table.to_string(), ref_column.value.clone()))
+    }
+
+    /// Value of a literal expression such as `42`, `-1.5`, `'text'` or `NULL`
+    pub fn convert_literal(&self, expr: &Expr) -> Result<Value, PlannerError> {
+        match expr {
+            Expr::Value(SqlValue::Null) => Ok(Value::Null),
+            Expr::Value(SqlValue::Boolean(b)) => Ok(Value::Boolean(*b)),
+            Expr::Value(SqlValue::SingleQuotedString(s)) => Ok(Value::Text(s.clone())),
+            Expr::Value(SqlValue::Number(n, _)) => n
+                .parse::<i64>()
+                .map(Value::Integer)
+                .or_else(|_| n.parse::<f64>().map(Value::Real))
+                .map_err(|_| PlannerError::UnsupportedExpression(n.clone())),
+            Expr::UnaryOp { op: UnaryOperator::Minus, expr } => match self.convert_literal(expr)? {
+                Value::Integer(i) => Ok(Value::Integer(-i)),
+                Value::Real(r) => Ok(Value::Real(-r)),
+                other => Err(PlannerError::UnsupportedExpression(format!("-{}", other))),
+            },
+            _ => Err(PlannerError::UnsupportedExpression(expr.to_string())),
+        }
+    }
+}
+
+/// Whether `predicate` holds a subquery
+fn reads_other_tables(predicate: &Predicate) -> bool {
+    match predicate {
+        Predicate::InTable { .. } | Predicate::InValueSet { .. } => true,
+        Predicate::Logical { op: LogicalOp::Not, left, .. } => reads_other_tables(left),
+        Predicate::Logical { left, right, .. } => {
+            reads_other_tables(left) || right.as_deref().is_some_and(reads_other_tables)
+        }
+        _ => false,
+    }
 }
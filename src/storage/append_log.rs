@@ -0,0 +1,95 @@
+use crate::types::{
+    RowId,
+    error::DatabaseError,
+    page::Page,
+    row::Row,
+    value::Value,
+};
+
+/// A point in an append-log table, in rowids or in the time of its TIMESTAMP column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogPosition {
+    RowId(RowId),
+    /// Milliseconds, compared with the table's `TableSchema::log_time_column`
+    Timestamp(i64),
+}
+
+/// What `StorageManager::drop_before` removed from the head of an append log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogTrim {
+    pub pages_freed: usize,
+    pub rows_removed: usize,
+}
+
+/// Which of a row's coordinates a range read or trim compares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LogAxis {
+    RowId,
+    /// Position of the TIMESTAMP column
+    Time(usize),
+}
+
+impl LogAxis {
+    /// `position` on this axis
+    pub(crate) fn coordinate(self, position: LogPosition) -> Result<i128, DatabaseError> {
+        match (self, position) {
+            (LogAxis::RowId, LogPosition::RowId(row_id)) => Ok(row_id as i128),
+            (LogAxis::Time(_), LogPosition::Timestamp(millis)) => Ok(millis as i128),
+            _ => Err(DatabaseError::InvalidData {
+                details: "A log range must be given in rowids or in timestamps, not both".to_string(),
+            }),
+        }
+    }
+
+    /// The coordinate of `row`, which the log stamped with a rowid and checked for a time
+    pub(crate) fn row_coordinate(self, row: &Row) -> Option<i128> {
+        match self {
+            LogAxis::RowId => row.row_id.map(|row_id| row_id as i128),
+            LogAxis::Time(column) => row_time(row, column).map(|millis| millis as i128),
+        }
+    }
+
+    /// Whether every row of a page lies before `bound`, given where the next page starts.
+    /// Rowids increase strictly, but rows either side of a page break may share a time.
+    pub(crate) fn page_precedes(self, next_start: i128, bound: i128) -> bool {
+        match self {
+            LogAxis::RowId => next_start <= bound,
+            LogAxis::Time(_) => next_start < bound,
+        }
+    }
+
+    /// The coordinate of the first row on `page`, read from its header and slot
+    /// directory alone, or `None` for a page with no rows yet
+    pub(crate) fn page_start(self, page: &Page) -> Option<i128> {
+        match self {
+            LogAxis::RowId => first_row_id(page).map(|row_id| row_id as i128),
+            LogAxis::Time(_) => first_timestamp(page).map(|millis| millis as i128),
+        }
+    }
+}
+
+/// The time in `column` of a log row
+pub(crate) fn row_time(row: &Row, column: usize) -> Option<i64> {
+    match row.values.get(column) {
+        Some(Value::Timestamp(millis)) => Some(*millis),
+        _ => None,
+    }
+}
+
+/// Rowid of the first row on a log page
+pub(crate) fn first_row_id(page: &Page) -> Option<RowId> {
+    page.slot_directory.slots.first().and_then(|slot| slot.row_id)
+}
+
+/// Time of the first row on a log page. Log pages have no parent, so their header's
+/// parent field carries it instead, where a range read finds it without the page's cells.
+/// The sign bit is flipped so no time but `i64::MAX` reads as the field's "no parent".
+pub(crate) fn first_timestamp(page: &Page) -> Option<i64> {
+    page.parent_page_id.map(|raw| (raw ^ TIME_SIGN_BIT) as i64)
+}
+
+pub(crate) fn set_first_timestamp(page: &mut Page, millis: Option<i64>) {
+    page.parent_page_id = millis.map(|millis| millis as u64 ^ TIME_SIGN_BIT);
+}
+
+const TIME_SIGN_BIT: u64 = 1 << 63;
@@ -1,15 +1,25 @@
 use std::{
-    collections::HashMap,
-    fs::File,
+    collections::HashSet,
     io::{Read, Seek, SeekFrom, Write},
+    ops::Range,
+    sync::Arc,
+    time::Duration,
 };
 
-use crate::types::{
-    PAGE_SIZE, PageId,
-    error::DatabaseError,
-    page::{Page, PageType},
-    row::Row,
-    value::Value,
+use crate::{
+    storage::{
+        freelist, header::BambangHeader, io_stats::IoCounters, journal::RollbackJournal,
+        page_cache::{CacheClock, CacheStats, PageCache},
+        page_store::PageStore,
+    },
+    types::{
+        PAGE_HEADER_SIZE, PAGE_SIZE, PageId, SLOT_DIRECTORY_ENTRY_SIZE,
+        error::DatabaseError,
+        validate_page_size,
+        page::{OverflowPointer, Page, PageType, StorageCost, overflow_threshold},
+        row::Row,
+        value::Value,
+    },
 };
 
 #[derive(Debug, Clone)]
@@ -25,36 +35,225 @@ pub struct SplitResult {
     pub separator_key: Value,
 }
 
+/// What rebalancing did to a pair of sibling pages
+enum Rebalanced {
+    Merged,
+    Redistributed,
+    Unchanged,
+}
+
 pub struct BPlusTree {
     pub root_page_id: PageId,
-    pub file: File,
-    pub page_cache: HashMap<PageId, Page>,
+    pub file: Box<dyn PageStore>,
+    pub page_cache: PageCache,
     pub next_page_id: PageId,
     pub order: usize,
+    extras: Option<u64>,
+    page_size: usize,
+    io_counters: Arc<IoCounters>,
+    partial_writes: bool,
+    journal: Option<Arc<RollbackJournal>>,
+    paranoid_checks: bool,
+    fixed_root: bool,
+    /// Leave leaves modified by `insert` dirty in the cache until `flush_dirty_pages`
+    deferred_writes: bool,
+    /// Fragmentation ratio above which `delete_cell` and `update_cell` compact the page
+    /// before writing it; `None` leaves compaction to the caller
+    compaction_threshold: Option<f32>,
+    /// Columns of a composite PRIMARY KEY whose tuple is the key; empty to key on the
+    /// first value
+    key_columns: Vec<usize>,
 }
 
 impl BPlusTree {
-    pub fn new(file: File, root_page_id: PageId) -> Result<Self, DatabaseError> {
+    pub fn new(file: impl PageStore + 'static, root_page_id: PageId) -> Result<Self, DatabaseError> {
         Self::new_with_extras(file, root_page_id, None)
     }
 
-    pub fn new_with_extras(file: File, root_page_id: PageId, extras: Option<u64>) -> Result<Self, DatabaseError> {
-        let file_size = file.metadata()?.len();
+    pub fn new_with_extras(file: impl PageStore + 'static, root_page_id: PageId, extras: Option<u64>) -> Result<Self, DatabaseError> {
+        Self::new_with_page_size(file, root_page_id, extras, PAGE_SIZE)
+    }
+
+    /// Open a tree in a file whose pages are `page_size` bytes rather than the default
+    pub fn new_with_page_size(
+        file: impl PageStore + 'static,
+        root_page_id: PageId,
+        extras: Option<u64>,
+        page_size: usize,
+    ) -> Result<Self, DatabaseError> {
+        validate_page_size(page_size)?;
+        let file_size = file.len()?;
         let data_size = if let Some(extras) = extras {
             file_size.saturating_sub(extras)
         } else {
             file_size
         };
-        let next_page_id = ((data_size / PAGE_SIZE as u64) + 1) as PageId;
+        let next_page_id = ((data_size / page_size as u64) + 1) as PageId;
         Ok(Self {
             root_page_id,
-            file,
-            page_cache: HashMap::new(),
+            file: Box::new(file),
+            page_cache: PageCache::default(),
             next_page_id,
             order: 4,
+            extras,
+            page_size,
+            io_counters: Arc::default(),
+            partial_writes: true,
+            journal: None,
+            paranoid_checks: false,
+            fixed_root: false,
+            deferred_writes: false,
+            compaction_threshold: None,
+            key_columns: Vec::new(),
         })
     }
 
+    /// Count this tree's page writes in `counters`, e.g. ones shared with other trees
+    pub fn with_io_counters(mut self, counters: Arc<IoCounters>) -> Self {
+        self.io_counters = counters;
+        self
+    }
+
+    /// Whether pages with few modified bytes are written by extent rather than whole
+    pub fn with_partial_writes(mut self, enabled: bool) -> Self {
+        self.partial_writes = enabled;
+        self
+    }
+
+    /// Save each page to `journal` before its first write, so a transaction can undo it
+    pub fn with_journal(mut self, journal: Option<Arc<RollbackJournal>>) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    /// Check every page before it is written and every split as it happens, failing
+    /// with `InternalInvariant` at the mutation that broke the tree rather than in a
+    /// later read. Meant for tests and debugging; it decodes each written page again.
+    pub fn with_paranoid_checks(mut self, enabled: bool) -> Self {
+        self.paranoid_checks = enabled;
+        self
+    }
+
+    /// Keep the root on `root_page_id` for good: a root split moves its contents into a
+    /// new child and a root left with one child takes that child's contents, as the
+    /// schema tree anchored on page 1 needs
+    pub fn with_fixed_root(mut self, enabled: bool) -> Self {
+        self.fixed_root = enabled;
+        self
+    }
+
+    /// Keep a leaf that takes a row without splitting in the cache, dirty, instead of
+    /// writing it on every insert, so a run of inserts into the same leaf writes it once.
+    /// Splits and evictions still write straight away; the rest reaches the file only
+    /// through `flush_dirty_pages`, which the caller must invoke before dropping the tree.
+    pub fn with_deferred_writes(mut self, enabled: bool) -> Self {
+        self.deferred_writes = enabled;
+        self
+    }
+
+    /// Compact a leaf whose fragmentation ratio exceeds `threshold` after `delete_cell` or
+    /// `update_cell`, before it is written. Deleted slots are kept, so slot indices the
+    /// caller collected earlier stay valid.
+    pub fn with_compaction_threshold(mut self, threshold: Option<f32>) -> Self {
+        self.compaction_threshold = threshold;
+        self
+    }
+
+    /// Write every dirty cached page and flush the file, returning how many were written
+    pub fn flush_dirty_pages(&mut self) -> Result<usize, DatabaseError> {
+        let mut written = 0;
+        for page_id in self.page_cache.page_ids() {
+            let Some(page) = self.page_cache.peek(&page_id).filter(|page| page.is_dirty).cloned() else {
+                continue;
+            };
+            let extras = self.extras;
+            self.write_page(page_id, page, extras)?;
+            written += 1;
+        }
+        self.file.flush()?;
+        Ok(written)
+    }
+
+    /// Key rows on the tuple of values at `key_columns` rather than on their first
+    /// value, for a table with a composite PRIMARY KEY
+    pub fn with_key_columns(mut self, key_columns: Vec<usize>) -> Self {
+        self.key_columns = key_columns;
+        self
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Key `row` is stored under. NaN equals nothing, not even itself, so a key holding
+    /// one could never be looked up.
+    fn row_key(&self, row: &Row) -> Result<Value, DatabaseError> {
+        let nan = match self.key_columns.as_slice() {
+            [] => row.values.first().is_some_and(Value::is_nan),
+            columns => columns.iter().any(|position| row.values.get(*position).is_some_and(Value::is_nan)),
+        };
+        if nan {
+            return Err(DatabaseError::InvalidData {
+                details: "NaN cannot be used as a key".to_string(),
+            });
+        }
+        Ok(row.key(&self.key_columns))
+    }
+
+    /// Bound the page cache to `capacity` pages, writing back any dirty pages it evicts
+    pub fn set_cache_capacity(&mut self, capacity: usize) -> Result<(), DatabaseError> {
+        for page in self.page_cache.set_capacity(capacity) {
+            self.write_back(page)?;
+        }
+        Ok(())
+    }
+
+    /// Take cached pages' ages from `clock` instead of the wall clock
+    pub fn with_cache_clock(mut self, clock: Arc<dyn CacheClock>) -> Self {
+        self.page_cache.set_clock(clock);
+        self
+    }
+
+    /// Every `interval`, as pages are loaded, drop the clean cached pages that went
+    /// unused for longer than `max_age`
+    pub fn with_cache_sweep(mut self, interval: Duration, max_age: Duration) -> Self {
+        self.page_cache.set_sweep(interval, max_age);
+        self
+    }
+
+    /// Drop the cached pages not used within the last `age`, writing back dirty ones when
+    /// `flush_dirty` is set and keeping them otherwise. Returns how many were dropped.
+    pub fn evict_cold_pages(&mut self, age: Duration, flush_dirty: bool) -> Result<usize, DatabaseError> {
+        let evicted = self.page_cache.evict_older_than(age, flush_dirty);
+        let count = evicted.len();
+        for page in evicted {
+            self.write_back(page)?;
+        }
+        Ok(count)
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.page_cache.stats()
+    }
+
+    /// Cache a page that has just been written, writing back whatever it evicts
+    fn cache_page(&mut self, page_id: PageId, mut page: Page) -> Result<(), DatabaseError> {
+        page.is_dirty = false;
+        page.clear_dirty_extents();
+        if let Some(evicted) = self.page_cache.insert(page_id, page) {
+            self.write_back(evicted)?;
+        }
+        Ok(())
+    }
+
+    fn write_back(&mut self, page: Page) -> Result<(), DatabaseError> {
+        if page.is_dirty {
+            let extras = self.extras;
+            self.write_page(page.page_id, page, extras)?;
+        }
+        Ok(())
+    }
+
     pub fn load_page(
         &mut self,
         page_id: PageId,
@@ -69,31 +268,64 @@ impl BPlusTree {
         }
         
         let offset = if let Some(extras) = extras {
-            extras as u64 + (page_id - 1) * PAGE_SIZE as u64
+            extras + (page_id - 1) * self.page_size as u64
         } else {
-            (page_id - 1) * PAGE_SIZE as u64
+            (page_id - 1) * self.page_size as u64
         };
         
         // Add bounds checking for file offset
-        let file_size = self.file.metadata()?.len();
-        if offset + PAGE_SIZE as u64 > file_size {
+        let file_size = self.file.len()?;
+        if offset + self.page_size as u64 > file_size {
             return Err(DatabaseError::CorruptedPage {
                 page_id,
                 reason: format!("Page offset {} exceeds file size {}", offset, file_size),
             });
         }
         
+        for page in self.page_cache.sweep_if_due() {
+            self.write_back(page)?;
+        }
         if !self.page_cache.contains_key(&page_id) {
-            let mut buffer = vec![0u8; PAGE_SIZE];
+            let mut buffer = vec![0u8; self.page_size];
             self.file.seek(SeekFrom::Start(offset))?;
             self.file.read_exact(&mut buffer)?;
+            self.io_counters.record_page_read();
             let page = Page::from_bytes(&buffer)?;
-            self.page_cache.insert(page_id, page);
+            if page.is_dirty {
+                // Persist repairs made while loading before anything else touches the page
+                self.write_page(page_id, page.clone(), extras)?;
+            }
+            self.cache_page(page_id, page)?;
         }
         Ok(self.page_cache.get(&page_id).unwrap())
     }
 
-    fn write_page(&mut self, page_id: PageId, page: Page, extras: Option<u64>) -> Result<(), DatabaseError> {
+    fn write_page(&mut self, page_id: PageId, mut page: Page, extras: Option<u64>) -> Result<(), DatabaseError> {
+        self.write_page_data(page_id, &mut page, extras)?;
+        // Don't add new pages to the cache when writing - only cache when pages are requested,
+        // but keep an already cached copy current (and most recently used)
+        if self.page_cache.contains_key(&page_id) {
+            page.is_dirty = false;
+            page.clear_dirty_extents();
+            self.page_cache.insert(page_id, page);
+        }
+        Ok(())
+    }
+
+    /// Write `page` and cache it, handing it over without a copy
+    fn store_page(&mut self, page_id: PageId, mut page: Page, extras: Option<u64>) -> Result<(), DatabaseError> {
+        self.write_page_data(page_id, &mut page, extras)?;
+        self.cache_page(page_id, page)
+    }
+
+    /// Take a page out of the cache to modify it in place; `store_page` puts it back
+    fn take_page(&mut self, page_id: PageId, extras: Option<u64>) -> Result<Page, DatabaseError> {
+        self.load_page(page_id, extras)?;
+        Ok(self.page_cache.remove(&page_id).expect("page was just loaded"))
+    }
+
+    /// Write a page's bytes to the file, leaving the cache alone
+    fn write_page_data(&mut self, page_id: PageId, page: &mut Page, extras: Option<u64>) -> Result<(), DatabaseError> {
         // Add bounds checking for page_id
         if page_id == 0 {
             return Err(DatabaseError::CorruptedPage {
@@ -102,34 +334,83 @@ impl BPlusTree {
             });
         }
         
+        if let Some(journal) = &self.journal {
+            journal.save_page(&mut self.file, page_id)?;
+        }
+        // Header fields such as leaf links may change after the last cell update
+        page.update_checksum();
         let page_bytes = page.to_bytes()?;
+        if self.paranoid_checks {
+            page.validate_invariants()?;
+            if let Err(error) = Page::from_bytes(&page_bytes) {
+                return Err(DatabaseError::InternalInvariant {
+                    details: format!("page {} would not read back after being written: {}", page_id, error),
+                });
+            }
+        }
         let offset = if let Some(extras) = extras {
-            extras as u64 + (page_id - 1) * PAGE_SIZE as u64
+            extras + (page_id - 1) * self.page_size as u64
         } else {
-            (page_id - 1) * PAGE_SIZE as u64
+            (page_id - 1) * self.page_size as u64
         };
         
-        self.file.seek(SeekFrom::Start(offset))?;
-        self.file.write_all(&page_bytes)?;
+        match page.write_extents().filter(|_| self.partial_writes) {
+            Some(extents) => {
+                let mut written = 0;
+                for extent in extents {
+                    self.file.seek(SeekFrom::Start(offset + extent.start as u64))?;
+                    self.file.write_all(&page_bytes[extent.clone()])?;
+                    written += extent.len();
+                }
+                self.io_counters.record_partial_write(written);
+            }
+            None => {
+                self.file.seek(SeekFrom::Start(offset))?;
+                self.file.write_all(&page_bytes)?;
+                self.io_counters.record_full_write(page_bytes.len());
+            }
+        }
         // Don't flush here - let batch operations handle flushing
-        // Don't add to cache when writing - only cache when pages are requested
         Ok(())
     }
 
     fn allocate_page(&mut self, page_type: PageType, extras: Option<u64>) -> Result<PageId, DatabaseError> {
-        let new_page_id = self.next_page_id;
-        self.next_page_id += 1;
-        let new_page = Page::new(new_page_id, page_type);
+        let new_page_id = self.allocate_page_id(extras)?;
+        let new_page = Page::new_with_size(new_page_id, page_type, self.page_size);
         self.write_page(new_page_id, new_page, extras)?;
         Ok(new_page_id)
     }
 
+    /// Claim a page id without writing the page, for callers that write it right away
+    fn allocate_page_id(&mut self, extras: Option<u64>) -> Result<PageId, DatabaseError> {
+        let new_page_id = if extras.is_some() {
+            // The tree lives in a database file: reuse freed pages and keep the header in sync
+            let mut header = BambangHeader::read_from(&mut self.file)?;
+            let page_id = match freelist::pop_free_page(&mut self.file, &mut header)? {
+                Some(page_id) => page_id,
+                None => {
+                    header.ensure_can_grow(1)?;
+                    header.database_size_pages += 1;
+                    header.database_size_pages as PageId
+                }
+            };
+            header.write_to(&mut self.file)?;
+            self.next_page_id = self.next_page_id.max(header.database_size_pages as PageId + 1);
+            page_id
+        } else {
+            let page_id = self.next_page_id;
+            self.next_page_id += 1;
+            page_id
+        };
+        Ok(new_page_id)
+    }
+
     pub fn insert(
         &mut self,
         row: Row,
         extras: Option<u64>,
     ) -> Result<Option<PageId>, DatabaseError> {
-        let key = row.values[0].clone();
+        let key = self.row_key(&row)?;
         let row_bytes = row.to_bytes();
         
         // Validate row data before insertion
@@ -149,9 +430,14 @@ impl BPlusTree {
             extras,
         )?;
         
-        if let Some(split) = split_result {
-            let new_root_id = self.allocate_page(PageType::InteriorTable, extras)?;
-            let mut new_root = Page::new(new_root_id, PageType::InteriorTable);
+        if let Some(mut split) = split_result {
+            let new_root_id = if self.fixed_root {
+                self.move_split_root_down(&mut split, extras)?;
+                self.root_page_id
+            } else {
+                self.allocate_page(PageType::InteriorTable, extras)?
+            };
+            let mut new_root = Page::new_with_size(new_root_id, PageType::InteriorTable, self.page_size);
             let left_entry_data =
                 self.create_interior_entry(&split.separator_key, split.left_page.page_id)?;
             let right_entry_data =
@@ -160,17 +446,15 @@ impl BPlusTree {
             new_root.insert_cell(&right_entry_data, None)?;
             
             // Batch write all pages to reduce I/O overhead
-            self.write_pages_batch(&[
-                (new_root_id, new_root.clone()),
-                (split.left_page.page_id, split.left_page.clone()),
-                (split.right_page.page_id, split.right_page.clone()),
+            self.write_pages_batch([
+                (new_root_id, new_root),
+                (split.left_page.page_id, split.left_page),
+                (split.right_page.page_id, split.right_page),
             ], extras)?;
             
-            // CRITICAL FIX: Update cache with all modified pages
-            self.page_cache.insert(new_root_id, new_root);
-            self.page_cache.insert(split.left_page.page_id, split.left_page);
-            self.page_cache.insert(split.right_page.page_id, split.right_page);
-            
+            if new_root_id == self.root_page_id {
+                return Ok(None);
+            }
             self.root_page_id = new_root_id;
             return Ok(Some(new_root_id));
         }
@@ -178,6 +462,844 @@ impl BPlusTree {
         Ok(None)
     }
 
+    /// Give the left half of a root split a page of its own so the root page can hold
+    /// the new interior root
+    fn move_split_root_down(&mut self, split: &mut SplitResult, extras: Option<u64>) -> Result<(), DatabaseError> {
+        let left_page_id = self.allocate_page(split.left_page.page_type.clone(), extras)?;
+        split.left_page.page_id = left_page_id;
+        split.left_page.parent_page_id = Some(self.root_page_id);
+        split.left_page.mark_fully_dirty();
+        split.right_page.parent_page_id = Some(self.root_page_id);
+        if split.left_page.page_type == PageType::LeafTable {
+            split.right_page.prev_leaf_page_id = Some(left_page_id);
+        }
+        Ok(())
+    }
+
+    /// Insert `rows` in one pass instead of descending once per row. The rows are sorted
+    /// by key and merged into the leaves they route to; a leaf that overflows is repacked
+    /// into as many pages as it needs, each filled to `fill_factor` of its capacity, and
+    /// interior pages absorb the new separators the same way, so every touched page is
+    /// written once. Rows too large to store inline, and rows bound for leaves holding
+    /// overflow cells, still go through `insert`. Returns the new root if the root changed.
+    pub fn bulk_insert(
+        &mut self,
+        rows: Vec<Row>,
+        fill_factor: f64,
+        extras: Option<u64>,
+    ) -> Result<Option<PageId>, DatabaseError> {
+        let old_root_page_id = self.root_page_id;
+        let keys = rows.iter().map(|row| self.row_key(row)).collect::<Result<Vec<_>, _>>()?;
+        // The root of a fixed-root tree cannot be replaced by a new level
+        if self.fixed_root {
+            for row in rows {
+                self.insert(row, extras)?;
+            }
+            return Ok((self.root_page_id != old_root_page_id).then_some(self.root_page_id));
+        }
+
+        let mut deferred = Vec::new();
+        let mut cells = Vec::with_capacity(rows.len());
+        for (row, key) in rows.into_iter().zip(keys) {
+            let data = row.to_bytes();
+            if data.is_empty() {
+                return Err(DatabaseError::CorruptedDatabase {
+                    reason: "Empty row data".to_string(),
+                });
+            }
+            if data.len() >= overflow_threshold(self.page_size) {
+                deferred.push(row);
+            } else {
+                cells.push((key, data));
+            }
+        }
+        cells.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let fill_factor = fill_factor.clamp(0.1, 1.0);
+        let pieces = self.bulk_merge(old_root_page_id, Value::Null, cells, fill_factor, &mut deferred, extras)?;
+        if pieces.len() > 1 {
+            self.root_page_id = self.build_interior_levels(pieces, fill_factor, extras)?;
+        }
+        for row in deferred {
+            self.insert(row, extras)?;
+        }
+        Ok((self.root_page_id != old_root_page_id).then_some(self.root_page_id))
+    }
+
+    /// Merge sorted `cells` into the subtree under `page_id`, whose bound in its parent is
+    /// `upper_bound`. Returns the pages now covering that range with their upper bounds:
+    /// `page_id` first, then any pages it was repacked into, the last keeping `upper_bound`.
+    fn bulk_merge(
+        &mut self,
+        page_id: PageId,
+        upper_bound: Value,
+        cells: Vec<(Value, Vec<u8>)>,
+        fill_factor: f64,
+        deferred: &mut Vec<Row>,
+        extras: Option<u64>,
+    ) -> Result<Vec<(PageId, Value)>, DatabaseError> {
+        if cells.is_empty() {
+            return Ok(vec![(page_id, upper_bound)]);
+        }
+        let page = self.load_page(page_id, extras)?.clone();
+        match page.page_type {
+            PageType::LeafTable => {
+                // Overflow pointers cannot be moved by copying cell bytes
+                if page.slot_directory.slots.iter().any(|slot| slot.is_overflow) {
+                    for (_, data) in cells {
+                        deferred.push(Row::from_bytes(&data)?);
+                    }
+                    return Ok(vec![(page_id, upper_bound)]);
+                }
+                let mut merged = Vec::with_capacity(page.active_cell_count() + cells.len());
+                for data in Self::live_cells(&page) {
+                    merged.push((self.extract_key_from_cell(&data)?, data));
+                }
+                merged.extend(cells);
+                merged.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                if let Some(mut leaf) = self.build_leaf(page_id, &merged)? {
+                    leaf.parent_page_id = page.parent_page_id;
+                    leaf.prev_leaf_page_id = page.prev_leaf_page_id;
+                    leaf.next_leaf_page_id = page.next_leaf_page_id;
+                    self.write_pages_batch([(page_id, leaf)], extras)?;
+                    return Ok(vec![(page_id, upper_bound)]);
+                }
+
+                let chunks = self.pack(merged.iter().map(|(_, data)| data.len()), fill_factor, 1);
+                let mut page_ids = vec![page_id];
+                for _ in 1..chunks.len() {
+                    page_ids.push(self.allocate_page_id(extras)?);
+                }
+                let mut pages = Vec::with_capacity(chunks.len() + 1);
+                let mut pieces = Vec::with_capacity(chunks.len());
+                for (index, chunk) in chunks.iter().enumerate() {
+                    let mut leaf = self
+                        .build_leaf(page_ids[index], &merged[chunk.clone()])?
+                        .ok_or(DatabaseError::PageFull { page_id: page_ids[index] })?;
+                    leaf.parent_page_id = page.parent_page_id;
+                    leaf.prev_leaf_page_id = match index {
+                        0 => page.prev_leaf_page_id,
+                        _ => Some(page_ids[index - 1]),
+                    };
+                    leaf.next_leaf_page_id = match page_ids.get(index + 1) {
+                        Some(next) => Some(*next),
+                        None => page.next_leaf_page_id,
+                    };
+                    // A piece covers keys below the first key of the piece after it
+                    let bound = match chunks.get(index + 1) {
+                        Some(next) => merged[next.start].0.clone(),
+                        None => upper_bound.clone(),
+                    };
+                    pages.push((page_ids[index], leaf));
+                    pieces.push((page_ids[index], bound));
+                }
+                if let Some(successor_id) = page.next_leaf_page_id {
+                    let mut successor = self.load_page(successor_id, extras)?.clone();
+                    successor.prev_leaf_page_id = page_ids.last().copied();
+                    pages.push((successor_id, successor));
+                }
+                self.write_pages_batch(pages, extras)?;
+                Ok(pieces)
+            }
+            PageType::InteriorTable => {
+                let entries = self.interior_entries(&page)?;
+                let mut groups: Vec<Vec<(Value, Vec<u8>)>> = vec![Vec::new(); entries.len()];
+                for cell in cells {
+                    let index = entries
+                        .iter()
+                        .position(|(_, bound)| Page::compare_upper_bounds(&cell.0, bound) == std::cmp::Ordering::Less)
+                        .unwrap_or(entries.len() - 1);
+                    groups[index].push(cell);
+                }
+                let mut new_entries = Vec::with_capacity(entries.len());
+                for ((child, bound), group) in entries.iter().zip(groups) {
+                    new_entries.extend(self.bulk_merge(*child, bound.clone(), group, fill_factor, deferred, extras)?);
+                }
+                if new_entries.len() == entries.len() {
+                    // No child was repacked, so this page's entries are unchanged
+                    return Ok(vec![(page_id, upper_bound)]);
+                }
+                if self.paranoid_checks {
+                    Self::check_parent_entries(page_id, &new_entries)?;
+                }
+
+                if self.interior_entries_fit(&new_entries)? {
+                    let updated_page = self.build_interior(page_id, page.parent_page_id, &new_entries)?;
+                    self.write_pages_batch([(page_id, updated_page)], extras)?;
+                    return Ok(vec![(page_id, upper_bound)]);
+                }
+                let chunks = self.pack(
+                    new_entries.iter().map(|(_, bound)| 12 + bound.to_bytes().len()),
+                    fill_factor,
+                    2,
+                );
+                let mut pages = Vec::with_capacity(chunks.len());
+                let mut pieces = Vec::with_capacity(chunks.len());
+                for (index, chunk) in chunks.iter().enumerate() {
+                    let chunk_page_id = match index {
+                        0 => page_id,
+                        _ => self.allocate_page_id(extras)?,
+                    };
+                    let chunk_entries = &new_entries[chunk.clone()];
+                    pages.push((chunk_page_id, self.build_interior(chunk_page_id, page.parent_page_id, chunk_entries)?));
+                    // Like a split, a piece is bounded by the last of its own entries
+                    let bound = match index + 1 < chunks.len() {
+                        true => chunk_entries[chunk_entries.len() - 1].1.clone(),
+                        false => upper_bound.clone(),
+                    };
+                    pieces.push((chunk_page_id, bound));
+                }
+                self.write_pages_batch(pages, extras)?;
+                Ok(pieces)
+            }
+            other => Err(DatabaseError::CorruptedPage {
+                page_id,
+                reason: format!("{:?} page where a B+ tree page was expected", other),
+            }),
+        }
+    }
+
+    /// Stack interior pages over `pieces`, the root's replacements from `bulk_merge`, until
+    /// a single page covers them all, and return that page as the new root
+    fn build_interior_levels(
+        &mut self,
+        mut pieces: Vec<(PageId, Value)>,
+        fill_factor: f64,
+        extras: Option<u64>,
+    ) -> Result<PageId, DatabaseError> {
+        while pieces.len() > 1 {
+            let chunks = self.pack(
+                pieces.iter().map(|(_, bound)| 12 + bound.to_bytes().len()),
+                fill_factor,
+                2,
+            );
+            let mut pages = Vec::with_capacity(chunks.len());
+            let mut level = Vec::with_capacity(chunks.len());
+            for chunk in chunks {
+                let level_page_id = self.allocate_page_id(extras)?;
+                let chunk_entries = &pieces[chunk];
+                pages.push((level_page_id, self.build_interior(level_page_id, None, chunk_entries)?));
+                level.push((level_page_id, chunk_entries[chunk_entries.len() - 1].1.clone()));
+            }
+            self.write_pages_batch(pages, extras)?;
+            pieces = level;
+        }
+        Ok(pieces[0].0)
+    }
+
+    /// An interior page holding `entries` in order
+    fn build_interior(
+        &self,
+        page_id: PageId,
+        parent_page_id: Option<PageId>,
+        entries: &[(PageId, Value)],
+    ) -> Result<Page, DatabaseError> {
+        let mut page = Page::new_with_size(page_id, PageType::InteriorTable, self.page_size);
+        page.parent_page_id = parent_page_id;
+        for (child, upper_bound) in entries {
+            let entry_data = self.create_interior_entry(upper_bound, *child)?;
+            page.insert_cell(&entry_data, None)?;
+        }
+        Ok(page)
+    }
+
+    /// Group consecutive cells of the given sizes into pages filled to `fill_factor` of
+    /// their cell area, with at least `min_cells` per page while cells remain
+    fn pack(&self, cell_sizes: impl Iterator<Item = usize>, fill_factor: f64, min_cells: usize) -> Vec<Range<usize>> {
+        let target = ((self.page_size - PAGE_HEADER_SIZE) as f64 * fill_factor) as usize;
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        let mut used = 0;
+        let mut end = 0;
+        for size in cell_sizes {
+            let cost = size + SLOT_DIRECTORY_ENTRY_SIZE;
+            if end - start >= min_cells && used + cost > target {
+                chunks.push(start..end);
+                start = end;
+                used = 0;
+            }
+            used += cost;
+            end += 1;
+        }
+        if end > start {
+            chunks.push(start..end);
+        }
+        chunks
+    }
+
+    /// Descend along the first child of each interior page to the leftmost leaf
+    pub fn first_leaf_page_id(&mut self, extras: Option<u64>) -> Result<PageId, DatabaseError> {
+        let mut page_id = self.root_page_id;
+        loop {
+            let page = self.load_page(page_id, extras)?.clone();
+            match page.page_type {
+                PageType::LeafTable => return Ok(page_id),
+                PageType::InteriorTable => {
+                    page_id = self
+                        .interior_entries(&page)?
+                        .first()
+                        .map(|(child, _)| *child)
+                        .ok_or(DatabaseError::CorruptedPage {
+                            page_id,
+                            reason: "Interior page has no children".to_string(),
+                        })?;
+                }
+                _ => {
+                    return Err(DatabaseError::CorruptedPage {
+                        page_id,
+                        reason: "Invalid page type in B+ tree".to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Descend along the last child of each interior page to the rightmost leaf
+    pub fn last_leaf_page_id(&mut self, extras: Option<u64>) -> Result<PageId, DatabaseError> {
+        let mut page_id = self.root_page_id;
+        loop {
+            let page = self.load_page(page_id, extras)?.clone();
+            match page.page_type {
+                PageType::LeafTable => return Ok(page_id),
+                PageType::InteriorTable => {
+                    page_id = self
+                        .interior_entries(&page)?
+                        .last()
+                        .map(|(child, _)| *child)
+                        .ok_or(DatabaseError::CorruptedPage {
+                            page_id,
+                            reason: "Interior page has no children".to_string(),
+                        })?;
+                }
+                _ => {
+                    return Err(DatabaseError::CorruptedPage {
+                        page_id,
+                        reason: "Invalid page type in B+ tree".to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Every page in the tree, interior pages before their children
+    pub fn page_ids(&mut self, extras: Option<u64>) -> Result<Vec<PageId>, DatabaseError> {
+        let mut page_ids = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![self.root_page_id];
+        while let Some(page_id) = stack.pop() {
+            if !visited.insert(page_id) {
+                return Err(DatabaseError::CorruptedPage {
+                    page_id,
+                    reason: "Page is reachable twice in B+ tree".to_string(),
+                });
+            }
+            page_ids.push(page_id);
+            let page = self.load_page(page_id, extras)?.clone();
+            if page.page_type == PageType::InteriorTable {
+                let children = self.interior_entries(&page)?;
+                stack.extend(children.iter().rev().map(|(child, _)| *child));
+            }
+        }
+        Ok(page_ids)
+    }
+
+    /// Page ids visited while routing `key` from the root down to its leaf
+    pub fn trace_key(&mut self, key: &Value, extras: Option<u64>) -> Result<Vec<PageId>, DatabaseError> {
+        let mut path = Vec::new();
+        let mut page_id = self.root_page_id;
+        loop {
+            if path.contains(&page_id) {
+                return Err(DatabaseError::CorruptedPage {
+                    page_id,
+                    reason: "Cycle detected while routing key".to_string(),
+                });
+            }
+            path.push(page_id);
+            let page = self.load_page(page_id, extras)?.clone();
+            match page.page_type {
+                PageType::LeafTable => return Ok(path),
+                PageType::InteriorTable => page_id = self.find_child_page(&page, key)?,
+                _ => {
+                    return Err(DatabaseError::CorruptedPage {
+                        page_id,
+                        reason: "Invalid page type in B+ tree".to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Point lookup: route `key` to its leaf and return the first row stored under it
+    pub fn search(&mut self, key: &Value, extras: Option<u64>) -> Result<Option<Row>, DatabaseError> {
+        let leaf_page_id = *self
+            .trace_key(key, extras)?
+            .last()
+            .expect("trace_key always visits the root");
+        let key_columns = self.key_columns.clone();
+        let page = self.load_page(leaf_page_id, extras)?.clone();
+        // Pages holding overflow pointers are never marked sorted, so every cell here is a row
+        if page.sorted {
+            let position = page.binary_search_key(key, |cell| Ok(Row::from_bytes(cell)?.key(&key_columns)))?;
+            return match position.found() {
+                true => Ok(Some(Row::from_bytes(page.get_cell(position.start).expect("slot was just found"))?)),
+                false => Ok(None),
+            };
+        }
+        for slot_index in 0..page.slot_directory.slots.len() {
+            if let Some(row) = self.leaf_row(&page, slot_index, extras)?
+                && row.key(&key_columns) == *key
+            {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The row in `slot_index` of a leaf page, read back from its overflow page when the
+    /// slot holds an overflow pointer, or `None` for a deleted or empty slot
+    pub fn leaf_row(&mut self, page: &Page, slot_index: usize, extras: Option<u64>) -> Result<Option<Row>, DatabaseError> {
+        let Some(cell) = page.get_cell(slot_index).filter(|cell| !cell.is_empty()) else {
+            return Ok(None);
+        };
+        if page.slot_directory.slots[slot_index].is_overflow {
+            let cell = self.read_overflow_cell(&OverflowPointer::from_bytes(cell)?, extras)?;
+            return Row::from_bytes(&cell).map(Some);
+        }
+        Row::from_bytes(cell).map(Some)
+    }
+
+    /// Every cell stored under `key` with where it lives, in leaf chain order. Keys equal
+    /// to a separator can sit on either side of it, so this descends to the leftmost leaf
+    /// that may hold `key` and walks right until the keys pass it.
+    pub fn search_cells(&mut self, key: &Value, extras: Option<u64>) -> Result<Vec<(PageId, usize, Row)>, DatabaseError> {
+        let mut path = Vec::new();
+        let mut page_id = self.root_page_id;
+        loop {
+            if path.contains(&page_id) {
+                return Err(DatabaseError::CorruptedPage {
+                    page_id,
+                    reason: "Cycle detected while routing key".to_string(),
+                });
+            }
+            path.push(page_id);
+            let page = self.load_page(page_id, extras)?.clone();
+            match page.page_type {
+                PageType::LeafTable => break,
+                PageType::InteriorTable if page.sorted => {
+                    let position = page.binary_search_key(key, |entry| self.parse_interior_entry(entry).map(|(_, bound)| bound))?;
+                    page_id = self.interior_child_at(&page, position.start)?;
+                }
+                PageType::InteriorTable => {
+                    let entries = self.interior_entries(&page)?;
+                    page_id = entries
+                        .iter()
+                        .find(|(_, upper_bound)| {
+                            Page::compare_upper_bounds(key, upper_bound) != std::cmp::Ordering::Greater
+                        })
+                        .or(entries.last())
+                        .map(|(child, _)| *child)
+                        .ok_or(DatabaseError::CorruptedPage {
+                            page_id,
+                            reason: "No valid child page found".to_string(),
+                        })?;
+                }
+                _ => {
+                    return Err(DatabaseError::CorruptedPage {
+                        page_id,
+                        reason: "Invalid page type in B+ tree".to_string(),
+                    });
+                }
+            }
+        }
+
+        let mut cells = Vec::new();
+        let mut next_page_id = Some(page_id);
+        while let Some(page_id) = next_page_id {
+            let page = self.load_page(page_id, extras)?.clone();
+            // A page in key order is read from its first cell not below `key` up to the
+            // first one above it
+            let first_slot = match page.sorted {
+                true => page.binary_search_key(key, |cell| self.extract_key_from_cell(cell))?.start,
+                false => 0,
+            };
+            let mut past_key = page.slot_directory.slots[..first_slot].iter().all(|slot| slot.length == 0);
+            for slot_index in first_slot..page.slot_directory.slots.len() {
+                let Some(row) = self.leaf_row(&page, slot_index, extras)? else {
+                    continue;
+                };
+                match row.key(&self.key_columns).total_cmp(key) {
+                    std::cmp::Ordering::Equal => {
+                        past_key = false;
+                        cells.push((page_id, slot_index, row));
+                    }
+                    std::cmp::Ordering::Less => past_key = false,
+                    std::cmp::Ordering::Greater if page.sorted => break,
+                    std::cmp::Ordering::Greater => {}
+                }
+            }
+            // Leaves after one holding only greater keys cannot hold `key` either
+            if past_key && page.active_cell_count() > 0 {
+                break;
+            }
+            next_page_id = page.next_leaf_page_id;
+        }
+        Ok(cells)
+    }
+
+    /// Every row stored under `key`, for trees that keep duplicate keys
+    pub fn search_all(&mut self, key: &Value, extras: Option<u64>) -> Result<Vec<Row>, DatabaseError> {
+        Ok(self
+            .search_cells(key, extras)?
+            .into_iter()
+            .map(|(_, _, row)| row)
+            .collect())
+    }
+
+    /// Rewrite the cell in `slot_index` of a leaf page. Returns `false` without touching
+    /// the page when the new data does not fit, or when the slot holds an overflow pointer
+    /// and the row has to be deleted and reinserted instead.
+    pub fn update_cell(
+        &mut self,
+        page_id: PageId,
+        slot_index: usize,
+        cell_data: &[u8],
+        extras: Option<u64>,
+    ) -> Result<bool, DatabaseError> {
+        let mut page = self.load_page(page_id, extras)?.clone();
+        let Some(slot) = page.slot_directory.slots.get(slot_index) else {
+            return Err(DatabaseError::InvalidSlotIndex {
+                index: slot_index,
+                max: page.slot_directory.slots.len(),
+            });
+        };
+        if slot.is_overflow {
+            return Ok(false);
+        }
+        let row_id = slot.row_id;
+        match page.update_cell(slot_index, cell_data, row_id) {
+            Ok(()) => {}
+            Err(DatabaseError::PageFull { .. }) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        self.compact_if_fragmented(&mut page)?;
+        self.write_page(page_id, page.clone(), extras)?;
+        self.cache_page(page_id, page)?;
+        Ok(true)
+    }
+
+    /// Mark the cell in `slot_index` of a leaf page as deleted. The overflow page of an
+    /// overflow cell goes back to the freelist.
+    pub fn delete_cell(
+        &mut self,
+        page_id: PageId,
+        slot_index: usize,
+        extras: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        let mut page = self.load_page(page_id, extras)?.clone();
+        let overflow_pointer = page
+            .slot_directory
+            .slots
+            .get(slot_index)
+            .and_then(|slot| slot.overflow_pointer.clone());
+        page.delete_cell(slot_index)?;
+        self.compact_if_fragmented(&mut page)?;
+        self.write_page(page_id, page.clone(), extras)?;
+        self.cache_page(page_id, page)?;
+        if let Some(pointer) = overflow_pointer {
+            self.free_page(pointer.page_id, extras)?;
+        }
+        Ok(())
+    }
+
+    /// Compact `page` in place when the compaction threshold is set and exceeded
+    fn compact_if_fragmented(&self, page: &mut Page) -> Result<(), DatabaseError> {
+        match self.compaction_threshold {
+            Some(threshold) if page.get_fragmentation_ratio() > threshold => page.compact_with(false),
+            _ => Ok(()),
+        }
+    }
+
+    /// Compact a page so space freed by deleted cells can be reused, dropping the deleted
+    /// slots past its last live cell. Slot indices from before any of the page's deletes
+    /// are stale afterwards.
+    pub fn compact_page(&mut self, page_id: PageId, extras: Option<u64>) -> Result<(), DatabaseError> {
+        let mut page = self.load_page(page_id, extras)?.clone();
+        page.compact_with(true)?;
+        self.write_page(page_id, page.clone(), extras)?;
+        self.cache_page(page_id, page)?;
+        Ok(())
+    }
+
+    /// Merge leaves left underfull by deletions into a sibling, or move cells over from
+    /// a sibling when the two do not fit in one page, then drop root levels left with a
+    /// single child. Freed pages go back to the freelist. Returns the new root if the
+    /// root changed.
+    pub fn rebalance(&mut self, extras: Option<u64>) -> Result<Option<PageId>, DatabaseError> {
+        let old_root_page_id = self.root_page_id;
+        self.rebalance_children(old_root_page_id, extras)?;
+        loop {
+            let root = self.load_page(self.root_page_id, extras)?.clone();
+            if root.page_type != PageType::InteriorTable {
+                break;
+            }
+            let entries = self.interior_entries(&root)?;
+            let [(only_child, _)] = entries.as_slice() else {
+                break;
+            };
+            let mut child = self.load_page(*only_child, extras)?.clone();
+            child.parent_page_id = None;
+            if self.fixed_root {
+                // The only child has no siblings, so no leaf links point at it
+                child.page_id = root.page_id;
+                child.mark_fully_dirty();
+                self.write_pages_batch([(root.page_id, child)], extras)?;
+                self.free_page(*only_child, extras)?;
+                continue;
+            }
+            self.write_pages_batch([(child.page_id, child)], extras)?;
+            self.free_page(root.page_id, extras)?;
+            self.root_page_id = *only_child;
+        }
+        Ok((self.root_page_id != old_root_page_id).then_some(self.root_page_id))
+    }
+
+    /// Rebalance the subtrees below an interior page, then its own underfull children
+    /// against their siblings, rewriting the page if its entries changed
+    fn rebalance_children(&mut self, page_id: PageId, extras: Option<u64>) -> Result<(), DatabaseError> {
+        let page = self.load_page(page_id, extras)?.clone();
+        if page.page_type != PageType::InteriorTable {
+            return Ok(());
+        }
+        let mut entries = self.interior_entries(&page)?;
+        for (child, _) in &entries {
+            self.rebalance_children(*child, extras)?;
+        }
+
+        let mut changed = false;
+        let mut index = 0;
+        while index < entries.len() && entries.len() > 1 {
+            let child = self.load_page(entries[index].0, extras)?.clone();
+            if !self.is_underfull(&child)? {
+                index += 1;
+                continue;
+            }
+            // Pair with the right sibling, or the left one for the last child
+            let left = if index + 1 < entries.len() { index } else { index - 1 };
+            let outcome = match child.page_type {
+                PageType::LeafTable => self.rebalance_leaves(page_id, &mut entries, left, extras)?,
+                _ => self.merge_interior_pages(page_id, &mut entries, left, extras)?,
+            };
+            match outcome {
+                Rebalanced::Merged => {
+                    changed = true;
+                    // The merged page may still be underfull
+                    index = left;
+                }
+                Rebalanced::Redistributed => {
+                    changed = true;
+                    index += 1;
+                }
+                Rebalanced::Unchanged => index += 1,
+            }
+        }
+
+        if changed {
+            let mut updated_page = Page::new_with_size(page_id, PageType::InteriorTable, self.page_size);
+            updated_page.parent_page_id = page.parent_page_id;
+            for (child, upper_bound) in &entries {
+                let entry_data = self.create_interior_entry(upper_bound, *child)?;
+                updated_page.insert_cell(&entry_data, None)?;
+            }
+            self.write_pages_batch([(page_id, updated_page)], extras)?;
+        }
+        Ok(())
+    }
+
+    /// A leaf is underfull below `order / 2` cells or a quarter of its page in cell
+    /// bytes, since rows vary in size; an interior page below `order / 2` children
+    fn is_underfull(&self, page: &Page) -> Result<bool, DatabaseError> {
+        Ok(match page.page_type {
+            PageType::LeafTable => {
+                let live_bytes: usize = Self::live_cells(page).iter().map(Vec::len).sum();
+                page.active_cell_count() < self.order / 2 || live_bytes < self.page_size / 4
+            }
+            _ => self.interior_entries(page)?.len() < self.order / 2,
+        })
+    }
+
+    /// Merge the leaves at `left` and `left + 1` in `entries` into the left one when
+    /// their cells fit in one page, otherwise split the cells evenly between them
+    fn rebalance_leaves(
+        &mut self,
+        parent_page_id: PageId,
+        entries: &mut Vec<(PageId, Value)>,
+        left: usize,
+        extras: Option<u64>,
+    ) -> Result<Rebalanced, DatabaseError> {
+        let left_page = self.load_page(entries[left].0, extras)?.clone();
+        let right_page = self.load_page(entries[left + 1].0, extras)?.clone();
+        if left_page.next_leaf_page_id != Some(right_page.page_id)
+            || right_page.prev_leaf_page_id != Some(left_page.page_id)
+        {
+            return Err(DatabaseError::CorruptedPage {
+                page_id: parent_page_id,
+                reason: format!(
+                    "Sibling leaves {} and {} are not linked to each other",
+                    left_page.page_id, right_page.page_id
+                ),
+            });
+        }
+
+        // Overflow pointers cannot be moved by copying cell bytes
+        if [&left_page, &right_page]
+            .iter()
+            .any(|page| page.slot_directory.slots.iter().any(|slot| slot.is_overflow))
+        {
+            return Ok(Rebalanced::Unchanged);
+        }
+
+        let mut cells = Vec::new();
+        for data in Self::live_cells(&left_page).into_iter().chain(Self::live_cells(&right_page)) {
+            cells.push((self.extract_key_from_cell(&data)?, data));
+        }
+        cells.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        if let Some(mut merged) = self.build_leaf(left_page.page_id, &cells)? {
+            merged.parent_page_id = Some(parent_page_id);
+            merged.prev_leaf_page_id = left_page.prev_leaf_page_id;
+            merged.next_leaf_page_id = right_page.next_leaf_page_id;
+            if let Some(successor_id) = right_page.next_leaf_page_id {
+                let mut successor = self.load_page(successor_id, extras)?.clone();
+                successor.prev_leaf_page_id = Some(left_page.page_id);
+                self.write_pages_batch([(successor_id, successor)], extras)?;
+            }
+            self.write_pages_batch([(left_page.page_id, merged)], extras)?;
+            self.free_page(right_page.page_id, extras)?;
+            // The merged page now covers everything below the right page's bound
+            let (_, upper_bound) = entries.remove(left + 1);
+            entries[left].1 = upper_bound;
+            return Ok(Rebalanced::Merged);
+        }
+
+        if cells.len() < 2 {
+            return Ok(Rebalanced::Unchanged);
+        }
+        // Split at the first cell reaching half the bytes, keeping both sides non-empty
+        let total_bytes: usize = cells.iter().map(|(_, data)| data.len()).sum();
+        let mut split_point = 0;
+        let mut left_bytes = 0;
+        while split_point < cells.len() && left_bytes * 2 < total_bytes {
+            left_bytes += cells[split_point].1.len();
+            split_point += 1;
+        }
+        let split_point = split_point.clamp(1, cells.len() - 1);
+        if split_point == left_page.active_cell_count() {
+            return Ok(Rebalanced::Unchanged);
+        }
+        let separator_key = cells[split_point].0.clone();
+        let mut new_entries = entries.clone();
+        new_entries[left].1 = separator_key;
+        let (Some(mut new_left), Some(mut new_right)) = (
+            self.build_leaf(left_page.page_id, &cells[..split_point])?,
+            self.build_leaf(right_page.page_id, &cells[split_point..])?,
+        ) else {
+            return Ok(Rebalanced::Unchanged);
+        };
+        if !self.interior_entries_fit(&new_entries)? {
+            return Ok(Rebalanced::Unchanged);
+        }
+        new_left.parent_page_id = Some(parent_page_id);
+        new_left.prev_leaf_page_id = left_page.prev_leaf_page_id;
+        new_left.next_leaf_page_id = Some(right_page.page_id);
+        new_right.parent_page_id = Some(parent_page_id);
+        new_right.prev_leaf_page_id = Some(left_page.page_id);
+        new_right.next_leaf_page_id = right_page.next_leaf_page_id;
+        self.write_pages_batch([(left_page.page_id, new_left), (right_page.page_id, new_right)], extras)?;
+        *entries = new_entries;
+        Ok(Rebalanced::Redistributed)
+    }
+
+    /// Merge the interior pages at `left` and `left + 1` in `entries` into the left one
+    /// when their entries fit in one page
+    fn merge_interior_pages(
+        &mut self,
+        parent_page_id: PageId,
+        entries: &mut Vec<(PageId, Value)>,
+        left: usize,
+        extras: Option<u64>,
+    ) -> Result<Rebalanced, DatabaseError> {
+        let left_page = self.load_page(entries[left].0, extras)?.clone();
+        let right_page = self.load_page(entries[left + 1].0, extras)?.clone();
+        let mut merged_entries = self.interior_entries(&left_page)?;
+        let right_entries = self.interior_entries(&right_page)?;
+        // Keys between the left page's last bound and its bound in the parent were routed
+        // to its last child, so that child takes the parent's bound
+        if let Some(last) = merged_entries.last_mut() {
+            last.1 = entries[left].1.clone();
+        }
+        merged_entries.extend(right_entries.iter().cloned());
+        if !self.interior_entries_fit(&merged_entries)? {
+            return Ok(Rebalanced::Unchanged);
+        }
+
+        let mut merged = Page::new_with_size(left_page.page_id, PageType::InteriorTable, self.page_size);
+        merged.parent_page_id = Some(parent_page_id);
+        for (child, upper_bound) in &merged_entries {
+            let entry_data = self.create_interior_entry(upper_bound, *child)?;
+            merged.insert_cell(&entry_data, None)?;
+        }
+        for (child_id, _) in &right_entries {
+            let mut child = self.load_page(*child_id, extras)?.clone();
+            child.parent_page_id = Some(left_page.page_id);
+            self.write_pages_batch([(*child_id, child)], extras)?;
+        }
+        self.write_pages_batch([(left_page.page_id, merged)], extras)?;
+        self.free_page(right_page.page_id, extras)?;
+        let (_, upper_bound) = entries.remove(left + 1);
+        entries[left].1 = upper_bound;
+        // Children that had different parents are siblings now and may merge in turn
+        self.rebalance_children(left_page.page_id, extras)?;
+        Ok(Rebalanced::Merged)
+    }
+
+    /// Bytes of every cell on a page that is not deleted, in slot order
+    fn live_cells(page: &Page) -> Vec<Vec<u8>> {
+        (0..page.slot_directory.slots.len())
+            .filter_map(|slot_index| page.get_cell(slot_index))
+            .filter(|data| !data.is_empty())
+            .map(<[u8]>::to_vec)
+            .collect()
+    }
+
+    /// A fresh leaf holding `cells` in order, or `None` if they do not fit in one page
+    fn build_leaf(&self, page_id: PageId, cells: &[(Value, Vec<u8>)]) -> Result<Option<Page>, DatabaseError> {
+        let mut page = Page::new_with_size(page_id, PageType::LeafTable, self.page_size);
+        for (_, data) in cells {
+            if !page.can_fit(data.len()) {
+                return Ok(None);
+            }
+            page.insert_cell(data, None)?;
+        }
+        Ok(Some(page))
+    }
+
+    /// Return a page the tree no longer references to the database's freelist. A tree
+    /// outside a database file has no freelist, so there the page is only forgotten.
+    fn free_page(&mut self, page_id: PageId, extras: Option<u64>) -> Result<(), DatabaseError> {
+        self.page_cache.remove(&page_id);
+        if extras.is_none() {
+            return Ok(());
+        }
+        if let Some(journal) = &self.journal {
+            journal.save_page(&mut self.file, page_id)?;
+        }
+        let mut header = BambangHeader::read_from(&mut self.file)?;
+        freelist::push_free_page(&mut self.file, &mut header, page_id)?;
+        header.write_to(&mut self.file)?;
+        Ok(())
+    }
+
     fn create_interior_entry(
         &self,
         key: &Value,
@@ -198,21 +1320,27 @@ impl BPlusTree {
         cell: Cell,
         extras: Option<u64>,
     ) -> Result<Option<SplitResult>, DatabaseError> {
-        let page = self.load_page(page_id, extras)?.clone();
+        let page_type = self.load_page(page_id, extras)?.page_type.clone();
         
-        match page.page_type {
+        match page_type {
             PageType::LeafTable => {
-                let mut updated_page = page;
-                if updated_page.can_fit(cell.data.len()) {
+                // A cell bound for an overflow page only needs room for its pointer here.
+                // Split only when even compacting away deleted cells leaves too little room.
+                let cost = StorageCost::for_cell_with_page_size(cell.data.len(), self.page_size);
+                let page = self.page_cache.peek(&page_id).expect("page was just loaded");
+                if page.would_fit(&cost) || page.fits_after_compaction(&cost) {
+                    // Modified in place rather than copied, as most inserts do not split
+                    let mut updated_page = self.take_page(page_id, extras)?;
+                    if !updated_page.would_fit(&cost) {
+                        updated_page.compact_with(true)?;
+                    }
                     if let Some(overflow_page_id) = cell.overflow_page_id {
                         updated_page.insert_cell_with_overflow(
                             &cell.data,
                             None,
                             Some(overflow_page_id),
                         )?;
-                        self.write_page(page_id, updated_page.clone(), extras)?;
-                        // CRITICAL FIX: Update cache
-                        self.page_cache.insert(page_id, updated_page);
+                        self.store_page(page_id, updated_page, extras)?;
                     } else {
                         if updated_page.needs_overflow(cell.data.len()) {
                             let overflow_id = self.allocate_overflow_page(&cell.data, extras)?;
@@ -221,49 +1349,77 @@ impl BPlusTree {
                                 None,
                                 Some(overflow_id),
                             )?;
-                            self.write_page(page_id, updated_page.clone(), extras)?;
-                            // CRITICAL FIX: Update cache
-                            self.page_cache.insert(page_id, updated_page);
+                            self.store_page(page_id, updated_page, extras)?;
                         } else {
                             // Use optimized insertion for regular cells
-                            self.insert_with_reduced_writes(page_id, updated_page, &cell.data, extras)?;
+                            self.insert_with_reduced_writes(page_id, updated_page, &key, &cell.data, extras)?;
                         }
                     }
                     Ok(None)
                 } else {
-                    let split_result = self.split_leaf_page(updated_page, key, cell, extras)?;
+                    let full_page = page.clone();
+                    let old_next_leaf = full_page.next_leaf_page_id;
+                    let split_result = self.split_leaf_page(full_page, key, cell, extras)?;
+                    if self.paranoid_checks {
+                        self.check_split(&split_result, old_next_leaf, extras)?;
+                    }
                     Ok(Some(split_result))
                 }
             }
             PageType::InteriorTable => {
-                let child_page_id = self.find_child_page(&page, &key)?;
+                let page = self.page_cache.peek(&page_id).expect("page was just loaded");
+                let child_page_id = self.find_child_page(page, &key)?;
                 let split_result = self.insert_recursive(child_page_id, key, cell, extras)?;
                 if let Some(split) = split_result {
-                    let new_entry_data =
-                        self.create_interior_entry(&split.separator_key, split.right_page.page_id)?;
-                    let mut updated_page = page;
-                    if !updated_page.can_fit(new_entry_data.len()) {
+                    // The child's split leaves this page as it was, so it is reloaded
+                    // rather than copied on every descent
+                    let page = self.load_page(page_id, extras)?.clone();
+                    // The split child keeps keys below the separator; its old upper bound
+                    // moves to the new right sibling
+                    let mut entries = self.interior_entries(&page)?;
+                    let index = entries
+                        .iter()
+                        .position(|(child, _)| *child == split.left_page.page_id)
+                        .ok_or(DatabaseError::CorruptedPage {
+                            page_id,
+                            reason: "Split child is not referenced by its parent".to_string(),
+                        })?;
+                    let upper_bound =
+                        std::mem::replace(&mut entries[index].1, split.separator_key.clone());
+                    entries.insert(index + 1, (split.right_page.page_id, upper_bound));
+                    if self.paranoid_checks {
+                        Self::check_parent_entries(page_id, &entries)?;
+                    }
+
+                    self.write_pages_batch([
+                        (split.left_page.page_id, split.left_page),
+                        (split.right_page.page_id, split.right_page),
+                    ], extras)?;
+
+                    let mut updated_page = Page::new_with_size(page_id, PageType::InteriorTable, self.page_size);
+                    updated_page.parent_page_id = page.parent_page_id;
+                    if self.interior_entries_fit(&entries)? {
+                        for (child, upper_bound) in &entries {
+                            let entry_data = self.create_interior_entry(upper_bound, *child)?;
+                            updated_page.insert_cell(&entry_data, None)?;
+                        }
+                        self.write_pages_batch([(page_id, updated_page)], extras)?;
+                        Ok(None)
+                    } else {
                         let interior_split =
-                            self.split_interior_page(updated_page, new_entry_data, extras)?;
+                            self.split_interior_page(updated_page, entries, extras)?;
+                        if self.paranoid_checks {
+                            self.check_split(&interior_split, None, extras)?;
+                        }
                         Ok(Some(interior_split))
-                    } else {
-                        updated_page.insert_cell(&new_entry_data, None)?;
-                        
-                        // Batch write all pages to reduce I/O overhead
-                        self.write_pages_batch(&[
-                            (page_id, updated_page),
-                            (split.left_page.page_id, split.left_page),
-                            (split.right_page.page_id, split.right_page),
-                        ], extras)?;
-                        
-                        Ok(None)
                     }
                 } else {
                     Ok(None)
                 }
             }
-            _ => Err(DatabaseError::CorruptedDatabase {
-                reason: "Invalid page type for B+ tree operation".to_string(),
+            other => Err(DatabaseError::CorruptedPage {
+                page_id,
+                reason: format!("{:?} page where a B+ tree page was expected", other),
             }),
         }
     }
@@ -272,71 +1428,97 @@ impl BPlusTree {
         &mut self,
         mut full_page: Page,
         key: Value,
-        cell: Cell,
+        mut cell: Cell,
         extras: Option<u64>,
     ) -> Result<SplitResult, DatabaseError> {
+        // A cell too big to share a leaf would leave one side of any split over full
+        if cell.overflow_page_id.is_none() && full_page.needs_overflow(cell.data.len()) {
+            cell.overflow_page_id = Some(self.allocate_overflow_page(&cell.data, extras)?);
+        }
         let new_page_id = self.allocate_page(PageType::LeafTable, extras)?;
-        let mut right_page = Page::new(new_page_id, PageType::LeafTable);
+        let mut right_page = Page::new_with_size(new_page_id, PageType::LeafTable, self.page_size);
         let mut all_cells = Vec::new();
         
         // Collect all existing cells from the full page
         for i in 0..full_page.slot_directory.slots.len() {
-            if let Some(cell_data) = full_page.get_cell(i) {
-                if !cell_data.is_empty() {  // Skip empty cells
-                    match self.extract_key_from_cell(cell_data) {
-                        Ok(extracted_key) => {
-                            all_cells.push((extracted_key, cell_data.to_vec()));
-                        }
-                        Err(_) => {
-                            // Skip corrupted cells but don't fail the entire operation
-                            continue;
-                        }
+            if let Some(cell_data) = full_page.get_cell(i)
+                && !cell_data.is_empty()
+            {
+                // An overflow pointer has no key of its own, so the row is read back to
+                // find where it sorts; the pointer itself moves unchanged
+                let existing = if full_page.slot_directory.slots[i].is_overflow {
+                    let pointer = OverflowPointer::from_bytes(cell_data)?;
+                    Cell {
+                        data: self.read_overflow_cell(&pointer, extras)?,
+                        overflow_page_id: Some(pointer.page_id),
+                    }
+                } else {
+                    Cell {
+                        data: cell_data.to_vec(),
+                        overflow_page_id: None,
+                    }
+                };
+                // Skip empty cells
+                match self.extract_key_from_cell(&existing.data) {
+                    Ok(extracted_key) => {
+                        all_cells.push((extracted_key, existing));
+                    }
+                    Err(_) => {
+                        // Skip corrupted cells but don't fail the entire operation
+                        continue;
                     }
                 }
             }
         }
         
-        // Add the new cell
-        all_cells.push((key, cell.data));
+        // Sort all cells by key and add the new one after any equal to it
+        all_cells.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let incoming = all_cells.partition_point(|(existing_key, _)| existing_key.total_cmp(&key).is_le());
+        all_cells.insert(incoming, (key, cell));
         
-        // Sort all cells by key
-        all_cells.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
-        
-        // Ensure we have at least one cell to split
-        if all_cells.is_empty() {
-            return Err(DatabaseError::CorruptedDatabase {
-                reason: "No cells to split".to_string(),
-            });
-        }
-        
-        let split_point = all_cells.len() / 2;
+        let costs: Vec<usize> = all_cells
+            .iter()
+            .map(|(_, cell)| match cell.overflow_page_id {
+                Some(_) => OverflowPointer::SERIALIZED_SIZE + SLOT_DIRECTORY_ENTRY_SIZE,
+                None => StorageCost::for_cell_with_page_size(cell.data.len(), self.page_size).page_bytes(),
+            })
+            .collect();
+        let page_id = full_page.page_id;
+        let split_failed = |source| DatabaseError::SplitFailed {
+            page_id,
+            source: Box::new(source),
+        };
+        let appending = incoming == all_cells.len() - 1 && full_page.next_leaf_page_id.is_none();
+        let prepending = incoming == 0 && full_page.prev_leaf_page_id.is_none();
+        let split_point = self
+            .plan_leaf_split(&costs, appending, prepending)
+            .ok_or_else(|| split_failed(DatabaseError::PageFull { page_id }))?;
         
-        // Clear the left page and rebuild it
+        // Clear the left page and rebuild it, in key order even if it was not before
+        full_page.mark_fully_dirty();
         full_page.slot_directory.slots.clear();
-        full_page.free_space_offset = PAGE_SIZE as u16;
+        full_page.free_space_offset = self.page_size as u32;
         full_page.cell_count = 0;
+        full_page.sorted = true;
         
-        // Insert cells into left page
-        for (_, cell_data) in &all_cells[..split_point] {
-            if let Err(e) = full_page.insert_cell(cell_data, None) {
-                return Err(DatabaseError::CorruptedDatabase {
-                    reason: format!("Failed to insert cell into left page: {}", e),
-                });
-            }
-        }
-        
-        // Insert cells into right page
-        for (_, cell_data) in &all_cells[split_point..] {
-            if let Err(e) = right_page.insert_cell(cell_data, None) {
-                return Err(DatabaseError::CorruptedDatabase {
-                    reason: format!("Failed to insert cell into right page: {}", e),
-                });
+        for (index, (_, cell)) in all_cells.iter().enumerate() {
+            let page = if index < split_point { &mut full_page } else { &mut right_page };
+            match cell.overflow_page_id {
+                Some(overflow_page_id) => page.insert_cell_with_overflow(&cell.data, None, Some(overflow_page_id)),
+                None => page.insert_cell(&cell.data, None),
             }
+            .map_err(split_failed)?;
         }
         
         // Update leaf page linkage
         right_page.next_leaf_page_id = full_page.next_leaf_page_id;
+        right_page.prev_leaf_page_id = Some(full_page.page_id);
         full_page.next_leaf_page_id = Some(new_page_id);
+        if let Some(successor_id) = right_page.next_leaf_page_id {
+            let mut successor = self.load_page(successor_id, extras)?.clone();
+            successor.prev_leaf_page_id = Some(new_page_id);
+            self.write_page(successor_id, successor, extras)?;
+        }
         
         let separator_key = all_cells[split_point].0.clone();
         Ok(SplitResult {
@@ -346,101 +1528,277 @@ impl BPlusTree {
         })
     }
 
+    /// Where to divide a splitting leaf's cells, given the page bytes each takes in key
+    /// order: the point that leaves both pages within a leaf's cell area and the emptier
+    /// of the two as full as possible. A cell added past either end of the leaf chain gets
+    /// a page of its own instead and the old cells stay together, so keys inserted in
+    /// order leave packed leaves behind. `None` if no point fits both pages.
+    fn plan_leaf_split(&self, costs: &[usize], appending: bool, prepending: bool) -> Option<usize> {
+        let capacity = self.page_size - PAGE_HEADER_SIZE;
+        let total: usize = costs.iter().sum();
+        let fits = |split: usize| {
+            let left: usize = costs[..split].iter().sum();
+            left <= capacity && total - left <= capacity
+        };
+        if appending && costs.len() > 1 && fits(costs.len() - 1) {
+            return Some(costs.len() - 1);
+        }
+        if prepending && costs.len() > 1 && fits(1) {
+            return Some(1);
+        }
+        let mut left = 0;
+        let mut best: Option<(usize, usize)> = None;
+        for split in 1..costs.len() {
+            left += costs[split - 1];
+            let right = total - left;
+            if left > capacity || right > capacity {
+                continue;
+            }
+            let emptier = left.min(right);
+            if best.is_none_or(|(_, best_emptier)| emptier > best_emptier) {
+                best = Some((split, emptier));
+            }
+        }
+        best.map(|(split, _)| split)
+    }
+
+    /// The cell `pointer` stands for, read back from its overflow page
+    fn read_overflow_cell(&mut self, pointer: &OverflowPointer, extras: Option<u64>) -> Result<Vec<u8>, DatabaseError> {
+        let overflow_page = self.load_page(pointer.page_id, extras)?;
+        overflow_page
+            .get_cell(0)
+            .filter(|_| overflow_page.page_type == PageType::OverflowPage)
+            .and_then(|cell| cell.get(..pointer.total_size as usize))
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| DatabaseError::CorruptedPage {
+                page_id: pointer.page_id,
+                reason: "Overflow page does not hold the cell pointed at it".to_string(),
+            })
+    }
+
+    /// Paranoid check of a split: keys on the left are at most the separator, keys on
+    /// the right at least it, and a leaf split links left <-> right <-> the old successor
+    fn check_split(
+        &mut self,
+        split: &SplitResult,
+        old_next_leaf: Option<PageId>,
+        extras: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        let (left, right) = (&split.left_page, &split.right_page);
+        let violated = |details: String| DatabaseError::InternalInvariant {
+            details: format!(
+                "{:?} split of page {} into {} and {} (separator {}): {}",
+                left.page_type, left.page_id, left.page_id, right.page_id, split.separator_key, details
+            ),
+        };
+        let separator = &split.separator_key;
+        let above = |key: &Value| Page::compare_upper_bounds(key, separator) == std::cmp::Ordering::Greater;
+        let below = |key: &Value| Page::compare_upper_bounds(key, separator) == std::cmp::Ordering::Less;
+        if let Some(key) = self.page_keys(left, extras)?.into_iter().find(|key| above(key)) {
+            return Err(violated(format!("key {} on the left page is above the separator", key)));
+        }
+        if let Some(key) = self.page_keys(right, extras)?.into_iter().find(|key| below(key)) {
+            return Err(violated(format!("key {} on the right page is below the separator", key)));
+        }
+
+        if left.page_type == PageType::LeafTable {
+            if left.next_leaf_page_id != Some(right.page_id) {
+                return Err(violated(format!(
+                    "left page links to {:?} instead of the new right page",
+                    left.next_leaf_page_id
+                )));
+            }
+            if right.next_leaf_page_id != old_next_leaf {
+                return Err(violated(format!(
+                    "right page links to {:?} but the split page used to link to {:?}",
+                    right.next_leaf_page_id, old_next_leaf
+                )));
+            }
+            if right.prev_leaf_page_id != Some(left.page_id) {
+                return Err(violated(format!(
+                    "right page links back to {:?} instead of the left page",
+                    right.prev_leaf_page_id
+                )));
+            }
+            if let Some(next_id) = old_next_leaf {
+                let next = self.load_page(next_id, extras)?;
+                if next.page_type != PageType::LeafTable {
+                    return Err(violated(format!("the leaf chain continues into non-leaf page {}", next_id)));
+                }
+                if next.prev_leaf_page_id != Some(right.page_id) {
+                    return Err(violated(format!(
+                        "page {} links back to {:?} instead of the new right page",
+                        next_id, next.prev_leaf_page_id
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys of a page's live cells in slot order: upper bounds on an interior page, and on
+    /// a leaf the keys of its rows, read back from overflow pages where they were moved
+    fn page_keys(&mut self, page: &Page, extras: Option<u64>) -> Result<Vec<Value>, DatabaseError> {
+        let mut keys = Vec::new();
+        for (slot_index, slot) in page.slot_directory.slots.iter().enumerate() {
+            let Some(cell) = page.get_cell(slot_index) else {
+                continue;
+            };
+            let key = match page.page_type {
+                PageType::InteriorTable => self.parse_interior_entry(cell)?.1,
+                _ if slot.is_overflow => {
+                    let row = self.read_overflow_cell(&OverflowPointer::from_bytes(cell)?, extras)?;
+                    self.extract_key_from_cell(&row)?
+                }
+                _ => self.extract_key_from_cell(cell)?,
+            };
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    /// Paranoid check of an interior page's entries after a child split: upper bounds
+    /// ascend, with the unbounded child last, and no child is referenced twice
+    fn check_parent_entries(page_id: PageId, entries: &[(PageId, Value)]) -> Result<(), DatabaseError> {
+        let mut children = HashSet::new();
+        for (index, (child, upper_bound)) in entries.iter().enumerate() {
+            if !children.insert(*child) {
+                return Err(DatabaseError::InternalInvariant {
+                    details: format!("interior page {} references child {} twice", page_id, child),
+                });
+            }
+            if let Some((_, next_bound)) = entries.get(index + 1)
+                && Page::compare_upper_bounds(upper_bound, next_bound) == std::cmp::Ordering::Greater
+            {
+                return Err(DatabaseError::InternalInvariant {
+                    details: format!(
+                        "interior page {} has upper bound {} for child {} before the smaller bound {}",
+                        page_id, upper_bound, child, next_bound
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
     pub fn extract_key_from_cell(&self, cell_data: &[u8]) -> Result<Value, DatabaseError> {
         let row = Row::from_bytes(cell_data)?;
-        Ok(row.values[0].clone())
+        Ok(row.key(&self.key_columns))
     }
 
+    /// Split an interior page's entries across `left_page` and a new right page. The
+    /// separator is the upper bound of the last entry kept on the left.
     fn split_interior_page(
         &mut self,
-        mut full_page: Page,
-        new_entry_data: Vec<u8>,
+        mut left_page: Page,
+        entries: Vec<(PageId, Value)>,
         extras: Option<u64>,
     ) -> Result<SplitResult, DatabaseError> {
         let new_page_id = self.allocate_page(PageType::InteriorTable, extras)?;
-        let mut right_page = Page::new(new_page_id, PageType::InteriorTable);
-        let mut all_entries = Vec::new();
-        for i in 0..full_page.slot_directory.slots.len() {
-            if let Some(entry_data) = full_page.get_cell(i) {
-                let key = self.extract_key_from_interior_entry(entry_data)?;
-                all_entries.push((key, entry_data.to_vec()));
+        let mut right_page = Page::new_with_size(new_page_id, PageType::InteriorTable, self.page_size);
+        let split_point = (entries.len() / 2).max(1);
+        let separator_key = entries[split_point - 1].1.clone();
+        let page_id = left_page.page_id;
+        let split_failed = |source| DatabaseError::SplitFailed {
+            page_id,
+            source: Box::new(source),
+        };
+        for (index, (child, upper_bound)) in entries.iter().enumerate() {
+            let entry_data = self.create_interior_entry(upper_bound, *child)?;
+            if index < split_point {
+                left_page.insert_cell(&entry_data, None).map_err(split_failed)?;
+            } else {
+                right_page.insert_cell(&entry_data, None).map_err(split_failed)?;
             }
         }
-        let new_key = self.extract_key_from_interior_entry(&new_entry_data)?;
-        all_entries.push((new_key, new_entry_data));
-        all_entries.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
-        let split_point = all_entries.len() / 2;
-        let separator_key = all_entries[split_point].0.clone();
-        full_page.slot_directory.slots.clear();
-        full_page.free_space_offset = PAGE_SIZE as u16;
-        full_page.cell_count = 0;
-        for (_, entry_data) in &all_entries[..split_point] {
-            full_page.insert_cell(entry_data, None)?;
-        }
-        for (_, entry_data) in &all_entries[split_point + 1..] {
-            right_page.insert_cell(entry_data, None)?;
-        }
         Ok(SplitResult {
-            left_page: full_page,
+            left_page,
             right_page,
             separator_key,
         })
     }
 
-    fn extract_key_from_interior_entry(&self, entry_data: &[u8]) -> Result<Value, DatabaseError> {
-        if entry_data.len() < 12 {
-            return Err(DatabaseError::CorruptedPage {
-                page_id: 0,
-                reason: "Interior entry too short".to_string(),
-            });
+    /// Decode an interior page's `(child, upper bound)` entries in routing order. A NULL
+    /// upper bound marks the unbounded rightmost child.
+    fn interior_entries(&self, page: &Page) -> Result<Vec<(PageId, Value)>, DatabaseError> {
+        let mut entries = Vec::new();
+        for i in 0..page.slot_directory.slots.len() {
+            if let Some(entry_data) = page.get_cell(i) {
+                entries.push(self.parse_interior_entry(entry_data)?);
+            }
         }
-        let key_length =
-            u32::from_le_bytes([entry_data[8], entry_data[9], entry_data[10], entry_data[11]])
-                as usize;
-        if entry_data.len() < 12 + key_length {
-            return Err(DatabaseError::CorruptedPage {
-                page_id: 0,
-                reason: "Interior entry key data incomplete".to_string(),
-            });
+        // Pages written before separators were kept in order may hold them unsorted
+        entries.sort_by(|(_, a), (_, b)| Page::compare_upper_bounds(a, b));
+        Ok(entries)
+    }
+
+    fn interior_entries_fit(&self, entries: &[(PageId, Value)]) -> Result<bool, DatabaseError> {
+        let mut page = Page::new_with_size(0, PageType::InteriorTable, self.page_size);
+        for (_, upper_bound) in entries {
+            let entry_size = 12 + upper_bound.to_bytes().len();
+            if !page.can_fit(entry_size) {
+                return Ok(false);
+            }
+            page.insert_cell(&vec![0u8; entry_size], None)?;
         }
-        let key_bytes = &entry_data[12..12 + key_length];
-        Value::from_bytes(key_bytes)
+        Ok(true)
     }
 
+    /// Store a cell too large for its leaf on an overflow page of its own. A cell that
+    /// does not fit a single overflow page is refused before any page is allocated.
     fn allocate_overflow_page(&mut self, data: &[u8], extras: Option<u64>) -> Result<PageId, DatabaseError> {
-        let overflow_page_id = self.allocate_page(PageType::OverflowPage, extras)?;
-        let mut overflow_page = Page::new(overflow_page_id, PageType::OverflowPage);
-        let available_space = overflow_page.available_space();
-        if data.len() <= available_space {
-            overflow_page.insert_cell(data, None)?;
-            self.write_page(overflow_page_id, overflow_page, extras)?;
-            Ok(overflow_page_id)
-        } else {
-            Err(DatabaseError::CorruptedDatabase {
-                reason: "Data too large for overflow page".to_string(),
-            })
+        let available_space = Page::new_with_size(0, PageType::OverflowPage, self.page_size).available_space();
+        if data.len() > available_space {
+            return Err(DatabaseError::RowTooLarge {
+                size: data.len(),
+                max: available_space,
+            });
         }
+        let overflow_page_id = self.allocate_page(PageType::OverflowPage, extras)?;
+        let mut overflow_page = Page::new_with_size(overflow_page_id, PageType::OverflowPage, self.page_size);
+        overflow_page.insert_cell(data, None)?;
+        self.write_page(overflow_page_id, overflow_page, extras)?;
+        Ok(overflow_page_id)
     }
 
+    /// Route `key` to the first child whose upper bound is greater than it, falling back
+    /// to the rightmost child. Pages in key order are binary searched; older ones have
+    /// every entry decoded.
     fn find_child_page(&self, interior_page: &Page, key: &Value) -> Result<PageId, DatabaseError> {
-        let mut child_page_id = None;
-        for i in 0..interior_page.slot_directory.slots.len() {
-            if let Some(entry_data) = interior_page.get_cell(i) {
-                let (entry_page_id, entry_key) = self.parse_interior_entry(entry_data)?;
-                if i < interior_page.slot_directory.slots.len() - 1 {
-                    if key <= &entry_key {
-                        child_page_id = Some(entry_page_id);
-                        break;
-                    }
-                } else {
-                    child_page_id = Some(entry_page_id);
-                }
-            }
+        if interior_page.sorted {
+            let position =
+                interior_page.binary_search_key(key, |entry| self.parse_interior_entry(entry).map(|(_, bound)| bound))?;
+            return self.interior_child_at(interior_page, position.end);
+        }
+        let entries = self.interior_entries(interior_page)?;
+        entries
+            .iter()
+            .find(|(_, upper_bound)| {
+                Page::compare_upper_bounds(key, upper_bound) == std::cmp::Ordering::Less
+            })
+            .or(entries.last())
+            .map(|(child, _)| *child)
+            .ok_or(DatabaseError::CorruptedPage {
+                page_id: interior_page.page_id,
+                reason: "No valid child page found".to_string(),
+            })
+    }
+
+    /// Child of the entry in `slot_index` of an interior page, or of its last entry when
+    /// `slot_index` is past them all
+    fn interior_child_at(&self, interior_page: &Page, slot_index: usize) -> Result<PageId, DatabaseError> {
+        let entry = interior_page.get_cell(slot_index).or_else(|| {
+            (0..interior_page.slot_directory.slots.len())
+                .rev()
+                .find_map(|slot_index| interior_page.get_cell(slot_index))
+        });
+        match entry {
+            Some(entry) => Ok(self.parse_interior_entry(entry)?.0),
+            None => Err(DatabaseError::CorruptedPage {
+                page_id: interior_page.page_id,
+                reason: "No valid child page found".to_string(),
+            }),
         }
-        child_page_id.ok_or(DatabaseError::CorruptedPage {
-            page_id: interior_page.page_id,
-            reason: "No valid child page found".to_string(),
-        })
     }
 
     fn parse_interior_entry(&self, entry_data: &[u8]) -> Result<(PageId, Value), DatabaseError> {
@@ -479,32 +1837,40 @@ impl BPlusTree {
         &mut self,
         page_id: PageId,
         mut page: Page,
+        key: &Value,
         cell_data: &[u8],
         extras: Option<u64>,
     ) -> Result<(), DatabaseError> {
-        // Insert the cell into the page structure
-        page.insert_cell(cell_data, None)?;
+        // Insert the cell into the page structure, after any cells with the same key on a
+        // page kept in key order
+        if page.sorted {
+            let position = page.binary_search_key(key, |cell| self.extract_key_from_cell(cell))?;
+            page.insert_cell_at(position.end, cell_data, None)?;
+        } else {
+            page.insert_cell(cell_data, None)?;
+        }
+        if self.deferred_writes {
+            page.is_dirty = true;
+            if let Some(evicted) = self.page_cache.insert(page_id, page) {
+                self.write_back(evicted)?;
+            }
+            return Ok(());
+        }
         
         // Write the entire page and flush immediately for single page operations
-        self.write_page(page_id, page.clone(), extras)?;
+        self.store_page(page_id, page, extras)?;
         self.file.flush()?;
-        
-        // CRITICAL FIX: Update cache with modified page
-        self.page_cache.insert(page_id, page);
-        
         Ok(())
     }
     
     /// Batch write multiple pages to reduce I/O overhead
     fn write_pages_batch(
         &mut self,
-        pages: &[(PageId, Page)],
+        pages: impl IntoIterator<Item = (PageId, Page)>,
         extras: Option<u64>,
     ) -> Result<(), DatabaseError> {
         for (page_id, page) in pages {
-            self.write_page(*page_id, page.clone(), extras)?;
-            // CRITICAL FIX: Update cache for each page
-            self.page_cache.insert(*page_id, page.clone());
+            self.store_page(page_id, page, extras)?;
         }
         // Single flush for all writes
         self.file.flush()?;
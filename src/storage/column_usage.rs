@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::types::{error::DatabaseError, row::Row, value::Value};
+
+/// How much one column has been used, as reported by `StorageManager::column_usage`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnUsage {
+    pub name: String,
+    /// Projections and predicates that named the column, each counted once per query
+    pub reads: u64,
+    /// Values stored in the column by inserts and updates
+    pub writes: u64,
+    /// Fraction of `writes` that were not NULL, 0 when nothing was written
+    pub non_null_ratio: f64,
+}
+
+/// Counters of one column: reads, writes and non-NULL writes
+#[derive(Debug, Default)]
+struct ColumnCounters {
+    reads: AtomicU64,
+    writes: AtomicU64,
+    non_null_writes: AtomicU64,
+}
+
+/// In-memory column usage counters keyed by table and column position. Reads are
+/// counted where a query's projection or predicate is compiled, so the cost is per query
+/// rather than per row.
+#[derive(Debug, Default)]
+pub struct ColumnUsageLog {
+    columns: RwLock<HashMap<(String, usize), ColumnCounters>>,
+}
+
+impl ColumnUsageLog {
+    /// Count one read of each column at `positions`
+    pub fn record_reads(&self, table: &str, positions: impl IntoIterator<Item = usize>) {
+        for position in positions {
+            self.with_counters(table, position, |counters| {
+                counters.reads.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+    }
+
+    /// Count writes tallied by `write_counts`
+    pub fn record_writes(&self, table: &str, counts: &[(u64, u64)]) {
+        for (position, &(writes, non_null_writes)) in counts.iter().enumerate() {
+            self.record_column_writes(table, position, writes, non_null_writes);
+        }
+    }
+
+    /// Count `count` writes of `value` to the column at `position`
+    pub fn record_assignment(&self, table: &str, position: usize, value: &Value, count: u64) {
+        let non_null = if value.is_null() { 0 } else { count };
+        self.record_column_writes(table, position, count, non_null);
+    }
+
+    fn record_column_writes(&self, table: &str, position: usize, writes: u64, non_null_writes: u64) {
+        if writes == 0 {
+            return;
+        }
+        self.with_counters(table, position, |counters| {
+            counters.writes.fetch_add(writes, Ordering::Relaxed);
+            counters.non_null_writes.fetch_add(non_null_writes, Ordering::Relaxed);
+        });
+    }
+
+    /// Reads, writes and non-NULL writes of each column of `table` by position, up to
+    /// `column_count` columns
+    pub fn snapshot(&self, table: &str, column_count: usize) -> Vec<[u64; 3]> {
+        let columns = self.columns.read().unwrap_or_else(|e| e.into_inner());
+        (0..column_count)
+            .map(|position| match columns.get(&(table.to_string(), position)) {
+                Some(counters) => [
+                    counters.reads.load(Ordering::Relaxed),
+                    counters.writes.load(Ordering::Relaxed),
+                    counters.non_null_writes.load(Ordering::Relaxed),
+                ],
+                None => [0; 3],
+            })
+            .collect()
+    }
+
+    /// Tables with any counters
+    pub fn tables(&self) -> Vec<String> {
+        let columns = self.columns.read().unwrap_or_else(|e| e.into_inner());
+        let mut tables: Vec<String> = columns.keys().map(|(table, _)| table.clone()).collect();
+        tables.sort_unstable();
+        tables.dedup();
+        tables
+    }
+
+    /// Add counts restored from a persisted snapshot
+    pub fn restore(&self, table: &str, counts: &[[u64; 3]]) {
+        for (position, [reads, writes, non_null_writes]) in counts.iter().enumerate() {
+            self.with_counters(table, position, |counters| {
+                counters.reads.fetch_add(*reads, Ordering::Relaxed);
+                counters.writes.fetch_add(*writes, Ordering::Relaxed);
+                counters.non_null_writes.fetch_add(*non_null_writes, Ordering::Relaxed);
+            });
+        }
+    }
+
+    /// Drop the counters of a table that no longer exists
+    pub fn forget(&self, table: &str) {
+        let mut columns = self.columns.write().unwrap_or_else(|e| e.into_inner());
+        columns.retain(|(owner, _), _| owner != table);
+    }
+
+    fn with_counters(&self, table: &str, position: usize, update: impl FnOnce(&ColumnCounters)) {
+        let key = (table.to_string(), position);
+        {
+            let columns = self.columns.read().unwrap_or_else(|e| e.into_inner());
+            if let Some(counters) = columns.get(&key) {
+                update(counters);
+                return;
+            }
+        }
+        let mut columns = self.columns.write().unwrap_or_else(|e| e.into_inner());
+        update(columns.entry(key).or_default());
+    }
+}
+
+/// Values `rows` store in each column by position: how many, and how many are not NULL.
+/// Taken before the rows are handed to an inserter and recorded once the insert succeeds.
+pub fn write_counts(rows: &[Row]) -> Vec<(u64, u64)> {
+    let mut counts = Vec::new();
+    for row in rows {
+        if counts.len() < row.values.len() {
+            counts.resize(row.values.len(), (0, 0));
+        }
+        for (count, value) in counts.iter_mut().zip(&row.values) {
+            count.0 += 1;
+            count.1 += u64::from(!value.is_null());
+        }
+    }
+    counts
+}
+
+/// A snapshot of a table's counters for storage in `sqlite_schema`:
+/// type, name, tbl_name, rootpage, sql, then reads, writes and non-NULL writes per column
+pub fn usage_schema_row(table: &str, counts: &[[u64; 3]]) -> Row {
+    let mut values = vec![
+        Value::Text("usage".to_string()),
+        Value::Text(table.to_string()),
+        Value::Text(table.to_string()),
+        Value::Integer(0),
+        Value::Text(String::new()),
+    ];
+    values.extend(counts.iter().flatten().map(|count| Value::Integer(*count as i64)));
+    Row::new(values)
+}
+
+/// The table and per-column counts of a snapshot written by `usage_schema_row`
+pub fn usage_from_schema_row(row: &Row) -> Result<(String, Vec<[u64; 3]>), DatabaseError> {
+    let corrupted = || DatabaseError::CorruptedDatabase {
+        reason: "Invalid column usage entry in schema".to_string(),
+    };
+    let (Some(Value::Text(table)), Some(counts)) = (row.values.get(1), row.values.get(5..)) else {
+        return Err(corrupted());
+    };
+    if counts.len() % 3 != 0 {
+        return Err(corrupted());
+    }
+    let counts = counts
+        .chunks(3)
+        .map(|chunk| {
+            let mut column = [0; 3];
+            for (count, value) in column.iter_mut().zip(chunk) {
+                match value {
+                    Value::Integer(value) if *value >= 0 => *count = *value as u64,
+                    _ => return Err(corrupted()),
+                }
+            }
+            Ok(column)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((table.clone(), counts))
+}
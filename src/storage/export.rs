@@ -0,0 +1,211 @@
+use std::io::Write;
+
+use crate::{
+    storage::schema::TableSchema,
+    types::{
+        error::DatabaseError,
+        row::Row,
+        value::{DataType, Value},
+    },
+};
+
+/// RFC 3339 layout used for timestamps in exported rows
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// How `JsonRowWriter` lays out rows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonLayout {
+    /// A single JSON array of objects
+    #[default]
+    Array,
+    /// Newline-delimited JSON: one object per line and nothing around them
+    Lines,
+}
+
+/// Writes rows as JSON objects keyed by column name, one row at a time
+pub struct JsonRowWriter<'a, W: Write> {
+    writer: &'a mut W,
+    columns: Vec<(String, DataType, usize)>,
+    layout: JsonLayout,
+    rows_written: usize,
+}
+
+impl<'a, W: Write> JsonRowWriter<'a, W> {
+    /// Open the array; columns are emitted in schema position order
+    pub fn begin(writer: &'a mut W, schema: &TableSchema) -> Result<Self, DatabaseError> {
+        Self::begin_with_layout(writer, schema, JsonLayout::Array)
+    }
+
+    pub fn begin_with_layout(writer: &'a mut W, schema: &TableSchema, layout: JsonLayout) -> Result<Self, DatabaseError> {
+        if layout == JsonLayout::Array {
+            writer.write_all(b"[")?;
+        }
+        Ok(Self {
+            writer,
+            columns: columns_in_position_order(schema),
+            layout,
+            rows_written: 0,
+        })
+    }
+
+    pub fn write_row(&mut self, row: &Row) -> Result<(), DatabaseError> {
+        if self.layout == JsonLayout::Array {
+            if self.rows_written > 0 {
+                self.writer.write_all(b",")?;
+            }
+            self.writer.write_all(b"\n  ")?;
+        }
+        self.writer.write_all(b"{")?;
+        for (i, (name, data_type, position)) in self.columns.iter().enumerate() {
+            if i > 0 {
+                self.writer.write_all(b", ")?;
+            }
+            let value = row.values.get(*position).unwrap_or(&Value::Null);
+            serde_json::to_writer(&mut *self.writer, name).map_err(json_error)?;
+            self.writer.write_all(b": ")?;
+            serde_json::to_writer(&mut *self.writer, &value_to_json(value, data_type))
+                .map_err(json_error)?;
+        }
+        self.writer.write_all(b"}")?;
+        if self.layout == JsonLayout::Lines {
+            self.writer.write_all(b"\n")?;
+        }
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    /// Close the array and return the number of rows written
+    pub fn finish(self) -> Result<usize, DatabaseError> {
+        if self.layout == JsonLayout::Array {
+            if self.rows_written > 0 {
+                self.writer.write_all(b"\n")?;
+            }
+            self.writer.write_all(b"]\n")?;
+        }
+        self.writer.flush()?;
+        Ok(self.rows_written)
+    }
+}
+
+/// Writes rows as RFC 4180 CSV under a header line of column names, one row at a time.
+/// NULL is an empty field and empty text a quoted one, so the two read back apart.
+pub struct CsvRowWriter<'a, W: Write> {
+    writer: &'a mut W,
+    columns: Vec<(String, DataType, usize)>,
+    rows_written: usize,
+}
+
+impl<'a, W: Write> CsvRowWriter<'a, W> {
+    /// Write the header; columns are emitted in schema position order
+    pub fn begin(writer: &'a mut W, schema: &TableSchema) -> Result<Self, DatabaseError> {
+        let columns = columns_in_position_order(schema);
+        let header: Vec<String> = columns.iter().map(|(name, _, _)| csv_quote(name)).collect();
+        writeln!(writer, "{}", header.join(","))?;
+        Ok(Self {
+            writer,
+            columns,
+            rows_written: 0,
+        })
+    }
+
+    pub fn write_row(&mut self, row: &Row) -> Result<(), DatabaseError> {
+        let fields: Vec<String> = self
+            .columns
+            .iter()
+            .map(|(_, data_type, position)| {
+                let value = row.values.get(*position).unwrap_or(&Value::Null);
+                match value_to_csv(value, data_type) {
+                    // Quoted so that it does not read back as NULL
+                    Some(text) if text.is_empty() => "\"\"".to_string(),
+                    Some(text) => csv_quote(&text),
+                    None => String::new(),
+                }
+            })
+            .collect();
+        writeln!(self.writer, "{}", fields.join(","))?;
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    /// Flush and return the number of rows written
+    pub fn finish(self) -> Result<usize, DatabaseError> {
+        self.writer.flush()?;
+        Ok(self.rows_written)
+    }
+}
+
+fn columns_in_position_order(schema: &TableSchema) -> Vec<(String, DataType, usize)> {
+    let mut columns: Vec<_> = schema
+        .columns
+        .iter()
+        .map(|col| (col.name.clone(), col.data_type.clone(), col.position))
+        .collect();
+    columns.sort_by_key(|(_, _, position)| *position);
+    columns
+}
+
+/// `text` quoted if it holds a comma, quote or line break, with quotes doubled
+pub fn csv_quote(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Unquoted CSV text of a stored value, or `None` for NULL. Timestamps and blobs are
+/// written as `value_to_json` writes them; reals keep every digit needed to read them
+/// back exactly.
+pub fn value_to_csv(value: &Value, data_type: &DataType) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::Integer(seconds) if *data_type == DataType::Timestamp => {
+            Value::Timestamp(*seconds).format_timestamp(TIMESTAMP_FORMAT)
+        }
+        Value::Timestamp(_) => value.format_timestamp(TIMESTAMP_FORMAT),
+        Value::Blob(bytes) => Some(hex_encode(bytes)),
+        Value::Boolean(b) => Some(b.to_string()),
+        Value::Integer(i) => Some(i.to_string()),
+        Value::Real(f) => Some(f.to_string()),
+        Value::Text(s) => Some(s.clone()),
+        Value::Decimal(d) => Some(d.to_string()),
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// JSON representation of a stored value. Integers in a TIMESTAMP column are written as
+/// timestamps, blobs as lowercase hex and non-finite reals as `null`. Decimals are strings
+/// so that consumers parsing numbers as doubles cannot round them.
+pub fn value_to_json(value: &Value, data_type: &DataType) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(seconds) if *data_type == DataType::Timestamp => {
+            timestamp_to_json(&Value::Timestamp(*seconds))
+        }
+        Value::Integer(i) => serde_json::Value::from(*i),
+        Value::Real(f) => serde_json::Number::from_f64(*f)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Value::Text(s) => serde_json::Value::String(s.clone()),
+        Value::Blob(bytes) => serde_json::Value::String(hex_encode(bytes)),
+        Value::Boolean(b) => serde_json::Value::Bool(*b),
+        Value::Decimal(d) => serde_json::Value::String(d.to_string()),
+        Value::Timestamp(_) => timestamp_to_json(value),
+    }
+}
+
+fn timestamp_to_json(value: &Value) -> serde_json::Value {
+    value
+        .format_timestamp(TIMESTAMP_FORMAT)
+        .map(serde_json::Value::String)
+        .unwrap_or(serde_json::Value::Null)
+}
+
+fn json_error(error: serde_json::Error) -> DatabaseError {
+    DatabaseError::SerializationError {
+        details: error.to_string(),
+    }
+}
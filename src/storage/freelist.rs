@@ -0,0 +1,101 @@
+use std::io::SeekFrom;
+
+use crate::{
+    storage::{SCHEMA_ROOT_PAGE_ID, header::BambangHeader, page_offset_with_size, page_store::PageStore},
+    types::{PageId, error::DatabaseError},
+};
+
+// Free pages form a chain of trunk pages. The header holds the head of the chain
+// (`freelist_trunk_page`, 0 when empty) and its length (`freelist_pages_count`);
+// each trunk page stores the id of the next trunk page in its first 8 bytes.
+// Callers are responsible for writing the updated header back to the file.
+
+/// Push a page onto the freelist, overwriting its contents with a trunk entry
+pub fn push_free_page(
+    file: &mut dyn PageStore,
+    header: &mut BambangHeader,
+    page_id: PageId,
+) -> Result<(), DatabaseError> {
+    if page_id == SCHEMA_ROOT_PAGE_ID {
+        return Err(DatabaseError::InvalidData {
+            details: format!("Cannot free page {}: it is the root of sqlite_schema", page_id),
+        });
+    }
+    if page_id == 0 || page_id > header.database_size_pages as PageId {
+        return Err(DatabaseError::InvalidData {
+            details: format!(
+                "Cannot free page {}: database has {} pages",
+                page_id, header.database_size_pages
+            ),
+        });
+    }
+
+    // A page pushed twice would become its own trunk and be handed out twice
+    if free_page_ids(file, header)?.contains(&page_id) {
+        return Err(DatabaseError::InvalidData {
+            details: format!("Cannot free page {}: it is already on the freelist", page_id),
+        });
+    }
+
+    let mut buffer = vec![0u8; header.page_size_bytes()];
+    buffer[0..8].copy_from_slice(&(header.freelist_trunk_page as PageId).to_le_bytes());
+    file.seek(SeekFrom::Start(page_offset_with_size(page_id, header.page_size_bytes())))?;
+    file.write_all(&buffer)?;
+
+    header.freelist_trunk_page = page_id as u32;
+    header.freelist_pages_count += 1;
+    Ok(())
+}
+
+/// Pop the most recently freed page, if any
+pub fn pop_free_page(
+    file: &mut dyn PageStore,
+    header: &mut BambangHeader,
+) -> Result<Option<PageId>, DatabaseError> {
+    if header.freelist_trunk_page == 0 {
+        return Ok(None);
+    }
+
+    let page_id = header.freelist_trunk_page as PageId;
+    let next_trunk = read_next_trunk(file, header, page_id)?;
+    header.freelist_trunk_page = next_trunk as u32;
+    header.freelist_pages_count = header.freelist_pages_count.saturating_sub(1);
+    Ok(Some(page_id))
+}
+
+/// Walk the freelist and return every free page id, most recently freed first
+pub fn free_page_ids(file: &mut dyn PageStore, header: &BambangHeader) -> Result<Vec<PageId>, DatabaseError> {
+    let mut page_ids = Vec::new();
+    let mut current = header.freelist_trunk_page as PageId;
+    while current != 0 {
+        if page_ids.len() > header.database_size_pages as usize {
+            return Err(DatabaseError::CorruptedDatabase {
+                reason: "Freelist contains a cycle".to_string(),
+            });
+        }
+        page_ids.push(current);
+        current = read_next_trunk(file, header, current)?;
+    }
+    Ok(page_ids)
+}
+
+fn read_next_trunk(
+    file: &mut dyn PageStore,
+    header: &BambangHeader,
+    page_id: PageId,
+) -> Result<PageId, DatabaseError> {
+    if page_id > header.database_size_pages as PageId {
+        return Err(DatabaseError::CorruptedDatabase {
+            reason: format!("Freelist page {} is beyond the end of the database", page_id),
+        });
+    }
+    if page_id == SCHEMA_ROOT_PAGE_ID {
+        return Err(DatabaseError::CorruptedDatabase {
+            reason: "Freelist holds page 1, the root of sqlite_schema".to_string(),
+        });
+    }
+    let mut next = [0u8; 8];
+    file.seek(SeekFrom::Start(page_offset_with_size(page_id, header.page_size_bytes())))?;
+    file.read_exact(&mut next)?;
+    Ok(PageId::from_le_bytes(next))
+}
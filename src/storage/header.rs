@@ -1,11 +1,25 @@
+use std::io::SeekFrom;
+
 use crate::{
-    storage::{BAMBANG_HEADER_SIZE, BAMBANG_MAGIC},
-    types::{PAGE_SIZE, error::DatabaseError},
+    storage::{BAMBANG_HEADER_SIZE, BAMBANG_MAGIC, SCHEMA_ROOT_PAGE_ID, page_store::PageStore},
+    types::{MAX_PAGE_SIZE, PAGE_SIZE, PageId, error::DatabaseError, validate_page_size},
 };
 
-#[derive(Debug)]
+/// Layout version of the pages in a database file, stored as `schema_format_number`.
+///
+/// Format 5 widened slot directory entries from 4 to 13 bytes so that they keep each
+/// cell's row_id and overflow flag, which format 4 dropped and guessed back on load.
+/// Format 6 grew the page header from 36 to 44 bytes to hold `prev_leaf_page_id`.
+/// Files of older formats are refused rather than converted: move their tables over by
+/// exporting them with a build that still reads the old format and inserting the rows
+/// into a new database.
+pub const SCHEMA_FORMAT_NUMBER: u32 = 6;
+
+#[derive(Debug, Clone)]
 pub struct BambangHeader {
     pub magic: [u8; 16],
+    /// Page size as stored, where 1 stands for `MAX_PAGE_SIZE` as in SQLite; read it
+    /// through `page_size_bytes`
     pub page_size: u16,
     pub file_format_write_version: u8,
     pub file_format_read_version: u8,
@@ -34,7 +48,7 @@ impl Default for BambangHeader {
     fn default() -> Self {
         Self {
             magic: *BAMBANG_MAGIC,
-            page_size: PAGE_SIZE as u16,
+            page_size: encode_page_size(PAGE_SIZE),
             file_format_write_version: 1,
             file_format_read_version: 1,
             reserved_space: 0,
@@ -46,7 +60,7 @@ impl Default for BambangHeader {
             freelist_trunk_page: 0,
             freelist_pages_count: 0,
             schema_cookie: 1,
-            schema_format_number: 4,
+            schema_format_number: SCHEMA_FORMAT_NUMBER,
             default_page_cache_size: 0,
             largest_root_btree_page: 1,
             text_encoding: 1,
@@ -55,12 +69,93 @@ impl Default for BambangHeader {
             application_id: 0,
             reserved: [0; 20],
             version_valid_for: 1,
-            bambang_version_number: 0001000,
+            bambang_version_number: 1000,
         }
     }
 }
 
 impl BambangHeader {
+    /// Read the header from the start of a database file
+    pub fn read_from(file: &mut dyn PageStore) -> Result<Self, DatabaseError> {
+        let mut buffer = vec![0u8; BAMBANG_HEADER_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut buffer)?;
+        Self::from_bytes(&buffer)
+    }
+
+    /// Write the header to the start of a database file
+    pub fn write_to(&self, file: &mut dyn PageStore) -> Result<(), DatabaseError> {
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Size of every page in the file, in bytes
+    pub fn page_size_bytes(&self) -> usize {
+        decode_page_size(self.page_size)
+    }
+
+    pub fn set_page_size(&mut self, page_size: usize) -> Result<(), DatabaseError> {
+        validate_page_size(page_size)?;
+        self.page_size = encode_page_size(page_size);
+        Ok(())
+    }
+
+    /// Size of the database file in bytes as described by the header
+    pub fn database_size_bytes(&self) -> u64 {
+        BAMBANG_HEADER_SIZE as u64 + self.database_size_pages as u64 * self.page_size_bytes() as u64
+    }
+
+    /// Maximum database size in bytes, stored in the first 8 reserved bytes (0 means unlimited)
+    pub fn max_database_size(&self) -> Option<u64> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&self.reserved[0..8]);
+        match u64::from_be_bytes(bytes) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+
+    pub fn set_max_database_size(&mut self, limit: Option<u64>) {
+        self.reserved[0..8].copy_from_slice(&limit.unwrap_or(0).to_be_bytes());
+    }
+
+    /// Root page of `sqlite_schema`, stored in reserved bytes 8..12. Files that predate
+    /// it hold 0 there and keep the schema root on page 1.
+    pub fn schema_root_page(&self) -> PageId {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&self.reserved[8..12]);
+        match u32::from_be_bytes(bytes) {
+            0 => SCHEMA_ROOT_PAGE_ID,
+            page_id => page_id as PageId,
+        }
+    }
+
+    pub fn set_schema_root_page(&mut self, page_id: PageId) {
+        self.reserved[8..12].copy_from_slice(&(page_id as u32).to_be_bytes());
+    }
+
+    /// Mark another write transaction; wraps around rather than overflowing
+    pub fn increment_change_counter(&mut self) {
+        self.file_change_counter = self.file_change_counter.wrapping_add(1);
+    }
+
+    /// Mark the schema as changed, invalidating anything prepared against the old one
+    pub fn increment_schema_cookie(&mut self) {
+        self.schema_cookie = self.schema_cookie.wrapping_add(1);
+    }
+
+    /// Check that the file may grow by `additional_pages` without exceeding the size quota
+    pub fn ensure_can_grow(&self, additional_pages: u32) -> Result<(), DatabaseError> {
+        if let Some(limit) = self.max_database_size() {
+            let current = self.database_size_bytes();
+            if current + additional_pages as u64 * self.page_size_bytes() as u64 > limit {
+                return Err(DatabaseError::QuotaExceeded { limit, current });
+            }
+        }
+        Ok(())
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buffer = Vec::with_capacity(BAMBANG_HEADER_SIZE);
 
@@ -111,7 +206,7 @@ impl BambangHeader {
         offset += 16;
 
         let page_size = u16::from_be_bytes([bytes[offset], bytes[offset + 1]]);
-        if page_size != PAGE_SIZE as u16 {
+        if validate_page_size(decode_page_size(page_size)).is_err() {
             return Err(DatabaseError::InvalidHeader {
                 reason: format!("Unsupported page size: {}", page_size),
             });
@@ -177,6 +272,14 @@ impl BambangHeader {
             bytes[offset + 2],
             bytes[offset + 3],
         ]);
+        if schema_format_number != SCHEMA_FORMAT_NUMBER {
+            return Err(DatabaseError::InvalidHeader {
+                reason: format!(
+                    "Unsupported schema format {} (this build reads format {})",
+                    schema_format_number, SCHEMA_FORMAT_NUMBER
+                ),
+            });
+        }
         offset += 4;
 
         let default_page_cache_size = u32::from_be_bytes([
@@ -273,3 +376,11 @@ impl BambangHeader {
         })
     }
 }
+
+fn encode_page_size(page_size: usize) -> u16 {
+    if page_size == MAX_PAGE_SIZE { 1 } else { page_size as u16 }
+}
+
+fn decode_page_size(stored: u16) -> usize {
+    if stored == 1 { MAX_PAGE_SIZE } else { stored as usize }
+}
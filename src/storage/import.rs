@@ -0,0 +1,405 @@
+use std::path::PathBuf;
+
+use crate::{
+    storage::schema::ColumnSchema,
+    types::{
+        error::DatabaseError,
+        value::{DataType, Value},
+    },
+};
+
+/// What `StorageManager::import_jsonl` does with a field holding a JSON object or array
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NestedJson {
+    /// Refuse the line
+    #[default]
+    Reject,
+    /// Store the nested value's JSON text in a TEXT column
+    AsText,
+}
+
+/// Options for `StorageManager::import_jsonl`
+#[derive(Debug, Clone)]
+pub struct JsonlImportOptions {
+    /// Create a missing table with a schema inferred from the first `sample_size`
+    /// records instead of failing with `TableNotFound`
+    pub create_table: bool,
+    /// Records read ahead to infer a new table's schema
+    pub sample_size: usize,
+    pub nested: NestedJson,
+    /// Skip lines that cannot be imported, listing them in the summary, instead of
+    /// stopping at the first one
+    pub lenient: bool,
+    /// In lenient mode, a file that receives every skipped line as it was read, so it
+    /// can be fixed and imported again
+    pub rejects_path: Option<PathBuf>,
+    /// Rows inserted per batch
+    pub batch_size: usize,
+}
+
+impl Default for JsonlImportOptions {
+    fn default() -> Self {
+        Self {
+            create_table: false,
+            sample_size: 1000,
+            nested: NestedJson::Reject,
+            lenient: false,
+            rejects_path: None,
+            batch_size: 1000,
+        }
+    }
+}
+
+impl JsonlImportOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_create_table(mut self, enabled: bool) -> Self {
+        self.create_table = enabled;
+        self
+    }
+
+    pub fn with_sample_size(mut self, records: usize) -> Self {
+        self.sample_size = records.max(1);
+        self
+    }
+
+    pub fn with_nested(mut self, nested: NestedJson) -> Self {
+        self.nested = nested;
+        self
+    }
+
+    pub fn with_lenient(mut self, enabled: bool) -> Self {
+        self.lenient = enabled;
+        self
+    }
+
+    pub fn with_rejects_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.rejects_path = Some(path.into());
+        self
+    }
+
+    pub fn with_batch_size(mut self, rows: usize) -> Self {
+        self.batch_size = rows.max(1);
+        self
+    }
+}
+
+/// A line `import_jsonl` skipped in lenient mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonlReject {
+    /// 1-based line number in the input
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Outcome of `StorageManager::import_jsonl`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonlImportSummary {
+    pub imported: usize,
+    pub rejected: Vec<JsonlReject>,
+    /// Whether the table was created from an inferred schema
+    pub created_table: bool,
+}
+
+/// Parse one line of JSON Lines input, which must hold an object
+pub fn parse_jsonl_record(line: &str) -> Result<serde_json::Map<String, serde_json::Value>, DatabaseError> {
+    match serde_json::from_str(line) {
+        Ok(serde_json::Value::Object(fields)) => Ok(fields),
+        Ok(other) => Err(DatabaseError::InvalidData {
+            details: format!("Expected a JSON object per line, found {}", other),
+        }),
+        Err(e) => Err(DatabaseError::SerializationError { details: e.to_string() }),
+    }
+}
+
+/// Column types of a table inferred from sample records, in the order keys first
+/// appear. A column's type widens as records disagree, Integer to Real to Text, and it
+/// is nullable when some record leaves it out or holds null.
+#[derive(Debug, Default)]
+pub struct JsonSchemaInference {
+    columns: Vec<InferredColumn>,
+    records: usize,
+}
+
+#[derive(Debug)]
+struct InferredColumn {
+    name: String,
+    /// `None` while only nulls have been seen
+    data_type: Option<DataType>,
+    present: usize,
+    saw_null: bool,
+}
+
+impl JsonSchemaInference {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Widen the inferred columns to take `record`. A record with a nested value that
+    /// `nested` refuses is not counted.
+    pub fn observe(
+        &mut self,
+        record: &serde_json::Map<String, serde_json::Value>,
+        nested: NestedJson,
+    ) -> Result<(), DatabaseError> {
+        let types = record
+            .iter()
+            .map(|(name, json)| json_type(json, nested).map_err(|e| nested_error(name, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for ((name, _), data_type) in record.iter().zip(types) {
+            let index = match self.columns.iter().position(|column| column.name == *name) {
+                Some(index) => index,
+                None => {
+                    self.columns.push(InferredColumn {
+                        name: name.clone(),
+                        data_type: None,
+                        present: 0,
+                        saw_null: false,
+                    });
+                    self.columns.len() - 1
+                }
+            };
+            let column = &mut self.columns[index];
+            column.present += 1;
+            match data_type {
+                None => column.saw_null = true,
+                Some(data_type) => {
+                    column.data_type = Some(match column.data_type.take() {
+                        None => data_type,
+                        Some(current) => widen(current, data_type),
+                    });
+                }
+            }
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Number of records observed
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// The inferred columns; one that only ever held null is TEXT
+    pub fn columns(&self) -> Vec<ColumnSchema> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(position, inferred)| {
+                let column = ColumnSchema::new(
+                    inferred.name.clone(),
+                    inferred.data_type.clone().unwrap_or(DataType::Text),
+                    position,
+                );
+                match inferred.saw_null || inferred.present < self.records {
+                    true => column,
+                    false => column.not_null(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Type a JSON value suggests for its column, `None` for null
+fn json_type(json: &serde_json::Value, nested: NestedJson) -> Result<Option<DataType>, DatabaseError> {
+    Ok(match json {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(_) => Some(DataType::Boolean),
+        serde_json::Value::Number(n) if n.is_i64() => Some(DataType::Integer),
+        serde_json::Value::Number(_) => Some(DataType::Real),
+        serde_json::Value::String(_) => Some(DataType::Text),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => match nested {
+            NestedJson::AsText => Some(DataType::Text),
+            NestedJson::Reject => {
+                return Err(DatabaseError::InvalidData {
+                    details: "nested objects and arrays are not imported".to_string(),
+                });
+            }
+        },
+    })
+}
+
+/// Narrowest type holding values of both `a` and `b`
+fn widen(a: DataType, b: DataType) -> DataType {
+    match (a, b) {
+        (a, b) if a == b => a,
+        (DataType::Integer, DataType::Real) | (DataType::Real, DataType::Integer) => DataType::Real,
+        _ => DataType::Text,
+    }
+}
+
+fn nested_error(column: &str, error: DatabaseError) -> DatabaseError {
+    DatabaseError::InvalidData {
+        details: format!("Column '{}': {}", column, error),
+    }
+}
+
+/// Value of a field of a JSON Lines record for a column of `data_type`. Besides what
+/// `json_to_value` reads, numbers and booleans are stored as their JSON text in TEXT
+/// columns, as are nested values when `nested` allows them.
+pub fn jsonl_field_to_value(
+    json: &serde_json::Value,
+    data_type: &DataType,
+    nested: NestedJson,
+) -> Result<Value, DatabaseError> {
+    match (json, data_type) {
+        (serde_json::Value::Array(_) | serde_json::Value::Object(_), _) => {
+            json_type(json, nested)?;
+            match data_type {
+                DataType::Text => Ok(Value::Text(json.to_string())),
+                _ => Err(DatabaseError::SerializationError {
+                    details: format!("Cannot read JSON {} as {}", json, data_type),
+                }),
+            }
+        }
+        (serde_json::Value::Number(_) | serde_json::Value::Bool(_), DataType::Text) => Ok(Value::Text(json.to_string())),
+        _ => json_to_value(json, data_type),
+    }
+}
+
+/// One field of a CSV record; `quoted` tells an empty field (NULL) from `""` (empty text)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvField {
+    pub text: String,
+    pub quoted: bool,
+}
+
+/// Split RFC 4180 CSV into records of fields. Quoted fields may hold commas, doubled
+/// quotes and line breaks; records end at LF or CRLF and blank lines are skipped.
+pub fn parse_csv(input: &str) -> Result<Vec<Vec<CsvField>>, DatabaseError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = CsvField {
+        text: String::new(),
+        quoted: false,
+    };
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.text.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.text.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.text.is_empty() && !field.quoted => {
+                field.quoted = true;
+                in_quotes = true;
+            }
+            '"' => {
+                return Err(DatabaseError::SerializationError {
+                    details: format!("Unexpected quote in unquoted CSV field on line {}", line),
+                });
+            }
+            ',' => record.push(std::mem::replace(
+                &mut field,
+                CsvField {
+                    text: String::new(),
+                    quoted: false,
+                },
+            )),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                end_record(&mut records, &mut record, &mut field);
+                line += 1;
+            }
+            _ if field.quoted => {
+                return Err(DatabaseError::SerializationError {
+                    details: format!("Text after closing quote in CSV field on line {}", line),
+                });
+            }
+            _ => field.text.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(DatabaseError::SerializationError {
+            details: "Unterminated quoted CSV field".to_string(),
+        });
+    }
+    end_record(&mut records, &mut record, &mut field);
+    Ok(records)
+}
+
+fn end_record(records: &mut Vec<Vec<CsvField>>, record: &mut Vec<CsvField>, field: &mut CsvField) {
+    let field = std::mem::replace(
+        field,
+        CsvField {
+            text: String::new(),
+            quoted: false,
+        },
+    );
+    if record.is_empty() && field.text.is_empty() && !field.quoted {
+        return;
+    }
+    record.push(field);
+    records.push(std::mem::take(record));
+}
+
+/// Value of a CSV field written by `CsvRowWriter` for a column of `data_type`: an
+/// unquoted empty field is NULL and blobs are hex
+pub fn csv_field_to_value(field: &CsvField, data_type: &DataType) -> Result<Value, DatabaseError> {
+    if field.text.is_empty() && !field.quoted {
+        return Ok(Value::Null);
+    }
+    text_to_value(&field.text, data_type)
+}
+
+/// Value of a JSON value written by `JsonRowWriter` for a column of `data_type`
+pub fn json_to_value(json: &serde_json::Value, data_type: &DataType) -> Result<Value, DatabaseError> {
+    let mismatch = || DatabaseError::SerializationError {
+        details: format!("Cannot read JSON {} as {}", json, data_type),
+    };
+    match (json, data_type) {
+        (serde_json::Value::Null, _) => Ok(Value::Null),
+        (serde_json::Value::String(text), _) => text_to_value(text, data_type),
+        (serde_json::Value::Bool(b), DataType::Boolean) => Ok(Value::Boolean(*b)),
+        (serde_json::Value::Number(n), DataType::Integer) => n.as_i64().map(Value::Integer).ok_or_else(mismatch),
+        (serde_json::Value::Number(n), DataType::Real) => n.as_f64().map(Value::Real).ok_or_else(mismatch),
+        (serde_json::Value::Number(n), DataType::Decimal) => text_to_value(&n.to_string(), data_type),
+        (serde_json::Value::Number(n), DataType::Timestamp) => {
+            n.as_i64().map(Value::Timestamp).ok_or_else(mismatch)
+        }
+        _ => Err(mismatch()),
+    }
+}
+
+fn text_to_value(text: &str, data_type: &DataType) -> Result<Value, DatabaseError> {
+    match data_type {
+        DataType::Text => Ok(Value::Text(text.to_string())),
+        DataType::Blob => hex_decode(text).map(Value::Blob),
+        DataType::Boolean => match text {
+            "true" => Ok(Value::Boolean(true)),
+            "false" => Ok(Value::Boolean(false)),
+            _ => Value::from_string(text, data_type),
+        },
+        _ => Value::from_string(text, data_type),
+    }
+}
+
+fn hex_decode(text: &str) -> Result<Vec<u8>, DatabaseError> {
+    let invalid = || DatabaseError::SerializationError {
+        details: format!("Cannot read '{}' as hex", text),
+    };
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    if !digits.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| digits.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()).ok_or_else(invalid))
+        .collect()
+}
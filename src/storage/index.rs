@@ -0,0 +1,74 @@
+use crate::types::{PageId, error::DatabaseError, row::Row, value::Value};
+
+/// A non-unique secondary index stored in `sqlite_schema`: a B+ tree of `[value, key]`
+/// rows mapping every non-NULL value of one column to the B+ tree key of the table row
+/// holding it. Equal values are kept as duplicate keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableIndex {
+    pub name: String,
+    pub table_name: String,
+    pub column_name: String,
+    pub root_page_id: PageId,
+}
+
+impl TableIndex {
+    pub fn new(table_name: &str, column_name: &str, root_page_id: PageId) -> Self {
+        Self {
+            name: Self::name_for(table_name, column_name),
+            table_name: table_name.to_string(),
+            column_name: column_name.to_string(),
+            root_page_id,
+        }
+    }
+
+    /// Name given to the index on `column_name` of `table_name`
+    pub fn name_for(table_name: &str, column_name: &str) -> String {
+        format!("idx_{}_{}", table_name, column_name)
+    }
+
+    pub fn definition_sql(&self) -> String {
+        format!("CREATE INDEX {} ON {}({})", self.name, self.table_name, self.column_name)
+    }
+
+    /// Index entry for `row`, whose indexed column is at `position` and whose table is
+    /// keyed as `key_columns` says, or `None` when the value there is NULL since NULL
+    /// never equals a lookup value
+    pub fn entry_for(row: &Row, position: usize, key_columns: &[usize]) -> Option<Row> {
+        let value = row.values.get(position).filter(|value| !value.is_null())?;
+        Some(Row::new(vec![value.clone(), row.key(key_columns)]))
+    }
+
+    /// Index entry: type, name, tbl_name, rootpage, sql, column
+    pub fn to_schema_row(&self) -> Row {
+        Row::new(vec![
+            Value::Text("index".to_string()),
+            Value::Text(self.name.clone()),
+            Value::Text(self.table_name.clone()),
+            Value::Integer(self.root_page_id as i64),
+            Value::Text(self.definition_sql()),
+            Value::Text(self.column_name.clone()),
+        ])
+    }
+
+    pub fn from_schema_row(row: &Row) -> Result<Self, DatabaseError> {
+        match &row.values[..] {
+            [
+                Value::Text(_),
+                Value::Text(name),
+                Value::Text(table_name),
+                Value::Integer(root_page_id),
+                Value::Text(_),
+                Value::Text(column_name),
+                ..,
+            ] => Ok(Self {
+                name: name.clone(),
+                table_name: table_name.clone(),
+                column_name: column_name.clone(),
+                root_page_id: *root_page_id as PageId,
+            }),
+            _ => Err(DatabaseError::CorruptedDatabase {
+                reason: "Invalid index entry in schema".to_string(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    storage::{BAMBANG_HEADER_SIZE, header::BambangHeader, page_offset_with_size, page_store::PageStore},
+    types::{
+        PageId,
+        error::DatabaseError,
+        page::{Page, PageType},
+        row::Row,
+        value::Value,
+    },
+};
+
+/// One problem found by `StorageManager::check_integrity`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegrityFinding {
+    /// Page the problem is on, or `None` for the file header and the freelist
+    pub page_id: Option<PageId>,
+    /// Table or index whose tree reached the page
+    pub tree: Option<String>,
+    pub details: String,
+}
+
+impl std::fmt::Display for IntegrityFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.page_id {
+            Some(page_id) => write!(f, "Page {}", page_id)?,
+            None => write!(f, "Header")?,
+        }
+        if let Some(tree) = &self.tree {
+            write!(f, " of '{}'", tree)?;
+        }
+        write!(f, ": {}", self.details)
+    }
+}
+
+/// Everything `StorageManager::check_integrity` found, in the order it was found
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IntegrityReport {
+    /// Tables and indexes walked, `sqlite_schema` included
+    pub trees_checked: usize,
+    /// Pages read and decoded, overflow pages included
+    pub pages_checked: usize,
+    pub findings: Vec<IntegrityFinding>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Findings on `page_id`
+    pub fn findings_for(&self, page_id: PageId) -> impl Iterator<Item = &IntegrityFinding> {
+        self.findings.iter().filter(move |finding| finding.page_id == Some(page_id))
+    }
+}
+
+/// Walks the pages of a database file, recording each problem as a finding and moving
+/// on, so one damaged page does not hide the rest
+pub struct IntegrityChecker<'a> {
+    file: &'a dyn PageStore,
+    page_size: usize,
+    /// Pages the header records, capped at the pages the file holds
+    page_count: u64,
+    free_pages: HashSet<PageId>,
+    /// Tree that first reached each page, to catch pages shared between trees
+    owners: HashMap<PageId, String>,
+    report: IntegrityReport,
+}
+
+impl<'a> IntegrityChecker<'a> {
+    /// Check the header at the start of `file`. `page_size` and `fallback` are what the
+    /// database was opened with, used when the stored header cannot be read.
+    pub fn new(file: &'a dyn PageStore, page_size: usize, fallback: &BambangHeader) -> Self {
+        let mut checker = Self {
+            file,
+            page_size,
+            page_count: 0,
+            free_pages: HashSet::new(),
+            owners: HashMap::new(),
+            report: IntegrityReport::default(),
+        };
+        let header = checker.check_header().unwrap_or_else(|| fallback.clone());
+        let file_pages = file
+            .len()
+            .map(|len| len.saturating_sub(BAMBANG_HEADER_SIZE as u64) / page_size as u64)
+            .unwrap_or(0);
+        checker.page_count = (header.database_size_pages as u64).min(file_pages);
+        checker
+    }
+
+    fn check_header(&mut self) -> Option<BambangHeader> {
+        let mut buffer = vec![0u8; BAMBANG_HEADER_SIZE];
+        if let Err(e) = self.file.read_exact_at(&mut buffer, 0) {
+            self.record(None, None, format!("Cannot read the header: {}", e));
+            return None;
+        }
+        let header = match BambangHeader::from_bytes(&buffer) {
+            Ok(header) => header,
+            Err(DatabaseError::InvalidHeader { reason }) => {
+                self.record(None, None, reason);
+                return None;
+            }
+            Err(e) => {
+                self.record(None, None, e.to_string());
+                return None;
+            }
+        };
+        if header.page_size_bytes() != self.page_size {
+            self.record(
+                None,
+                None,
+                format!(
+                    "Page size {} does not match the {} bytes the database was opened with",
+                    header.page_size_bytes(),
+                    self.page_size
+                ),
+            );
+        }
+        match self.file.len() {
+            Ok(len) => {
+                let expected = page_offset_with_size(header.database_size_pages as u64 + 1, self.page_size);
+                if len != expected {
+                    self.record(
+                        None,
+                        None,
+                        format!(
+                            "Header records {} pages ({} bytes) but the file is {} bytes",
+                            header.database_size_pages, expected, len
+                        ),
+                    );
+                }
+            }
+            Err(e) => self.record(None, None, format!("Cannot read the file size: {}", e)),
+        }
+        Some(header)
+    }
+
+    /// Pages on the freelist are reported if a tree reaches them
+    pub fn set_free_pages(&mut self, free_pages: impl IntoIterator<Item = PageId>) {
+        self.free_pages = free_pages.into_iter().collect();
+    }
+
+    /// Record a problem that is not tied to a page
+    pub fn record_error(&mut self, tree: Option<&str>, error: &DatabaseError) {
+        let page_id = match error {
+            DatabaseError::CorruptedPage { page_id, .. } => Some(*page_id),
+            _ => None,
+        };
+        self.record(page_id, tree, error_details(error));
+    }
+
+    /// Walk the B+ tree rooted at `root_page_id`: every page must decode, interior
+    /// entries must lead to table pages, every row must deserialize, and the leaf chain
+    /// must link exactly the leaves reached from the root, in the same order
+    pub fn check_btree(&mut self, tree: &str, root_page_id: PageId) {
+        self.report.trees_checked += 1;
+        let mut leaves = Vec::new();
+        self.check_node(tree, root_page_id, &mut leaves);
+        self.check_leaf_chain(tree, &leaves);
+    }
+
+    fn check_node(&mut self, tree: &str, page_id: PageId, leaves: &mut Vec<PageId>) {
+        let Some(page) = self.load_page(tree, page_id) else {
+            return;
+        };
+        match page.page_type {
+            PageType::InteriorTable => {
+                for child in self.interior_children(tree, &page) {
+                    self.check_node(tree, child, leaves);
+                }
+            }
+            PageType::LeafTable => {
+                self.check_rows(tree, &page);
+                leaves.push(page_id);
+            }
+            other => self.record(Some(page_id), Some(tree), format!("{:?} page in a table tree", other)),
+        }
+    }
+
+    /// Children of an interior page in routing order, skipping entries that do not decode
+    fn interior_children(&mut self, tree: &str, page: &Page) -> Vec<PageId> {
+        let mut entries: Vec<(PageId, Value)> = Vec::new();
+        for slot_index in 0..page.slot_directory.slots.len() {
+            let Some(entry) = page.get_cell(slot_index) else {
+                continue;
+            };
+            match parse_interior_entry(entry) {
+                Ok(parsed) => entries.push(parsed),
+                Err(details) => self.record(Some(page.page_id), Some(tree), format!("Slot {}: {}", slot_index, details)),
+            }
+        }
+        if entries.is_empty() {
+            self.record(Some(page.page_id), Some(tree), "Interior page has no entries".to_string());
+        }
+        entries.sort_by(|(_, a), (_, b)| Page::compare_upper_bounds(a, b));
+        entries.into_iter().map(|(child, _)| child).collect()
+    }
+
+    /// Walk an append log's page chain from `root_page_id`: each page must be a decodable
+    /// leaf whose rows deserialize and which links back to the page before it
+    pub fn check_append_log(&mut self, tree: &str, root_page_id: PageId) {
+        self.report.trees_checked += 1;
+        let mut prev_page_id = None;
+        let mut next_page_id = Some(root_page_id);
+        while let Some(page_id) = next_page_id {
+            let Some(page) = self.load_page(tree, page_id) else {
+                return;
+            };
+            if page.page_type != PageType::LeafTable {
+                self.record(Some(page_id), Some(tree), format!("Append log page is a {:?} page", page.page_type));
+                return;
+            }
+            if page.prev_leaf_page_id != prev_page_id {
+                self.record(
+                    Some(page_id),
+                    Some(tree),
+                    format!("Links back to {:?} instead of {:?}", page.prev_leaf_page_id, prev_page_id),
+                );
+            }
+            self.check_rows(tree, &page);
+            prev_page_id = Some(page_id);
+            next_page_id = page.next_leaf_page_id;
+        }
+    }
+
+    fn check_rows(&mut self, tree: &str, page: &Page) {
+        for (slot_index, slot) in page.slot_directory.slots.iter().enumerate() {
+            let Some(cell) = page.get_cell(slot_index) else {
+                continue;
+            };
+            let row_bytes = match &slot.overflow_pointer {
+                Some(pointer) => {
+                    let Some(overflow_page) = self.load_page(tree, pointer.page_id) else {
+                        continue;
+                    };
+                    let cell = overflow_page
+                        .get_cell(0)
+                        .filter(|_| overflow_page.page_type == PageType::OverflowPage)
+                        .and_then(|cell| cell.get(..pointer.total_size as usize));
+                    match cell {
+                        Some(cell) => cell.to_vec(),
+                        None => {
+                            self.record(
+                                Some(page.page_id),
+                                Some(tree),
+                                format!("Slot {}: overflow page {} does not hold its cell", slot_index, pointer.page_id),
+                            );
+                            continue;
+                        }
+                    }
+                }
+                None => cell.to_vec(),
+            };
+            if let Err(e) = Row::from_bytes(&row_bytes) {
+                self.record(Some(page.page_id), Some(tree), format!("Slot {}: {}", slot_index, error_details(&e)));
+            }
+        }
+    }
+
+    /// Follow `next_leaf_page_id` from the first leaf and compare the chain with the
+    /// leaves reached from the root
+    fn check_leaf_chain(&mut self, tree: &str, leaves: &[PageId]) {
+        let Some(&first) = leaves.first() else {
+            return;
+        };
+        let mut chain = Vec::new();
+        let mut prev_page_id = None;
+        let mut next_page_id = Some(first);
+        // Stop one past the tree's leaf count so a cycle in the chain cannot loop forever
+        while let Some(page_id) = next_page_id
+            && chain.len() <= leaves.len()
+        {
+            let Some(page) = self.read_page(page_id) else {
+                break;
+            };
+            if page.prev_leaf_page_id != prev_page_id {
+                self.record(
+                    Some(page_id),
+                    Some(tree),
+                    format!("Leaf links back to {:?} instead of {:?}", page.prev_leaf_page_id, prev_page_id),
+                );
+            }
+            chain.push(page_id);
+            prev_page_id = Some(page_id);
+            next_page_id = page.next_leaf_page_id;
+        }
+        if chain != leaves {
+            self.record(
+                None,
+                Some(tree),
+                format!("Leaf chain {:?} does not match the leaves under the root {:?}", chain, leaves),
+            );
+        }
+    }
+
+    /// Read and decode a page the first time a tree reaches it. Pages out of range, on
+    /// the freelist, already reached or failing to decode are recorded and give `None`.
+    fn load_page(&mut self, tree: &str, page_id: PageId) -> Option<Page> {
+        if page_id == 0 || page_id > self.page_count {
+            self.record(
+                Some(page_id),
+                Some(tree),
+                format!("Page is out of range (the database has {} pages)", self.page_count),
+            );
+            return None;
+        }
+        if self.free_pages.contains(&page_id) {
+            self.record(Some(page_id), Some(tree), "Page is on the freelist".to_string());
+            return None;
+        }
+        if let Some(owner) = self.owners.get(&page_id) {
+            let details = if owner == tree {
+                "Page is reached twice".to_string()
+            } else {
+                format!("Page is also reached from '{}'", owner)
+            };
+            self.record(Some(page_id), Some(tree), details);
+            return None;
+        }
+        self.owners.insert(page_id, tree.to_string());
+        self.report.pages_checked += 1;
+
+        let page = match self.read_page_bytes(page_id).and_then(|bytes| Page::from_bytes(&bytes)) {
+            Ok(page) => page,
+            Err(e) => {
+                self.record(Some(page_id), Some(tree), error_details(&e));
+                return None;
+            }
+        };
+        for warning in &page.reconciliation_warnings {
+            self.record(Some(page_id), Some(tree), warning.clone());
+        }
+        Some(page)
+    }
+
+    /// Decode a page without recording it, for pages `load_page` has already reported on
+    fn read_page(&self, page_id: PageId) -> Option<Page> {
+        if page_id == 0 || page_id > self.page_count {
+            return None;
+        }
+        self.read_page_bytes(page_id).and_then(|bytes| Page::from_bytes(&bytes)).ok()
+    }
+
+    fn read_page_bytes(&self, page_id: PageId) -> Result<Vec<u8>, DatabaseError> {
+        let mut buffer = vec![0u8; self.page_size];
+        self.file.read_exact_at(&mut buffer, page_offset_with_size(page_id, self.page_size))?;
+        Ok(buffer)
+    }
+
+    fn record(&mut self, page_id: Option<PageId>, tree: Option<&str>, details: String) {
+        self.report.findings.push(IntegrityFinding {
+            page_id,
+            tree: tree.map(str::to_string),
+            details,
+        });
+    }
+
+    pub fn finish(self) -> IntegrityReport {
+        self.report
+    }
+}
+
+/// An error's reason without the page id a finding already carries
+fn error_details(error: &DatabaseError) -> String {
+    match error {
+        DatabaseError::CorruptedPage { reason, .. } => reason.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// `(child, upper bound)` of an interior entry: child u64 LE, key length u32 LE, key
+fn parse_interior_entry(entry: &[u8]) -> Result<(PageId, Value), String> {
+    let (Some(child), Some(key_length)) = (entry.get(..8), entry.get(8..12)) else {
+        return Err("Interior entry too short".to_string());
+    };
+    let child = PageId::from_le_bytes(child.try_into().expect("8-byte slice"));
+    let key_length = u32::from_le_bytes(key_length.try_into().expect("4-byte slice")) as usize;
+    let key_bytes = entry
+        .get(12..12 + key_length)
+        .ok_or_else(|| "Interior entry key data incomplete".to_string())?;
+    let key = Value::from_bytes(key_bytes).map_err(|e| e.to_string())?;
+    Ok((child, key))
+}
@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Page reads and writes issued to a database file since it was opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoStats {
+    /// Pages read from the file; B+ tree page cache hits are not counted
+    pub pages_read: u64,
+    pub bytes_written: u64,
+    pub full_page_writes: u64,
+    /// Writes that covered only the modified extents of a page
+    pub partial_page_writes: u64,
+}
+
+/// Counters shared by every B+ tree a `StorageManager` opens
+#[derive(Debug, Default)]
+pub struct IoCounters {
+    pages_read: AtomicU64,
+    bytes_written: AtomicU64,
+    full_page_writes: AtomicU64,
+    partial_page_writes: AtomicU64,
+}
+
+impl IoCounters {
+    pub fn record_page_read(&self) {
+        self.pages_read.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_full_write(&self, bytes: usize) {
+        self.full_page_writes.fetch_add(1, Ordering::Relaxed);
+        self.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_partial_write(&self, bytes: usize) {
+        self.partial_page_writes.fetch_add(1, Ordering::Relaxed);
+        self.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> IoStats {
+        IoStats {
+            pages_read: self.pages_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            full_page_writes: self.full_page_writes.load(Ordering::Relaxed),
+            partial_page_writes: self.partial_page_writes.load(Ordering::Relaxed),
+        }
+    }
+}
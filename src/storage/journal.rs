@@ -0,0 +1,73 @@
+use std::{collections::HashMap, io::SeekFrom, sync::Mutex};
+
+use crate::{
+    storage::{BAMBANG_HEADER_SIZE, page_offset_with_size, page_store::PageStore},
+    types::{PageId, error::DatabaseError},
+};
+
+/// Original images of the pages a transaction overwrites, kept in memory so rollback
+/// can put them back. Pages appended during the transaction are not saved: rollback
+/// truncates the file to its original length instead. Shared between the storage
+/// manager and the B+ trees it creates, which save a page before their first write to it.
+#[derive(Debug)]
+pub struct RollbackJournal {
+    page_size: usize,
+    original_len: u64,
+    original_page_count: PageId,
+    header: Vec<u8>,
+    pages: Mutex<HashMap<PageId, Vec<u8>>>,
+}
+
+impl RollbackJournal {
+    /// Start journaling `file`, capturing its header and length
+    pub fn begin(file: &mut dyn PageStore, page_size: usize) -> Result<Self, DatabaseError> {
+        let original_len = file.len()?;
+        let mut header = vec![0u8; BAMBANG_HEADER_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+        Ok(Self {
+            page_size,
+            original_len,
+            original_page_count: original_len.saturating_sub(BAMBANG_HEADER_SIZE as u64) / page_size as u64,
+            header,
+            pages: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Save the current image of `page_id` unless it is already saved or did not exist
+    /// when the transaction began. Call before every write to the page.
+    pub fn save_page(&self, file: &mut dyn PageStore, page_id: PageId) -> Result<(), DatabaseError> {
+        if page_id == 0 || page_id > self.original_page_count {
+            return Ok(());
+        }
+        let mut pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        if pages.contains_key(&page_id) {
+            return Ok(());
+        }
+        let mut image = vec![0u8; self.page_size];
+        file.seek(SeekFrom::Start(page_offset_with_size(page_id, self.page_size)))?;
+        file.read_exact(&mut image)?;
+        pages.insert(page_id, image);
+        Ok(())
+    }
+
+    /// Number of pages saved so far
+    pub fn saved_page_count(&self) -> usize {
+        self.pages.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Write every saved page and the original header back, drop pages appended since
+    /// the transaction began, and sync the result
+    pub fn restore(&self, file: &mut dyn PageStore) -> Result<(), DatabaseError> {
+        let pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        for (page_id, image) in pages.iter() {
+            file.seek(SeekFrom::Start(page_offset_with_size(*page_id, self.page_size)))?;
+            file.write_all(image)?;
+        }
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&self.header)?;
+        file.set_len(self.original_len)?;
+        file.sync()?;
+        Ok(())
+    }
+}
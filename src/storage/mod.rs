@@ -1,7 +1,40 @@
+use crate::types::{PAGE_SIZE, PageId};
+
+pub mod append_log;
 pub mod bplus_tree;
+pub mod column_usage;
+pub mod export;
+pub mod freelist;
 pub mod header;
+pub mod import;
+pub mod index;
+pub mod integrity;
+pub mod io_stats;
+pub mod journal;
+pub mod options;
+pub mod page_cache;
+pub mod page_image;
+pub mod page_store;
+pub mod row_locator;
 pub mod schema;
+pub mod schema_watch;
+pub mod sequence;
 pub mod storage_manager;
+pub mod workload;
 
 pub const BAMBANG_HEADER_SIZE: usize = 100;
+/// Root of the `sqlite_schema` B+ tree. It never moves and never joins the freelist, so
+/// opening a database always starts here.
+pub const SCHEMA_ROOT_PAGE_ID: PageId = 1;
 const BAMBANG_MAGIC: &[u8; 16] = b"BAMBANG DB v0.1\0";
+
+/// Byte offset of a page in a database file of the default page size, accounting for
+/// the file header
+pub fn page_offset(page_id: PageId) -> u64 {
+    page_offset_with_size(page_id, PAGE_SIZE)
+}
+
+/// Byte offset of a page in a database file whose pages are `page_size` bytes
+pub fn page_offset_with_size(page_id: PageId, page_size: usize) -> u64 {
+    BAMBANG_HEADER_SIZE as u64 + (page_id - 1) * page_size as u64
+}
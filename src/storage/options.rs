@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use crate::{
+    executor::{predicate::PredicateMode, subquery::DEFAULT_SUBQUERY_MEMORY_BUDGET},
+    storage::page_store::IoLog,
+    types::{PAGE_SIZE, value::ValueComparison},
+};
+
+/// Database size as seen by the quota check
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuotaUsage {
+    pub used_bytes: u64,
+    pub limit_bytes: Option<u64>,
+}
+
+impl QuotaUsage {
+    /// Fraction of the quota in use, or `None` when no quota is set
+    pub fn fraction(&self) -> Option<f64> {
+        self.limit_bytes
+            .map(|limit| self.used_bytes as f64 / limit as f64)
+    }
+}
+
+/// Callback invoked when database usage crosses the quota warning threshold
+pub type QuotaWarningHook = Arc<dyn Fn(&QuotaUsage) + Send + Sync>;
+
+/// Callback receiving the storage manager's informational messages, such as which file
+/// was opened or created
+pub type LogHook = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// How much of the schema `StorageManager::open_with_options` reads up front
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OpenMode {
+    /// Parse and validate every table's schema while opening
+    #[default]
+    Eager,
+    /// Only index table names, roots and where their schema rows live; a table's schema
+    /// is parsed, and any error in it reported, when the table is first used
+    Lazy,
+}
+
+/// When inserts make their writes durable with a sync of the database file. Inside a
+/// transaction `commit` is the only sync point whatever the mode, and `close` always
+/// syncs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Sync after every row. A row survives a crash once its insert returns, even within
+    /// a batch, which `insert_batch_into_table` then inserts row by row instead of
+    /// bulk-loading. Every row pays for a sync.
+    Always,
+    /// Sync once at the end of every insert call, so a batch is durable once
+    /// `insert_batch_into_table` returns and the sync is shared by all of its rows. A
+    /// crash before then may leave the batch partly written.
+    OnBatch,
+    /// Never sync on insert, as earlier versions did: rows reach the operating system
+    /// but survive a power loss only after `StorageManager::sync`, `commit` or `close`.
+    #[default]
+    Off,
+}
+
+/// Options applied when opening a database through `StorageManager::open_with_options`
+#[derive(Clone)]
+pub struct StorageManagerOptions {
+    /// Hard cap on the database file size in bytes. When set it is persisted in the
+    /// header; when `None` any quota already stored in the file stays in effect.
+    pub max_database_size: Option<u64>,
+    /// Fraction of the quota (0.0 - 1.0) at which `on_quota_warning` fires
+    pub quota_warning_threshold: f64,
+    pub on_quota_warning: Option<QuotaWarningHook>,
+    /// Bytes an `IN (other table)` predicate may buffer before spilling values to disk
+    pub subquery_memory_budget: usize,
+    /// Sequence values reserved per write to the schema; a crash burns at most this many
+    pub sequence_cache_size: u32,
+    /// Let row validation replace mismatched values with their lossless cast, e.g. the
+    /// text `'42'` in an INTEGER column. Lossy casts and BLOB columns still error.
+    pub implicit_coercion: bool,
+    /// Whether predicates and PRIMARY KEY/UNIQUE checks relate values across types
+    pub value_comparison: ValueComparison,
+    /// Whether predicates follow SQL's three-valued logic for NULL. Defaults to
+    /// `PredicateMode::Legacy`, the plain boolean behaviour of earlier versions.
+    pub predicate_mode: PredicateMode,
+    /// Write only the modified byte ranges of a page rather than all of it. The file
+    /// contents are the same either way; disable to compare against full-page writes.
+    pub partial_page_writes: bool,
+    /// Page size in bytes for a newly created database, a power of two from 512 to
+    /// 65536. An existing file keeps the page size recorded in its header.
+    pub page_size: usize,
+    /// Check page and B+ tree invariants on every write and split, failing with
+    /// `InternalInvariant` where the corruption happens instead of in a later scan.
+    /// On in the crate's own unit tests; costs a decode of every written page.
+    pub paranoid_checks: bool,
+    /// Whether table schemas are parsed while opening or on first use
+    pub open_mode: OpenMode,
+    /// Record every write, sync and truncation the database file receives, for tests
+    /// that check durability ordering
+    pub io_log: Option<IoLog>,
+    /// Fraction of each page's cell area a batch insert fills before starting the next
+    /// page, clamped to 0.1 - 1.0. Lower values leave room for later inserts between
+    /// the batch's keys without splitting.
+    pub bulk_load_fill_factor: f64,
+    /// Save the session's column usage counters in `sqlite_schema` on `close` and pick
+    /// them up again on open, so they accumulate across sessions. Off by default, which
+    /// keeps the counters per session.
+    pub persist_column_usage: bool,
+    /// How often inserts sync the database file
+    pub sync_mode: SyncMode,
+    /// Where informational messages go. `None`, the default, drops them, so the
+    /// library never writes to stdout on its own.
+    pub on_log: Option<LogHook>,
+    /// Fragmentation ratio (0.0 - 1.0) above which a page that lost or shrank a cell in a
+    /// delete or update is compacted before it is written back. `None` leaves deleted
+    /// cells' bytes on the page until it is compacted some other way, e.g. by
+    /// `StorageManager::vacuum_table`.
+    pub compaction_threshold: Option<f64>,
+}
+
+impl Default for StorageManagerOptions {
+    fn default() -> Self {
+        Self {
+            max_database_size: None,
+            quota_warning_threshold: 0.9,
+            on_quota_warning: None,
+            subquery_memory_budget: DEFAULT_SUBQUERY_MEMORY_BUDGET,
+            sequence_cache_size: 32,
+            implicit_coercion: false,
+            value_comparison: ValueComparison::Coercive,
+            predicate_mode: PredicateMode::Legacy,
+            partial_page_writes: true,
+            page_size: PAGE_SIZE,
+            paranoid_checks: cfg!(test),
+            open_mode: OpenMode::Eager,
+            io_log: None,
+            bulk_load_fill_factor: 0.9,
+            persist_column_usage: false,
+            sync_mode: SyncMode::Off,
+            on_log: None,
+            compaction_threshold: Some(0.5),
+        }
+    }
+}
+
+impl StorageManagerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_database_size(mut self, max_bytes: u64) -> Self {
+        self.max_database_size = Some(max_bytes);
+        self
+    }
+
+    pub fn with_quota_warning<F>(mut self, threshold: f64, hook: F) -> Self
+    where
+        F: Fn(&QuotaUsage) + Send + Sync + 'static,
+    {
+        self.quota_warning_threshold = threshold;
+        self.on_quota_warning = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn with_subquery_memory_budget(mut self, bytes: usize) -> Self {
+        self.subquery_memory_budget = bytes;
+        self
+    }
+
+    pub fn with_sequence_cache_size(mut self, values: u32) -> Self {
+        self.sequence_cache_size = values;
+        self
+    }
+
+    pub fn with_implicit_coercion(mut self, enabled: bool) -> Self {
+        self.implicit_coercion = enabled;
+        self
+    }
+
+    pub fn with_value_comparison(mut self, mode: ValueComparison) -> Self {
+        self.value_comparison = mode;
+        self
+    }
+
+    pub fn with_predicate_mode(mut self, mode: PredicateMode) -> Self {
+        self.predicate_mode = mode;
+        self
+    }
+
+    pub fn with_partial_page_writes(mut self, enabled: bool) -> Self {
+        self.partial_page_writes = enabled;
+        self
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn with_paranoid_checks(mut self, enabled: bool) -> Self {
+        self.paranoid_checks = enabled;
+        self
+    }
+
+    pub fn with_open_mode(mut self, mode: OpenMode) -> Self {
+        self.open_mode = mode;
+        self
+    }
+
+    pub fn with_io_log(mut self, io_log: IoLog) -> Self {
+        self.io_log = Some(io_log);
+        self
+    }
+
+    pub fn with_bulk_load_fill_factor(mut self, fill_factor: f64) -> Self {
+        self.bulk_load_fill_factor = fill_factor;
+        self
+    }
+
+    pub fn with_persist_column_usage(mut self, enabled: bool) -> Self {
+        self.persist_column_usage = enabled;
+        self
+    }
+
+    pub fn with_sync_mode(mut self, mode: SyncMode) -> Self {
+        self.sync_mode = mode;
+        self
+    }
+
+    pub fn with_log<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_log = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn with_compaction_threshold(mut self, threshold: Option<f64>) -> Self {
+        self.compaction_threshold = threshold;
+        self
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::types::{PageId, page::Page};
+
+/// Number of pages a B+ tree keeps cached unless configured otherwise
+pub const DEFAULT_PAGE_CACHE_CAPACITY: usize = 256;
+
+/// Source of the time a cached page was last used, replaceable so tests can age pages
+/// without waiting
+pub trait CacheClock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The wall clock
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl CacheClock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// What a page cache holds and what it has let go of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub resident_pages: usize,
+    /// Memory held by the resident pages, as `Page::memory_footprint` counts it
+    pub resident_bytes: usize,
+    /// Pages evicted to stay within the capacity
+    pub evicted_by_capacity: u64,
+    /// Pages evicted for going unused longer than an age limit
+    pub evicted_by_age: u64,
+}
+
+#[derive(Debug)]
+struct Frame {
+    page: Page,
+    tick: u64,
+    last_access: Instant,
+}
+
+/// Periodic age-based eviction, checked whenever the cache is used
+#[derive(Debug)]
+struct Sweep {
+    interval: Duration,
+    max_age: Duration,
+    last_run: Instant,
+}
+
+/// Bounded page cache that evicts the least-recently-used page once full, and optionally
+/// pages left unused for too long
+#[derive(Debug)]
+pub struct PageCache {
+    capacity: usize,
+    pages: HashMap<PageId, Frame>,
+    recency: BTreeMap<u64, PageId>,
+    clock: u64,
+    time: Arc<dyn CacheClock>,
+    sweep: Option<Sweep>,
+    evicted_by_capacity: u64,
+    evicted_by_age: u64,
+}
+
+impl Default for PageCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_PAGE_CACHE_CAPACITY)
+    }
+}
+
+impl PageCache {
+    /// Create a cache holding at most `capacity` pages (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            pages: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+            time: Arc::new(SystemClock),
+            sweep: None,
+            evicted_by_capacity: 0,
+            evicted_by_age: 0,
+        }
+    }
+
+    /// Take page ages from `time` instead of the wall clock
+    pub fn set_clock(&mut self, time: Arc<dyn CacheClock>) {
+        let now = time.now();
+        for frame in self.pages.values_mut() {
+            frame.last_access = now;
+        }
+        if let Some(sweep) = &mut self.sweep {
+            sweep.last_run = now;
+        }
+        self.time = time;
+    }
+
+    /// Every `interval`, evict the clean pages unused for longer than `max_age`. The
+    /// sweep runs from `sweep_if_due`, so an idle cache keeps its pages until next used.
+    pub fn set_sweep(&mut self, interval: Duration, max_age: Duration) {
+        self.sweep = Some(Sweep {
+            interval,
+            max_age,
+            last_run: self.time.now(),
+        });
+    }
+
+    /// Run the periodic sweep if its interval has passed, returning the evicted pages
+    pub fn sweep_if_due(&mut self) -> Vec<Page> {
+        let now = self.time.now();
+        let max_age = match &mut self.sweep {
+            Some(sweep) if now.duration_since(sweep.last_run) >= sweep.interval => {
+                sweep.last_run = now;
+                sweep.max_age
+            }
+            _ => return Vec::new(),
+        };
+        self.evict_older_than(max_age, false)
+    }
+
+    /// Evict the pages not used within the last `age`, returning them. Dirty pages stay
+    /// cached unless `include_dirty` is set, in which case the caller writes them back.
+    pub fn evict_older_than(&mut self, age: Duration, include_dirty: bool) -> Vec<Page> {
+        let now = self.time.now();
+        let cold: Vec<PageId> = self
+            .recency
+            .values()
+            .copied()
+            .filter(|page_id| {
+                let frame = &self.pages[page_id];
+                now.duration_since(frame.last_access) > age && (include_dirty || !frame.page.is_dirty)
+            })
+            .collect();
+        let evicted: Vec<Page> = cold.iter().filter_map(|page_id| self.remove(page_id)).collect();
+        self.evicted_by_age += evicted.len() as u64;
+        evicted
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            resident_pages: self.pages.len(),
+            resident_bytes: self.pages.values().map(|frame| frame.page.memory_footprint()).sum(),
+            evicted_by_capacity: self.evicted_by_capacity,
+            evicted_by_age: self.evicted_by_age,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity, returning any pages evicted to fit the new bound
+    pub fn set_capacity(&mut self, capacity: usize) -> Vec<Page> {
+        self.capacity = capacity.max(1);
+        let mut evicted = Vec::new();
+        while self.pages.len() > self.capacity {
+            match self.evict_lru() {
+                Some(page) => evicted.push(page),
+                None => break,
+            }
+        }
+        evicted
+    }
+
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    pub fn contains_key(&self, page_id: &PageId) -> bool {
+        self.pages.contains_key(page_id)
+    }
+
+    /// Look up a page and mark it as most recently used
+    pub fn get(&mut self, page_id: &PageId) -> Option<&Page> {
+        self.touch(*page_id);
+        self.pages.get(page_id).map(|frame| &frame.page)
+    }
+
+    /// Mutable lookup; the page is marked as most recently used
+    pub fn get_mut(&mut self, page_id: &PageId) -> Option<&mut Page> {
+        self.touch(*page_id);
+        self.pages.get_mut(page_id).map(|frame| &mut frame.page)
+    }
+
+    /// Look up a page without affecting its recency
+    pub fn peek(&self, page_id: &PageId) -> Option<&Page> {
+        self.pages.get(page_id).map(|frame| &frame.page)
+    }
+
+    /// Insert or replace a page. Returns the page evicted to make room, if any.
+    pub fn insert(&mut self, page_id: PageId, page: Page) -> Option<Page> {
+        let tick = self.next_tick();
+        let last_access = self.time.now();
+        if let Some(old) = self.pages.insert(page_id, Frame { page, tick, last_access }) {
+            self.recency.remove(&old.tick);
+        }
+        self.recency.insert(tick, page_id);
+
+        if self.pages.len() > self.capacity {
+            self.evict_lru()
+        } else {
+            None
+        }
+    }
+
+    pub fn remove(&mut self, page_id: &PageId) -> Option<Page> {
+        let frame = self.pages.remove(page_id)?;
+        self.recency.remove(&frame.tick);
+        Some(frame.page)
+    }
+
+    /// Drop every cached page, returning them so dirty ones can be written back
+    pub fn drain(&mut self) -> Vec<Page> {
+        self.recency.clear();
+        self.pages.drain().map(|(_, frame)| frame.page).collect()
+    }
+
+    /// Page ids from least to most recently used
+    pub fn page_ids(&self) -> Vec<PageId> {
+        self.recency.values().copied().collect()
+    }
+
+    fn touch(&mut self, page_id: PageId) {
+        if !self.pages.contains_key(&page_id) {
+            return;
+        }
+        let tick = self.next_tick();
+        let now = self.time.now();
+        if let Some(frame) = self.pages.get_mut(&page_id) {
+            self.recency.remove(&frame.tick);
+            frame.tick = tick;
+            frame.last_access = now;
+        }
+        self.recency.insert(tick, page_id);
+    }
+
+    fn evict_lru(&mut self) -> Option<Page> {
+        let (_, page_id) = self.recency.pop_first()?;
+        self.evicted_by_capacity += 1;
+        self.pages.remove(&page_id).map(|frame| frame.page)
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    storage::{page_offset_with_size, schema::TableSchema},
+    types::{
+        PageId,
+        error::DatabaseError,
+        page::{Page, PageType},
+    },
+};
+
+// A page image stream is laid out as:
+//   PAGE_IMAGE_MAGIC
+//   u32 LE manifest length, manifest JSON
+//   `page_ids.len()` raw pages of `page_size` bytes, in manifest order
+//   u32 LE CRC32 digest over the raw pages
+
+const PAGE_IMAGE_MAGIC: &[u8; 16] = b"BAMBANG PAGES\0\0\0";
+
+/// Bumped whenever the stream layout or the on-disk page format changes
+pub const PAGE_IMAGE_FORMAT_VERSION: u32 = 1;
+
+/// Describes the pages that follow it in a page image stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageImageManifest {
+    pub format_version: u32,
+    pub page_size: usize,
+    pub schema_format: u32,
+    pub schema: TableSchema,
+    /// Source page ids in stream order; the first is the root
+    pub page_ids: Vec<PageId>,
+}
+
+/// What a page image export wrote or an import read
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageImageSummary {
+    pub table_name: String,
+    pub page_count: usize,
+    /// CRC32 over the page images as stored in the source database
+    pub digest: u32,
+}
+
+/// Raw images of a set of pages, read lazily in order
+pub struct TablePages {
+    file: File,
+    page_ids: std::vec::IntoIter<PageId>,
+    page_size: usize,
+}
+
+impl TablePages {
+    pub fn new(file: File, page_ids: Vec<PageId>, page_size: usize) -> Self {
+        Self {
+            file,
+            page_ids: page_ids.into_iter(),
+            page_size,
+        }
+    }
+
+    /// Ids of the pages not yet yielded
+    pub fn page_ids(&self) -> &[PageId] {
+        self.page_ids.as_slice()
+    }
+}
+
+impl Iterator for TablePages {
+    type Item = Result<(PageId, Vec<u8>), DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let page_id = self.page_ids.next()?;
+        let mut buffer = vec![0u8; self.page_size];
+        let read = self
+            .file
+            .seek(SeekFrom::Start(page_offset_with_size(page_id, self.page_size)))
+            .and_then(|_| self.file.read_exact(&mut buffer));
+        Some(read.map(|_| (page_id, buffer)).map_err(DatabaseError::from))
+    }
+}
+
+pub fn write_manifest(writer: &mut impl Write, manifest: &PageImageManifest) -> Result<(), DatabaseError> {
+    let json = serde_json::to_vec(manifest).map_err(|e| DatabaseError::SerializationError {
+        details: e.to_string(),
+    })?;
+    writer.write_all(PAGE_IMAGE_MAGIC)?;
+    writer.write_all(&(json.len() as u32).to_le_bytes())?;
+    writer.write_all(&json)?;
+    Ok(())
+}
+
+/// Read the manifest and check that its pages can be copied into a database with the
+/// given page size and schema format
+pub fn read_manifest(
+    reader: &mut impl Read,
+    page_size: usize,
+    schema_format: u32,
+) -> Result<PageImageManifest, DatabaseError> {
+    let mut magic = [0u8; 16];
+    reader.read_exact(&mut magic)?;
+    if &magic != PAGE_IMAGE_MAGIC {
+        return Err(DatabaseError::IncompatiblePageImage {
+            details: "stream is not a page image export".to_string(),
+        });
+    }
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length)?;
+    let mut json = vec![0u8; u32::from_le_bytes(length) as usize];
+    reader.read_exact(&mut json)?;
+    let manifest: PageImageManifest =
+        serde_json::from_slice(&json).map_err(|e| DatabaseError::SerializationError {
+            details: e.to_string(),
+        })?;
+
+    let mismatch = if manifest.format_version != PAGE_IMAGE_FORMAT_VERSION {
+        Some(format!(
+            "format version {} (expected {})",
+            manifest.format_version, PAGE_IMAGE_FORMAT_VERSION
+        ))
+    } else if manifest.page_size != page_size {
+        Some(format!("page size {} (expected {})", manifest.page_size, page_size))
+    } else if manifest.schema_format != schema_format {
+        Some(format!(
+            "schema format {} (expected {})",
+            manifest.schema_format, schema_format
+        ))
+    } else if manifest.page_ids.is_empty() {
+        Some("no pages".to_string())
+    } else {
+        None
+    };
+    match mismatch {
+        Some(details) => Err(DatabaseError::IncompatiblePageImage { details }),
+        None => Ok(manifest),
+    }
+}
+
+/// Renumber a copied page: its own id, its parent and sibling pointers, and the child
+/// pointers of interior pages all move to their new ids
+pub fn rewrite_page_ids(page: &mut Page, new_ids: &HashMap<PageId, PageId>) -> Result<(), DatabaseError> {
+    let old_page_id = page.page_id;
+    let remap = |page_id: PageId| {
+        new_ids
+            .get(&page_id)
+            .copied()
+            .ok_or_else(|| DatabaseError::CorruptedPage {
+                page_id: old_page_id,
+                reason: format!("Points at page {} outside the copied table", page_id),
+            })
+    };
+
+    page.page_id = remap(old_page_id)?;
+    // The root's parent pointer may be stale, so parents outside the copy are dropped
+    page.parent_page_id = page.parent_page_id.and_then(|parent| new_ids.get(&parent).copied());
+    page.next_leaf_page_id = page.next_leaf_page_id.map(remap).transpose()?;
+    page.prev_leaf_page_id = page.prev_leaf_page_id.map(remap).transpose()?;
+
+    match page.page_type {
+        PageType::LeafTable => {
+            if page.slot_directory.slots.iter().any(|slot| slot.is_overflow) {
+                return Err(DatabaseError::IncompatiblePageImage {
+                    details: format!("page {} has overflow cells, which cannot be copied physically", old_page_id),
+                });
+            }
+        }
+        PageType::InteriorTable => {
+            for i in 0..page.slot_directory.slots.len() {
+                let Some(entry) = page.get_cell(i) else {
+                    continue;
+                };
+                if entry.len() < 8 {
+                    return Err(DatabaseError::CorruptedPage {
+                        page_id: old_page_id,
+                        reason: format!("Interior entry {} is too short", i),
+                    });
+                }
+                let mut entry = entry.to_vec();
+                let child = PageId::from_le_bytes(entry[..8].try_into().expect("length checked"));
+                entry[..8].copy_from_slice(&remap(child)?.to_le_bytes());
+                let row_id = page.slot_directory.slots[i].row_id;
+                page.update_cell(i, &entry, row_id)?;
+            }
+        }
+        _ => {
+            return Err(DatabaseError::CorruptedPage {
+                page_id: old_page_id,
+                reason: "Invalid page type in B+ tree".to_string(),
+            });
+        }
+    }
+    page.update_checksum();
+    Ok(())
+}
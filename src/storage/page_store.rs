@@ -0,0 +1,280 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    storage::BAMBANG_HEADER_SIZE,
+    types::{PageId, error::DatabaseError},
+};
+
+/// Storage a database file is read from and written to. `File` is the real one; tests
+/// wrap it in a `RecordingStore` to see the order writes and syncs reach it in.
+pub trait PageStore: Read + Write + Seek + Send {
+    /// Current length in bytes
+    fn len(&self) -> io::Result<u64>;
+
+    fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()>;
+
+    /// Make every write so far durable
+    fn sync(&mut self) -> io::Result<()>;
+
+    /// Fill `buf` from `offset` without needing exclusive access. Moves the same cursor
+    /// `Seek` does.
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()>;
+}
+
+impl PageStore for File {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.sync_all()
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let mut file = self;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    }
+}
+
+impl<S: PageStore + ?Sized> PageStore for Box<S> {
+    fn len(&self) -> io::Result<u64> {
+        (**self).len()
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        (**self).set_len(len)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        (**self).sync()
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        (**self).read_exact_at(buf, offset)
+    }
+}
+
+/// Open the database file at `path` for reading and writing, recording into `io_log`
+/// when one is given
+pub fn open_store(path: &Path, io_log: Option<&IoLog>, page_size: usize) -> Result<Box<dyn PageStore>, DatabaseError> {
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    Ok(match io_log {
+        Some(io_log) => Box::new(RecordingStore::new(file, io_log.clone(), page_size)),
+        None => Box::new(file),
+    })
+}
+
+/// A store operation as seen by a `RecordingStore`. Reads are not recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoOp {
+    /// Bytes written within the file header
+    HeaderWrite { offset: u64, len: usize },
+    /// Bytes written within a page, possibly only some of it
+    PageWrite { page_id: PageId, offset: u64, len: usize },
+    Sync,
+    SetLen { len: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoEvent {
+    /// Position in the log, counted across every store sharing it
+    pub seq: usize,
+    pub op: IoOp,
+}
+
+/// Operations recorded by every `RecordingStore` sharing it, in the order they happened
+#[derive(Debug, Clone, Default)]
+pub struct IoLog {
+    events: Arc<Mutex<Vec<IoEvent>>>,
+}
+
+impl IoLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record(&self, op: IoOp) {
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = events.len();
+        events.push(IoEvent { seq, op });
+    }
+
+    pub fn events(&self) -> Vec<IoEvent> {
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Sequence number the next operation will get
+    pub fn next_seq(&self) -> usize {
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Events from sequence number `seq` on
+    pub fn events_since(&self, seq: usize) -> Vec<IoEvent> {
+        self.events().split_off(seq)
+    }
+
+    /// Sequence numbers of every sync
+    pub fn syncs(&self) -> Vec<usize> {
+        self.events()
+            .iter()
+            .filter(|event| event.op == IoOp::Sync)
+            .map(|event| event.seq)
+            .collect()
+    }
+
+    /// Sequence numbers of every write touching `page_id`
+    pub fn page_writes(&self, page_id: PageId) -> Vec<usize> {
+        self.events()
+            .iter()
+            .filter(|event| matches!(event.op, IoOp::PageWrite { page_id: written, .. } if written == page_id))
+            .map(|event| event.seq)
+            .collect()
+    }
+
+    /// Distinct pages written from sequence number `seq` on, in order of first write
+    pub fn pages_written_since(&self, seq: usize) -> Vec<PageId> {
+        let mut pages = Vec::new();
+        for event in self.events_since(seq) {
+            if let IoOp::PageWrite { page_id, .. } = event.op
+                && !pages.contains(&page_id)
+            {
+                pages.push(page_id);
+            }
+        }
+        pages
+    }
+}
+
+/// Passes every operation through to `inner`, logging writes, syncs and truncations
+pub struct RecordingStore<S: PageStore> {
+    inner: S,
+    io_log: IoLog,
+    page_size: usize,
+}
+
+impl<S: PageStore> RecordingStore<S> {
+    /// `page_size` is used to tell which page a write lands in
+    pub fn new(inner: S, io_log: IoLog, page_size: usize) -> Self {
+        Self { inner, io_log, page_size }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: PageStore> Read for RecordingStore<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<S: PageStore> Seek for RecordingStore<S> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+impl<S: PageStore> Write for RecordingStore<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let offset = self.inner.stream_position()?;
+        let len = self.inner.write(buf)?;
+        let header_size = BAMBANG_HEADER_SIZE as u64;
+        let op = if offset < header_size {
+            IoOp::HeaderWrite { offset, len }
+        } else {
+            let page_id = (offset - header_size) / self.page_size as u64 + 1;
+            IoOp::PageWrite { page_id, offset, len }
+        };
+        self.io_log.record(op);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: PageStore> PageStore for RecordingStore<S> {
+    fn len(&self) -> io::Result<u64> {
+        self.inner.len()
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        self.inner.set_len(len)?;
+        self.io_log.record(IoOp::SetLen { len });
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.inner.sync()?;
+        self.io_log.record(IoOp::Sync);
+        Ok(())
+    }
+
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.inner.read_exact_at(buf, offset)
+    }
+}
+
+/// Panic unless the event at `barrier_seq` is a sync and every page in `pages` was
+/// written at least once before it and not again after it
+pub fn assert_sync_after_writes(io_log: &IoLog, pages: &[PageId], barrier_seq: usize) {
+    let events = io_log.events();
+    let barrier = events
+        .get(barrier_seq)
+        .unwrap_or_else(|| panic!("no operation {} in a log of {}", barrier_seq, events.len()));
+    assert_eq!(barrier.op, IoOp::Sync, "operation {} is not a sync", barrier_seq);
+    for page_id in pages {
+        let writes = io_log.page_writes(*page_id);
+        assert!(
+            writes.iter().any(|seq| *seq < barrier_seq),
+            "page {} was not written before the sync at {}",
+            page_id,
+            barrier_seq
+        );
+        if let Some(late) = writes.iter().find(|seq| **seq > barrier_seq) {
+            panic!("page {} was written at {}, after the sync at {}", page_id, late, barrier_seq);
+        }
+    }
+}
+
+/// Panic unless something was recorded from `from_seq` on and the last operation is a
+/// sync, leaving no write after it
+pub fn assert_ends_with_sync(io_log: &IoLog, from_seq: usize) {
+    let events = io_log.events_since(from_seq);
+    let last = events.last().unwrap_or_else(|| panic!("nothing recorded since {}", from_seq));
+    assert_eq!(last.op, IoOp::Sync, "last operation is {:?}, not a sync", last.op);
+}
+
+/// Panic unless the first write to `page_id` from `from_seq` on follows a header write,
+/// as pages allocated by growing the file must be
+pub fn assert_header_written_before(io_log: &IoLog, page_id: PageId, from_seq: usize) {
+    let events = io_log.events_since(from_seq);
+    let first_page_write = events
+        .iter()
+        .find(|event| matches!(event.op, IoOp::PageWrite { page_id: written, .. } if written == page_id))
+        .unwrap_or_else(|| panic!("page {} was not written since {}", page_id, from_seq));
+    assert!(
+        events
+            .iter()
+            .any(|event| event.seq < first_page_write.seq && matches!(event.op, IoOp::HeaderWrite { .. })),
+        "page {} was written at {} before any header write",
+        page_id,
+        first_page_write.seq
+    );
+}
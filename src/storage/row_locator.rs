@@ -0,0 +1,57 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::types::{RowId, value::Value};
+
+/// B+ tree keys of a table's rows by rowid, so rows can be fetched by rowid with point
+/// lookups rather than a scan. Built by one scan of the table, it stays valid while the
+/// database's change counter does not move.
+#[derive(Debug)]
+pub struct RowLocator {
+    change_counter: u32,
+    keys: HashMap<RowId, Value>,
+}
+
+impl RowLocator {
+    pub fn new(change_counter: u32, keys: HashMap<RowId, Value>) -> Self {
+        Self { change_counter, keys }
+    }
+
+    /// Key of the row stamped with `row_id`, or `None` if no row has it
+    pub fn key(&self, row_id: RowId) -> Option<&Value> {
+        self.keys.get(&row_id)
+    }
+}
+
+/// Row locators of the tables read by rowid so far, keyed by table name
+#[derive(Debug, Default)]
+pub struct RowLocatorCache {
+    locators: Mutex<HashMap<String, Arc<RowLocator>>>,
+}
+
+impl RowLocatorCache {
+    /// The locator of `table`, if one was built at `change_counter`
+    pub fn get(&self, table: &str, change_counter: u32) -> Option<Arc<RowLocator>> {
+        let locators = self.locators.lock().unwrap_or_else(|e| e.into_inner());
+        locators
+            .get(table)
+            .filter(|locator| locator.change_counter == change_counter)
+            .cloned()
+    }
+
+    pub fn insert(&self, table: &str, locator: Arc<RowLocator>) {
+        let mut locators = self.locators.lock().unwrap_or_else(|e| e.into_inner());
+        locators.insert(table.to_string(), locator);
+    }
+
+    pub fn remove(&self, table: &str) {
+        self.locators.lock().unwrap_or_else(|e| e.into_inner()).remove(table);
+    }
+
+    /// Forget every locator, e.g. after a rollback brought back an earlier change counter
+    pub fn clear(&self) {
+        self.locators.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
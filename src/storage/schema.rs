@@ -1,12 +1,20 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, sync::OnceLock};
 use serde::{Deserialize, Serialize};
-use crate::types::{
-    value::{DataType, Value},
-    error::DatabaseError,
-    row::Row,
-    PageId,
+use crate::{
+    executor::predicate::{Predicate, PredicateMode, TriBool},
+    types::{
+        value::{DataType, Value, ValueComparison},
+        error::DatabaseError,
+        row::Row,
+        PageId,
+    },
 };
 
+/// Most columns a table may have. Rows and schema entries could hold more, but each
+/// column costs a `sqlite_schema` entry and every row a value, so the limit turns a
+/// runaway generated schema into an error at CREATE TABLE rather than slow opens later.
+pub const MAX_COLUMNS: usize = 2048;
+
 /// Represents a column definition in a table schema
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ColumnSchema {
@@ -17,6 +25,30 @@ pub struct ColumnSchema {
     pub default_value: Option<Value>,
     pub primary_key: bool,
     pub unique: bool,
+    /// `CHECK` condition every stored row must not make false. It may name any column
+    /// of the row, and is stored as the text it displays as.
+    #[serde(default, with = "check_text")]
+    pub check: Option<Predicate>,
+}
+
+/// Serde form of a CHECK condition: its SQL text, which parses back to the predicate
+mod check_text {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use crate::executor::predicate::Predicate;
+
+    pub fn serialize<S: Serializer>(check: &Option<Predicate>, serializer: S) -> Result<S::Ok, S::Error> {
+        match check {
+            Some(predicate) => serializer.serialize_some(&predicate.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Predicate>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| Predicate::parse(&text).map_err(D::Error::custom))
+            .transpose()
+    }
 }
 
 impl ColumnSchema {
@@ -29,6 +61,7 @@ impl ColumnSchema {
             default_value: None,
             primary_key: false,
             unique: false,
+            check: None,
         }
     }
 
@@ -53,9 +86,60 @@ impl ColumnSchema {
         self
     }
 
+    pub fn with_check(mut self, check: Predicate) -> Self {
+        self.check = Some(check);
+        self
+    }
+
+    /// Lossless cast of `value` to this column's type, unless the column is a BLOB
+    fn implicit_cast(&self, value: &Value) -> Option<Value> {
+        if self.data_type == DataType::Blob {
+            return None;
+        }
+        value.cast(&self.data_type).ok()
+    }
+
+    /// Error for a value that does not fit this column, hinting at a cast when one exists
+    pub fn type_mismatch(&self, value: &Value) -> DatabaseError {
+        const PREVIEW_CHARS: usize = 32;
+        let rendered = value.to_string();
+        let mut preview: String = rendered.chars().take(PREVIEW_CHARS).collect();
+        if preview.len() < rendered.len() {
+            preview.push_str("...");
+        }
+        let preview = match value {
+            Value::Text(_) => format!("'{}'", preview),
+            _ => preview,
+        };
+        let hint = match self.implicit_cast(value) {
+            Some(_) => format!(
+                "; hint: value parses as {}; enable implicit coercion or cast explicitly",
+                self.data_type
+            ),
+            None => String::new(),
+        };
+        DatabaseError::ColumnTypeMismatch {
+            column: self.name.clone(),
+            expected: self.data_type.clone(),
+            actual: value.data_type(),
+            preview,
+            hint,
+        }
+    }
+
+    /// Position of the column within its table's PRIMARY KEY, as stored in the column
+    /// entry of `sqlite_schema`: the entry holds 0 for columns outside the key, otherwise
+    /// the 1-based place of the column in the key
+    pub fn primary_key_ordinal(row: &Row) -> Option<usize> {
+        match row.values.get(7) {
+            Some(Value::Integer(ordinal)) if *ordinal > 0 => Some(*ordinal as usize - 1),
+            _ => None,
+        }
+    }
+
     /// Convert column schema to a row for storage in sqlite_schema
     pub fn to_schema_row(&self, table_name: &str) -> Row {
-        Row::new(vec![
+        let mut row = Row::new(vec![
             Value::Text("column".to_string()),
             Value::Text(self.name.clone()),
             Value::Text(table_name.to_string()),
@@ -70,7 +154,12 @@ impl ColumnSchema {
             ),
             Value::Integer(if self.primary_key { 1 } else { 0 }),
             Value::Integer(if self.unique { 1 } else { 0 }),
-        ])
+        ]);
+        // Only a column with a CHECK constraint has the extra field
+        if let Some(check) = &self.check {
+            row.values.push(Value::Text(check.to_string()));
+        }
+        row
     }
 
     /// Create column schema from a schema row
@@ -130,6 +219,14 @@ impl ColumnSchema {
             }),
         };
 
+        let check = match row.values.get(9) {
+            Some(Value::Text(check)) => Some(Predicate::parse(check)?),
+            None | Some(Value::Null) => None,
+            Some(_) => return Err(DatabaseError::CorruptedDatabase {
+                reason: "Invalid check constraint in schema".to_string(),
+            }),
+        };
+
         Ok(Self {
             name,
             data_type,
@@ -138,10 +235,136 @@ impl ColumnSchema {
             default_value,
             primary_key,
             unique,
+            check,
         })
     }
 }
 
+/// A column whose non-NULL values must each match a row of another table, as declared
+/// by `REFERENCES ref_table(ref_column)` or `FOREIGN KEY (column) REFERENCES ...`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForeignKey {
+    /// Referencing column of this table
+    pub column: String,
+    pub ref_table: String,
+    /// PRIMARY KEY or UNIQUE column of `ref_table` the values are matched against
+    pub ref_column: String,
+}
+
+impl ForeignKey {
+    pub fn new(column: String, ref_table: String, ref_column: String) -> Self {
+        Self {
+            column,
+            ref_table,
+            ref_column,
+        }
+    }
+
+    /// The referenced table and column, like `customers.id`
+    pub fn reference(&self) -> String {
+        format!("{}.{}", self.ref_table, self.ref_column)
+    }
+
+    /// The foreign key a column entry of `sqlite_schema` declares. Such an entry has the
+    /// referenced table and column after the CHECK field, which is NULL if it has no CHECK.
+    pub fn from_schema_row(row: &Row) -> Result<Option<Self>, DatabaseError> {
+        match (row.values.get(1), row.values.get(10), row.values.get(11)) {
+            (_, None, None) => Ok(None),
+            (Some(Value::Text(column)), Some(Value::Text(ref_table)), Some(Value::Text(ref_column))) => {
+                Ok(Some(Self::new(column.clone(), ref_table.clone(), ref_column.clone())))
+            }
+            _ => Err(DatabaseError::CorruptedDatabase {
+                reason: "Invalid foreign key in schema".to_string(),
+            }),
+        }
+    }
+}
+
+/// Row metadata that queries can read like a column. Pseudo-columns are not among a
+/// table's columns, so `SELECT *` leaves them out and nothing can write them; a column
+/// declared with the same name hides the pseudo-column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PseudoColumn {
+    /// `_rowid`: the rowid stamped on the row at insert, NULL for rows without one
+    RowId,
+    /// `_version`: bumped by every update of the row, starting from 0
+    Version,
+}
+
+impl PseudoColumn {
+    pub const ALL: [PseudoColumn; 2] = [PseudoColumn::RowId, PseudoColumn::Version];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PseudoColumn::RowId => "_rowid",
+            PseudoColumn::Version => "_version",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|pseudo| pseudo.name() == name)
+    }
+
+    pub fn data_type(self) -> DataType {
+        DataType::Integer
+    }
+
+    pub fn value(self, row: &Row) -> Value {
+        match self {
+            PseudoColumn::RowId => row.row_id.map_or(Value::Null, |row_id| Value::Integer(row_id as i64)),
+            PseudoColumn::Version => Value::Integer(row.version as i64),
+        }
+    }
+}
+
+/// Where the value a column name refers to comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnSource {
+    /// The row value at this position
+    Position(usize),
+    Pseudo(PseudoColumn),
+}
+
+impl ColumnSource {
+    /// The value in `row`, or `None` for a position past the row's end
+    pub fn value<'a>(self, row: &'a Row) -> Option<Cow<'a, Value>> {
+        match self {
+            ColumnSource::Position(position) => row.values.get(position).map(Cow::Borrowed),
+            ColumnSource::Pseudo(pseudo) => Some(Cow::Owned(pseudo.value(row))),
+        }
+    }
+}
+
+/// How a table's rows are laid out in the file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TableKind {
+    /// A B+ tree keyed on the first column or the PRIMARY KEY
+    #[default]
+    BTree,
+    /// A chain of leaf pages filled in insertion order, with no interior pages and no
+    /// key order. Rows can only be appended, scanned forward, read by rowid or time range
+    /// and dropped from the head a page at a time.
+    AppendLog,
+}
+
+impl TableKind {
+    /// Name stored in the table's `sqlite_schema` entry
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TableKind::BTree => "btree",
+            TableKind::AppendLog => "append_log",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "btree" => Some(TableKind::BTree),
+            "append_log" => Some(TableKind::AppendLog),
+            _ => None,
+        }
+    }
+}
+
 /// Represents a complete table schema with all column definitions
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TableSchema {
@@ -149,26 +372,115 @@ pub struct TableSchema {
     pub columns: Vec<ColumnSchema>,
     pub root_page_id: PageId,
     pub sql: String,
+    /// Positions of the PRIMARY KEY columns in key order. A table with more than one is
+    /// keyed on the tuple of their values rather than on its first column.
+    #[serde(default)]
+    pub primary_key: Vec<usize>,
+    /// Columns whose values must match a row of another table, or of this one
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKey>,
+    #[serde(default)]
+    pub kind: TableKind,
 }
 
 impl TableSchema {
     pub fn new(table_name: String, columns: Vec<ColumnSchema>, root_page_id: PageId, sql: String) -> Self {
+        let mut primary_key: Vec<usize> = columns
+            .iter()
+            .filter(|column| column.primary_key)
+            .map(|column| column.position)
+            .collect();
+        primary_key.sort_unstable();
         Self {
             table_name,
             columns,
             root_page_id,
             sql,
+            primary_key,
+            foreign_keys: Vec::new(),
+            kind: TableKind::BTree,
         }
     }
 
+    /// Make the columns at `positions` the PRIMARY KEY, in that order
+    pub fn with_primary_key(mut self, positions: Vec<usize>) -> Self {
+        for column in &mut self.columns {
+            column.primary_key = false;
+            if positions.contains(&column.position) {
+                column.primary_key = true;
+                column.nullable = false;
+            }
+        }
+        self.primary_key = positions;
+        self
+    }
+
+    /// Declare `foreign_keys`, at most one per column
+    pub fn with_foreign_keys(mut self, foreign_keys: Vec<ForeignKey>) -> Self {
+        self.foreign_keys = foreign_keys;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: TableKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// The first TIMESTAMP column, which orders an append log's rows in time and is
+    /// what its time range reads and retention compare against
+    pub fn log_time_column(&self) -> Option<usize> {
+        self.columns
+            .iter()
+            .find(|column| column.data_type == DataType::Timestamp)
+            .map(|column| column.position)
+    }
+
+    /// The foreign key declared on `column`, if any
+    pub fn foreign_key(&self, column: &str) -> Option<&ForeignKey> {
+        self.foreign_keys.iter().find(|foreign_key| foreign_key.column == column)
+    }
+
+    /// Columns whose tuple keys the table's B+ tree: those of a composite PRIMARY KEY, or
+    /// none when the tree is keyed on the first column
+    pub fn key_columns(&self) -> &[usize] {
+        match self.primary_key.len() {
+            0 | 1 => &[],
+            _ => &self.primary_key,
+        }
+    }
+
+    /// Column entries for `sqlite_schema`, recording each key column's place in the key
+    /// and the table and column a foreign key column references
+    pub fn column_schema_rows(&self) -> Vec<Row> {
+        self.columns
+            .iter()
+            .map(|column| {
+                let mut row = column.to_schema_row(&self.table_name);
+                if let Some(ordinal) = self.primary_key.iter().position(|position| *position == column.position) {
+                    row.values[7] = Value::Integer(ordinal as i64 + 1);
+                }
+                if let Some(foreign_key) = self.foreign_key(&column.name) {
+                    row.values.resize(10, Value::Null);
+                    row.values.push(Value::Text(foreign_key.ref_table.clone()));
+                    row.values.push(Value::Text(foreign_key.ref_column.clone()));
+                }
+                row
+            })
+            .collect()
+    }
+
     /// Get column by name
     pub fn get_column(&self, name: &str) -> Option<&ColumnSchema> {
         self.columns.iter().find(|col| col.name == name)
     }
 
-    /// Get column by position
+    /// Get column by position. Columns are normally kept in position order, making this
+    /// a direct lookup; otherwise it falls back to a search.
     pub fn get_column_by_position(&self, position: usize) -> Option<&ColumnSchema> {
-        self.columns.iter().find(|col| col.position == position)
+        match self.columns.get(position) {
+            Some(column) if column.position == position => Some(column),
+            _ => self.columns.iter().find(|col| col.position == position),
+        }
     }
 
     /// Get column index by name
@@ -176,6 +488,33 @@ impl TableSchema {
         self.columns.iter().position(|col| col.name == name)
     }
 
+    /// What `name` refers to in a query over this table: one of its columns, or else a
+    /// pseudo-column
+    pub fn column_source(&self, name: &str) -> Option<ColumnSource> {
+        match self.get_column_index(name) {
+            Some(index) => Some(ColumnSource::Position(index)),
+            None => PseudoColumn::from_name(name).map(ColumnSource::Pseudo),
+        }
+    }
+
+    /// The column an INSERT or UPDATE may write as `name`, failing with `ReadOnlyColumn`
+    /// for a pseudo-column
+    pub fn writable_column(&self, name: &str) -> Result<&ColumnSchema, DatabaseError> {
+        if let Some(column) = self.get_column(name) {
+            return Ok(column);
+        }
+        if PseudoColumn::from_name(name).is_some() {
+            return Err(DatabaseError::ReadOnlyColumn {
+                name: name.to_string(),
+                table: self.table_name.clone(),
+            });
+        }
+        Err(DatabaseError::ColumnNotFound {
+            name: name.to_string(),
+            table: self.table_name.clone(),
+        })
+    }
+
     /// Get all column names in order
     pub fn column_names(&self) -> Vec<String> {
         let mut sorted_columns = self.columns.clone();
@@ -183,9 +522,12 @@ impl TableSchema {
         sorted_columns.iter().map(|col| col.name.clone()).collect()
     }
 
-    /// Get primary key columns
+    /// Get primary key columns, in key order
     pub fn primary_key_columns(&self) -> Vec<&ColumnSchema> {
-        self.columns.iter().filter(|col| col.primary_key).collect()
+        self.primary_key
+            .iter()
+            .filter_map(|position| self.get_column_by_position(*position))
+            .collect()
     }
 
     /// Validate a row against this schema
@@ -202,34 +544,70 @@ impl TableSchema {
             });
         }
 
-        // Validate each column
-        for (i, value) in row.values.iter().enumerate() {
-            if let Some(column) = self.get_column_by_position(i) {
-                // Check null constraints
-                if !column.nullable && matches!(value, Value::Null) {
-                    return Err(DatabaseError::InvalidData {
-                        details: format!(
-                            "Column '{}' cannot be NULL",
-                            column.name
-                        ),
-                    });
-                }
+        // Validate each column. Every column knows its position, so no value needs a
+        // lookup and wide rows validate in linear time.
+        for column in &self.columns {
+            let Some(value) = row.values.get(column.position) else {
+                continue;
+            };
+            // Check null constraints
+            if !column.nullable && matches!(value, Value::Null) {
+                return Err(DatabaseError::InvalidData {
+                    details: format!(
+                        "Column '{}' cannot be NULL",
+                        column.name
+                    ),
+                });
+            }
 
-                // Check data type compatibility
-                if !matches!(value, Value::Null) && !value.is_compatible_with_type(&column.data_type) {
-                    return Err(DatabaseError::InvalidData {
-                        details: format!(
-                            "Value {:?} is not compatible with column '{}' of type {:?}",
-                            value, column.name, column.data_type
-                        ),
-                    });
-                }
+            // Check data type compatibility
+            if !matches!(value, Value::Null) && !value.is_compatible_with_type(&column.data_type) {
+                return Err(column.type_mismatch(value));
             }
         }
 
+        self.check_row(row)
+    }
+
+    /// Whether the table has any CHECK constraint
+    pub fn has_checks(&self) -> bool {
+        self.columns.iter().any(|column| column.check.is_some())
+    }
+
+    /// Evaluate the CHECK constraints against `row`. As in SQL, a condition that is
+    /// unknown because of a NULL passes; only one that is false rejects the row.
+    pub fn check_row(&self, row: &Row) -> Result<(), DatabaseError> {
+        for column in &self.columns {
+            let Some(check) = &column.check else {
+                continue;
+            };
+            let outcome = check.evaluate_tri(row, self, ValueComparison::Coercive, PredicateMode::Sql)?;
+            if outcome == TriBool::False {
+                return Err(DatabaseError::CheckConstraintViolation {
+                    constraint: check.to_string(),
+                    column: column.name.clone(),
+                });
+            }
+        }
         Ok(())
     }
 
+    /// Validate a row, first replacing values of the wrong type with their lossless cast
+    /// when `implicit_coercion` is on. BLOB columns are never coerced.
+    pub fn coerce_row(&self, row: &mut Row, implicit_coercion: bool) -> Result<(), DatabaseError> {
+        if implicit_coercion {
+            for column in &self.columns {
+                if let Some(value) = row.values.get_mut(column.position)
+                    && !value.is_compatible_with_type(&column.data_type)
+                    && let Some(cast) = column.implicit_cast(value)
+                {
+                    *value = cast;
+                }
+            }
+        }
+        self.validate_row(row)
+    }
+
     /// Apply default values to a row where values are missing or null
     pub fn apply_defaults(&self, row: &mut Row) -> Result<(), DatabaseError> {
         // Extend row if it has fewer values than columns
@@ -239,12 +617,11 @@ impl TableSchema {
 
         // Apply default values
         for column in &self.columns {
-            if let Some(default_value) = &column.default_value {
-                if row.values.len() > column.position {
-                    if matches!(row.values[column.position], Value::Null) {
-                        row.values[column.position] = default_value.clone();
-                    }
-                }
+            if let Some(default_value) = &column.default_value
+                && row.values.len() > column.position
+                && matches!(row.values[column.position], Value::Null)
+            {
+                row.values[column.position] = default_value.clone();
             }
         }
 
@@ -252,42 +629,148 @@ impl TableSchema {
     }
 }
 
+/// A problem with a table's stored schema that an open worked around instead of failing,
+/// such as a column type this version does not know
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaWarning {
+    pub table: String,
+    pub details: String,
+}
+
+impl std::fmt::Display for SchemaWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Table '{}': {}", self.table, self.details)
+    }
+}
+
+/// Where a row of `sqlite_schema` was found: a schema leaf page and a slot on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaCellLocation {
+    pub page_id: PageId,
+    pub slot_index: usize,
+}
+
+/// A table indexed by a lazy open: its root and where its schema rows were seen. The
+/// schema itself is parsed the first time it is asked for and kept from then on.
+#[derive(Debug, Clone)]
+pub struct LazyTableSchema {
+    pub root_page_id: PageId,
+    pub table_cell: SchemaCellLocation,
+    pub column_cells: Vec<SchemaCellLocation>,
+    /// `Ok(None)` when the CREATE TABLE statement could not be understood, as an eager
+    /// open leaves such tables without a schema
+    parsed: OnceLock<Result<Option<TableSchema>, String>>,
+}
+
+impl LazyTableSchema {
+    pub fn new(root_page_id: PageId, table_cell: SchemaCellLocation, column_cells: Vec<SchemaCellLocation>) -> Self {
+        Self {
+            root_page_id,
+            table_cell,
+            column_cells,
+            parsed: OnceLock::new(),
+        }
+    }
+
+    /// The parsed schema, running `parse` if this is the first time it is needed. A
+    /// failed parse is kept too, and reported again on every later call.
+    pub fn get_or_parse<F>(&self, parse: F) -> &Result<Option<TableSchema>, String>
+    where
+        F: FnOnce() -> Result<Option<TableSchema>, String>,
+    {
+        self.parsed.get_or_init(parse)
+    }
+
+    /// The schema if it has been parsed successfully
+    pub fn parsed(&self) -> Option<&TableSchema> {
+        self.parsed.get().and_then(|parsed| parsed.as_ref().ok()).and_then(Option::as_ref)
+    }
+
+    fn set_root_page_id(&mut self, root_page_id: PageId) {
+        self.root_page_id = root_page_id;
+        if let Some(Ok(Some(schema))) = self.parsed.get_mut() {
+            schema.root_page_id = root_page_id;
+        }
+    }
+
+    fn into_parsed(self) -> Option<TableSchema> {
+        self.parsed.into_inner().and_then(Result::ok).flatten()
+    }
+}
+
 /// Schema manager for handling table and column schemas
 #[derive(Debug, Clone)]
 pub struct SchemaManager {
     pub table_schemas: HashMap<String, TableSchema>,
+    /// Tables indexed by a lazy open whose schemas are parsed on first use
+    pub lazy_schemas: HashMap<String, LazyTableSchema>,
 }
 
 impl SchemaManager {
     pub fn new() -> Self {
         Self {
             table_schemas: HashMap::new(),
+            lazy_schemas: HashMap::new(),
         }
     }
 
     /// Add a table schema
     pub fn add_table_schema(&mut self, schema: TableSchema) {
+        self.lazy_schemas.remove(&schema.table_name);
         self.table_schemas.insert(schema.table_name.clone(), schema);
     }
 
-    /// Get a table schema by name
+    /// Index a table whose schema is parsed when first asked for
+    pub fn add_lazy_table_schema(&mut self, table_name: String, schema: LazyTableSchema) {
+        self.table_schemas.remove(&table_name);
+        self.lazy_schemas.insert(table_name, schema);
+    }
+
+    /// Get a table schema by name. A lazily indexed table only has one once it has been
+    /// parsed.
     pub fn get_table_schema(&self, table_name: &str) -> Option<&TableSchema> {
-        self.table_schemas.get(table_name)
+        self.table_schemas
+            .get(table_name)
+            .or_else(|| self.lazy_schemas.get(table_name).and_then(LazyTableSchema::parsed))
+    }
+
+    pub fn get_lazy_table_schema(&self, table_name: &str) -> Option<&LazyTableSchema> {
+        self.lazy_schemas.get(table_name)
+    }
+
+    /// Point the table's schema at a new root page
+    pub fn set_root_page_id(&mut self, table_name: &str, root_page_id: PageId) {
+        if let Some(schema) = self.table_schemas.get_mut(table_name) {
+            schema.root_page_id = root_page_id;
+        }
+        if let Some(schema) = self.lazy_schemas.get_mut(table_name) {
+            schema.set_root_page_id(root_page_id);
+        }
     }
 
     /// Remove a table schema
     pub fn remove_table_schema(&mut self, table_name: &str) -> Option<TableSchema> {
-        self.table_schemas.remove(table_name)
+        let lazy = self.lazy_schemas.remove(table_name).and_then(LazyTableSchema::into_parsed);
+        self.table_schemas.remove(table_name).or(lazy)
     }
 
     /// Get all table names
     pub fn table_names(&self) -> Vec<&str> {
-        self.table_schemas.keys().map(|s| s.as_str()).collect()
+        self.table_schemas
+            .keys()
+            .chain(self.lazy_schemas.keys())
+            .map(|s| s.as_str())
+            .collect()
     }
 
     /// Check if a table exists
     pub fn table_exists(&self, table_name: &str) -> bool {
-        self.table_schemas.contains_key(table_name)
+        self.table_schemas.contains_key(table_name) || self.lazy_schemas.contains_key(table_name)
+    }
+
+    /// Number of table schemas parsed so far, lazily indexed ones included
+    pub fn parsed_schema_count(&self) -> usize {
+        self.table_schemas.len() + self.lazy_schemas.values().filter(|schema| schema.parsed().is_some()).count()
     }
 }
 
@@ -0,0 +1,104 @@
+use std::{
+    sync::mpsc::{Receiver, RecvTimeoutError, SyncSender, TrySendError, sync_channel},
+    time::Duration,
+};
+
+/// Events a watcher buffers before further ones are dropped for it
+pub const SCHEMA_WATCH_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    TableCreated(String),
+    TableDropped(String),
+    TableAltered(String),
+    /// The in-memory schema was re-read from disk; it may differ in any way
+    SchemasReloaded,
+}
+
+/// A schema change together with the header's schema cookie after it. Every DDL bumps the
+/// cookie by one, so a gap between consecutive events means some were dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaEvent {
+    pub schema_cookie: u32,
+    pub change: SchemaChange,
+}
+
+/// Receiving end handed out by `StorageManager::subscribe_schema_changes`
+pub struct SchemaWatcher {
+    receiver: Receiver<SchemaEvent>,
+    last_cookie: u32,
+    missed_events: bool,
+}
+
+impl SchemaWatcher {
+    /// Next pending event, without blocking
+    pub fn try_recv(&mut self) -> Option<SchemaEvent> {
+        let event = self.receiver.try_recv().ok()?;
+        Some(self.observe(event))
+    }
+
+    /// Wait up to `timeout` for the next event
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<SchemaEvent> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(event) => Some(self.observe(event)),
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// All pending events, oldest first
+    pub fn drain(&mut self) -> Vec<SchemaEvent> {
+        std::iter::from_fn(|| self.try_recv()).collect()
+    }
+
+    /// Schema cookie of the last event received, or at subscription time
+    pub fn last_cookie(&self) -> u32 {
+        self.last_cookie
+    }
+
+    /// Whether any event was dropped because this watcher fell behind; cached schema
+    /// state should then be rebuilt from scratch
+    pub fn missed_events(&self) -> bool {
+        self.missed_events
+    }
+
+    fn observe(&mut self, event: SchemaEvent) -> SchemaEvent {
+        if event.change != SchemaChange::SchemasReloaded {
+            if event.schema_cookie != self.last_cookie.wrapping_add(1) {
+                self.missed_events = true;
+            }
+            self.last_cookie = event.schema_cookie;
+        }
+        event
+    }
+}
+
+/// Fan-out of schema events to live watchers. Emission never blocks: a full watcher
+/// misses the event and a dropped watcher is forgotten.
+#[derive(Debug, Default)]
+pub struct SchemaNotifier {
+    subscribers: Vec<SyncSender<SchemaEvent>>,
+}
+
+impl SchemaNotifier {
+    pub fn subscribe(&mut self, schema_cookie: u32) -> SchemaWatcher {
+        let (sender, receiver) = sync_channel(SCHEMA_WATCH_CAPACITY);
+        self.subscribers.push(sender);
+        SchemaWatcher {
+            receiver,
+            last_cookie: schema_cookie,
+            missed_events: false,
+        }
+    }
+
+    pub fn emit(&mut self, event: SchemaEvent) {
+        self.subscribers
+            .retain(|subscriber| match subscriber.try_send(event.clone()) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.len()
+    }
+}
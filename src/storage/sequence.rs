@@ -0,0 +1,180 @@
+use crate::types::{error::DatabaseError, row::Row, value::Value};
+
+/// A named counter stored in `sqlite_schema` and shared by any number of tables.
+///
+/// Values are handed out from a reserved block: before the first value of a block is
+/// returned, the value a recovering session must restart from is persisted. A crash can
+/// therefore burn the rest of a block but never hands the same value out twice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequence {
+    pub name: String,
+    pub start: i64,
+    pub increment: i64,
+    pub min_value: i64,
+    pub max_value: i64,
+    pub cycle: bool,
+    /// Next value to hand out, or `None` once a non-cycling sequence is exhausted
+    next: Option<i64>,
+    /// Where the next session resumes; always at or past every value handed out so far
+    restart: Option<i64>,
+    /// Values left in the current reserved block
+    reserved: u32,
+    /// Last value returned by `next_val` in this session
+    current: Option<i64>,
+}
+
+impl Sequence {
+    /// Build a sequence, defaulting the bounds like PostgreSQL: ascending sequences run
+    /// from 1 to `i64::MAX`, descending ones from `i64::MIN` to -1
+    pub fn new(
+        name: &str,
+        start: i64,
+        increment: i64,
+        min_value: Option<i64>,
+        max_value: Option<i64>,
+        cycle: bool,
+    ) -> Result<Self, DatabaseError> {
+        let invalid = |details: String| Err(DatabaseError::InvalidData { details });
+        if increment == 0 {
+            return invalid(format!("Sequence '{}' has an increment of zero", name));
+        }
+        let min_value = min_value.unwrap_or(if increment > 0 { 1 } else { i64::MIN });
+        let max_value = max_value.unwrap_or(if increment > 0 { i64::MAX } else { -1 });
+        if min_value > max_value {
+            return invalid(format!(
+                "Sequence '{}' has MINVALUE {} above MAXVALUE {}",
+                name, min_value, max_value
+            ));
+        }
+        if start < min_value || start > max_value {
+            return invalid(format!(
+                "Sequence '{}' starts at {} outside [{}, {}]",
+                name, start, min_value, max_value
+            ));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            start,
+            increment,
+            min_value,
+            max_value,
+            cycle,
+            next: Some(start),
+            restart: Some(start),
+            reserved: 0,
+            current: None,
+        })
+    }
+
+    /// Last value returned by `next_val` in this session
+    pub fn current_val(&self) -> Option<i64> {
+        self.current
+    }
+
+    /// Whether handing out the next value first needs a new block reserved on disk
+    pub fn needs_reservation(&self) -> bool {
+        self.reserved == 0
+    }
+
+    /// Reserve the next `block_size` values, returning the restart point to persist
+    pub fn reserve(&mut self, block_size: u32) -> Option<i64> {
+        let block_size = block_size.max(1);
+        let mut restart = self.next;
+        for _ in 0..block_size {
+            restart = restart.and_then(|value| self.step(value));
+        }
+        self.restart = restart;
+        self.reserved = block_size;
+        restart
+    }
+
+    /// Hand out the next value from the reserved block
+    pub fn advance(&mut self) -> Result<i64, DatabaseError> {
+        let value = self.next.ok_or_else(|| DatabaseError::SequenceExhausted {
+            name: self.name.clone(),
+        })?;
+        self.next = self.step(value);
+        self.reserved = self.reserved.saturating_sub(1);
+        self.current = Some(value);
+        Ok(value)
+    }
+
+    /// The value after `value`, wrapping to the opposite bound when cycling
+    fn step(&self, value: i64) -> Option<i64> {
+        match value.checked_add(self.increment) {
+            Some(next) if (self.min_value..=self.max_value).contains(&next) => Some(next),
+            _ if self.cycle => Some(if self.increment > 0 { self.min_value } else { self.max_value }),
+            _ => None,
+        }
+    }
+
+    fn definition_sql(&self) -> String {
+        format!(
+            "CREATE SEQUENCE {} START WITH {} INCREMENT BY {} MINVALUE {} MAXVALUE {}{}",
+            self.name,
+            self.start,
+            self.increment,
+            self.min_value,
+            self.max_value,
+            if self.cycle { " CYCLE" } else { "" }
+        )
+    }
+
+    /// Convert the sequence to a row for storage in sqlite_schema:
+    /// type, name, tbl_name, rootpage, sql, start, increment, min, max, cycle, restart
+    pub fn to_schema_row(&self) -> Row {
+        Row::new(vec![
+            Value::Text("sequence".to_string()),
+            Value::Text(self.name.clone()),
+            Value::Text(self.name.clone()),
+            Value::Integer(0),
+            Value::Text(self.definition_sql()),
+            Value::Integer(self.start),
+            Value::Integer(self.increment),
+            Value::Integer(self.min_value),
+            Value::Integer(self.max_value),
+            Value::Boolean(self.cycle),
+            self.restart.map(Value::Integer).unwrap_or(Value::Null),
+        ])
+    }
+
+    /// Restore a sequence from its schema row. The session resumes at the persisted
+    /// restart point, skipping whatever was left of the previous session's block.
+    pub fn from_schema_row(row: &Row) -> Result<Self, DatabaseError> {
+        let corrupted = |field: &str| DatabaseError::CorruptedDatabase {
+            reason: format!("Invalid sequence {} in schema", field),
+        };
+        if row.values.len() < 11 {
+            return Err(corrupted("row format"));
+        }
+        let integer = |index: usize, field: &str| match &row.values[index] {
+            Value::Integer(value) => Ok(*value),
+            _ => Err(corrupted(field)),
+        };
+        let name = match &row.values[1] {
+            Value::Text(name) => name.clone(),
+            _ => return Err(corrupted("name")),
+        };
+        let cycle = match &row.values[9] {
+            Value::Boolean(cycle) => *cycle,
+            _ => return Err(corrupted("cycle flag")),
+        };
+        let restart = match &row.values[10] {
+            Value::Integer(restart) => Some(*restart),
+            Value::Null => None,
+            _ => return Err(corrupted("restart value")),
+        };
+        Ok(Self {
+            name,
+            start: integer(5, "start")?,
+            increment: integer(6, "increment")?,
+            min_value: integer(7, "minimum")?,
+            max_value: integer(8, "maximum")?,
+            cycle,
+            next: restart,
+            restart,
+            reserved: 0,
+            current: None,
+        })
+    }
+}
@@ -1,31 +1,62 @@
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     fs::{File, OpenOptions},
-    io::{Read, Seek, SeekFrom, Write},
+    io::{BufRead, BufWriter, Read, Seek, SeekFrom, Write},
+    ops::ControlFlow,
     path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use crate::{
     executor::{
+        aggregate::{AggregateExecutor, AggregateSpec},
+        create_table::CreateTableExecutor,
+        distinct::DistinctExecutor,
         insert::{Inserter, TableInserter},
         predicate::Predicate,
-        scan::Scanner,
-        sequential_scan::SequentialScanner
+        scan::{CorruptionLog, FilterScanner, ScanOptions, Scanner},
+        sequential_scan::{ScanStats, SequentialScanner},
+        sort::compare_rows,
+        subquery::ValueSetBuilder,
     },
+    planner::{parser::SqlParser, types::SortOrder},
     storage::{
+        append_log::{self, LogAxis, LogPosition, LogTrim},
         bplus_tree::BPlusTree,
+        column_usage::{self, ColumnUsage, ColumnUsageLog},
+        export::{CsvRowWriter, JsonLayout, JsonRowWriter},
+        import::{self, JsonlImportOptions, JsonlImportSummary, JsonlReject, NestedJson},
+        index::TableIndex,
+        integrity::{IntegrityChecker, IntegrityReport},
+        freelist,
         header::BambangHeader,
-        schema::{SchemaManager, TableSchema, ColumnSchema},
-        BAMBANG_HEADER_SIZE
+        io_stats::{IoCounters, IoStats},
+        journal::RollbackJournal,
+        options::{OpenMode, QuotaUsage, StorageManagerOptions, SyncMode},
+        page_image::{self, PageImageManifest, PageImageSummary, TablePages, PAGE_IMAGE_FORMAT_VERSION},
+        page_store::{self, PageStore},
+        row_locator::{RowLocator, RowLocatorCache},
+        schema::{
+            SchemaManager, TableKind, TableSchema, ColumnSchema, ColumnSource, ForeignKey, LazyTableSchema, SchemaCellLocation,
+            SchemaWarning, MAX_COLUMNS,
+        },
+        schema_watch::{SchemaChange, SchemaEvent, SchemaNotifier, SchemaWatcher},
+        workload::{IndexRecommendation, ScanRecord, WorkloadLog},
+        sequence::Sequence,
+        BAMBANG_HEADER_SIZE, SCHEMA_ROOT_PAGE_ID,
     },
     types::{
         error::DatabaseError,
-        page::{Page, PageType},
+        page::{DatabaseVacuumReport, Page, PageType, StorageCost, TablePageStats, VacuumReport, overflow_page_capacity, overflow_threshold},
         row::Row,
-        value::{Value, DataType},
+        value::{DataType, Value},
         PageId,
-        PAGE_SIZE
+        PAGE_HEADER_SIZE,
+        RowId,
     },
+    utils::progress::{ProgressSink, report_progress},
 };
 
 pub struct DatabaseInfo {
@@ -35,50 +66,120 @@ pub struct DatabaseInfo {
     pub file_size: u64,
 }
 
+/// Which of the rows matching a predicate a rewrite applies to
+#[derive(Debug, Clone, Copy, Default)]
+struct RowSelection {
+    /// Only the row stamped with this id
+    row_id: Option<RowId>,
+    /// Fail with `VersionConflict`, writing nothing, unless every selected row is at this version
+    expected_version: Option<u64>,
+}
+
+/// A column entry of `sqlite_schema`: the column, its place in the PRIMARY KEY and the
+/// foreign key it declares
+type StoredColumn = (ColumnSchema, Option<usize>, Option<ForeignKey>);
+
+/// Rows `vacuum` reads from a table and loads into the new file at a time
+const VACUUM_BATCH_ROWS: usize = 4096;
 
 pub struct StorageManager {
     pub db_info: DatabaseInfo,
-    pub file: File,
+    pub file: Box<dyn PageStore>,
     pub table_roots: HashMap<String, PageId>,
+    /// Next rowid to assign per table. Tables missing here resume from their highest
+    /// stored rowid on first insert.
+    pub next_row_ids: HashMap<String, RowId>,
     pub schema_manager: SchemaManager,
+    pub options: StorageManagerOptions,
+    sequences: HashMap<String, Sequence>,
+    /// Secondary indexes by name
+    indexes: HashMap<String, TableIndex>,
+    schema_notifier: SchemaNotifier,
+    quota_warned: bool,
+    pub(crate) io_counters: Arc<IoCounters>,
+    workload: WorkloadLog,
+    column_usage: ColumnUsageLog,
+    row_locators: RowLocatorCache,
+    /// Pages to restore on rollback, while a transaction is active
+    pub(crate) journal: Option<Arc<RollbackJournal>>,
+    /// Last page of each append-log table appended to so far, found by walking its chain
+    /// on the first append
+    log_tails: HashMap<String, PageId>,
+    /// Problems in stored table schemas the last schema load worked around
+    schema_warnings: Vec<SchemaWarning>,
 }
 
 impl StorageManager {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, DatabaseError> {
+        Self::open_with_options(path, StorageManagerOptions::default())
+    }
+
+    pub fn open_with_options<P: AsRef<Path>>(
+        path: P,
+        options: StorageManagerOptions,
+    ) -> Result<Self, DatabaseError> {
         let path = path.as_ref();
         let db_info = if path.exists() {
-            println!("Opening existing database at path: {}", path.display());
+            Self::log_with(&options, || format!("Opening existing database at path: {}", path.display()));
             Self::open_existing(path)?
         } else {
-            println!("Creating new database at path: {}", path.display());
-            Self::create_new(path)?
+            Self::log_with(&options, || format!("Creating new database at path: {}", path.display()));
+            Self::create_new(path, options.page_size)?
         };
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(&db_info.path)?;
+        let file = page_store::open_store(&db_info.path, options.io_log.as_ref(), db_info.header.page_size_bytes())?;
         let mut storage_manager = Self {
             db_info,
             file,
             table_roots: HashMap::new(),
+            next_row_ids: HashMap::new(),
             schema_manager: SchemaManager::new(),
+            options,
+            sequences: HashMap::new(),
+            indexes: HashMap::new(),
+            schema_notifier: SchemaNotifier::default(),
+            quota_warned: false,
+            io_counters: Arc::default(),
+            workload: WorkloadLog::default(),
+            column_usage: ColumnUsageLog::default(),
+            row_locators: RowLocatorCache::default(),
+            journal: None,
+            log_tails: HashMap::new(),
+            schema_warnings: Vec::new(),
         };
+        if let Some(max_bytes) = storage_manager.options.max_database_size {
+            storage_manager.set_max_database_size(Some(max_bytes))?;
+        }
         storage_manager.load_table_roots_and_schemas()?;
         Ok(storage_manager)
     }
 
+    /// Pass a message to the `on_log` hook, formatting it only when one is set
+    fn log_with<F: FnOnce() -> String>(options: &StorageManagerOptions, message: F) {
+        if let Some(hook) = &options.on_log {
+            hook(&message());
+        }
+    }
+
+    /// Size of every page in this database, as recorded in its header
+    pub fn page_size(&self) -> usize {
+        self.db_info.header.page_size_bytes()
+    }
+
     fn page_offset(&self, page_id: PageId) -> u64 {
-        BAMBANG_HEADER_SIZE as u64 + (page_id - 1) * PAGE_SIZE as u64
+        crate::storage::page_offset_with_size(page_id, self.page_size())
     }
 
-    fn read_page(&mut self, page_id: PageId) -> Result<Page, DatabaseError> {
-        let mut buffer = vec![0u8; PAGE_SIZE];
-        self.file.seek(SeekFrom::Start(self.page_offset(page_id)))?;
-        self.file.read_exact(&mut buffer)?;
+    fn read_page(&self, page_id: PageId) -> Result<Page, DatabaseError> {
+        let mut buffer = vec![0u8; self.page_size()];
+        self.file.read_exact_at(&mut buffer, self.page_offset(page_id))?;
+        self.io_counters.record_page_read();
         Page::from_bytes(&buffer)
     }
 
     fn write_page(&mut self, page_id: PageId, page: &Page) -> Result<(), DatabaseError> {
+        if let Some(journal) = &self.journal {
+            journal.save_page(&mut self.file, page_id)?;
+        }
         let page_bytes = page.to_bytes()?;
         self.file.seek(SeekFrom::Start(self.page_offset(page_id)))?;
         self.file.write_all(&page_bytes)?;
@@ -86,17 +187,20 @@ impl StorageManager {
         Ok(())
     }
 
-    pub fn create_new<P: AsRef<Path>>(path: P) -> Result<DatabaseInfo, DatabaseError> {
+    /// Create a database file whose pages are `page_size` bytes
+    pub fn create_new<P: AsRef<Path>>(path: P, page_size: usize) -> Result<DatabaseInfo, DatabaseError> {
         let path = path.as_ref();
+        let mut header = BambangHeader::default();
+        header.set_page_size(page_size)?;
+        header.set_schema_root_page(SCHEMA_ROOT_PAGE_ID);
         let mut file = OpenOptions::new()
             .create(true)
             .write(true)
             .read(true)
             .truncate(true)
             .open(path)?;
-        let header = BambangHeader::default();
         file.write_all(&header.to_bytes())?;
-        let schema_page = Self::init_schema_page();
+        let schema_page = Self::init_schema_page(page_size);
         let page_bytes = schema_page.to_bytes()?;
         file.write_all(&page_bytes)?;
         file.flush()?;
@@ -123,8 +227,8 @@ impl StorageManager {
         }
         let file_size = file.metadata()?.len();
         let data_size = file_size - BAMBANG_HEADER_SIZE as u64;
-        let page_count = data_size / PAGE_SIZE as u64;
-        if page_count != header.database_size_pages.into() {
+        let page_count = data_size / header.page_size_bytes() as u64;
+        if page_count != header.database_size_pages as u64 {
             return Err(DatabaseError::CorruptedDatabase {
                 reason: "File size doesn't match header".to_string(),
             });
@@ -138,282 +242,3810 @@ impl StorageManager {
     }
 
     fn load_table_roots_and_schemas(&mut self) -> Result<(), DatabaseError> {
-        let schema_page = self.read_page(1)?;
-        let mut table_schemas: HashMap<String, (PageId, String, Vec<ColumnSchema>)> = HashMap::new();
-        
-        for i in 0..schema_page.slot_directory.slots.len() {
-            if let Some(cell_data) = schema_page.get_cell(i) {
-                let row = Row::from_bytes(cell_data)?;
-                if row.values.len() >= 5 {
-                    match &row.values[0] {
-                        Value::Text(entry_type) if entry_type == "table" => {
-                            // Table entry: type, name, tbl_name, rootpage, sql
-                            if let (Value::Text(table_name), Value::Integer(root_page), Value::Text(sql)) =
-                                (&row.values[1], &row.values[3], &row.values[4])
-                            {
-                                self.table_roots.insert(table_name.clone(), *root_page as PageId);
-                                table_schemas.insert(
+        let lazy = self.options.open_mode == OpenMode::Lazy;
+        let mut tables: HashMap<String, (PageId, Option<RowId>, SchemaCellLocation, String, TableKind)> = HashMap::new();
+        // Column entries sort ahead of table entries once the schema spans several leaves,
+        // so they are collected apart and matched to their table afterwards
+        let mut column_cells: HashMap<String, Vec<SchemaCellLocation>> = HashMap::new();
+        let mut columns: HashMap<String, Vec<StoredColumn>> = HashMap::new();
+        let mut sequences = Vec::new();
+        let mut indexes = Vec::new();
+        let mut usage = Vec::new();
+        let mut warnings = Vec::new();
+
+        self.for_each_schema_row(|location, row| {
+            if row.values.len() < 5 {
+                return Ok(ControlFlow::Continue(()));
+            }
+            match &row.values[0] {
+                Value::Text(entry_type) if entry_type == "table" => {
+                    // Table entry: type, name, tbl_name, rootpage, sql[, next_rowid[, kind]]
+                    if let (Value::Text(table_name), Value::Integer(root_page), Value::Text(sql)) =
+                        (&row.values[1], &row.values[3], &row.values[4])
+                    {
+                        let next_row_id = match row.values.get(5) {
+                            Some(Value::Integer(next_row_id)) => Some(*next_row_id as RowId),
+                            _ => None,
+                        };
+                        // A table of a kind this version cannot read is left out entirely
+                        match Self::stored_kind(&row) {
+                            Ok(kind) => {
+                                tables.insert(
                                     table_name.clone(),
-                                    (*root_page as PageId, sql.clone(), Vec::new())
+                                    (*root_page as PageId, next_row_id, location, sql.clone(), kind),
                                 );
                             }
+                            Err(error) => warnings.push(SchemaWarning {
+                                table: table_name.clone(),
+                                details: format!("table skipped: {}", error),
+                            }),
                         }
-                        Value::Text(entry_type) if entry_type == "column" => {
-                            // Column entry: type, name, tbl_name, position, data_type, nullable, default, primary_key, unique
-                            if row.values.len() >= 9 {
-                                if let Value::Text(table_name) = &row.values[2] {
-                                    let column_schema = ColumnSchema::from_schema_row(&row)?;
-                                    if let Some((_, _, columns)) = table_schemas.get_mut(table_name) {
-                                        columns.push(column_schema);
-                                    }
+                    }
+                }
+                Value::Text(entry_type) if entry_type == "column" => {
+                    // Column entry: type, name, tbl_name, position, data_type, nullable, default,
+                    // primary_key, unique[, check[, ref_table, ref_column]]
+                    if row.values.len() >= 9
+                        && let Value::Text(table_name) = &row.values[2]
+                    {
+                        if lazy {
+                            column_cells.entry(table_name.clone()).or_default().push(location);
+                        } else {
+                            match Self::stored_column_or_placeholder(&row) {
+                                Ok((column, warning)) => {
+                                    warnings.extend(warning.map(|details| SchemaWarning {
+                                        table: table_name.clone(),
+                                        details,
+                                    }));
+                                    columns.entry(table_name.clone()).or_default().push(column);
                                 }
+                                Err(error) => warnings.push(SchemaWarning {
+                                    table: table_name.clone(),
+                                    details: format!("column entry skipped: {}", error),
+                                }),
                             }
                         }
-                        _ => {} // Ignore other entry types
                     }
                 }
+                Value::Text(entry_type) if entry_type == "sequence" => {
+                    sequences.push(Sequence::from_schema_row(&row)?);
+                }
+                Value::Text(entry_type) if entry_type == "index" => {
+                    indexes.push(TableIndex::from_schema_row(&row)?);
+                }
+                Value::Text(entry_type) if entry_type == "usage" && self.options.persist_column_usage => {
+                    usage.push(column_usage::usage_from_schema_row(&row)?);
+                }
+                _ => {} // Ignore other entry types
+            }
+            Ok(ControlFlow::Continue(()))
+        })?;
+
+        for sequence in sequences {
+            self.sequences.insert(sequence.name.clone(), sequence);
+        }
+        for index in indexes {
+            self.indexes.insert(index.name.clone(), index);
+        }
+        for (table_name, counts) in usage {
+            self.column_usage.restore(&table_name, &counts);
+        }
+        for (table_name, (root_page_id, next_row_id, location, sql, kind)) in tables {
+            self.table_roots.insert(table_name.clone(), root_page_id);
+            if let Some(next_row_id) = next_row_id {
+                self.next_row_ids.insert(table_name.clone(), next_row_id);
+            }
+            if lazy {
+                let cells = column_cells.remove(&table_name).unwrap_or_default();
+                self.schema_manager
+                    .add_lazy_table_schema(table_name, LazyTableSchema::new(root_page_id, location, cells));
+                continue;
+            }
+            let table_columns = columns.remove(&table_name).unwrap_or_default();
+            // A table whose columns do not add up keeps its rows but has no schema
+            match Self::build_table_schema(&table_name, root_page_id, sql, kind, table_columns) {
+                Ok(Some(table_schema)) => self.schema_manager.add_table_schema(table_schema),
+                Ok(None) => {}
+                Err(error) => warnings.push(SchemaWarning {
+                    table: table_name,
+                    details: format!("schema not loaded: {}", error),
+                }),
+            }
+        }
+
+        warnings.sort_by(|a, b| a.table.cmp(&b.table));
+        for warning in &warnings {
+            Self::log_with(&self.options, || format!("Warning: {}", warning));
+        }
+        self.schema_warnings = warnings;
+        Ok(())
+    }
+
+    /// Problems in stored table schemas that the last open or schema reload worked
+    /// around: columns of unknown types read as BLOB, column entries skipped, and tables
+    /// left without a schema. Tables a lazy open parses later report theirs through the
+    /// log hook.
+    pub fn schema_warnings(&self) -> &[SchemaWarning] {
+        &self.schema_warnings
+    }
+
+    /// The kind recorded in a table entry of `sqlite_schema`; entries without one are
+    /// B+ trees
+    fn stored_kind(row: &Row) -> Result<TableKind, DatabaseError> {
+        match row.values.get(6) {
+            None | Some(Value::Null) => Ok(TableKind::BTree),
+            Some(Value::Text(name)) => TableKind::from_name(name).ok_or_else(|| DatabaseError::CorruptedDatabase {
+                reason: format!("Unknown table kind '{}'", name),
+            }),
+            Some(other) => Err(DatabaseError::CorruptedDatabase {
+                reason: format!("Table kind {} is not a name", other),
+            }),
+        }
+    }
+
+    /// Read a column entry of `sqlite_schema`
+    fn stored_column(row: &Row) -> Result<StoredColumn, DatabaseError> {
+        Ok((
+            ColumnSchema::from_schema_row(row)?,
+            ColumnSchema::primary_key_ordinal(row),
+            ForeignKey::from_schema_row(row)?,
+        ))
+    }
+
+    /// Read a column entry of `sqlite_schema`, standing a BLOB column in for one whose type
+    /// this version does not know, such as one written by a newer version. The default
+    /// is dropped with the type it was written for, and the warning says so.
+    fn stored_column_or_placeholder(row: &Row) -> Result<(StoredColumn, Option<String>), DatabaseError> {
+        let Some(Value::Text(type_name)) = row.values.get(4) else {
+            return Ok((Self::stored_column(row)?, None));
+        };
+        if DataType::from_string(type_name).is_ok() {
+            return Ok((Self::stored_column(row)?, None));
+        }
+        let mut placeholder = row.clone();
+        placeholder.values[4] = Value::Text(DataType::Blob.to_string());
+        placeholder.values[6] = Value::Text("NULL".to_string());
+        let column = Self::stored_column(&placeholder)?;
+        let warning = format!("column '{}' has unknown type '{}' and is read as BLOB", column.0.name, type_name);
+        Ok((column, Some(warning)))
+    }
+
+    /// Assemble a table schema from its stored column entries, each with its place in the
+    /// PRIMARY KEY and its foreign key. Tables created from plain SQL have none and get
+    /// theirs from the statement, and a statement that cannot be understood is an error.
+    fn build_table_schema(
+        table_name: &str,
+        root_page_id: PageId,
+        sql: String,
+        kind: TableKind,
+        mut columns: Vec<StoredColumn>,
+    ) -> Result<Option<TableSchema>, DatabaseError> {
+        if columns.is_empty() {
+            return Ok(Some(Self::schema_from_sql(table_name, root_page_id, &sql)?.with_kind(kind)));
+        }
+        columns.sort_by_key(|(col, _, _)| col.position);
+        let mut key_columns: Vec<(usize, usize)> = columns
+            .iter()
+            .filter_map(|(col, ordinal, _)| ordinal.map(|ordinal| (ordinal, col.position)))
+            .collect();
+        key_columns.sort_unstable();
+        let mut foreign_keys = Vec::new();
+        let columns: Vec<ColumnSchema> = columns
+            .into_iter()
+            .map(|(col, _, foreign_key)| {
+                foreign_keys.extend(foreign_key);
+                col
+            })
+            .collect();
+        Self::validate_column_positions(table_name, &columns)?;
+        let schema = TableSchema::new(table_name.to_string(), columns, root_page_id, sql);
+        Ok(Some(
+            schema
+                .with_primary_key(key_columns.into_iter().map(|(_, position)| position).collect())
+                .with_foreign_keys(foreign_keys)
+                .with_kind(kind),
+        ))
+    }
+
+    /// Call `f` with every row of `sqlite_schema` and where it is stored, walking the leaf
+    /// chain from the schema tree's first leaf until `f` breaks
+    fn for_each_schema_row<F>(&self, f: F) -> Result<(), DatabaseError>
+    where
+        F: FnMut(SchemaCellLocation, Row) -> Result<ControlFlow<()>, DatabaseError>,
+    {
+        walk_schema_rows(
+            self.first_schema_leaf()?,
+            self.db_info.header.database_size_pages as u64,
+            |page_id| self.read_page(page_id),
+            f,
+        )
+    }
+
+    /// Parse the schema of a table indexed by a lazy open from the cells recorded for it,
+    /// searching `sqlite_schema` again if those cells have moved since
+    fn parse_lazy_schema(&self, table_name: &str, lazy: &LazyTableSchema) -> Result<Option<TableSchema>, DatabaseError> {
+        let (table_row, column_rows) = match self.read_recorded_schema_rows(table_name, lazy)? {
+            Some(rows) => rows,
+            None => self.find_schema_rows(table_name)?,
+        };
+        let Some(Value::Text(sql)) = table_row.values.get(4) else {
+            return Err(DatabaseError::CorruptedDatabase {
+                reason: format!("Table '{}' has no CREATE TABLE statement", table_name),
+            });
+        };
+        let mut columns = Vec::with_capacity(column_rows.len());
+        for row in &column_rows {
+            let (column, warning) = Self::stored_column_or_placeholder(row)?;
+            if let Some(details) = warning {
+                let warning = SchemaWarning {
+                    table: table_name.to_string(),
+                    details,
+                };
+                Self::log_with(&self.options, || format!("Warning: {}", warning));
+            }
+            columns.push(column);
+        }
+        Self::build_table_schema(table_name, lazy.root_page_id, sql.clone(), Self::stored_kind(&table_row)?, columns)
+    }
+
+    /// The table's entry and column entries at the locations a lazy open recorded, or
+    /// `None` if any of them no longer holds the expected entry
+    fn read_recorded_schema_rows(
+        &self,
+        table_name: &str,
+        lazy: &LazyTableSchema,
+    ) -> Result<Option<(Row, Vec<Row>)>, DatabaseError> {
+        let mut pages: HashMap<PageId, Page> = HashMap::new();
+        let mut read_row = |location: SchemaCellLocation, entry_type: &str| -> Result<Option<Row>, DatabaseError> {
+            if let std::collections::hash_map::Entry::Vacant(entry) = pages.entry(location.page_id) {
+                entry.insert(self.read_page(location.page_id)?);
             }
+            let Some(cell_data) = pages[&location.page_id].get_cell(location.slot_index) else {
+                return Ok(None);
+            };
+            let row = Row::from_bytes(cell_data)?;
+            let owner = if entry_type == "table" { 1 } else { 2 };
+            let matches = matches!(row.values.first(), Some(Value::Text(row_type)) if row_type == entry_type)
+                && matches!(row.values.get(owner), Some(Value::Text(name)) if name == table_name);
+            Ok(matches.then_some(row))
+        };
+        let Some(table_row) = read_row(lazy.table_cell, "table")? else {
+            return Ok(None);
+        };
+        let mut column_rows = Vec::with_capacity(lazy.column_cells.len());
+        for location in &lazy.column_cells {
+            let Some(row) = read_row(*location, "column")? else {
+                return Ok(None);
+            };
+            column_rows.push(row);
         }
-        
-        // Create TableSchema objects and add them to schema manager
-        for (table_name, (root_page_id, sql, mut columns)) in table_schemas {
-            // Sort columns by position
-            columns.sort_by_key(|col| col.position);
-            let table_schema = TableSchema::new(table_name.clone(), columns, root_page_id, sql);
-            self.schema_manager.add_table_schema(table_schema);
+        Ok(Some((table_row, column_rows)))
+    }
+
+    /// The table's entry and column entries, found by walking all of `sqlite_schema`
+    fn find_schema_rows(&self, table_name: &str) -> Result<(Row, Vec<Row>), DatabaseError> {
+        let mut table_row = None;
+        let mut column_rows = Vec::new();
+        self.for_each_schema_row(|_, row| {
+            match &row.values[..] {
+                [Value::Text(entry_type), Value::Text(name), ..] if entry_type == "table" && name == table_name => {
+                    table_row = Some(row);
+                }
+                [Value::Text(entry_type), _, Value::Text(owner), ..]
+                    if entry_type == "column" && owner == table_name && row.values.len() >= 9 =>
+                {
+                    column_rows.push(row);
+                }
+                _ => {}
+            }
+            Ok(ControlFlow::Continue(()))
+        })?;
+        let table_row = table_row.ok_or_else(|| DatabaseError::TableNotFound {
+            name: table_name.to_string(),
+        })?;
+        Ok((table_row, column_rows))
+    }
+
+    /// Ensure sorted column positions run 0..n without gaps or duplicates, since rows are
+    /// indexed by position
+    fn validate_column_positions(
+        table_name: &str,
+        columns: &[ColumnSchema],
+    ) -> Result<(), DatabaseError> {
+        for (expected, column) in columns.iter().enumerate() {
+            if column.position != expected {
+                return Err(DatabaseError::CorruptedDatabase {
+                    reason: format!(
+                        "Table '{}' has column '{}' at position {}, expected position {}",
+                        table_name, column.name, column.position, expected
+                    ),
+                });
+            }
         }
-        
         Ok(())
     }
 
     pub fn create_table(&mut self, table_name: &str, sql: &str) -> Result<PageId, DatabaseError> {
+        self.create_table_with_kind(table_name, sql, TableKind::BTree)
+    }
+
+    /// Create a table laid out as `kind` from its CREATE TABLE statement. An append log
+    /// needs no PRIMARY KEY or UNIQUE column, as it has no key lookups to enforce them with.
+    pub fn create_table_with_kind(&mut self, table_name: &str, sql: &str, kind: TableKind) -> Result<PageId, DatabaseError> {
+        let mut table_schema = Self::schema_from_sql(table_name, 0, sql)?.with_kind(kind);
+        if kind == TableKind::AppendLog
+            && table_schema.columns.iter().any(|column| column.primary_key || column.unique)
+        {
+            return Err(Self::append_log_unsupported(table_name, "PRIMARY KEY or UNIQUE columns"));
+        }
+        if table_schema.columns.len() > MAX_COLUMNS {
+            return Err(DatabaseError::TooManyColumns {
+                count: table_schema.columns.len(),
+                max: MAX_COLUMNS,
+            });
+        }
+        self.validate_foreign_keys(&table_schema)?;
         let new_root_page_id = self.allocate_new_page(PageType::LeafTable)?;
-        let schema_row = Row::new(vec![
+        let mut schema_row = Row::new(vec![
             Value::Text("table".to_string()),
             Value::Text(table_name.to_string()),
             Value::Text(table_name.to_string()),
             Value::Integer(new_root_page_id as i64),
             Value::Text(sql.to_string()),
         ]);
-        let schema_file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(&self.db_info.path)?;
-        let mut schema_btree =
-            BPlusTree::new_with_extras(schema_file, 1, Some(BAMBANG_HEADER_SIZE as u64))?;
-        if let Some(new_root) = schema_btree.insert(schema_row, Some(BAMBANG_HEADER_SIZE as u64))? {
-            self.table_roots
-                .insert("sqlite_schema".to_string(), new_root);
+        if kind != TableKind::BTree {
+            schema_row.values.push(Value::Integer(1));
+            schema_row.values.push(Value::Text(kind.as_str().to_string()));
         }
+        self.insert_schema_rows([schema_row])?;
+        self.reload_header()?;
         self.table_roots
             .insert(table_name.to_string(), new_root_page_id);
+        self.next_row_ids.insert(table_name.to_string(), 1);
+        table_schema.root_page_id = new_root_page_id;
+        self.schema_manager.add_table_schema(table_schema);
+        self.record_schema_change(SchemaChange::TableCreated(table_name.to_string()))?;
         Ok(new_root_page_id)
     }
 
+    /// Build a table schema from a CREATE TABLE statement, failing if the statement cannot
+    /// be understood
+    fn schema_from_sql(table_name: &str, root_page_id: PageId, sql: &str) -> Result<TableSchema, DatabaseError> {
+        let (_, columns, primary_key, foreign_keys) =
+            SqlParser::new()
+                .parse_create_table(sql)
+                .map_err(|e| DatabaseError::InvalidTableSchema {
+                    table: table_name.to_string(),
+                    reason: format!("cannot read its CREATE TABLE statement: {}", e),
+                })?;
+        let schema = TableSchema::new(
+            table_name.to_string(),
+            columns,
+            root_page_id,
+            sql.to_string(),
+        )
+        .with_foreign_keys(foreign_keys);
+        Ok(match primary_key.is_empty() {
+            true => schema,
+            false => schema.with_primary_key(primary_key),
+        })
+    }
+
     pub fn insert_into_table(&mut self, table_name: &str, row: Row) -> Result<(), DatabaseError> {
+        if self.table_kind(table_name)? == TableKind::AppendLog {
+            return self.append_to_log(table_name, vec![row]);
+        }
+        self.check_references(table_name, &row, None)?;
         // Create a TableInserter and delegate the insertion
+        self.load_row_id_counter(table_name)?;
+        let mut inserter = TableInserter::new(self, table_name.to_string())?;
+        let written = column_usage::write_counts(std::slice::from_ref(&row));
+        let result = inserter.insert(row);
+        self.finish_insert(table_name, &inserter)?;
+        if result.is_ok() {
+            self.column_usage.record_writes(table_name, &written);
+        }
+        result
+    }
+
+    /// Insert `row` unless a row with the same primary key already exists. Returns
+    /// whether the row was inserted.
+    pub fn insert_or_ignore(&mut self, table_name: &str, row: Row) -> Result<bool, DatabaseError> {
+        // An append log has no unique columns for the row to collide on
+        if self.table_kind(table_name)? == TableKind::AppendLog {
+            return self.append_to_log(table_name, vec![row]).map(|()| true);
+        }
+        self.check_references(table_name, &row, None)?;
+        self.load_row_id_counter(table_name)?;
         let mut inserter = TableInserter::new(self, table_name.to_string())?;
-        inserter.insert(row)?;
-        
-        // Update the root page ID if it changed during insertion
+        let written = column_usage::write_counts(std::slice::from_ref(&row));
+        let result = inserter.insert_or_ignore(row);
+        self.finish_insert(table_name, &inserter)?;
+        if let Ok(true) = result {
+            self.column_usage.record_writes(table_name, &written);
+        }
+        result
+    }
+
+    /// Pick up header, root page and rowid changes made by an inserter, then sync unless
+    /// the sync mode is `Off`. Runs even when the insert failed part-way, since earlier
+    /// rows may already have split the tree.
+    fn finish_insert(&mut self, table_name: &str, inserter: &TableInserter) -> Result<(), DatabaseError> {
+        self.reload_header()?;
         let new_root_page_id = inserter.root_page_id();
-        if let Some(current_root) = self.table_roots.get(table_name) {
-            if *current_root != new_root_page_id {
-                self.update_table_root(table_name, new_root_page_id)?;
+        if let Some(current_root) = self.table_roots.get(table_name)
+            && *current_root != new_root_page_id
+        {
+            self.update_table_root(table_name, new_root_page_id)?;
+        }
+        for index in inserter.indexes() {
+            if self.indexes.get(&index.name).is_some_and(|known| known.root_page_id != index.root_page_id) {
+                self.update_index_root(&index.name, index.root_page_id)?;
             }
         }
-        
-        Ok(())
+        self.finish_row_writes(table_name, inserter.next_row_id())
     }
 
-    fn update_table_root(
-        &mut self,
-        table_name: &str,
-        new_root_page_id: PageId,
-    ) -> Result<(), DatabaseError> {
-        self.table_roots
-            .insert(table_name.to_string(), new_root_page_id);
-        println!(
-            "Updated root page for table '{}' to page {}",
-            table_name, new_root_page_id
-        );
+    /// Persist the table's rowid counter if written rows moved it, count the change, then
+    /// sync unless the sync mode is `Off`
+    fn finish_row_writes(&mut self, table_name: &str, next_row_id: RowId) -> Result<(), DatabaseError> {
+        if self.next_row_ids.get(table_name) != Some(&next_row_id) {
+            self.next_row_ids.insert(table_name.to_string(), next_row_id);
+            self.persist_table_entry(table_name, |row| {
+                let value = Value::Integer(next_row_id as i64);
+                if row.values.len() > 5 {
+                    row.values[5] = value;
+                } else {
+                    row.values.push(value);
+                }
+            })?;
+        }
+        self.record_change()?;
+        // Syncing last makes the new root and rowid counter as durable as the rows.
+        // Inside a transaction only `commit` syncs.
+        if self.options.sync_mode != SyncMode::Off && !self.in_transaction() {
+            self.sync()?;
+        }
         Ok(())
     }
 
-    pub fn allocate_new_page(&mut self, page_type: PageType) -> Result<PageId, DatabaseError> {
-        let new_page_id = self.db_info.page_count + 1;
-        let new_page = Page::new(new_page_id, page_type);
-        self.write_page(new_page_id, &new_page)?;
-        self.db_info.page_count = new_page_id;
-        self.db_info.file_size += PAGE_SIZE as u64;
-        self.db_info.header.database_size_pages = new_page_id as u32;
-        self.update_header_in_file()?;
-        Ok(new_page_id)
+    /// Next rowid the table would assign, derived from the highest stored rowid when the
+    /// counter has not been loaded yet
+    pub fn next_row_id(&self, table_name: &str) -> Result<RowId, DatabaseError> {
+        if let Some(next_row_id) = self.next_row_ids.get(table_name) {
+            return Ok(*next_row_id);
+        }
+        let max_row_id = self.fold_rows(table_name, None, 0, |max, row| {
+            max.max(row.row_id.unwrap_or(0))
+        })?;
+        Ok(max_row_id + 1)
     }
 
-    fn update_header_in_file(&mut self) -> Result<(), DatabaseError> {
-        let header_bytes = self.db_info.header.to_bytes();
-        self.file.seek(SeekFrom::Start(0))?;
-        self.file.write_all(&header_bytes)?;
-        self.file.flush()?;
+    fn load_row_id_counter(&mut self, table_name: &str) -> Result<(), DatabaseError> {
+        if !self.next_row_ids.contains_key(table_name) && self.table_roots.contains_key(table_name) {
+            let next_row_id = self.next_row_id(table_name)?;
+            self.next_row_ids.insert(table_name.to_string(), next_row_id);
+        }
         Ok(())
     }
 
-    fn init_schema_page() -> Page {
-        let mut schema_page = Page::new(1, PageType::LeafTable);
-        let schema_table_row = Row::new(vec![
-            Value::Text("table".to_string()),
-            Value::Text("sqlite_schema".to_string()),
-            Value::Text("sqlite_schema".to_string()),
-            Value::Integer(1),
-            Value::Text("CREATE TABLE sqlite_schema(type text,name text,tbl_name text,rootpage integer,sql text)".to_string()),
-        ]);
-        let row_bytes = schema_table_row.to_bytes();
-        let _ = schema_page.insert_cell(&row_bytes, None);
-        schema_page
+    /// Find the row stamped with `row_id`, through the table's row locator
+    pub fn get_by_rowid(&self, table_name: &str, row_id: RowId) -> Result<Option<Row>, DatabaseError> {
+        if self.table_kind(table_name)? == TableKind::AppendLog {
+            let next = Some(LogPosition::RowId(row_id + 1));
+            return Ok(self.read_log_range(table_name, LogPosition::RowId(row_id), next)?.pop());
+        }
+        if let Some(schema) = self.load_table_schema(table_name)?
+            && let Some(rows) = self.rows_by_id(table_name, schema, vec![row_id], None)?
+        {
+            return Ok(rows.into_iter().next());
+        }
+        let mut scanner = self.create_scanner(table_name, None)?;
+        while let Some(row) = scanner.scan()? {
+            if row.row_id == Some(row_id) {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
     }
 
-    /// Create a sequential scanner for the specified table
-    pub fn create_scanner(
-        &self,
-        table_name: &str,
-        batch_size: Option<usize>,
-    ) -> Result<SequentialScanner, DatabaseError> {
-        SequentialScanner::new(self, table_name.to_string(), batch_size)
+    /// Find the row whose PRIMARY KEY columns hold `key`, given in key order. Composite
+    /// keys and single keys in the first column are point lookups; other keys are scanned.
+    pub fn get_by_primary_key(&self, table_name: &str, key: &[Value]) -> Result<Option<Row>, DatabaseError> {
+        self.require_btree(table_name, "primary key lookups")?;
+        let schema = self
+            .get_table_schema(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        if schema.primary_key.is_empty() || key.len() != schema.primary_key.len() {
+            return Err(DatabaseError::InvalidData {
+                details: format!(
+                    "Table '{}' has a {}-column primary key, got {} values",
+                    table_name,
+                    schema.primary_key.len(),
+                    key.len()
+                ),
+            });
+        }
+        let extras = Some(BAMBANG_HEADER_SIZE as u64);
+        if !schema.key_columns().is_empty() {
+            let (mut btree, _) = self.open_table_btree(table_name)?;
+            return btree.search(&Value::composite_key(key), extras);
+        }
+        if schema.primary_key[0] == 0 {
+            let (mut btree, _) = self.open_table_btree(table_name)?;
+            return Ok(btree
+                .search_all(&key[0], extras)?
+                .into_iter()
+                .find(|row| row.values.first().is_some_and(|stored| stored.total_cmp(&key[0]).is_eq())));
+        }
+        let column = schema.columns[schema.primary_key[0]].name.clone();
+        Ok(self
+            .scan_table(table_name, Some(Predicate::eq(column, key[0].clone())))?
+            .into_iter()
+            .next())
     }
 
-    /// Scan all rows from a table using the scanner, optionally with predicate filtering
-    pub fn scan_table(&self, table_name: &str, predicate: Option<Predicate>) -> Result<Vec<Row>, DatabaseError> {
-        let mut scanner = self.create_scanner(table_name, None)?;
-        let mut rows = Vec::new();
-
-        // Get table schema for predicate validation and evaluation if predicate is provided
-        let table_schema = if predicate.is_some() {
-            Some(self.get_table_schema(table_name)
-                .ok_or_else(|| DatabaseError::TableNotFound {
-                    name: table_name.to_string(),
-                })?)
-        } else {
-            None
+    /// The first of `values` held in the column at `position` by a row of `table_name`
+    /// that `skip` does not exclude. The column is probed with point lookups when it keys
+    /// the table's B+ tree or has an index; otherwise one scan looks for all of them.
+    fn find_stored_value<F>(
+        &self,
+        table_name: &str,
+        position: usize,
+        values: &[Value],
+        skip: F,
+    ) -> Result<Option<Value>, DatabaseError>
+    where
+        F: Fn(&Row) -> bool,
+    {
+        let schema = self.load_table_schema(table_name)?.ok_or_else(|| DatabaseError::TableNotFound {
+            name: table_name.to_string(),
+        })?;
+        let holds = |row: &Row, value: &Value| {
+            !skip(row)
+                && row
+                    .values
+                    .get(position)
+                    .is_some_and(|stored| self.options.value_comparison.equals(stored, value))
         };
-
-        // Validate predicate against schema if provided
-        if let (Some(pred), Some(schema)) = (&predicate, &table_schema) {
-            pred.validate_against_schema(schema)?;
+        let values: Vec<&Value> = values.iter().filter(|value| !value.is_null()).collect();
+        let column = &schema.columns[position].name;
+        if schema.key_columns().is_empty() && position == 0 {
+            let (mut btree, _) = self.open_table_btree(table_name)?;
+            for value in values {
+                let rows = btree.search_all(value, Some(BAMBANG_HEADER_SIZE as u64))?;
+                if rows.iter().any(|row| holds(row, value)) {
+                    return Ok(Some(value.clone()));
+                }
+            }
+            return Ok(None);
         }
-
+        if self.is_column_indexed(table_name, column) {
+            for value in values {
+                if self.lookup_by_index(table_name, column, value)?.iter().any(|row| holds(row, value)) {
+                    return Ok(Some(value.clone()));
+                }
+            }
+            return Ok(None);
+        }
+        let mut scanner = self.create_scanner(table_name, None)?;
         while let Some(row) = scanner.scan()? {
-            // Apply predicate filtering if provided
-            let matches = if let (Some(pred), Some(schema)) = (&predicate, &table_schema) {
-                pred.evaluate(&row, schema)?
-            } else {
-                true // No predicate means all rows match
-            };
-
-            if matches {
-                rows.push(row);
+            if let Some(value) = values.iter().find(|value| holds(&row, value)) {
+                return Ok(Some((*value).clone()));
             }
         }
-
-        Ok(rows)
-    }
-
-    /// Create a table inserter for the specified table
-    pub fn create_inserter(&self, table_name: &str) -> Result<TableInserter, DatabaseError> {
-        TableInserter::new(self, table_name.to_string())
+        Ok(None)
     }
 
-    /// Insert multiple rows into a table using batch insertion
-    pub fn insert_batch_into_table(&mut self, table_name: &str, rows: Vec<Row>) -> Result<(), DatabaseError> {
-        if rows.is_empty() {
+    /// Reject `row` if a value in one of its foreign key columns matches no row of the
+    /// referenced table. NULLs reference nothing, and values `previous` already held were
+    /// checked when stored, so an update only looks up those it changes. A row may
+    /// reference itself.
+    fn check_references(&self, table_name: &str, row: &Row, previous: Option<&Row>) -> Result<(), DatabaseError> {
+        let Some(schema) = self.load_table_schema(table_name)? else {
             return Ok(());
-        }
-
-        // Create a TableInserter and delegate the batch insertion
-        let mut inserter = TableInserter::new(self, table_name.to_string())?;
-        inserter.insert_batch(rows)?;
-        
-        // Update the root page ID if it changed during insertion
-        let new_root_page_id = inserter.root_page_id();
-        if let Some(current_root) = self.table_roots.get(table_name) {
-            if *current_root != new_root_page_id {
-                self.update_table_root(table_name, new_root_page_id)?;
+        };
+        for foreign_key in &schema.foreign_keys {
+            let Some(position) = schema.get_column_index(&foreign_key.column) else {
+                continue;
+            };
+            let Some(value) = row.values.get(position).filter(|value| !value.is_null()) else {
+                continue;
+            };
+            if previous.is_some_and(|previous| previous.values.get(position) == Some(value)) {
+                continue;
+            }
+            let parent = self
+                .load_table_schema(&foreign_key.ref_table)?
+                .ok_or_else(|| DatabaseError::TableNotFound {
+                    name: foreign_key.ref_table.clone(),
+                })?;
+            let ref_position = parent.get_column_index(&foreign_key.ref_column).ok_or_else(|| {
+                DatabaseError::ColumnNotFound {
+                    name: foreign_key.ref_column.clone(),
+                    table: foreign_key.ref_table.clone(),
+                }
+            })?;
+            if foreign_key.ref_table == table_name && row.values.get(ref_position) == Some(value) {
+                continue;
+            }
+            let values = std::slice::from_ref(value);
+            if self.find_stored_value(&foreign_key.ref_table, ref_position, values, |_| false)?.is_none() {
+                return Err(DatabaseError::ForeignKeyViolation {
+                    column: format!("{}.{}", table_name, foreign_key.column),
+                    reference: foreign_key.reference(),
+                    value: value.clone(),
+                });
             }
         }
-        
         Ok(())
     }
 
-    /// Get table schema by name
-    pub fn get_table_schema(&self, table_name: &str) -> Option<&TableSchema> {
-        self.schema_manager.get_table_schema(table_name)
+    /// Foreign keys referencing `table_name`, each with the table declaring it, which may
+    /// be `table_name` itself
+    fn referencing_foreign_keys(&self, table_name: &str) -> Vec<(String, ForeignKey)> {
+        let mut referencing = Vec::new();
+        for child in self.get_table_names() {
+            let Some(schema) = self.get_table_schema(&child) else {
+                continue;
+            };
+            for foreign_key in schema.foreign_keys.iter().filter(|foreign_key| foreign_key.ref_table == table_name) {
+                referencing.push((child.clone(), foreign_key.clone()));
+            }
+        }
+        referencing
     }
 
-    /// Add a new table schema and persist it
-    pub fn add_table_schema(&mut self, schema: TableSchema) -> Result<(), DatabaseError> {
-        // Store table entry in sqlite_schema
-        let table_row = Row::new(vec![
-            Value::Text("table".to_string()),
-            Value::Text(schema.table_name.clone()),
-            Value::Text(schema.table_name.clone()),
-            Value::Integer(schema.root_page_id as i64),
-            Value::Text(schema.sql.clone()),
-        ]);
-
-        let schema_file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(&self.db_info.path)?;
-        let mut schema_btree =
-            BPlusTree::new_with_extras(schema_file, 1, Some(BAMBANG_HEADER_SIZE as u64))?;
-        
-        // Insert table entry
-        if let Some(new_root) = schema_btree.insert(table_row, Some(BAMBANG_HEADER_SIZE as u64))? {
-            self.table_roots.insert("sqlite_schema".to_string(), new_root);
-        }
-
-        // Store column entries
-        for column in &schema.columns {
-            let column_row = column.to_schema_row(&schema.table_name);
-            let schema_file = OpenOptions::new()
-                .read(true)
-                .write(true)
-                .open(&self.db_info.path)?;
-            let mut schema_btree =
-                BPlusTree::new_with_extras(schema_file, 1, Some(BAMBANG_HEADER_SIZE as u64))?;
-            
-            if let Some(new_root) = schema_btree.insert(column_row, Some(BAMBANG_HEADER_SIZE as u64))? {
-                self.table_roots.insert("sqlite_schema".to_string(), new_root);
+    /// Reject changes to rows of `table_name` that would take away a value rows of a
+    /// referencing table still hold. Each change is a row's old contents and its new ones,
+    /// `None` for a deleted row. Rows the change itself rewrites or deletes no longer hold
+    /// their old values, so a table may drop rows that only reference each other.
+    fn check_not_referenced(
+        &self,
+        table_name: &str,
+        schema: &TableSchema,
+        referencing: &[(String, ForeignKey)],
+        changes: &[(&Row, Option<&Row>)],
+    ) -> Result<(), DatabaseError> {
+        let changed: HashSet<RowId> = changes.iter().filter_map(|(row, _)| row.row_id).collect();
+        for (child, foreign_key) in referencing {
+            let Some(position) = schema.get_column_index(&foreign_key.ref_column) else {
+                continue;
+            };
+            let removed: Vec<Value> = changes
+                .iter()
+                .filter_map(|(old, new)| {
+                    let value = old.values.get(position)?;
+                    let kept = new.is_some_and(|new| new.values.get(position) == Some(value));
+                    (!kept).then(|| value.clone())
+                })
+                .collect();
+            if removed.is_empty() {
+                continue;
+            }
+            let child_schema = self.load_table_schema(child)?.ok_or_else(|| DatabaseError::TableNotFound {
+                name: child.clone(),
+            })?;
+            let Some(child_position) = child_schema.get_column_index(&foreign_key.column) else {
+                continue;
+            };
+            let skip = |row: &Row| child == table_name && row.row_id.is_some_and(|row_id| changed.contains(&row_id));
+            if let Some(value) = self.find_stored_value(child, child_position, &removed, skip)? {
+                return Err(DatabaseError::ForeignKeyViolation {
+                    column: format!("{}.{}", child, foreign_key.column),
+                    reference: foreign_key.reference(),
+                    value,
+                });
             }
         }
+        Ok(())
+    }
 
-        // Add to in-memory schema manager
-        self.table_roots.insert(schema.table_name.clone(), schema.root_page_id);
-        self.schema_manager.add_table_schema(schema);
-        
+    fn update_table_root(
+        &mut self,
+        table_name: &str,
+        new_root_page_id: PageId,
+    ) -> Result<(), DatabaseError> {
+        self.table_roots
+            .insert(table_name.to_string(), new_root_page_id);
+        self.persist_table_root(table_name, new_root_page_id)?;
+        self.schema_manager.set_root_page_id(table_name, new_root_page_id);
+        Self::log_with(&self.options, || {
+            format!("Updated root page for table '{}' to page {}", table_name, new_root_page_id)
+        });
         Ok(())
     }
 
-    /// Validate a row against table schema
-    pub fn validate_row(&self, table_name: &str, row: &Row) -> Result<(), DatabaseError> {
-        if let Some(schema) = self.get_table_schema(table_name) {
-            schema.validate_row(row)
-        } else {
-            Err(DatabaseError::TableNotFound {
-                name: table_name.to_string(),
-            })
-        }
+    /// Rewrite the rootpage column of the table's `sqlite_schema` entry so reopened
+    /// databases and resetting scanners find the current root
+    fn persist_table_root(
+        &mut self,
+        table_name: &str,
+        new_root_page_id: PageId,
+    ) -> Result<(), DatabaseError> {
+        self.persist_table_entry(table_name, |row| {
+            row.values[3] = Value::Integer(new_root_page_id as i64);
+        })
     }
 
-    /// Apply default values to a row based on table schema
-    pub fn apply_defaults(&self, table_name: &str, row: &mut Row) -> Result<(), DatabaseError> {
-        if let Some(schema) = self.get_table_schema(table_name) {
-            schema.apply_defaults(row)
-        } else {
-            Err(DatabaseError::TableNotFound {
-                name: table_name.to_string(),
-            })
-        }
+    /// Rewrite the table's `sqlite_schema` entry in place
+    fn persist_table_entry<F>(&mut self, table_name: &str, update: F) -> Result<(), DatabaseError>
+    where
+        F: FnOnce(&mut Row),
+    {
+        self.persist_schema_entry("table", table_name, update)
+    }
+
+    /// Rewrite the `entry_type` entry named `name` in `sqlite_schema` in place
+    fn persist_schema_entry<F>(&mut self, entry_type: &str, name: &str, update: F) -> Result<(), DatabaseError>
+    where
+        F: FnOnce(&mut Row),
+    {
+        let Some(location) = self.find_schema_entry(entry_type, name)? else {
+            return Ok(());
+        };
+        let mut schema_page = self.read_page(location.page_id)?;
+        let i = location.slot_index;
+        let mut row = Row::from_bytes(schema_page.get_cell(i).expect("slot was just found"))?;
+        update(&mut row);
+        let row_id = schema_page.slot_directory.slots[i].row_id;
+        schema_page.update_cell(i, &row.to_bytes(), row_id)?;
+        self.write_page(location.page_id, &schema_page)
+    }
+
+    /// Where the `entry_type` entry named `name` is stored in `sqlite_schema`
+    fn find_schema_entry(&self, entry_type: &str, name: &str) -> Result<Option<SchemaCellLocation>, DatabaseError> {
+        let mut found = None;
+        self.for_each_schema_row(|location, row| {
+            let matches = matches!(&row.values[..], [Value::Text(row_type), Value::Text(row_name), ..]
+                if row_type == entry_type && row_name == name);
+            if matches && row.values.len() >= 5 {
+                found = Some(location);
+                return Ok(ControlFlow::Break(()));
+            }
+            Ok(ControlFlow::Continue(()))
+        })?;
+        Ok(found)
+    }
+
+    pub fn allocate_new_page(&mut self, page_type: PageType) -> Result<PageId, DatabaseError> {
+        self.reload_header()?;
+        if let Some(page_id) = freelist::pop_free_page(&mut self.file, &mut self.db_info.header)? {
+            let new_page = Page::new_with_size(page_id, page_type, self.page_size());
+            self.write_page(page_id, &new_page)?;
+            self.db_info.header.increment_change_counter();
+            self.update_header_in_file()?;
+            return Ok(page_id);
+        }
+        self.db_info.header.ensure_can_grow(1)?;
+        let new_page_id = self.db_info.page_count + 1;
+        let new_page = Page::new_with_size(new_page_id, page_type, self.page_size());
+        self.write_page(new_page_id, &new_page)?;
+        self.db_info.page_count = new_page_id;
+        self.db_info.file_size += self.page_size() as u64;
+        self.db_info.header.database_size_pages = new_page_id as u32;
+        self.db_info.header.increment_change_counter();
+        self.update_header_in_file()?;
+        self.check_quota_warning();
+        Ok(new_page_id)
+    }
+
+    /// Load every in-use page and report those whose stored header disagreed with their
+    /// slot directory, along with what loading them repaired
+    pub fn check_page_consistency(&mut self) -> Result<Vec<(PageId, Vec<String>)>, DatabaseError> {
+        self.reload_header()?;
+        let free_pages = freelist::free_page_ids(&mut self.file, &self.db_info.header)?;
+        let mut reports = Vec::new();
+        for page_id in 1..=self.db_info.page_count {
+            if free_pages.contains(&page_id) {
+                continue;
+            }
+            let page = self.read_page(page_id)?;
+            if !page.reconciliation_warnings.is_empty() {
+                reports.push((page_id, page.reconciliation_warnings));
+            }
+        }
+        Ok(reports)
+    }
+
+    /// Verify that `sqlite_schema` is rooted at page 1 as the header records, that page 1
+    /// is not on the freelist, and that the schema leaves reached through the tree are the
+    /// ones on its leaf chain, in the same order
+    pub fn check_schema_anchor(&mut self) -> Result<(), DatabaseError> {
+        self.reload_header()?;
+        let corrupted = |reason: String| DatabaseError::CorruptedDatabase { reason };
+        let root_page_id = self.schema_root_page_id();
+        if root_page_id != SCHEMA_ROOT_PAGE_ID {
+            return Err(corrupted(format!(
+                "Header records page {} as the root of sqlite_schema, expected {}",
+                root_page_id, SCHEMA_ROOT_PAGE_ID
+            )));
+        }
+        if self.table_roots.get("sqlite_schema") != Some(&root_page_id) {
+            return Err(corrupted(format!(
+                "sqlite_schema is not rooted at page {} in the schema catalog",
+                root_page_id
+            )));
+        }
+        if self.free_page_ids()?.contains(&root_page_id) {
+            return Err(corrupted(format!("Schema root page {} is on the freelist", root_page_id)));
+        }
+        let root = self.read_page(root_page_id)?;
+        if !matches!(root.page_type, PageType::LeafTable | PageType::InteriorTable) {
+            return Err(corrupted(format!(
+                "Schema root page {} is a {:?} page",
+                root_page_id, root.page_type
+            )));
+        }
+
+        let mut tree_leaves = Vec::new();
+        let tree_pages = self
+            .readable_btree(self.open_store()?, root_page_id)?
+            .page_ids(Some(BAMBANG_HEADER_SIZE as u64))?;
+        for page_id in tree_pages {
+            if self.read_page(page_id)?.page_type == PageType::LeafTable {
+                tree_leaves.push(page_id);
+            }
+        }
+        let mut chain = Vec::new();
+        let mut prev_page_id = None;
+        let mut next_page_id = tree_leaves.first().copied();
+        // Stop one past the tree's leaf count so a cycle in the chain cannot loop forever
+        while let Some(page_id) = next_page_id
+            && chain.len() <= tree_leaves.len()
+        {
+            let page = self.read_page(page_id)?;
+            if page.prev_leaf_page_id != prev_page_id {
+                return Err(corrupted(format!(
+                    "Schema leaf {} links back to {:?} instead of {:?}",
+                    page_id, page.prev_leaf_page_id, prev_page_id
+                )));
+            }
+            chain.push(page_id);
+            prev_page_id = Some(page_id);
+            next_page_id = page.next_leaf_page_id;
+        }
+        if chain != tree_leaves {
+            return Err(corrupted(format!(
+                "Schema leaf chain {:?} does not match the leaves under the root {:?}",
+                chain, tree_leaves
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check the whole file: the header against the file size, then every page of
+    /// `sqlite_schema`, each table and each index, reached from their roots. Problems are
+    /// reported page by page rather than failing on the first one; an `Err` means the
+    /// file could not be read at all.
+    pub fn check_integrity(&mut self) -> Result<IntegrityReport, DatabaseError> {
+        let free_pages = freelist::free_page_ids(&mut self.file, &self.db_info.header);
+        let mut checker = IntegrityChecker::new(self.file.as_ref(), self.page_size(), &self.db_info.header);
+        match free_pages {
+            Ok(free_pages) => checker.set_free_pages(free_pages),
+            Err(e) => checker.record_error(None, &e),
+        }
+
+        let schema_root_page_id = self.schema_root_page_id();
+        checker.check_btree("sqlite_schema", schema_root_page_id);
+        let mut tables: Vec<(&String, &PageId)> =
+            self.table_roots.iter().filter(|(name, _)| name.as_str() != "sqlite_schema").collect();
+        tables.sort();
+        for (table_name, &root_page_id) in tables {
+            match self.table_kind(table_name) {
+                Ok(TableKind::AppendLog) => checker.check_append_log(table_name, root_page_id),
+                Ok(TableKind::BTree) => checker.check_btree(table_name, root_page_id),
+                Err(e) => {
+                    checker.record_error(Some(table_name), &e);
+                    checker.check_btree(table_name, root_page_id);
+                }
+            }
+        }
+        let mut indexes: Vec<&TableIndex> = self.indexes.values().collect();
+        indexes.sort_by(|a, b| a.name.cmp(&b.name));
+        for index in indexes {
+            checker.check_btree(&index.name, index.root_page_id);
+        }
+        Ok(checker.finish())
+    }
+
+    /// Return a page to the freelist so later allocations can reuse it
+    pub fn free_page(&mut self, page_id: PageId) -> Result<(), DatabaseError> {
+        self.reload_header()?;
+        if let Some(journal) = &self.journal {
+            journal.save_page(&mut self.file, page_id)?;
+        }
+        freelist::push_free_page(&mut self.file, &mut self.db_info.header, page_id)?;
+        self.db_info.header.increment_change_counter();
+        self.update_header_in_file()?;
+        Ok(())
+    }
+
+    /// Ids of all pages currently on the freelist, most recently freed first
+    pub fn free_page_ids(&mut self) -> Result<Vec<PageId>, DatabaseError> {
+        self.reload_header()?;
+        freelist::free_page_ids(&mut self.file, &self.db_info.header)
+    }
+
+    /// Start a transaction: until `commit` or `rollback`, the original image of every page
+    /// modified is kept so `rollback` can restore the file as it is now. Transactions do
+    /// not nest.
+    pub fn begin_transaction(&mut self) -> Result<(), DatabaseError> {
+        if self.journal.is_some() {
+            return Err(DatabaseError::ExecutionError {
+                details: "A transaction is already active".to_string(),
+            });
+        }
+        self.file.flush()?;
+        let page_size = self.page_size();
+        let journal = RollbackJournal::begin(&mut self.file, page_size)?;
+        self.journal = Some(Arc::new(journal));
+        Ok(())
+    }
+
+    /// Run `f`, undoing every page it wrote if it fails. Inside a transaction the
+    /// transaction's journal already covers the writes, and undoing them is left to its
+    /// `rollback`.
+    fn with_rollback<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, DatabaseError>) -> Result<T, DatabaseError> {
+        if self.in_transaction() {
+            return f(self);
+        }
+        self.begin_transaction()?;
+        match f(self) {
+            Ok(value) => {
+                self.journal = None;
+                Ok(value)
+            }
+            Err(e) => {
+                self.rollback()?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Whether `begin_transaction` has been called without a matching commit or rollback
+    pub fn in_transaction(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Make the transaction's changes durable and discard the saved page images
+    pub fn commit(&mut self) -> Result<(), DatabaseError> {
+        self.active_journal()?;
+        self.file.sync()?;
+        self.journal = None;
+        Ok(())
+    }
+
+    /// Undo every change made since `begin_transaction`, restoring modified pages,
+    /// dropping allocated ones, and reloading tables, schemas and rowid counters
+    pub fn rollback(&mut self) -> Result<(), DatabaseError> {
+        let journal = self.active_journal()?;
+        self.journal = None;
+        self.reload_header()?;
+        let schema_cookie = self.db_info.header.schema_cookie;
+        journal.restore(&mut self.file)?;
+        self.row_locators.clear();
+        self.reload_schema_state()?;
+        if self.db_info.header.schema_cookie != schema_cookie {
+            self.schema_notifier.emit(SchemaEvent {
+                schema_cookie: self.db_info.header.schema_cookie,
+                change: SchemaChange::SchemasReloaded,
+            });
+        }
+        Ok(())
+    }
+
+    /// Make every write so far durable. Under `SyncMode::Off`, the default, inserts leave
+    /// this to the caller: rows written since the last sync, `commit` or `close` may be
+    /// lost or partly written if the machine crashes.
+    pub fn sync(&mut self) -> Result<(), DatabaseError> {
+        self.file.sync()?;
+        Ok(())
+    }
+
+    /// Flush every write to disk and close the database. A transaction still open is
+    /// rolled back first, since its journal lives only in memory.
+    pub fn close(mut self) -> Result<(), DatabaseError> {
+        if self.in_transaction() {
+            self.rollback()?;
+        }
+        if self.options.persist_column_usage {
+            self.persist_column_usage()?;
+        }
+        self.file.sync()?;
+        Ok(())
+    }
+
+    fn active_journal(&self) -> Result<Arc<RollbackJournal>, DatabaseError> {
+        self.journal.clone().ok_or_else(|| DatabaseError::ExecutionError {
+            details: "No transaction is active".to_string(),
+        })
+    }
+
+    /// Refresh the cached header, which B+ tree operations update directly in the file
+    fn reload_header(&mut self) -> Result<(), DatabaseError> {
+        let header = BambangHeader::read_from(&mut self.file)?;
+        self.db_info.page_count = header.database_size_pages as u64;
+        self.db_info.file_size = self.file.len()?;
+        self.db_info.header = header;
+        self.check_quota_warning();
+        Ok(())
+    }
+
+    /// Current database size against the configured quota
+    pub fn quota_usage(&self) -> QuotaUsage {
+        QuotaUsage {
+            used_bytes: self.db_info.header.database_size_bytes(),
+            limit_bytes: self.db_info.header.max_database_size(),
+        }
+    }
+
+    /// Persist a new size quota in the header; `None` removes it
+    pub fn set_max_database_size(&mut self, max_bytes: Option<u64>) -> Result<(), DatabaseError> {
+        self.reload_header()?;
+        self.db_info.header.set_max_database_size(max_bytes);
+        self.update_header_in_file()?;
+        self.quota_warned = false;
+        self.check_quota_warning();
+        Ok(())
+    }
+
+    /// Application-defined version number stored in the header, like SQLite's
+    /// `PRAGMA user_version`
+    pub fn user_version(&self) -> u32 {
+        self.db_info.header.user_version
+    }
+
+    pub fn set_user_version(&mut self, version: u32) -> Result<(), DatabaseError> {
+        self.reload_header()?;
+        self.db_info.header.user_version = version;
+        self.db_info.header.increment_change_counter();
+        self.update_header_in_file()
+    }
+
+    /// Create a named sequence usable from any table. `min_value` and `max_value` default
+    /// to the range of the increment's sign.
+    pub fn create_sequence(
+        &mut self,
+        name: &str,
+        start: i64,
+        increment: i64,
+        min_value: Option<i64>,
+        max_value: Option<i64>,
+        cycle: bool,
+    ) -> Result<(), DatabaseError> {
+        if self.sequences.contains_key(name) {
+            return Err(DatabaseError::SequenceAlreadyExists {
+                name: name.to_string(),
+            });
+        }
+        let sequence = Sequence::new(name, start, increment, min_value, max_value, cycle)?;
+        self.insert_schema_row(sequence.to_schema_row())?;
+        self.sequences.insert(name.to_string(), sequence);
+        self.record_change()
+    }
+
+    /// Advance the sequence and return its new value. A block of values is reserved on
+    /// disk first, so values are never reused after a crash, though some may be skipped.
+    pub fn next_val(&mut self, name: &str) -> Result<i64, DatabaseError> {
+        let block_size = self.options.sequence_cache_size;
+        let sequence = self
+            .sequences
+            .get_mut(name)
+            .ok_or_else(|| DatabaseError::SequenceNotFound {
+                name: name.to_string(),
+            })?;
+        if sequence.needs_reservation() {
+            let restart = sequence.reserve(block_size);
+            self.persist_schema_entry("sequence", name, |row| {
+                row.values[10] = restart.map(Value::Integer).unwrap_or(Value::Null);
+            })?;
+            self.record_change()?;
+        }
+        self.sequences
+            .get_mut(name)
+            .expect("sequence was found above")
+            .advance()
+    }
+
+    /// Value most recently returned by `next_val` in this session, if any
+    pub fn current_val(&self, name: &str) -> Result<Option<i64>, DatabaseError> {
+        self.sequences
+            .get(name)
+            .map(Sequence::current_val)
+            .ok_or_else(|| DatabaseError::SequenceNotFound {
+                name: name.to_string(),
+            })
+    }
+
+    pub fn drop_sequence(&mut self, name: &str) -> Result<(), DatabaseError> {
+        if self.sequences.remove(name).is_none() {
+            return Err(DatabaseError::SequenceNotFound {
+                name: name.to_string(),
+            });
+        }
+        if let Some(location) = self.find_schema_entry("sequence", name)? {
+            let mut schema_page = self.read_page(location.page_id)?;
+            schema_page.delete_cell(location.slot_index)?;
+            self.write_page(location.page_id, &schema_page)?;
+        }
+        self.record_change()
+    }
+
+    /// Build a non-unique index on `column` of `table_name` from the rows already stored.
+    /// Later inserts, updates and deletes keep it current, and `lookup_by_index` uses it.
+    pub fn create_index(&mut self, table_name: &str, column: &str) -> Result<(), DatabaseError> {
+        self.require_btree(table_name, "indexes")?;
+        let schema = self
+            .load_table_schema(table_name)?
+            .cloned()
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        let position = schema
+            .get_column_index(column)
+            .ok_or_else(|| DatabaseError::ColumnNotFound {
+                name: column.to_string(),
+                table: table_name.to_string(),
+            })?;
+        let name = TableIndex::name_for(table_name, column);
+        if self.indexes.contains_key(&name) {
+            return Err(DatabaseError::IndexAlreadyExists { name });
+        }
+
+        let entries = self.fold_rows(table_name, None, Vec::new(), |mut entries, row| {
+            entries.extend(TableIndex::entry_for(row, position, schema.key_columns()));
+            entries
+        })?;
+        let root_page_id = self.allocate_new_page(PageType::LeafTable)?;
+        let mut btree = self.writable_btree(self.open_store()?, root_page_id)?;
+        for entry in entries {
+            btree.insert(entry, Some(BAMBANG_HEADER_SIZE as u64))?;
+        }
+        btree.file.flush()?;
+        self.reload_header()?;
+
+        let index = TableIndex::new(table_name, column, btree.root_page_id);
+        self.insert_schema_row(index.to_schema_row())?;
+        self.indexes.insert(index.name.clone(), index);
+        self.record_schema_change(SchemaChange::TableAltered(table_name.to_string()))
+    }
+
+    /// Rows of `table_name` whose `column` equals `value`, found through the index on
+    /// that column rather than by scanning the table. NULL matches nothing.
+    pub fn lookup_by_index(&self, table_name: &str, column: &str, value: &Value) -> Result<Vec<Row>, DatabaseError> {
+        let (index, position) = self
+            .table_indexes(table_name)?
+            .into_iter()
+            .find(|(index, _)| index.column_name == column)
+            .ok_or_else(|| DatabaseError::IndexNotFound {
+                table: table_name.to_string(),
+                column: column.to_string(),
+            })?;
+        if value.is_null() {
+            return Ok(Vec::new());
+        }
+        let extras = Some(BAMBANG_HEADER_SIZE as u64);
+        let mut keys: Vec<Value> = self
+            .readable_btree(self.open_store()?, index.root_page_id)?
+            .with_io_counters(self.io_counters.clone())
+            .search_all(value, extras)?
+            .into_iter()
+            .filter_map(|entry| entry.values.get(1).cloned())
+            .collect();
+        keys.sort_by(|a, b| a.total_cmp(b));
+        keys.dedup_by(|a, b| a.total_cmp(b).is_eq());
+
+        // Several rows may share a key, so each is checked against the value again
+        let (mut btree, _) = self.open_table_btree(table_name)?;
+        let mut rows = Vec::new();
+        for key in keys {
+            rows.extend(btree.search_all(&key, extras)?.into_iter().filter(|row| {
+                row.values
+                    .get(position)
+                    .is_some_and(|stored| stored.total_cmp(value).is_eq())
+            }));
+        }
+        Ok(rows)
+    }
+
+    fn has_indexes(&self, table_name: &str) -> bool {
+        self.indexes.values().any(|index| index.table_name == table_name)
+    }
+
+    /// Secondary indexes on `table_name`, each with the position of the column it covers
+    pub(crate) fn table_indexes(&self, table_name: &str) -> Result<Vec<(TableIndex, usize)>, DatabaseError> {
+        let mut indexes: Vec<&TableIndex> = self
+            .indexes
+            .values()
+            .filter(|index| index.table_name == table_name)
+            .collect();
+        if indexes.is_empty() {
+            return Ok(Vec::new());
+        }
+        indexes.sort_by(|a, b| a.name.cmp(&b.name));
+        let schema = self
+            .load_table_schema(table_name)?
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        indexes
+            .into_iter()
+            .map(|index| {
+                let position = schema.get_column_index(&index.column_name).ok_or_else(|| {
+                    DatabaseError::CorruptedDatabase {
+                        reason: format!(
+                            "Index '{}' covers column '{}', which table '{}' does not have",
+                            index.name, index.column_name, table_name
+                        ),
+                    }
+                })?;
+                Ok((index.clone(), position))
+            })
+            .collect()
+    }
+
+    /// Bring the table's indexes in line with rewritten rows: each change is a row's old
+    /// and new contents, `None` for a row that was inserted or deleted
+    fn update_indexes(&mut self, table_name: &str, changes: &[(Option<Row>, Option<Row>)]) -> Result<(), DatabaseError> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let extras = Some(BAMBANG_HEADER_SIZE as u64);
+        let key_columns = self
+            .get_table_schema(table_name)
+            .map(|schema| schema.key_columns().to_vec())
+            .unwrap_or_default();
+        for (index, position) in self.table_indexes(table_name)? {
+            let mut btree = self.writable_btree(self.open_store()?, index.root_page_id)?;
+            let mut touched_pages = Vec::new();
+            let mut additions = Vec::new();
+            for (old_row, new_row) in changes {
+                let old_entry = old_row.as_ref().and_then(|row| TableIndex::entry_for(row, position, &key_columns));
+                let new_entry = new_row.as_ref().and_then(|row| TableIndex::entry_for(row, position, &key_columns));
+                if old_entry == new_entry {
+                    continue;
+                }
+                if let Some(old_entry) = old_entry {
+                    let stale = btree
+                        .search_cells(&old_entry.values[0], extras)?
+                        .into_iter()
+                        .find(|(_, _, entry)| entry.values.get(1) == old_entry.values.get(1));
+                    if let Some((page_id, slot_index, _)) = stale {
+                        btree.delete_cell(page_id, slot_index, extras)?;
+                        touched_pages.push(page_id);
+                    }
+                }
+                additions.extend(new_entry);
+            }
+            touched_pages.sort_unstable();
+            touched_pages.dedup();
+            for page_id in &touched_pages {
+                btree.compact_page(*page_id, extras)?;
+            }
+            for entry in additions {
+                btree.insert(entry, extras)?;
+            }
+            if !touched_pages.is_empty() {
+                btree.rebalance(extras)?;
+            }
+            btree.file.flush()?;
+            self.reload_header()?;
+            if btree.root_page_id != index.root_page_id {
+                self.update_index_root(&index.name, btree.root_page_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the new root of an index tree that split or shrank
+    fn update_index_root(&mut self, index_name: &str, new_root_page_id: PageId) -> Result<(), DatabaseError> {
+        if let Some(index) = self.indexes.get_mut(index_name) {
+            index.root_page_id = new_root_page_id;
+        }
+        self.persist_schema_entry("index", index_name, |row| {
+            row.values[3] = Value::Integer(new_root_page_id as i64);
+        })
+    }
+
+    /// Fire the quota warning hook once each time usage rises past the threshold
+    fn check_quota_warning(&mut self) {
+        let usage = self.quota_usage();
+        let above = usage
+            .fraction()
+            .is_some_and(|fraction| fraction >= self.options.quota_warning_threshold);
+        if above
+            && !self.quota_warned
+            && let Some(hook) = &self.options.on_quota_warning
+        {
+            hook(&usage);
+        }
+        self.quota_warned = above;
+    }
+
+    /// Number of write transactions committed to the file, as of the last header read.
+    /// Another handle on the same file can poll this to notice that the data changed.
+    pub fn change_counter(&self) -> u32 {
+        self.db_info.header.file_change_counter
+    }
+
+    /// Bump the header's change counter after a mutating operation and persist it
+    fn record_change(&mut self) -> Result<(), DatabaseError> {
+        self.reload_header()?;
+        self.db_info.header.increment_change_counter();
+        self.update_header_in_file()
+    }
+
+    /// Bump the schema cookie along with the change counter, then tell watchers what changed
+    fn record_schema_change(&mut self, change: SchemaChange) -> Result<(), DatabaseError> {
+        self.reload_header()?;
+        self.db_info.header.increment_schema_cookie();
+        self.db_info.header.increment_change_counter();
+        self.update_header_in_file()?;
+        self.schema_notifier.emit(SchemaEvent {
+            schema_cookie: self.db_info.header.schema_cookie,
+            change,
+        });
+        Ok(())
+    }
+
+    /// Schema version stored in the header; bumped by every DDL operation
+    pub fn schema_cookie(&self) -> u32 {
+        self.db_info.header.schema_cookie
+    }
+
+    /// Receive an event for every schema change made through this handle. Watchers that
+    /// fall behind miss events rather than stalling DDL; see `SchemaWatcher::missed_events`.
+    pub fn subscribe_schema_changes(&mut self) -> SchemaWatcher {
+        self.schema_notifier.subscribe(self.db_info.header.schema_cookie)
+    }
+
+    /// Number of live schema watchers; dropped watchers are forgotten on the next event
+    pub fn schema_subscriber_count(&self) -> usize {
+        self.schema_notifier.subscriber_count()
+    }
+
+    /// Re-read every table, column, sequence and index entry from `sqlite_schema`, picking up
+    /// changes made through other handles on the same file
+    pub fn reload_schemas(&mut self) -> Result<(), DatabaseError> {
+        self.reload_schema_state()?;
+        self.schema_notifier.emit(SchemaEvent {
+            schema_cookie: self.db_info.header.schema_cookie,
+            change: SchemaChange::SchemasReloaded,
+        });
+        Ok(())
+    }
+
+    fn reload_schema_state(&mut self) -> Result<(), DatabaseError> {
+        self.reload_header()?;
+        self.table_roots.retain(|name, _| name == "sqlite_schema");
+        self.next_row_ids.clear();
+        self.log_tails.clear();
+        self.schema_manager = SchemaManager::new();
+        self.sequences.clear();
+        self.indexes.clear();
+        self.load_table_roots_and_schemas()
+    }
+
+    fn update_header_in_file(&mut self) -> Result<(), DatabaseError> {
+        let header_bytes = self.db_info.header.to_bytes();
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header_bytes)?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn init_schema_page(page_size: usize) -> Page {
+        let mut schema_page = Page::new_with_size(SCHEMA_ROOT_PAGE_ID, PageType::LeafTable, page_size);
+        let schema_table_row = Row::new(vec![
+            Value::Text("table".to_string()),
+            Value::Text("sqlite_schema".to_string()),
+            Value::Text("sqlite_schema".to_string()),
+            Value::Integer(SCHEMA_ROOT_PAGE_ID as i64),
+            Value::Text("CREATE TABLE sqlite_schema(type text,name text,tbl_name text,rootpage integer,sql text)".to_string()),
+        ]);
+        let row_bytes = schema_table_row.to_bytes();
+        let _ = schema_page.insert_cell(&row_bytes, None);
+        schema_page
+    }
+
+    /// Page writes made through this manager's B+ trees since the database was opened
+    pub fn io_stats(&self) -> IoStats {
+        self.io_counters.snapshot()
+    }
+
+    /// Page space `row` will take when inserted into `table_name`, counting the rowid the
+    /// inserter assigns to rows without one
+    pub fn row_storage_cost(&self, table_name: &str, row: &Row) -> Result<StorageCost, DatabaseError> {
+        if !self.table_roots.contains_key(table_name) {
+            return Err(DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            });
+        }
+        let assigned_row_id = if row.row_id.is_none() { 8 } else { 0 };
+        Ok(StorageCost::for_cell_with_page_size(row.size() + assigned_row_id, self.page_size()))
+    }
+
+    /// Filtered scans this manager has run, as fed to `recommend_indexes`
+    pub fn workload_log(&self) -> &WorkloadLog {
+        &self.workload
+    }
+
+    /// Per-session column read and write counters, which queries compiled outside the
+    /// storage manager feed as well
+    pub fn column_usage_log(&self) -> &ColumnUsageLog {
+        &self.column_usage
+    }
+
+    /// How often each column of `table_name` has been read by projections and predicates
+    /// and written by inserts and updates, in column order. Counts cover this session, or
+    /// every session with `persist_column_usage` on.
+    pub fn column_usage(&self, table_name: &str) -> Result<Vec<ColumnUsage>, DatabaseError> {
+        let schema = self.require_table_schema(table_name)?;
+        let counts = self.column_usage.snapshot(table_name, schema.columns.len());
+        let mut columns: Vec<&ColumnSchema> = schema.columns.iter().collect();
+        columns.sort_by_key(|column| column.position);
+        Ok(columns
+            .into_iter()
+            .map(|column| {
+                let [reads, writes, non_null_writes] = counts[column.position];
+                ColumnUsage {
+                    name: column.name.clone(),
+                    reads,
+                    writes,
+                    non_null_ratio: if writes == 0 { 0.0 } else { non_null_writes as f64 / writes as f64 },
+                }
+            })
+            .collect())
+    }
+
+    /// Count a read of every column `predicate` references
+    fn record_predicate_reads(&self, schema: &TableSchema, predicate: &Predicate) {
+        let positions = predicate
+            .get_referenced_columns()
+            .iter()
+            .filter_map(|column| schema.get_column_index(column))
+            .collect::<Vec<_>>();
+        self.column_usage.record_reads(&schema.table_name, positions);
+    }
+
+    /// Save the column usage counters as one `usage` entry per table in `sqlite_schema`,
+    /// replacing the entries saved by an earlier session
+    fn persist_column_usage(&mut self) -> Result<(), DatabaseError> {
+        for table_name in self.column_usage.tables() {
+            let Some(column_count) = self.load_table_schema(&table_name)?.map(|schema| schema.columns.len()) else {
+                continue;
+            };
+            let row = column_usage::usage_schema_row(&table_name, &self.column_usage.snapshot(&table_name, column_count));
+            let Some(location) = self.find_schema_entry("usage", &table_name)? else {
+                self.insert_schema_row(row)?;
+                continue;
+            };
+            let mut schema_page = self.read_page(location.page_id)?;
+            let stored = Row::from_bytes(schema_page.get_cell(location.slot_index).expect("slot was just found"))?;
+            // Counts are fixed-width integers, so the entry of a table whose columns did
+            // not change is rewritten in place rather than leaving a deleted slot behind
+            if stored.values.len() == row.values.len() {
+                self.persist_schema_entry("usage", &table_name, |stored| *stored = row)?;
+            } else {
+                schema_page.delete_cell(location.slot_index)?;
+                self.write_page(location.page_id, &schema_page)?;
+                self.insert_schema_row(row)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether lookups on `column` of `table_name` avoid a full scan: it is the column the
+    /// table's B+ tree is keyed on or has an index of its own. A composite key is only
+    /// looked up whole, through `get_by_primary_key`.
+    pub fn is_column_indexed(&self, table_name: &str, column: &str) -> bool {
+        self.indexes.contains_key(&TableIndex::name_for(table_name, column))
+            || self.get_table_schema(table_name).is_some_and(|schema| {
+                schema.key_columns().is_empty() && schema.get_column_index(column) == Some(0)
+            })
+    }
+
+    /// Suggest indexes for columns that selective filtered scans within
+    /// `observation_window` kept scanning in full, most pages saved first
+    pub fn recommend_indexes(&self, observation_window: Duration) -> Vec<IndexRecommendation> {
+        self.workload
+            .recommend(observation_window, |table, column| self.is_column_indexed(table, column))
+    }
+
+    /// Create a sequential scanner for the specified table
+    pub fn create_scanner(
+        &self,
+        table_name: &str,
+        batch_size: Option<usize>,
+    ) -> Result<SequentialScanner, DatabaseError> {
+        SequentialScanner::new(self, table_name.to_string(), batch_size)
+    }
+
+    /// Create a scanner returning the rows of `table_name` matching `predicate` as it
+    /// reads them, where `scan_table` collects them all first
+    pub fn create_filtered_scanner(
+        &self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+    ) -> Result<Box<dyn Scanner>, DatabaseError> {
+        let scanner = self.create_scanner(table_name, None)?;
+        let Some(predicate) = predicate else {
+            return Ok(Box::new(scanner));
+        };
+        let schema = self
+            .load_table_schema(table_name)?
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        predicate.validate_against_schema(schema)?;
+        self.record_predicate_reads(schema, &predicate);
+        let resolved = self.resolve_subqueries(&predicate, schema)?;
+        Ok(Box::new(
+            FilterScanner::new(scanner, resolved, schema.clone())?
+                .with_value_comparison(self.options.value_comparison)
+                .with_predicate_mode(self.options.predicate_mode),
+        ))
+    }
+
+    /// Scan all rows from a table using the scanner, optionally with predicate filtering
+    pub fn scan_table(&self, table_name: &str, predicate: Option<Predicate>) -> Result<Vec<Row>, DatabaseError> {
+        let mut rows = Vec::new();
+        self.for_each_matching_row(table_name, predicate.as_ref(), |row| rows.push(row))?;
+        Ok(rows)
+    }
+
+    /// Scan the rows matching `predicate` sorted by `order_by`, later keys breaking ties
+    /// in earlier ones. Values of different types are ordered as in `value_total_cmp`:
+    /// NULL lowest, so NULLs come first ascending and last descending, as in SQLite.
+    /// Rows that tie on every key keep their scan order.
+    pub fn scan_table_sorted(
+        &self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        order_by: Vec<(String, SortOrder)>,
+    ) -> Result<Vec<Row>, DatabaseError> {
+        let schema = self.load_table_schema(table_name)?.ok_or_else(|| DatabaseError::TableNotFound {
+            name: table_name.to_string(),
+        })?;
+        let keys = order_by
+            .into_iter()
+            .map(|(column, order)| {
+                schema
+                    .get_column_index(&column)
+                    .map(|index| (index, order))
+                    .ok_or_else(|| DatabaseError::ColumnNotFound {
+                        name: column,
+                        table: table_name.to_string(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut rows = self.scan_table(table_name, predicate)?;
+        rows.sort_by(|a, b| compare_rows(a, b, &keys));
+        Ok(rows)
+    }
+
+    /// Scan the rows matching `predicate`, keeping only the named columns in the order
+    /// given. The predicate may reference columns outside the projection.
+    pub fn scan_table_projected(
+        &self,
+        table_name: &str,
+        columns: &[&str],
+        predicate: Option<Predicate>,
+    ) -> Result<Vec<Row>, DatabaseError> {
+        let schema = self.load_table_schema(table_name)?.ok_or_else(|| DatabaseError::TableNotFound {
+            name: table_name.to_string(),
+        })?;
+        let sources = columns
+            .iter()
+            .map(|column| {
+                schema.column_source(column).ok_or_else(|| DatabaseError::ColumnNotFound {
+                    name: column.to_string(),
+                    table: table_name.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let indices: Option<Vec<usize>> = sources
+            .iter()
+            .map(|source| match source {
+                ColumnSource::Position(index) => Some(*index),
+                ColumnSource::Pseudo(_) => None,
+            })
+            .collect();
+        self.column_usage.record_reads(table_name, sources.iter().filter_map(|source| match source {
+            ColumnSource::Position(index) => Some(*index),
+            ColumnSource::Pseudo(_) => None,
+        }));
+        if predicate.is_none() && let Some(indices) = indices {
+            let mut scanner = self.create_scanner(table_name, None)?;
+            scanner.set_projection(indices);
+            let mut rows = Vec::new();
+            while let Some(row) = scanner.scan()? {
+                rows.push(row);
+            }
+            return Ok(rows);
+        }
+
+        // Filtering needs the predicate's columns too, and pseudo-columns come from the
+        // whole row, so project after evaluating it
+        let mut rows = Vec::new();
+        self.for_each_matching_row(table_name, predicate.as_ref(), |row| {
+            let values = sources
+                .iter()
+                .map(|source| source.value(&row).map_or(Value::Null, Cow::into_owned))
+                .collect();
+            rows.push(Row { row_id: row.row_id, version: row.version, values });
+        })?;
+        Ok(rows)
+    }
+
+    /// Scan the distinct rows matching `predicate`, comparing only the named columns, or
+    /// every column when `columns` is empty. Rows hold the compared columns in the order
+    /// given and come back in order of first occurrence. Values are compared as
+    /// `DistinctExecutor` compares them, so `1` and `1.0` are distinct.
+    pub fn scan_table_distinct(
+        &self,
+        table_name: &str,
+        columns: &[&str],
+        predicate: Option<Predicate>,
+    ) -> Result<Vec<Row>, DatabaseError> {
+        let schema = self.load_table_schema(table_name)?.ok_or_else(|| DatabaseError::TableNotFound {
+            name: table_name.to_string(),
+        })?;
+        let indices = columns
+            .iter()
+            .map(|column| {
+                schema.get_column_index(column).ok_or_else(|| DatabaseError::ColumnNotFound {
+                    name: column.to_string(),
+                    table: table_name.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match indices.is_empty() {
+            true => self.column_usage.record_reads(table_name, 0..schema.columns.len()),
+            false => self.column_usage.record_reads(table_name, indices.iter().copied()),
+        }
+        let scanner = self.create_scanner(table_name, None)?;
+        let rows = match predicate {
+            Some(predicate) => {
+                predicate.validate_against_schema(schema)?;
+                self.record_predicate_reads(schema, &predicate);
+                let resolved = self.resolve_subqueries(&predicate, schema)?;
+                let filtered = FilterScanner::new(scanner, resolved, schema.clone())?
+                    .with_value_comparison(self.options.value_comparison)
+                    .with_predicate_mode(self.options.predicate_mode);
+                collect_distinct(DistinctExecutor::new(filtered), indices)?
+            }
+            None => collect_distinct(DistinctExecutor::new(scanner), indices)?,
+        };
+        Ok(rows)
+    }
+
+    /// Scan with explicit `ScanOptions`, e.g. `ScanOptions::skip_corrupt()` to read what
+    /// is still readable. Returns the matching rows and whatever the scan had to skip.
+    pub fn scan_table_with_options(
+        &self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        options: ScanOptions,
+    ) -> Result<(Vec<Row>, CorruptionLog), DatabaseError> {
+        let mut scanner = self.create_scanner(table_name, None)?.with_scan_options(options);
+        let mut rows = Vec::new();
+        self.scan_matching_rows(&mut scanner, table_name, predicate.as_ref(), |row| {
+            rows.push(row);
+            ControlFlow::Continue(())
+        })?;
+        Ok((rows, scanner.take_corruption_log()))
+    }
+
+    /// Whether the table holds no rows. Only leaf headers are read, stopping at the first
+    /// leaf with a live row, so this is cheap on any table that is not mostly deleted.
+    pub fn is_table_empty(&self, table_name: &str) -> Result<bool, DatabaseError> {
+        self.create_scanner(table_name, None)?.is_empty()
+    }
+
+    /// Count the rows matching `predicate` without collecting them. With no predicate the
+    /// count comes from leaf slot directories alone.
+    pub fn count_rows(&self, table_name: &str, predicate: Option<Predicate>) -> Result<usize, DatabaseError> {
+        if predicate.is_none() {
+            return self.create_scanner(table_name, None)?.count_active_cells();
+        }
+        let mut count = 0;
+        self.for_each_matching_row(table_name, predicate.as_ref(), |_| count += 1)?;
+        Ok(count)
+    }
+
+    /// Compute every aggregate of `spec` over the rows matching `predicate` in a single
+    /// scan, returning the results in spec order
+    pub fn aggregate(
+        &self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        spec: AggregateSpec,
+    ) -> Result<Vec<Value>, DatabaseError> {
+        let schema = self.load_table_schema(table_name)?.ok_or_else(|| DatabaseError::TableNotFound {
+            name: table_name.to_string(),
+        })?;
+        let mut executor = AggregateExecutor::new(schema, spec)?;
+        let mut scanner = self.create_scanner(table_name, None)?;
+        if scanner.is_empty()? {
+            if let Some(predicate) = &predicate {
+                predicate.validate_against_schema(schema)?;
+            }
+            return Ok(executor.finish());
+        }
+        let mut failure = None;
+        self.scan_matching_rows(&mut scanner, table_name, predicate.as_ref(), |row| match executor.update(&row) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(e) => {
+                failure = Some(e);
+                ControlFlow::Break(())
+            }
+        })?;
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(executor.finish()),
+        }
+    }
+
+    /// Stream the rows matching `predicate` through `f`, folding them into an accumulator
+    /// without materializing the table
+    pub fn fold_rows<B, F>(
+        &self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        init: B,
+        mut f: F,
+    ) -> Result<B, DatabaseError>
+    where
+        F: FnMut(B, &Row) -> B,
+    {
+        let mut acc = Some(init);
+        self.for_each_matching_row(table_name, predicate.as_ref(), |row| {
+            acc = acc.take().map(|value| f(value, &row));
+        })?;
+        Ok(acc.expect("accumulator is always restored after each row"))
+    }
+
+    /// Stream the rows matching `predicate` to `writer` as JSON objects keyed by column
+    /// name, e.g. for piping into `jq`, and return how many were written
+    pub fn export_json(
+        &self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        writer: &mut impl Write,
+        layout: JsonLayout,
+    ) -> Result<usize, DatabaseError> {
+        let schema = self.require_table_schema(table_name)?;
+        let mut json = JsonRowWriter::begin_with_layout(writer, schema, layout)?;
+        self.export_rows(table_name, predicate.as_ref(), |row| json.write_row(row))?;
+        json.finish()
+    }
+
+    /// Stream the rows matching `predicate` to `writer` as CSV with a header line of
+    /// column names, and return how many were written
+    pub fn export_csv(
+        &self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        writer: &mut impl Write,
+    ) -> Result<usize, DatabaseError> {
+        let schema = self.require_table_schema(table_name)?;
+        let mut csv = CsvRowWriter::begin(writer, schema)?;
+        self.export_rows(table_name, predicate.as_ref(), |row| csv.write_row(row))?;
+        csv.finish()
+    }
+
+    /// Feed each row matching `predicate` to `write` as the scanner reaches it, stopping
+    /// at the first write error
+    fn export_rows<F>(&self, table_name: &str, predicate: Option<&Predicate>, mut write: F) -> Result<(), DatabaseError>
+    where
+        F: FnMut(&Row) -> Result<(), DatabaseError>,
+    {
+        let mut scanner = self.create_scanner(table_name, None)?;
+        let mut write_error = None;
+        self.scan_matching_rows(&mut scanner, table_name, predicate, |row| match write(&row) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(error) => {
+                write_error = Some(error);
+                ControlFlow::Break(())
+            }
+        })?;
+        write_error.map_or(Ok(()), Err)
+    }
+
+    /// Insert the rows of CSV written by `export_csv` and return how many there were. The
+    /// header line names the columns in any order; columns it leaves out are NULL.
+    pub fn import_csv(&mut self, table_name: &str, reader: &mut impl Read) -> Result<usize, DatabaseError> {
+        let schema = self.require_table_schema(table_name)?.clone();
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        let mut records = import::parse_csv(&input)?.into_iter();
+        let Some(header) = records.next() else {
+            return Ok(0);
+        };
+        let columns = header
+            .iter()
+            .map(|field| Self::import_column(&schema, &field.text))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut imported = 0;
+        for (line, record) in records.enumerate() {
+            if record.len() != columns.len() {
+                return Err(DatabaseError::InvalidData {
+                    details: format!(
+                        "CSV record {} has {} fields but the header has {}",
+                        line + 1,
+                        record.len(),
+                        columns.len()
+                    ),
+                });
+            }
+            let mut values = vec![Value::Null; schema.columns.len()];
+            for ((position, data_type), field) in columns.iter().zip(&record) {
+                values[*position] = import::csv_field_to_value(field, data_type)?;
+            }
+            self.import_row(table_name, &schema, Row::new(values))?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    /// Insert the rows of JSON written by `export_json` in either layout and return how
+    /// many there were. Keys missing from an object are NULL.
+    pub fn import_json(&mut self, table_name: &str, reader: &mut impl Read) -> Result<usize, DatabaseError> {
+        let schema = self.require_table_schema(table_name)?.clone();
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        let parse = |text: &str| {
+            serde_json::from_str::<serde_json::Value>(text).map_err(|e| DatabaseError::SerializationError {
+                details: e.to_string(),
+            })
+        };
+        let objects = if input.trim_start().starts_with('[') {
+            match parse(&input)? {
+                serde_json::Value::Array(objects) => objects,
+                _ => unreachable!("JSON starting with '[' is an array"),
+            }
+        } else {
+            input
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(parse)
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        let mut imported = 0;
+        for object in &objects {
+            let serde_json::Value::Object(fields) = object else {
+                return Err(DatabaseError::InvalidData {
+                    details: format!("Expected a JSON object per row, found {}", object),
+                });
+            };
+            let mut values = vec![Value::Null; schema.columns.len()];
+            for (name, json) in fields {
+                let (position, data_type) = Self::import_column(&schema, name)?;
+                values[position] = import::json_to_value(json, &data_type)?;
+            }
+            self.import_row(table_name, &schema, Row::new(values))?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    /// Stream JSON Lines from `reader` into the table, one object per line, inserting
+    /// rows in batches of `options.batch_size`. Keys name columns in any order; missing
+    /// ones are NULL. If the table does not exist and `options.create_table` is set, its
+    /// schema is inferred from the first `options.sample_size` records, which are then
+    /// imported with the rest. A line that cannot be stored stops the import with
+    /// `ImportLine`, leaving the lines before it imported, unless `options.lenient` is set.
+    pub fn import_jsonl(
+        &mut self,
+        table_name: &str,
+        reader: impl BufRead,
+        options: &JsonlImportOptions,
+    ) -> Result<JsonlImportSummary, DatabaseError> {
+        let mut summary = JsonlImportSummary::default();
+        let mut rejects = match (&options.rejects_path, options.lenient) {
+            (Some(path), true) => Some(BufWriter::new(File::create(path)?)),
+            _ => None,
+        };
+        let mut lines = reader
+            .lines()
+            .enumerate()
+            .map(|(index, text)| text.map(|text| (index + 1, text)))
+            .filter(|line| !matches!(line, Ok((_, text)) if text.trim().is_empty()));
+
+        let mut sample = Vec::new();
+        if !self.table_exists(table_name) {
+            if !options.create_table {
+                return Err(DatabaseError::TableNotFound {
+                    name: table_name.to_string(),
+                });
+            }
+            let mut inference = import::JsonSchemaInference::new();
+            for line in lines.by_ref().take(options.sample_size) {
+                let (number, text) = line?;
+                let record = import::parse_jsonl_record(&text)
+                    .and_then(|record| inference.observe(&record, options.nested).map(|()| record));
+                match record {
+                    // Fail before the table is created
+                    Err(error) if !options.lenient => {
+                        Self::reject_jsonl_line(number, &text, error, options, &mut summary, &mut rejects)?
+                    }
+                    record => sample.push((number, text, record)),
+                }
+            }
+            if inference.records() == 0 {
+                // Nothing to build a table from; any lines read were rejects
+                for (number, text, record) in sample {
+                    if let Err(error) = record {
+                        Self::reject_jsonl_line(number, &text, error, options, &mut summary, &mut rejects)?;
+                    }
+                }
+                return Ok(summary);
+            }
+            let columns = inference.columns();
+            let definitions: Vec<String> = columns
+                .iter()
+                .map(|column| {
+                    let quoted = format!("\"{}\" {}", column.name.replace('"', "\"\""), column.data_type);
+                    match column.nullable {
+                        true => quoted,
+                        false => quoted + " NOT NULL",
+                    }
+                })
+                .collect();
+            let sql = format!("CREATE TABLE {} ({})", table_name, definitions.join(", "));
+            self.create_table_with_constraints(table_name.to_string(), columns, Vec::new(), Vec::new(), sql)?;
+            summary.created_table = true;
+        }
+
+        let schema = self.require_table_schema(table_name)?.clone();
+        let parsed = lines.map(|line| line.map(|(number, text)| {
+            let record = import::parse_jsonl_record(&text);
+            (number, text, record)
+        }));
+        let mut batch = Vec::with_capacity(options.batch_size);
+        for line in sample.into_iter().map(Ok).chain(parsed) {
+            let (number, text, record) = line?;
+            match record.and_then(|record| Self::jsonl_row(&schema, &record, options.nested)) {
+                Ok(row) => batch.push((number, text, row)),
+                Err(error) => {
+                    if !options.lenient {
+                        // The lines before this one are imported whatever happens to it
+                        self.insert_jsonl_batch(table_name, &mut batch, options, &mut summary, &mut rejects)?;
+                    }
+                    Self::reject_jsonl_line(number, &text, error, options, &mut summary, &mut rejects)?
+                }
+            }
+            if batch.len() >= options.batch_size {
+                self.insert_jsonl_batch(table_name, &mut batch, options, &mut summary, &mut rejects)?;
+            }
+        }
+        self.insert_jsonl_batch(table_name, &mut batch, options, &mut summary, &mut rejects)?;
+        if let Some(rejects) = &mut rejects {
+            rejects.flush()?;
+        }
+        Ok(summary)
+    }
+
+    /// Row for a JSON Lines record, checked against the table's schema
+    fn jsonl_row(
+        schema: &TableSchema,
+        record: &serde_json::Map<String, serde_json::Value>,
+        nested: NestedJson,
+    ) -> Result<Row, DatabaseError> {
+        let mut values = vec![Value::Null; schema.columns.len()];
+        for (name, json) in record {
+            let (position, data_type) = Self::import_column(schema, name)?;
+            values[position] = import::jsonl_field_to_value(json, &data_type, nested).map_err(|e| {
+                DatabaseError::InvalidData {
+                    details: format!("Column '{}': {}", name, e),
+                }
+            })?;
+        }
+        let row = Row::new(values);
+        schema.validate_row(&row)?;
+        Ok(row)
+    }
+
+    /// Insert the batched lines' rows. A row the table refuses is rejected as its line
+    /// and the rows after it are inserted again; the row id counter shows how many of
+    /// the batch went in before it.
+    fn insert_jsonl_batch(
+        &mut self,
+        table_name: &str,
+        batch: &mut Vec<(usize, String, Row)>,
+        options: &JsonlImportOptions,
+        summary: &mut JsonlImportSummary,
+        rejects: &mut Option<BufWriter<File>>,
+    ) -> Result<(), DatabaseError> {
+        let mut pending = std::mem::take(batch);
+        while !pending.is_empty() {
+            let first_row_id = self.next_row_id(table_name)?;
+            let rows = pending.iter().map(|(_, _, row)| row.clone()).collect();
+            match self.insert_batch_into_table(table_name, rows) {
+                Ok(()) => {
+                    summary.imported += pending.len();
+                    break;
+                }
+                Err(error) if Self::refuses_row(&error) => {
+                    let inserted = (self.next_row_id(table_name)? - first_row_id) as usize;
+                    if inserted >= pending.len() {
+                        return Err(error);
+                    }
+                    summary.imported += inserted;
+                    let mut rest = pending.split_off(inserted + 1);
+                    let (number, text, _) = pending.pop().expect("the failed row is pending");
+                    Self::reject_jsonl_line(number, &text, error, options, summary, rejects)?;
+                    std::mem::swap(&mut pending, &mut rest);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether an insert failed because of the row itself rather than the database
+    fn refuses_row(error: &DatabaseError) -> bool {
+        matches!(
+            error,
+            DatabaseError::UniqueConstraintViolation { .. }
+                | DatabaseError::ForeignKeyViolation { .. }
+                | DatabaseError::CheckConstraintViolation { .. }
+                | DatabaseError::ColumnTypeMismatch { .. }
+                | DatabaseError::TypeMismatch { .. }
+                | DatabaseError::InvalidData { .. }
+                | DatabaseError::RowTooLarge { .. }
+        )
+    }
+
+    /// Skip a line in lenient mode, or fail the import with its line number otherwise
+    fn reject_jsonl_line(
+        line: usize,
+        text: &str,
+        error: DatabaseError,
+        options: &JsonlImportOptions,
+        summary: &mut JsonlImportSummary,
+        rejects: &mut Option<BufWriter<File>>,
+    ) -> Result<(), DatabaseError> {
+        if !options.lenient {
+            return Err(DatabaseError::ImportLine {
+                line,
+                source: Box::new(error),
+            });
+        }
+        summary.rejected.push(JsonlReject {
+            line,
+            reason: error.to_string(),
+        });
+        if let Some(rejects) = rejects {
+            writeln!(rejects, "{}", text)?;
+        }
+        Ok(())
+    }
+
+    /// Position and type of the column an imported field belongs to
+    fn import_column(schema: &TableSchema, name: &str) -> Result<(usize, DataType), DatabaseError> {
+        schema
+            .writable_column(name)
+            .map(|column| (column.position, column.data_type.clone()))
+    }
+
+    fn import_row(&mut self, table_name: &str, schema: &TableSchema, row: Row) -> Result<(), DatabaseError> {
+        schema.validate_row(&row)?;
+        self.insert_into_table(table_name, row)
+    }
+
+    /// The table's schema, or `TableNotFound`
+    fn require_table_schema(&self, table_name: &str) -> Result<&TableSchema, DatabaseError> {
+        self.load_table_schema(table_name)?
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })
+    }
+
+    /// Raw images of every page in the table's B+ tree, root first
+    pub fn iter_table_pages(&self, table_name: &str) -> Result<TablePages, DatabaseError> {
+        let root_page_id = self
+            .table_roots
+            .get(table_name)
+            .copied()
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        let file = OpenOptions::new().read(true).open(&self.db_info.path)?;
+        let page_ids = self.table_page_ids(table_name, root_page_id)?;
+        Ok(TablePages::new(file, page_ids, self.page_size()))
+    }
+
+    /// Every page of the table rooted at `root_page_id`: its B+ tree's, or an append
+    /// log's chain
+    fn table_page_ids(&self, table_name: &str, root_page_id: PageId) -> Result<Vec<PageId>, DatabaseError> {
+        match self.table_kind(table_name)? {
+            TableKind::BTree => self
+                .readable_btree(self.open_store()?, root_page_id)?
+                .page_ids(Some(BAMBANG_HEADER_SIZE as u64)),
+            TableKind::AppendLog => self.log_page_ids(table_name),
+        }
+    }
+
+    /// Space accounting over every page of the table's B+ tree
+    pub fn table_page_stats(&self, table_name: &str) -> Result<TablePageStats, DatabaseError> {
+        let mut stats = TablePageStats::default();
+        for page in self.iter_table_pages(table_name)? {
+            let (_, bytes) = page?;
+            stats.add(&Page::from_bytes(&bytes)?);
+        }
+        Ok(stats)
+    }
+
+    /// Compact every leaf of the table, dropping the bytes and trailing slots of deleted
+    /// cells, and with `merge_underfull` also merge or refill leaves left underfull.
+    /// Rows are unchanged, but slot positions taken before the vacuum are stale.
+    pub fn vacuum_table(&mut self, table_name: &str, merge_underfull: bool) -> Result<VacuumReport, DatabaseError> {
+        self.require_btree(table_name, "VACUUM")?;
+        let before = self.table_page_stats(table_name)?;
+        let extras = Some(BAMBANG_HEADER_SIZE as u64);
+        let (mut btree, _) = self.open_table_btree(table_name)?;
+        for page_id in btree.page_ids(extras)? {
+            let page = btree.load_page(page_id, extras)?;
+            let reclaimable = page.slot_directory.slots.iter().any(|slot| slot.is_deleted())
+                || page.get_fragmentation_ratio() > 0.0;
+            if page.page_type == PageType::LeafTable && reclaimable {
+                btree.compact_page(page_id, extras)?;
+            }
+        }
+        let new_root_page_id = if merge_underfull {
+            btree.rebalance(extras)?
+        } else {
+            None
+        };
+        btree.file.flush()?;
+        if let Some(new_root_page_id) = new_root_page_id {
+            self.reload_header()?;
+            self.update_table_root(table_name, new_root_page_id)?;
+        }
+        let after = self.table_page_stats(table_name)?;
+        if after != before {
+            self.record_change()?;
+        }
+        Ok(VacuumReport { before, after })
+    }
+
+    /// Rebuild the whole database into a fresh file of tightly packed pages and swap it
+    /// into place, shrinking the file to what its tables, indexes and schema need. The
+    /// copy is built beside the database and renamed over it only once complete, so a
+    /// vacuum that fails or is interrupted leaves the original file as it was.
+    pub fn vacuum(&mut self) -> Result<DatabaseVacuumReport, DatabaseError> {
+        if self.in_transaction() {
+            return Err(DatabaseError::ExecutionError {
+                details: "VACUUM cannot run inside a transaction".to_string(),
+            });
+        }
+        self.reload_header()?;
+        let pages_before = self.db_info.page_count;
+        let bytes_before = self.db_info.file_size;
+        let path = self.db_info.path.clone();
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push("-vacuum");
+        let temp_path = PathBuf::from(temp_path);
+
+        // A copy left by an interrupted vacuum is incomplete and never replaced the database
+        if temp_path.exists() {
+            std::fs::remove_file(&temp_path)?;
+        }
+        let copy = self.write_vacuum_copy(&temp_path).and_then(|mut copy| copy.sync());
+        if let Err(e) = copy {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        std::fs::rename(&temp_path, &path)?;
+
+        self.file = page_store::open_store(&path, self.options.io_log.as_ref(), self.page_size())?;
+        self.row_locators.clear();
+        self.reload_schema_state()?;
+        self.schema_notifier.emit(SchemaEvent {
+            schema_cookie: self.db_info.header.schema_cookie,
+            change: SchemaChange::SchemasReloaded,
+        });
+        Ok(DatabaseVacuumReport {
+            pages_before,
+            pages_after: self.db_info.page_count,
+            bytes_before,
+            bytes_after: self.db_info.file_size,
+        })
+    }
+
+    /// Write a compact copy of the database to `temp_path`: every table's rows reloaded
+    /// into new pages, its indexes rebuilt from them, and `sqlite_schema` pointing at the
+    /// new roots. Rows are copied as stored, without checking constraints again.
+    fn write_vacuum_copy(&self, temp_path: &Path) -> Result<StorageManager, DatabaseError> {
+        let options = StorageManagerOptions {
+            page_size: self.page_size(),
+            open_mode: OpenMode::Eager,
+            io_log: None,
+            max_database_size: None,
+            on_quota_warning: None,
+            persist_column_usage: false,
+            sync_mode: SyncMode::Off,
+            on_log: None,
+            ..self.options.clone()
+        };
+        let mut copy = StorageManager::open_with_options(temp_path, options)?;
+        let extras = Some(BAMBANG_HEADER_SIZE as u64);
+
+        let mut table_names = self.get_table_names();
+        table_names.sort();
+        let mut new_roots = HashMap::new();
+        for table_name in table_names {
+            let key_columns = self
+                .load_table_schema(&table_name)?
+                .map(|schema| schema.key_columns().to_vec())
+                .unwrap_or_default();
+            let kind = self.table_kind(&table_name)?;
+            let indexes = self.table_indexes(&table_name)?;
+            let root_page_id = copy.allocate_new_page(PageType::LeafTable)?;
+            let mut btree = copy
+                .writable_btree(copy.open_store()?, root_page_id)?
+                .with_key_columns(key_columns.clone());
+            let mut log_page = Page::new_with_size(root_page_id, PageType::LeafTable, copy.page_size());
+            let time_column = self.load_table_schema(&table_name)?.and_then(|schema| schema.log_time_column());
+            let mut index_btrees = Vec::with_capacity(indexes.len());
+            for (index, position) in &indexes {
+                let index_root = copy.allocate_new_page(PageType::LeafTable)?;
+                index_btrees.push((index, *position, copy.writable_btree(copy.open_store()?, index_root)?));
+            }
+
+            let mut scanner = self.create_scanner(&table_name, None)?;
+            loop {
+                let rows = scanner.scan_batch(VACUUM_BATCH_ROWS)?;
+                if rows.is_empty() {
+                    break;
+                }
+                for (_, position, index_btree) in &mut index_btrees {
+                    let entries: Vec<Row> = rows
+                        .iter()
+                        .filter_map(|row| TableIndex::entry_for(row, *position, &key_columns))
+                        .collect();
+                    if !entries.is_empty() {
+                        index_btree.bulk_insert(entries, 1.0, extras)?;
+                    }
+                }
+                match kind {
+                    TableKind::BTree => {
+                        btree.bulk_insert(rows, 1.0, extras)?;
+                    }
+                    TableKind::AppendLog => {
+                        for row in rows {
+                            let row_id = row.row_id.ok_or_else(|| DatabaseError::CorruptedDatabase {
+                                reason: format!("A row of append log '{}' has no rowid", table_name),
+                            })?;
+                            let time = time_column.and_then(|column| append_log::row_time(&row, column));
+                            copy.push_log_cell(&table_name, &mut log_page, &row.to_bytes(), row_id, time)?;
+                        }
+                    }
+                }
+            }
+
+            btree.file.flush()?;
+            new_roots.insert(("table", table_name.clone()), btree.root_page_id);
+            for (index, _, mut index_btree) in index_btrees {
+                index_btree.file.flush()?;
+                new_roots.insert(("index", index.name.clone()), index_btree.root_page_id);
+            }
+            if kind == TableKind::AppendLog {
+                copy.write_log_page(&mut log_page)?;
+            }
+            copy.reload_header()?;
+        }
+
+        // Every other entry, from columns to sequences, is copied as it is
+        let mut schema_rows = Vec::new();
+        self.for_each_schema_row(|_, mut row| {
+            let entry = match (row.values.first(), row.values.get(1)) {
+                (Some(Value::Text(entry_type)), Some(Value::Text(name))) => (entry_type.as_str(), name.clone()),
+                _ => ("", String::new()),
+            };
+            if entry == ("table", "sqlite_schema".to_string()) {
+                return Ok(ControlFlow::Continue(()));
+            }
+            let new_root = match entry {
+                ("table", name) => new_roots.get(&("table", name)),
+                ("index", name) => new_roots.get(&("index", name)),
+                _ => None,
+            };
+            if let Some(new_root) = new_root {
+                row.values[3] = Value::Integer(*new_root as i64);
+            }
+            schema_rows.push(row);
+            Ok(ControlFlow::Continue(()))
+        })?;
+        copy.insert_schema_rows(schema_rows)?;
+
+        // The copy keeps the database's settings, with no free pages and a new schema
+        // cookie so other handles reload the moved roots
+        copy.reload_header()?;
+        let mut header = self.db_info.header.clone();
+        header.database_size_pages = copy.db_info.header.database_size_pages;
+        header.freelist_trunk_page = 0;
+        header.freelist_pages_count = 0;
+        header.set_schema_root_page(copy.schema_root_page_id());
+        header.increment_change_counter();
+        header.increment_schema_cookie();
+        copy.db_info.header = header;
+        copy.update_header_in_file()?;
+        Ok(copy)
+    }
+
+    /// Stream the table's pages with a manifest, for `import_table_pages` on a database
+    /// with the same page size and format. Much faster than a logical copy for big tables.
+    pub fn export_table_pages(&self, table_name: &str, writer: &mut impl Write) -> Result<PageImageSummary, DatabaseError> {
+        self.require_btree(table_name, "page image export")?;
+        let schema = self
+            .load_table_schema(table_name)?
+            .cloned()
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        let pages = self.iter_table_pages(table_name)?;
+        let manifest = PageImageManifest {
+            format_version: PAGE_IMAGE_FORMAT_VERSION,
+            page_size: self.page_size(),
+            schema_format: self.db_info.header.schema_format_number,
+            schema,
+            page_ids: pages.page_ids().to_vec(),
+        };
+        page_image::write_manifest(writer, &manifest)?;
+        let mut hasher = crc32fast::Hasher::new();
+        for page in pages {
+            let (_, bytes) = page?;
+            hasher.update(&bytes);
+            writer.write_all(&bytes)?;
+        }
+        let digest = hasher.finalize();
+        writer.write_all(&digest.to_le_bytes())?;
+        writer.flush()?;
+        Ok(PageImageSummary {
+            table_name: table_name.to_string(),
+            page_count: manifest.page_ids.len(),
+            digest,
+        })
+    }
+
+    /// Copy a table exported by `export_table_pages` into a fresh contiguous page range,
+    /// renumbering every page pointer. Nothing is kept if the digest does not match.
+    pub fn import_table_pages(&mut self, reader: &mut impl Read) -> Result<PageImageSummary, DatabaseError> {
+        self.import_table_pages_with_progress(reader, None)
+    }
+
+    /// `import_table_pages`, reporting each page copied to `progress`. If the sink
+    /// cancels the import, the pages copied so far are truncated away and the database
+    /// is left as it was.
+    pub fn import_table_pages_with_progress(
+        &mut self,
+        reader: &mut impl Read,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<PageImageSummary, DatabaseError> {
+        self.reload_header()?;
+        let manifest =
+            page_image::read_manifest(reader, self.page_size(), self.db_info.header.schema_format_number)?;
+        let table_name = manifest.schema.table_name.clone();
+        if self.table_exists(&table_name) {
+            return Err(DatabaseError::ExecutionError {
+                details: format!("Table '{}' already exists", table_name),
+            });
+        }
+        let page_count = manifest.page_ids.len();
+        self.db_info.header.ensure_can_grow(page_count as u32)?;
+        let first_page_id = self.db_info.page_count + 1;
+        let new_ids: HashMap<PageId, PageId> = manifest
+            .page_ids
+            .iter()
+            .enumerate()
+            .map(|(i, old)| (*old, first_page_id + i as PageId))
+            .collect();
+
+        let original_len = self.file.len()?;
+        let copied = self.copy_page_images(reader, &manifest, &new_ids, progress);
+        let digest = match copied {
+            Ok(digest) => digest,
+            Err(e) => {
+                self.file.set_len(original_len)?;
+                return Err(e);
+            }
+        };
+
+        self.db_info.page_count += page_count as u64;
+        self.db_info.file_size = self.file.len()?;
+        self.db_info.header.database_size_pages = self.db_info.page_count as u32;
+        self.update_header_in_file()?;
+        let mut schema = manifest.schema;
+        schema.root_page_id = new_ids[&manifest.page_ids[0]];
+        self.add_table_schema(schema)?;
+        Ok(PageImageSummary {
+            table_name,
+            page_count,
+            digest,
+        })
+    }
+
+    /// Write the incoming pages at their new ids and check the trailing digest
+    fn copy_page_images(
+        &mut self,
+        reader: &mut impl Read,
+        manifest: &PageImageManifest,
+        new_ids: &HashMap<PageId, PageId>,
+        progress: Option<&dyn ProgressSink>,
+    ) -> Result<u32, DatabaseError> {
+        let mut hasher = crc32fast::Hasher::new();
+        let mut buffer = vec![0u8; self.page_size()];
+        let total = manifest.page_ids.len() as u64;
+        for copied in 0..total {
+            report_progress(progress, "Page image import", copied, Some(total), "copying pages")?;
+            reader.read_exact(&mut buffer)?;
+            hasher.update(&buffer);
+            let mut page = Page::from_bytes(&buffer)?;
+            page_image::rewrite_page_ids(&mut page, new_ids)?;
+            self.write_page(page.page_id, &page)?;
+        }
+        report_progress(progress, "Page image import", total, Some(total), "copying pages")?;
+        let digest = hasher.finalize();
+        let mut expected = [0u8; 4];
+        reader.read_exact(&mut expected)?;
+        if u32::from_le_bytes(expected) != digest {
+            return Err(DatabaseError::CorruptedDatabase {
+                reason: format!(
+                    "Page image digest mismatch: expected {:08x}, computed {:08x}",
+                    u32::from_le_bytes(expected),
+                    digest
+                ),
+            });
+        }
+        Ok(digest)
+    }
+
+    /// Return at most `limit` rows matching `predicate`, after skipping the first `offset`
+    /// of them. Scanning stops as soon as the page of results is full.
+    pub fn scan_table_paged(
+        &self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Row>, DatabaseError> {
+        Ok(self.scan_table_paged_with_stats(table_name, predicate, limit, offset)?.0)
+    }
+
+    /// `scan_table_paged`, also returning the scanner's counters, e.g. to see how many
+    /// pages were read to serve the page of results
+    pub fn scan_table_paged_with_stats(
+        &self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<Row>, ScanStats), DatabaseError> {
+        let mut rows = Vec::new();
+        if limit == 0 {
+            return Ok((rows, ScanStats::default()));
+        }
+        let mut to_skip = offset;
+        let mut scanner = self.create_scanner(table_name, None)?;
+        self.scan_matching_rows(&mut scanner, table_name, predicate.as_ref(), |row| {
+            if to_skip > 0 {
+                to_skip -= 1;
+                return ControlFlow::Continue(());
+            }
+            rows.push(row);
+            if rows.len() == limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+        Ok((rows, scanner.stats().clone()))
+    }
+
+    fn for_each_matching_row<F>(
+        &self,
+        table_name: &str,
+        predicate: Option<&Predicate>,
+        mut f: F,
+    ) -> Result<(), DatabaseError>
+    where
+        F: FnMut(Row),
+    {
+        // A predicate pinning `_rowid` fetches those rows instead of scanning
+        if let Some(predicate) = predicate
+            && let Some(schema) = self.load_table_schema(table_name)?
+            && let Some(row_ids) = predicate.target_row_ids(schema)
+            && let Some(rows) = self.rows_by_id(table_name, schema, row_ids, Some(predicate))?
+        {
+            rows.into_iter().for_each(f);
+            return Ok(());
+        }
+        let mut scanner = self.create_scanner(table_name, None)?;
+        self.scan_matching_rows(&mut scanner, table_name, predicate, |row| {
+            f(row);
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Feed the rows of `scanner` matching `predicate` to `f` until it breaks or the
+    /// table ends
+    fn scan_matching_rows<F>(
+        &self,
+        scanner: &mut SequentialScanner,
+        table_name: &str,
+        predicate: Option<&Predicate>,
+        mut f: F,
+    ) -> Result<(), DatabaseError>
+    where
+        F: FnMut(Row) -> ControlFlow<()>,
+    {
+        // Get table schema for predicate validation and evaluation if predicate is provided
+        let table_schema = if predicate.is_some() {
+            Some(self.load_table_schema(table_name)?
+                .ok_or_else(|| DatabaseError::TableNotFound {
+                    name: table_name.to_string(),
+                })?)
+        } else {
+            None
+        };
+
+        // Validate predicate against schema if provided, then materialize any subqueries
+        let resolved = match (predicate, table_schema) {
+            (Some(pred), Some(schema)) => {
+                pred.validate_against_schema(schema)?;
+                self.record_predicate_reads(schema, pred);
+                Some(self.resolve_subqueries(pred, schema)?)
+            }
+            _ => None,
+        };
+        let predicate = resolved.as_ref();
+        let rows_before = scanner.stats().rows_scanned;
+        let pages_before = scanner.stats().pages_read;
+        let mut rows_returned = 0;
+
+        while let Some(row) = scanner.scan()? {
+            // Apply predicate filtering if provided
+            let matches = if let (Some(pred), Some(schema)) = (predicate, table_schema) {
+                pred.evaluate_in(&row, schema, self.options.value_comparison, self.options.predicate_mode)?
+            } else {
+                true // No predicate means all rows match
+            };
+
+            if matches {
+                rows_returned += 1;
+                if f(row).is_break() {
+                    break;
+                }
+            }
+        }
+
+        if let Some(pred) = predicate {
+            self.workload.record(ScanRecord {
+                table: table_name.to_string(),
+                columns: pred.get_referenced_columns(),
+                rows_examined: scanner.stats().rows_scanned - rows_before,
+                rows_returned,
+                pages_read: scanner.stats().pages_read - pages_before,
+                at: Instant::now(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Rows of `table_name` stamped with one of `row_ids` and matching `predicate`, in key
+    /// order, each fetched by a point lookup of the key the table's row locator records
+    /// for it. `None` if a row is no longer under that key, leaving the caller to scan.
+    fn rows_by_id(
+        &self,
+        table_name: &str,
+        schema: &TableSchema,
+        mut row_ids: Vec<RowId>,
+        predicate: Option<&Predicate>,
+    ) -> Result<Option<Vec<Row>>, DatabaseError> {
+        // An append log has no keys to look rows up by
+        if schema.kind == TableKind::AppendLog {
+            return Ok(None);
+        }
+        let resolved = match predicate {
+            Some(predicate) => {
+                predicate.validate_against_schema(schema)?;
+                self.record_predicate_reads(schema, predicate);
+                Some(self.resolve_subqueries(predicate, schema)?)
+            }
+            None => None,
+        };
+        let pages_before = self.io_counters.snapshot().pages_read;
+        let (locator, locator_pages_read) = self.row_locator(table_name, schema)?;
+        row_ids.sort_unstable();
+        row_ids.dedup();
+        let mut targets: Vec<(&Value, RowId)> = row_ids
+            .iter()
+            .filter_map(|row_id| locator.key(*row_id).map(|key| (key, *row_id)))
+            .collect();
+        targets.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        let extras = Some(BAMBANG_HEADER_SIZE as u64);
+        let (mut btree, _) = self.open_table_btree(table_name)?;
+        let mut rows = Vec::with_capacity(targets.len());
+        for (key, row_id) in targets {
+            let found = btree.search_all(key, extras)?.into_iter().find(|row| row.row_id == Some(row_id));
+            let Some(row) = found else {
+                self.row_locators.remove(table_name);
+                return Ok(None);
+            };
+            let matches = match &resolved {
+                Some(predicate) => {
+                    predicate.evaluate_in(&row, schema, self.options.value_comparison, self.options.predicate_mode)?
+                }
+                None => true,
+            };
+            if matches {
+                rows.push(row);
+            }
+        }
+
+        if let Some(predicate) = predicate {
+            self.workload.record(ScanRecord {
+                table: table_name.to_string(),
+                columns: predicate.get_referenced_columns(),
+                rows_examined: row_ids.len() as u64,
+                rows_returned: rows.len() as u64,
+                pages_read: locator_pages_read + self.io_counters.snapshot().pages_read - pages_before,
+                at: Instant::now(),
+            });
+        }
+        Ok(Some(rows))
+    }
+
+    /// The row locator of `table_name` and the pages read to build it, none when the one
+    /// built earlier is still current. Building it scans the table's key columns.
+    fn row_locator(&self, table_name: &str, schema: &TableSchema) -> Result<(Arc<RowLocator>, u64), DatabaseError> {
+        let change_counter = self.db_info.header.file_change_counter;
+        if let Some(locator) = self.row_locators.get(table_name, change_counter) {
+            return Ok((locator, 0));
+        }
+        let composite = !schema.key_columns().is_empty();
+        let mut scanner = self.create_scanner(table_name, None)?;
+        scanner.set_projection(if composite { schema.key_columns().to_vec() } else { vec![0] });
+        let mut keys = HashMap::new();
+        while let Some(row) = scanner.scan()? {
+            let Some(row_id) = row.row_id else {
+                continue;
+            };
+            let key = if composite {
+                Value::composite_key(row.values.iter())
+            } else {
+                row.values.into_iter().next().unwrap_or(Value::Null)
+            };
+            keys.insert(row_id, key);
+        }
+        let locator = Arc::new(RowLocator::new(change_counter, keys));
+        self.row_locators.insert(table_name, Arc::clone(&locator));
+        Ok((locator, scanner.stats().pages_read))
+    }
+
+    /// Replace `InTable` predicates with the materialized values of the other table's column
+    pub(crate) fn resolve_subqueries(
+        &self,
+        predicate: &Predicate,
+        schema: &TableSchema,
+    ) -> Result<Predicate, DatabaseError> {
+        match predicate {
+            Predicate::InTable { column_name, other_table, other_column, negated } => {
+                let data_type = match schema.column_source(column_name) {
+                    Some(ColumnSource::Position(index)) => schema.columns[index].data_type.clone(),
+                    Some(ColumnSource::Pseudo(pseudo)) => pseudo.data_type(),
+                    None => {
+                        return Err(DatabaseError::ColumnNotFound {
+                            name: column_name.clone(),
+                            table: schema.table_name.clone(),
+                        });
+                    }
+                };
+                let other_schema = self.load_table_schema(other_table)?.ok_or_else(|| {
+                    DatabaseError::TableNotFound { name: other_table.clone() }
+                })?;
+                let other = other_schema.get_column(other_column).ok_or_else(|| {
+                    DatabaseError::ColumnNotFound {
+                        name: other_column.clone(),
+                        table: other_table.clone(),
+                    }
+                })?;
+                if !data_type.is_comparable_with(&other.data_type) {
+                    return Err(DatabaseError::TypeMismatch {
+                        expected: data_type.to_string(),
+                        actual: other.data_type.to_string(),
+                    });
+                }
+
+                let mut builder = ValueSetBuilder::new(self.options.subquery_memory_budget);
+                let mut scanner = self.create_scanner(other_table, None)?;
+                while let Some(mut row) = scanner.scan()? {
+                    let value = if other.position < row.values.len() {
+                        row.values.swap_remove(other.position)
+                    } else {
+                        Value::Null
+                    };
+                    builder.push(value)?;
+                }
+
+                Ok(Predicate::InValueSet {
+                    column_name: column_name.clone(),
+                    values: Arc::new(builder.finish()?),
+                    negated: *negated,
+                })
+            }
+            Predicate::Logical { op, left, right } => Ok(Predicate::Logical {
+                op: op.clone(),
+                left: Box::new(self.resolve_subqueries(left, schema)?),
+                right: match right {
+                    Some(right) => Some(Box::new(self.resolve_subqueries(right, schema)?)),
+                    None => None,
+                },
+            }),
+            other => Ok(other.clone()),
+        }
+    }
+
+    /// Create a table inserter for the specified table
+    pub fn create_inserter(&self, table_name: &str) -> Result<TableInserter, DatabaseError> {
+        TableInserter::new(self, table_name.to_string())
+    }
+
+    /// Insert multiple rows into a table using batch insertion. Rows before a failing one
+    /// stay inserted; run the batch in a transaction to undo them with `rollback`.
+    pub fn insert_batch_into_table(&mut self, table_name: &str, mut rows: Vec<Row>) -> Result<(), DatabaseError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        // Each row is made durable before the next is written, which a bulk load that
+        // writes whole pages at a time cannot do. Rows of a table referencing itself may
+        // reference rows ahead of them in the batch, so those go in one at a time too.
+        let references_itself = self
+            .load_table_schema(table_name)?
+            .is_some_and(|schema| schema.foreign_keys.iter().any(|foreign_key| foreign_key.ref_table == table_name));
+        if self.options.sync_mode == SyncMode::Always || references_itself {
+            return rows.into_iter().try_for_each(|row| self.insert_into_table(table_name, row));
+        }
+        if self.table_kind(table_name)? == TableKind::AppendLog {
+            return self.append_to_log(table_name, rows);
+        }
+
+        // A dangling reference stops the batch where inserting row by row would
+        let mut dangling = None;
+        for (index, row) in rows.iter().enumerate() {
+            match self.check_references(table_name, row, None) {
+                Ok(()) => {}
+                Err(e @ DatabaseError::ForeignKeyViolation { .. }) => {
+                    dangling = Some((index, e));
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if let Some((index, _)) = &dangling {
+            rows.truncate(*index);
+        }
+
+        // Create a TableInserter and delegate the batch insertion
+        self.load_row_id_counter(table_name)?;
+        let mut inserter = TableInserter::new(self, table_name.to_string())?;
+        let written = column_usage::write_counts(&rows);
+        let result = inserter.insert_batch(rows);
+        self.finish_insert(table_name, &inserter)?;
+        if result.is_ok() {
+            self.column_usage.record_writes(table_name, &written);
+        }
+        result.and(dangling.map_or(Ok(()), |(_, e)| Err(e)))
+    }
+
+    /// How the table's rows are laid out
+    pub fn table_kind(&self, table_name: &str) -> Result<TableKind, DatabaseError> {
+        Ok(self
+            .load_table_schema(table_name)?
+            .map(|schema| schema.kind)
+            .unwrap_or_default())
+    }
+
+    /// Fail with `AppendLogUnsupported` unless the table is a B+ tree
+    fn require_btree(&self, table_name: &str, operation: &str) -> Result<(), DatabaseError> {
+        match self.table_kind(table_name)? {
+            TableKind::BTree => Ok(()),
+            TableKind::AppendLog => Err(Self::append_log_unsupported(table_name, operation)),
+        }
+    }
+
+    fn append_log_unsupported(table_name: &str, operation: &str) -> DatabaseError {
+        DatabaseError::AppendLogUnsupported {
+            table: table_name.to_string(),
+            operation: operation.to_string(),
+        }
+    }
+
+    /// Append `rows` to an append log, filling its last page and chaining new pages after
+    /// it, so each page is written once however many rows it takes. Rows are stamped with
+    /// increasing rowids and, when the table has a TIMESTAMP column, must not go back in
+    /// time. A row that fails stops the batch, keeping the rows before it.
+    fn append_to_log(&mut self, table_name: &str, rows: Vec<Row>) -> Result<(), DatabaseError> {
+        let schema = self.require_table_schema(table_name)?.clone();
+        let time_column = schema.log_time_column();
+        let max_cell_size = overflow_page_capacity(self.page_size());
+        self.load_row_id_counter(table_name)?;
+        let mut next_row_id = self.next_row_id(table_name)?;
+        let tail_page_id = self.log_tail(table_name)?;
+        let mut page = self.read_page(tail_page_id)?;
+        let mut last_time = match time_column {
+            Some(column) => Self::last_log_row(&page)?.and_then(|row| append_log::row_time(&row, column)),
+            None => None,
+        };
+        let written = column_usage::write_counts(&rows);
+
+        let result = rows.into_iter().try_for_each(|mut row| {
+            self.check_references(table_name, &row, None)?;
+            if schema.has_checks() {
+                schema.check_row(&row)?;
+            }
+            let time = match time_column {
+                Some(column) => {
+                    let time = append_log::row_time(&row, column).ok_or_else(|| DatabaseError::InvalidData {
+                        details: format!(
+                            "Column '{}' of append log '{}' needs a timestamp in every row",
+                            schema.columns[column].name, table_name
+                        ),
+                    })?;
+                    if let Some(last_time) = last_time.filter(|last_time| time < *last_time) {
+                        return Err(DatabaseError::InvalidData {
+                            details: format!(
+                                "Row at {} is older than the last row of append log '{}' at {}",
+                                time, table_name, last_time
+                            ),
+                        });
+                    }
+                    Some(time)
+                }
+                None => None,
+            };
+            let row_id = *row.row_id.get_or_insert(next_row_id);
+            if row_id < next_row_id {
+                return Err(DatabaseError::InvalidData {
+                    details: format!("Rowid {} of append log '{}' must be at least {}", row_id, table_name, next_row_id),
+                });
+            }
+            let cell = row.to_bytes();
+            if cell.len() > max_cell_size {
+                return Err(DatabaseError::RowTooLarge {
+                    size: cell.len(),
+                    max: max_cell_size,
+                });
+            }
+            self.push_log_cell(table_name, &mut page, &cell, row_id, time)?;
+            next_row_id = row_id + 1;
+            last_time = time.or(last_time);
+            Ok(())
+        });
+
+        self.write_log_page(&mut page)?;
+        self.finish_row_writes(table_name, next_row_id)?;
+        if result.is_ok() {
+            self.column_usage.record_writes(table_name, &written);
+        }
+        result
+    }
+
+    /// Add `cell` to `page`, the tail of an append log. When it does not fit, the page is
+    /// written out and `page` becomes a new tail linked after it.
+    fn push_log_cell(
+        &mut self,
+        table_name: &str,
+        page: &mut Page,
+        cell: &[u8],
+        row_id: RowId,
+        time: Option<i64>,
+    ) -> Result<(), DatabaseError> {
+        if !page.can_fit(cell.len()) {
+            let next_page_id = self.allocate_new_page(PageType::LeafTable)?;
+            page.next_leaf_page_id = Some(next_page_id);
+            self.write_log_page(page)?;
+            let prev_page_id = page.page_id;
+            *page = Page::new_with_size(next_page_id, PageType::LeafTable, self.page_size());
+            page.prev_leaf_page_id = Some(prev_page_id);
+            self.log_tails.insert(table_name.to_string(), next_page_id);
+        }
+        if page.cell_count == 0 {
+            page.sorted = false;
+            append_log::set_first_timestamp(page, time);
+        }
+        page.insert_cell(cell, Some(row_id))?;
+        Ok(())
+    }
+
+    /// The last live row on a log page
+    fn last_log_row(page: &Page) -> Result<Option<Row>, DatabaseError> {
+        (0..page.slot_directory.slots.len())
+            .rev()
+            .find_map(|slot_index| page.get_cell(slot_index))
+            .map(Row::from_bytes)
+            .transpose()
+    }
+
+    fn write_log_page(&mut self, page: &mut Page) -> Result<(), DatabaseError> {
+        // Header fields such as the next page link change after the last cell insert
+        page.update_checksum();
+        self.write_page(page.page_id, page)
+    }
+
+    /// Last page of an append log's chain, which appends go to
+    fn log_tail(&mut self, table_name: &str) -> Result<PageId, DatabaseError> {
+        if let Some(tail) = self.log_tails.get(table_name) {
+            return Ok(*tail);
+        }
+        let page_ids = self.log_page_ids(table_name)?;
+        let tail = *page_ids.last().expect("a log chain starts at its root page");
+        self.log_tails.insert(table_name.to_string(), tail);
+        Ok(tail)
+    }
+
+    /// Pages of an append log from head to tail, following the links in their headers
+    fn log_page_ids(&self, table_name: &str) -> Result<Vec<PageId>, DatabaseError> {
+        let mut page_ids = Vec::new();
+        let mut next_page_id = Some(self.table_root(table_name)?);
+        while let Some(page_id) = next_page_id {
+            self.check_log_chain_length(table_name, page_ids.len())?;
+            page_ids.push(page_id);
+            next_page_id = self.read_page_metadata(page_id)?.next_leaf_page_id;
+        }
+        Ok(page_ids)
+    }
+
+    fn check_log_chain_length(&self, table_name: &str, pages_visited: usize) -> Result<(), DatabaseError> {
+        if pages_visited as u64 >= self.db_info.header.database_size_pages as u64 {
+            return Err(DatabaseError::CorruptedDatabase {
+                reason: format!("Page chain of append log '{}' loops back on itself", table_name),
+            });
+        }
+        Ok(())
+    }
+
+    fn table_root(&self, table_name: &str) -> Result<PageId, DatabaseError> {
+        self.table_roots
+            .get(table_name)
+            .copied()
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })
+    }
+
+    /// A page's header and slot directory without its cells
+    fn read_page_metadata(&self, page_id: PageId) -> Result<Page, DatabaseError> {
+        let offset = self.page_offset(page_id);
+        let mut header = vec![0u8; PAGE_HEADER_SIZE];
+        self.file.read_exact_at(&mut header, offset)?;
+        let metadata_size = Page::calculate_metadata_size(&header)?.min(self.page_size());
+        let mut metadata = vec![0u8; metadata_size];
+        self.file.read_exact_at(&mut metadata, offset)?;
+        Page::from_header_bytes_with_size(&metadata, self.page_size())
+    }
+
+    /// The axis `position` measures an append log on: rowids, or the table's TIMESTAMP
+    /// column
+    fn log_axis(&self, table_name: &str, position: LogPosition) -> Result<LogAxis, DatabaseError> {
+        let schema = self.require_table_schema(table_name)?;
+        if schema.kind != TableKind::AppendLog {
+            return Err(DatabaseError::ExecutionError {
+                details: format!("Table '{}' is not an append log", table_name),
+            });
+        }
+        match position {
+            LogPosition::RowId(_) => Ok(LogAxis::RowId),
+            LogPosition::Timestamp(_) => schema.log_time_column().map(LogAxis::Time).ok_or_else(|| {
+                DatabaseError::InvalidData {
+                    details: format!("Append log '{}' has no TIMESTAMP column to measure time by", table_name),
+                }
+            }),
+        }
+    }
+
+    /// Rows of an append log from `from` up to but not including `to`, or to the end when
+    /// `to` is `None`, both given in rowids or both in times. Pages are pruned by their
+    /// first row's position as recorded in their headers, so only the pages that can hold
+    /// rows in the range are read in full.
+    pub fn read_log_range(
+        &self,
+        table_name: &str,
+        from: LogPosition,
+        to: Option<LogPosition>,
+    ) -> Result<Vec<Row>, DatabaseError> {
+        let axis = self.log_axis(table_name, from)?;
+        let start = axis.coordinate(from)?;
+        let end = to.map(|to| axis.coordinate(to)).transpose()?;
+        let mut rows = Vec::new();
+        let mut pages_visited = 0;
+        let mut current = Some(self.read_page_metadata(self.table_root(table_name)?)?);
+        while let Some(page) = current {
+            self.check_log_chain_length(table_name, pages_visited)?;
+            pages_visited += 1;
+            if let (Some(end), Some(page_start)) = (end, axis.page_start(&page))
+                && page_start >= end
+            {
+                break;
+            }
+            let following = page
+                .next_leaf_page_id
+                .map(|page_id| self.read_page_metadata(page_id))
+                .transpose()?;
+            let behind = following
+                .as_ref()
+                .and_then(|next| axis.page_start(next))
+                .is_some_and(|next_start| axis.page_precedes(next_start, start));
+            if !behind && page.active_cell_count() > 0 {
+                let full_page = self.read_page(page.page_id)?;
+                for slot_index in 0..full_page.slot_directory.slots.len() {
+                    let Some(cell) = full_page.get_cell(slot_index) else {
+                        continue;
+                    };
+                    let row = Row::from_bytes(cell)?;
+                    if axis
+                        .row_coordinate(&row)
+                        .is_some_and(|position| position >= start && end.is_none_or(|end| position < end))
+                    {
+                        rows.push(row);
+                    }
+                }
+            }
+            current = following;
+        }
+        Ok(rows)
+    }
+
+    /// Free the pages at the head of an append log whose rows all come before `cutoff`,
+    /// given in rowids or in times. Retention works in whole pages: rows before the cutoff
+    /// that share a page with later rows, or sit on the last page, stay until a later trim.
+    pub fn drop_before(&mut self, table_name: &str, cutoff: LogPosition) -> Result<LogTrim, DatabaseError> {
+        let axis = self.log_axis(table_name, cutoff)?;
+        let cutoff = axis.coordinate(cutoff)?;
+        let mut trim = LogTrim::default();
+        let mut freed = Vec::new();
+        let mut page = self.read_page_metadata(self.table_root(table_name)?)?;
+        while let Some(next_page_id) = page.next_leaf_page_id {
+            self.check_log_chain_length(table_name, freed.len())?;
+            let next = self.read_page_metadata(next_page_id)?;
+            if !axis.page_start(&next).is_some_and(|next_start| axis.page_precedes(next_start, cutoff)) {
+                break;
+            }
+            trim.rows_removed += page.active_cell_count();
+            freed.push(page.page_id);
+            page = next;
+        }
+        if freed.is_empty() {
+            return Ok(trim);
+        }
+
+        let mut head = self.read_page(page.page_id)?;
+        head.prev_leaf_page_id = None;
+        self.write_log_page(&mut head)?;
+        self.update_table_root(table_name, head.page_id)?;
+        for page_id in &freed {
+            self.free_page(*page_id)?;
+        }
+        trim.pages_freed = freed.len();
+        self.record_change()?;
+        Ok(trim)
+    }
+
+    /// Verify an append log's chain: table leaves each linking back to the one before,
+    /// rowids increasing and times never going back along it, and every page's header
+    /// recording the time of its first row
+    pub fn check_append_log(&self, table_name: &str) -> Result<(), DatabaseError> {
+        let schema = self.require_table_schema(table_name)?;
+        let time_column = schema.log_time_column();
+        let corrupted = |page_id: PageId, reason: String| DatabaseError::CorruptedPage { page_id, reason };
+        let mut prev_page_id = None;
+        let mut last: Option<(RowId, Option<i64>)> = None;
+        for page_id in self.log_page_ids(table_name)? {
+            let page = self.read_page(page_id)?;
+            if page.page_type != PageType::LeafTable {
+                return Err(corrupted(page_id, format!("Append log page is a {:?} page", page.page_type)));
+            }
+            if page.prev_leaf_page_id != prev_page_id {
+                return Err(corrupted(
+                    page_id,
+                    format!("Links back to {:?} instead of {:?}", page.prev_leaf_page_id, prev_page_id),
+                ));
+            }
+            let mut first = true;
+            for slot_index in 0..page.slot_directory.slots.len() {
+                let Some(cell) = page.get_cell(slot_index) else {
+                    continue;
+                };
+                let row = Row::from_bytes(cell)?;
+                let row_id = row.row_id.ok_or_else(|| corrupted(page_id, format!("Slot {} has no rowid", slot_index)))?;
+                let time = time_column.and_then(|column| append_log::row_time(&row, column));
+                if time_column.is_some() && time.is_none() {
+                    return Err(corrupted(page_id, format!("Row {} has no timestamp", row_id)));
+                }
+                if first && time != append_log::first_timestamp(&page) {
+                    return Err(corrupted(
+                        page_id,
+                        format!("Header records {:?} as the first time, row {} is at {:?}", append_log::first_timestamp(&page), row_id, time),
+                    ));
+                }
+                if let Some((last_row_id, last_time)) = last
+                    && (row_id <= last_row_id || time < last_time)
+                {
+                    return Err(corrupted(
+                        page_id,
+                        format!("Row {} at {:?} follows row {} at {:?}", row_id, time, last_row_id, last_time),
+                    ));
+                }
+                first = false;
+                last = Some((row_id, time));
+            }
+            prev_page_id = Some(page_id);
+        }
+        Ok(())
+    }
+
+    /// Apply `assignments` to every row matching `predicate` and return the number of rows
+    /// modified. This is a blind write: it does not check the rows' versions, but still
+    /// bumps them.
+    pub fn update_table(
+        &mut self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        assignments: &[(String, Value)],
+    ) -> Result<usize, DatabaseError> {
+        self.update_table_versioned(table_name, predicate, assignments, None)
+    }
+
+    /// Like `update_table`, but when `expected_version` is given every matching row must
+    /// still be at that version. Otherwise the update fails with `VersionConflict` and no
+    /// row is modified, so a read-modify-write cannot silently overwrite a concurrent
+    /// update. Pass `None` for a blind write.
+    pub fn update_table_versioned(
+        &mut self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+        assignments: &[(String, Value)],
+        expected_version: Option<u64>,
+    ) -> Result<usize, DatabaseError> {
+        self.require_btree(table_name, "UPDATE")?;
+        let schema = self
+            .load_table_schema(table_name)?
+            .cloned()
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        let assignments = Self::resolve_assignments(&schema, assignments)?;
+        let selection = RowSelection {
+            row_id: None,
+            expected_version,
+        };
+        let updated = self.rewrite_rows(table_name, &schema, predicate.as_ref(), selection, |row| {
+            Self::apply_assignments(row, &assignments)
+        })?;
+        self.record_assignments(table_name, &assignments, updated as u64);
+        Ok(updated)
+    }
+
+    fn record_assignments(&self, table_name: &str, assignments: &[(usize, Value)], rows: u64) {
+        for (position, value) in assignments {
+            self.column_usage.record_assignment(table_name, *position, value, rows);
+        }
+    }
+
+    /// Apply `assignments` to the row stamped with `row_id`, checking its version as
+    /// `update_table_versioned` does. Returns the row's new version, or `None` if no row
+    /// has that id.
+    pub fn update_by_rowid(
+        &mut self,
+        table_name: &str,
+        row_id: RowId,
+        assignments: &[(String, Value)],
+        expected_version: Option<u64>,
+    ) -> Result<Option<u64>, DatabaseError> {
+        self.require_btree(table_name, "UPDATE")?;
+        let schema = self
+            .load_table_schema(table_name)?
+            .cloned()
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        let assignments = Self::resolve_assignments(&schema, assignments)?;
+        let mut new_version = None;
+        let selection = RowSelection {
+            row_id: Some(row_id),
+            expected_version,
+        };
+        self.rewrite_rows(table_name, &schema, None, selection, |row| {
+            let new_row = Self::apply_assignments(row, &assignments);
+            new_version = Some(new_row.version);
+            new_row
+        })?;
+        if new_version.is_some() {
+            self.record_assignments(table_name, &assignments, 1);
+        }
+        Ok(new_version)
+    }
+
+    /// Copy of `row` with `assignments` applied and its version bumped
+    fn apply_assignments(row: &Row, assignments: &[(usize, Value)]) -> Row {
+        let mut new_row = row.clone();
+        for (position, value) in assignments {
+            if new_row.values.len() <= *position {
+                new_row.values.resize(position + 1, Value::Null);
+            }
+            new_row.values[*position] = value.clone();
+        }
+        new_row.version = row.version.wrapping_add(1);
+        new_row
+    }
+
+    /// Replace every row matching `predicate` with the result of `rewrite`, returning the
+    /// number of rows rewritten. Rows are updated in place when possible; a row whose key
+    /// changes or that no longer fits its page is deleted and reinserted through the B+ tree.
+    /// `selection` narrows the matches further and may require them to be at a version.
+    /// The new rows are checked against PRIMARY KEY and UNIQUE constraints, as inserts
+    /// are, before anything is written.
+    fn rewrite_rows<F>(
+        &mut self,
+        table_name: &str,
+        schema: &TableSchema,
+        predicate: Option<&Predicate>,
+        selection: RowSelection,
+        mut rewrite: F,
+    ) -> Result<usize, DatabaseError>
+    where
+        F: FnMut(&Row) -> Row,
+    {
+        let extras = Some(BAMBANG_HEADER_SIZE as u64);
+        let (mut btree, root_page_id) = self.open_table_btree(table_name)?;
+        // Collect every change before writing so reinserted rows are never visited twice
+        let mut matches = self.collect_matching_cells(&mut btree, schema, predicate)?;
+        if let Some(row_id) = selection.row_id {
+            matches.retain(|(_, _, row)| row.row_id == Some(row_id));
+        }
+        if let Some(expected) = selection.expected_version
+            && let Some((_, _, row)) = matches.iter().find(|(_, _, row)| row.version != expected)
+        {
+            return Err(DatabaseError::VersionConflict {
+                row_id: row.row_id.unwrap_or_default(),
+                expected,
+                actual: row.version,
+            });
+        }
+        let track_indexes = self.has_indexes(table_name);
+        let referencing = self.referencing_foreign_keys(table_name);
+        let mut index_changes = Vec::new();
+        let mut referenced_changes = Vec::new();
+        let mut new_rows = Vec::with_capacity(matches.len());
+        let mut pending = Vec::with_capacity(matches.len());
+        for (page_id, slot_index, row) in &matches {
+            let new_row = rewrite(row);
+            schema.check_row(&new_row)?;
+            self.check_references(table_name, &new_row, Some(row))?;
+            let cell_data = new_row.to_bytes();
+            let max_cell_size = overflow_page_capacity(self.page_size());
+            if cell_data.len() > max_cell_size {
+                return Err(DatabaseError::RowTooLarge {
+                    size: cell_data.len(),
+                    max: max_cell_size,
+                });
+            }
+            // Rows that need an overflow page are reinserted rather than rewritten in place
+            let reinsert = new_row.key(schema.key_columns()) != row.key(schema.key_columns())
+                || cell_data.len() >= overflow_threshold(self.page_size());
+            if !referencing.is_empty() {
+                referenced_changes.push((row.clone(), new_row.clone()));
+            }
+            if track_indexes {
+                index_changes.push((Some(row.clone()), Some(new_row.clone())));
+            }
+            new_rows.push(new_row);
+            pending.push((*page_id, *slot_index, reinsert, cell_data));
+        }
+        if !referenced_changes.is_empty() {
+            let changes: Vec<(&Row, Option<&Row>)> =
+                referenced_changes.iter().map(|(row, new_row)| (row, Some(new_row))).collect();
+            self.check_not_referenced(table_name, schema, &referencing, &changes)?;
+        }
+        self.load_row_id_counter(table_name)?;
+        TableInserter::new(self, table_name.to_string())?.check_rewrites(&mut btree, &matches, &new_rows)?;
+
+        let rewritten = pending.len();
+        if rewritten == 0 {
+            return Ok(0);
+        }
+        // Rows are deleted before their replacements go in, so a failure in between must
+        // not leave them lost
+        self.with_rollback(|storage| {
+            let (mut btree, _) = storage.open_table_btree(table_name)?;
+            let mut reinserts = Vec::new();
+            for ((page_id, slot_index, reinsert, cell_data), new_row) in pending.into_iter().zip(new_rows) {
+                if !reinsert && btree.update_cell(page_id, slot_index, &cell_data, extras)? {
+                    continue;
+                }
+                btree.delete_cell(page_id, slot_index, extras)?;
+                reinserts.push(new_row);
+            }
+            for row in reinserts {
+                btree.insert(row, extras)?;
+            }
+            storage.reload_header()?;
+
+            if btree.root_page_id != root_page_id {
+                storage.update_table_root(table_name, btree.root_page_id)?;
+            }
+            storage.update_indexes(table_name, &index_changes)?;
+            storage.record_change()
+        })?;
+        Ok(rewritten)
+    }
+
+    /// Delete every row matching `predicate` and return the number of rows removed. Without a
+    /// predicate the table is emptied but keeps its schema. Pages that lost cells are
+    /// compacted, and leaves left underfull are merged into or refilled from a sibling, with
+    /// pages no longer needed going to the freelist.
+    pub fn delete_from_table(
+        &mut self,
+        table_name: &str,
+        predicate: Option<Predicate>,
+    ) -> Result<usize, DatabaseError> {
+        self.require_btree(table_name, "DELETE; trim it with drop_before")?;
+        let schema = self
+            .load_table_schema(table_name)?
+            .cloned()
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        let extras = Some(BAMBANG_HEADER_SIZE as u64);
+        let (btree, _) = self.open_table_btree(table_name)?;
+        // Every page losing a cell is compacted once its deletes are done
+        let mut btree = btree.with_compaction_threshold(None);
+        let matches = self.collect_matching_cells(&mut btree, &schema, predicate.as_ref())?;
+        // Rows still referenced by a foreign key are not deleted (RESTRICT)
+        let referencing = self.referencing_foreign_keys(table_name);
+        if !referencing.is_empty() {
+            let changes: Vec<(&Row, Option<&Row>)> = matches.iter().map(|(_, _, row)| (row, None)).collect();
+            self.check_not_referenced(table_name, &schema, &referencing, &changes)?;
+        }
+
+        let mut touched_pages = Vec::new();
+        for (page_id, slot_index, _) in &matches {
+            btree.delete_cell(*page_id, *slot_index, extras)?;
+            if touched_pages.last() != Some(page_id) {
+                touched_pages.push(*page_id);
+            }
+        }
+        for page_id in touched_pages {
+            btree.compact_page(page_id, extras)?;
+        }
+        let new_root_page_id = if !matches.is_empty() {
+            btree.rebalance(extras)?
+        } else {
+            None
+        };
+        btree.file.flush()?;
+        if let Some(new_root_page_id) = new_root_page_id {
+            self.reload_header()?;
+            self.update_table_root(table_name, new_root_page_id)?;
+        }
+        let deleted = matches.len();
+        if self.has_indexes(table_name) {
+            let index_changes: Vec<_> = matches.into_iter().map(|(_, _, row)| (Some(row), None)).collect();
+            self.update_indexes(table_name, &index_changes)?;
+        }
+        if deleted > 0 {
+            self.record_change()?;
+        }
+        Ok(deleted)
+    }
+
+    /// Debugging aid: the page ids visited descending from the table's root to the leaf
+    /// that should contain `key`
+    pub fn trace_key(&mut self, table_name: &str, key: &Value) -> Result<Vec<PageId>, DatabaseError> {
+        self.require_btree(table_name, "key lookups")?;
+        let (mut btree, _) = self.open_table_btree(table_name)?;
+        btree.trace_key(key, Some(BAMBANG_HEADER_SIZE as u64))
+    }
+
+    /// Open a B+ tree over the table's current root
+    fn open_table_btree(&self, table_name: &str) -> Result<(BPlusTree, PageId), DatabaseError> {
+        let root_page_id = self.table_roots.get(table_name).copied().ok_or_else(|| {
+            DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            }
+        })?;
+        let key_columns = self
+            .get_table_schema(table_name)
+            .map(|schema| schema.key_columns().to_vec())
+            .unwrap_or_default();
+        let file = self.open_store()?;
+        let btree = self
+            .writable_btree(file, root_page_id)?
+            .with_fixed_root(root_page_id == self.schema_root_page_id())
+            .with_key_columns(key_columns);
+        Ok((btree, root_page_id))
+    }
+
+    /// The database file opened anew for reading and writing, recording into the
+    /// configured `io_log` if there is one
+    fn open_store(&self) -> Result<Box<dyn PageStore>, DatabaseError> {
+        page_store::open_store(&self.db_info.path, self.options.io_log.as_ref(), self.page_size())
+    }
+
+    /// A B+ tree over this database's file and page size
+    fn readable_btree(&self, file: impl PageStore + 'static, root_page_id: PageId) -> Result<BPlusTree, DatabaseError> {
+        BPlusTree::new_with_page_size(file, root_page_id, Some(BAMBANG_HEADER_SIZE as u64), self.page_size())
+    }
+
+    /// A B+ tree for modifying the database, sharing this manager's I/O counters and
+    /// write options
+    fn writable_btree(&self, file: impl PageStore + 'static, root_page_id: PageId) -> Result<BPlusTree, DatabaseError> {
+        Ok(self
+            .readable_btree(file, root_page_id)?
+            .with_io_counters(self.io_counters.clone())
+            .with_partial_writes(self.options.partial_page_writes)
+            .with_paranoid_checks(self.options.paranoid_checks)
+            .with_compaction_threshold(self.options.compaction_threshold.map(|threshold| threshold as f32))
+            .with_journal(self.journal.clone()))
+    }
+
+    /// Walk the leaf chain and return the page, slot and decoded row of every cell
+    /// matching `predicate`
+    fn collect_matching_cells(
+        &self,
+        btree: &mut BPlusTree,
+        schema: &TableSchema,
+        predicate: Option<&Predicate>,
+    ) -> Result<Vec<(PageId, usize, Row)>, DatabaseError> {
+        let predicate = match predicate {
+            Some(pred) => {
+                pred.validate_against_schema(schema)?;
+                self.record_predicate_reads(schema, pred);
+                Some(self.resolve_subqueries(pred, schema)?)
+            }
+            None => None,
+        };
+
+        let extras = Some(BAMBANG_HEADER_SIZE as u64);
+        let mut matches = Vec::new();
+        let mut next_page_id = Some(btree.first_leaf_page_id(extras)?);
+        while let Some(page_id) = next_page_id {
+            let page = btree.load_page(page_id, extras)?.clone();
+            for slot_index in 0..page.slot_directory.slots.len() {
+                let Some(row) = btree.leaf_row(&page, slot_index, extras)? else {
+                    continue;
+                };
+                if let Some(pred) = &predicate
+                    && !pred.evaluate_in(&row, schema, self.options.value_comparison, self.options.predicate_mode)?
+                {
+                    continue;
+                }
+                matches.push((page_id, slot_index, row));
+            }
+            next_page_id = page.next_leaf_page_id;
+        }
+        Ok(matches)
+    }
+
+    /// Append a column to an existing table, backfilling stored rows with the column's
+    /// default value (or NULL when it has none)
+    pub fn add_column(&mut self, table_name: &str, column: ColumnSchema) -> Result<(), DatabaseError> {
+        self.require_btree(table_name, "adding columns")?;
+        let mut schema = self
+            .load_table_schema(table_name)?
+            .cloned()
+            .ok_or_else(|| DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })?;
+        if column.position != schema.columns.len() {
+            return Err(DatabaseError::InvalidData {
+                details: format!(
+                    "New column '{}' must have position {}, got {}",
+                    column.name,
+                    schema.columns.len(),
+                    column.position
+                ),
+            });
+        }
+        if column.primary_key {
+            return Err(DatabaseError::InvalidData {
+                details: format!("Cannot add PRIMARY KEY column '{}'", column.name),
+            });
+        }
+        let backfill = column.default_value.clone().unwrap_or(Value::Null);
+        if backfill.is_null() && !column.nullable {
+            return Err(DatabaseError::InvalidData {
+                details: format!("Cannot add NOT NULL column '{}' without a default value", column.name),
+            });
+        }
+        if !backfill.is_compatible_with_type(&column.data_type) {
+            return Err(DatabaseError::TypeMismatch {
+                expected: column.data_type.to_string(),
+                actual: backfill.data_type().to_string(),
+            });
+        }
+        let mut columns = schema.columns.clone();
+        columns.push(column.clone());
+        CreateTableExecutor::new().validate_columns(&columns)?;
+
+        let position = column.position;
+        self.rewrite_rows(table_name, &schema, None, RowSelection::default(), |row| {
+            let mut new_row = row.clone();
+            new_row.values.resize(position, Value::Null);
+            new_row.values.push(backfill.clone());
+            new_row
+        })?;
+
+        // Tables created from plain SQL have no column entries yet; persist the existing
+        // columns too, otherwise the loader would only see the new one
+        let mut new_entries = Vec::new();
+        if !self.has_column_entries(table_name)? {
+            new_entries.extend(schema.column_schema_rows());
+        }
+        new_entries.push(column.to_schema_row(table_name));
+        for row in new_entries {
+            self.insert_schema_row(row)?;
+        }
+        self.reload_header()?;
+
+        schema.columns = columns;
+        self.schema_manager.add_table_schema(schema);
+        self.record_schema_change(SchemaChange::TableAltered(table_name.to_string()))
+    }
+
+    /// Whether `sqlite_schema` holds column entries for the table
+    fn has_column_entries(&self, table_name: &str) -> Result<bool, DatabaseError> {
+        let mut found = false;
+        self.for_each_schema_row(|_, row| {
+            found = matches!(row.values.first(), Some(Value::Text(entry_type)) if entry_type == "column")
+                && matches!(row.values.get(2), Some(Value::Text(name)) if name == table_name);
+            Ok(if found { ControlFlow::Break(()) } else { ControlFlow::Continue(()) })
+        })?;
+        Ok(found)
+    }
+
+    fn insert_schema_row(&mut self, row: Row) -> Result<(), DatabaseError> {
+        self.insert_schema_rows([row])
+    }
+
+    /// Add `rows` to `sqlite_schema` through a single B+ tree, whose page cache keeps the
+    /// leaf being filled across rows, rather than reopening the tree for each one. Leaves
+    /// are written once at the end rather than after every row.
+    fn insert_schema_rows(&mut self, rows: impl IntoIterator<Item = Row>) -> Result<(), DatabaseError> {
+        let mut schema_btree = self.schema_btree()?.with_deferred_writes(true);
+        for row in rows {
+            schema_btree.insert(row, Some(BAMBANG_HEADER_SIZE as u64))?;
+        }
+        schema_btree.flush_dirty_pages()?;
+        Ok(())
+    }
+
+    /// Root page of `sqlite_schema`, as the header records it
+    fn schema_root_page_id(&self) -> PageId {
+        self.db_info.header.schema_root_page()
+    }
+
+    /// A B+ tree for modifying `sqlite_schema`. Its root stays put as it splits.
+    fn schema_btree(&self) -> Result<BPlusTree, DatabaseError> {
+        Ok(self
+            .writable_btree(self.open_store()?, self.schema_root_page_id())?
+            .with_fixed_root(true))
+    }
+
+    /// First leaf of `sqlite_schema`, found by descending from its root, which is an
+    /// interior page once the schema outgrows one page
+    fn first_schema_leaf(&self) -> Result<PageId, DatabaseError> {
+        self.readable_btree(self.open_store()?, self.schema_root_page_id())?
+            .with_io_counters(self.io_counters.clone())
+            .first_leaf_page_id(Some(BAMBANG_HEADER_SIZE as u64))
+    }
+
+    /// Map update assignments to column positions, checking types and NOT NULL constraints
+    fn resolve_assignments(
+        schema: &TableSchema,
+        assignments: &[(String, Value)],
+    ) -> Result<Vec<(usize, Value)>, DatabaseError> {
+        assignments
+            .iter()
+            .map(|(column_name, value)| {
+                let column = schema.writable_column(column_name)?;
+                if value.is_null() && !column.nullable {
+                    return Err(DatabaseError::InvalidData {
+                        details: format!("Column '{}' cannot be NULL", column.name),
+                    });
+                }
+                if !value.is_compatible_with_type(&column.data_type) {
+                    return Err(DatabaseError::TypeMismatch {
+                        expected: column.data_type.to_string(),
+                        actual: value.data_type().to_string(),
+                    });
+                }
+                Ok((column.position, value.clone()))
+            })
+            .collect()
+    }
+
+    /// Get table schema by name. A table indexed by a lazy open is parsed here on first
+    /// use; one whose schema cannot be loaded has none.
+    pub fn get_table_schema(&self, table_name: &str) -> Option<&TableSchema> {
+        self.load_table_schema(table_name).ok().flatten()
+    }
+
+    /// Get table schema by name, parsing it first if a lazy open deferred that. Fails
+    /// with `InvalidTableSchema` when the table's stored schema is unusable.
+    pub fn load_table_schema(&self, table_name: &str) -> Result<Option<&TableSchema>, DatabaseError> {
+        let Some(lazy) = self.schema_manager.get_lazy_table_schema(table_name) else {
+            return Ok(self.schema_manager.get_table_schema(table_name));
+        };
+        lazy.get_or_parse(|| {
+            self.parse_lazy_schema(table_name, lazy)
+                .map_err(|err| err.to_string())
+        })
+        .as_ref()
+        .map(Option::as_ref)
+        .map_err(|reason| DatabaseError::InvalidTableSchema {
+            table: table_name.to_string(),
+            reason: reason.clone(),
+        })
+    }
+
+    /// Add a new table schema and persist it
+    pub fn add_table_schema(&mut self, schema: TableSchema) -> Result<(), DatabaseError> {
+        // Store table entry in sqlite_schema
+        let table_row = Row::new(vec![
+            Value::Text("table".to_string()),
+            Value::Text(schema.table_name.clone()),
+            Value::Text(schema.table_name.clone()),
+            Value::Integer(schema.root_page_id as i64),
+            Value::Text(schema.sql.clone()),
+        ]);
+
+        let mut rows = vec![table_row];
+        rows.extend(schema.column_schema_rows());
+        self.insert_schema_rows(rows)?;
+
+        self.reload_header()?;
+
+        // Add to in-memory schema manager
+        self.table_roots.insert(schema.table_name.clone(), schema.root_page_id);
+        let table_name = schema.table_name.clone();
+        self.schema_manager.add_table_schema(schema);
+
+        self.record_schema_change(SchemaChange::TableCreated(table_name))
+    }
+
+    /// Remove a table and its column and index entries from `sqlite_schema` and return the
+    /// pages of its B+ tree and index trees to the freelist
+    pub fn drop_table(&mut self, table_name: &str) -> Result<(), DatabaseError> {
+        let root_page_id = match self.table_roots.get(table_name) {
+            Some(root_page_id) if table_name != "sqlite_schema" => *root_page_id,
+            _ => {
+                return Err(DatabaseError::TableNotFound {
+                    name: table_name.to_string(),
+                });
+            }
+        };
+        if let Some((child, _)) = self
+            .referencing_foreign_keys(table_name)
+            .into_iter()
+            .find(|(child, _)| child != table_name)
+        {
+            return Err(DatabaseError::ExecutionError {
+                details: format!("Table '{}' is referenced by a FOREIGN KEY of table '{}'", table_name, child),
+            });
+        }
+        let mut page_ids = self.table_page_ids(table_name, root_page_id)?;
+        let index_names: Vec<String> = self
+            .indexes
+            .values()
+            .filter(|index| index.table_name == table_name)
+            .map(|index| index.name.clone())
+            .collect();
+        for name in &index_names {
+            let index_root_page_id = self.indexes[name].root_page_id;
+            page_ids.extend(
+                self.readable_btree(self.open_store()?, index_root_page_id)?
+                    .page_ids(Some(BAMBANG_HEADER_SIZE as u64))?,
+            );
+        }
+
+        // Rebuild the schema leaves holding the table's entries rather than deleting in
+        // place: deleted slots are never reused, so repeated create/drop cycles would
+        // otherwise fill them up
+        let owned_by_table = |row: &Row| match &row.values[..] {
+            [Value::Text(entry_type), Value::Text(name), ..] if entry_type == "table" => name == table_name,
+            [Value::Text(entry_type), _, Value::Text(owner), ..]
+                if entry_type == "column" || entry_type == "index" || entry_type == "usage" =>
+            {
+                owner == table_name
+            }
+            _ => false,
+        };
+        let mut owning_pages = Vec::new();
+        self.for_each_schema_row(|location, row| {
+            if owned_by_table(&row) && owning_pages.last() != Some(&location.page_id) {
+                owning_pages.push(location.page_id);
+            }
+            Ok(ControlFlow::Continue(()))
+        })?;
+        for page_id in owning_pages {
+            let schema_page = self.read_page(page_id)?;
+            let mut rebuilt = Page::new_with_size(page_id, PageType::LeafTable, self.page_size());
+            rebuilt.parent_page_id = schema_page.parent_page_id;
+            rebuilt.next_leaf_page_id = schema_page.next_leaf_page_id;
+            rebuilt.prev_leaf_page_id = schema_page.prev_leaf_page_id;
+            // Kept cells stay in slot order, which is key order only if it was before
+            rebuilt.sorted = schema_page.sorted;
+            for (i, slot) in schema_page.slot_directory.slots.iter().enumerate() {
+                let Some(cell_data) = schema_page.get_cell(i) else {
+                    continue;
+                };
+                if !owned_by_table(&Row::from_bytes(cell_data)?) {
+                    rebuilt.insert_cell(cell_data, slot.row_id)?;
+                }
+            }
+            // A leaf left empty never had its checksum refreshed by an insert
+            rebuilt.update_checksum();
+            self.write_page(page_id, &rebuilt)?;
+        }
+        self.schema_btree()?.rebalance(Some(BAMBANG_HEADER_SIZE as u64))?;
+
+        for page_id in page_ids {
+            self.free_page(page_id)?;
+        }
+        self.table_roots.remove(table_name);
+        self.next_row_ids.remove(table_name);
+        self.log_tails.remove(table_name);
+        for name in index_names {
+            self.indexes.remove(&name);
+        }
+        self.schema_manager.remove_table_schema(table_name);
+        self.column_usage.forget(table_name);
+        self.record_schema_change(SchemaChange::TableDropped(table_name.to_string()))
+    }
+
+    /// Validate a row against table schema
+    pub fn validate_row(&self, table_name: &str, row: &Row) -> Result<(), DatabaseError> {
+        if let Some(schema) = self.load_table_schema(table_name)? {
+            schema.validate_row(row)
+        } else {
+            Err(DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })
+        }
+    }
+
+    /// Validate a row that may be rewritten first: with `implicit_coercion` enabled,
+    /// values of the wrong type are replaced by their lossless cast
+    pub fn coerce_row(&self, table_name: &str, row: &mut Row) -> Result<(), DatabaseError> {
+        if let Some(schema) = self.load_table_schema(table_name)? {
+            schema.coerce_row(row, self.options.implicit_coercion)
+        } else {
+            Err(DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })
+        }
+    }
+
+    /// Apply default values to a row based on table schema
+    pub fn apply_defaults(&self, table_name: &str, row: &mut Row) -> Result<(), DatabaseError> {
+        if let Some(schema) = self.load_table_schema(table_name)? {
+            schema.apply_defaults(row)
+        } else {
+            Err(DatabaseError::TableNotFound {
+                name: table_name.to_string(),
+            })
+        }
     }
 
     /// Check if a table exists
@@ -426,3 +4058,55 @@ impl StorageManager {
         self.schema_manager.table_names().iter().map(|s| s.to_string()).collect()
     }
 }
+
+/// Call `f` with every row of `sqlite_schema` and where it is stored, following the leaf
+/// chain from `first_leaf` with pages read by `read_page`. A chain longer than
+/// `page_count` pages loops back on itself and is reported as corruption.
+pub(crate) fn walk_schema_rows<R, F>(
+    first_leaf: PageId,
+    page_count: u64,
+    mut read_page: R,
+    mut f: F,
+) -> Result<(), DatabaseError>
+where
+    R: FnMut(PageId) -> Result<Page, DatabaseError>,
+    F: FnMut(SchemaCellLocation, Row) -> Result<ControlFlow<()>, DatabaseError>,
+{
+    let mut next_page_id = Some(first_leaf);
+    let mut pages_visited = 0u64;
+    while let Some(page_id) = next_page_id {
+        pages_visited += 1;
+        if pages_visited > page_count {
+            return Err(DatabaseError::CorruptedDatabase {
+                reason: "Schema leaf chain loops back on itself".to_string(),
+            });
+        }
+        let page = read_page(page_id)?;
+        for slot_index in 0..page.slot_directory.slots.len() {
+            if let Some(cell_data) = page.get_cell(slot_index) {
+                let row = Row::from_bytes(cell_data)?;
+                if f(SchemaCellLocation { page_id, slot_index }, row)?.is_break() {
+                    return Ok(());
+                }
+            }
+        }
+        next_page_id = page.next_leaf_page_id;
+    }
+    Ok(())
+}
+
+fn collect_distinct<S: Scanner>(
+    executor: DistinctExecutor<S>,
+    indices: Vec<usize>,
+) -> Result<Vec<Row>, DatabaseError> {
+    let mut executor = if indices.is_empty() {
+        executor
+    } else {
+        executor.with_projection(indices)
+    };
+    let mut rows = Vec::new();
+    while let Some(row) = executor.scan()? {
+        rows.push(row);
+    }
+    Ok(rows)
+}
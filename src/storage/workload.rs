@@ -0,0 +1,123 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Scans kept by a `WorkloadLog` before the oldest are dropped
+pub const WORKLOAD_LOG_CAPACITY: usize = 10_000;
+/// Filtered scans a column needs within the window before an index is suggested for it
+pub const MIN_SCANS_FOR_RECOMMENDATION: u64 = 5;
+/// Fraction of examined rows a predicate may return and still count as selective
+pub const MAX_RECOMMENDED_SELECTIVITY: f64 = 0.1;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// One filtered scan as observed by the storage manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanRecord {
+    pub table: String,
+    /// Columns the scan's predicate references
+    pub columns: Vec<String>,
+    pub rows_examined: u64,
+    pub rows_returned: u64,
+    pub pages_read: u64,
+    pub at: Instant,
+}
+
+/// A suggested index, ranked by `estimated_pages_saved_per_day`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexRecommendation {
+    pub table: String,
+    pub columns: Vec<String>,
+    /// Leaf pages the observed scans would have skipped, scaled to a day from the window
+    pub estimated_pages_saved_per_day: f64,
+    pub create_statement: String,
+}
+
+/// In-memory log of recent filtered scans, bounded to `WORKLOAD_LOG_CAPACITY` entries
+#[derive(Debug, Default)]
+pub struct WorkloadLog {
+    records: Mutex<VecDeque<ScanRecord>>,
+}
+
+#[derive(Default)]
+struct ColumnWorkload {
+    scans: u64,
+    rows_examined: u64,
+    rows_returned: u64,
+    pages_read: u64,
+}
+
+impl WorkloadLog {
+    pub fn record(&self, record: ScanRecord) {
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        if records.len() == WORKLOAD_LOG_CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Scans recorded within the last `window`, oldest first
+    pub fn recent(&self, window: Duration) -> Vec<ScanRecord> {
+        let records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        records
+            .iter()
+            .filter(|record| record.at.elapsed() <= window)
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Rank single-column indexes for the columns that selective scans within `window`
+    /// filtered on most, skipping columns `is_covered` reports as already indexed. A scan
+    /// filtering on several columns counts towards each of them.
+    pub fn recommend(
+        &self,
+        window: Duration,
+        is_covered: impl Fn(&str, &str) -> bool,
+    ) -> Vec<IndexRecommendation> {
+        let mut workloads: HashMap<(String, String), ColumnWorkload> = HashMap::new();
+        for record in self.recent(window) {
+            let columns: HashSet<&String> = record.columns.iter().collect();
+            for column in columns {
+                let workload = workloads.entry((record.table.clone(), column.clone())).or_default();
+                workload.scans += 1;
+                workload.rows_examined += record.rows_examined;
+                workload.rows_returned += record.rows_returned;
+                workload.pages_read += record.pages_read;
+            }
+        }
+
+        let days = window.as_secs_f64().max(1.0) / SECONDS_PER_DAY;
+        let mut recommendations: Vec<IndexRecommendation> = workloads
+            .into_iter()
+            .filter(|((table, column), workload)| {
+                workload.scans >= MIN_SCANS_FOR_RECOMMENDATION
+                    && workload.rows_examined > 0
+                    && (workload.rows_returned as f64 / workload.rows_examined as f64)
+                        <= MAX_RECOMMENDED_SELECTIVITY
+                    && !is_covered(table, column)
+            })
+            .map(|((table, column), workload)| {
+                let selectivity = workload.rows_returned as f64 / workload.rows_examined as f64;
+                let pages_saved = workload.pages_read as f64 * (1.0 - selectivity);
+                IndexRecommendation {
+                    create_statement: format!("CREATE INDEX idx_{}_{} ON {}({})", table, column, table, column),
+                    estimated_pages_saved_per_day: pages_saved / days,
+                    columns: vec![column],
+                    table,
+                }
+            })
+            .collect();
+        recommendations.sort_by(|a, b| {
+            b.estimated_pages_saved_per_day
+                .total_cmp(&a.estimated_pages_saved_per_day)
+                .then_with(|| a.create_statement.cmp(&b.create_statement))
+        });
+        recommendations
+    }
+}
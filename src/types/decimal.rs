@@ -0,0 +1,162 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::error::DatabaseError;
+
+/// Largest number of fractional digits a decimal may carry; 10^38 still fits in an i128
+pub const MAX_DECIMAL_SCALE: u8 = 38;
+
+/// Exact fixed-point number: `mantissa / 10^scale`. Trailing zeros are kept, so `1.50`
+/// prints as written, but compares equal to `1.5`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    pub fn new(mantissa: i128, scale: u8) -> Result<Self, DatabaseError> {
+        if scale > MAX_DECIMAL_SCALE {
+            return Err(DatabaseError::InvalidData {
+                details: format!("Decimal scale {} exceeds {}", scale, MAX_DECIMAL_SCALE),
+            });
+        }
+        Ok(Self { mantissa, scale })
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Nearest `f64`; may round for long mantissas
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    /// The value as an integer, if it has no fractional part and fits in an i64
+    pub fn to_i64(&self) -> Option<i64> {
+        let divisor = 10i128.pow(self.scale as u32);
+        if self.mantissa % divisor != 0 {
+            return None;
+        }
+        i64::try_from(self.mantissa / divisor).ok()
+    }
+
+    /// Mantissa rescaled to `scale` digits, or `None` if that overflows an i128
+    fn mantissa_at(&self, scale: u8) -> Option<i128> {
+        10i128
+            .checked_pow((scale - self.scale) as u32)
+            .and_then(|factor| self.mantissa.checked_mul(factor))
+    }
+
+    /// Byte layout: 16-byte little-endian mantissa followed by the scale
+    pub fn to_bytes(&self) -> [u8; 17] {
+        let mut bytes = [0u8; 17];
+        bytes[..16].copy_from_slice(&self.mantissa.to_le_bytes());
+        bytes[16] = self.scale;
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DatabaseError> {
+        if bytes.len() != 17 {
+            return Err(DatabaseError::SerializationError {
+                details: "Invalid decimal data length".to_string(),
+            });
+        }
+        let mut mantissa = [0u8; 16];
+        mantissa.copy_from_slice(&bytes[..16]);
+        Self::new(i128::from_le_bytes(mantissa), bytes[16])
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Self {
+            mantissa: value as i128,
+            scale: 0,
+        }
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = DatabaseError;
+
+    /// Parse plain decimal notation such as `-12.340` or `+.5`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DatabaseError::SerializationError {
+            details: format!("Cannot parse '{}' as decimal", s),
+        };
+        let trimmed = s.trim();
+        let (negative, digits) = match trimmed.as_bytes().first() {
+            Some(b'-') => (true, &trimmed[1..]),
+            Some(b'+') => (false, &trimmed[1..]),
+            _ => (false, trimmed),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty()
+            || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let scale = u8::try_from(fraction.len())
+            .ok()
+            .filter(|scale| *scale <= MAX_DECIMAL_SCALE)
+            .ok_or_else(invalid)?;
+        let mut mantissa: i128 = 0;
+        for digit in whole.bytes().chain(fraction.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add((digit - b'0') as i128))
+                .ok_or_else(invalid)?;
+        }
+        Ok(Self {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale,
+        })
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        match (self.mantissa_at(scale), other.mantissa_at(scale)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            // Rescaling overflowed, so that side's magnitude exceeds anything the other holds
+            (None, _) => self.mantissa.signum().cmp(&0),
+            (_, None) => 0.cmp(&other.mantissa.signum()),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = padded.split_at(padded.len() - scale);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
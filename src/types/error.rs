@@ -1,6 +1,9 @@
 use thiserror::Error;
 
-use crate::types::PageId;
+use crate::types::{
+    PageId, RowId,
+    value::{DataType, Value},
+};
 
 #[derive(Error, Debug)]
 pub enum DatabaseError {
@@ -16,14 +19,22 @@ pub enum DatabaseError {
     SerializationError { details: String },
     #[error("Table '{name}' not found")]
     TableNotFound { name: String },
+    #[error("Schema of table '{table}' could not be loaded: {reason}")]
+    InvalidTableSchema { table: String, reason: String },
     #[error("Column '{name}' not found in table '{table}'")]
     ColumnNotFound { name: String, table: String },
+    #[error("Column '{name}' of table '{table}' is row metadata and cannot be written")]
+    ReadOnlyColumn { name: String, table: String },
     #[error("SQL parsing error: {details}")]
     SqlParseError { details: String },
+    #[error("Syntax error at byte {offset} near '{token}': {details}")]
+    PredicateSyntax { offset: usize, token: String, details: String },
     #[error("Query execution error: {details}")]
     ExecutionError { details: String },
     #[error("Type mismatch: expected {expected}, got {actual}")]
     TypeMismatch { expected: String, actual: String },
+    #[error("Division by zero")]
+    DivisionByZero,
     #[error("Concurrent access violation")]
     ConcurrencyError,
     #[error("Buffer pool exhausted")]
@@ -32,6 +43,8 @@ pub enum DatabaseError {
     TransactionAborted { reason: String },
     #[error("Invalid page size: {expected} bytes, got {actual} bytes")]
     InvalidPageSize { expected: usize, actual: usize },
+    #[error("Unsupported page size {size}: must be a power of two from 512 to 65536 bytes")]
+    UnsupportedPageSize { size: usize },
     #[error("Corrupted page: page_id={page_id}, reason={reason}")]
     CorruptedPage { page_id: PageId, reason: String },
     #[error("Invalid page type: {0}")]
@@ -50,6 +63,69 @@ pub enum DatabaseError {
     CorruptedDatabase { reason: String },
     #[error("Invalid data: {details}")]
     InvalidData { details: String },
+    #[error("Database size quota exceeded: limit {limit} bytes, current {current} bytes")]
+    QuotaExceeded { limit: u64, current: u64 },
+    #[error("Row of {size} bytes exceeds the maximum inline row size of {max} bytes")]
+    RowTooLarge { size: usize, max: usize },
+    #[error("UNIQUE constraint failed: {table}.{column} already contains {value}")]
+    UniqueConstraintViolation {
+        table: String,
+        column: String,
+        value: Value,
+    },
+    #[error("Table has {count} columns, more than the maximum of {max}")]
+    TooManyColumns { count: usize, max: usize },
+    #[error("CHECK constraint failed on column '{column}': {constraint}")]
+    CheckConstraintViolation { constraint: String, column: String },
+    /// `column` and `reference` are qualified by their tables, like `orders.customer_id`
+    /// and `customers.id`
+    #[error("FOREIGN KEY constraint failed: {column} = {value} must match a row of {reference}")]
+    ForeignKeyViolation {
+        column: String,
+        reference: String,
+        value: Value,
+    },
+    #[error("Page {page_id} holds only metadata; {operation} requires the full page")]
+    MetadataOnlyPage { page_id: PageId, operation: String },
+    #[error("Column '{column}' expects {expected} but got {actual} value {preview}{hint}")]
+    ColumnTypeMismatch {
+        column: String,
+        expected: DataType,
+        actual: DataType,
+        /// Truncated rendering of the offending value
+        preview: String,
+        /// Suggested fix, starting with "; hint: ", or empty
+        hint: String,
+    },
+    #[error("Sequence '{name}' not found")]
+    SequenceNotFound { name: String },
+    #[error("Sequence '{name}' already exists")]
+    SequenceAlreadyExists { name: String },
+    #[error("Sequence '{name}' has reached its limit")]
+    SequenceExhausted { name: String },
+    #[error("No index on column '{column}' of table '{table}'")]
+    IndexNotFound { table: String, column: String },
+    #[error("Index '{name}' already exists")]
+    IndexAlreadyExists { name: String },
+    #[error("{operation} was cancelled")]
+    Cancelled { operation: String },
+    #[error("Row {row_id} was changed by another writer: expected version {expected}, found {actual}")]
+    VersionConflict { row_id: RowId, expected: u64, actual: u64 },
+    /// A B+ tree split could not lay out the pages it produced. `source` is the error
+    /// that stopped it, e.g. `PageFull` when a single cell cannot fit a fresh page.
+    #[error("Split of page {page_id} failed: {source}")]
+    SplitFailed { page_id: PageId, source: Box<DatabaseError> },
+    /// A line of imported text that could not be stored; `line` counts from 1
+    #[error("Line {line}: {source}")]
+    ImportLine { line: usize, source: Box<DatabaseError> },
+    /// An operation that needs a B+ tree, such as a key lookup, an index or an in-place
+    /// update, tried on an append-log table
+    #[error("Table '{table}' is an append log, which does not support {operation}")]
+    AppendLogUnsupported { table: String, operation: String },
+    #[error("Internal invariant violated: {details}")]
+    InternalInvariant { details: String },
+    #[error("Incompatible page image: {details}; copy the table logically (scan and insert rows) instead")]
+    IncompatiblePageImage { details: String },
 }
 
 pub type Result<T> = std::result::Result<T, DatabaseError>;
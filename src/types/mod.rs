@@ -1,3 +1,4 @@
+pub mod decimal;
 pub mod entry;
 pub mod error;
 pub mod page;
@@ -11,11 +12,22 @@ pub type TransactionId = u64;
 pub type ColumnId = u32;
 
 // Constants following SQLite specifications
-pub const PAGE_SIZE: usize = 4096;
+pub const PAGE_SIZE: usize = 4096; // Default page size for new databases
+pub const MIN_PAGE_SIZE: usize = 512;
+pub const MAX_PAGE_SIZE: usize = 65536;
 pub const MAX_PAGE_COUNT: u64 = 1099511627775; // 2^40 - 1 (SQLite limit)
 pub const HEADER_SIZE: usize = 100; // Database header size
-pub const PAGE_HEADER_SIZE: usize = 36; // Per-page header
+pub const PAGE_HEADER_SIZE: usize = 44; // Per-page header
 
-pub const SLOT_DIRECTORY_ENTRY_SIZE: usize = 4; // offset (2 bytes) + length (2 bytes)
+pub const SLOT_DIRECTORY_ENTRY_SIZE: usize = 13; // offset (2) + length (2) + flags (1) + row_id (8)
 pub const CHECKSUM_SIZE: usize = 4; // CRC32 checksum size
 pub const OVERFLOW_POINTER_SIZE: usize = 8; // PageId for overflow page
+
+/// Check that `page_size` is a power of two between `MIN_PAGE_SIZE` and `MAX_PAGE_SIZE`
+pub fn validate_page_size(page_size: usize) -> Result<(), error::DatabaseError> {
+    if page_size.is_power_of_two() && (MIN_PAGE_SIZE..=MAX_PAGE_SIZE).contains(&page_size) {
+        Ok(())
+    } else {
+        Err(error::DatabaseError::UnsupportedPageSize { size: page_size })
+    }
+}
@@ -1,8 +1,9 @@
-use std::io::Cursor;
+use std::{cmp::Ordering, io::Cursor, ops::Range};
 
 use crate::{
     types::{
         PAGE_HEADER_SIZE, PAGE_SIZE, PageId, RowId, SLOT_DIRECTORY_ENTRY_SIZE, error::DatabaseError,
+        validate_page_size, value::Value,
     },
     utils::hash::{calculate_page_checksum, verify_page_checksum},
 };
@@ -55,6 +56,27 @@ impl OverflowPointer {
         buffer.extend_from_slice(&self.total_size.to_le_bytes());
         Ok(buffer)
     }
+
+    /// Decode a pointer written by `serialize_to_vec`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DatabaseError> {
+        if bytes.len() != Self::SERIALIZED_SIZE {
+            return Err(DatabaseError::InvalidData {
+                details: format!(
+                    "overflow pointer is {} bytes, expected {}",
+                    bytes.len(),
+                    Self::SERIALIZED_SIZE
+                ),
+            });
+        }
+        let mut page_id = [0u8; 8];
+        page_id.copy_from_slice(&bytes[..8]);
+        let mut total_size = [0u8; 4];
+        total_size.copy_from_slice(&bytes[8..]);
+        Ok(Self {
+            page_id: PageId::from_le_bytes(page_id),
+            total_size: u32::from_le_bytes(total_size),
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -67,6 +89,11 @@ pub struct SlotEntry {
 }
 
 impl SlotEntry {
+    /// Flag bit set on entries whose cell is an `OverflowPointer`
+    pub const FLAG_OVERFLOW: u8 = 0b01;
+    /// Flag bit set on entries that carry a row_id
+    pub const FLAG_ROW_ID: u8 = 0b10;
+
     pub fn new_regular(offset: u16, length: u16, row_id: Option<RowId>) -> Self {
         Self {
             offset,
@@ -93,17 +120,55 @@ impl SlotEntry {
     }
 
     /// Calculate absolute file offset for this slot's data
-    pub fn absolute_file_offset(&self, page_id: PageId) -> u64 {
-        (page_id * PAGE_SIZE as u64) + self.offset as u64
+    pub fn absolute_file_offset(&self, page_id: PageId, page_size: usize) -> u64 {
+        crate::storage::page_offset_with_size(page_id, page_size) + self.offset as u64
     }
 
     /// Get the data range for this slot in the file
-    pub fn file_range(&self, page_id: PageId) -> (u64, u64) {
-        let start = self.absolute_file_offset(page_id);
+    pub fn file_range(&self, page_id: PageId, page_size: usize) -> (u64, u64) {
+        let start = self.absolute_file_offset(page_id, page_size);
         let end = start + self.length as u64;
         (start, end)
     }
 
+    /// On-disk form of this entry: offset (2), length (2), flags (1) and row_id (8), all
+    /// little-endian. A missing row_id is written as 0 with `FLAG_ROW_ID` clear, so a
+    /// deleted slot is all zeroes.
+    pub fn to_bytes(&self) -> [u8; SLOT_DIRECTORY_ENTRY_SIZE] {
+        let mut bytes = [0u8; SLOT_DIRECTORY_ENTRY_SIZE];
+        bytes[0..2].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[2..4].copy_from_slice(&self.length.to_le_bytes());
+        let mut flags = 0;
+        if self.is_overflow {
+            flags |= Self::FLAG_OVERFLOW;
+        }
+        if self.row_id.is_some() {
+            flags |= Self::FLAG_ROW_ID;
+        }
+        bytes[4] = flags;
+        bytes[5..13].copy_from_slice(&self.row_id.unwrap_or(0).to_le_bytes());
+        bytes
+    }
+
+    /// Decode an entry written by `to_bytes`. The overflow pointer lives in the cell, so
+    /// it is left unset here; `Page::from_bytes` fills it in. Returns `None` if a flag
+    /// bit this version does not know is set.
+    pub fn from_bytes(bytes: &[u8; SLOT_DIRECTORY_ENTRY_SIZE]) -> Option<Self> {
+        let flags = bytes[4];
+        if flags & !(Self::FLAG_OVERFLOW | Self::FLAG_ROW_ID) != 0 {
+            return None;
+        }
+        let mut row_id = [0u8; 8];
+        row_id.copy_from_slice(&bytes[5..13]);
+        Some(Self {
+            offset: u16::from_le_bytes([bytes[0], bytes[1]]),
+            length: u16::from_le_bytes([bytes[2], bytes[3]]),
+            row_id: (flags & Self::FLAG_ROW_ID != 0).then_some(RowId::from_le_bytes(row_id)),
+            is_overflow: flags & Self::FLAG_OVERFLOW != 0,
+            overflow_pointer: None,
+        })
+    }
+
     /// Check if this slot is deleted (has zero length AND no row_id)
     pub fn is_deleted(&self) -> bool {
         self.length == 0 && self.row_id.is_none()
@@ -115,6 +180,12 @@ pub struct SlotDirectory {
     pub slots: Vec<SlotEntry>,
 }
 
+impl Default for SlotDirectory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SlotDirectory {
     pub fn new() -> Self {
         Self { slots: Vec::new() }
@@ -127,6 +198,25 @@ impl SlotDirectory {
     }
 }
 
+/// Where a key falls among the live cells of a page kept in key order, as found by
+/// `Page::binary_search_key`. Cells with an equal key occupy `start..end`, skipping
+/// deleted slots; both are the directory length when no live cell lies past them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotPos {
+    /// Slot of the first live cell whose key is not below the searched one
+    pub start: usize,
+    /// Slot of the first live cell whose key is above the searched one, where a new cell
+    /// with the searched key goes after any it equals
+    pub end: usize,
+}
+
+impl SlotPos {
+    /// Whether a live cell holds the searched key, in which case `start` is its slot
+    pub fn found(&self) -> bool {
+        self.start < self.end
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PageStats {
     pub page_id: PageId,
@@ -140,43 +230,240 @@ pub struct PageStats {
     pub utilization_ratio: f32,
 }
 
+/// Space accounting summed over the pages of a B+ tree, as `StorageManager::table_page_stats`
+/// reports it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TablePageStats {
+    pub page_count: usize,
+    pub leaf_pages: usize,
+    pub interior_pages: usize,
+    pub total_slots: usize,
+    pub active_slots: usize,
+    pub deleted_slots: usize,
+    pub free_space: usize,
+    pub used_space: usize,
+    pub wasted_space: usize,
+    /// Bytes between the pages' free space offsets and their ends, live cells and gaps
+    pub cell_area_bytes: usize,
+    /// Bytes the pages could hold in cells and slot entries
+    pub capacity_bytes: usize,
+}
+
+impl TablePageStats {
+    /// Count `page` in the totals
+    pub fn add(&mut self, page: &Page) {
+        let stats = page.get_page_stats();
+        self.page_count += 1;
+        match page.page_type {
+            PageType::LeafTable | PageType::LeafIndex => self.leaf_pages += 1,
+            PageType::InteriorTable | PageType::InteriorIndex => self.interior_pages += 1,
+            PageType::OverflowPage => {}
+        }
+        self.total_slots += stats.total_slots;
+        self.active_slots += stats.active_slots;
+        self.deleted_slots += stats.deleted_slots;
+        self.free_space += stats.free_space;
+        self.used_space += stats.used_space;
+        self.wasted_space += stats.wasted_space;
+        self.cell_area_bytes += page.page_size - page.free_space_offset as usize;
+        self.capacity_bytes += page.page_size - PAGE_HEADER_SIZE;
+    }
+
+    /// Share of the cell area no live cell uses, as in `Page::get_fragmentation_ratio`
+    pub fn fragmentation_ratio(&self) -> f32 {
+        if self.cell_area_bytes == 0 {
+            0.0
+        } else {
+            self.wasted_space as f32 / self.cell_area_bytes as f32
+        }
+    }
+
+    /// Share of the pages' capacity taken by the cell area, as in `Page::get_utilization_ratio`
+    pub fn utilization_ratio(&self) -> f32 {
+        if self.capacity_bytes == 0 {
+            0.0
+        } else {
+            self.cell_area_bytes as f32 / self.capacity_bytes as f32
+        }
+    }
+}
+
+/// A table's page stats from before and after `StorageManager::vacuum_table`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VacuumReport {
+    pub before: TablePageStats,
+    pub after: TablePageStats,
+}
+
+impl VacuumReport {
+    /// Bytes that were taken by deleted cells and gaps and are free again
+    pub fn reclaimed_bytes(&self) -> usize {
+        self.before.wasted_space.saturating_sub(self.after.wasted_space)
+    }
+
+    /// Pages merging underfull leaves returned to the freelist
+    pub fn freed_pages(&self) -> usize {
+        self.before.page_count.saturating_sub(self.after.page_count)
+    }
+}
+
+/// Size of the database file before and after `StorageManager::vacuum`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DatabaseVacuumReport {
+    pub pages_before: u64,
+    pub pages_after: u64,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+impl DatabaseVacuumReport {
+    /// Bytes the file shrank by
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+
+    pub fn freed_pages(&self) -> u64 {
+        self.pages_before.saturating_sub(self.pages_after)
+    }
+}
+
+/// Modified bytes beyond which a page is written whole rather than by extents
+pub const fn partial_write_limit(page_size: usize) -> usize {
+    page_size / 2
+}
+
+/// Cells of at least this many bytes are moved to an overflow page
+pub const fn overflow_threshold(page_size: usize) -> usize {
+    page_size / 2
+}
+
+/// Cell bytes one overflow page holds
+pub const fn overflow_page_capacity(page_size: usize) -> usize {
+    page_size - PAGE_HEADER_SIZE - SLOT_DIRECTORY_ENTRY_SIZE
+}
+
+/// `partial_write_limit` at the default page size
+pub const PARTIAL_WRITE_LIMIT: usize = partial_write_limit(PAGE_SIZE);
+
+/// `overflow_threshold` at the default page size
+pub const OVERFLOW_THRESHOLD: usize = overflow_threshold(PAGE_SIZE);
+
+/// `overflow_page_capacity` at the default page size
+pub const OVERFLOW_PAGE_CAPACITY: usize = overflow_page_capacity(PAGE_SIZE);
+
+/// Page space a cell takes once inserted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageCost {
+    /// Bytes in the page's cell area: the cell itself, or an overflow pointer
+    pub inline_bytes: usize,
+    pub slot_bytes: usize,
+    pub needs_overflow: bool,
+    /// Overflow pages the cell spills into; zero when it is stored inline
+    pub overflow_pages_estimate: usize,
+}
+
+impl StorageCost {
+    /// Cost of a cell on a page of the default size
+    pub fn for_cell(cell_len: usize) -> Self {
+        Self::for_cell_with_page_size(cell_len, PAGE_SIZE)
+    }
+
+    pub fn for_cell_with_page_size(cell_len: usize, page_size: usize) -> Self {
+        let needs_overflow = cell_len >= overflow_threshold(page_size);
+        Self {
+            inline_bytes: if needs_overflow {
+                OverflowPointer::SERIALIZED_SIZE
+            } else {
+                cell_len
+            },
+            slot_bytes: SLOT_DIRECTORY_ENTRY_SIZE,
+            needs_overflow,
+            overflow_pages_estimate: if needs_overflow {
+                cell_len.div_ceil(overflow_page_capacity(page_size))
+            } else {
+                0
+            },
+        }
+    }
+
+    /// Free space the cell takes from the page it is inserted into
+    pub fn page_bytes(&self) -> usize {
+        self.inline_bytes + self.slot_bytes
+    }
+}
+
+/// Decoded header fields: page id, type, parent, next leaf, prev leaf, cell count, free space
+/// offset, checksum, flags
+type PageHeaderFields = (PageId, PageType, Option<PageId>, Option<PageId>, Option<PageId>, u16, u16, u32, u8);
+
 #[derive(Debug, Clone)]
 pub struct Page {
     pub page_id: PageId,
     pub page_type: PageType,
     pub parent_page_id: Option<PageId>,
     pub next_leaf_page_id: Option<PageId>,
+    /// Leaf before this one in key order, for scans that walk the leaves backwards
+    pub prev_leaf_page_id: Option<PageId>,
+    /// Whether the live cells are in key order by slot, so `binary_search_key` can be used
+    /// on the page. Stored as `FLAG_SORTED`; pages written before cells were kept in order
+    /// lack it and are searched linearly.
+    pub sorted: bool,
     pub is_dirty: bool,
+    /// Size of the page in bytes, as set for the whole database file
+    pub page_size: usize,
 
     // Slotted page structure
     pub slot_directory: SlotDirectory,
-    pub free_space_offset: u16,
+    /// Start of the cell area. Stored on disk as a u16, where a page of `MAX_PAGE_SIZE`
+    /// with no cells writes 0, as SQLite does.
+    pub free_space_offset: u32,
+    /// Number of slot entries, deleted ones included; `active_cell_count` counts only
+    /// the live cells
     pub cell_count: u16,
 
     // Optional data - None means metadata-only mode for read-heavy workloads
     pub data: Option<Vec<u8>>,
     pub checksum: u32,
     pub overflow_pages: Vec<PageId>,
+    /// Inconsistencies repaired while loading the page; the page is left dirty when non-empty
+    pub reconciliation_warnings: Vec<String>,
+    /// Byte ranges changed since the page was last read or written, or `None` when the
+    /// whole page must be written, e.g. because it is new or was compacted
+    pub dirty_extents: Option<Vec<Range<usize>>>,
 }
 
 impl Page {
-    /// Create a new empty page with full data
+    /// Header flag bit set on pages whose cells are in key order
+    pub const FLAG_SORTED: u8 = 0b01;
+    /// Offset of the flags byte in the page header, after `prev_leaf_page_id`
+    const FLAGS_OFFSET: usize = 41;
+
+    /// Create a new empty page of the default size with full data
     pub fn new(page_id: PageId, page_type: PageType) -> Self {
-        let mut data = Vec::with_capacity(PAGE_SIZE);
-        data.resize(PAGE_SIZE, 0);
+        Self::new_with_size(page_id, page_type, PAGE_SIZE)
+    }
+
+    /// Create a new empty page of `page_size` bytes, which the caller has validated
+    pub fn new_with_size(page_id: PageId, page_type: PageType, page_size: usize) -> Self {
+        let data = vec![0; page_size];
 
         let mut page = Self {
             page_id,
             page_type,
             parent_page_id: None,
             next_leaf_page_id: None,
+            prev_leaf_page_id: None,
+            sorted: true,
             is_dirty: false,
+            page_size,
             slot_directory: SlotDirectory::new(),
-            free_space_offset: PAGE_SIZE as u16,
+            free_space_offset: page_size as u32,
             cell_count: 0,
             data: Some(data),
             checksum: 0,
             overflow_pages: Vec::new(),
+            reconciliation_warnings: Vec::new(),
+            dirty_extents: None,
         };
         page.update_checksum();
         page
@@ -185,6 +472,12 @@ impl Page {
     /// Create metadata-only page from header + slot directory bytes
     /// Expected: header_bytes.len() >= PAGE_HEADER_SIZE + (cell_count * SLOT_DIRECTORY_ENTRY_SIZE)
     pub fn from_header_bytes(header_bytes: &[u8]) -> Result<Self, DatabaseError> {
+        Self::from_header_bytes_with_size(header_bytes, PAGE_SIZE)
+    }
+
+    /// Create a metadata-only page of a database whose pages are `page_size` bytes
+    pub fn from_header_bytes_with_size(header_bytes: &[u8], page_size: usize) -> Result<Self, DatabaseError> {
+        validate_page_size(page_size)?;
         if header_bytes.len() < PAGE_HEADER_SIZE {
             return Err(DatabaseError::InvalidPageSize {
                 expected: PAGE_HEADER_SIZE,
@@ -198,9 +491,11 @@ impl Page {
             page_type,
             parent_page_id,
             next_leaf_page_id,
+            prev_leaf_page_id,
             cell_count,
             free_space_offset,
             checksum,
+            flags,
         ) = Self::read_header(&header_bytes[..PAGE_HEADER_SIZE])?;
 
         // Calculate expected size including slot directory
@@ -212,26 +507,36 @@ impl Page {
             });
         }
 
+        let free_space_offset = checked_free_space_offset(free_space_offset, page_id, page_size)?;
+
         // Parse slot directory from remaining bytes
         let slots = Self::read_slot_directory(
             &header_bytes[PAGE_HEADER_SIZE..expected_size],
             cell_count,
             page_id,
+            page_size,
         )?;
 
-        Ok(Page {
+        let mut page = Page {
             page_id,
             page_type,
             parent_page_id,
             next_leaf_page_id,
+            prev_leaf_page_id,
+            sorted: flags & Self::FLAG_SORTED != 0,
             is_dirty: false,
+            page_size,
             slot_directory: SlotDirectory { slots },
             free_space_offset,
             cell_count,
             data: None, // Metadata-only mode
             checksum,
             overflow_pages: Vec::new(),
-        })
+            reconciliation_warnings: Vec::new(),
+            dirty_extents: Some(Vec::new()),
+        };
+        page.reconcile_free_space_offset();
+        Ok(page)
     }
 
     /// Calculate the exact bytes needed for metadata-only parsing
@@ -281,9 +586,9 @@ impl Page {
 
     /// Upgrade metadata-only page to full page by loading complete data
     pub fn load_full_data(&mut self, page_data: Vec<u8>) -> Result<(), DatabaseError> {
-        if page_data.len() != PAGE_SIZE {
+        if page_data.len() != self.page_size {
             return Err(DatabaseError::InvalidPageSize {
-                expected: PAGE_SIZE,
+                expected: self.page_size,
                 actual: page_data.len(),
             });
         }
@@ -292,6 +597,75 @@ impl Page {
         Ok(())
     }
 
+    /// Upgrade a metadata-only page in place, fetching its full bytes through `loader`.
+    /// Pages that already hold their data are left untouched and `loader` is not called.
+    pub fn ensure_full_data(
+        &mut self,
+        loader: impl FnOnce(PageId) -> Result<Vec<u8>, DatabaseError>,
+    ) -> Result<(), DatabaseError> {
+        if self.is_metadata_only() {
+            let page_data = loader(self.page_id)?;
+            self.load_full_data(page_data)?;
+        }
+        Ok(())
+    }
+
+    fn require_full_data(&self, operation: &str) -> Result<(), DatabaseError> {
+        if self.is_metadata_only() {
+            return Err(DatabaseError::MetadataOnlyPage {
+                page_id: self.page_id,
+                operation: operation.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Record that `range` of the serialized page changed
+    fn mark_dirty_extent(&mut self, range: Range<usize>) {
+        if let Some(extents) = &mut self.dirty_extents {
+            extents.push(range);
+            if extents.iter().map(|extent| extent.len()).sum::<usize>() > partial_write_limit(self.page_size) {
+                self.dirty_extents = None;
+            }
+        }
+    }
+
+    fn mark_slot_entry_dirty(&mut self, slot_index: usize) {
+        let start = PAGE_HEADER_SIZE + slot_index * SLOT_DIRECTORY_ENTRY_SIZE;
+        self.mark_dirty_extent(start..start + SLOT_DIRECTORY_ENTRY_SIZE);
+    }
+
+    /// Require the next write to cover the whole page. Code that rearranges the slot
+    /// directory or cell area directly, rather than through the cell methods, must call this.
+    pub fn mark_fully_dirty(&mut self) {
+        self.dirty_extents = None;
+    }
+
+    /// Forget the recorded extents once the page matches what is on disk
+    pub fn clear_dirty_extents(&mut self) {
+        self.dirty_extents = Some(Vec::new());
+    }
+
+    /// Sorted, coalesced byte ranges a write of this page has to cover, always including
+    /// the header since the checksum lives there. `None` means the whole page.
+    pub fn write_extents(&self) -> Option<Vec<Range<usize>>> {
+        let extents = self.dirty_extents.as_ref()?;
+        let mut ranges: Vec<Range<usize>> = std::iter::once(0..PAGE_HEADER_SIZE)
+            .chain(extents.iter().cloned())
+            .filter(|range| !range.is_empty())
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        let total: usize = merged.iter().map(|range| range.len()).sum();
+        (total <= partial_write_limit(self.page_size)).then_some(merged)
+    }
+
     // Updated checksum methods using utility functions
     pub fn update_checksum(&mut self) {
         self.checksum = calculate_page_checksum(
@@ -299,8 +673,10 @@ impl Page {
             &self.page_type,
             self.parent_page_id,
             self.next_leaf_page_id,
+            self.prev_leaf_page_id,
+            self.flags(),
             self.cell_count,
-            self.free_space_offset,
+            self.free_space_offset as u16,
             &self.slot_directory.slots,
             self.data.as_deref(),
             self.free_space_offset as usize,
@@ -313,8 +689,10 @@ impl Page {
             &self.page_type,
             self.parent_page_id,
             self.next_leaf_page_id,
+            self.prev_leaf_page_id,
+            self.flags(),
             self.cell_count,
-            self.free_space_offset,
+            self.free_space_offset as u16,
             &self.slot_directory.slots,
             self.data.as_deref(),
             self.free_space_offset as usize,
@@ -323,7 +701,7 @@ impl Page {
     }
 
     pub fn needs_overflow(&self, data_size: usize) -> bool {
-        data_size >= (PAGE_SIZE / 2)
+        data_size >= overflow_threshold(self.page_size)
     }
 
     pub fn create_overflow_pointer(
@@ -344,11 +722,7 @@ impl Page {
         row_id: Option<RowId>,
         overflow_page_id: Option<PageId>,
     ) -> Result<usize, DatabaseError> {
-        if self.is_metadata_only() {
-            return Err(DatabaseError::SerializationError {
-                details: "Insertion requires full page".to_string(),
-            });
-        }
+        self.require_full_data("insertion")?;
 
         let needs_overflow = overflow_page_id.is_some() || self.needs_overflow(data.len());
 
@@ -357,13 +731,14 @@ impl Page {
                 let overflow_ptr = self.create_overflow_pointer(data, overflow_id);
                 let overflow_data = overflow_ptr.serialize_to_vec()?;
 
-                if !self.can_fit(overflow_data.len()) {
+                let reused = self.nearest_deleted_slot(self.slot_directory.slots.len());
+                if !self.fits(overflow_data.len(), reused.is_none()) {
                     return Err(DatabaseError::PageFull {
                         page_id: self.page_id,
                     });
                 }
 
-                let new_offset = self.free_space_offset - overflow_data.len() as u16;
+                let new_offset = self.free_space_offset - overflow_data.len() as u32;
                 let start = new_offset as usize;
                 let end = start + overflow_data.len();
 
@@ -371,13 +746,15 @@ impl Page {
                     data[start..end].copy_from_slice(&overflow_data);
                 }
 
-                let slot_index = self.slot_directory.slots.len();
-                self.slot_directory.slots.push(SlotEntry::new_overflow(
-                    new_offset,
-                    overflow_data.len() as u16,
-                    row_id,
-                    overflow_ptr,
-                ));
+                // The pointer does not carry the row's key, so the page can no longer be
+                // binary searched, and any deleted slot may take the cell
+                self.sorted = false;
+                let slot_index = self.place_slot(
+                    self.slot_directory.slots.len(),
+                    reused,
+                    SlotEntry::new_overflow(new_offset as u16, overflow_data.len() as u16, row_id, overflow_ptr),
+                );
+                self.mark_dirty_extent(start..end);
 
                 self.free_space_offset = new_offset;
                 self.cell_count = self.slot_directory.slots.len() as u16; // FIX: Keep in sync
@@ -419,21 +796,48 @@ impl Page {
 
     pub fn available_space(&self) -> usize {
         let slot_directory_size = self.slot_directory.slots.len() * SLOT_DIRECTORY_ENTRY_SIZE;
-        let used_data_space = (PAGE_SIZE as u16 - self.free_space_offset) as usize;
-        PAGE_SIZE.saturating_sub(PAGE_HEADER_SIZE + slot_directory_size + used_data_space)
+        let used_data_space = self.page_size - self.free_space_offset as usize;
+        self.page_size.saturating_sub(PAGE_HEADER_SIZE + slot_directory_size + used_data_space)
     }
 
+    /// Whether a cell of `data_size` bytes fits with a slot entry of its own. An insert
+    /// that takes over a deleted slot may fit when this says it does not.
     pub fn can_fit(&self, data_size: usize) -> bool {
+        self.fits(data_size, true)
+    }
+
+    fn fits(&self, data_size: usize, new_slot: bool) -> bool {
         // What will the total space usage be after this insertion?
-        let new_slot_count = self.slot_directory.slots.len() + 1;
+        let new_slot_count = self.slot_directory.slots.len() + usize::from(new_slot);
         let new_slot_directory_size = new_slot_count * SLOT_DIRECTORY_ENTRY_SIZE;
-        let new_used_data_space = (PAGE_SIZE as u16 - self.free_space_offset) as usize + data_size;
+        let new_used_data_space = self.page_size - self.free_space_offset as usize + data_size;
         let total_used_after_insert =
             PAGE_HEADER_SIZE + new_slot_directory_size + new_used_data_space;
 
-        let fits = total_used_after_insert <= PAGE_SIZE;
+        total_used_after_insert <= self.page_size
+    }
+
+    /// Whether a cell of the given cost can be inserted without splitting the page. A cell
+    /// that leaves zero bytes free fits.
+    pub fn would_fit(&self, cost: &StorageCost) -> bool {
+        self.can_fit(cost.inline_bytes)
+    }
+
+    /// Whether the cell would fit once `compact` gives back the space of deleted cells
+    pub fn fits_after_compaction(&self, cost: &StorageCost) -> bool {
+        self.available_space() + self.reclaimable_space() >= cost.page_bytes()
+    }
 
-        fits
+    /// Cell-area bytes no live cell uses, which `compact` would free
+    pub fn reclaimable_space(&self) -> usize {
+        let live_bytes: usize = self
+            .slot_directory
+            .slots
+            .iter()
+            .filter(|slot| !slot.is_deleted())
+            .map(|slot| slot.length as usize)
+            .sum();
+        (self.page_size - self.free_space_offset as usize).saturating_sub(live_bytes)
     }
 
     pub fn insert_cell(
@@ -441,19 +845,35 @@ impl Page {
         data: &[u8],
         row_id: Option<RowId>,
     ) -> Result<usize, DatabaseError> {
-        if self.is_metadata_only() {
-            return Err(DatabaseError::SerializationError {
-                details: "Insertion requires full page".to_string(),
+        self.insert_cell_at(self.slot_directory.slots.len(), data, row_id)
+    }
+
+    /// Insert a cell whose slot entry goes at `slot_index`, so a page kept in key order
+    /// stays that way. The nearest deleted slot is taken over, moving the entries between
+    /// it and `slot_index` along by one, and the directory only grows when there is none.
+    /// Returns the slot the cell ends up in, which is `slot_index` or the one before it.
+    pub fn insert_cell_at(
+        &mut self,
+        slot_index: usize,
+        data: &[u8],
+        row_id: Option<RowId>,
+    ) -> Result<usize, DatabaseError> {
+        self.require_full_data("insertion")?;
+
+        if slot_index > self.slot_directory.slots.len() {
+            return Err(DatabaseError::InvalidSlotIndex {
+                index: slot_index,
+                max: self.slot_directory.slots.len(),
             });
         }
-
-        if !self.can_fit(data.len()) {
+        let reused = self.nearest_deleted_slot(slot_index);
+        if !self.fits(data.len(), reused.is_none()) {
             return Err(DatabaseError::PageFull {
                 page_id: self.page_id,
             });
         }
 
-        let new_offset = self.free_space_offset - data.len() as u16;
+        let new_offset = self.free_space_offset - data.len() as u32;
 
         if let Some(ref mut page_data) = self.data {
             let start = new_offset as usize;
@@ -461,12 +881,13 @@ impl Page {
             page_data[start..end].copy_from_slice(data);
         }
 
-        let slot_index = self.slot_directory.slots.len();
-        self.slot_directory.slots.push(SlotEntry::new_regular(
-            new_offset,
-            data.len() as u16,
-            row_id,
-        ));
+        let slot_index = self.place_slot(
+            slot_index,
+            reused,
+            SlotEntry::new_regular(new_offset as u16, data.len() as u16, row_id),
+        );
+        let start = new_offset as usize;
+        self.mark_dirty_extent(start..start + data.len());
 
         self.free_space_offset = new_offset;
         self.cell_count = self.slot_directory.slots.len() as u16; // FIX: Keep in sync
@@ -476,14 +897,54 @@ impl Page {
         Ok(slot_index)
     }
 
+    /// Deleted slot closest to where an entry inserted at `slot_index` would go, measured
+    /// in entries that would have to move to close the gap
+    fn nearest_deleted_slot(&self, slot_index: usize) -> Option<usize> {
+        let slots = &self.slot_directory.slots;
+        let before = (0..slot_index).rev().find(|&index| slots[index].is_deleted());
+        let after = (slot_index..slots.len()).find(|&index| slots[index].is_deleted());
+        match (before, after) {
+            (Some(before), Some(after)) if after - slot_index < slot_index - 1 - before => Some(after),
+            (before, after) => before.or(after),
+        }
+    }
+
+    /// Put `entry` in the directory at `slot_index`, filling the deleted slot `reused` if
+    /// given. On a page kept in key order the entries between the two move along by one
+    /// so the live cells keep their order; otherwise the entry simply replaces the
+    /// deleted one. Returns the entry's slot.
+    fn place_slot(&mut self, slot_index: usize, reused: Option<usize>, entry: SlotEntry) -> usize {
+        let slots = &mut self.slot_directory.slots;
+        let (placed, moved) = match reused {
+            None => {
+                slots.insert(slot_index, entry);
+                (slot_index, slot_index..slots.len())
+            }
+            Some(hole) if !self.sorted => {
+                slots[hole] = entry;
+                (hole, hole..hole + 1)
+            }
+            Some(hole) if hole < slot_index => {
+                slots[hole..slot_index].rotate_left(1);
+                slots[slot_index - 1] = entry;
+                (slot_index - 1, hole..slot_index)
+            }
+            Some(hole) => {
+                slots[slot_index..=hole].rotate_right(1);
+                slots[slot_index] = entry;
+                (slot_index, slot_index..hole + 1)
+            }
+        };
+        for slot in moved {
+            self.mark_slot_entry_dirty(slot);
+        }
+        placed
+    }
+
     /// Delete a cell at the specified slot index
     /// This marks the slot as deleted but doesn't immediately reclaim space
     pub fn delete_cell(&mut self, slot_index: usize) -> Result<(), DatabaseError> {
-        if self.is_metadata_only() {
-            return Err(DatabaseError::SerializationError {
-                details: "Deletion requires full page".to_string(),
-            });
-        }
+        self.require_full_data("deletion")?;
 
         if slot_index >= self.slot_directory.slots.len() {
             return Err(DatabaseError::InvalidSlotIndex {
@@ -507,16 +968,17 @@ impl Page {
         self.slot_directory.slots[slot_index].row_id = None;
 
         // FIX: Clean up overflow information
-        if self.slot_directory.slots[slot_index].is_overflow {
-            if let Some(overflow_ptr) = &self.slot_directory.slots[slot_index].overflow_pointer {
-                // Remove from overflow_pages list
-                self.overflow_pages
-                    .retain(|&page_id| page_id != overflow_ptr.page_id);
-            }
+        if self.slot_directory.slots[slot_index].is_overflow
+            && let Some(overflow_ptr) = &self.slot_directory.slots[slot_index].overflow_pointer
+        {
+            // Remove from overflow_pages list
+            self.overflow_pages
+                .retain(|&page_id| page_id != overflow_ptr.page_id);
         }
 
         self.slot_directory.slots[slot_index].is_overflow = false;
         self.slot_directory.slots[slot_index].overflow_pointer = None;
+        self.mark_slot_entry_dirty(slot_index);
 
         self.is_dirty = true;
         self.update_checksum();
@@ -535,11 +997,7 @@ impl Page {
         new_data: &[u8],
         row_id: Option<RowId>,
     ) -> Result<(), DatabaseError> {
-        if self.is_metadata_only() {
-            return Err(DatabaseError::SerializationError {
-                details: "Update requires full page".to_string(),
-            });
-        }
+        self.require_full_data("update")?;
 
         if slot_index >= self.slot_directory.slots.len() {
             return Err(DatabaseError::InvalidSlotIndex {
@@ -563,8 +1021,8 @@ impl Page {
 
         // Case 1: New data fits exactly in the same space
         if new_length == old_length {
+            let start = slot.offset as usize;
             if let Some(ref mut page_data) = self.data {
-                let start = slot.offset as usize;
                 let end = start + new_length;
                 // FIX: Add bounds checking
                 if end <= page_data.len() {
@@ -578,6 +1036,7 @@ impl Page {
             }
 
             self.slot_directory.slots[slot_index].row_id = row_id;
+            self.mark_dirty_extent(start..start + new_length);
             self.is_dirty = true;
             self.update_checksum();
             return Ok(());
@@ -585,8 +1044,8 @@ impl Page {
 
         // Case 2: New data is smaller - we can update in place but will create fragmentation
         if new_length < old_length {
+            let start = slot.offset as usize;
             if let Some(ref mut page_data) = self.data {
-                let start = slot.offset as usize;
                 // FIX: Add bounds checking
                 if start + old_length <= page_data.len() {
                     page_data[start..start + new_length].copy_from_slice(new_data);
@@ -602,6 +1061,8 @@ impl Page {
 
             self.slot_directory.slots[slot_index].length = new_length as u16;
             self.slot_directory.slots[slot_index].row_id = row_id;
+            self.mark_slot_entry_dirty(slot_index);
+            self.mark_dirty_extent(start..start + old_length);
             self.is_dirty = true;
             self.update_checksum();
 
@@ -613,10 +1074,10 @@ impl Page {
         }
 
         // Case 3: New data is larger - need to relocate or compact
-        // First, try to see if we have enough free space after compaction
-        let current_free_space = self.available_space();
+        // First, try to see if we have enough free space after compaction, which also
+        // reclaims the bytes of deleted cells
+        let current_free_space = self.available_space() + self.reclaimable_space();
         let space_gained_from_deletion = old_length;
-        let net_space_needed = new_length.saturating_sub(old_length);
 
         if current_free_space + space_gained_from_deletion < new_length {
             return Err(DatabaseError::PageFull {
@@ -630,7 +1091,7 @@ impl Page {
         self.compact()?;
 
         // Now insert the new data in the freed space
-        let new_offset = self.free_space_offset - new_data.len() as u16;
+        let new_offset = self.free_space_offset - new_data.len() as u32;
 
         if let Some(ref mut page_data) = self.data {
             let start = new_offset as usize;
@@ -640,7 +1101,7 @@ impl Page {
 
         // Update the slot entry
         self.slot_directory.slots[slot_index] =
-            SlotEntry::new_regular(new_offset, new_data.len() as u16, row_id);
+            SlotEntry::new_regular(new_offset as u16, new_data.len() as u16, row_id);
 
         self.free_space_offset = new_offset;
         self.is_dirty = true;
@@ -652,15 +1113,20 @@ impl Page {
     /// Compact the page to eliminate fragmentation
     /// This moves all active cells to the end of the page, removing gaps
     pub fn compact(&mut self) -> Result<(), DatabaseError> {
-        if self.is_metadata_only() {
-            return Err(DatabaseError::SerializationError {
-                details: "Compaction requires full page".to_string(),
-            });
-        }
+        self.compact_with(false)
+    }
+
+    /// Compact the page, also dropping the deleted slots after the last live cell when
+    /// `truncate_trailing_slots` is set. No live cell changes slot either way, but a
+    /// caller still holding the index of one of the dropped slots must not pass it in.
+    pub fn compact_with(&mut self, truncate_trailing_slots: bool) -> Result<(), DatabaseError> {
+        self.require_full_data("compaction")?;
 
+        let page_id = self.page_id;
         let Some(ref mut page_data) = self.data else {
-            return Err(DatabaseError::SerializationError {
-                details: "Compaction requires full page".to_string(),
+            return Err(DatabaseError::MetadataOnlyPage {
+                page_id,
+                operation: "compaction".to_string(),
             });
         };
 
@@ -689,11 +1155,11 @@ impl Page {
         }
 
         // Rewrite cells from the end of the page backwards
-        let mut new_free_space_offset = PAGE_SIZE as u16;
+        let mut new_free_space_offset = self.page_size as u32;
 
         for (slot_index, cell_data, mut slot_entry) in active_cells.into_iter().rev() {
             let cell_size = cell_data.len();
-            new_free_space_offset -= cell_size as u16;
+            new_free_space_offset -= cell_size as u32;
 
             let start = new_free_space_offset as usize;
             let end = start + cell_size;
@@ -704,7 +1170,7 @@ impl Page {
             }
 
             // Update the slot entry with new offset
-            slot_entry.offset = new_free_space_offset;
+            slot_entry.offset = new_free_space_offset as u16;
             self.slot_directory.slots[slot_index] = slot_entry;
         }
 
@@ -712,14 +1178,98 @@ impl Page {
         // This maintains slot index stability
         // self.slot_directory.slots.retain(|slot| !slot.is_deleted());
         // self.cell_count = self.slot_directory.slots.len() as u16;
+        if truncate_trailing_slots {
+            let live_len = self
+                .slot_directory
+                .slots
+                .iter()
+                .rposition(|slot| !slot.is_deleted())
+                .map_or(0, |last_live| last_live + 1);
+            self.slot_directory.slots.truncate(live_len);
+            self.cell_count = live_len as u16;
+        }
 
         self.free_space_offset = new_free_space_offset;
+        self.mark_fully_dirty();
         self.is_dirty = true;
         self.update_checksum();
 
         Ok(())
     }
 
+    /// Locate `key` among the live cells of a page kept in key order, reading the key of
+    /// each probed cell with `key_extractor`. Only O(log n) cells are parsed, give or take
+    /// runs of deleted slots. Pages that are not `sorted` are refused; they have to be
+    /// searched slot by slot.
+    pub fn binary_search_key<F>(&self, key: &Value, key_extractor: F) -> Result<SlotPos, DatabaseError>
+    where
+        F: Fn(&[u8]) -> Result<Value, DatabaseError>,
+    {
+        self.require_full_data("binary search")?;
+        if !self.sorted {
+            return Err(DatabaseError::InternalInvariant {
+                details: format!("page {} is not in key order and cannot be binary searched", self.page_id),
+            });
+        }
+        let start = self.partition_slots(0, key, &key_extractor, Ordering::is_lt)?;
+        let end = self.partition_slots(start, key, &key_extractor, Ordering::is_le)?;
+        Ok(SlotPos { start, end })
+    }
+
+    /// First live slot from `low` on whose key, compared to `key`, does not satisfy
+    /// `before`, or the directory length if there is none
+    fn partition_slots<F>(
+        &self,
+        mut low: usize,
+        key: &Value,
+        key_extractor: &F,
+        before: fn(Ordering) -> bool,
+    ) -> Result<usize, DatabaseError>
+    where
+        F: Fn(&[u8]) -> Result<Value, DatabaseError>,
+    {
+        let slots = &self.slot_directory.slots;
+        let live = |slot_index: &usize| slots[*slot_index].length > 0;
+        let mut high = slots.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            // A deleted slot has no key, so the nearest live one is probed instead
+            let Some(probe) = (mid..high).find(live).or_else(|| (low..mid).rev().find(live)) else {
+                break;
+            };
+            let cell = self.get_cell(probe).ok_or_else(|| DatabaseError::CorruptedPage {
+                page_id: self.page_id,
+                reason: format!("Slot {} lies outside the page", probe),
+            })?;
+            if before(self.compare_keys(&key_extractor(cell)?, key)) {
+                low = probe + 1;
+            } else {
+                high = probe;
+            }
+        }
+        Ok((low..slots.len()).find(live).unwrap_or(slots.len()))
+    }
+
+    /// Order of cell keys on this page: `Value::total_cmp`, or `compare_upper_bounds` on
+    /// interior pages
+    pub fn compare_keys(&self, a: &Value, b: &Value) -> Ordering {
+        match self.page_type {
+            PageType::InteriorTable | PageType::InteriorIndex => Self::compare_upper_bounds(a, b),
+            _ => a.total_cmp(b),
+        }
+    }
+
+    /// Order of interior page upper bounds, where NULL marks the unbounded rightmost child
+    /// and sorts after every other bound
+    pub fn compare_upper_bounds(a: &Value, b: &Value) -> Ordering {
+        match (a.is_null(), b.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => a.total_cmp(b),
+        }
+    }
+
     /// Check if a slot is deleted (has zero length)
     pub fn is_slot_deleted(&self, slot_index: usize) -> bool {
         self.slot_directory
@@ -752,26 +1302,8 @@ impl Page {
             .map(|slot| slot.length as usize)
             .sum();
 
-        // Calculate wasted space: space used by deleted cells
-        let deleted_cell_data_size: usize = self
-            .slot_directory
-            .slots
-            .iter()
-            .filter(|slot| slot.is_deleted())
-            .map(|slot| {
-                // For deleted slots, we need to estimate the space they previously occupied
-                // Since we zero out the length on deletion, we'll use a heuristic
-                // In a real implementation, we'd track this better
-                if slot.offset > 0 {
-                    // Estimate based on typical cell size or use a minimum
-                    100 // Assume deleted cells were around 100 bytes
-                } else {
-                    0
-                }
-            })
-            .sum();
-
-        let total_used_space = (PAGE_SIZE as u16 - self.free_space_offset) as usize;
+        // Wasted space is whatever the data area holds beyond live cells
+        let total_used_space = self.page_size - self.free_space_offset as usize;
         let wasted_space = total_used_space.saturating_sub(active_cell_data_size);
 
         PageStats {
@@ -780,7 +1312,7 @@ impl Page {
             active_slots,
             deleted_slots,
             free_space: self.available_space(),
-            used_space: PAGE_SIZE - self.available_space(),
+            used_space: self.page_size - self.available_space(),
             wasted_space,
             fragmentation_ratio: self.get_fragmentation_ratio(),
             utilization_ratio: self.get_utilization_ratio(),
@@ -800,7 +1332,7 @@ impl Page {
             .map(|slot| slot.length as usize)
             .sum();
 
-        let used_space = PAGE_SIZE - self.free_space_offset as usize;
+        let used_space = self.page_size - self.free_space_offset as usize;
 
         if used_space == 0 || active_data_size == 0 {
             0.0
@@ -812,26 +1344,13 @@ impl Page {
     }
 
     pub fn get_utilization_ratio(&self) -> f32 {
-        let used_space = PAGE_SIZE - self.free_space_offset as usize;
-        let available_space = PAGE_SIZE - PAGE_HEADER_SIZE;
+        let used_space = self.page_size - self.free_space_offset as usize;
+        let available_space = self.page_size - PAGE_HEADER_SIZE;
         used_space as f32 / available_space as f32
     }
 
     // Helper methods
-    fn read_header(
-        bytes: &[u8],
-    ) -> Result<
-        (
-            PageId,
-            PageType,
-            Option<PageId>,
-            Option<PageId>,
-            u16,
-            u16,
-            u32,
-        ),
-        DatabaseError,
-    > {
+    fn read_header(bytes: &[u8]) -> Result<PageHeaderFields, DatabaseError> {
         let mut offset = 0;
 
         let page_id = u64::from_le_bytes([
@@ -895,15 +1414,33 @@ impl Page {
             bytes[offset + 2],
             bytes[offset + 3],
         ]);
+        offset += 4;
+
+        let mut prev_leaf_id_raw = [0u8; 8];
+        prev_leaf_id_raw.copy_from_slice(&bytes[offset..offset + 8]);
+        let prev_leaf_page_id = match u64::from_le_bytes(prev_leaf_id_raw) {
+            u64::MAX => None,
+            prev_leaf_id => Some(prev_leaf_id),
+        };
+
+        let flags = bytes[Self::FLAGS_OFFSET];
+        if flags & !Self::FLAG_SORTED != 0 {
+            return Err(DatabaseError::CorruptedPage {
+                page_id,
+                reason: format!("Unknown page flags {:#04x}", flags),
+            });
+        }
 
         Ok((
             page_id,
             page_type,
             parent_page_id,
             next_leaf_page_id,
+            prev_leaf_page_id,
             cell_count,
             free_space_offset,
             checksum,
+            flags,
         ))
     }
 
@@ -911,6 +1448,7 @@ impl Page {
         bytes: &[u8],
         cell_count: u16,
         page_id: PageId,
+        page_size: usize,
     ) -> Result<Vec<SlotEntry>, DatabaseError> {
         let mut slots = Vec::with_capacity(cell_count as usize);
         let mut offset = 0;
@@ -923,43 +1461,49 @@ impl Page {
                 });
             }
 
-            let slot_offset = u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
-            offset += 2;
-            let length = u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
-            offset += 2;
+            let entry: &[u8; SLOT_DIRECTORY_ENTRY_SIZE] = bytes[offset..offset + SLOT_DIRECTORY_ENTRY_SIZE]
+                .try_into()
+                .expect("slice has the entry size");
+            offset += SLOT_DIRECTORY_ENTRY_SIZE;
+            let slot = SlotEntry::from_bytes(entry).ok_or_else(|| DatabaseError::CorruptedPage {
+                page_id,
+                reason: format!("Slot {} has unknown flags {:#04x}", slots.len(), entry[4]),
+            })?;
 
             // FIX: Only validate non-deleted slots
-            if length > 0 && slot_offset as usize + length as usize > PAGE_SIZE {
+            if slot.length > 0 && slot.offset as usize + slot.length as usize > page_size {
                 return Err(DatabaseError::CorruptedPage {
                     page_id,
                     reason: format!(
                         "Slot at offset {} with length {} exceeds page boundary",
-                        slot_offset, length
+                        slot.offset, slot.length
+                    ),
+                });
+            }
+            if slot.is_overflow && slot.length as usize != OverflowPointer::SERIALIZED_SIZE {
+                return Err(DatabaseError::CorruptedPage {
+                    page_id,
+                    reason: format!(
+                        "Overflow slot {} is {} bytes, not an overflow pointer",
+                        slots.len(),
+                        slot.length
                     ),
                 });
             }
 
-            // Detect overflow slots based on size
-            let is_overflow = length as usize == OverflowPointer::SERIALIZED_SIZE;
-
-            slots.push(SlotEntry {
-                offset: slot_offset,
-                length,
-                row_id: None,
-                is_overflow,
-                overflow_pointer: None, // Will be parsed when data is loaded
-            });
+            slots.push(slot);
         }
 
         Ok(slots)
     }
 
-    // Keep existing from_bytes for backward compatibility
+    /// Decode a full page; its size is the length of `bytes`
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DatabaseError> {
-        if bytes.len() != PAGE_SIZE {
+        let page_size = bytes.len();
+        if validate_page_size(page_size).is_err() {
             return Err(DatabaseError::InvalidPageSize {
                 expected: PAGE_SIZE,
-                actual: bytes.len(),
+                actual: page_size,
             });
         }
 
@@ -968,35 +1512,36 @@ impl Page {
             page_type,
             parent_page_id,
             next_leaf_page_id,
+            prev_leaf_page_id,
             cell_count,
             free_space_offset,
             stored_checksum,
+            flags,
         ) = Self::read_header(&bytes[..PAGE_HEADER_SIZE])?;
 
-        if free_space_offset as usize > PAGE_SIZE {
-            return Err(DatabaseError::CorruptedPage {
-                page_id,
-                reason: format!("Invalid free_space_offset: {}", free_space_offset),
-            });
-        }
-
-        let slots = Self::read_slot_directory(&bytes[PAGE_HEADER_SIZE..], cell_count, page_id)?;
+        let free_space_offset = checked_free_space_offset(free_space_offset, page_id, page_size)?;
+        let slots = Self::read_slot_directory(&bytes[PAGE_HEADER_SIZE..], cell_count, page_id, page_size)?;
 
-        let mut data = Vec::with_capacity(PAGE_SIZE);
+        let mut data = Vec::with_capacity(page_size);
         data.extend_from_slice(bytes);
 
-        let page = Page {
+        let mut page = Page {
             page_id,
             page_type,
             parent_page_id,
             next_leaf_page_id,
+            prev_leaf_page_id,
+            sorted: flags & Self::FLAG_SORTED != 0,
             is_dirty: false,
+            page_size,
             slot_directory: SlotDirectory { slots },
             free_space_offset,
             cell_count,
             data: Some(data),
             checksum: stored_checksum,
             overflow_pages: Vec::new(),
+            reconciliation_warnings: Vec::new(),
+            dirty_extents: Some(Vec::new()),
         };
 
         if !page.verify_checksum() {
@@ -1006,19 +1551,182 @@ impl Page {
             });
         }
 
+        page.recover_uncounted_slots(bytes);
+        page.reconcile_free_space_offset();
+        page.validate_structure()?;
+        page.load_overflow_pointers()?;
+        if page.is_dirty {
+            page.mark_fully_dirty();
+            page.update_checksum();
+        }
         Ok(page)
     }
 
+    /// Checks run after every mutation in paranoid mode: the slot layout passes
+    /// `validate_structure`, `cell_count` matches the slot directory, and the stored
+    /// checksum matches the page's contents
+    pub fn validate_invariants(&self) -> Result<(), DatabaseError> {
+        let violated = |details: String| DatabaseError::InternalInvariant {
+            details: format!("page {} ({:?}): {}", self.page_id, self.page_type, details),
+        };
+        if let Err(error) = self.validate_structure() {
+            let details = match error {
+                DatabaseError::CorruptedPage { reason, .. } => reason,
+                other => other.to_string(),
+            };
+            return Err(violated(details));
+        }
+        if self.cell_count as usize != self.slot_directory.slots.len() {
+            return Err(violated(format!(
+                "cell_count {} but {} slot entries",
+                self.cell_count,
+                self.slot_directory.slots.len()
+            )));
+        }
+        if self.data.is_some() && !self.verify_checksum() {
+            return Err(violated(format!("stored checksum {:#010x} is stale", self.checksum)));
+        }
+        Ok(())
+    }
+
+    /// Check that the slot directory describes a consistent cell area: the directory ends
+    /// before `free_space_offset`, every live cell lies within
+    /// `[free_space_offset, page_size)`, and no two live cells overlap, so together they
+    /// fit in the used space. The used space may exceed the live bytes, since deleted
+    /// and shrunk cells leave gaps until the page is compacted. A page that passes its
+    /// checksum can still fail here if a write was torn before the checksum was updated
+    /// for it, e.g. one whose slot entries were written but not its header.
+    pub fn validate_structure(&self) -> Result<(), DatabaseError> {
+        let corrupted = |reason: String| DatabaseError::CorruptedPage {
+            page_id: self.page_id,
+            reason,
+        };
+        let free_space_offset = self.free_space_offset as usize;
+        let directory_end = PAGE_HEADER_SIZE + self.slot_directory.slots.len() * SLOT_DIRECTORY_ENTRY_SIZE;
+        if directory_end > free_space_offset {
+            return Err(corrupted(format!(
+                "Slot directory ends at {}, past free_space_offset {}",
+                directory_end, free_space_offset
+            )));
+        }
+
+        let mut cells: Vec<(usize, usize, usize)> = Vec::new();
+        for (slot_index, slot) in self.slot_directory.slots.iter().enumerate() {
+            if slot.length == 0 {
+                continue;
+            }
+            let (start, end) = (slot.offset as usize, slot.offset as usize + slot.length as usize);
+            if start < free_space_offset || end > self.page_size {
+                return Err(corrupted(format!(
+                    "Slot {} spans {}..{}, outside the cell area {}..{}",
+                    slot_index, start, end, free_space_offset, self.page_size
+                )));
+            }
+            cells.push((start, end, slot_index));
+        }
+
+        cells.sort_unstable();
+        for pair in cells.windows(2) {
+            let ((_, end, first), (start, _, second)) = (pair[0], pair[1]);
+            if start < end {
+                return Err(corrupted(format!("Slots {} and {} overlap", first, second)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adopt slot entries written past `cell_count`. A header whose count fell behind its
+    /// slot directory would otherwise hide those cells and let new inserts reuse the slots.
+    fn recover_uncounted_slots(&mut self, bytes: &[u8]) {
+        let directory_end = PAGE_HEADER_SIZE + self.slot_directory.slots.len() * SLOT_DIRECTORY_ENTRY_SIZE;
+        let cell_area_start = self
+            .lowest_live_cell_offset()
+            .map_or(self.free_space_offset as usize, |lowest| {
+                lowest.min(self.free_space_offset as usize)
+            })
+            .max(directory_end);
+
+        // The gap between the directory and the cell area is zeroed when a page is written
+        let entries: Vec<&[u8; SLOT_DIRECTORY_ENTRY_SIZE]> = bytes[directory_end..cell_area_start]
+            .chunks_exact(SLOT_DIRECTORY_ENTRY_SIZE)
+            .map(|entry| entry.try_into().expect("chunk has the entry size"))
+            .collect();
+        let Some(last) = entries.iter().rposition(|entry| entry.iter().any(|&byte| byte != 0)) else {
+            return;
+        };
+        let Some(entries) = entries[..=last]
+            .iter()
+            .map(|entry| SlotEntry::from_bytes(entry))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return;
+        };
+        let total_slots = self.slot_directory.slots.len() + entries.len();
+        let new_directory_end = PAGE_HEADER_SIZE + total_slots * SLOT_DIRECTORY_ENTRY_SIZE;
+        let plausible = entries.iter().all(|slot| {
+            slot.to_bytes() == [0; SLOT_DIRECTORY_ENTRY_SIZE]
+                || (slot.length > 0
+                    && slot.offset as usize >= new_directory_end
+                    && slot.offset as usize + slot.length as usize <= self.page_size
+                    && (!slot.is_overflow || slot.length as usize == OverflowPointer::SERIALIZED_SIZE))
+        });
+        if !plausible {
+            return;
+        }
+
+        self.slot_directory.slots.extend(entries);
+        // Nothing says the adopted cells came in key order
+        self.sorted = false;
+        self.reconciliation_warnings.push(format!(
+            "cell_count {} disagreed with a slot directory of {} entries",
+            self.cell_count, total_slots
+        ));
+        self.cell_count = total_slots as u16;
+        self.is_dirty = true;
+    }
+
+    /// Decode the pointer held in each overflow cell and record the page it leads to
+    fn load_overflow_pointers(&mut self) -> Result<(), DatabaseError> {
+        let Some(data) = &self.data else {
+            return Ok(());
+        };
+        for slot in self.slot_directory.slots.iter_mut().filter(|slot| slot.is_overflow) {
+            let start = slot.offset as usize;
+            let pointer = OverflowPointer::from_bytes(&data[start..start + slot.length as usize])?;
+            self.overflow_pages.push(pointer.page_id);
+            slot.overflow_pointer = Some(pointer);
+        }
+        Ok(())
+    }
+
+    /// Clamp a free_space_offset that claims free space already occupied by live cells
+    fn reconcile_free_space_offset(&mut self) {
+        if let Some(lowest) = self.lowest_live_cell_offset()
+            && (self.free_space_offset as usize) > lowest
+        {
+            self.reconciliation_warnings.push(format!(
+                "free_space_offset {} overlapped live cells starting at {}",
+                self.free_space_offset, lowest
+            ));
+            self.free_space_offset = lowest as u32;
+            self.is_dirty = true;
+        }
+    }
+
+    fn lowest_live_cell_offset(&self) -> Option<usize> {
+        self.slot_directory
+            .slots
+            .iter()
+            .filter(|slot| slot.length > 0)
+            .map(|slot| slot.offset as usize)
+            .min()
+    }
+
     /// Serialize the page to bytes (only works in full data mode)
     pub fn to_bytes(&self) -> Result<Vec<u8>, DatabaseError> {
-        if self.is_metadata_only() {
-            return Err(DatabaseError::SerializationError {
-                details: "Serialization requires full page".to_string(),
-            });
-        }
+        self.require_full_data("serialization")?;
 
-        let mut buffer = Vec::with_capacity(PAGE_SIZE);
-        buffer.resize(PAGE_SIZE, 0);
+        let mut buffer = vec![0; self.page_size];
 
         let mut cursor = Cursor::new(&mut buffer);
         self.write_header(&mut cursor);
@@ -1026,17 +1734,15 @@ impl Page {
         // Write SLOT DIRECTORY
         let mut offset = PAGE_HEADER_SIZE;
         for slot in &self.slot_directory.slots {
-            buffer[offset..offset + 2].copy_from_slice(&slot.offset.to_le_bytes());
-            offset += 2;
-            buffer[offset..offset + 2].copy_from_slice(&slot.length.to_le_bytes());
-            offset += 2;
+            buffer[offset..offset + SLOT_DIRECTORY_ENTRY_SIZE].copy_from_slice(&slot.to_bytes());
+            offset += SLOT_DIRECTORY_ENTRY_SIZE;
         }
 
         // Copy CELL DATA
         if let Some(ref data) = self.data {
             let data_start = self.free_space_offset as usize;
-            if data_start < PAGE_SIZE && data_start < data.len() {
-                let copy_len = std::cmp::min(PAGE_SIZE - data_start, data.len() - data_start);
+            if data_start < self.page_size && data_start < data.len() {
+                let copy_len = std::cmp::min(self.page_size - data_start, data.len() - data_start);
                 buffer[data_start..data_start + copy_len]
                     .copy_from_slice(&data[data_start..data_start + copy_len]);
             }
@@ -1066,9 +1772,42 @@ impl Page {
         buffer[offset..offset + 2].copy_from_slice(&self.cell_count.to_le_bytes());
         offset += 2;
 
-        buffer[offset..offset + 2].copy_from_slice(&self.free_space_offset.to_le_bytes());
+        // Truncates MAX_PAGE_SIZE to 0; decode_free_space_offset reverses it
+        buffer[offset..offset + 2].copy_from_slice(&(self.free_space_offset as u16).to_le_bytes());
         offset += 2;
 
         buffer[offset..offset + 4].copy_from_slice(&self.checksum.to_le_bytes());
+        offset += 4;
+
+        let prev_leaf_id = self.prev_leaf_page_id.unwrap_or(u64::MAX);
+        buffer[offset..offset + 8].copy_from_slice(&prev_leaf_id.to_le_bytes());
+
+        buffer[Self::FLAGS_OFFSET] = self.flags();
+    }
+
+    /// The header's flags byte
+    fn flags(&self) -> u8 {
+        if self.sorted { Self::FLAG_SORTED } else { 0 }
+    }
+}
+
+/// `decode_free_space_offset`, rejecting offsets past the end of the page
+fn checked_free_space_offset(raw: u16, page_id: PageId, page_size: usize) -> Result<u32, DatabaseError> {
+    let free_space_offset = decode_free_space_offset(raw, page_size);
+    if free_space_offset as usize > page_size {
+        return Err(DatabaseError::CorruptedPage {
+            page_id,
+            reason: format!("Invalid free_space_offset: {}", free_space_offset),
+        });
+    }
+    Ok(free_space_offset)
+}
+
+/// The in-memory free_space_offset for one read from a page header
+fn decode_free_space_offset(raw: u16, page_size: usize) -> u32 {
+    if raw == 0 && page_size > u16::MAX as usize {
+        page_size as u32
+    } else {
+        raw as u32
     }
 }
@@ -5,13 +5,22 @@ use crate::types::{RowId, error::DatabaseError, value::Value};
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Row {
     pub row_id: Option<RowId>,
+    /// Bumped by every update, so a writer can detect that the row changed since it was
+    /// read. Rows start at version 0, which is not stored.
+    #[serde(default)]
+    pub version: u64,
     pub values: Vec<Value>,
 }
 
+/// Flag bits in a row's first byte
+const HAS_ROW_ID: u8 = 0x01;
+const HAS_VERSION: u8 = 0x02;
+
 impl Row {
     pub fn new(values: Vec<Value>) -> Self {
         Self {
             row_id: None,
+            version: 0,
             values,
         }
     }
@@ -19,10 +28,24 @@ impl Row {
     pub fn with_row_id(row_id: RowId, values: Vec<Value>) -> Self {
         Self {
             row_id: Some(row_id),
+            version: 0,
             values,
         }
     }
 
+    /// The B+ tree key of this row: its first value, or for a table with a composite
+    /// PRIMARY KEY the `Value::composite_key` of the values at `key_columns`
+    pub fn key(&self, key_columns: &[usize]) -> Value {
+        if key_columns.is_empty() {
+            return self.values.first().cloned().unwrap_or(Value::Null);
+        }
+        Value::composite_key(
+            key_columns
+                .iter()
+                .map(|position| self.values.get(*position).unwrap_or(&Value::Null)),
+        )
+    }
+
     pub fn get_value(&self, column_index: usize) -> Option<&Value> {
         self.values.get(column_index)
     }
@@ -38,11 +61,14 @@ impl Row {
     }
 
     pub fn size(&self) -> usize {
-        let mut size = 1; // has_row_id flag
+        let mut size = 1; // flags
 
         if self.row_id.is_some() {
             size += 8; // row_id (8 bytes for u64/i64)
         }
+        if self.version > 0 {
+            size += 8; // version
+        }
 
         size += 4; // value_count (4 bytes for u32)
 
@@ -57,15 +83,20 @@ impl Row {
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
 
-        // Row ID presence and value
-        match self.row_id {
-            Some(id) => {
-                buffer.push(1); // Has row ID
-                buffer.extend_from_slice(&id.to_le_bytes());
-            }
-            None => {
-                buffer.push(0); // No row ID
-            }
+        // Flags, then the row ID and version if present
+        let mut flags = 0;
+        if self.row_id.is_some() {
+            flags |= HAS_ROW_ID;
+        }
+        if self.version > 0 {
+            flags |= HAS_VERSION;
+        }
+        buffer.push(flags);
+        if let Some(id) = self.row_id {
+            buffer.extend_from_slice(&id.to_le_bytes());
+        }
+        if self.version > 0 {
+            buffer.extend_from_slice(&self.version.to_le_bytes());
         }
 
         // Value count
@@ -81,38 +112,82 @@ impl Row {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DatabaseError> {
+        Self::from_bytes_prefix(bytes, usize::MAX)
+    }
+
+    /// Decode at most the first `max_values` values, leaving trailing columns unread
+    pub fn from_bytes_prefix(bytes: &[u8], max_values: usize) -> Result<Self, DatabaseError> {
+        let (row_id, version, value_count, mut cursor) = Self::decode_header(bytes)?;
+        let value_count = value_count.min(max_values);
+
+        // Parse values using Value's from_bytes method
+        let mut values = Vec::with_capacity(value_count);
+        for _ in 0..value_count {
+            let (value, consumed) = Self::deserialize_value(&bytes[cursor..])?;
+            values.push(value);
+            cursor += consumed;
+        }
+
+        Ok(Row { row_id, version, values })
+    }
+
+    /// Decode only the values at `indices`, in that order. Other values are skipped using
+    /// their length prefixes without being materialized.
+    pub fn from_bytes_projected(bytes: &[u8], indices: &[usize]) -> Result<Self, DatabaseError> {
+        let (row_id, version, value_count, mut cursor) = Self::decode_header(bytes)?;
+        let decoded_len = indices.iter().max().map_or(0, |max| max + 1);
+        if decoded_len > value_count {
+            return Err(DatabaseError::ColumnIndexOutOfBounds {
+                index: decoded_len - 1,
+            });
+        }
+
+        let mut decoded: Vec<Option<Value>> = vec![None; decoded_len];
+        for (position, slot) in decoded.iter_mut().enumerate() {
+            if indices.contains(&position) {
+                let (value, consumed) = Self::deserialize_value(&bytes[cursor..])?;
+                *slot = Some(value);
+                cursor += consumed;
+            } else {
+                cursor += Self::encoded_value_len(&bytes[cursor..])?;
+            }
+        }
+
+        let values = indices
+            .iter()
+            .map(|&index| decoded[index].clone().expect("requested positions were decoded"))
+            .collect();
+        Ok(Row { row_id, version, values })
+    }
+
+    /// Parse the row id, version and value count, returning them with the offset of the
+    /// first value
+    fn decode_header(bytes: &[u8]) -> Result<(Option<RowId>, u64, usize, usize), DatabaseError> {
         if bytes.is_empty() {
             return Err(DatabaseError::SerializationError {
                 details: "Empty bytes".to_string(),
             });
         }
 
-        let mut cursor = 0;
-
-        // Parse row ID
-        let row_id = if bytes[cursor] == 1 {
-            cursor += 1;
-            if cursor + 8 > bytes.len() {
+        let flags = bytes[0];
+        let mut cursor = 1;
+        let mut read_u64 = |what: &str| {
+            let Some(field) = bytes.get(cursor..cursor + 8) else {
                 return Err(DatabaseError::SerializationError {
-                    details: "Incomplete row ID".to_string(),
+                    details: format!("Incomplete {}", what),
                 });
-            }
-            let id = RowId::from_le_bytes([
-                bytes[cursor],
-                bytes[cursor + 1],
-                bytes[cursor + 2],
-                bytes[cursor + 3],
-                bytes[cursor + 4],
-                bytes[cursor + 5],
-                bytes[cursor + 6],
-                bytes[cursor + 7],
-            ]);
+            };
             cursor += 8;
-            Some(id)
+            Ok(u64::from_le_bytes(field.try_into().expect("slice is 8 bytes")))
+        };
+
+        // Parse row ID and version
+        let row_id = if flags & HAS_ROW_ID != 0 {
+            Some(read_u64("row ID")?)
         } else {
-            cursor += 1;
             None
         };
+        let version = if flags & HAS_VERSION != 0 { read_u64("row version")? } else { 0 };
 
         // Parse value count
         if cursor + 4 > bytes.len() {
@@ -127,20 +202,25 @@ impl Row {
             bytes[cursor + 3],
         ]) as usize;
         cursor += 4;
-
-        // Parse values using Value's from_bytes method
-        let mut values = Vec::with_capacity(value_count);
-        for _ in 0..value_count {
-            let (value, consumed) = Self::deserialize_value(&bytes[cursor..])?;
-            values.push(value);
-            cursor += consumed;
+        // Every value takes at least one byte, so a larger count cannot be honest and must
+        // not size an allocation
+        if value_count > bytes.len() - cursor {
+            return Err(DatabaseError::SerializationError {
+                details: format!("Value count {} exceeds the {} bytes left", value_count, bytes.len() - cursor),
+            });
         }
-
-        Ok(Row { row_id, values })
+        Ok((row_id, version, value_count, cursor))
     }
 
     /// Helper function to deserialize a value and return the number of bytes consumed
     fn deserialize_value(bytes: &[u8]) -> Result<(Value, usize), DatabaseError> {
+        let expected_size = Self::encoded_value_len(bytes)?;
+        let value = Value::from_bytes(&bytes[0..expected_size])?;
+        Ok((value, expected_size))
+    }
+
+    /// Size of the encoded value at the start of `bytes`, checked against the bytes available
+    fn encoded_value_len(bytes: &[u8]) -> Result<usize, DatabaseError> {
         if bytes.is_empty() {
             return Err(DatabaseError::SerializationError {
                 details: "Empty value bytes".to_string(),
@@ -186,6 +266,7 @@ impl Row {
             }
             5 => 1 + 1, // Boolean
             6 => 1 + 8, // Timestamp
+            7 => 1 + 17, // Decimal
             _ => {
                 return Err(DatabaseError::SerializationError {
                     details: format!("Unknown type discriminant: {}", type_discriminant),
@@ -201,10 +282,6 @@ impl Row {
             });
         }
 
-        // Extract the exact bytes for this value and deserialize
-        let value_bytes = &bytes[0..expected_size];
-        let value = Value::from_bytes(value_bytes)?;
-        
-        Ok((value, expected_size))
+        Ok(expected_size)
     }
 }
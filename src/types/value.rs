@@ -3,7 +3,7 @@ use std::cmp::Ordering;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::types::error::DatabaseError;
+use crate::types::{decimal::Decimal, error::DatabaseError};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DataType {
@@ -14,6 +14,7 @@ pub enum DataType {
     Blob,
     Boolean,
     Timestamp,
+    Decimal,
 }
 
 impl std::fmt::Display for DataType {
@@ -26,6 +27,7 @@ impl std::fmt::Display for DataType {
             DataType::Blob => write!(f, "BLOB"),
             DataType::Boolean => write!(f, "BOOLEAN"),
             DataType::Timestamp => write!(f, "TIMESTAMP"),
+            DataType::Decimal => write!(f, "DECIMAL"),
         }
     }
 }
@@ -41,11 +43,24 @@ impl DataType {
             "BLOB" | "BINARY" => Ok(DataType::Blob),
             "BOOLEAN" | "BOOL" => Ok(DataType::Boolean),
             "TIMESTAMP" | "DATETIME" => Ok(DataType::Timestamp),
+            "DECIMAL" | "NUMERIC" => Ok(DataType::Decimal),
             _ => Err(DatabaseError::SerializationError {
                 details: format!("Unknown data type: {}", s),
             }),
         }
     }
+
+    /// Whether values of the two types can be meaningfully compared
+    pub fn is_comparable_with(&self, other: &DataType) -> bool {
+        match (self, other) {
+            (DataType::Null, _) | (_, DataType::Null) => true,
+            (
+                DataType::Integer | DataType::Real | DataType::Decimal,
+                DataType::Integer | DataType::Real | DataType::Decimal,
+            ) => true,
+            (a, b) => a == b,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +72,8 @@ pub enum Value {
     Blob(Vec<u8>),
     Boolean(bool),
     Timestamp(i64),
+    /// Exact fixed-point number, e.g. for currency amounts
+    Decimal(Decimal),
 }
 
 impl Value {
@@ -69,6 +86,7 @@ impl Value {
             Value::Blob(_) => DataType::Blob,
             Value::Boolean(_) => DataType::Boolean,
             Value::Timestamp(_) => DataType::Timestamp,
+            Value::Decimal(_) => DataType::Decimal,
         }
     }
 
@@ -81,6 +99,7 @@ impl Value {
             Value::Blob(b) => b.len(),
             Value::Boolean(_) => 1,
             Value::Timestamp(_) => 8, // 8 bytes for timestamp (Unix timestamp as i64)
+            Value::Decimal(_) => 17,  // i128 mantissa + scale
         }
     }
 
@@ -88,6 +107,80 @@ impl Value {
         matches!(self, Value::Null)
     }
 
+    /// Whether this is a REAL holding NaN, which cannot be a B+ tree key or UNIQUE value
+    pub fn is_nan(&self) -> bool {
+        matches!(self, Value::Real(r) if r.is_nan())
+    }
+
+    /// Total order over all values, used for B+ tree keys, sorting and value sets. It
+    /// agrees with the coercive `PartialOrd` wherever that relates two values, so
+    /// -Infinity and +Infinity sit below and above every other number. Values it cannot
+    /// relate are ordered by type: NULL, numbers, booleans, timestamps, text, blobs. NaN
+    /// sorts above +Infinity and equal to itself.
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap_or_else(|| {
+            self.type_rank()
+                .cmp(&other.type_rank())
+                .then_with(|| self.is_nan().cmp(&other.is_nan()))
+        })
+    }
+
+    /// B+ tree key for a tuple of values, as used by tables with a composite PRIMARY KEY:
+    /// a BLOB whose byte order matches ordering the tuples component by component, with
+    /// each component ordered as in `total_cmp`. Numbers of any type that are equal encode
+    /// identically. NaN components must be rejected before encoding.
+    pub fn composite_key<'a>(parts: impl IntoIterator<Item = &'a Value>) -> Value {
+        let mut key = Vec::new();
+        for part in parts {
+            part.write_sort_key(&mut key);
+        }
+        Value::Blob(key)
+    }
+
+    /// Append this value's tag and an order-preserving encoding of it to `out`
+    fn write_sort_key(&self, out: &mut Vec<u8>) {
+        // Numbers compare by their f64 image, then exactly for integers beyond f64 precision
+        let number = |out: &mut Vec<u8>, approximate: f64, exact: i64| {
+            let approximate = if approximate == 0.0 { 0.0 } else { approximate };
+            let bits = approximate.to_bits();
+            let ordered = if bits >> 63 == 1 { !bits } else { bits | 1 << 63 };
+            out.extend_from_slice(&ordered.to_be_bytes());
+            out.extend_from_slice(&((exact as u64) ^ 1 << 63).to_be_bytes());
+        };
+        // Bytes escape 0x00 as 0x00 0xFF and end with 0x00 0x00, so a prefix sorts first
+        let bytes = |out: &mut Vec<u8>, data: &[u8]| {
+            for byte in data {
+                out.push(*byte);
+                if *byte == 0 {
+                    out.push(0xFF);
+                }
+            }
+            out.extend_from_slice(&[0, 0]);
+        };
+        out.push(self.type_rank());
+        match self {
+            Value::Null => {}
+            Value::Integer(i) => number(out, *i as f64, *i),
+            Value::Real(r) => number(out, *r, *r as i64),
+            Value::Decimal(d) => number(out, d.to_f64(), d.to_f64() as i64),
+            Value::Boolean(b) => out.push(*b as u8),
+            Value::Timestamp(ts) => out.extend_from_slice(&((*ts as u64) ^ 1 << 63).to_be_bytes()),
+            Value::Text(s) => bytes(out, s.as_bytes()),
+            Value::Blob(b) => bytes(out, b),
+        }
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Integer(_) | Value::Real(_) | Value::Decimal(_) => 1,
+            Value::Boolean(_) => 2,
+            Value::Timestamp(_) => 3,
+            Value::Text(_) => 4,
+            Value::Blob(_) => 5,
+        }
+    }
+
     pub fn coerce_to_number(&self) -> Option<f64> {
         match self {
             Value::Integer(i) => Some(*i as f64),
@@ -95,6 +188,7 @@ impl Value {
             Value::Text(s) => s.parse().ok(),
             Value::Boolean(b) => Some(if *b { 1.0 } else { 0.0 }),
             Value::Timestamp(ts) => Some(*ts as f64),
+            Value::Decimal(d) => Some(d.to_f64()),
             _ => None,
         }
     }
@@ -105,6 +199,7 @@ impl Value {
             Value::Boolean(b) => Some(*b),
             Value::Integer(i) => Some(*i != 0),
             Value::Real(r) => Some(*r != 0.0),
+            Value::Decimal(d) => Some(d.mantissa() != 0),
             Value::Text(s) => match s.to_lowercase().as_str() {
                 "true" | "t" | "yes" | "y" | "1" => Some(true),
                 "false" | "f" | "no" | "n" | "0" => Some(false),
@@ -166,7 +261,8 @@ impl Value {
     /// Convert Value to bytes using custom binary format
     ///
     /// Binary format:
-    /// - 1 byte: type discriminant (0=Null, 1=Integer, 2=Real, 3=Text, 4=Blob, 5=Boolean, 6=Timestamp)
+    /// - 1 byte: type discriminant (0=Null, 1=Integer, 2=Real, 3=Text, 4=Blob, 5=Boolean, 6=Timestamp,
+    ///   7=Decimal)
     /// - Variable length data based on type
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
@@ -205,6 +301,10 @@ impl Value {
                 bytes.push(6); // Type discriminant for Timestamp
                 bytes.extend_from_slice(&ts.to_le_bytes());
             }
+            Value::Decimal(d) => {
+                bytes.push(7); // Type discriminant for Decimal
+                bytes.extend_from_slice(&d.to_bytes());
+            }
         }
 
         bytes
@@ -310,6 +410,7 @@ impl Value {
                 ts_bytes.copy_from_slice(data);
                 Ok(Value::Timestamp(i64::from_le_bytes(ts_bytes)))
             }
+            7 => Decimal::from_bytes(data).map(Value::Decimal),
             _ => Err(DatabaseError::SerializationError {
                 details: format!("Unknown type discriminant: {}", type_discriminant),
             }),
@@ -326,6 +427,7 @@ impl Value {
             Value::Blob(b) => 1 + 4 + b.len(), // Type + length (4 bytes) + blob bytes
             Value::Boolean(_) => 1 + 1,        // Type + 1 byte for boolean
             Value::Timestamp(_) => 1 + 8,      // Type + 8 bytes for i64
+            Value::Decimal(_) => 1 + 17,       // Type + 16-byte mantissa + scale
         }
     }
 
@@ -359,7 +461,7 @@ impl Value {
                     // Simple hex decode without external dependency
                     let mut bytes = Vec::new();
                     let chars: Vec<char> = hex_str.chars().collect();
-                    if chars.len() % 2 != 0 {
+                    if !chars.len().is_multiple_of(2) {
                         return Err(DatabaseError::SerializationError {
                             details: format!("Invalid hex string length: {}", s),
                         });
@@ -388,6 +490,7 @@ impl Value {
                 }
             }
             DataType::Timestamp => Value::timestamp_from_str(s),
+            DataType::Decimal => s.parse().map(Value::Decimal),
         }
     }
 
@@ -401,12 +504,60 @@ impl Value {
             (Value::Blob(_), DataType::Blob) => true,
             (Value::Boolean(_), DataType::Boolean) => true,
             (Value::Timestamp(_), DataType::Timestamp) => true,
+            (Value::Decimal(_), DataType::Decimal) => true,
             // Allow some cross-type compatibility
             (Value::Integer(_), DataType::Real) => true, // Integer can be promoted to Real
+            (Value::Integer(_), DataType::Decimal) => true, // Integers are exact decimals
             (Value::Boolean(_), DataType::Integer) => true, // Boolean can be converted to Integer
             _ => false,
         }
     }
+
+    /// Convert this value to `target` without losing information. Casts that would
+    /// truncate, round or reinterpret the value (e.g. `1.5` to INTEGER) are errors.
+    pub fn cast(&self, target: &DataType) -> Result<Value, DatabaseError> {
+        let lossy = || DatabaseError::TypeMismatch {
+            expected: target.to_string(),
+            actual: self.data_type().to_string(),
+        };
+        match (self, target) {
+            (Value::Null, _) => Ok(Value::Null),
+            (value, target) if value.data_type() == *target => Ok(value.clone()),
+            (Value::Integer(i), DataType::Real) if i.unsigned_abs() <= 1 << 53 => Ok(Value::Real(*i as f64)),
+            (Value::Integer(i), DataType::Text) => Ok(Value::Text(i.to_string())),
+            (Value::Integer(i @ (0 | 1)), DataType::Boolean) => Ok(Value::Boolean(*i == 1)),
+            (Value::Integer(i), DataType::Timestamp) => Ok(Value::Timestamp(*i)),
+            (Value::Real(r), DataType::Integer)
+                if r.fract() == 0.0 && *r >= i64::MIN as f64 && *r < i64::MAX as f64 =>
+            {
+                Ok(Value::Integer(*r as i64))
+            }
+            (Value::Real(r), DataType::Text) => Ok(Value::Text(r.to_string())),
+            (Value::Boolean(b), DataType::Integer) => Ok(Value::Integer(*b as i64)),
+            (Value::Boolean(_), DataType::Text) => Ok(Value::Text(self.to_string())),
+            (Value::Timestamp(ts), DataType::Integer) => Ok(Value::Integer(*ts)),
+            (Value::Integer(i), DataType::Decimal) => Ok(Value::Decimal(Decimal::from(*i))),
+            (Value::Decimal(d), DataType::Integer) => d.to_i64().map(Value::Integer).ok_or_else(lossy),
+            (Value::Decimal(d), DataType::Text) => Ok(Value::Text(d.to_string())),
+            (Value::Real(r), DataType::Decimal) if r.is_finite() => {
+                r.to_string().parse().map(Value::Decimal).map_err(|_| lossy())
+            }
+            (Value::Text(s), DataType::Decimal) => s.parse().map(Value::Decimal).map_err(|_| lossy()),
+            (Value::Text(s), DataType::Integer) => s.trim().parse().map(Value::Integer).map_err(|_| lossy()),
+            (Value::Text(s), DataType::Real) => match s.trim().parse::<f64>() {
+                Ok(r) if r.is_finite() => Ok(Value::Real(r)),
+                _ => Err(lossy()),
+            },
+            (Value::Text(s), DataType::Boolean | DataType::Timestamp) => {
+                Value::from_string(s.trim(), target).map_err(|_| lossy())
+            }
+            (Value::Text(s), DataType::Blob) => Ok(Value::Blob(s.as_bytes().to_vec())),
+            (Value::Blob(bytes), DataType::Text) => String::from_utf8(bytes.clone())
+                .map(Value::Text)
+                .map_err(|_| lossy()),
+            _ => Err(lossy()),
+        }
+    }
 }
 
 impl PartialOrd for Value {
@@ -423,6 +574,9 @@ impl PartialOrd for Value {
             (Value::Blob(a), Value::Blob(b)) => a.partial_cmp(b),
             (Value::Boolean(a), Value::Boolean(b)) => a.partial_cmp(b),
             (Value::Timestamp(a), Value::Timestamp(b)) => a.partial_cmp(b),
+            (Value::Decimal(a), Value::Decimal(b)) => a.partial_cmp(b),
+            (Value::Decimal(a), Value::Integer(b)) => a.partial_cmp(&Decimal::from(*b)),
+            (Value::Integer(a), Value::Decimal(b)) => Decimal::from(*a).partial_cmp(b),
             (a, b) => {
                 match (a.coerce_to_number(), b.coerce_to_number()) {
                     (Some(x), Some(y)) => x.partial_cmp(&y),
@@ -444,6 +598,10 @@ impl PartialEq for Value {
             (Value::Blob(a), Value::Blob(b)) => a == b,
             (Value::Boolean(a), Value::Boolean(b)) => a == b,
             (Value::Timestamp(a), Value::Timestamp(b)) => a == b,
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
+            (Value::Decimal(a), Value::Integer(b)) | (Value::Integer(b), Value::Decimal(a)) => {
+                *a == Decimal::from(*b)
+            }
 
             // Cross-type numeric comparisons
             (Value::Integer(a), Value::Real(b)) => (*a as f64) == *b,
@@ -465,6 +623,254 @@ impl PartialEq for Value {
     }
 }
 
+/// How values of different types are related by predicates and constraint checks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValueComparison {
+    /// Coerce across types as `Value`'s own `PartialEq`/`PartialOrd` do, e.g. `1 = TRUE`
+    /// and `'5' > 4`
+    #[default]
+    Coercive,
+    /// Only values of the same type are related, except that INTEGER, REAL and DECIMAL
+    /// compare numerically. Everything else is unequal and incomparable.
+    Strict,
+}
+
+impl ValueComparison {
+    pub fn equals(self, left: &Value, right: &Value) -> bool {
+        match self {
+            ValueComparison::Coercive => left == right,
+            ValueComparison::Strict => self.relates(left, right) && left == right,
+        }
+    }
+
+    pub fn compare(self, left: &Value, right: &Value) -> Option<Ordering> {
+        match self {
+            ValueComparison::Strict if !self.relates(left, right) => None,
+            _ => left.partial_cmp(right),
+        }
+    }
+
+    /// Key putting `value` in one GROUP BY group with the values it groups with under this
+    /// mode. Coercive groups as `HashableValue` compares; Strict also keeps every type
+    /// apart, numbers included, so `Integer(1)` and `Real(1.0)` form separate groups.
+    pub fn group_key(self, value: Value) -> GroupKey {
+        let kind = match self {
+            ValueComparison::Coercive => None,
+            ValueComparison::Strict => Some(std::mem::discriminant(&value)),
+        };
+        GroupKey {
+            kind,
+            value: HashableValue(value),
+        }
+    }
+
+    /// Whether the two values may be compared at all under this mode
+    fn relates(self, left: &Value, right: &Value) -> bool {
+        let numeric = |value: &Value| matches!(value, Value::Integer(_) | Value::Real(_) | Value::Decimal(_));
+        match self {
+            ValueComparison::Coercive => true,
+            ValueComparison::Strict => {
+                std::mem::discriminant(left) == std::mem::discriminant(right)
+                    || numeric(left) && numeric(right)
+            }
+        }
+    }
+}
+
+/// A `Value` usable as a `HashMap` key or `HashSet` element.
+///
+/// `Value`'s own `==` coerces across types (`1 = TRUE`, `'5' = 5`) and is not transitive,
+/// so no hash can agree with it. The wrapper instead compares canonical forms:
+/// - INTEGER, REAL and DECIMAL with no fractional part that fit in an i64 are that
+///   integer, so `Integer(1)`, `Real(1.0)` and a DECIMAL `1.00` are equal and hash alike.
+///   Other REAL and DECIMAL values compare by their `f64`, with `-0.0` equal to `0.0`.
+/// - NaN equals NaN, so it forms a single group.
+/// - NULL equals NULL, as GROUP BY and DISTINCT require.
+/// - TEXT, BLOB, BOOLEAN and TIMESTAMP equal only values of the same type.
+#[derive(Debug, Clone)]
+pub struct HashableValue(pub Value);
+
+/// What `HashableValue` compares and hashes
+#[derive(PartialEq, Eq, Hash)]
+enum CanonicalValue<'a> {
+    Null,
+    Integer(i64),
+    /// Bits of a non-integral, non-NaN float
+    Real(u64),
+    NaN,
+    Text(&'a str),
+    Blob(&'a [u8]),
+    Boolean(bool),
+    Timestamp(i64),
+}
+
+impl HashableValue {
+    pub fn into_inner(self) -> Value {
+        self.0
+    }
+
+    fn canonical(&self) -> CanonicalValue<'_> {
+        match &self.0 {
+            Value::Null => CanonicalValue::Null,
+            Value::Integer(i) => CanonicalValue::Integer(*i),
+            Value::Real(r) => Self::canonical_real(*r),
+            Value::Decimal(d) => match d.to_i64() {
+                Some(i) => CanonicalValue::Integer(i),
+                None => Self::canonical_real(d.to_f64()),
+            },
+            Value::Text(s) => CanonicalValue::Text(s),
+            Value::Blob(b) => CanonicalValue::Blob(b),
+            Value::Boolean(b) => CanonicalValue::Boolean(*b),
+            Value::Timestamp(ts) => CanonicalValue::Timestamp(*ts),
+        }
+    }
+
+    fn canonical_real(r: f64) -> CanonicalValue<'static> {
+        // i64::MAX as f64 rounds up to 2^63, which is already out of range
+        if r.is_nan() {
+            CanonicalValue::NaN
+        } else if r.fract() == 0.0 && r >= i64::MIN as f64 && r < i64::MAX as f64 {
+            CanonicalValue::Integer(r as i64)
+        } else {
+            CanonicalValue::Real(r.to_bits())
+        }
+    }
+}
+
+impl PartialEq for HashableValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+impl Eq for HashableValue {}
+
+impl std::hash::Hash for HashableValue {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state);
+    }
+}
+
+/// A GROUP BY key made by `ValueComparison::group_key`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GroupKey {
+    /// Type of the value, when the mode keeps types apart
+    kind: Option<std::mem::Discriminant<Value>>,
+    value: HashableValue,
+}
+
+impl GroupKey {
+    pub fn into_inner(self) -> Value {
+        self.value.into_inner()
+    }
+}
+
+impl From<Value> for HashableValue {
+    fn from(value: Value) -> Self {
+        Self(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Real(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Blob(value)
+    }
+}
+
+/// The error for taking a Rust value of type `expected` out of `value`. The conversions
+/// below are exact: they never cast between types, so e.g. a REAL is not an `i64`.
+fn conversion_mismatch(expected: DataType, value: &Value) -> DatabaseError {
+    DatabaseError::TypeMismatch {
+        expected: expected.to_string(),
+        actual: value.data_type().to_string(),
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = DatabaseError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Integer(i) => Ok(i),
+            other => Err(conversion_mismatch(DataType::Integer, &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = DatabaseError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Real(r) => Ok(r),
+            other => Err(conversion_mismatch(DataType::Real, &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = DatabaseError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Boolean(b) => Ok(b),
+            other => Err(conversion_mismatch(DataType::Boolean, &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = DatabaseError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Text(s) => Ok(s),
+            other => Err(conversion_mismatch(DataType::Text, &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Vec<u8> {
+    type Error = DatabaseError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Blob(b) => Ok(b),
+            other => Err(conversion_mismatch(DataType::Blob, &other)),
+        }
+    }
+}
+
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -474,6 +880,7 @@ impl std::fmt::Display for Value {
             Value::Text(s) => write!(f, "{}", s),
             Value::Blob(b) => write!(f, "BLOB({} bytes)", b.len()),
             Value::Boolean(b) => write!(f, "{}", if *b { "TRUE" } else { "FALSE" }),
+            Value::Decimal(d) => write!(f, "{}", d),
             Value::Timestamp(ts) => {
                 if let Some(dt) = Utc.timestamp_opt(*ts, 0).single() {
                     write!(f, "{}", dt.format("%Y-%m-%d %H:%M:%S UTC"))
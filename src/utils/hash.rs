@@ -2,11 +2,14 @@ use crc32fast::Hasher;
 
 use crate::types::{page::{PageType, SlotEntry}, PageId};
 
+#[allow(clippy::too_many_arguments)]
 pub fn calculate_page_checksum(
     page_id: PageId,
     page_type: &PageType,
     parent_page_id: Option<PageId>,
     next_leaf_page_id: Option<PageId>,
+    prev_leaf_page_id: Option<PageId>,
+    flags: u8,
     cell_count: u16,
     free_space_offset: u16,
     slots: &[SlotEntry],
@@ -19,13 +22,16 @@ pub fn calculate_page_checksum(
     hasher.update(&[page_type.as_u8()]);
     hasher.update(&parent_page_id.unwrap_or(u64::MAX).to_le_bytes());
     hasher.update(&next_leaf_page_id.unwrap_or(u64::MAX).to_le_bytes());
+    hasher.update(&prev_leaf_page_id.unwrap_or(u64::MAX).to_le_bytes());
+    // Pages from before the header had flags hash no flags byte, and keep their checksums
+    if flags != 0 {
+        hasher.update(&[flags]);
+    }
     hasher.update(&cell_count.to_le_bytes());
     hasher.update(&free_space_offset.to_le_bytes());
 
     for slot in slots {
-        hasher.update(&slot.offset.to_le_bytes());
-        hasher.update(&slot.length.to_le_bytes());
-        hasher.update(&[if slot.is_overflow { 1 } else { 0 }]);
+        hasher.update(&slot.to_bytes());
     }
 
     // Only hash data if we have it loaded
@@ -36,11 +42,14 @@ pub fn calculate_page_checksum(
     hasher.finalize()
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn verify_page_checksum(
     page_id: PageId,
     page_type: &PageType,
     parent_page_id: Option<PageId>,
     next_leaf_page_id: Option<PageId>,
+    prev_leaf_page_id: Option<PageId>,
+    flags: u8,
     cell_count: u16,
     free_space_offset: u16,
     slots: &[SlotEntry],
@@ -53,6 +62,8 @@ pub fn verify_page_checksum(
         page_type,
         parent_page_id,
         next_leaf_page_id,
+        prev_leaf_page_id,
+        flags,
         cell_count,
         free_space_offset,
         slots,
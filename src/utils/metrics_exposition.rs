@@ -0,0 +1,167 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{storage::storage_manager::StorageManager, types::error::DatabaseError};
+
+/// Content type of the Prometheus text exposition format
+pub const EXPOSITION_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds of the `bambang_query_duration_seconds` buckets, in seconds. `+Inf` follows
+/// the last one.
+pub const QUERY_DURATION_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Statements run, counted by kind, and a histogram of how long they took
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryMetrics {
+    /// Statements run, keyed by kind: the statement's first keyword, lowercased
+    pub queries: BTreeMap<String, u64>,
+    /// Statements that took at most each bound of `QUERY_DURATION_BUCKETS`, not cumulative
+    bucket_counts: [u64; QUERY_DURATION_BUCKETS.len()],
+    duration_sum: Duration,
+    duration_count: u64,
+}
+
+impl QueryMetrics {
+    /// Count one run of `statement`, which took `elapsed`
+    pub fn record(&mut self, statement: &str, elapsed: Duration) {
+        *self.queries.entry(statement_kind(statement)).or_default() += 1;
+        let seconds = elapsed.as_secs_f64();
+        if let Some(bucket) = QUERY_DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.bucket_counts[bucket] += 1;
+        }
+        self.duration_sum += elapsed;
+        self.duration_count += 1;
+    }
+}
+
+/// Kind a statement is counted under: its first keyword, lowercased
+fn statement_kind(statement: &str) -> String {
+    statement
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .find(|word| !word.is_empty())
+        .unwrap_or("unknown")
+        .to_ascii_lowercase()
+}
+
+/// Render the database's counters and gauges, with `queries`, in the Prometheus text
+/// exposition format. The metric names and labels are stable:
+///
+/// - `bambang_pages_read_total` (counter): pages read from the file since it was opened;
+///   page cache hits are not counted
+/// - `bambang_page_writes_total{kind="full"|"partial"}` (counter): page writes, whole or
+///   of the modified extents only
+/// - `bambang_bytes_written_total` (counter): bytes those writes covered
+/// - `bambang_queries_total{kind}` (counter): statements run, by first keyword
+/// - `bambang_query_duration_seconds` (histogram): how long statements took
+/// - `bambang_database_size_bytes` (gauge): size of the database file
+/// - `bambang_freelist_pages` (gauge): pages free for reuse
+/// - `bambang_table_rows{table}` (gauge): rows in each table
+pub fn render_metrics(storage: &mut StorageManager, queries: &QueryMetrics) -> Result<String, DatabaseError> {
+    let io = storage.io_stats();
+    let mut out = String::new();
+    let unlabelled = |value: u64| [(String::new(), value.to_string())];
+    let samples = unlabelled(io.pages_read);
+    write_metric(&mut out, "bambang_pages_read_total", "counter", "Pages read from the database file", &samples);
+    let samples = [
+        ("kind=\"full\"".to_string(), io.full_page_writes.to_string()),
+        ("kind=\"partial\"".to_string(), io.partial_page_writes.to_string()),
+    ];
+    write_metric(&mut out, "bambang_page_writes_total", "counter", "Page writes to the database file", &samples);
+    let samples = unlabelled(io.bytes_written);
+    write_metric(&mut out, "bambang_bytes_written_total", "counter", "Bytes written to the database file", &samples);
+
+    let samples: Vec<(String, String)> = queries
+        .queries
+        .iter()
+        .map(|(kind, count)| (format!("kind=\"{}\"", escape_label(kind)), count.to_string()))
+        .collect();
+    write_metric(&mut out, "bambang_queries_total", "counter", "Statements run, by kind", &samples);
+
+    let mut cumulative = 0;
+    let mut samples: Vec<(String, String)> = QUERY_DURATION_BUCKETS
+        .iter()
+        .zip(queries.bucket_counts)
+        .map(|(bound, count)| {
+            cumulative += count;
+            (format!("le=\"{}\"", bound), cumulative.to_string())
+        })
+        .collect();
+    samples.push(("le=\"+Inf\"".to_string(), queries.duration_count.to_string()));
+    let _ = writeln!(out, "# HELP bambang_query_duration_seconds Time taken to run a statement");
+    let _ = writeln!(out, "# TYPE bambang_query_duration_seconds histogram");
+    for (labels, value) in &samples {
+        let _ = writeln!(out, "bambang_query_duration_seconds_bucket{{{}}} {}", labels, value);
+    }
+    let _ = writeln!(out, "bambang_query_duration_seconds_sum {}", queries.duration_sum.as_secs_f64());
+    let _ = writeln!(out, "bambang_query_duration_seconds_count {}", queries.duration_count);
+
+    let samples = unlabelled(storage.db_info.file_size);
+    write_metric(&mut out, "bambang_database_size_bytes", "gauge", "Size of the database file", &samples);
+    let samples = unlabelled(storage.free_page_ids()?.len() as u64);
+    write_metric(&mut out, "bambang_freelist_pages", "gauge", "Pages free for reuse", &samples);
+    let mut samples = Vec::new();
+    for table in storage.get_table_names() {
+        if table == "sqlite_schema" {
+            continue;
+        }
+        let rows = storage.count_rows(&table, None)?;
+        samples.push((format!("table=\"{}\"", escape_label(&table)), rows.to_string()));
+    }
+    write_metric(&mut out, "bambang_table_rows", "gauge", "Rows in each table", &samples);
+    Ok(out)
+}
+
+/// Write a metric's HELP and TYPE lines, then one sample per `(labels, value)`
+fn write_metric(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, String)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{} {}", name, value);
+        } else {
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+        }
+    }
+}
+
+/// Escape a label value: backslash, double quote and newline
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Answer every HTTP request on `listener` with the latest exposition in `snapshot`, on a
+/// thread of its own. Whoever owns the database renders into `snapshot` as it changes,
+/// so scrapes never wait on a running statement.
+pub fn serve_metrics(listener: TcpListener, snapshot: Arc<Mutex<String>>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            // A client that goes away mid-request costs only its own response
+            let _ = stream.and_then(|stream| respond(stream, &snapshot));
+        }
+    })
+}
+
+/// Read one request up to its blank line and answer it with `snapshot`
+fn respond(mut stream: TcpStream, snapshot: &Mutex<String>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && line.trim_end() != "" {
+        line.clear();
+    }
+    let body = snapshot.lock().map(|body| body.clone()).unwrap_or_default();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        EXPOSITION_CONTENT_TYPE,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
@@ -1,12 +1,13 @@
 use std::{
     fs,
     path::PathBuf,
-    time::{SystemTime, UNIX_EPOCH},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use tempfile::env::temp_dir;
 
-use crate::storage::storage_manager::StorageManager;
+use crate::storage::{options::StorageManagerOptions, page_cache::CacheClock, storage_manager::StorageManager};
 
 pub fn get_unix_timestamp_millis() -> u128 {
     SystemTime::now()
@@ -27,11 +28,49 @@ pub fn create_temp_db_path_with_prefix(prefix: &str) -> PathBuf {
     temp_path
 }
 
+/// A `CacheClock` that stands still until advanced
+#[derive(Debug)]
+pub struct MockClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.elapsed.lock().unwrap_or_else(|e| e.into_inner()) += by;
+    }
+}
+
+impl CacheClock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
 pub struct TempDatabase {
     pub path: PathBuf,
     pub storage_manager: Option<StorageManager>,
 }
 
+impl Default for TempDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TempDatabase {
     pub fn new() -> Self {
         Self {
@@ -55,6 +94,15 @@ impl TempDatabase {
         Ok(self.storage_manager.as_mut().unwrap())
     }
 
+    pub fn create_storage_manager_with_options(
+        &mut self,
+        options: StorageManagerOptions,
+    ) -> Result<&mut StorageManager, Box<dyn std::error::Error>> {
+        let sm = StorageManager::open_with_options(&self.path, options)?;
+        self.storage_manager = Some(sm);
+        Ok(self.storage_manager.as_mut().unwrap())
+    }
+
     pub fn get_storage_manager(&mut self) -> Option<&mut StorageManager> {
         self.storage_manager.as_mut()
     }
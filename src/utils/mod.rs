@@ -1,2 +1,5 @@
 pub mod hash;
-pub mod mock;
\ No newline at end of file
+pub mod metrics_exposition;
+pub mod mock;
+pub mod progress;
+pub mod result_format;
\ No newline at end of file
@@ -0,0 +1,146 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+};
+
+use crate::types::error::DatabaseError;
+
+/// Receives progress from a long-running operation and may ask it to stop. Operations
+/// report and check for cancellation once per page or chunk; a cancelled operation
+/// returns `DatabaseError::Cancelled` after undoing or completing its partial work, as
+/// each operation documents.
+pub trait ProgressSink {
+    /// `done` units of `total` (if known) are finished in the named `phase`
+    fn report(&self, done: u64, total: Option<u64>, phase: &str);
+
+    fn should_cancel(&self) -> bool {
+        false
+    }
+}
+
+/// Report to `sink`, if any, and fail with `Cancelled` if it asks `operation` to stop
+pub fn report_progress(
+    sink: Option<&dyn ProgressSink>,
+    operation: &str,
+    done: u64,
+    total: Option<u64>,
+    phase: &str,
+) -> Result<(), DatabaseError> {
+    let Some(sink) = sink else {
+        return Ok(());
+    };
+    sink.report(done, total, phase);
+    if sink.should_cancel() {
+        return Err(DatabaseError::Cancelled {
+            operation: operation.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// One report as delivered by a `ChannelProgress`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    pub done: u64,
+    pub total: Option<u64>,
+    pub phase: String,
+}
+
+/// Forwards reports to a channel, e.g. to watch an operation from another thread, and
+/// stops it once `cancel` is called on any clone of its cancellation handle
+pub struct ChannelProgress {
+    sender: Mutex<Sender<ProgressEvent>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ChannelProgress {
+    pub fn new() -> (Self, Receiver<ProgressEvent>) {
+        let (sender, receiver) = mpsc::channel();
+        let sink = Self {
+            sender: Mutex::new(sender),
+            cancelled: Arc::default(),
+        };
+        (sink, receiver)
+    }
+
+    /// Flag that can be set from any thread to cancel the operation
+    pub fn cancel_handle(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+impl ProgressSink for ChannelProgress {
+    fn report(&self, done: u64, total: Option<u64>, phase: &str) {
+        // A dropped receiver just means nobody is watching any more
+        let _ = self.sender.lock().unwrap_or_else(|e| e.into_inner()).send(ProgressEvent {
+            done,
+            total,
+            phase: phase.to_string(),
+        });
+    }
+
+    fn should_cancel(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// Redraws a one-line progress bar in place, for the CLI. Call `finish` to end the line.
+pub struct TerminalProgress<W: Write> {
+    out: Mutex<W>,
+}
+
+impl TerminalProgress<io::Stderr> {
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+}
+
+impl<W: Write> TerminalProgress<W> {
+    pub fn new(out: W) -> Self {
+        Self { out: Mutex::new(out) }
+    }
+
+    /// Move past the bar so later output starts on a new line
+    pub fn finish(&self) {
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(out);
+        let _ = out.flush();
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<W: Write> ProgressSink for TerminalProgress<W> {
+    fn report(&self, done: u64, total: Option<u64>, phase: &str) {
+        let line = match total {
+            Some(total) if total > 0 => {
+                let filled = (done.min(total) as usize * PROGRESS_BAR_WIDTH) / total as usize;
+                format!(
+                    "{} [{}{}] {}/{}",
+                    phase,
+                    "#".repeat(filled),
+                    " ".repeat(PROGRESS_BAR_WIDTH - filled),
+                    done,
+                    total
+                )
+            }
+            _ => format!("{} {}", phase, done),
+        };
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        // Terminal output is best effort and must not fail the operation
+        let _ = write!(out, "\r{}", line);
+        let _ = out.flush();
+    }
+}
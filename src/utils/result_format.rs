@@ -0,0 +1,226 @@
+use std::{fmt::Write, str::FromStr};
+
+use crate::{
+    storage::export::{csv_quote, value_to_json},
+    types::{row::Row, value::Value},
+};
+
+/// Widest a table cell gets before it is cut short with an ellipsis, in terminal columns
+pub const DEFAULT_MAX_CELL_WIDTH: usize = 40;
+
+/// How query results are rendered, as chosen with `.mode` in the shell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Aligned ASCII table with a header row
+    #[default]
+    Table,
+    /// RFC 4180 CSV with a header line; NULL is an empty field and blobs are hex
+    Csv,
+    /// One JSON object per line, keyed by column name
+    Json,
+}
+
+impl OutputMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputMode::Table => "table",
+            OutputMode::Csv => "csv",
+            OutputMode::Json => "json",
+        }
+    }
+}
+
+impl FromStr for OutputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Ok(OutputMode::Table),
+            "csv" => Ok(OutputMode::Csv),
+            "json" => Ok(OutputMode::Json),
+            _ => Err(format!("Unknown output mode '{}', expected table, csv or json", s)),
+        }
+    }
+}
+
+/// Renders result rows under their column names in an `OutputMode`
+#[derive(Debug, Clone, Copy)]
+pub struct ResultFormatter {
+    pub mode: OutputMode,
+    /// Only applies to `OutputMode::Table`; CSV and JSON keep every value whole
+    pub max_cell_width: usize,
+}
+
+impl Default for ResultFormatter {
+    fn default() -> Self {
+        Self::new(OutputMode::default())
+    }
+}
+
+impl ResultFormatter {
+    pub fn new(mode: OutputMode) -> Self {
+        Self {
+            mode,
+            max_cell_width: DEFAULT_MAX_CELL_WIDTH,
+        }
+    }
+
+    pub fn with_max_cell_width(mut self, width: usize) -> Self {
+        self.max_cell_width = width.max(1);
+        self
+    }
+
+    /// `rows` rendered with a trailing newline. Rows with fewer values than there are
+    /// columns are padded with NULL.
+    pub fn format(&self, columns: &[String], rows: &[Row]) -> String {
+        match self.mode {
+            OutputMode::Table => self.format_table(columns, rows),
+            OutputMode::Csv => format_csv(columns, rows),
+            OutputMode::Json => format_json(columns, rows),
+        }
+    }
+
+    fn format_table(&self, columns: &[String], rows: &[Row]) -> String {
+        let header: Vec<String> = columns.iter().map(|column| self.table_cell(column)).collect();
+        let body: Vec<Vec<(String, bool)>> = rows
+            .iter()
+            .map(|row| {
+                (0..columns.len())
+                    .map(|i| {
+                        let value = row.values.get(i).unwrap_or(&Value::Null);
+                        (self.table_cell(&value.to_string()), is_numeric(value))
+                    })
+                    .collect()
+            })
+            .collect();
+        let widths: Vec<usize> = (0..columns.len())
+            .map(|i| {
+                body.iter()
+                    .map(|cells| display_width(&cells[i].0))
+                    .chain(std::iter::once(display_width(&header[i])))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let border = widths.iter().fold(String::from("+"), |mut line, width| {
+            line.push_str(&"-".repeat(width + 2));
+            line.push('+');
+            line
+        });
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", border);
+        let header_cells: Vec<(String, bool)> = header.into_iter().map(|name| (name, false)).collect();
+        push_table_line(&mut out, &header_cells, &widths);
+        let _ = writeln!(out, "{}", border);
+        for cells in &body {
+            push_table_line(&mut out, cells, &widths);
+        }
+        if !body.is_empty() {
+            let _ = writeln!(out, "{}", border);
+        }
+        out
+    }
+
+    /// `text` on one line, cut to `max_cell_width` terminal columns
+    fn table_cell(&self, text: &str) -> String {
+        let single_line = text.replace('\r', "\\r").replace('\n', "\\n").replace('\t', "\\t");
+        if display_width(&single_line) <= self.max_cell_width {
+            return single_line;
+        }
+        // Leave a column for the ellipsis
+        let mut truncated = String::new();
+        let mut width = 0;
+        for c in single_line.chars() {
+            width += char_width(c);
+            if width >= self.max_cell_width {
+                break;
+            }
+            truncated.push(c);
+        }
+        truncated.push('…');
+        truncated
+    }
+}
+
+fn push_table_line(out: &mut String, cells: &[(String, bool)], widths: &[usize]) {
+    out.push('|');
+    for ((text, right_align), width) in cells.iter().zip(widths) {
+        let padding = " ".repeat(width - display_width(text));
+        if *right_align {
+            let _ = write!(out, " {}{} |", padding, text);
+        } else {
+            let _ = write!(out, " {}{} |", text, padding);
+        }
+    }
+    out.push('\n');
+}
+
+/// Columns `text` takes in a terminal
+fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Terminal columns of one character: two for East Asian wide characters and emoji, none
+/// for combining marks. An approximation of UAX #11 covering the common ranges.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+fn is_numeric(value: &Value) -> bool {
+    matches!(value, Value::Integer(_) | Value::Real(_) | Value::Decimal(_))
+}
+
+fn format_csv(columns: &[String], rows: &[Row]) -> String {
+    let mut out = String::new();
+    let header: Vec<String> = columns.iter().map(|column| csv_quote(column)).collect();
+    let _ = writeln!(out, "{}", header.join(","));
+    for row in rows {
+        let fields: Vec<String> = (0..columns.len())
+            .map(|i| match row.values.get(i).unwrap_or(&Value::Null) {
+                Value::Null => String::new(),
+                // Quoted so that it does not read back as NULL
+                Value::Text(text) if text.is_empty() => "\"\"".to_string(),
+                Value::Blob(bytes) => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+                value => csv_quote(&value.to_string()),
+            })
+            .collect();
+        let _ = writeln!(out, "{}", fields.join(","));
+    }
+    out
+}
+
+/// Values are written as `JsonRowWriter` writes them, keeping the column order
+fn format_json(columns: &[String], rows: &[Row]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        out.push('{');
+        for (i, column) in columns.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            let value = row.values.get(i).unwrap_or(&Value::Null);
+            let json = value_to_json(value, &value.data_type());
+            let _ = write!(out, "{}: {}", serde_json::Value::String(column.clone()), json);
+        }
+        out.push_str("}\n");
+    }
+    out
+}
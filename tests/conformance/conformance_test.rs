@@ -0,0 +1,48 @@
+use bambang::storage::options::StorageManagerOptions;
+
+use super::workload::{check_seed, file_after, generate};
+
+const DEFAULT_SEEDS: u64 = 20;
+const DEFAULT_OPS: usize = 200;
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[test]
+fn test_randomized_workloads_match_reference() {
+    for seed in 0..DEFAULT_SEEDS {
+        check_seed(seed, DEFAULT_OPS);
+    }
+}
+
+#[test]
+fn test_partial_page_writes_leave_the_same_file_as_full_writes() {
+    for seed in 0..DEFAULT_SEEDS {
+        let ops = generate(seed, DEFAULT_OPS);
+        let partial = file_after(&ops, StorageManagerOptions::new()).unwrap();
+        let full = file_after(&ops, StorageManagerOptions::new().with_partial_page_writes(false)).unwrap();
+        assert_eq!(partial.len(), full.len(), "seed {}: file sizes differ", seed);
+        if let Some(offset) = partial.iter().zip(&full).position(|(a, b)| a != b) {
+            panic!("seed {}: files first differ at byte {}", seed, offset);
+        }
+    }
+}
+
+/// Long-running mode for nightly fuzzing, skipped unless `BAMBANG_FUZZ_SEEDS` is set, e.g.
+/// `BAMBANG_FUZZ_SEEDS=500 BAMBANG_FUZZ_OPS=5000 cargo test fuzz_workloads`.
+/// `BAMBANG_FUZZ_SEED_START` picks up where an earlier run stopped.
+#[test]
+fn test_fuzz_workloads_match_reference() {
+    let Some(seeds) = std::env::var("BAMBANG_FUZZ_SEEDS").ok().and_then(|v| v.parse::<u64>().ok()) else {
+        return;
+    };
+    let start = env_or("BAMBANG_FUZZ_SEED_START", DEFAULT_SEEDS);
+    let ops = env_or("BAMBANG_FUZZ_OPS", 2_000usize);
+    for seed in start..start + seeds {
+        check_seed(seed, ops);
+    }
+}
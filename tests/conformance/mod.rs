@@ -0,0 +1,3 @@
+pub mod reference;
+pub mod workload;
+pub mod conformance_test;
@@ -0,0 +1,208 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use bambang::types::{RowId, row::Row, value::Value};
+
+/// Row filter understood by the reference model; the workload driver translates it into a
+/// bambang `Predicate`
+#[derive(Debug, Clone)]
+pub enum Filter {
+    All,
+    Compare {
+        column: usize,
+        op: CompareOp,
+        value: Value,
+    },
+    InList {
+        column: usize,
+        values: Vec<Value>,
+    },
+    IsNull(usize),
+    IsNotNull(usize),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Filter {
+    pub fn matches(&self, row: &Row) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Compare { column, op, value } => {
+                let Some(ordering) = compare(&row.values[*column], value) else {
+                    return false;
+                };
+                match op {
+                    CompareOp::Eq => ordering == Ordering::Equal,
+                    CompareOp::Ne => ordering != Ordering::Equal,
+                    CompareOp::Lt => ordering == Ordering::Less,
+                    CompareOp::Le => ordering != Ordering::Greater,
+                    CompareOp::Gt => ordering == Ordering::Greater,
+                    CompareOp::Ge => ordering != Ordering::Less,
+                }
+            }
+            Filter::InList { column, values } => values
+                .iter()
+                .any(|value| compare(&row.values[*column], value) == Some(Ordering::Equal)),
+            Filter::IsNull(column) => matches!(row.values[*column], Value::Null),
+            Filter::IsNotNull(column) => !matches!(row.values[*column], Value::Null),
+            Filter::And(left, right) => left.matches(row) && right.matches(row),
+            Filter::Or(left, right) => left.matches(row) || right.matches(row),
+        }
+    }
+}
+
+/// Only same-typed, non-NULL values are comparable in the model
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// COUNT, SUM, MIN and MAX over one integer column
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Aggregate {
+    pub count: usize,
+    pub sum: i64,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+}
+
+impl Aggregate {
+    pub fn accumulate(mut self, value: &Value) -> Self {
+        self.count += 1;
+        if let Value::Integer(v) = value {
+            self.sum += v;
+            self.min = Some(self.min.map_or(*v, |min| min.min(*v)));
+            self.max = Some(self.max.map_or(*v, |max| max.max(*v)));
+        }
+        self
+    }
+}
+
+/// Constraint violation reported by the model
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniqueViolation {
+    pub column: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReferenceColumn {
+    pub name: String,
+    pub unique: bool,
+}
+
+#[derive(Debug, Clone)]
+struct ReferenceTable {
+    columns: Vec<ReferenceColumn>,
+    rows: Vec<Row>,
+    next_row_id: RowId,
+}
+
+/// Deliberately naive in-memory database: every operation is a linear pass over a `Vec`
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceDb {
+    tables: HashMap<String, ReferenceTable>,
+}
+
+impl ReferenceDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create_table(&mut self, name: &str, columns: Vec<ReferenceColumn>) {
+        self.tables.insert(
+            name.to_string(),
+            ReferenceTable {
+                columns,
+                rows: Vec::new(),
+                next_row_id: 1,
+            },
+        );
+    }
+
+    /// Insert `row`, stamping it with the table's next rowid
+    pub fn insert(&mut self, table: &str, mut row: Row) -> Result<(), UniqueViolation> {
+        let table = self.table_mut(table);
+        for (position, column) in table.columns.iter().enumerate() {
+            let value = &row.values[position];
+            if column.unique
+                && !matches!(value, Value::Null)
+                && table
+                    .rows
+                    .iter()
+                    .any(|existing| compare(&existing.values[position], value) == Some(Ordering::Equal))
+            {
+                return Err(UniqueViolation {
+                    column: column.name.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        row.row_id = Some(table.next_row_id);
+        table.next_row_id += 1;
+        table.rows.push(row);
+        Ok(())
+    }
+
+    /// Insert rows in order, stopping at the first violation; earlier rows stay inserted
+    pub fn insert_batch(&mut self, table: &str, rows: Vec<Row>) -> Result<(), UniqueViolation> {
+        for row in rows {
+            self.insert(table, row)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, table: &str, filter: &Filter) -> usize {
+        let table = self.table_mut(table);
+        let before = table.rows.len();
+        table.rows.retain(|row| !filter.matches(row));
+        before - table.rows.len()
+    }
+
+    pub fn update(&mut self, table: &str, filter: &Filter, assignments: &[(usize, Value)]) -> usize {
+        let mut updated = 0;
+        for row in self.table_mut(table).rows.iter_mut() {
+            if filter.matches(row) {
+                for (position, value) in assignments {
+                    row.values[*position] = value.clone();
+                }
+                row.version += 1;
+                updated += 1;
+            }
+        }
+        updated
+    }
+
+    pub fn scan(&self, table: &str, filter: &Filter) -> Vec<Row> {
+        self.tables[table]
+            .rows
+            .iter()
+            .filter(|row| filter.matches(row))
+            .cloned()
+            .collect()
+    }
+
+    pub fn aggregate(&self, table: &str, filter: &Filter, column: usize) -> Aggregate {
+        self.scan(table, filter)
+            .iter()
+            .fold(Aggregate::default(), |acc, row| acc.accumulate(&row.values[column]))
+    }
+
+    fn table_mut(&mut self, table: &str) -> &mut ReferenceTable {
+        self.tables
+            .get_mut(table)
+            .unwrap_or_else(|| panic!("reference table '{}' does not exist", table))
+    }
+}
@@ -0,0 +1,447 @@
+use std::path::PathBuf;
+
+use bambang::{
+    executor::predicate::Predicate,
+    storage::{options::StorageManagerOptions, storage_manager::StorageManager},
+    types::{error::DatabaseError, row::Row, value::Value},
+};
+use tempfile::TempDir;
+
+use super::reference::{Aggregate, CompareOp, Filter, ReferenceColumn, ReferenceDb, UniqueViolation};
+
+/// Tables every workload runs against: `(name, unique name column, CREATE TABLE sql)`
+const TABLES: &[(&str, bool, &str)] = &[
+    (
+        "items",
+        false,
+        "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL, score INTEGER NOT NULL, note TEXT)",
+    ),
+    (
+        "accounts",
+        true,
+        "CREATE TABLE accounts (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE, score INTEGER NOT NULL, note TEXT)",
+    ),
+];
+const COLUMNS: &[&str] = &["id", "name", "score", "note"];
+const ID: usize = 0;
+const NAME: usize = 1;
+const SCORE: usize = 2;
+const NOTE: usize = 3;
+
+const ID_SPACE: i64 = 400;
+const NAME_SPACE: u64 = 60;
+const SCORE_SPACE: i64 = 100;
+const MAX_NOTE_PADDING: u64 = 300;
+
+/// Small deterministic generator so a seed always replays the same workload
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// splitmix64
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Op {
+    Insert { table: usize, row: Row },
+    InsertBatch { table: usize, rows: Vec<Row> },
+    Delete { table: usize, filter: Filter },
+    Update { table: usize, filter: Filter, assignments: Vec<(usize, Value)> },
+    Scan { table: usize, filter: Filter },
+    Count { table: usize, filter: Filter },
+    Aggregate { table: usize, filter: Filter },
+    Reopen,
+}
+
+/// Generate `len` operations for `seed`. Inserts dominate so tables grow across page splits.
+pub fn generate(seed: u64, len: usize) -> Vec<Op> {
+    let mut rng = Rng::new(seed);
+    (0..len)
+        .map(|_| {
+            let table = rng.below(TABLES.len() as u64) as usize;
+            match rng.below(100) {
+                0..=39 => Op::Insert {
+                    table,
+                    row: random_row(&mut rng),
+                },
+                40..=49 => Op::InsertBatch {
+                    table,
+                    rows: (0..1 + rng.below(12)).map(|_| random_row(&mut rng)).collect(),
+                },
+                50..=57 => Op::Delete {
+                    table,
+                    filter: random_filter(&mut rng, 2),
+                },
+                58..=67 => Op::Update {
+                    table,
+                    filter: random_filter(&mut rng, 2),
+                    assignments: random_assignments(&mut rng),
+                },
+                68..=81 => Op::Scan {
+                    table,
+                    filter: random_filter(&mut rng, 2),
+                },
+                82..=88 => Op::Count {
+                    table,
+                    filter: random_filter(&mut rng, 2),
+                },
+                89..=95 => Op::Aggregate {
+                    table,
+                    filter: random_filter(&mut rng, 2),
+                },
+                _ => Op::Reopen,
+            }
+        })
+        .collect()
+}
+
+fn random_row(rng: &mut Rng) -> Row {
+    Row::new(vec![
+        Value::Integer(rng.below(ID_SPACE as u64) as i64),
+        random_name(rng),
+        Value::Integer(rng.below(SCORE_SPACE as u64) as i64),
+        random_note(rng),
+    ])
+}
+
+fn random_name(rng: &mut Rng) -> Value {
+    Value::Text(format!("name_{:03}", rng.below(NAME_SPACE)))
+}
+
+fn random_note(rng: &mut Rng) -> Value {
+    if rng.chance(25) {
+        Value::Null
+    } else {
+        let padding = rng.below(MAX_NOTE_PADDING) as usize;
+        Value::Text(format!("note_{}{}", rng.below(10), "-".repeat(padding)))
+    }
+}
+
+fn random_assignments(rng: &mut Rng) -> Vec<(usize, Value)> {
+    let mut assignments = vec![(SCORE, Value::Integer(rng.below(SCORE_SPACE as u64) as i64))];
+    if rng.chance(50) {
+        assignments.push((NOTE, random_note(rng)));
+    }
+    assignments
+}
+
+fn random_filter(rng: &mut Rng, depth: u32) -> Filter {
+    let choice = if depth == 0 { rng.below(6) } else { rng.below(8) };
+    match choice {
+        0 => Filter::All,
+        1 | 2 => {
+            let (column, value) = match rng.below(3) {
+                0 => (ID, Value::Integer(rng.below(ID_SPACE as u64) as i64)),
+                1 => (NAME, random_name(rng)),
+                _ => (SCORE, Value::Integer(rng.below(SCORE_SPACE as u64) as i64)),
+            };
+            let op = match rng.below(6) {
+                0 => CompareOp::Eq,
+                1 => CompareOp::Ne,
+                2 => CompareOp::Lt,
+                3 => CompareOp::Le,
+                4 => CompareOp::Gt,
+                _ => CompareOp::Ge,
+            };
+            Filter::Compare { column, op, value }
+        }
+        3 => Filter::InList {
+            column: SCORE,
+            values: (0..1 + rng.below(5))
+                .map(|_| Value::Integer(rng.below(SCORE_SPACE as u64) as i64))
+                .collect(),
+        },
+        4 => Filter::IsNull(NOTE),
+        5 => Filter::IsNotNull(NOTE),
+        6 => Filter::And(
+            Box::new(random_filter(rng, depth - 1)),
+            Box::new(random_filter(rng, depth - 1)),
+        ),
+        _ => Filter::Or(
+            Box::new(random_filter(rng, depth - 1)),
+            Box::new(random_filter(rng, depth - 1)),
+        ),
+    }
+}
+
+fn to_predicate(filter: &Filter) -> Option<Predicate> {
+    let column = |index: usize| COLUMNS[index].to_string();
+    Some(match filter {
+        Filter::All => return None,
+        Filter::Compare { column: index, op, value } => {
+            let (name, value) = (column(*index), value.clone());
+            match op {
+                CompareOp::Eq => Predicate::eq(name, value),
+                CompareOp::Ne => Predicate::ne(name, value),
+                CompareOp::Lt => Predicate::lt(name, value),
+                CompareOp::Le => Predicate::le(name, value),
+                CompareOp::Gt => Predicate::gt(name, value),
+                CompareOp::Ge => Predicate::ge(name, value),
+            }
+        }
+        Filter::InList { column: index, values } => Predicate::in_list(column(*index), values.clone()),
+        Filter::IsNull(index) => Predicate::is_null(column(*index)),
+        Filter::IsNotNull(index) => Predicate::is_not_null(column(*index)),
+        Filter::And(left, right) => Predicate::and(
+            to_predicate(left).unwrap_or(Predicate::True),
+            to_predicate(right).unwrap_or(Predicate::True),
+        ),
+        Filter::Or(left, right) => Predicate::or(
+            to_predicate(left).unwrap_or(Predicate::True),
+            to_predicate(right).unwrap_or(Predicate::True),
+        ),
+    })
+}
+
+/// A bambang database and the reference model it is checked against
+pub struct Harness {
+    _dir: TempDir,
+    path: PathBuf,
+    storage: Option<StorageManager>,
+    options: StorageManagerOptions,
+    reference: ReferenceDb,
+}
+
+impl Harness {
+    pub fn new() -> Result<Self, DatabaseError> {
+        Self::with_options(StorageManagerOptions::default())
+    }
+
+    pub fn with_options(options: StorageManagerOptions) -> Result<Self, DatabaseError> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("conformance.db");
+        let mut storage = StorageManager::open_with_options(&path, options.clone())?;
+        let mut reference = ReferenceDb::new();
+        for &(name, unique_name, sql) in TABLES {
+            storage.create_table(name, sql)?;
+            reference.create_table(
+                name,
+                COLUMNS
+                    .iter()
+                    .enumerate()
+                    .map(|(position, column)| ReferenceColumn {
+                        name: column.to_string(),
+                        unique: position == ID || (position == NAME && unique_name),
+                    })
+                    .collect(),
+            );
+        }
+        Ok(Self {
+            _dir: dir,
+            path,
+            storage: Some(storage),
+            options,
+            reference,
+        })
+    }
+
+    fn storage(&mut self) -> &mut StorageManager {
+        self.storage.as_mut().expect("storage is open between operations")
+    }
+
+    /// Apply `op` to both databases and compare what they report
+    pub fn apply(&mut self, op: &Op) -> Result<(), String> {
+        match op {
+            Op::Insert { table, row } => {
+                let name = TABLES[*table].0;
+                let expected = self.reference.insert(name, row.clone());
+                let actual = self.storage().insert_into_table(name, row.clone());
+                compare_insert(expected, actual)
+            }
+            Op::InsertBatch { table, rows } => {
+                let name = TABLES[*table].0;
+                let expected = self.reference.insert_batch(name, rows.clone());
+                let actual = self.storage().insert_batch_into_table(name, rows.clone());
+                compare_insert(expected, actual)
+            }
+            Op::Delete { table, filter } => {
+                let name = TABLES[*table].0;
+                let expected = self.reference.delete(name, filter);
+                let actual = self
+                    .storage()
+                    .delete_from_table(name, to_predicate(filter))
+                    .map_err(|e| format!("delete failed: {}", e))?;
+                expect_equal("deleted rows", expected, actual)
+            }
+            Op::Update { table, filter, assignments } => {
+                let name = TABLES[*table].0;
+                let expected = self.reference.update(name, filter, assignments);
+                let named: Vec<(String, Value)> = assignments
+                    .iter()
+                    .map(|(position, value)| (COLUMNS[*position].to_string(), value.clone()))
+                    .collect();
+                let actual = self
+                    .storage()
+                    .update_table(name, to_predicate(filter), &named)
+                    .map_err(|e| format!("update failed: {}", e))?;
+                expect_equal("updated rows", expected, actual)
+            }
+            Op::Scan { table, filter } => self.check_scan(TABLES[*table].0, filter),
+            Op::Count { table, filter } => {
+                let name = TABLES[*table].0;
+                let expected = self.reference.scan(name, filter).len();
+                let actual = self
+                    .storage()
+                    .count_rows(name, to_predicate(filter))
+                    .map_err(|e| format!("count failed: {}", e))?;
+                expect_equal("row count", expected, actual)
+            }
+            Op::Aggregate { table, filter } => {
+                let name = TABLES[*table].0;
+                let expected = self.reference.aggregate(name, filter, SCORE);
+                let actual = self
+                    .storage()
+                    .fold_rows(name, to_predicate(filter), Aggregate::default(), |acc, row| {
+                        acc.accumulate(&row.values[SCORE])
+                    })
+                    .map_err(|e| format!("aggregate failed: {}", e))?;
+                expect_equal("aggregate", expected, actual)
+            }
+            Op::Reopen => {
+                self.storage = None;
+                let storage = StorageManager::open_with_options(&self.path, self.options.clone())
+                    .map_err(|e| format!("reopen failed: {}", e))?;
+                self.storage = Some(storage);
+                Ok(())
+            }
+        }
+    }
+
+    /// Compare the full contents of every table
+    pub fn check_all(&mut self) -> Result<(), String> {
+        for &(name, _, _) in TABLES {
+            self.check_scan(name, &Filter::All)?;
+        }
+        Ok(())
+    }
+
+    /// Close the database and return its file contents
+    pub fn into_file_bytes(mut self) -> std::io::Result<Vec<u8>> {
+        self.storage = None;
+        std::fs::read(&self.path)
+    }
+
+    fn check_scan(&mut self, table: &str, filter: &Filter) -> Result<(), String> {
+        let expected = sorted_by_id(self.reference.scan(table, filter));
+        let actual = self
+            .storage()
+            .scan_table(table, to_predicate(filter))
+            .map_err(|e| format!("scan of '{}' failed: {}", table, e))?;
+        expect_equal(&format!("rows of '{}'", table), expected, sorted_by_id(actual))
+    }
+}
+
+fn sorted_by_id(mut rows: Vec<Row>) -> Vec<Row> {
+    rows.sort_by_key(|row| match row.values[ID] {
+        Value::Integer(id) => id,
+        _ => i64::MIN,
+    });
+    rows
+}
+
+fn compare_insert(
+    expected: Result<(), UniqueViolation>,
+    actual: Result<(), DatabaseError>,
+) -> Result<(), String> {
+    match (expected, actual) {
+        (Ok(()), Ok(())) => Ok(()),
+        (Err(violation), Err(DatabaseError::UniqueConstraintViolation { column, value, .. }))
+            if violation.column == column && violation.value == value =>
+        {
+            Ok(())
+        }
+        (expected, actual) => Err(format!(
+            "insert outcome differs: reference {:?}, bambang {:?}",
+            expected, actual
+        )),
+    }
+}
+
+fn expect_equal<T: PartialEq + std::fmt::Debug>(what: &str, expected: T, actual: T) -> Result<(), String> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(format!("{} differ:\n  reference: {:?}\n  bambang:   {:?}", what, expected, actual))
+    }
+}
+
+/// Run `ops` against a fresh harness, checking every table after the last operation.
+/// Returns the index of the failing operation (`ops.len()` for the final check) and why.
+pub fn run(ops: &[Op]) -> Result<(), (usize, String)> {
+    let mut harness = Harness::new().map_err(|e| (0, format!("setup failed: {}", e)))?;
+    for (index, op) in ops.iter().enumerate() {
+        harness.apply(op).map_err(|reason| (index, reason))?;
+    }
+    harness.check_all().map_err(|reason| (ops.len(), reason))
+}
+
+/// Run `ops` against a fresh database opened with `options` and return the resulting
+/// file, for comparing the on-disk effect of storage options
+pub fn file_after(ops: &[Op], options: StorageManagerOptions) -> Result<Vec<u8>, (usize, String)> {
+    let mut harness = Harness::with_options(options).map_err(|e| (0, format!("setup failed: {}", e)))?;
+    for (index, op) in ops.iter().enumerate() {
+        harness.apply(op).map_err(|reason| (index, reason))?;
+    }
+    harness
+        .into_file_bytes()
+        .map_err(|e| (ops.len(), format!("reading the database failed: {}", e)))
+}
+
+/// Reduce a failing operation list by repeatedly dropping chunks that are not needed to
+/// reproduce a failure
+pub fn shrink(mut ops: Vec<Op>) -> Vec<Op> {
+    if let Err((index, _)) = run(&ops) {
+        ops.truncate(index + 1);
+    }
+    let mut chunk = ops.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < ops.len() {
+            let end = (start + chunk).min(ops.len());
+            let candidate: Vec<Op> = ops[..start].iter().chain(&ops[end..]).cloned().collect();
+            if run(&candidate).is_err() {
+                ops = candidate;
+            } else {
+                start += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+    ops
+}
+
+/// Run the workload for `seed`, panicking with a shrunk reproduction on mismatch
+pub fn check_seed(seed: u64, len: usize) {
+    let ops = generate(seed, len);
+    if let Err((index, reason)) = run(&ops) {
+        let reduced = shrink(ops);
+        let outcome = run(&reduced).err().map(|(_, reason)| reason).unwrap_or_default();
+        panic!(
+            "seed {} diverged at op {}: {}\nreduced to {} ops: {:#?}\nreduced failure: {}",
+            seed,
+            index,
+            reason,
+            reduced.len(),
+            reduced,
+            outcome
+        );
+    }
+}
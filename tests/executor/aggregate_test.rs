@@ -0,0 +1,299 @@
+use bambang::{
+    executor::{
+        aggregate::{AggFunc, Aggregate, AggregateExecutor, AggregateSpec, aggregate, group_by},
+        predicate::Predicate,
+        scan::FilterScanner,
+    },
+    storage::{options::StorageManagerOptions, storage_manager::StorageManager},
+    types::{
+        error::DatabaseError,
+        row::Row,
+        value::{Value, ValueComparison},
+    },
+    utils::mock::TempDatabase,
+};
+
+fn seed_scores(storage: &mut StorageManager) -> Result<(), DatabaseError> {
+    storage.create_table(
+        "scores",
+        "CREATE TABLE scores(id INTEGER, name TEXT, points INTEGER, ratio REAL)",
+    )?;
+    let rows = vec![
+        (1, "ani", Value::Integer(10), Value::Real(0.5)),
+        (2, "budi", Value::Integer(30), Value::Real(1.5)),
+        (3, "cici", Value::Null, Value::Real(2.5)),
+        (4, "dina", Value::Integer(20), Value::Null),
+    ];
+    for (id, name, points, ratio) in rows {
+        storage.insert_into_table(
+            "scores",
+            Row::new(vec![Value::Integer(id), Value::Text(name.to_string()), points, ratio]),
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_aggregate_functions_skip_nulls() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("aggregate_functions");
+    let storage = temp_db.create_storage_manager().unwrap();
+    seed_scores(storage)?;
+
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Sum, None)?, Value::Integer(60));
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Avg, None)?, Value::Real(20.0));
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Min, None)?, Value::Integer(10));
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Max, None)?, Value::Integer(30));
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Count, None)?, Value::Integer(3));
+    assert_eq!(aggregate(storage, "scores", "*", AggFunc::Count, None)?, Value::Integer(4));
+
+    assert_eq!(aggregate(storage, "scores", "ratio", AggFunc::Sum, None)?, Value::Real(4.5));
+    assert_eq!(aggregate(storage, "scores", "ratio", AggFunc::Avg, None)?, Value::Real(1.5));
+    assert_eq!(
+        aggregate(storage, "scores", "name", AggFunc::Max, None)?,
+        Value::Text("dina".to_string())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_aggregate_with_predicate_and_empty_input() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("aggregate_empty");
+    let storage = temp_db.create_storage_manager().unwrap();
+    seed_scores(storage)?;
+
+    let over_15 = || Some(Predicate::gt("points".to_string(), Value::Integer(15)));
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Sum, over_15())?, Value::Integer(50));
+    assert_eq!(aggregate(storage, "scores", "id", AggFunc::Min, over_15())?, Value::Integer(2));
+
+    let none = || Some(Predicate::gt("points".to_string(), Value::Integer(100)));
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Sum, none())?, Value::Null);
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Avg, none())?, Value::Null);
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Min, none())?, Value::Null);
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Max, none())?, Value::Null);
+    assert_eq!(aggregate(storage, "scores", "points", AggFunc::Count, none())?, Value::Integer(0));
+    assert_eq!(aggregate(storage, "scores", "*", AggFunc::Count, none())?, Value::Integer(0));
+
+    assert!(matches!(
+        aggregate(storage, "scores", "missing", AggFunc::Sum, None),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    assert!(matches!(
+        aggregate(storage, "nope", "points", AggFunc::Sum, None),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_aggregate_spec_in_one_pass() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("aggregate_spec");
+    let storage = temp_db.create_storage_manager().unwrap();
+    seed_scores(storage)?;
+
+    let spec = AggregateSpec::new()
+        .with_aggregate(Aggregate::count_star())
+        .with_aggregate(Aggregate::count("ratio"))
+        .with_aggregate(Aggregate::sum("points"))
+        .with_aggregate(Aggregate::min("ratio"))
+        .with_aggregate(Aggregate::max("points"))
+        .with_aggregate(Aggregate::avg("ratio"));
+    assert_eq!(
+        storage.aggregate("scores", None, spec.clone())?,
+        vec![
+            Value::Integer(4),
+            Value::Integer(3),
+            Value::Integer(60),
+            Value::Real(0.5),
+            Value::Integer(30),
+            Value::Real(1.5),
+        ]
+    );
+
+    // The executor works over any scanner, filtered or not
+    let schema = storage.get_table_schema("scores").unwrap().clone();
+    let predicate = Predicate::is_not_null("ratio".to_string());
+    let mut scanner = FilterScanner::new(storage.create_scanner("scores", None)?, predicate, schema.clone())?;
+    let values = AggregateExecutor::new(&schema, spec)?.execute(&mut scanner)?;
+    assert_eq!(values[0], Value::Integer(3));
+    assert_eq!(values[2], Value::Integer(40));
+    Ok(())
+}
+
+#[test]
+fn test_aggregate_rejects_text_sum_and_promotes_overflow() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("aggregate_overflow");
+    let storage = temp_db.create_storage_manager().unwrap();
+    seed_scores(storage)?;
+
+    let err = storage
+        .aggregate("scores", None, AggregateSpec::new().with_aggregate(Aggregate::sum("name")))
+        .unwrap_err();
+    assert!(err.to_string().contains("SUM(name)"), "{}", err);
+    assert!(aggregate(storage, "scores", "name", AggFunc::Avg, None).is_err());
+    // MIN/MAX/COUNT are fine on text
+    assert_eq!(aggregate(storage, "scores", "name", AggFunc::Count, None)?, Value::Integer(4));
+
+    storage.create_table("big", "CREATE TABLE big(id INTEGER, amount INTEGER)")?;
+    for (id, amount) in [(1, i64::MAX), (2, 10), (3, -5)] {
+        storage.insert_into_table("big", Row::new(vec![Value::Integer(id), Value::Integer(amount)]))?;
+    }
+    let expected = i64::MAX as f64 + 5.0;
+    assert_eq!(aggregate(storage, "big", "amount", AggFunc::Sum, None)?, Value::Real(expected));
+    assert_eq!(aggregate(storage, "big", "amount", AggFunc::Avg, None)?, Value::Real(expected / 3.0));
+    assert_eq!(aggregate(storage, "big", "amount", AggFunc::Max, None)?, Value::Integer(i64::MAX));
+    Ok(())
+}
+
+#[test]
+fn test_aggregate_matches_naive_computation_on_50k_rows() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("aggregate_50k");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("readings", "CREATE TABLE readings(id INTEGER, reading INTEGER)")?;
+
+    let readings: Vec<Option<i64>> = (0..50_000i64)
+        .map(|i| if i % 7 == 0 { None } else { Some((i * 7919) % 1000 - 500) })
+        .collect();
+    let rows = readings
+        .iter()
+        .enumerate()
+        .map(|(id, reading)| {
+            let value = reading.map(Value::Integer).unwrap_or(Value::Null);
+            Row::new(vec![Value::Integer(id as i64), value])
+        })
+        .collect();
+    storage.insert_batch_into_table("readings", rows)?;
+
+    let present: Vec<i64> = readings.iter().flatten().copied().collect();
+    let sum: i64 = present.iter().sum();
+    let spec = AggregateSpec::new()
+        .with_aggregate(Aggregate::count_star())
+        .with_aggregate(Aggregate::count("reading"))
+        .with_aggregate(Aggregate::sum("reading"))
+        .with_aggregate(Aggregate::min("reading"))
+        .with_aggregate(Aggregate::max("reading"))
+        .with_aggregate(Aggregate::avg("reading"));
+    let values = storage.aggregate("readings", None, spec)?;
+    assert_eq!(values[0], Value::Integer(50_000));
+    assert_eq!(values[1], Value::Integer(present.len() as i64));
+    assert_eq!(values[2], Value::Integer(sum));
+    assert_eq!(values[3], Value::Integer(*present.iter().min().unwrap()));
+    assert_eq!(values[4], Value::Integer(*present.iter().max().unwrap()));
+    let Value::Real(avg) = values[5] else {
+        panic!("AVG should be real, got {:?}", values[5]);
+    };
+    assert!((avg - sum as f64 / present.len() as f64).abs() < 1e-9);
+    Ok(())
+}
+
+#[test]
+fn test_group_by_region_sums_amounts() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("aggregate_group_by");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table(
+        "sales",
+        "CREATE TABLE sales(id INTEGER, region TEXT, product TEXT, amount INTEGER)",
+    )?;
+    let sales = [
+        (1, Value::Text("west".to_string()), "pen", 10),
+        (2, Value::Text("east".to_string()), "pen", 5),
+        (3, Value::Text("west".to_string()), "ink", 7),
+        (4, Value::Null, "pen", 1),
+        (5, Value::Text("east".to_string()), "ink", 20),
+        (6, Value::Text("west".to_string()), "pen", 3),
+        (7, Value::Null, "ink", 2),
+    ];
+    for (id, region, product, amount) in sales {
+        storage.insert_into_table(
+            "sales",
+            Row::new(vec![
+                Value::Integer(id),
+                region,
+                Value::Text(product.to_string()),
+                Value::Integer(amount),
+            ]),
+        )?;
+    }
+    let text = |s: &str| Value::Text(s.to_string());
+
+    let by_region = group_by(
+        storage,
+        "sales",
+        vec!["region".to_string()],
+        ("amount".to_string(), AggFunc::Sum),
+        None,
+    )?;
+    assert_eq!(
+        by_region,
+        vec![
+            (vec![Value::Null], Value::Integer(3)),
+            (vec![text("east")], Value::Integer(25)),
+            (vec![text("west")], Value::Integer(20)),
+        ]
+    );
+
+    let by_region_and_product = group_by(
+        storage,
+        "sales",
+        vec!["region".to_string(), "product".to_string()],
+        ("*".to_string(), AggFunc::Count),
+        Some(Predicate::gt("amount".to_string(), Value::Integer(2))),
+    )?;
+    assert_eq!(
+        by_region_and_product,
+        vec![
+            (vec![text("east"), text("ink")], Value::Integer(1)),
+            (vec![text("east"), text("pen")], Value::Integer(1)),
+            (vec![text("west"), text("ink")], Value::Integer(1)),
+            (vec![text("west"), text("pen")], Value::Integer(2)),
+        ]
+    );
+
+    assert!(matches!(
+        group_by(storage, "sales", vec!["zone".to_string()], ("amount".to_string(), AggFunc::Sum), None),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_group_by_follows_value_comparison() -> Result<(), DatabaseError> {
+    for mode in [ValueComparison::Coercive, ValueComparison::Strict] {
+        let mut temp_db = TempDatabase::with_prefix("aggregate_group_by_comparison");
+        let options = StorageManagerOptions::new().with_value_comparison(mode);
+        let storage = temp_db.create_storage_manager_with_options(options).unwrap();
+        storage.create_table("readings", "CREATE TABLE readings(id INTEGER, level REAL, amount INTEGER)")?;
+        for (id, level, amount) in [
+            (1, Value::Integer(1), 10),
+            (2, Value::Real(1.0), 5),
+            (3, Value::Real(2.5), 7),
+            (4, Value::Integer(1), 1),
+        ] {
+            storage.insert_into_table(
+                "readings",
+                Row::new(vec![Value::Integer(id), level, Value::Integer(amount)]),
+            )?;
+        }
+
+        let by_level = group_by(
+            storage,
+            "readings",
+            vec!["level".to_string()],
+            ("amount".to_string(), AggFunc::Sum),
+            None,
+        )?;
+        let sums: Vec<Value> = by_level.iter().map(|(_, sum)| sum.clone()).collect();
+        match mode {
+            ValueComparison::Coercive => {
+                assert_eq!(sums, vec![Value::Integer(16), Value::Integer(7)]);
+            }
+            ValueComparison::Strict => {
+                // 1 and 1.0 tie in key order, so compare the groups themselves
+                assert_eq!(by_level.len(), 3);
+                assert!(by_level.contains(&(vec![Value::Integer(1)], Value::Integer(11))), "{:?}", by_level);
+                assert!(by_level.contains(&(vec![Value::Real(1.0)], Value::Integer(5))), "{:?}", by_level);
+                assert_eq!(by_level[2], (vec![Value::Real(2.5)], Value::Integer(7)));
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,11 @@
-use std::fs;
 use tempfile::tempdir;
 
 use bambang::{
-    executor::create_table::{CreateTableExecutor, TableCreator, TableSchemaBuilder},
+    executor::{create_table::TableSchemaBuilder, statement::execute_statement},
     storage::{
+        options::StorageManagerOptions,
         storage_manager::StorageManager,
-        schema::{ColumnSchema, TableSchema},
+        schema::ColumnSchema,
     },
     types::{
         error::DatabaseError,
@@ -403,4 +403,123 @@ fn test_table_schema_validation() {
     
     let result = storage_manager.validate_row("validation_test", &wrong_count_row);
     assert!(result.is_err());
-}
\ No newline at end of file
+}
+#[test]
+fn test_type_mismatch_suggests_cast() {
+    let (mut storage_manager, _temp_dir) = setup_test_db();
+    let columns = vec![
+        ColumnSchema::new("id".to_string(), DataType::Integer, 0).primary_key(),
+        ColumnSchema::new("note".to_string(), DataType::Text, 1),
+    ];
+    storage_manager
+        .create_table_with_schema("hints".to_string(), columns, "CREATE TABLE hints (id INTEGER PRIMARY KEY, note TEXT)".to_string())
+        .unwrap();
+
+    let parsable = Row::new(vec![Value::Text("42".to_string()), Value::Null]);
+    let message = storage_manager.validate_row("hints", &parsable).unwrap_err().to_string();
+    assert!(message.contains("Column 'id' expects INTEGER but got TEXT value '42'"), "{}", message);
+    assert!(message.contains("hint: value parses as INTEGER"), "{}", message);
+
+    let long_text = "x".repeat(100);
+    let garbage = Row::new(vec![Value::Text(long_text), Value::Null]);
+    match storage_manager.validate_row("hints", &garbage) {
+        Err(DatabaseError::ColumnTypeMismatch { column, expected, actual, preview, hint }) => {
+            assert_eq!(column, "id");
+            assert_eq!(expected, DataType::Integer);
+            assert_eq!(actual, DataType::Text);
+            assert_eq!(preview, format!("'{}...'", "x".repeat(32)));
+            assert!(hint.is_empty());
+        }
+        other => panic!("expected ColumnTypeMismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_implicit_coercion_applies_only_safe_casts() {
+    let temp_dir = tempdir().unwrap();
+    let options = StorageManagerOptions::new().with_implicit_coercion(true);
+    let mut storage_manager = StorageManager::open_with_options(temp_dir.path().join("test.db"), options).unwrap();
+    let columns = vec![
+        ColumnSchema::new("id".to_string(), DataType::Integer, 0).primary_key(),
+        ColumnSchema::new("payload".to_string(), DataType::Blob, 1),
+    ];
+    storage_manager
+        .create_table_with_schema("imports".to_string(), columns, "CREATE TABLE imports (id INTEGER PRIMARY KEY, payload BLOB)".to_string())
+        .unwrap();
+
+    let mut safe = Row::new(vec![Value::Text("42".to_string()), Value::Blob(vec![1])]);
+    storage_manager.coerce_row("imports", &mut safe).unwrap();
+    assert_eq!(safe.values[0], Value::Integer(42));
+
+    let mut lossy = Row::new(vec![Value::Real(1.5), Value::Null]);
+    assert!(matches!(
+        storage_manager.coerce_row("imports", &mut lossy),
+        Err(DatabaseError::ColumnTypeMismatch { .. })
+    ));
+    assert_eq!(lossy.values[0], Value::Real(1.5));
+
+    // Text could be stored as bytes, but BLOB columns never coerce implicitly
+    let mut text_blob = Row::new(vec![Value::Integer(1), Value::Text("raw".to_string())]);
+    match storage_manager.coerce_row("imports", &mut text_blob) {
+        Err(DatabaseError::ColumnTypeMismatch { column, hint, .. }) => {
+            assert_eq!(column, "payload");
+            assert!(hint.is_empty());
+        }
+        other => panic!("expected ColumnTypeMismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_check_constraint_rejects_rows_that_make_it_false() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    {
+        let mut storage_manager = StorageManager::new(&db_path).unwrap();
+        execute_statement(
+            &mut storage_manager,
+            "CREATE TABLE people (id INTEGER PRIMARY KEY, age INTEGER CHECK (age >= 0), name TEXT)",
+        )
+        .unwrap();
+
+        let negative = execute_statement(&mut storage_manager, "INSERT INTO people VALUES (1, -5, 'Ann')");
+        match negative {
+            Err(DatabaseError::CheckConstraintViolation { constraint, column }) => {
+                assert_eq!(column, "age");
+                assert_eq!(constraint, "age >= 0");
+            }
+            other => panic!("expected CheckConstraintViolation, got {:?}", other),
+        }
+        // Callers that skip validation are held to it too, and an unknown outcome passes
+        let row = Row::new(vec![Value::Integer(2), Value::Integer(-1), Value::Null]);
+        assert!(matches!(
+            storage_manager.insert_into_table("people", row),
+            Err(DatabaseError::CheckConstraintViolation { .. })
+        ));
+        execute_statement(&mut storage_manager, "INSERT INTO people VALUES (3, 30, 'Bo'), (4, NULL, 'Cy')").unwrap();
+        assert_eq!(storage_manager.scan_table("people", None).unwrap().len(), 2);
+    }
+
+    // The constraint survives a reopen and covers updates
+    let mut storage_manager = StorageManager::new(&db_path).unwrap();
+    let schema = storage_manager.get_table_schema("people").unwrap();
+    assert_eq!(schema.get_column("age").unwrap().check.as_ref().map(ToString::to_string).as_deref(), Some("age >= 0"));
+    let update = storage_manager.update_table("people", None, &[("age".to_string(), Value::Integer(-2))]);
+    assert!(matches!(update, Err(DatabaseError::CheckConstraintViolation { .. })));
+    let batch = vec![
+        Row::new(vec![Value::Integer(5), Value::Integer(1), Value::Null]),
+        Row::new(vec![Value::Integer(6), Value::Integer(-6), Value::Null]),
+    ];
+    assert!(storage_manager.insert_batch_into_table("people", batch).is_err());
+    let ages: Vec<Value> = storage_manager
+        .scan_table_projected("people", &["age"], None)
+        .unwrap()
+        .into_iter()
+        .map(|row| row.values[0].clone())
+        .collect();
+    assert!(ages.iter().all(|age| matches!(age, Value::Null | Value::Integer(0..))), "{:?}", ages);
+
+    assert!(matches!(
+        execute_statement(&mut storage_manager, "CREATE TABLE bad (a INTEGER CHECK (b > 0))"),
+        Err(DatabaseError::InvalidData { .. })
+    ));
+}
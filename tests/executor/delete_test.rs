@@ -0,0 +1,155 @@
+use std::fs::OpenOptions;
+
+use bambang::{
+    executor::{
+        delete::{DeleteExecutor, Deleter},
+        predicate::Predicate,
+    },
+    storage::{BAMBANG_HEADER_SIZE, bplus_tree::BPlusTree, storage_manager::StorageManager},
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::TempDatabase,
+};
+
+fn insert_items(storage: &mut StorageManager, ids: impl Iterator<Item = i64>) -> Result<(), DatabaseError> {
+    for id in ids {
+        storage.insert_into_table(
+            "items",
+            Row::new(vec![Value::Integer(id), Value::Text(format!("item_{}", id))]),
+        )?;
+    }
+    Ok(())
+}
+
+fn scanned_ids(storage: &StorageManager) -> Result<Vec<i64>, DatabaseError> {
+    let mut ids: Vec<i64> = storage
+        .scan_table("items", None)?
+        .iter()
+        .map(|row| match row.values[0] {
+            Value::Integer(id) => id,
+            _ => panic!("Expected integer ID"),
+        })
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+#[test]
+fn test_delete_interleaved_rows_then_reinsert() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("delete_interleaved");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+    insert_items(storage, 1..=30)?;
+    let root_page_id = storage.table_roots["items"];
+    let page_count = storage.db_info.page_count;
+
+    let evens: Vec<Value> = (1..=30).filter(|id| id % 2 == 0).map(Value::Integer).collect();
+    let deleted = storage.delete_from_table("items", Some(Predicate::in_list("id".to_string(), evens)))?;
+    assert_eq!(deleted, 15);
+    assert_eq!(scanned_ids(storage)?, (1..=30).filter(|id| id % 2 == 1).collect::<Vec<_>>());
+
+    // The compaction pass leaves no dead bytes behind, and drops the deleted slot of
+    // the last row
+    let file = OpenOptions::new().read(true).write(true).open(&temp_db.path)?;
+    let extras = Some(BAMBANG_HEADER_SIZE as u64);
+    let mut btree = BPlusTree::new_with_extras(file, root_page_id, extras)?;
+    let stats = btree.load_page(root_page_id, extras)?.get_page_stats();
+    assert_eq!(stats.active_slots, 15);
+    assert_eq!(stats.deleted_slots, 14);
+    assert_eq!(stats.wasted_space, 0);
+
+    let storage = temp_db.get_storage_manager().unwrap();
+    insert_items(storage, (1..=30).filter(|id| id % 2 == 0))?;
+    assert_eq!(scanned_ids(storage)?, (1..=30).collect::<Vec<_>>());
+    assert_eq!(storage.db_info.page_count, page_count);
+    Ok(())
+}
+
+#[test]
+fn test_delete_without_predicate_empties_table() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("delete_truncate");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+    insert_items(storage, 1..=300)?;
+
+    assert_eq!(storage.delete_from_table("items", None)?, 300);
+    assert!(scanned_ids(storage)?.is_empty());
+    assert!(storage.table_exists("items"));
+    assert_eq!(storage.get_table_schema("items").unwrap().columns.len(), 2);
+    assert_eq!(storage.delete_from_table("items", None)?, 0);
+
+    insert_items(storage, 1..=3)?;
+    assert_eq!(scanned_ids(storage)?, vec![1, 2, 3]);
+    Ok(())
+}
+
+#[test]
+fn test_deleted_rows_stay_deleted_after_reopen() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("delete_reopen");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+    insert_items(storage, 1..=10)?;
+    let deleted = storage.delete_from_table("items", Some(Predicate::gt("id".to_string(), Value::Integer(7))))?;
+    assert_eq!(deleted, 3);
+    temp_db.storage_manager = None;
+
+    let reopened = StorageManager::new(&temp_db.path)?;
+    assert_eq!(scanned_ids(&reopened)?, (1..=7).collect::<Vec<_>>());
+    let missing = reopened.scan_table("items", Some(Predicate::eq("id".to_string(), Value::Integer(9))))?;
+    assert!(missing.is_empty());
+    temp_db.storage_manager = Some(reopened);
+
+    let storage = temp_db.get_storage_manager().unwrap();
+    let invalid = storage.delete_from_table("items", Some(Predicate::eq("nope".to_string(), Value::Integer(1))));
+    assert!(invalid.is_err());
+    assert!(matches!(
+        storage.delete_from_table("missing", None),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_delete_executor_removes_minors() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("delete_executor");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("people", "CREATE TABLE people(id INTEGER, name TEXT, age INTEGER)")?;
+    // Enough rows to span several leaves; ids repeat so deletion cannot be by key alone
+    let ages = [12, 40, 17, 18, 65, 3, 30];
+    for i in 0..140i64 {
+        let age = ages[i as usize % ages.len()];
+        storage.insert_into_table(
+            "people",
+            Row::new(vec![
+                Value::Integer(i % 70),
+                Value::Text(format!("person_{}_{}", i, "x".repeat(40))),
+                Value::Integer(age),
+            ]),
+        )?;
+    }
+
+    let mut executor = DeleteExecutor::new(storage, "people".to_string())?;
+    assert_eq!(executor.table_name(), "people");
+    let deleted = executor.delete_where(Predicate::lt("age".to_string(), Value::Integer(18)))?;
+    assert_eq!(deleted, 60);
+    assert!(matches!(
+        executor.delete_where(Predicate::lt("height".to_string(), Value::Integer(1))),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+
+    let survivors = storage.scan_table("people", None)?;
+    assert_eq!(survivors.len(), 80);
+    assert!(survivors.iter().all(|row| matches!(row.values[2], Value::Integer(age) if age >= 18)));
+    assert_eq!(
+        DeleteExecutor::new(storage, "people".to_string())?
+            .delete_where(Predicate::lt("age".to_string(), Value::Integer(18)))?,
+        0
+    );
+
+    assert!(matches!(
+        DeleteExecutor::new(storage, "ghosts".to_string()),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+    assert_eq!(DeleteExecutor::new(storage, "people".to_string())?.delete_all()?, 80);
+    assert!(storage.scan_table("people", None)?.is_empty());
+    Ok(())
+}
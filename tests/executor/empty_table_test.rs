@@ -0,0 +1,155 @@
+use bambang::{
+    executor::{
+        aggregate::{AggFunc, Aggregate, AggregateSpec, group_by},
+        distinct::DistinctExecutor,
+        predicate::Predicate,
+        scan::{BatchPolicy, Scanner},
+        sequential_scan::SequentialScanner,
+    },
+    planner::types::SortOrder,
+    storage::{page_offset, storage_manager::StorageManager},
+    types::{
+        error::DatabaseError,
+        page::{Page, PageType},
+        row::Row,
+        value::Value,
+    },
+    utils::mock::TempDatabase,
+};
+
+fn create_items(storage: &mut StorageManager) -> Result<(), DatabaseError> {
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, label TEXT, weight REAL)")?;
+    Ok(())
+}
+
+fn insert_items(storage: &mut StorageManager, ids: impl Iterator<Item = i64>) -> Result<(), DatabaseError> {
+    for id in ids {
+        storage.insert_into_table(
+            "items",
+            Row::new(vec![
+                Value::Integer(id),
+                Value::Text(format!("item_{}_{}", id, "x".repeat(60))),
+                Value::Real(id as f64),
+            ]),
+        )?;
+    }
+    Ok(())
+}
+
+/// Run every read path over `items`, which must hold no rows
+fn assert_reads_empty(storage: &StorageManager) -> Result<(), DatabaseError> {
+    assert!(storage.is_table_empty("items")?);
+    assert!(storage.scan_table("items", None)?.is_empty());
+    assert!(storage.scan_table("items", Some(Predicate::gt("id".to_string(), Value::Integer(0))))?.is_empty());
+    assert!(storage.scan_table_projected("items", &["label"], None)?.is_empty());
+    assert!(
+        storage
+            .scan_table_sorted("items", None, vec![("weight".to_string(), SortOrder::Descending)])?
+            .is_empty()
+    );
+    assert!(storage.scan_table_distinct("items", &["label"], None)?.is_empty());
+    assert_eq!(storage.count_rows("items", None)?, 0);
+    assert_eq!(storage.count_rows("items", Some(Predicate::is_null("label".to_string())))?, 0);
+
+    let spec = AggregateSpec::new()
+        .with_aggregate(Aggregate::count_star())
+        .with_aggregate(Aggregate::count("label"))
+        .with_aggregate(Aggregate::sum("id"))
+        .with_aggregate(Aggregate::avg("weight"))
+        .with_aggregate(Aggregate::min("label"))
+        .with_aggregate(Aggregate::max("weight"));
+    assert_eq!(
+        storage.aggregate("items", None, spec)?,
+        vec![Value::Integer(0), Value::Integer(0), Value::Null, Value::Null, Value::Null, Value::Null]
+    );
+    assert!(
+        group_by(storage, "items", vec!["label".to_string()], ("id".to_string(), AggFunc::Sum), None)?
+            .is_empty()
+    );
+
+    let mut scanner = SequentialScanner::new(storage, "items".to_string(), None)?;
+    assert!(scanner.scan_batch(64)?.is_empty());
+    assert!(scanner.scan_batch_with_policy(BatchPolicy::Auto)?.is_empty());
+    assert!(scanner.scan()?.is_none());
+    assert_eq!(scanner.stats().pages_read, 0);
+    assert_eq!(scanner.stats().rows_scanned, 0);
+
+    let scanner = SequentialScanner::new(storage, "items".to_string(), None)?;
+    let mut distinct = DistinctExecutor::new(scanner).with_spill(1);
+    assert!(distinct.scan()?.is_none());
+    assert_eq!(distinct.into_inner().stats().pages_read, 0);
+    Ok(())
+}
+
+#[test]
+fn test_fresh_table_is_empty_for_every_executor() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("empty_fresh");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_items(storage)?;
+    assert_reads_empty(storage)?;
+
+    assert_eq!(storage.update_table("items", None, &[("label".to_string(), Value::Null)])?, 0);
+    assert_eq!(storage.delete_from_table("items", None)?, 0);
+
+    // Subqueries over an empty table match nothing, and NOT IN everything
+    storage.create_table("orders", "CREATE TABLE orders(id INTEGER, item_id INTEGER)")?;
+    for id in 1..=3 {
+        storage.insert_into_table("orders", Row::new(vec![Value::Integer(id), Value::Integer(id)]))?;
+    }
+    let in_items = Predicate::in_table("item_id".to_string(), "items".to_string(), "id".to_string());
+    assert!(storage.scan_table("orders", Some(in_items))?.is_empty());
+    let not_in_items = Predicate::not_in_table("item_id".to_string(), "items".to_string(), "id".to_string());
+    assert_eq!(storage.scan_table("orders", Some(not_in_items))?.len(), 3);
+
+    assert!(!storage.is_table_empty("orders")?);
+    assert!(matches!(storage.is_table_empty("ghosts"), Err(DatabaseError::TableNotFound { .. })));
+    assert!(matches!(
+        storage.aggregate("items", Some(Predicate::eq("nope".to_string(), Value::Integer(1))), AggregateSpec::new()),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_table_emptied_by_delete_shrinks_to_one_leaf() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("empty_after_delete");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_items(storage)?;
+    insert_items(storage, 1..=400)?;
+    assert!(storage.iter_table_pages("items")?.page_ids().len() > 1);
+
+    assert_eq!(storage.delete_from_table("items", None)?, 400);
+    assert_eq!(storage.iter_table_pages("items")?.page_ids().len(), 1);
+    assert_reads_empty(storage)?;
+
+    insert_items(storage, 1000..=1000)?;
+    assert!(!storage.is_table_empty("items")?);
+    assert_eq!(storage.count_rows("items", None)?, 1);
+    assert_eq!(storage.scan_table("items", None)?[0].values[0], Value::Integer(1000));
+    Ok(())
+}
+
+#[test]
+fn test_interior_root_without_children_scans_as_empty() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("empty_interior_root");
+    let path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_items(storage)?;
+    let root_page_id = storage.table_roots["items"];
+
+    {
+        use std::io::{Seek, SeekFrom, Write};
+        let mut root = Page::new(root_page_id, PageType::InteriorTable);
+        root.update_checksum();
+        let mut file = std::fs::OpenOptions::new().write(true).open(&path)?;
+        file.seek(SeekFrom::Start(page_offset(root_page_id)))?;
+        file.write_all(&root.to_bytes()?)?;
+    }
+
+    assert_reads_empty(storage)?;
+    let mut scanner = SequentialScanner::new(storage, "items".to_string(), None)?;
+    assert_eq!(scanner.count_active_cells()?, 0);
+    scanner.reset()?;
+    assert!(scanner.scan()?.is_none());
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use bambang::{
+    executor::statement::{StatementResult, execute_statement},
+    storage::storage_manager::StorageManager,
+    types::{error::DatabaseError, value::Value},
+    utils::mock::TempDatabase,
+};
+
+fn create_fixture(storage: &mut StorageManager) -> Result<(), DatabaseError> {
+    for sql in [
+        "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT, age INTEGER)",
+        "INSERT INTO users VALUES (1, 'Ann', 30), (2, 'Bob', 17), (3, 'Cy', 45), (4, 'Di', 22)",
+        "CREATE TABLE orders(id INTEGER PRIMARY KEY, user_id INTEGER, total INTEGER)",
+        "INSERT INTO orders VALUES (10, 1, 250), (11, 3, 40), (12, 1, 75), (13, 5, 10), (14, NULL, 5)",
+    ] {
+        execute_statement(storage, sql)?;
+    }
+    Ok(())
+}
+
+fn explain(storage: &mut StorageManager, query: &str) -> Result<String, DatabaseError> {
+    match execute_statement(storage, &format!("EXPLAIN ANALYZE {}", query))? {
+        StatementResult::Explained { plan } => Ok(plan.render_without_timings()),
+        other => panic!("expected a plan, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_explain_analyze_filtered_scan() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("explain_filtered_scan");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_fixture(storage)?;
+
+    let plan = explain(storage, "SELECT * FROM users WHERE age >= 18")?;
+    assert_eq!(
+        plan,
+        concat!(
+            "Projection: id, name, age  (rows in=3 out=3)\n",
+            "  ->  Filter: age >= 18  (rows in=4 out=3, comparisons=4)\n",
+            "        ->  Seq Scan on users  (rows in=4 out=4, pages read=1)\n",
+        )
+    );
+    Ok(())
+}
+
+#[test]
+fn test_explain_analyze_sorted_projection() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("explain_sorted_projection");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_fixture(storage)?;
+
+    let plan = explain(storage, "SELECT name, age AS years FROM users ORDER BY years DESC, id LIMIT 2")?;
+    assert_eq!(
+        plan,
+        concat!(
+            "Limit: 2  (rows in=2 out=2)\n",
+            "  ->  Projection: name, age  (rows in=2 out=2)\n",
+            "        ->  Sort: age DESC, id  (rows in=4 out=2, memory=154 bytes, spilled=no)\n",
+            "              ->  Seq Scan on users  (rows in=4 out=4, pages read=1)\n",
+        )
+    );
+    Ok(())
+}
+
+#[test]
+fn test_explain_analyze_two_table_join() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("explain_two_table_join");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_fixture(storage)?;
+
+    let plan = explain(
+        storage,
+        "SELECT u.name, o.total FROM orders o JOIN users u ON o.user_id = u.id WHERE o.total > 50",
+    )?;
+    assert_eq!(
+        plan,
+        concat!(
+            "Projection: u.name, o.total  (rows in=2 out=2)\n",
+            "  ->  Filter: \"o.total\" > 50  (rows in=3 out=2, comparisons=3)\n",
+            "        ->  Hash Join: o.user_id = u.id  (rows in=9 out=3, build rows=4, buckets=4, probe rows=5)\n",
+            "              ->  Seq Scan on orders  (rows in=5 out=5, pages read=1)\n",
+            "              ->  Seq Scan on users  (rows in=4 out=4, pages read=1)\n",
+        )
+    );
+    Ok(())
+}
+
+fn rows(storage: &mut StorageManager, query: &str) -> Result<Vec<Vec<Value>>, DatabaseError> {
+    match execute_statement(storage, query)? {
+        StatementResult::Rows { rows, .. } => Ok(rows.into_iter().map(|row| row.values).collect()),
+        other => panic!("expected rows, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_explain_analyze_elides_sort_on_table_key() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("explain_elide_key_sort");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_fixture(storage)?;
+
+    let plan = explain(storage, "SELECT name FROM users WHERE age >= 18 ORDER BY id DESC")?;
+    assert_eq!(
+        plan,
+        concat!(
+            "Projection: name  (rows in=3 out=3)\n",
+            "  ->  Filter: age >= 18  (rows in=4 out=3, comparisons=4)\n",
+            "        ->  Index Scan Backward using key on users, sort elided (index order)  (rows in=4 out=4, pages read=1)\n",
+        )
+    );
+    assert_eq!(
+        rows(storage, "SELECT name FROM users WHERE age >= 18 ORDER BY id DESC")?,
+        vec![
+            vec![Value::Text("Di".to_string())],
+            vec![Value::Text("Cy".to_string())],
+            vec![Value::Text("Ann".to_string())],
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_explain_analyze_elides_sort_on_composite_key_prefix() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("explain_elide_composite_sort");
+    let storage = temp_db.create_storage_manager().unwrap();
+    execute_statement(storage, "CREATE TABLE grid(x INTEGER, y INTEGER, label TEXT, PRIMARY KEY (x, y))")?;
+    execute_statement(storage, "INSERT INTO grid VALUES (2, 1, 'c'), (1, 2, 'b'), (2, 0, 'd'), (1, 1, 'a')")?;
+
+    let plan = explain(storage, "SELECT label FROM grid ORDER BY x DESC, y DESC")?;
+    assert!(plan.contains("Index Scan Backward using key on grid, sort elided (index order)"), "{}", plan);
+    assert_eq!(
+        rows(storage, "SELECT label FROM grid ORDER BY x DESC, y DESC")?,
+        ["c", "d", "b", "a"].map(|label| vec![Value::Text(label.to_string())])
+    );
+    // Columns out of key order still need the Sort
+    assert!(explain(storage, "SELECT label FROM grid ORDER BY y")?.contains("->  Sort: y"));
+    Ok(())
+}
+
+#[test]
+fn test_explain_analyze_elides_sort_on_indexed_column() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("explain_elide_index_sort");
+    let storage = temp_db.create_storage_manager().unwrap();
+    execute_statement(storage, "CREATE TABLE items(id INTEGER PRIMARY KEY, rank INTEGER NOT NULL, copy INTEGER)")?;
+    execute_statement(storage, "INSERT INTO items VALUES (1, 3, 3), (2, 1, 1), (3, 2, 2), (4, 1, 1), (5, 3, 3)")?;
+    storage.create_index("items", "rank")?;
+
+    for (order, scan) in [("", "Index Scan"), (" DESC", "Index Scan Backward")] {
+        let plan = explain(storage, &format!("SELECT id FROM items ORDER BY rank{}", order))?;
+        assert_eq!(
+            plan,
+            format!(
+                concat!(
+                    "Projection: id  (rows in=5 out=5)\n",
+                    "  ->  {} using idx_items_rank on items, sort elided (index order)  (rows in=5 out=5, pages read=1)\n",
+                ),
+                scan
+            )
+        );
+    }
+    assert_eq!(
+        rows(storage, "SELECT id FROM items ORDER BY rank")?,
+        [2, 4, 3, 1, 5].map(|id| vec![Value::Integer(id)])
+    );
+    assert_eq!(
+        rows(storage, "SELECT id FROM items ORDER BY rank DESC")?,
+        [5, 1, 3, 4, 2].map(|id| vec![Value::Integer(id)])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_explain_analyze_keeps_sort_index_cannot_provide() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("explain_keep_sort");
+    let storage = temp_db.create_storage_manager().unwrap();
+    execute_statement(storage, "CREATE TABLE items(id INTEGER PRIMARY KEY, rank INTEGER NOT NULL, note INTEGER)")?;
+    execute_statement(storage, "INSERT INTO items VALUES (1, 3, NULL), (2, 1, 7), (3, 2, 5)")?;
+    storage.create_index("items", "rank")?;
+    storage.create_index("items", "note")?;
+
+    // A second key, mixed directions and a nullable column all need the Sort
+    for order_by in ["rank, id", "id, rank DESC", "note"] {
+        let plan = explain(storage, &format!("SELECT id FROM items ORDER BY {}", order_by))?;
+        assert!(plan.contains("->  Sort: "), "{}: {}", order_by, plan);
+        assert!(!plan.contains("sort elided"), "{}: {}", order_by, plan);
+    }
+    assert_eq!(
+        rows(storage, "SELECT id FROM items ORDER BY note")?,
+        [1, 3, 2].map(|id| vec![Value::Integer(id)])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_elided_sort_matches_sort_across_pages() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("elided_sort_across_pages");
+    let storage = temp_db.create_storage_manager().unwrap();
+    execute_statement(
+        storage,
+        "CREATE TABLE events(id INTEGER PRIMARY KEY, bucket INTEGER NOT NULL, copy INTEGER, tag TEXT)",
+    )?;
+    // Enough rows for several leaves, with runs of equal buckets crossing leaf boundaries
+    for chunk in (0..600).collect::<Vec<i64>>().chunks(50) {
+        let values: Vec<String> = chunk
+            .iter()
+            .map(|id| {
+                let bucket = (id * 7919) % 13;
+                format!("({}, {}, {}, 'event {:04}')", (id * 389) % 600, bucket, bucket, id)
+            })
+            .collect();
+        execute_statement(storage, &format!("INSERT INTO events VALUES {}", values.join(", ")))?;
+    }
+    storage.create_index("events", "bucket")?;
+
+    for order in ["", " DESC"] {
+        let plan = explain(storage, &format!("SELECT id FROM events ORDER BY bucket{}", order))?;
+        assert!(plan.contains("sort elided (index order)"), "{}", plan);
+        assert!(!plan.contains("Sort:"), "{}", plan);
+        assert!(!plan.contains("pages read=1)"), "{}", plan);
+
+        // Rows tying on the bucket may come in either order, so compare within buckets
+        let elided = rows(storage, &format!("SELECT bucket, id FROM events ORDER BY bucket{}", order))?;
+        let mut sorted = rows(storage, &format!("SELECT copy, id FROM events ORDER BY copy{}", order))?;
+        let mut by_bucket = elided.clone();
+        assert_eq!(
+            elided.iter().map(|row| row[0].clone()).collect::<Vec<_>>(),
+            sorted.iter().map(|row| row[0].clone()).collect::<Vec<_>>()
+        );
+        by_bucket.sort_by(|a, b| a[0].total_cmp(&b[0]).then_with(|| a[1].total_cmp(&b[1])));
+        sorted.sort_by(|a, b| a[0].total_cmp(&b[0]).then_with(|| a[1].total_cmp(&b[1])));
+        assert_eq!(by_bucket, sorted);
+        assert_eq!(elided.len(), 600);
+
+        let by_id = rows(storage, &format!("SELECT id FROM events ORDER BY id{}", order))?;
+        let mut expected: Vec<Vec<Value>> = (0..600).map(|id| vec![Value::Integer(id)]).collect();
+        if order == " DESC" {
+            expected.reverse();
+        }
+        assert_eq!(by_id, expected);
+    }
+    Ok(())
+}
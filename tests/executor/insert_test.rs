@@ -1,6 +1,10 @@
 use bambang::{
-    executor::insert::{Inserter, TableInserter, InsertIterator},
-    types::{error::DatabaseError, row::Row, value::Value},
+    executor::{
+        insert::{Inserter, TableInserter, InsertIterator},
+        predicate::Predicate,
+    },
+    storage::{options::StorageManagerOptions, storage_manager::StorageManager},
+    types::{error::DatabaseError, row::Row, value::{Value, ValueComparison}},
     utils::mock::TempDatabase,
 };
 
@@ -254,3 +258,502 @@ fn test_batch_insertion() -> Result<(), DatabaseError> {
 
     Ok(())
 }
+
+#[test]
+fn test_duplicate_primary_key_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_duplicate_pk");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)")?;
+
+    storage.insert_into_table(
+        "users",
+        Row::new(vec![Value::Integer(1), Value::Text("Alice".to_string())]),
+    )?;
+    let result = storage.insert_into_table(
+        "users",
+        Row::new(vec![Value::Integer(1), Value::Text("Bob".to_string())]),
+    );
+    match result {
+        Err(DatabaseError::UniqueConstraintViolation { table, column, value }) => {
+            assert_eq!(table, "users");
+            assert_eq!(column, "id");
+            assert_eq!(value, Value::Integer(1));
+        }
+        other => panic!("Expected UniqueConstraintViolation, got {:?}", other),
+    }
+
+    let rows = storage.scan_table("users", None)?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].values[1], Value::Text("Alice".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_primary_key_of_overflow_row_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_duplicate_overflow_pk");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("files", "CREATE TABLE files(id INTEGER PRIMARY KEY, name TEXT, data BLOB)")?;
+    for id in 1..=5 {
+        let size = if id == 3 { 3000 } else { 10 };
+        storage.insert_into_table(
+            "files",
+            Row::new(vec![Value::Integer(id), Value::Text(format!("a{}", id)), Value::Blob(vec![0xAB; size])]),
+        )?;
+    }
+
+    for id in [3, 4] {
+        let result = storage.insert_into_table(
+            "files",
+            Row::new(vec![Value::Integer(id), Value::Text("copy".to_string()), Value::Blob(vec![1])]),
+        );
+        assert!(
+            matches!(result, Err(DatabaseError::UniqueConstraintViolation { ref column, .. }) if column == "id"),
+            "id {}: {:?}",
+            id,
+            result
+        );
+    }
+    assert_eq!(storage.get_by_primary_key("files", &[Value::Integer(3)])?.unwrap().values[2], Value::Blob(vec![0xAB; 3000]));
+    assert_eq!(storage.scan_table("files", None)?.len(), 5);
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_non_unique_column_allowed() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_duplicate_non_unique");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)")?;
+
+    storage.insert_into_table(
+        "users",
+        Row::new(vec![Value::Integer(1), Value::Text("Alice".to_string())]),
+    )?;
+    storage.insert_into_table(
+        "users",
+        Row::new(vec![Value::Integer(2), Value::Text("Alice".to_string())]),
+    )?;
+
+    assert_eq!(storage.scan_table("users", None)?.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_batch_failing_midway_keeps_earlier_rows() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_batch_duplicate");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)")?;
+
+    let mut rows: Vec<Row> = (1..=200)
+        .map(|i| Row::new(vec![Value::Integer(i), Value::Text(format!("user_{:0>40}", i))]))
+        .collect();
+    rows.push(Row::new(vec![Value::Integer(50), Value::Text("duplicate".to_string())]));
+    rows.push(Row::new(vec![Value::Integer(201), Value::Text("never inserted".to_string())]));
+
+    let result = storage.insert_batch_into_table("users", rows);
+    assert!(matches!(
+        result,
+        Err(DatabaseError::UniqueConstraintViolation { ref value, .. }) if *value == Value::Integer(50)
+    ));
+
+    let mut ids: Vec<i64> = storage
+        .scan_table("users", None)?
+        .iter()
+        .map(|row| match row.values[0] {
+            Value::Integer(id) => id,
+            _ => panic!("Expected integer ID"),
+        })
+        .collect();
+    ids.sort();
+    assert_eq!(ids, (1..=200).collect::<Vec<i64>>());
+    Ok(())
+}
+
+#[test]
+fn test_insert_or_ignore_skips_duplicates() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_or_ignore");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)")?;
+
+    let alice = Row::new(vec![Value::Integer(1), Value::Text("Alice".to_string())]);
+    let bob = Row::new(vec![Value::Integer(1), Value::Text("Bob".to_string())]);
+    assert!(storage.insert_or_ignore("users", alice)?);
+    assert!(!storage.insert_or_ignore("users", bob)?);
+
+    let rows = storage.scan_table("users", None)?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].values[1], Value::Text("Alice".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_primary_key_lookup_across_interior_levels() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_pk_deep_tree");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)")?;
+
+    // Long keys keep interior fan-out low so the tree grows past two levels
+    let name = "x".repeat(1000);
+    let rows: Vec<Row> = (0..600)
+        .map(|i| Row::new(vec![Value::Integer((i * 37) % 600), Value::Text(name.clone())]))
+        .collect();
+    storage.insert_batch_into_table("users", rows)?;
+    assert!(storage.trace_key("users", &Value::Integer(0))?.len() >= 3);
+    assert_eq!(storage.scan_table("users", None)?.len(), 600);
+
+    for id in [0, 299, 599] {
+        let duplicate = Row::new(vec![Value::Integer(id), Value::Text("dup".to_string())]);
+        assert!(matches!(
+            storage.insert_into_table("users", duplicate),
+            Err(DatabaseError::UniqueConstraintViolation { .. })
+        ));
+    }
+    Ok(())
+}
+
+fn account(id: i64, email: &str, username: Option<&str>) -> Row {
+    Row::new(vec![
+        Value::Integer(id),
+        Value::Text(email.to_string()),
+        username.map_or(Value::Null, |name| Value::Text(name.to_string())),
+    ])
+}
+
+#[test]
+fn test_duplicate_unique_column_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_duplicate_unique");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table(
+        "accounts",
+        "CREATE TABLE accounts (id INTEGER PRIMARY KEY, email TEXT NOT NULL UNIQUE, username TEXT UNIQUE)",
+    )?;
+
+    storage.insert_into_table("accounts", account(1, "a@example.com", Some("alice")))?;
+    match storage.insert_into_table("accounts", account(2, "a@example.com", Some("bob"))) {
+        Err(DatabaseError::UniqueConstraintViolation { table, column, value }) => {
+            assert_eq!(table, "accounts");
+            assert_eq!(column, "email");
+            assert_eq!(value, Value::Text("a@example.com".to_string()));
+        }
+        other => panic!("Expected UniqueConstraintViolation, got {:?}", other),
+    }
+    assert!(matches!(
+        storage.insert_into_table("accounts", account(3, "c@example.com", Some("alice"))),
+        Err(DatabaseError::UniqueConstraintViolation { ref column, .. }) if column == "username"
+    ));
+
+    assert_eq!(storage.scan_table("accounts", None)?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_unique_value_of_overflow_row_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_duplicate_overflow_unique");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("files", "CREATE TABLE files(id INTEGER PRIMARY KEY, name TEXT UNIQUE, data BLOB)")?;
+    for id in 1..=5 {
+        let size = if id == 3 { 3000 } else { 10 };
+        storage.insert_into_table(
+            "files",
+            Row::new(vec![Value::Integer(id), Value::Text(format!("a{}", id)), Value::Blob(vec![0xAB; size])]),
+        )?;
+    }
+
+    for (id, name) in [(6, "a3"), (7, "a4")] {
+        let result = storage.insert_into_table(
+            "files",
+            Row::new(vec![Value::Integer(id), Value::Text(name.to_string()), Value::Blob(vec![1])]),
+        );
+        match result {
+            Err(DatabaseError::UniqueConstraintViolation { column, value, .. }) => {
+                assert_eq!(column, "name");
+                assert_eq!(value, Value::Text(name.to_string()));
+            }
+            other => panic!("Expected UniqueConstraintViolation for {}, got {:?}", name, other),
+        }
+    }
+    assert_eq!(storage.scan_table("files", None)?.len(), 5);
+    Ok(())
+}
+
+#[test]
+fn test_unique_column_allows_repeated_nulls() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_unique_nulls");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table(
+        "accounts",
+        "CREATE TABLE accounts (id INTEGER PRIMARY KEY, email TEXT NOT NULL UNIQUE, username TEXT UNIQUE)",
+    )?;
+
+    storage.insert_into_table("accounts", account(1, "a@example.com", None))?;
+    storage.insert_into_table("accounts", account(2, "b@example.com", None))?;
+    assert_eq!(storage.scan_table("accounts", None)?.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_batch_with_duplicate_unique_value_inside_batch() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_unique_in_batch");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table(
+        "accounts",
+        "CREATE TABLE accounts (id INTEGER PRIMARY KEY, email TEXT NOT NULL UNIQUE, username TEXT UNIQUE)",
+    )?;
+
+    let rows = vec![
+        account(1, "a@example.com", Some("alice")),
+        account(2, "b@example.com", None),
+        account(3, "a@example.com", Some("carol")),
+        account(4, "d@example.com", Some("dave")),
+    ];
+    assert!(matches!(
+        storage.insert_batch_into_table("accounts", rows),
+        Err(DatabaseError::UniqueConstraintViolation { ref column, .. }) if column == "email"
+    ));
+
+    let mut ids: Vec<Value> = storage
+        .scan_table("accounts", None)?
+        .into_iter()
+        .map(|row| row.values[0].clone())
+        .collect();
+    ids.sort_by_key(|value| value.to_string());
+    assert_eq!(ids, vec![Value::Integer(1), Value::Integer(2)]);
+    Ok(())
+}
+
+fn row_ids(storage: &StorageManager, table: &str) -> Result<Vec<u64>, DatabaseError> {
+    let mut ids: Vec<u64> = storage
+        .scan_table(table, None)?
+        .iter()
+        .map(|row| row.row_id.expect("stored rows carry a rowid"))
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+#[test]
+fn test_rowids_are_assigned_contiguously() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_rowids");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+
+    storage.insert_into_table("items", Row::new(vec![Value::Integer(10), Value::Text("a".to_string())]))?;
+    let batch: Vec<Row> = (11..=15)
+        .map(|i| Row::new(vec![Value::Integer(i), Value::Text(format!("item_{}", i))]))
+        .collect();
+    storage.insert_batch_into_table("items", batch)?;
+    assert_eq!(row_ids(storage, "items")?, vec![1, 2, 3, 4, 5, 6]);
+
+    let row = storage.get_by_rowid("items", 4)?.expect("rowid 4 exists");
+    assert_eq!(row.values[0], Value::Integer(13));
+    assert!(storage.get_by_rowid("items", 99)?.is_none());
+
+    // An explicit rowid is kept and later rows continue after it
+    storage.insert_into_table(
+        "items",
+        Row::with_row_id(50, vec![Value::Integer(16), Value::Text("explicit".to_string())]),
+    )?;
+    storage.insert_into_table("items", Row::new(vec![Value::Integer(17), Value::Text("b".to_string())]))?;
+    assert_eq!(storage.get_by_rowid("items", 51)?.unwrap().values[0], Value::Integer(17));
+    Ok(())
+}
+
+#[test]
+fn test_rowid_counter_survives_reopen() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_rowid_reopen");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+    storage.create_table("legacy", "CREATE TABLE legacy(id INTEGER, name TEXT)")?;
+    for i in 1..=3 {
+        storage.insert_into_table("items", Row::new(vec![Value::Integer(i), Value::Text("x".to_string())]))?;
+    }
+    // Deleting the newest row must not let its rowid be reused
+    storage.delete_from_table("items", Some(Predicate::eq("id".to_string(), Value::Integer(3))))?;
+
+    // Rows written by a standalone inserter never persist the counter
+    let mut inserter = TableInserter::new(storage, "legacy".to_string())?;
+    inserter.insert_batch(
+        (1..=4)
+            .map(|i| Row::new(vec![Value::Integer(i), Value::Text("y".to_string())]))
+            .collect(),
+    )?;
+
+    let mut reopened = StorageManager::new(&temp_db.path)?;
+    reopened.insert_into_table("items", Row::new(vec![Value::Integer(4), Value::Text("x".to_string())]))?;
+    assert_eq!(row_ids(&reopened, "items")?, vec![1, 2, 4]);
+
+    // Without a stored counter the table resumes after its highest rowid
+    reopened.insert_into_table("legacy", Row::new(vec![Value::Integer(5), Value::Text("y".to_string())]))?;
+    assert_eq!(row_ids(&reopened, "legacy")?, vec![1, 2, 3, 4, 5]);
+    Ok(())
+}
+
+#[test]
+fn test_unique_constraints_follow_value_comparison_mode() -> Result<(), DatabaseError> {
+    for mode in [ValueComparison::Coercive, ValueComparison::Strict] {
+        let temp_db = TempDatabase::with_prefix(&format!("unique_comparison_{:?}", mode));
+        let options = StorageManagerOptions::new().with_value_comparison(mode);
+        let mut storage = StorageManager::open_with_options(&temp_db.path, options)?;
+        storage.create_table(
+            "accounts",
+            "CREATE TABLE accounts(id INTEGER PRIMARY KEY, code INTEGER UNIQUE)",
+        )?;
+        storage.insert_into_table("accounts", Row::new(vec![Value::Integer(1), Value::Integer(7)]))?;
+
+        let text_key = storage.insert_into_table(
+            "accounts",
+            Row::new(vec![Value::Text("1".to_string()), Value::Integer(8)]),
+        );
+        let boolean_code = storage.insert_into_table(
+            "accounts",
+            Row::new(vec![Value::Integer(2), Value::Boolean(true)]),
+        );
+        let text_code = storage.insert_into_table(
+            "accounts",
+            Row::new(vec![Value::Integer(3), Value::Text("7".to_string())]),
+        );
+        let real_key = storage.insert_into_table(
+            "accounts",
+            Row::new(vec![Value::Real(1.0), Value::Integer(9)]),
+        );
+
+        // Integer/Real promotion holds in both modes
+        assert!(matches!(real_key, Err(DatabaseError::UniqueConstraintViolation { .. })));
+        match mode {
+            ValueComparison::Coercive => {
+                assert!(matches!(text_key, Err(DatabaseError::UniqueConstraintViolation { .. })));
+                assert!(matches!(text_code, Err(DatabaseError::UniqueConstraintViolation { .. })));
+                boolean_code?;
+                assert_eq!(storage.count_rows("accounts", None)?, 2);
+            }
+            ValueComparison::Strict => {
+                text_key?;
+                boolean_code?;
+                text_code?;
+                assert_eq!(storage.count_rows("accounts", None)?, 4);
+                let sevens = Predicate::eq("code".to_string(), Value::Integer(7));
+                assert_eq!(storage.count_rows("accounts", Some(sevens))?, 1);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn keys(storage: &StorageManager, table: &str) -> Result<Vec<i64>, DatabaseError> {
+    Ok(storage
+        .scan_table(table, None)?
+        .iter()
+        .map(|row| match row.values[0] {
+            Value::Integer(id) => id,
+            _ => panic!("Expected integer ID"),
+        })
+        .collect())
+}
+
+fn wide_row(id: i64) -> Row {
+    Row::new(vec![Value::Integer(id), Value::Text(format!("row_{:0>60}", id))])
+}
+
+#[test]
+fn test_batch_insert_packs_pages_to_the_fill_factor() -> Result<(), DatabaseError> {
+    const ROWS: i64 = 5000;
+    // Shuffled so the batch has to be sorted before it is packed
+    let batch = || (0..ROWS).map(|i| wide_row(i * 7919 % ROWS)).collect::<Vec<_>>();
+
+    let mut leaf_pages = Vec::new();
+    for fill_factor in [1.0, 0.5] {
+        let mut temp_db = TempDatabase::with_prefix("inserter_bulk_fill");
+        let options = StorageManagerOptions::new().with_bulk_load_fill_factor(fill_factor);
+        let storage = temp_db.create_storage_manager_with_options(options).unwrap();
+        storage.create_table("items", "CREATE TABLE items(id INTEGER PRIMARY KEY, name TEXT)")?;
+
+        let before = storage.io_stats();
+        storage.insert_batch_into_table("items", batch())?;
+        let after = storage.io_stats();
+        let page_writes = after.full_page_writes + after.partial_page_writes
+            - before.full_page_writes
+            - before.partial_page_writes;
+        // Each page of the new tree is written once
+        assert_eq!(page_writes as usize, storage.table_page_stats("items")?.page_count);
+
+        assert_eq!(keys(storage, "items")?, (0..ROWS).collect::<Vec<_>>());
+        assert_eq!(row_ids(storage, "items")?, (1..=ROWS as u64).collect::<Vec<_>>());
+        leaf_pages.push(storage.table_page_stats("items")?.leaf_pages);
+    }
+    // Half-full pages take about twice as many
+    assert!(leaf_pages[1] >= leaf_pages[0] * 9 / 5, "leaf pages per fill factor: {:?}", leaf_pages);
+
+    // One row at a time, splits leave pages partly empty
+    let mut temp_db = TempDatabase::with_prefix("inserter_bulk_fill_rows");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER PRIMARY KEY, name TEXT)")?;
+    for row in batch() {
+        storage.insert_into_table("items", row)?;
+    }
+    let row_leaves = storage.table_page_stats("items")?.leaf_pages;
+    assert!(row_leaves > leaf_pages[0] * 5 / 4, "{} leaves row by row, {} packed", row_leaves, leaf_pages[0]);
+    Ok(())
+}
+
+#[test]
+fn test_batch_insert_merges_into_an_existing_tree() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_bulk_merge");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER PRIMARY KEY, name TEXT)")?;
+    storage.insert_batch_into_table("items", (0..3000).map(|i| wide_row(i * 2)).collect())?;
+    for i in 0..50 {
+        storage.insert_into_table("items", wide_row(6000 + i))?;
+    }
+
+    // Odd keys land between every stored pair, splitting leaves all over the tree
+    let odd: Vec<Row> = (0..3000).rev().map(|i| wide_row(i * 2 + 1)).collect();
+    storage.insert_batch_into_table("items", odd)?;
+    assert_eq!(keys(storage, "items")?, (0..6050).collect::<Vec<_>>());
+
+    // A key already stored fails the batch at that row, keeping the rows before it
+    let result = storage.insert_batch_into_table("items", vec![wide_row(7000), wide_row(4321), wide_row(7001)]);
+    assert!(matches!(
+        result,
+        Err(DatabaseError::UniqueConstraintViolation { ref value, .. }) if *value == Value::Integer(4321)
+    ));
+
+    let reopened = StorageManager::new(&temp_db.path)?;
+    let mut expected: Vec<i64> = (0..6050).collect();
+    expected.push(7000);
+    assert_eq!(keys(&reopened, "items")?, expected);
+    for id in [0, 1, 2999, 3001, 6049, 7000] {
+        let found = reopened.scan_table("items", Some(Predicate::eq("id".to_string(), Value::Integer(id))))?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].values, wide_row(id).values);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_batch_with_unique_value_of_overflow_row_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("inserter_batch_overflow_unique");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("files", "CREATE TABLE files(id INTEGER PRIMARY KEY, name TEXT UNIQUE, data BLOB)")?;
+    for id in 1..=5 {
+        let size = if id == 3 { 3000 } else { 10 };
+        storage.insert_into_table(
+            "files",
+            Row::new(vec![Value::Integer(id), Value::Text(format!("a{}", id)), Value::Blob(vec![0xAB; size])]),
+        )?;
+    }
+
+    let batch = [(6, "b6"), (7, "a3"), (8, "b8")]
+        .into_iter()
+        .map(|(id, name)| Row::new(vec![Value::Integer(id), Value::Text(name.to_string()), Value::Blob(vec![1])]))
+        .collect();
+    let result = storage.insert_batch_into_table("files", batch);
+    assert!(
+        matches!(&result, Err(DatabaseError::UniqueConstraintViolation { value, .. }) if *value == Value::Text("a3".to_string())),
+        "{:?}",
+        result
+    );
+    // Rows ahead of the failing one stay, as inserting them one at a time would leave them
+    let ids: Vec<Value> = storage.scan_table("files", None)?.into_iter().map(|row| row.values[0].clone()).collect();
+    assert_eq!(ids.len(), 6);
+    assert!(ids.contains(&Value::Integer(6)) && !ids.contains(&Value::Integer(8)));
+    Ok(())
+}
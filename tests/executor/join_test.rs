@@ -0,0 +1,44 @@
+use bambang::{
+    executor::statement::{StatementResult, execute_statement},
+    types::{error::DatabaseError, value::Value},
+    utils::mock::TempDatabase,
+};
+
+#[test]
+fn test_join_pairs_matching_rows_and_skips_null_keys() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("join_matching_rows");
+    let storage = temp_db.create_storage_manager().unwrap();
+    for sql in [
+        "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)",
+        "INSERT INTO users VALUES (1, 'Ann'), (2, 'Bob'), (3, 'Cy')",
+        "CREATE TABLE orders(id INTEGER PRIMARY KEY, user_id INTEGER, total INTEGER)",
+        "INSERT INTO orders VALUES (10, 1, 250), (11, 3, 40), (12, 1, 75), (13, NULL, 5)",
+    ] {
+        execute_statement(storage, sql)?;
+    }
+
+    let StatementResult::Rows { columns, rows } = execute_statement(
+        storage,
+        "SELECT users.name, total FROM users JOIN orders ON orders.user_id = users.id ORDER BY total",
+    )?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(columns, vec!["name", "total"]);
+    let values: Vec<Vec<Value>> = rows.into_iter().map(|row| row.values).collect();
+    assert_eq!(
+        values,
+        vec![
+            vec![Value::Text("Cy".to_string()), Value::Integer(40)],
+            vec![Value::Text("Ann".to_string()), Value::Integer(75)],
+            vec![Value::Text("Ann".to_string()), Value::Integer(250)],
+        ]
+    );
+
+    // `id` names a column of both tables
+    assert!(matches!(
+        execute_statement(storage, "SELECT id FROM users JOIN orders ON user_id = users.id"),
+        Err(DatabaseError::SqlParseError { details }) if details.contains("ambiguous")
+    ));
+    Ok(())
+}
@@ -3,4 +3,11 @@ pub mod update_test;
 pub mod delete_test;
 pub mod insert_test;
 pub mod create_table_test;
-pub mod join_test;
\ No newline at end of file
+pub mod join_test;
+pub mod predicate_test;
+pub mod aggregate_test;
+pub mod distinct_test;
+pub mod empty_table_test;
+pub mod statement_test;
+pub mod explain_test;
+pub mod pseudo_column_test;
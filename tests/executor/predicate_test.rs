@@ -0,0 +1,694 @@
+use bambang::{
+    executor::{
+        like::like_match,
+        predicate::{ArithmeticOp, ComparisonOp, Expr, Predicate, PredicateBuilder, PredicateMode, TriBool},
+        scan::{FilterScanner, ScanIterator},
+        sequential_scan::SequentialScanner,
+        subquery::ValueSetBuilder,
+    },
+    storage::{options::StorageManagerOptions, storage_manager::StorageManager},
+    types::{
+        error::DatabaseError,
+        row::Row,
+        value::{Value, ValueComparison},
+    },
+    utils::mock::TempDatabase,
+};
+
+fn setup_users_and_banned(storage: &mut StorageManager) -> Result<(), DatabaseError> {
+    storage.create_table("users", "CREATE TABLE users(id INTEGER, referrer INTEGER, name TEXT)")?;
+    storage.create_table("banned", "CREATE TABLE banned(user_id INTEGER, reason TEXT)")?;
+    let users = [
+        (1, Value::Integer(10)),
+        (2, Value::Integer(20)),
+        (3, Value::Null),
+        (4, Value::Integer(20)),
+        (5, Value::Integer(30)),
+    ];
+    for (id, referrer) in users {
+        storage.insert_into_table(
+            "users",
+            Row::new(vec![Value::Integer(id), referrer, Value::Text(format!("user{}", id))]),
+        )?;
+    }
+    Ok(())
+}
+
+fn ban(storage: &mut StorageManager, user_id: Value) -> Result<(), DatabaseError> {
+    storage.insert_into_table("banned", Row::new(vec![user_id, Value::Text("spam".to_string())]))
+}
+
+fn ids(rows: &[Row]) -> Vec<i64> {
+    let mut ids: Vec<i64> = rows
+        .iter()
+        .map(|row| match row.values[0] {
+            Value::Integer(id) => id,
+            _ => panic!("unexpected id"),
+        })
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_in_table_positive_and_negated() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("in_table_basic");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_users_and_banned(storage)?;
+    ban(storage, Value::Integer(20))?;
+    ban(storage, Value::Integer(20))?;
+    ban(storage, Value::Integer(30))?;
+
+    let in_banned = Predicate::in_table("referrer".to_string(), "banned".to_string(), "user_id".to_string());
+    assert_eq!(ids(&storage.scan_table("users", Some(in_banned))?), vec![2, 4, 5]);
+
+    let not_in_banned = PredicateBuilder::new()
+        .not_in_table("referrer".to_string(), "banned".to_string(), "user_id".to_string())
+        .build();
+    assert_eq!(ids(&storage.scan_table("users", Some(not_in_banned))?), vec![1]);
+
+    let combined = PredicateBuilder::new()
+        .in_table("referrer".to_string(), "banned".to_string(), "user_id".to_string())
+        .ne("id".to_string(), Value::Integer(5))
+        .build();
+    assert_eq!(ids(&storage.scan_table("users", Some(combined))?), vec![2, 4]);
+    Ok(())
+}
+
+#[test]
+fn test_in_table_against_empty_table() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("in_table_empty");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_users_and_banned(storage)?;
+
+    let in_banned = Predicate::in_table("referrer".to_string(), "banned".to_string(), "user_id".to_string());
+    assert!(storage.scan_table("users", Some(in_banned))?.is_empty());
+
+    let not_in_banned = Predicate::not_in_table("referrer".to_string(), "banned".to_string(), "user_id".to_string());
+    assert_eq!(ids(&storage.scan_table("users", Some(not_in_banned))?), vec![1, 2, 4, 5]);
+    Ok(())
+}
+
+#[test]
+fn test_not_in_table_with_null_in_other_table() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("in_table_null");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_users_and_banned(storage)?;
+    ban(storage, Value::Integer(10))?;
+    ban(storage, Value::Null)?;
+
+    let in_banned = Predicate::in_table("referrer".to_string(), "banned".to_string(), "user_id".to_string());
+    assert_eq!(ids(&storage.scan_table("users", Some(in_banned))?), vec![1]);
+
+    // x NOT IN (..., NULL) is never true
+    let not_in_banned = Predicate::not_in_table("referrer".to_string(), "banned".to_string(), "user_id".to_string());
+    assert!(storage.scan_table("users", Some(not_in_banned))?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_in_table_spills_with_tiny_budget() -> Result<(), DatabaseError> {
+    let temp_db = TempDatabase::with_prefix("in_table_spill");
+    let options = StorageManagerOptions::new().with_subquery_memory_budget(256);
+    let mut storage = StorageManager::open_with_options(&temp_db.path, options)?;
+    setup_users_and_banned(&mut storage)?;
+    for user_id in (0..300).rev() {
+        ban(&mut storage, Value::Integer(user_id * 10))?;
+    }
+
+    let in_banned = Predicate::in_table("referrer".to_string(), "banned".to_string(), "user_id".to_string());
+    assert_eq!(ids(&storage.scan_table("users", Some(in_banned))?), vec![1, 2, 4, 5]);
+    let not_in_banned = Predicate::not_in_table("referrer".to_string(), "banned".to_string(), "user_id".to_string());
+    assert!(storage.scan_table("users", Some(not_in_banned))?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_value_set_builder_spills_and_dedupes() -> Result<(), DatabaseError> {
+    let mut builder = ValueSetBuilder::new(128);
+    for i in (0..1000).rev() {
+        builder.push(Value::Integer(i % 500 * 2))?;
+    }
+    builder.push(Value::Null)?;
+    let set = builder.finish()?;
+    assert!(set.is_spilled());
+    assert!(set.contains_null());
+    assert_eq!(set.len(), 500);
+    for i in 0..500 {
+        assert!(set.contains(&Value::Integer(i * 2))?);
+        assert!(!set.contains(&Value::Integer(i * 2 + 1))?);
+    }
+    assert!(set.contains(&Value::Real(10.0))?);
+    assert!(!set.contains(&Value::Integer(-1))?);
+    assert!(!set.contains(&Value::Null)?);
+
+    let mut small = ValueSetBuilder::new(1024 * 1024);
+    small.push(Value::Text("b".to_string()))?;
+    small.push(Value::Text("a".to_string()))?;
+    small.push(Value::Text("b".to_string()))?;
+    let small = small.finish()?;
+    assert!(!small.is_spilled());
+    assert_eq!(small.len(), 2);
+    assert!(small.contains(&Value::Text("a".to_string()))?);
+    Ok(())
+}
+
+#[test]
+fn test_in_table_validation_errors() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("in_table_validation");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_users_and_banned(storage)?;
+
+    let missing_table = Predicate::in_table("referrer".to_string(), "nope".to_string(), "user_id".to_string());
+    assert!(matches!(
+        storage.scan_table("users", Some(missing_table)),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+    let missing_column = Predicate::in_table("referrer".to_string(), "banned".to_string(), "nope".to_string());
+    assert!(matches!(
+        storage.scan_table("users", Some(missing_column)),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    let mismatched = Predicate::in_table("referrer".to_string(), "banned".to_string(), "reason".to_string());
+    assert!(matches!(
+        storage.scan_table("users", Some(mismatched)),
+        Err(DatabaseError::TypeMismatch { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_expression_comparison_between_columns() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("expr_comparison");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("orders", "CREATE TABLE orders(id INTEGER, price REAL, quantity INTEGER)")?;
+    let orders = [
+        (1, Value::Real(10.0), Value::Integer(4)),
+        (2, Value::Real(9.0), Value::Integer(4)),
+        (3, Value::Real(7.5), Value::Integer(3)),
+        (4, Value::Null, Value::Integer(1)),
+        (5, Value::Real(3.0), Value::Integer(0)),
+    ];
+    for (id, price, quantity) in orders {
+        storage.insert_into_table("orders", Row::new(vec![Value::Integer(id), price, quantity]))?;
+    }
+
+    // price > quantity * 2
+    let doubled = Expr::binary(
+        ArithmeticOp::Mul,
+        Expr::column("quantity".to_string()),
+        Expr::literal(Value::Integer(2)),
+    );
+    let predicate = Predicate::compare_exprs(
+        Expr::column("price".to_string()),
+        ComparisonOp::GreaterThan,
+        doubled,
+    );
+    assert_eq!(
+        predicate.get_referenced_columns(),
+        vec!["price".to_string(), "quantity".to_string()]
+    );
+    assert_eq!(ids(&storage.scan_table("orders", Some(predicate))?), vec![1, 2, 3, 5]);
+
+    // id = price / quantity divides by zero on row 5, which yields NULL
+    let per_unit = Predicate::compare_exprs(
+        Expr::column("id".to_string()),
+        ComparisonOp::Equal,
+        Expr::binary(
+            ArithmeticOp::Div,
+            Expr::column("price".to_string()),
+            Expr::column("quantity".to_string()),
+        ),
+    );
+    assert!(storage.scan_table("orders", Some(per_unit))?.is_empty());
+
+    let unknown_column = Predicate::compare_exprs(
+        Expr::column("cost".to_string()),
+        ComparisonOp::LessThan,
+        Expr::literal(Value::Integer(1)),
+    );
+    assert!(matches!(
+        storage.scan_table("orders", Some(unknown_column)),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_like_pattern_table() -> Result<(), DatabaseError> {
+    // (text, pattern, escape, case_insensitive, expected)
+    let cases: &[(&str, &str, Option<char>, bool, bool)] = &[
+        ("abc", "abc", None, false, true),
+        ("abc", "ab", None, false, false),
+        ("", "", None, false, true),
+        ("", "%", None, false, true),
+        ("", "_", None, false, false),
+        ("anything", "%", None, false, true),
+        ("abc", "a%", None, false, true),
+        ("abc", "%c", None, false, true),
+        ("abc", "%b%", None, false, true),
+        ("abc", "%d%", None, false, false),
+        ("axxbyyc", "a%b%c", None, false, true),
+        ("axxcyyb", "a%b%c", None, false, false),
+        ("abcbc", "a%bc", None, false, true),
+        ("aXbXbXc", "a%b%c", None, false, true),
+        ("foo", "_oo", None, false, true),
+        ("fooo", "_oo", None, false, false),
+        ("oo", "_oo", None, false, false),
+        ("abc", "___", None, false, true),
+        ("abc", "__", None, false, false),
+        ("abc", "a_c", None, false, true),
+        ("abcd", "a%_d", None, false, true),
+        ("ad", "a%_d", None, false, false),
+        ("mississippi", "%iss%ppi", None, false, true),
+        ("mississippi", "m%%%i", None, false, true),
+        ("50%", "50\\%", Some('\\'), false, true),
+        ("500", "50\\%", Some('\\'), false, false),
+        ("50% off", "50\\%%", Some('\\'), false, true),
+        ("a_b", "a!_b", Some('!'), false, true),
+        ("axb", "a!_b", Some('!'), false, false),
+        ("a!b", "a!!b", Some('!'), false, true),
+        ("50\\x", "50\\%", None, false, true),
+        ("ABC", "abc", None, false, false),
+        ("ABC", "abc", None, true, true),
+        ("Hello World", "hello%WORLD", None, true, true),
+        ("café", "caf_", None, false, true),
+        ("café", "CAF%", None, true, true),
+        ("ÉCOLE", "école", None, true, true),
+        ("日本語テキスト", "日本%", None, false, true),
+        ("日本語テキスト", "%テキ_ト", None, false, true),
+        ("日本語", "__", None, false, false),
+        ("emoji 🦀 crab", "%🦀%", None, false, true),
+        ("🦀", "_", None, false, true),
+    ];
+    assert!(cases.len() >= 30);
+    for (text, pattern, escape, case_insensitive, expected) in cases {
+        assert_eq!(
+            like_match(text, pattern, *escape, *case_insensitive)?,
+            *expected,
+            "{:?} LIKE {:?} ESCAPE {:?} (ci: {})",
+            text,
+            pattern,
+            escape,
+            case_insensitive
+        );
+    }
+    assert!(like_match("abc", "abc\\", Some('\\'), false).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_like_predicates_filter_text_only() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("predicate_like");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("codes", "CREATE TABLE codes(id INTEGER, code TEXT)")?;
+    let codes = [
+        (1, Value::Text("Sale 50%".to_string())),
+        (2, Value::Text("sale 500".to_string())),
+        (3, Value::Text("clearance".to_string())),
+        (4, Value::Null),
+        (5, Value::Integer(50)),
+    ];
+    for (id, code) in codes {
+        storage.insert_into_table("codes", Row::new(vec![Value::Integer(id), code]))?;
+    }
+
+    let scan = |predicate: Predicate| -> Result<Vec<i64>, DatabaseError> {
+        Ok(ids(&storage.scan_table("codes", Some(predicate))?))
+    };
+    assert_eq!(scan(Predicate::like("code".to_string(), "%50%".to_string()))?, vec![1, 2]);
+    assert_eq!(scan(Predicate::like("code".to_string(), "sale%".to_string()))?, vec![2]);
+    assert_eq!(scan(Predicate::ilike("code".to_string(), "SALE%".to_string()))?, vec![1, 2]);
+    assert_eq!(
+        scan(Predicate::like_with_escape("code".to_string(), "%50\\%".to_string(), '\\'))?,
+        vec![1]
+    );
+    // Non-text values match neither LIKE nor NOT LIKE
+    assert_eq!(scan(Predicate::not_like("code".to_string(), "%50%".to_string()))?, vec![3]);
+    let builder = PredicateBuilder::new()
+        .ilike("code".to_string(), "%a%".to_string())
+        .gt("id".to_string(), Value::Integer(1));
+    assert_eq!(scan(builder.build())?, vec![2, 3]);
+    assert!(matches!(
+        storage.scan_table("codes", Some(Predicate::like_with_escape("code".to_string(), "50#".to_string(), '#'))),
+        Err(DatabaseError::ExecutionError { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_tribool_follows_kleene_logic() {
+    use TriBool::{False, True, Unknown};
+    let values = [True, False, Unknown];
+    // Rows and columns in `values` order
+    let and = [[True, False, Unknown], [False, False, False], [Unknown, False, Unknown]];
+    let or = [[True, True, True], [True, False, Unknown], [True, Unknown, Unknown]];
+    for (i, left) in values.iter().enumerate() {
+        for (j, right) in values.iter().enumerate() {
+            assert_eq!(left.and(*right), and[i][j], "{:?} AND {:?}", left, right);
+            assert_eq!(left.or(*right), or[i][j], "{:?} OR {:?}", left, right);
+        }
+    }
+    assert_eq!(!True, False);
+    assert_eq!(!False, True);
+    assert_eq!(!Unknown, Unknown);
+    assert!(True.is_true() && !False.is_true() && !Unknown.is_true());
+    assert_eq!(TriBool::from(true), True);
+}
+
+/// Ids of `readings` rows matching `predicate` under legacy and SQL null handling
+fn ids_in_both_modes(predicate: Predicate) -> Result<(Vec<i64>, Vec<i64>), DatabaseError> {
+    let mut results = Vec::new();
+    for (prefix, mode) in [("tribool_legacy", PredicateMode::Legacy), ("tribool_sql", PredicateMode::Sql)] {
+        let temp_db = TempDatabase::with_prefix(prefix);
+        let options = StorageManagerOptions::new().with_predicate_mode(mode);
+        let mut storage = StorageManager::open_with_options(&temp_db.path, options)?;
+        storage.create_table("readings", "CREATE TABLE readings(id INTEGER, level INTEGER, tag TEXT)")?;
+        let rows = [
+            (1, Value::Integer(1), Value::Text("a".to_string())),
+            (2, Value::Integer(5), Value::Null),
+            (3, Value::Null, Value::Text("a".to_string())),
+            (4, Value::Null, Value::Null),
+            (5, Value::Integer(9), Value::Text("b".to_string())),
+        ];
+        for (id, level, tag) in rows {
+            storage.insert_into_table("readings", Row::new(vec![Value::Integer(id), level, tag]))?;
+        }
+        results.push(ids(&storage.scan_table("readings", Some(predicate.clone()))?));
+        // The same predicate through a standalone filter scanner
+        let schema = storage.get_table_schema("readings").unwrap().clone();
+        let scanner = SequentialScanner::new(&storage, "readings".to_string(), None)?;
+        let filtered = FilterScanner::new(scanner, predicate.clone(), schema)?.with_predicate_mode(mode);
+        let rows: Vec<Row> = ScanIterator::new(filtered).collect::<Result<_, _>>()?;
+        assert_eq!(ids(&rows), *results.last().unwrap());
+    }
+    Ok((results.remove(0), results.remove(0)))
+}
+
+#[test]
+fn test_sql_mode_filters_unknown_comparisons() -> Result<(), DatabaseError> {
+    let level = || "level".to_string();
+    let tag = || "tag".to_string();
+
+    // NULL = NULL is true in legacy mode, unknown in SQL
+    assert_eq!(ids_in_both_modes(Predicate::eq(level(), Value::Null))?, (vec![3, 4], vec![]));
+    assert_eq!(ids_in_both_modes(Predicate::ne(level(), Value::Integer(1)))?, (vec![2, 3, 4, 5], vec![2, 5]));
+    assert_eq!(ids_in_both_modes(Predicate::gt(level(), Value::Integer(2)))?, (vec![2, 5], vec![2, 5]));
+    // NOT unknown stays unknown
+    assert_eq!(ids_in_both_modes(!Predicate::gt(level(), Value::Integer(2)))?, (vec![1, 3, 4], vec![1]));
+    assert_eq!(
+        ids_in_both_modes(!Predicate::like(tag(), "a%".to_string()))?,
+        (vec![2, 4, 5], vec![5])
+    );
+
+    // Kleene AND / OR: a known side can decide the result on its own
+    let unknown_or_true = Predicate::or(
+        Predicate::gt(level(), Value::Integer(2)),
+        Predicate::eq(tag(), Value::Text("a".to_string())),
+    );
+    assert_eq!(ids_in_both_modes(unknown_or_true.clone())?, (vec![1, 2, 3, 5], vec![1, 2, 3, 5]));
+    assert_eq!(ids_in_both_modes(!unknown_or_true)?, (vec![4], vec![]));
+    // Only rows where a side is known false pass NOT (... AND ...)
+    let maybe_false = Predicate::and(
+        Predicate::gt(level(), Value::Integer(2)),
+        Predicate::eq(tag(), Value::Text("z".to_string())),
+    );
+    assert_eq!(ids_in_both_modes(!maybe_false)?, (vec![1, 2, 3, 4, 5], vec![1, 3, 5]));
+
+    // IS [NOT] NULL behave the same in both modes
+    assert_eq!(ids_in_both_modes(Predicate::is_null(level()))?, (vec![3, 4], vec![3, 4]));
+    assert_eq!(ids_in_both_modes(!Predicate::is_not_null(tag()))?, (vec![2, 4], vec![2, 4]));
+    let null_or_big = Predicate::or(Predicate::is_null(level()), Predicate::gt(level(), Value::Integer(8)));
+    assert_eq!(ids_in_both_modes(null_or_big)?, (vec![3, 4, 5], vec![3, 4, 5]));
+
+    // IN and NOT IN with a NULL in the list
+    let with_null = || vec![Value::Integer(1), Value::Null];
+    assert_eq!(ids_in_both_modes(Predicate::in_list(level(), with_null()))?, (vec![1, 3, 4], vec![1]));
+    assert_eq!(ids_in_both_modes(Predicate::not_in_list(level(), with_null()))?, (vec![2, 5], vec![]));
+    assert_eq!(
+        ids_in_both_modes(Predicate::not_in_list(level(), vec![Value::Integer(1)]))?,
+        (vec![2, 3, 4, 5], vec![2, 5])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_evaluate_tri_reports_unknown() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("tribool_direct");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("t", "CREATE TABLE t(a INTEGER, b INTEGER)")?;
+    let schema = storage.get_table_schema("t").unwrap().clone();
+    let row = Row::new(vec![Value::Null, Value::Integer(2)]);
+    let a_is_one = Predicate::eq("a".to_string(), Value::Integer(1));
+    let coercive = ValueComparison::Coercive;
+
+    assert_eq!(a_is_one.evaluate_tri(&row, &schema, coercive, PredicateMode::Sql)?, TriBool::Unknown);
+    assert_eq!(a_is_one.evaluate_tri(&row, &schema, coercive, PredicateMode::Legacy)?, TriBool::False);
+    assert_eq!((!a_is_one.clone()).evaluate_tri(&row, &schema, coercive, PredicateMode::Sql)?, TriBool::Unknown);
+    assert!(!(!a_is_one.clone()).evaluate_in(&row, &schema, coercive, PredicateMode::Sql)?);
+    assert!((!a_is_one.clone()).evaluate(&row, &schema)?);
+    let b_is_two = Predicate::eq("b".to_string(), Value::Integer(2));
+    assert_eq!(
+        Predicate::or(a_is_one.clone(), b_is_two.clone()).evaluate_tri(&row, &schema, coercive, PredicateMode::Sql)?,
+        TriBool::True
+    );
+    assert_eq!(
+        Predicate::and(a_is_one, b_is_two).evaluate_tri(&row, &schema, coercive, PredicateMode::Sql)?,
+        TriBool::Unknown
+    );
+    Ok(())
+}
+
+#[test]
+fn test_between_with_constant_and_computed_bounds() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("predicate_between");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("lines", "CREATE TABLE lines(id INTEGER, price REAL, quantity INTEGER)")?;
+    let lines = [
+        (1, Value::Real(10.0), Value::Integer(5)),
+        (2, Value::Real(25.0), Value::Integer(2)),
+        (3, Value::Real(50.0), Value::Integer(3)),
+        (4, Value::Null, Value::Integer(1)),
+        (5, Value::Real(8.0), Value::Integer(0)),
+    ];
+    for (id, price, quantity) in lines {
+        storage.insert_into_table("lines", Row::new(vec![Value::Integer(id), price, quantity]))?;
+    }
+    let scan = |predicate: Predicate| -> Result<Vec<i64>, DatabaseError> {
+        Ok(ids(&storage.scan_table("lines", Some(predicate))?))
+    };
+    let price = || "price".to_string();
+
+    // Bounds are inclusive and may be of another numeric type
+    assert_eq!(scan(Predicate::between(price(), Value::Integer(10), Value::Real(25.0)))?, vec![1, 2]);
+    assert_eq!(scan(Predicate::not_between(price(), Value::Integer(10), Value::Integer(25)))?, vec![3, 5]);
+    assert!(scan(Predicate::between(price(), Value::Integer(30), Value::Integer(20)))?.is_empty());
+    // A NULL operand satisfies neither form; a NULL bound only decides the result
+    // when the other bound does not
+    assert!(scan(Predicate::between(price(), Value::Null, Value::Integer(100)))?.is_empty());
+    assert!(scan(Predicate::not_between(price(), Value::Null, Value::Integer(100)))?.is_empty());
+    assert_eq!(scan(Predicate::not_between(price(), Value::Null, Value::Integer(9)))?, vec![1, 2, 3]);
+
+    // price BETWEEN quantity * 5 AND 100 / quantity; the zero quantity gives a NULL bound
+    let computed = Predicate::between_exprs(
+        price(),
+        Expr::binary(ArithmeticOp::Mul, Expr::column("quantity".to_string()), Expr::literal(Value::Integer(5))),
+        Expr::binary(ArithmeticOp::Div, Expr::literal(Value::Integer(100)), Expr::column("quantity".to_string())),
+    );
+    assert_eq!(
+        computed.get_referenced_columns(),
+        vec!["price".to_string(), "quantity".to_string()]
+    );
+    assert_eq!(scan(computed)?, vec![2]);
+
+    // price * quantity > 100, built up with the predicate builder
+    let total = Expr::binary(ArithmeticOp::Mul, Expr::column(price()), Expr::column("quantity".to_string()));
+    let builder = PredicateBuilder::new()
+        .expr(total, ComparisonOp::GreaterThan, Expr::literal(Value::Integer(40)))
+        .between("id".to_string(), Value::Integer(1), Value::Integer(2));
+    assert_eq!(scan(builder.build())?, vec![1, 2]);
+
+    assert!(matches!(
+        storage.scan_table(
+            "lines",
+            Some(Predicate::between_exprs(price(), Expr::column("cost".to_string()), Expr::literal(Value::Integer(1))))
+        ),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_division_by_zero_yields_null() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("predicate_div_zero");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("t", "CREATE TABLE t(a INTEGER, b REAL)")?;
+    let schema = storage.get_table_schema("t").unwrap().clone();
+    let row = Row::new(vec![Value::Integer(7), Value::Real(0.0)]);
+    let divide = |divisor: Expr| Expr::binary(ArithmeticOp::Div, Expr::column("a".to_string()), divisor);
+
+    assert_eq!(divide(Expr::literal(Value::Integer(0))).evaluate(&row, &schema)?, Value::Null);
+    assert_eq!(divide(Expr::column("b".to_string())).evaluate(&row, &schema)?, Value::Null);
+    assert_eq!(divide(Expr::literal(Value::Integer(2))).evaluate(&row, &schema)?, Value::Real(3.5));
+
+    let compared = Predicate::compare_exprs(
+        divide(Expr::literal(Value::Integer(0))),
+        ComparisonOp::GreaterThan,
+        Expr::literal(Value::Integer(1)),
+    );
+    let coercive = ValueComparison::Coercive;
+    assert_eq!(compared.evaluate_tri(&row, &schema, coercive, PredicateMode::Sql)?, TriBool::Unknown);
+    assert!(!compared.evaluate(&row, &schema)?);
+    let is_null = Predicate::compare_exprs(
+        divide(Expr::literal(Value::Integer(0))),
+        ComparisonOp::IsNull,
+        Expr::literal(Value::Null),
+    );
+    assert!(is_null.evaluate(&row, &schema)?);
+    Ok(())
+}
+
+fn col(name: &str) -> String {
+    name.to_string()
+}
+
+#[test]
+fn test_parse_builds_predicate_tree() -> Result<(), DatabaseError> {
+    assert_eq!(
+        Predicate::parse("age >= 18 AND (name LIKE 'A%' OR email IS NULL)")?,
+        Predicate::and(
+            Predicate::ge(col("age"), Value::Integer(18)),
+            Predicate::or(Predicate::like(col("name"), "A%".to_string()), Predicate::is_null(col("email"))),
+        )
+    );
+    // AND binds tighter than OR, NOT tighter than AND
+    assert_eq!(
+        Predicate::parse("a = 1 or not b = 2 and c <> 3")?,
+        Predicate::or(
+            Predicate::eq(col("a"), Value::Integer(1)),
+            Predicate::and(!Predicate::eq(col("b"), Value::Integer(2)), Predicate::ne(col("c"), Value::Integer(3))),
+        )
+    );
+    assert_eq!(
+        Predicate::parse("id NOT IN (1, -2, 'x''y', NULL) AND score BETWEEN 1.5 AND 2e3")?,
+        Predicate::and(
+            Predicate::not_in_list(
+                col("id"),
+                vec![Value::Integer(1), Value::Integer(-2), Value::Text("x'y".to_string()), Value::Null],
+            ),
+            Predicate::between(col("score"), Value::Real(1.5), Value::Real(2000.0)),
+        )
+    );
+    assert_eq!(
+        Predicate::parse("price * quantity > 100 AND flag = TRUE AND \"order\" != X'0aFF'")?,
+        Predicate::and(
+            Predicate::and(
+                Predicate::compare_exprs(
+                    Expr::binary(ArithmeticOp::Mul, Expr::column(col("price")), Expr::column(col("quantity"))),
+                    ComparisonOp::GreaterThan,
+                    Expr::literal(Value::Integer(100)),
+                ),
+                Predicate::eq(col("flag"), Value::Boolean(true)),
+            ),
+            Predicate::ne(col("order"), Value::Blob(vec![0x0a, 0xff])),
+        )
+    );
+    assert_eq!(
+        Predicate::parse("code LIKE '50!%' ESCAPE '!' AND name NOT ILIKE 'a%'")?,
+        Predicate::and(
+            Predicate::like_with_escape(col("code"), "50!%".to_string(), '!'),
+            !Predicate::ilike(col("name"), "a%".to_string()),
+        )
+    );
+    assert_eq!(
+        Predicate::parse("user_id IN (SELECT id FROM banned) OR (a + 1) * 2 IS NOT NULL")?,
+        Predicate::or(
+            Predicate::in_table(col("user_id"), "banned".to_string(), col("id")),
+            Predicate::compare_exprs(
+                Expr::binary(
+                    ArithmeticOp::Mul,
+                    Expr::binary(ArithmeticOp::Add, Expr::column(col("a")), Expr::literal(Value::Integer(1))),
+                    Expr::literal(Value::Integer(2)),
+                ),
+                ComparisonOp::IsNotNull,
+                Expr::literal(Value::Null),
+            ),
+        )
+    );
+    assert_eq!(Predicate::parse("((TRUE))")?, Predicate::True);
+    Ok(())
+}
+
+#[test]
+fn test_parse_errors_report_offset_and_token() {
+    let cases = [
+        ("age >= ", 7, "end of input"),
+        ("age >= 18 AND", 13, "end of input"),
+        ("age 18", 4, "18"),
+        ("name = 'unterminated", 7, "'unterminated"),
+        ("(a = 1", 6, "end of input"),
+        ("a = 1)", 5, ")"),
+        ("a IN (1, b)", 9, "b"),
+        ("a LIKE 'x' ESCAPE 'ab'", 18, "'ab'"),
+        ("1 BETWEEN 0 AND 2", 0, "1"),
+        ("a = 1 ; drop", 6, ";"),
+        ("select = 1", 0, "select"),
+        ("", 0, "end of input"),
+    ];
+    for (input, offset, token) in cases {
+        match Predicate::parse(input) {
+            Err(DatabaseError::PredicateSyntax { offset: at, token: near, .. }) => {
+                assert_eq!((at, near.as_str()), (offset, token), "{:?}", input)
+            }
+            other => panic!("{:?} should not parse, got {:?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_parse_round_trips_through_display() -> Result<(), DatabaseError> {
+    let inputs = [
+        "age >= 18 AND (name LIKE 'A%' OR email IS NULL)",
+        "NOT (a = 1 OR b = 2) AND NOT NOT c IS NOT NULL",
+        "a = 1 AND (b = 2 AND c = 3)",
+        "a - (b - c) = a - b - c",
+        "x / (y * 2) <= -9223372036854775808",
+        "name = 'it''s' AND \"weird col\" = 'ü' AND \"AND\" = 1",
+        "score NOT BETWEEN low + 1 AND high AND tag IN () AND id NOT IN (SELECT uid FROM \"from\")",
+        "blob = X'00FF' OR code NOT LIKE '%\\_%' ESCAPE '\\' OR name ILIKE 'a%'",
+        "r = 0.1 OR r = -2.5 OR r = 1e100 OR flag = FALSE OR FALSE",
+    ];
+    for input in inputs {
+        let parsed = Predicate::parse(input)?;
+        let printed = parsed.to_string();
+        assert_eq!(Predicate::parse(&printed)?, parsed, "{:?} printed as {:?}", input, printed);
+    }
+    assert_eq!(Predicate::parse("a = 1 AND b = 2 OR c = 3")?.to_string(), "a = 1 AND b = 2 OR c = 3");
+
+    let built = [
+        Predicate::and(Predicate::eq(col("a"), Value::Integer(1)), Predicate::or(Predicate::True, Predicate::False)),
+        !Predicate::in_list(col("b"), vec![Value::Real(2.0), Value::Text(String::new())]),
+        Predicate::not_between(col("c"), Value::Integer(-1), Value::Null),
+        Predicate::like_with_escape(col("d"), "100'%".to_string(), '\''),
+    ];
+    for predicate in built {
+        assert_eq!(Predicate::parse(&predicate.to_string())?, predicate, "{}", predicate);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parsed_predicate_filters_scan() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("predicate_parse_scan");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_users_and_banned(storage)?;
+    ban(storage, Value::Integer(4))?;
+    let scan = |condition: &str| -> Result<Vec<i64>, DatabaseError> {
+        Ok(ids(&storage.scan_table("users", Some(Predicate::parse(condition)?))?))
+    };
+    assert_eq!(scan("referrer = 20 OR referrer IS NULL")?, vec![2, 3, 4]);
+    assert_eq!(scan("id NOT IN (SELECT user_id FROM banned) AND id BETWEEN 2 AND 5")?, vec![2, 3, 5]);
+    assert_eq!(scan("referrer / 10 = id")?, vec![1, 2]);
+    assert!(matches!(scan("missing = 1"), Err(DatabaseError::ColumnNotFound { .. })));
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::{fs, time::Duration};
+
+use bambang::{
+    executor::{
+        predicate::Predicate,
+        statement::{StatementResult, execute_statement},
+    },
+    storage::storage_manager::StorageManager,
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::create_temp_db_path_with_prefix,
+};
+
+fn event(id: i64) -> Row {
+    Row::new(vec![
+        Value::Integer(id),
+        Value::Text(format!("event_{}_{}", id, "e".repeat(80))),
+        Value::Integer(id % 7),
+    ])
+}
+
+fn ids(rows: &[Row]) -> Vec<i64> {
+    rows.iter()
+        .map(|row| match row.values[0] {
+            Value::Integer(id) => id,
+            ref other => panic!("expected an integer id, got {:?}", other),
+        })
+        .collect()
+}
+
+fn read_only<T>(result: Result<T, DatabaseError>) -> bool {
+    matches!(result, Err(DatabaseError::ReadOnlyColumn { ref name, .. }) if name == "_rowid" || name == "_version")
+}
+
+/// Pages the most recent filtered scan of the workload log read
+fn last_scan_pages(storage: &StorageManager) -> u64 {
+    storage.workload_log().recent(Duration::from_secs(60)).last().unwrap().pages_read
+}
+
+#[test]
+fn test_rowid_lists_are_fetched_without_scanning() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("pseudo_rowid");
+    let mut storage = StorageManager::new(&path)?;
+    storage.create_table("events", "CREATE TABLE events (id INTEGER PRIMARY KEY, payload TEXT, kind INTEGER)")?;
+    // Keys run opposite to insertion, so rowid order is not key order
+    storage.insert_batch_into_table("events", (0..2000).rev().map(event).collect())?;
+
+    let row_ids: Vec<(i64, i64)> = storage
+        .scan_table_projected("events", &["_rowid", "id"], None)?
+        .into_iter()
+        .map(|row| match row.values[..] {
+            [Value::Integer(row_id), Value::Integer(id)] => (row_id, id),
+            ref other => panic!("unexpected projection {:?}", other),
+        })
+        .collect();
+    assert_eq!(row_ids.len(), 2000);
+    let id_of = |row_id: i64| row_ids.iter().find(|(stamped, _)| *stamped == row_id).unwrap().1;
+
+    storage.scan_table("events", Some(Predicate::eq("kind".to_string(), Value::Integer(3))))?;
+    let full_scan_pages = last_scan_pages(&storage);
+    assert!(full_scan_pages >= 20, "table should span many leaves, read {}", full_scan_pages);
+
+    // The first lookup builds the table's row locator with one scan; later ones only
+    // descend the tree to each row
+    let targets = Predicate::parse("_rowid IN (7, 1500, 42, 999999)")?;
+    let rows = storage.scan_table("events", Some(targets.clone()))?;
+    let mut expected = vec![id_of(7), id_of(1500), id_of(42)];
+    expected.sort();
+    assert_eq!(ids(&rows), expected);
+    let rows = storage.scan_table("events", Some(targets))?;
+    assert_eq!(ids(&rows), expected);
+    let pages = last_scan_pages(&storage);
+    assert!(pages <= 9 && pages * 4 < full_scan_pages, "read {} pages of {}", pages, full_scan_pages);
+
+    // Other conjuncts still filter the fetched rows
+    let narrowed = Predicate::parse(&format!("_rowid = 7 AND id > {}", id_of(7)))?;
+    assert!(storage.scan_table("events", Some(narrowed))?.is_empty());
+    assert_eq!(storage.get_by_rowid("events", 1500)?.map(|row| ids(&[row])), Some(vec![id_of(1500)]));
+
+    // Writes retire the locator, so deleted rows are gone and moved or new ones found
+    storage.delete_from_table("events", Some(Predicate::parse("_rowid = 42")?))?;
+    storage.update_table("events", Some(Predicate::parse("_rowid = 7")?), &[("id".to_string(), Value::Integer(5000))])?;
+    storage.insert_into_table("events", event(6000))?;
+    let rows = storage.scan_table("events", Some(Predicate::parse("_rowid IN (7, 42, 2001)")?))?;
+    assert_eq!(ids(&rows), vec![5000, 6000]);
+    assert_eq!(storage.get_by_rowid("events", 42)?, None);
+
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_versions_select_rows_changed_since_a_point() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("pseudo_version");
+    let mut storage = StorageManager::new(&path)?;
+    execute_statement(&mut storage, "CREATE TABLE tasks (id INTEGER PRIMARY KEY, state TEXT)")?;
+    execute_statement(&mut storage, "INSERT INTO tasks VALUES (1, 'new'), (2, 'new'), (3, 'new'), (4, 'new')")?;
+
+    let set_state = |state: &str| [("state".to_string(), Value::Text(state.to_string()))];
+    storage.update_table("tasks", Some(Predicate::parse("id >= 2")?), &set_state("queued"))?;
+    storage.update_table("tasks", Some(Predicate::parse("id IN (3, 4)")?), &set_state("running"))?;
+    storage.update_table("tasks", Some(Predicate::parse("id = 4")?), &set_state("done"))?;
+
+    let changed = storage.scan_table("tasks", Some(Predicate::parse("_version > 1")?))?;
+    assert_eq!(ids(&changed), vec![3, 4]);
+    assert_eq!(ids(&storage.scan_table("tasks", Some(Predicate::parse("_version = 0")?))?), vec![1]);
+
+    // SELECT may name them, ORDER BY them and filter on them, but `*` leaves them out
+    let StatementResult::Rows { columns, rows } =
+        execute_statement(&mut storage, "SELECT id, _version FROM tasks WHERE _version >= 1 ORDER BY _version DESC")?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(columns, vec!["id", "_version"]);
+    let values: Vec<Vec<Value>> = rows.into_iter().map(|row| row.values).collect();
+    assert_eq!(
+        values,
+        vec![
+            vec![Value::Integer(4), Value::Integer(3)],
+            vec![Value::Integer(3), Value::Integer(2)],
+            vec![Value::Integer(2), Value::Integer(1)],
+        ]
+    );
+    let StatementResult::Rows { columns, rows } = execute_statement(&mut storage, "SELECT * FROM tasks WHERE _rowid = 1")?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(columns, vec!["id", "state"]);
+    assert_eq!(rows[0].values.len(), 2);
+
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_pseudo_columns_cannot_be_written_and_yield_to_real_columns() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("pseudo_writes");
+    let mut storage = StorageManager::new(&path)?;
+    execute_statement(&mut storage, "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)")?;
+    execute_statement(&mut storage, "INSERT INTO notes VALUES (1, 'a')")?;
+
+    assert!(read_only(execute_statement(&mut storage, "INSERT INTO notes (id, _rowid) VALUES (2, 9)")));
+    assert!(read_only(storage.update_table("notes", None, &[("_version".to_string(), Value::Integer(9))])));
+    assert!(read_only(storage.update_by_rowid("notes", 1, &[("_rowid".to_string(), Value::Integer(9))], None)));
+    assert!(matches!(
+        storage.update_table("notes", None, &[("missing".to_string(), Value::Integer(9))]),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    assert_eq!(storage.scan_table("notes", None)?.len(), 1);
+    assert!(matches!(
+        storage.scan_table("notes", Some(Predicate::parse("_rowd = 1")?)),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+
+    // A declared column of the same name is an ordinary column
+    execute_statement(&mut storage, "CREATE TABLE ledger (id INTEGER PRIMARY KEY, _version INTEGER)")?;
+    execute_statement(&mut storage, "INSERT INTO ledger VALUES (1, 40), (2, 41)")?;
+    storage.update_table("ledger", Some(Predicate::parse("id = 2")?), &[("_version".to_string(), Value::Integer(50))])?;
+    assert_eq!(ids(&storage.scan_table("ledger", Some(Predicate::parse("_version = 50")?))?), vec![2]);
+    let StatementResult::Rows { columns, .. } = execute_statement(&mut storage, "SELECT * FROM ledger")? else {
+        panic!("expected rows");
+    };
+    assert_eq!(columns, vec!["id", "_version"]);
+
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
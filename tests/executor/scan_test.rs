@@ -1,12 +1,20 @@
 use bambang::{
     executor::{
-        scan::{ScanIterator, Scanner},
+        scan::{BatchPolicy, FilterScanner, LimitScanner, ScanIterator, ScanOptions, Scanner},
+        predicate::Predicate,
         sequential_scan::SequentialScanner,
     },
-    types::{error::DatabaseError, row::Row, value::Value},
+    planner::types::SortOrder,
+    storage::{page_offset, storage_manager::StorageManager},
+    types::{
+        PAGE_SIZE,
+        error::DatabaseError,
+        page::{Page, PageType},
+        row::Row,
+        value::Value,
+    },
     utils::mock::TempDatabase,
 };
-use std::collections::HashSet;
 
 #[test]
 fn test_sequential_scanner_basic_functionality() -> Result<(), DatabaseError> {
@@ -49,6 +57,7 @@ fn test_sequential_scanner_basic_functionality() -> Result<(), DatabaseError> {
 #[test]
 fn test_scanner_reset_functionality() -> Result<(), DatabaseError> {
     let mut temp_db = TempDatabase::with_prefix("scan_reset");
+    let db_path = temp_db.path.clone();
     let storage = temp_db.create_storage_manager().unwrap();
     storage.create_table("reset_test", "CREATE TABLE reset_test(id INTEGER)")?;
     for i in 1..=3 {
@@ -60,10 +69,80 @@ fn test_scanner_reset_functionality() -> Result<(), DatabaseError> {
     assert!(first_row.is_some());
     scanner.reset()?;
     let mut count = 0;
-    while let Some(_) = scanner.scan()? {
+    while scanner.scan()?.is_some() {
         count += 1;
     }
     assert_eq!(count, 3);
+
+    // Grow the table until its root splits, then reset the scanner created before the split
+    let old_root = storage.table_roots["reset_test"];
+    scanner.reset()?;
+    assert!(scanner.scan()?.is_some());
+    let padding = "x".repeat(400);
+    let mut total = 3;
+    while storage.table_roots["reset_test"] == old_root {
+        total += 1;
+        let row = Row::new(vec![Value::Integer(total), Value::Text(padding.clone())]);
+        storage.insert_into_table("reset_test", row)?;
+    }
+    for _ in 0..20 {
+        total += 1;
+        let row = Row::new(vec![Value::Integer(total), Value::Text(padding.clone())]);
+        storage.insert_into_table("reset_test", row)?;
+    }
+    scanner.reset()?;
+    let new_root = storage.table_roots["reset_test"];
+    assert_eq!(scanner.root_page_id(), new_root);
+    let reopened = StorageManager::new(&db_path)?;
+    assert_eq!(reopened.table_roots["reset_test"], new_root);
+    drop(reopened);
+    let mut ids = Vec::new();
+    while let Some(row) = scanner.scan()? {
+        ids.push(row.values[0].clone());
+    }
+    assert_eq!(ids.len(), total as usize);
+    ids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    ids.dedup();
+    assert_eq!(ids.len(), total as usize);
+    Ok(())
+}
+
+#[test]
+fn test_scanner_reset_with_multi_level_schema_tree() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_reset_schema_tree");
+    let db_path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    let schema_root_type = |storage: &StorageManager| -> Result<PageType, DatabaseError> {
+        let (_, bytes) = storage.iter_table_pages("sqlite_schema")?.next().unwrap()?;
+        Ok(Page::from_bytes(&bytes)?.page_type)
+    };
+    let mut table_count = 0;
+    while schema_root_type(storage)? == PageType::LeafTable {
+        storage.create_table(
+            &format!("filler_{}", table_count),
+            &format!("CREATE TABLE filler_{}(id INTEGER, name TEXT, email TEXT)", table_count),
+        )?;
+        table_count += 1;
+    }
+    storage.create_table("reset_test", "CREATE TABLE reset_test(id INTEGER, body TEXT)")?;
+    storage.insert_into_table("reset_test", Row::new(vec![Value::Integer(0), Value::Text("x".to_string())]))?;
+
+    let mut scanner = SequentialScanner::new(storage, "reset_test".to_string(), None)?;
+    let old_root = storage.table_roots["reset_test"];
+    let padding = "x".repeat(400);
+    let mut total = 1;
+    while storage.table_roots["reset_test"] == old_root {
+        storage.insert_into_table("reset_test", Row::new(vec![Value::Integer(total), Value::Text(padding.clone())]))?;
+        total += 1;
+    }
+    scanner.reset()?;
+    assert_eq!(scanner.root_page_id(), storage.table_roots["reset_test"]);
+    let mut count = 0;
+    while scanner.scan()?.is_some() {
+        count += 1;
+    }
+    assert_eq!(count, total);
+    assert_eq!(StorageManager::new(&db_path)?.table_roots["reset_test"], scanner.root_page_id());
     Ok(())
 }
 
@@ -121,6 +200,45 @@ fn test_scan_iterator_wrapper() -> Result<(), DatabaseError> {
     Ok(())
 }
 
+#[test]
+fn test_map_rows_collects_typed_tuples_lazily() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_map_rows");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("people", "CREATE TABLE people(id INTEGER, name TEXT)")?;
+    for id in 1..=100 {
+        storage.insert_into_table("people", Row::new(vec![Value::Integer(id), Value::Text(format!("person_{}", id))]))?;
+    }
+    let to_tuple = |row: Row| match (row.get_value(0), row.get_value(1)) {
+        (Some(Value::Integer(id)), Some(Value::Text(name))) => Ok((*id, name.clone())),
+        _ => Err(DatabaseError::InvalidData {
+            details: format!("Unexpected row {:?}", row.values),
+        }),
+    };
+
+    let scanner = SequentialScanner::new(storage, "people".to_string(), None)?;
+    let people: Vec<(i64, String)> = scanner.map_rows(to_tuple).collect::<Result<_, _>>()?;
+    assert_eq!(people.len(), 100);
+    assert_eq!(people[0], (1, "person_1".to_string()));
+    assert_eq!(people[99], (100, "person_100".to_string()));
+
+    // Taking a few rows scans only those
+    let mut iter = SequentialScanner::new(storage, "people".to_string(), None)?.map_rows(to_tuple);
+    let first: Vec<(i64, String)> = iter.by_ref().take(2).collect::<Result<_, _>>()?;
+    assert_eq!(first, vec![(1, "person_1".to_string()), (2, "person_2".to_string())]);
+    assert_eq!(iter.into_inner().stats().rows_scanned, 2);
+
+    // A failing conversion is yielded like a scan error
+    let scanner = SequentialScanner::new(storage, "people".to_string(), None)?;
+    let ids: Result<Vec<i64>, _> = scanner
+        .map_rows(|row| match row.get_value(1) {
+            Some(Value::Integer(id)) => Ok(*id),
+            other => Err(DatabaseError::InvalidData { details: format!("{:?} is not an id", other) }),
+        })
+        .collect();
+    assert!(matches!(ids, Err(DatabaseError::InvalidData { .. })));
+    Ok(())
+}
+
 // #[test] TODO: Fix this
 // fn test_scanner_with_large_dataset() -> Result<(), DatabaseError> {
 //     let mut temp_db = TempDatabase::with_prefix("scan_large");
@@ -357,3 +475,461 @@ fn test_scanner_slot_directory_efficiency() -> Result<(), DatabaseError> {
     assert_eq!(count, 15);
     Ok(())
 }
+
+#[test]
+fn test_auto_batches_track_byte_budget() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_auto_batch");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("mixed", "CREATE TABLE mixed(id INTEGER, payload TEXT)")?;
+    let row_count = 400;
+    for i in 1..=row_count {
+        let payload = "p".repeat((i as usize * 37) % 300 + 10);
+        storage.insert_into_table("mixed", Row::new(vec![Value::Integer(i), Value::Text(payload)]))?;
+    }
+
+    let budget = 4096;
+    let mut scanner = SequentialScanner::new(storage, "mixed".to_string(), Some(4))?
+        .with_batch_byte_budget(budget);
+    let mut batches = Vec::new();
+    loop {
+        let batch = scanner.scan_batch_with_policy(BatchPolicy::Auto)?;
+        if batch.is_empty() {
+            break;
+        }
+        let stats = scanner.stats();
+        assert_eq!(stats.last_batch_rows, batch.len());
+        batches.push((batch.len(), stats.last_batch_bytes));
+    }
+
+    let total_rows: usize = batches.iter().map(|(rows, _)| rows).sum();
+    assert_eq!(total_rows, row_count as usize);
+    assert_eq!(batches[0].0, 4);
+    let auto_size = scanner.stats().auto_batch_size.unwrap();
+    assert!(auto_size > 4);
+    // Skip the warm-up batch and the final partial batch
+    let last = batches.len() - 1;
+    for &(_, bytes) in &batches[1..last] {
+        assert!(
+            bytes as usize >= budget / 2 && bytes as usize <= budget * 2,
+            "batch of {} bytes strays from the {} byte budget",
+            bytes,
+            budget
+        );
+    }
+
+    let mut fixed = SequentialScanner::new(storage, "mixed".to_string(), None)?;
+    let mut fixed_rows = 0;
+    loop {
+        let batch = fixed.scan_batch_with_policy(BatchPolicy::Fixed(32))?;
+        if batch.is_empty() {
+            break;
+        }
+        fixed_rows += batch.len();
+    }
+    assert_eq!(fixed_rows, total_rows);
+    assert_eq!(fixed.stats().rows_scanned, row_count as u64);
+    assert_eq!(fixed.stats().auto_batch_size, None);
+    Ok(())
+}
+
+#[test]
+fn test_scanner_projection_keeps_requested_columns() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_projection");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table(
+        "people",
+        "CREATE TABLE people(id INTEGER, name TEXT, age INTEGER, city TEXT)",
+    )?;
+    for i in 1..=40 {
+        storage.insert_into_table(
+            "people",
+            Row::with_row_id(
+                100 + i as u64,
+                vec![
+                    Value::Integer(i),
+                    Value::Text(format!("person_{}", i)),
+                    Value::Integer(20 + i),
+                    Value::Text(format!("city_{}", i % 5)),
+                ],
+            ),
+        )?;
+    }
+
+    let mut scanner = SequentialScanner::new(storage, "people".to_string(), None)?;
+    scanner.set_projection(vec![0, 2]);
+    let mut rows = Vec::new();
+    while let Some(row) = scanner.scan()? {
+        rows.push(row);
+    }
+    assert_eq!(rows.len(), 40);
+    for row in &rows {
+        let Value::Integer(id) = row.values[0] else {
+            panic!("Expected integer id");
+        };
+        assert_eq!(row.values, vec![Value::Integer(id), Value::Integer(20 + id)]);
+        assert_eq!(row.row_id, Some(100 + id as u64));
+    }
+
+    let mut named = SequentialScanner::with_projection(
+        storage,
+        "people".to_string(),
+        vec!["city".to_string(), "id".to_string()],
+        Some(8),
+    )?;
+    let batch = named.scan_batch(8)?;
+    assert_eq!(batch.len(), 8);
+    assert!(batch.iter().all(|row| row.values.len() == 2
+        && matches!(row.values[0], Value::Text(_))
+        && matches!(row.values[1], Value::Integer(_))));
+
+    let missing = SequentialScanner::with_projection(
+        storage,
+        "people".to_string(),
+        vec!["salary".to_string()],
+        None,
+    );
+    assert!(matches!(missing, Err(DatabaseError::ColumnNotFound { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_scan_table_projected_filters_on_unprojected_columns() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_table_projected");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table(
+        "documents",
+        "CREATE TABLE documents(id INTEGER, body BLOB, title TEXT, size INTEGER)",
+    )?;
+    for i in 1..=12 {
+        storage.insert_into_table(
+            "documents",
+            Row::new(vec![
+                Value::Integer(i),
+                Value::Blob(vec![i as u8; 512]),
+                Value::Text(format!("doc_{}", i)),
+                Value::Integer(i * 100),
+            ]),
+        )?;
+    }
+
+    let mut all = storage.scan_table_projected("documents", &["title", "id"], None)?;
+    all.sort_by(|a, b| a.values[1].partial_cmp(&b.values[1]).unwrap());
+    assert_eq!(all.len(), 12);
+    assert_eq!(all[0].values, vec![Value::Text("doc_1".to_string()), Value::Integer(1)]);
+
+    let large = Predicate::gt("size".to_string(), Value::Integer(1000));
+    let mut titles: Vec<Value> = storage
+        .scan_table_projected("documents", &["title"], Some(large))?
+        .into_iter()
+        .map(|row| {
+            assert_eq!(row.values.len(), 1);
+            row.values[0].clone()
+        })
+        .collect();
+    titles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(
+        titles,
+        vec![Value::Text("doc_11".to_string()), Value::Text("doc_12".to_string())]
+    );
+
+    assert!(matches!(
+        storage.scan_table_projected("documents", &["id", "author"], None),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_scan_table_sorted_orders_mixed_numeric_column_descending() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_table_sorted");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("scores", "CREATE TABLE scores(id INTEGER, name TEXT, score REAL)")?;
+    let scores = [
+        (1, "dina", Value::Integer(3)),
+        (2, "adi", Value::Real(2.5)),
+        (3, "budi", Value::Null),
+        (4, "citra", Value::Real(3.0)),
+        (5, "eko", Value::Integer(10)),
+        (6, "fajar", Value::Real(-1.5)),
+    ];
+    for (id, name, score) in scores {
+        storage.insert_into_table(
+            "scores",
+            Row::new(vec![Value::Integer(id), Value::Text(name.to_string()), score]),
+        )?;
+    }
+
+    let order_by = vec![
+        ("score".to_string(), SortOrder::Descending),
+        ("name".to_string(), SortOrder::Ascending),
+    ];
+    let names = |rows: Vec<Row>| -> Vec<Value> { rows.into_iter().map(|row| row.values[1].clone()).collect() };
+    let text = |names: &[&str]| -> Vec<Value> { names.iter().map(|name| Value::Text(name.to_string())).collect() };
+
+    // 3 and 3.0 tie on score and fall back to name; NULL sorts last descending
+    let sorted = storage.scan_table_sorted("scores", None, order_by.clone())?;
+    assert_eq!(names(sorted), text(&["eko", "citra", "dina", "adi", "fajar", "budi"]));
+
+    let ascending = storage.scan_table_sorted(
+        "scores",
+        Some(Predicate::gt("id".to_string(), Value::Integer(2))),
+        vec![("score".to_string(), SortOrder::Ascending)],
+    )?;
+    assert_eq!(names(ascending), text(&["budi", "fajar", "citra", "eko"]));
+
+    assert!(matches!(
+        storage.scan_table_sorted("scores", None, vec![("rank".to_string(), SortOrder::Ascending)]),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_scan_table_paged_stops_reading_pages_early() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_table_paged");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("events", "CREATE TABLE events(id INTEGER, kind TEXT, payload TEXT)")?;
+    for i in 0..600 {
+        storage.insert_into_table(
+            "events",
+            Row::new(vec![
+                Value::Integer(i),
+                Value::Text(if i % 3 == 0 { "click" } else { "view" }.to_string()),
+                Value::Text(format!("payload_{}_{}", i, "p".repeat(60))),
+            ]),
+        )?;
+    }
+
+    let (_, full_stats) = storage.scan_table_paged_with_stats("events", None, usize::MAX, 0)?;
+    assert!(full_stats.pages_read >= 4, "table should span several leaves");
+
+    let (first_page, stats) = storage.scan_table_paged_with_stats("events", None, 5, 0)?;
+    assert_eq!(first_page.len(), 5);
+    assert_eq!(stats.pages_read, 1);
+    assert!(stats.pages_read < full_stats.pages_read);
+
+    // The offset counts only rows matching the predicate
+    let clicks = || Predicate::eq("kind".to_string(), Value::Text("click".to_string()));
+    let all_clicks = storage.scan_table("events", Some(clicks()))?;
+    let paged = storage.scan_table_paged("events", Some(clicks()), 10, 20)?;
+    assert_eq!(paged, all_clicks[20..30].to_vec());
+
+    let tail = storage.scan_table_paged("events", Some(clicks()), 50, all_clicks.len() - 3)?;
+    assert_eq!(tail.len(), 3);
+    assert!(storage.scan_table_paged("events", None, 10, 600)?.is_empty());
+    assert!(storage.scan_table_paged("events", None, 0, 0)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_limit_scanner_offsets_filtered_rows_and_stops_early() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("limit_scanner");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("logs", "CREATE TABLE logs(id INTEGER, level TEXT, message TEXT)")?;
+    for i in 0..500 {
+        storage.insert_into_table(
+            "logs",
+            Row::new(vec![
+                Value::Integer(i),
+                Value::Text(if i % 2 == 0 { "warn" } else { "info" }.to_string()),
+                Value::Text(format!("message {} {}", i, "m".repeat(80))),
+            ]),
+        )?;
+    }
+    let schema = storage.get_table_schema("logs").unwrap().clone();
+    let warnings = Predicate::eq("level".to_string(), Value::Text("warn".to_string()));
+
+    let filtered = FilterScanner::new(SequentialScanner::new(storage, "logs".to_string(), None)?, warnings, schema)?;
+    let mut limited = LimitScanner::new(filtered, 3, 4);
+    let ids = |rows: Vec<Row>| -> Vec<Value> { rows.into_iter().map(|row| row.values[0].clone()).collect() };
+    let expected = vec![Value::Integer(8), Value::Integer(10), Value::Integer(12)];
+    assert_eq!(ids(limited.scan_batch(10)?), expected);
+    assert!(limited.scan()?.is_none());
+
+    limited.reset()?;
+    assert_eq!(ids(limited.scan_batch(3)?), expected);
+    // One leaf per pass; scanner stats accumulate across resets
+    assert_eq!(limited.into_inner().into_inner().stats().pages_read, 2);
+
+    let past_end = LimitScanner::new(SequentialScanner::new(storage, "logs".to_string(), None)?, 10, 495);
+    assert_eq!(ScanIterator::new(past_end).count(), 5);
+    Ok(())
+}
+
+/// Rewrite a page image through `mutate`, optionally leaving the stored checksum stale
+fn corrupt_page(path: &std::path::Path, page_id: u64, fix_checksum: bool, mutate: impl FnOnce(&mut Page)) {
+    use std::io::{Read, Seek, SeekFrom, Write};
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).open(path).unwrap();
+    let mut bytes = vec![0u8; PAGE_SIZE];
+    file.seek(SeekFrom::Start(page_offset(page_id))).unwrap();
+    file.read_exact(&mut bytes).unwrap();
+    let mut page = Page::from_bytes(&bytes).unwrap();
+    let checksum = page.checksum;
+    mutate(&mut page);
+    if fix_checksum {
+        page.update_checksum();
+    } else {
+        page.checksum = checksum;
+    }
+    file.seek(SeekFrom::Start(page_offset(page_id))).unwrap();
+    file.write_all(&page.to_bytes().unwrap()).unwrap();
+}
+
+#[test]
+fn test_skip_corrupt_scan_logs_bad_cells_and_pages() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_skip_corrupt");
+    let path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, label TEXT)")?;
+    for i in 0..300 {
+        storage.insert_into_table(
+            "items",
+            Row::new(vec![Value::Integer(i), Value::Text(format!("item {} {}", i, "i".repeat(80)))]),
+        )?;
+    }
+
+    let leaves: Vec<Page> = storage
+        .iter_table_pages("items")?
+        .map(|page| Page::from_bytes(&page.unwrap().1).unwrap())
+        .filter(|page| page.page_type == PageType::LeafTable)
+        .collect();
+    assert!(leaves.len() >= 3);
+    let ids_on = |page: &Page| -> Vec<Value> {
+        (0..page.slot_directory.slots.len())
+            .filter_map(|i| page.get_cell(i))
+            .map(|cell| Row::from_bytes(cell).unwrap().values[0].clone())
+            .collect()
+    };
+    let (bad_cells_page, bad_page) = (&leaves[0], &leaves[1]);
+    let mut expected = storage.scan_table("items", None)?.len();
+    expected -= 2 + ids_on(bad_page).len();
+
+    // Two cells whose first value has an unknown type tag, then a page with a stale checksum
+    corrupt_page(&path, bad_cells_page.page_id, true, |page| {
+        for slot_index in [2, 5] {
+            let slot = page.slot_directory.slots[slot_index].clone();
+            let data = page.data.as_mut().unwrap();
+            let tag = slot.offset as usize + if data[slot.offset as usize] == 1 { 13 } else { 5 };
+            data[tag] = 0xEE;
+        }
+    });
+    corrupt_page(&path, bad_page.page_id, false, |page| {
+        let offset = page.slot_directory.slots[0].offset as usize + 20;
+        page.data.as_mut().unwrap()[offset] ^= 0xFF;
+    });
+
+    assert!(storage.scan_table("items", None).is_err());
+
+    let hook_calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = hook_calls.clone();
+    let options = ScanOptions::skip_corrupt().with_corruption_hook(move |_| {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    });
+    let (rows, log) = storage.scan_table_with_options("items", None, options)?;
+    assert_eq!(rows.len(), expected);
+    let logged: Vec<_> = log.entries.iter().map(|entry| (entry.page_id, entry.slot_index)).collect();
+    assert_eq!(
+        logged,
+        vec![
+            (bad_cells_page.page_id, Some(2)),
+            (bad_cells_page.page_id, Some(5)),
+            (bad_page.page_id, None),
+        ]
+    );
+    assert!(log.entries[0].error.contains("Unknown type discriminant"));
+    assert!(log.entries[2].error.contains("Checksum"));
+    assert_eq!(hook_calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+    let returned: Vec<Value> = rows.iter().map(|row| row.values[0].clone()).collect();
+    let lost = ids_on(bad_page);
+    assert!(returned.iter().all(|id| !lost.contains(id)));
+    Ok(())
+}
+
+#[test]
+fn test_read_ahead_follows_leaf_chain_across_resets() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_read_ahead");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("events", "CREATE TABLE events(id INTEGER, payload TEXT)")?;
+    for i in 0..400 {
+        storage.insert_into_table(
+            "events",
+            Row::new(vec![Value::Integer(i), Value::Text(format!("payload_{}_{}", i, "p".repeat(60)))]),
+        )?;
+    }
+    let ids = |rows: &[Row]| -> Vec<i64> {
+        rows.iter()
+            .map(|row| match row.values[0] {
+                Value::Integer(id) => id,
+                _ => panic!("Expected integer ID"),
+            })
+            .collect()
+    };
+    let expected: Vec<i64> = (0..400).collect();
+
+    let mut baseline = SequentialScanner::new(storage, "events".to_string(), None)?.with_read_ahead(0);
+    assert_eq!(ids(&baseline.scan_batch(usize::MAX)?), expected);
+    let leaf_pages = baseline.stats().pages_read;
+    assert!(leaf_pages >= 4, "table should span several leaves");
+
+    for read_ahead in [1, 2, 3, 8] {
+        let mut scanner = SequentialScanner::new(storage, "events".to_string(), None)?.with_read_ahead(read_ahead);
+        // Stop partway through pages, after read-ahead has queued their successors
+        for stop_after in [7, 60, 150, 399] {
+            let partial = scanner.scan_batch(stop_after)?;
+            assert_eq!(ids(&partial), expected[..stop_after].to_vec(), "read-ahead {}", read_ahead);
+            scanner.reset()?;
+        }
+        let before = scanner.stats().pages_read;
+        let mut rows = Vec::new();
+        loop {
+            let batch = scanner.scan_batch(13)?;
+            if batch.is_empty() {
+                break;
+            }
+            rows.extend(batch);
+        }
+        assert_eq!(ids(&rows), expected, "read-ahead {}", read_ahead);
+        // Every leaf is served exactly once, whether it was read ahead or not
+        assert_eq!(scanner.stats().pages_read - before, leaf_pages, "read-ahead {}", read_ahead);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reverse_scan_mirrors_forward_scan() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("scan_reverse");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("events", "CREATE TABLE events(id INTEGER, payload TEXT)")?;
+    let insert = |storage: &mut StorageManager, id: i64| {
+        storage.insert_into_table(
+            "events",
+            Row::new(vec![Value::Integer(id), Value::Text(format!("payload_{}_{}", id, "p".repeat(60)))]),
+        )
+    };
+    for id in (0..800).step_by(2) {
+        insert(storage, id)?;
+    }
+    let mut scanner = SequentialScanner::new(storage, "events".to_string(), None)?;
+    let forward = scanner.scan_batch(usize::MAX)?;
+    assert!(scanner.stats().pages_read >= 4, "table should span several leaves");
+    let mut reversed = forward.clone();
+    reversed.reverse();
+    assert_eq!(scanner.scan_reverse()?, reversed);
+
+    // Splits in the middle of the chain must relink the following leaf back to the new one
+    for id in (1..800).step_by(2) {
+        insert(storage, id)?;
+    }
+    let mut scanner = SequentialScanner::new(storage, "events".to_string(), None)?;
+    let ids: Vec<i64> = scanner
+        .scan_reverse()?
+        .iter()
+        .map(|row| match row.values[0] {
+            Value::Integer(id) => id,
+            _ => panic!("Expected integer ID"),
+        })
+        .collect();
+    assert_eq!(ids, (0..800).rev().collect::<Vec<i64>>());
+    Ok(())
+}
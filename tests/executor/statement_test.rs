@@ -0,0 +1,89 @@
+use bambang::{
+    executor::statement::{StatementResult, execute_statement, split_statements},
+    types::{error::DatabaseError, value::Value},
+    utils::mock::TempDatabase,
+};
+
+#[test]
+fn test_split_statements_ignores_quoted_semicolons() {
+    let statements = split_statements("INSERT INTO t VALUES (1, 'a;b');  ; SELECT \"x;y\" FROM t;");
+    assert_eq!(
+        statements,
+        vec!["INSERT INTO t VALUES (1, 'a;b')", "SELECT \"x;y\" FROM t"]
+    );
+}
+
+#[test]
+fn test_sql_statements_round_trip_through_storage() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("statement_round_trip");
+    let storage = temp_db.create_storage_manager().unwrap();
+
+    let created = execute_statement(
+        storage,
+        "CREATE TABLE people(id INTEGER PRIMARY KEY, name TEXT, age INTEGER DEFAULT 18)",
+    )?;
+    assert_eq!(created, StatementResult::TableCreated { table: "people".to_string() });
+
+    let inserted = execute_statement(
+        storage,
+        "INSERT INTO people VALUES (1, 'Ann', 30), (2, 'Bob', 25), (3, 'Cy', -4)",
+    )?;
+    assert_eq!(inserted, StatementResult::RowsInserted { count: 3 });
+    execute_statement(storage, "INSERT INTO people (name, id) VALUES ('Di', 4)")?;
+
+    let StatementResult::Rows { columns, rows } =
+        execute_statement(storage, "SELECT name, age AS years FROM people WHERE age >= 18 LIMIT 2")?
+    else {
+        panic!("expected rows");
+    };
+    assert_eq!(columns, vec!["name", "years"]);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].values, vec![Value::Text("Ann".to_string()), Value::Integer(30)]);
+
+    // The unlisted column took its default
+    let StatementResult::Rows { rows, .. } = execute_statement(storage, "SELECT * FROM people WHERE id = 4")? else {
+        panic!("expected rows");
+    };
+    assert_eq!(rows[0].values[2], Value::Integer(18));
+
+    let deleted = execute_statement(storage, "DELETE FROM people WHERE age < 20 OR name = 'Bob'")?;
+    assert_eq!(deleted, StatementResult::RowsDeleted { count: 3 });
+    assert_eq!(storage.scan_table("people", None)?.len(), 1);
+
+    let dropped = execute_statement(storage, "DROP TABLE people")?;
+    assert_eq!(dropped, StatementResult::TablesDropped { tables: vec!["people".to_string()] });
+    assert!(!storage.table_exists("people"));
+    assert_eq!(
+        execute_statement(storage, "DROP TABLE IF EXISTS people")?,
+        StatementResult::TablesDropped { tables: vec![] }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_failing_statement_leaves_session_usable() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("statement_errors");
+    let storage = temp_db.create_storage_manager().unwrap();
+    execute_statement(storage, "CREATE TABLE t(id INTEGER, name TEXT)")?;
+
+    assert!(matches!(
+        execute_statement(storage, "SELEC * FROM t"),
+        Err(DatabaseError::SqlParseError { .. })
+    ));
+    assert!(matches!(
+        execute_statement(storage, "SELECT missing FROM t"),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    assert!(matches!(
+        execute_statement(storage, "INSERT INTO t VALUES (1)"),
+        Err(DatabaseError::SqlParseError { .. })
+    ));
+
+    let results: Vec<_> = split_statements("INSERT INTO nope VALUES (1, 'x'); INSERT INTO t VALUES (1, 'x')")
+        .into_iter()
+        .map(|statement| execute_statement(storage, statement))
+        .collect();
+    assert!(matches!(results[0], Err(DatabaseError::TableNotFound { .. })));
+    assert!(matches!(results[1], Ok(StatementResult::RowsInserted { count: 1 })));
+    Ok(())
+}
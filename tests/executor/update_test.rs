@@ -0,0 +1,359 @@
+use bambang::{
+    executor::predicate::Predicate,
+    storage::{BAMBANG_HEADER_SIZE, options::StorageManagerOptions, storage_manager::StorageManager},
+    types::{PAGE_SIZE, error::DatabaseError, row::Row, value::Value},
+    utils::mock::TempDatabase,
+};
+
+fn setup_accounts(storage: &mut StorageManager, count: i64) -> Result<(), DatabaseError> {
+    storage.create_table(
+        "accounts",
+        "CREATE TABLE accounts(id INTEGER PRIMARY KEY, owner TEXT NOT NULL, balance INTEGER)",
+    )?;
+    for id in 1..=count {
+        storage.insert_into_table(
+            "accounts",
+            Row::new(vec![
+                Value::Integer(id),
+                Value::Text(format!("owner{}", id)),
+                Value::Integer(id * 100),
+            ]),
+        )?;
+    }
+    Ok(())
+}
+
+fn sorted_rows(storage: &StorageManager) -> Result<Vec<Row>, DatabaseError> {
+    let mut rows = storage.scan_table("accounts", None)?;
+    rows.sort_by(|a, b| a.values[0].partial_cmp(&b.values[0]).unwrap());
+    Ok(rows)
+}
+
+#[test]
+fn test_update_matching_rows_in_place() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_in_place");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_accounts(storage, 5)?;
+
+    let predicate = Predicate::gt("balance".to_string(), Value::Integer(250));
+    let updated = storage.update_table(
+        "accounts",
+        Some(predicate),
+        &[("balance".to_string(), Value::Integer(0))],
+    )?;
+    assert_eq!(updated, 3);
+
+    let rows = sorted_rows(storage)?;
+    assert_eq!(rows.len(), 5);
+    let balances: Vec<Value> = rows.iter().map(|row| row.values[2].clone()).collect();
+    assert_eq!(
+        balances,
+        vec![
+            Value::Integer(100),
+            Value::Integer(200),
+            Value::Integer(0),
+            Value::Integer(0),
+            Value::Integer(0),
+        ]
+    );
+
+    let updated = storage.update_table("accounts", None, &[("owner".to_string(), Value::Text("x".to_string()))])?;
+    assert_eq!(updated, 5);
+    assert!(sorted_rows(storage)?.iter().all(|row| row.values[1] == Value::Text("x".to_string())));
+    Ok(())
+}
+
+#[test]
+fn test_update_primary_key_reinserts_row() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_primary_key");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_accounts(storage, 5)?;
+
+    let predicate = Predicate::eq("id".to_string(), Value::Integer(3));
+    let updated = storage.update_table("accounts", Some(predicate), &[("id".to_string(), Value::Integer(100))])?;
+    assert_eq!(updated, 1);
+
+    let rows = sorted_rows(storage)?;
+    let ids: Vec<Value> = rows.iter().map(|row| row.values[0].clone()).collect();
+    assert_eq!(
+        ids,
+        vec![
+            Value::Integer(1),
+            Value::Integer(2),
+            Value::Integer(4),
+            Value::Integer(5),
+            Value::Integer(100),
+        ]
+    );
+    assert_eq!(rows[4].values[1], Value::Text("owner3".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_update_to_taken_primary_key_is_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_key_collision");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_accounts(storage, 10)?;
+    let before = sorted_rows(storage)?;
+
+    let predicate = Predicate::eq("id".to_string(), Value::Integer(5));
+    let result = storage.update_table("accounts", Some(predicate.clone()), &[("id".to_string(), Value::Integer(6))]);
+    assert!(matches!(result, Err(DatabaseError::UniqueConstraintViolation { .. })), "{:?}", result);
+    assert_eq!(sorted_rows(storage)?, before);
+
+    // A row keeps its own key, and may take one another row gives up in the same update
+    assert_eq!(storage.update_table("accounts", Some(predicate), &[("id".to_string(), Value::Integer(5))])?, 1);
+    let last = Predicate::eq("id".to_string(), Value::Integer(10));
+    assert_eq!(storage.update_table("accounts", Some(last), &[("id".to_string(), Value::Integer(11))])?, 1);
+    assert!(storage.get_by_primary_key("accounts", &[Value::Integer(10)])?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_bulk_update_collapsing_primary_keys_is_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_key_collapse");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_accounts(storage, 100)?;
+    let before = sorted_rows(storage)?;
+
+    let predicate = Predicate::gt("id".to_string(), Value::Integer(89));
+    let result = storage.update_table("accounts", Some(predicate), &[("id".to_string(), Value::Integer(1000))]);
+    assert!(matches!(result, Err(DatabaseError::UniqueConstraintViolation { .. })), "{:?}", result);
+    assert_eq!(sorted_rows(storage)?, before);
+    assert!(storage.get_by_primary_key("accounts", &[Value::Integer(1000)])?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_update_failing_part_way_leaves_rows_unchanged() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_rollback");
+    let limit = (BAMBANG_HEADER_SIZE + 6 * PAGE_SIZE) as u64;
+    let storage = temp_db
+        .create_storage_manager_with_options(StorageManagerOptions::new().with_max_database_size(limit))
+        .unwrap();
+    setup_accounts(storage, 30)?;
+    let before = sorted_rows(storage)?;
+
+    // Growing every row needs more pages than the quota leaves, after some rows were
+    // already deleted for reinsertion
+    let result = storage.update_table("accounts", None, &[("owner".to_string(), Value::Text("o".repeat(1500)))]);
+    assert!(matches!(result, Err(DatabaseError::QuotaExceeded { .. })), "{:?}", result);
+    assert_eq!(sorted_rows(storage)?, before);
+    assert!(storage.check_integrity()?.is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_update_to_taken_unique_value_is_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_unique_collision");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, email TEXT UNIQUE)")?;
+    for id in 1..=5 {
+        storage.insert_into_table("users", Row::new(vec![Value::Integer(id), Value::Text(format!("u{}@x", id))]))?;
+    }
+    let email = |id: i64| ("email".to_string(), Value::Text(format!("u{}@x", id)));
+    let id_is = |id: i64| Some(Predicate::eq("id".to_string(), Value::Integer(id)));
+
+    let result = storage.update_table("users", id_is(2), &[email(1)]);
+    assert!(
+        matches!(&result, Err(DatabaseError::UniqueConstraintViolation { column, .. }) if column == "email"),
+        "{:?}",
+        result
+    );
+    // Two matched rows given the same value collide with each other
+    let result = storage.update_table("users", Some(Predicate::gt("id".to_string(), Value::Integer(3))), &[email(9)]);
+    assert!(matches!(result, Err(DatabaseError::UniqueConstraintViolation { .. })), "{:?}", result);
+    let emails: Vec<Value> = storage.scan_table("users", None)?.into_iter().map(|row| row.values[1].clone()).collect();
+    assert!(!emails.contains(&Value::Text("u9@x".to_string())));
+
+    assert_eq!(storage.update_table("users", id_is(1), &[email(1)])?, 1);
+    assert_eq!(storage.update_table("users", id_is(1), &[email(6)])?, 1);
+    assert_eq!(storage.update_table("users", id_is(2), &[email(1)])?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_update_growing_row_past_page_capacity() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_grow");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_accounts(storage, 60)?;
+
+    // A 1500 byte owner no longer fits beside its neighbours and forces a reinsertion
+    let predicate = Predicate::eq("id".to_string(), Value::Integer(10));
+    let big_owner = "o".repeat(1500);
+    let updated = storage.update_table(
+        "accounts",
+        Some(predicate),
+        &[("owner".to_string(), Value::Text(big_owner.clone()))],
+    )?;
+    assert_eq!(updated, 1);
+
+    let rows = sorted_rows(storage)?;
+    assert_eq!(rows.len(), 60);
+    assert_eq!(rows[9].values[0], Value::Integer(10));
+    assert_eq!(rows[9].values[1], Value::Text(big_owner));
+    assert_eq!(rows[9].values[2], Value::Integer(1000));
+    Ok(())
+}
+
+#[test]
+fn test_update_into_overflow_is_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_overflow");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_accounts(storage, 3)?;
+
+    let result = storage.update_table(
+        "accounts",
+        None,
+        &[("owner".to_string(), Value::Text("o".repeat(PAGE_SIZE)))],
+    );
+    assert!(matches!(result, Err(DatabaseError::RowTooLarge { .. })));
+    assert!(sorted_rows(storage)?
+        .iter()
+        .all(|row| matches!(&row.values[1], Value::Text(owner) if owner.starts_with("owner"))));
+    Ok(())
+}
+
+#[test]
+fn test_update_rejects_invalid_assignments() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_invalid");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_accounts(storage, 3)?;
+
+    let not_null = storage.update_table("accounts", None, &[("owner".to_string(), Value::Null)]);
+    assert!(matches!(not_null, Err(DatabaseError::InvalidData { .. })));
+    let primary_key = storage.update_table("accounts", None, &[("id".to_string(), Value::Null)]);
+    assert!(matches!(primary_key, Err(DatabaseError::InvalidData { .. })));
+    let wrong_type = storage.update_table(
+        "accounts",
+        None,
+        &[("balance".to_string(), Value::Text("lots".to_string()))],
+    );
+    assert!(matches!(wrong_type, Err(DatabaseError::TypeMismatch { .. })));
+    let missing = storage.update_table("accounts", None, &[("nope".to_string(), Value::Integer(1))]);
+    assert!(matches!(missing, Err(DatabaseError::ColumnNotFound { .. })));
+    let no_table = storage.update_table("nope", None, &[]);
+    assert!(matches!(no_table, Err(DatabaseError::TableNotFound { .. })));
+
+    let rows = sorted_rows(storage)?;
+    assert_eq!(rows[0].values[1], Value::Text("owner1".to_string()));
+    Ok(())
+}
+
+fn balance(row: &Row) -> i64 {
+    match row.values[2] {
+        Value::Integer(balance) => balance,
+        _ => panic!("Expected integer balance"),
+    }
+}
+
+#[test]
+fn test_stale_read_modify_write_gets_version_conflict() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_version_conflict");
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_accounts(storage, 3)?;
+    let row_id = storage.scan_table("accounts", Some(Predicate::eq("id".to_string(), Value::Integer(2))))?[0]
+        .row_id
+        .unwrap();
+
+    // Both writers read the row, then each adds to the balance it read
+    let first = storage.get_by_rowid("accounts", row_id)?.unwrap();
+    let second = storage.get_by_rowid("accounts", row_id)?.unwrap();
+    assert_eq!(first.version, 0);
+    let deposit = [("balance".to_string(), Value::Integer(balance(&first) + 50))];
+    assert_eq!(storage.update_by_rowid("accounts", row_id, &deposit, Some(first.version))?, Some(1));
+
+    let withdrawal = [("balance".to_string(), Value::Integer(balance(&second) - 30))];
+    match storage.update_by_rowid("accounts", row_id, &withdrawal, Some(second.version)) {
+        Err(DatabaseError::VersionConflict { row_id: conflicted, expected, actual }) => {
+            assert_eq!((conflicted, expected, actual), (row_id, 0, 1));
+        }
+        other => panic!("Expected a version conflict, got {:?}", other),
+    }
+    let stored = storage.get_by_rowid("accounts", row_id)?.unwrap();
+    assert_eq!((balance(&stored), stored.version), (250, 1));
+
+    // Retrying from a fresh read succeeds
+    let retry = [("balance".to_string(), Value::Integer(balance(&stored) - 30))];
+    assert_eq!(storage.update_by_rowid("accounts", row_id, &retry, Some(stored.version))?, Some(2));
+    assert_eq!(balance(&storage.get_by_rowid("accounts", row_id)?.unwrap()), 220);
+    assert_eq!(storage.update_by_rowid("accounts", 99, &retry, None)?, None);
+
+    // A versioned multi-row update writes nothing if any match is stale
+    let all = [("owner".to_string(), Value::Text("bank".to_string()))];
+    let before = sorted_rows(storage)?;
+    assert!(matches!(
+        storage.update_table_versioned("accounts", None, &all, Some(0)),
+        Err(DatabaseError::VersionConflict { expected: 0, actual: 2, .. })
+    ));
+    assert_eq!(sorted_rows(storage)?, before);
+    // Blind writes skip the check but still bump every version
+    assert_eq!(storage.update_table("accounts", None, &all)?, 3);
+    let versions: Vec<u64> = sorted_rows(storage)?.iter().map(|row| row.version).collect();
+    assert_eq!(versions, vec![1, 3, 1]);
+    Ok(())
+}
+
+#[test]
+fn test_row_versions_survive_compaction_and_reopen() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_version_persist");
+    let path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    setup_accounts(storage, 40)?;
+    for round in 1..=3 {
+        storage.update_table(
+            "accounts",
+            Some(Predicate::lt("id".to_string(), Value::Integer(10 * round))),
+            &[("balance".to_string(), Value::Integer(round))],
+        )?;
+    }
+    // Deleting compacts the pages the surviving rows live on
+    storage.delete_from_table("accounts", Some(Predicate::gt("id".to_string(), Value::Integer(25))))?;
+
+    let expected: Vec<(i64, u64)> = (1..=25).map(|id| (id, 3 - id as u64 / 10)).collect();
+    let versions = |rows: Vec<Row>| -> Vec<(i64, u64)> {
+        rows.iter()
+            .map(|row| match row.values[0] {
+                Value::Integer(id) => (id, row.version),
+                _ => panic!("Expected integer ID"),
+            })
+            .collect()
+    };
+    assert_eq!(versions(sorted_rows(storage)?), expected);
+    temp_db.storage_manager = None;
+
+    let reopened = StorageManager::new(&path)?;
+    assert_eq!(versions(sorted_rows(&reopened)?), expected);
+    Ok(())
+}
+
+#[test]
+fn test_update_to_unique_value_of_overflow_row_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("update_overflow_unique");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("files", "CREATE TABLE files(id INTEGER PRIMARY KEY, name TEXT UNIQUE, data BLOB)")?;
+    for id in 1..=5 {
+        let size = if id == 3 { 3000 } else { 10 };
+        storage.insert_into_table(
+            "files",
+            Row::new(vec![Value::Integer(id), Value::Text(format!("a{}", id)), Value::Blob(vec![0xAB; size])]),
+        )?;
+    }
+
+    // Onto the overflow row's value, and the overflow row onto another row's
+    for (id, name) in [(5, "a3"), (3, "a4")] {
+        let predicate = Predicate::eq("id".to_string(), Value::Integer(id));
+        let result = storage.update_table("files", Some(predicate), &[("name".to_string(), Value::Text(name.to_string()))]);
+        match result {
+            Err(DatabaseError::UniqueConstraintViolation { column, value, .. }) => {
+                assert_eq!(column, "name");
+                assert_eq!(value, Value::Text(name.to_string()));
+            }
+            other => panic!("Expected UniqueConstraintViolation for {}, got {:?}", name, other),
+        }
+    }
+    let mut names: Vec<Value> = storage.scan_table("files", None)?.into_iter().map(|row| row.values[1].clone()).collect();
+    names.sort_by(|a, b| a.total_cmp(b));
+    assert_eq!(names, (1..=5).map(|id| Value::Text(format!("a{}", id))).collect::<Vec<_>>());
+    Ok(())
+}
@@ -1,5 +1,7 @@
+pub mod conformance;
 pub mod executor;
 pub mod optimizer;
 pub mod planner;
 pub mod storage;
 pub mod types;
+pub mod utils;
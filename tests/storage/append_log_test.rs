@@ -0,0 +1,180 @@
+use bambang::{
+    executor::{scan::Scanner, sequential_scan::SequentialScanner},
+    storage::{
+        append_log::{LogPosition, LogTrim},
+        schema::TableKind,
+        storage_manager::StorageManager,
+    },
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::TempDatabase,
+};
+
+const EVENTS_SQL: &str = "CREATE TABLE events(at TIMESTAMP, body TEXT)";
+
+/// An event every second from time 0, padded so a few dozen fill a page
+fn event(second: i64) -> Row {
+    Row::new(vec![Value::Timestamp(second * 1000), Value::Text(format!("{:0>200}", second))])
+}
+
+fn create_events(storage: &mut StorageManager, count: i64) -> Result<(), DatabaseError> {
+    storage.create_table_with_kind("events", EVENTS_SQL, TableKind::AppendLog)?;
+    storage.insert_batch_into_table("events", (0..count).map(event).collect())?;
+    Ok(())
+}
+
+fn seconds(rows: &[Row]) -> Vec<i64> {
+    rows.iter()
+        .map(|row| match row.values[0] {
+            Value::Timestamp(millis) => millis / 1000,
+            ref other => panic!("Expected a timestamp, got {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_append_log_scans_in_insert_order_and_survives_reopen() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("append_log_reopen");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_events(storage, 300)?;
+    storage.insert_into_table("events", event(300))?;
+
+    let rows = storage.scan_table("events", None)?;
+    assert_eq!(seconds(&rows), (0..=300).collect::<Vec<_>>());
+    assert_eq!(rows.iter().map(|row| row.row_id).collect::<Vec<_>>(), (1..=301).map(Some).collect::<Vec<_>>());
+    assert!(storage.table_page_stats("events")?.leaf_pages > 1);
+    storage.check_append_log("events")?;
+
+    let mut storage = StorageManager::new(&temp_db.path)?;
+    assert_eq!(storage.load_table_schema("events")?.unwrap().kind, TableKind::AppendLog);
+    assert_eq!(storage.scan_table("events", None)?, rows);
+    assert_eq!(storage.get_by_rowid("events", 42)?, Some(rows[41].clone()));
+
+    // Appends carry on from the tail and the rowid where they left off
+    storage.insert_into_table("events", event(301))?;
+    let last = storage.scan_table("events", None)?.pop().unwrap();
+    assert_eq!(last.row_id, Some(302));
+    let mut scanner = SequentialScanner::new(&storage, "events".to_string(), None)?;
+    assert_eq!(scanner.scan_batch(1000)?.len(), 302);
+    storage.check_append_log("events")?;
+    Ok(())
+}
+
+#[test]
+fn test_append_log_range_reads_prune_pages() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("append_log_range");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_events(storage, 600)?;
+    let leaf_pages = storage.table_page_stats("events")?.leaf_pages as u64;
+
+    let before = storage.io_stats().pages_read;
+    let rows = storage.read_log_range("events", LogPosition::Timestamp(500_000), Some(LogPosition::Timestamp(510_000)))?;
+    let pages_read = storage.io_stats().pages_read - before;
+    assert_eq!(seconds(&rows), (500..510).collect::<Vec<_>>());
+    assert!(pages_read <= 2, "read {} of {} pages", pages_read, leaf_pages);
+
+    let before = storage.io_stats().pages_read;
+    let rows = storage.read_log_range("events", LogPosition::RowId(11), Some(LogPosition::RowId(21)))?;
+    assert!(storage.io_stats().pages_read - before <= 2);
+    assert_eq!(seconds(&rows), (10..20).collect::<Vec<_>>());
+
+    // An open-ended range runs to the tail
+    let rows = storage.read_log_range("events", LogPosition::Timestamp(595_500), None)?;
+    assert_eq!(seconds(&rows), (596..600).collect::<Vec<_>>());
+
+    let mixed = storage.read_log_range("events", LogPosition::RowId(1), Some(LogPosition::Timestamp(0)));
+    assert!(matches!(mixed, Err(DatabaseError::InvalidData { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_drop_before_frees_whole_head_pages() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("append_log_retention");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_events(storage, 600)?;
+    let pages_before = storage.table_page_stats("events")?.leaf_pages;
+
+    let trim = storage.drop_before("events", LogPosition::Timestamp(300_000))?;
+    assert!(trim.pages_freed > 0);
+    assert_eq!(storage.free_page_ids()?.len(), trim.pages_freed);
+    assert_eq!(storage.table_page_stats("events")?.leaf_pages, pages_before - trim.pages_freed);
+
+    // Whole pages go, so a few rows before the cutoff may stay on the new head page
+    let remaining = seconds(&storage.scan_table("events", None)?);
+    assert_eq!(remaining.len(), 600 - trim.rows_removed);
+    assert!(remaining[0] <= 300);
+    assert_eq!(remaining, (remaining[0]..600).collect::<Vec<_>>());
+    storage.check_append_log("events")?;
+
+    // Trimming again to the same point finds nothing to free
+    assert_eq!(storage.drop_before("events", LogPosition::Timestamp(300_000))?, LogTrim::default());
+
+    // The new head is the table's root after a reopen, and freed pages are reused
+    let mut storage = StorageManager::new(&temp_db.path)?;
+    assert_eq!(seconds(&storage.scan_table("events", None)?), remaining);
+    storage.insert_batch_into_table("events", (600..700).map(event).collect())?;
+    assert!(storage.free_page_ids()?.len() < trim.pages_freed);
+    storage.check_append_log("events")?;
+
+    // The tail page is never freed, even when every row is before the cutoff
+    let trim = storage.drop_before("events", LogPosition::RowId(u64::MAX))?;
+    assert!(trim.pages_freed > 0);
+    assert_eq!(storage.table_page_stats("events")?.leaf_pages, 1);
+    assert_eq!(seconds(&storage.scan_table("events", None)?).last(), Some(&699));
+    Ok(())
+}
+
+#[test]
+fn test_append_log_rejects_time_going_backwards() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("append_log_order");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_events(storage, 10)?;
+
+    // Equal times are allowed, earlier ones are not
+    storage.insert_into_table("events", event(9))?;
+    assert!(storage.insert_into_table("events", event(5)).is_err());
+    assert_eq!(seconds(&storage.scan_table("events", None)?).last(), Some(&9));
+
+    // As with any batch, rows before the failing one stay appended
+    assert!(storage.insert_batch_into_table("events", vec![event(10), event(8)]).is_err());
+    assert_eq!(seconds(&storage.scan_table("events", None)?)[9..], [9, 9, 10]);
+    storage.check_append_log("events")?;
+    Ok(())
+}
+
+#[test]
+fn test_append_log_refuses_btree_operations() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("append_log_unsupported");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_events(storage, 10)?;
+
+    let unsupported = |result: Result<(), DatabaseError>| {
+        assert!(matches!(result, Err(DatabaseError::AppendLogUnsupported { .. })), "{:?}", result);
+    };
+    unsupported(storage.delete_from_table("events", None).map(|_| ()));
+    unsupported(storage.update_table("events", None, &[("body".to_string(), Value::Text("x".to_string()))]).map(|_| ()));
+    unsupported(storage.vacuum_table("events", false).map(|_| ()));
+    unsupported(storage.create_index("events", "at").map(|_| ()));
+    let mut scanner = SequentialScanner::new(storage, "events".to_string(), None)?;
+    unsupported(scanner.scan_reverse().map(|_| ()));
+
+    // A log has no keys to keep unique
+    let keyed = storage.create_table_with_kind(
+        "keyed",
+        "CREATE TABLE keyed(id INTEGER PRIMARY KEY, at TIMESTAMP)",
+        TableKind::AppendLog,
+    );
+    assert!(matches!(keyed, Err(DatabaseError::AppendLogUnsupported { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_append_log_exports_csv() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("append_log_export");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_events(storage, 100)?;
+
+    let mut csv = Vec::new();
+    assert_eq!(storage.export_csv("events", None, &mut csv)?, 100);
+    assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 101);
+    Ok(())
+}
@@ -1,13 +1,19 @@
 use bambang::{
-    storage::bplus_tree::BPlusTree,
+    storage::{bplus_tree::BPlusTree, io_stats::IoCounters},
     types::{
-        PAGE_SIZE,
+        PAGE_HEADER_SIZE, PAGE_SIZE, SLOT_DIRECTORY_ENTRY_SIZE,
+        error::DatabaseError,
         page::{Page, PageType},
         row::Row,
         value::Value,
     },
 };
-use std::io::Write;
+use bambang::utils::mock::MockClock;
+use std::{
+    io::{Seek, SeekFrom, Write},
+    sync::Arc,
+    time::Duration,
+};
 use tempfile::NamedTempFile;
 
 fn create_test_db_file() -> NamedTempFile {
@@ -19,6 +25,11 @@ fn create_test_db_file() -> NamedTempFile {
     temp_file
 }
 
+/// A tree over `file` that checks page and split invariants on every write
+fn paranoid_btree(file: std::fs::File) -> BPlusTree {
+    BPlusTree::new(file, 1).unwrap().with_paranoid_checks(true)
+}
+
 fn create_test_row(key: i64, name: &str) -> Row {
     Row::new(vec![Value::Integer(key), Value::Text(name.to_string())])
 }
@@ -27,7 +38,7 @@ fn create_test_row(key: i64, name: &str) -> Row {
 fn test_bplus_tree_creation() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let btree = BPlusTree::new(file, 1).unwrap();
+    let btree = paranoid_btree(file);
     assert_eq!(btree.root_page_id, 1);
     assert_eq!(btree.order, 4);
     assert_eq!(btree.next_page_id, 2);
@@ -38,7 +49,7 @@ fn test_bplus_tree_creation() {
 fn test_single_row_insert() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let test_row = create_test_row(1, "Alice");
     let result = btree.insert(test_row, None).unwrap();
     assert!(result.is_none());
@@ -51,7 +62,7 @@ fn test_single_row_insert() {
 fn test_multiple_row_insert_no_split() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let rows = vec![
         create_test_row(1, "Alice"),
         create_test_row(2, "Bob"),
@@ -70,7 +81,7 @@ fn test_multiple_row_insert_no_split() {
 fn test_row_insert_with_leaf_split() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let large_name = "A".repeat(1000);
     let mut rows = Vec::new();
     for i in 1..=10 {
@@ -95,7 +106,7 @@ fn test_row_insert_with_leaf_split() {
 fn test_ordered_insertion() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     for i in 1..=5 {
         let row = create_test_row(i, &format!("User{}", i));
         btree.insert(row, None).unwrap();
@@ -108,7 +119,7 @@ fn test_ordered_insertion() {
 fn test_reverse_ordered_insertion() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     for i in (1..=5).rev() {
         let row = create_test_row(i, &format!("User{}", i));
         btree.insert(row, None).unwrap();
@@ -121,7 +132,7 @@ fn test_reverse_ordered_insertion() {
 fn test_random_insertion() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let keys = vec![3, 1, 4, 2, 5];
     for key in keys {
         let row = create_test_row(key, &format!("User{}", key));
@@ -135,7 +146,7 @@ fn test_random_insertion() {
 fn test_duplicate_key_insertion() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let row1 = create_test_row(1, "Alice");
     let row2 = create_test_row(1, "Bob");
     btree.insert(row1, None).unwrap();
@@ -148,10 +159,10 @@ fn test_duplicate_key_insertion() {
 fn test_large_data_insertion_with_overflow() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let large_data = "X".repeat(PAGE_SIZE / 2);
     let large_row = create_test_row(1, &large_data);
-    let result = btree.insert(large_row, None).unwrap();
+    let _result = btree.insert(large_row, None).unwrap();
     let root_page = btree.load_page(btree.root_page_id, None).unwrap();
     assert!(root_page.cell_count > 0);
 }
@@ -160,7 +171,7 @@ fn test_large_data_insertion_with_overflow() {
 fn test_interior_page_creation() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let large_name = "Data".repeat(500);
     let mut interior_created = false;
     for i in 1..=20 {
@@ -178,7 +189,7 @@ fn test_interior_page_creation() {
 fn test_page_allocation() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let initial_next_page = btree.next_page_id;
     let large_data = "X".repeat(1000);
     for i in 1..=10 {
@@ -192,7 +203,7 @@ fn test_page_allocation() {
 fn test_cell_data_integrity() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let test_data = vec![
         (1, "Alice"),
         (2, "Bob with special chars: !@#$%^&*()"),
@@ -222,7 +233,7 @@ fn test_cell_data_integrity() {
 fn test_split_result_structure() {
     let temp_file = create_test_db_file();
     let file = temp_file.reopen().unwrap();
-    let mut btree = BPlusTree::new(file, 1).unwrap();
+    let mut btree = paranoid_btree(file);
     let large_data = "X".repeat(800);
     let mut split_result = None;
     for i in 1..=15 {
@@ -235,3 +246,310 @@ fn test_split_result_structure() {
     assert!(split_result.is_some());
     assert!(btree.root_page_id > 1);
 }
+
+#[test]
+fn test_page_cache_stays_bounded() {
+    let temp_file = create_test_db_file();
+    let file = temp_file.reopen().unwrap();
+    let mut btree = paranoid_btree(file);
+    btree.set_cache_capacity(4).unwrap();
+    assert_eq!(btree.page_cache.capacity(), 4);
+    let large_data = "X".repeat(500);
+    for i in 1..=100 {
+        let row = create_test_row(i, &format!("{}{}", large_data, i));
+        btree.insert(row, None).unwrap();
+        assert!(btree.page_cache.len() <= 4);
+    }
+    assert!(btree.next_page_id > 5);
+    let leaf_cells: u64 = (1..btree.next_page_id)
+        .map(|page_id| {
+            let page = btree.load_page(page_id, None).unwrap();
+            if page.page_type == PageType::LeafTable {
+                page.active_cell_count() as u64
+            } else {
+                0
+            }
+        })
+        .sum();
+    assert_eq!(leaf_cells, 100);
+    assert!(btree.page_cache.len() <= 4);
+}
+
+#[test]
+fn test_page_cache_shrinks_on_capacity_change() {
+    let temp_file = create_test_db_file();
+    let file = temp_file.reopen().unwrap();
+    let mut btree = paranoid_btree(file);
+    let large_data = "X".repeat(500);
+    for i in 1..=40 {
+        btree.insert(create_test_row(i, &format!("{}{}", large_data, i)), None).unwrap();
+    }
+    assert!(btree.page_cache.len() > 2);
+    btree.set_cache_capacity(2).unwrap();
+    assert_eq!(btree.page_cache.len(), 2);
+    let root_id = btree.root_page_id;
+    let root_page = btree.load_page(root_id, None).unwrap();
+    assert_eq!(root_page.page_type, PageType::InteriorTable);
+}
+
+/// Leaf cells across every page of the tree, loading each page through the cache
+fn count_leaf_cells(btree: &mut BPlusTree) -> u64 {
+    (1..btree.next_page_id)
+        .map(|page_id| {
+            let page = btree.load_page(page_id, None).unwrap();
+            if page.page_type == PageType::LeafTable {
+                page.active_cell_count() as u64
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+#[test]
+fn test_cold_pages_are_evicted_by_age() {
+    let temp_file = create_test_db_file();
+    let clock = Arc::new(MockClock::new());
+    let counters = Arc::new(IoCounters::default());
+    let mut btree = paranoid_btree(temp_file.reopen().unwrap())
+        .with_cache_clock(clock.clone())
+        .with_io_counters(counters.clone());
+    let large_data = "X".repeat(500);
+    for i in 1..=40 {
+        btree.insert(create_test_row(i, &format!("{}{}", large_data, i)), None).unwrap();
+    }
+    assert_eq!(count_leaf_cells(&mut btree), 40);
+    let warm = btree.cache_stats();
+    assert_eq!(warm.resident_pages as u64, btree.next_page_id - 1);
+    assert_eq!(warm.evicted_by_age, 0);
+
+    // Only the root is used within the window
+    clock.advance(Duration::from_secs(60));
+    let root_id = btree.root_page_id;
+    btree.load_page(root_id, None).unwrap();
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(btree.evict_cold_pages(Duration::from_secs(30), false).unwrap(), warm.resident_pages - 1);
+    let cold = btree.cache_stats();
+    assert_eq!(cold.resident_pages, 1);
+    assert_eq!(cold.evicted_by_age as usize, warm.resident_pages - 1);
+    assert!(cold.resident_bytes * 4 < warm.resident_bytes);
+    assert!(btree.page_cache.contains_key(&root_id));
+
+    // Evicted pages come back from the file
+    let reads_before = counters.snapshot().pages_read;
+    assert_eq!(count_leaf_cells(&mut btree), 40);
+    assert_eq!(counters.snapshot().pages_read - reads_before, warm.resident_pages as u64 - 1);
+}
+
+#[test]
+fn test_dirty_pages_survive_age_eviction_unless_flushed() {
+    let temp_file = create_test_db_file();
+    let clock = Arc::new(MockClock::new());
+    let mut btree = paranoid_btree(temp_file.reopen().unwrap()).with_cache_clock(clock.clone());
+    for i in 1..=3 {
+        btree.insert(create_test_row(i, &format!("User{}", i)), None).unwrap();
+    }
+    btree.load_page(1, None).unwrap();
+    btree.page_cache.get_mut(&1).unwrap().is_dirty = true;
+
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(btree.evict_cold_pages(Duration::from_secs(1), false).unwrap(), 0);
+    assert!(btree.page_cache.contains_key(&1));
+    assert_eq!(btree.evict_cold_pages(Duration::from_secs(1), true).unwrap(), 1);
+    assert!(btree.page_cache.is_empty());
+    assert_eq!(btree.load_page(1, None).unwrap().active_cell_count(), 3);
+}
+
+#[test]
+fn test_cache_sweep_runs_once_its_interval_passes() {
+    let temp_file = create_test_db_file();
+    let clock = Arc::new(MockClock::new());
+    let mut btree = paranoid_btree(temp_file.reopen().unwrap())
+        .with_cache_clock(clock.clone())
+        .with_cache_sweep(Duration::from_secs(10), Duration::from_secs(5));
+    let large_data = "X".repeat(500);
+    for i in 1..=40 {
+        btree.insert(create_test_row(i, &format!("{}{}", large_data, i)), None).unwrap();
+    }
+    assert_eq!(count_leaf_cells(&mut btree), 40);
+    let resident = btree.cache_stats().resident_pages;
+    assert!(resident > 2);
+
+    // Pages are old enough but the sweep is not due yet
+    clock.advance(Duration::from_secs(6));
+    let root_id = btree.root_page_id;
+    btree.load_page(root_id, None).unwrap();
+    assert_eq!(btree.cache_stats().resident_pages, resident);
+
+    clock.advance(Duration::from_secs(4));
+    btree.load_page(root_id, None).unwrap();
+    let stats = btree.cache_stats();
+    assert_eq!(stats.resident_pages, 1);
+    assert_eq!(stats.evicted_by_age as usize, resident - 1);
+    assert_eq!(count_leaf_cells(&mut btree), 40);
+}
+
+#[test]
+fn test_paranoid_checks_catch_corruption_at_the_write() {
+    let temp_file = create_test_db_file();
+    let file = temp_file.reopen().unwrap();
+    let mut btree = paranoid_btree(file);
+    for i in 1..=3 {
+        btree.insert(create_test_row(i, &format!("User{}", i)), None).unwrap();
+    }
+
+    // Point the second slot into the first cell, as a buggy compaction might
+    let root = btree.page_cache.get_mut(&1).unwrap();
+    let first = root.slot_directory.slots[0].clone();
+    root.slot_directory.slots[1].offset = first.offset;
+
+    match btree.insert(create_test_row(4, "User4"), None) {
+        Err(DatabaseError::InternalInvariant { details }) => {
+            assert!(details.contains("page 1"), "{}", details);
+            assert!(details.contains("overlap"), "{}", details);
+        }
+        other => panic!("expected an invariant violation, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_split_divides_large_cells_by_size() {
+    let temp_file = create_test_db_file();
+    let file = temp_file.reopen().unwrap();
+    let mut btree = paranoid_btree(file);
+    let large = "L".repeat(1800);
+    btree.insert(create_test_row(1, &large), None).unwrap();
+    btree.insert(create_test_row(2, &large), None).unwrap();
+    let mut key = 3;
+    while btree.load_page(1, None).unwrap().available_space() > 40 {
+        btree.insert(create_test_row(key, "small"), None).unwrap();
+        key += 1;
+    }
+
+    // Splitting by cell count would put all three large cells on the left, which cannot
+    // hold them; splitting by size gives each page what fits
+    btree.insert(create_test_row(0, &large), None).unwrap();
+    assert_eq!(btree.load_page(btree.root_page_id, None).unwrap().page_type, PageType::InteriorTable);
+    for id in 0..key {
+        assert!(btree.search(&Value::Integer(id), None).unwrap().is_some(), "key {}", id);
+    }
+}
+
+/// Keys of the live cells of `page` in slot order; interior pages give their upper bounds
+fn slot_keys(btree: &mut BPlusTree, page_id: u64) -> Vec<Value> {
+    let page = btree.load_page(page_id, None).unwrap().clone();
+    (0..page.slot_directory.slots.len())
+        .filter_map(|slot| page.get_cell(slot))
+        .map(|cell| match page.page_type {
+            PageType::InteriorTable => Value::from_bytes(&cell[12..]).unwrap(),
+            _ => btree.extract_key_from_cell(cell).unwrap(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_pages_keep_keys_in_order_and_older_pages_are_searched_linearly() {
+    let temp_file = create_test_db_file();
+    let mut btree = paranoid_btree(temp_file.reopen().unwrap());
+    let keys: Vec<i64> = (0..300).map(|i| i * 7919 % 300).collect();
+    for key in &keys {
+        btree.insert(create_test_row(*key, &format!("User{}", key)), None).unwrap();
+    }
+    let page_ids = btree.page_ids(None).unwrap();
+    assert!(page_ids.len() > 3);
+    for page_id in &page_ids {
+        assert!(btree.load_page(*page_id, None).unwrap().sorted);
+        let mut sorted = slot_keys(&mut btree, *page_id);
+        sorted.sort_by(Page::compare_upper_bounds);
+        assert_eq!(slot_keys(&mut btree, *page_id), sorted, "page {}", page_id);
+    }
+    for key in [0, 150, 299] {
+        let row = btree.search(&Value::Integer(key), None).unwrap().unwrap();
+        assert_eq!(row.values[1], Value::Text(format!("User{}", key)));
+    }
+    assert!(btree.search(&Value::Integer(300), None).unwrap().is_none());
+
+    // Rewrite every page the way an older version left it: cells in reverse order and
+    // no sorted flag
+    let mut file = temp_file.reopen().unwrap();
+    for page_id in &page_ids {
+        let mut page = btree.load_page(*page_id, None).unwrap().clone();
+        page.slot_directory.slots.reverse();
+        page.sorted = false;
+        page.update_checksum();
+        file.seek(SeekFrom::Start((page_id - 1) * PAGE_SIZE as u64)).unwrap();
+        file.write_all(&page.to_bytes().unwrap()).unwrap();
+    }
+    let root_page_id = btree.root_page_id;
+    drop(btree);
+
+    let mut btree = BPlusTree::new(temp_file.reopen().unwrap(), root_page_id).unwrap().with_paranoid_checks(true);
+    for key in keys.iter().step_by(7) {
+        let row = btree.search(&Value::Integer(*key), None).unwrap().unwrap();
+        assert_eq!(row.values[1], Value::Text(format!("User{}", key)));
+    }
+    for key in 300..400 {
+        btree.insert(create_test_row(key, &format!("User{}", key)), None).unwrap();
+    }
+    for key in [5, 299, 300, 399] {
+        assert!(btree.search(&Value::Integer(key), None).unwrap().is_some(), "key {}", key);
+    }
+    assert_eq!(count_leaf_cells(&mut btree), 400);
+}
+
+/// Insert rows of the given body sizes under `keys`, in that order, then check every row
+/// reads back and the leaves are kept full: at least `min_utilization` of their cell area
+/// on average, and no more leaves than the data needs at half that
+fn assert_packed_leaves(keys: &[i64], body_len: impl Fn(i64) -> usize, min_utilization: f64) {
+    let temp_file = create_test_db_file();
+    let mut btree = paranoid_btree(temp_file.reopen().unwrap());
+    let body = |key: i64| "b".repeat(body_len(key));
+    let mut data_bytes = 0;
+    for key in keys {
+        let row = create_test_row(*key, &body(*key));
+        data_bytes += row.to_bytes().len() + SLOT_DIRECTORY_ENTRY_SIZE;
+        btree.insert(row, None).unwrap();
+    }
+    for key in keys {
+        let row = btree.search(&Value::Integer(*key), None).unwrap();
+        assert_eq!(row.map(|row| row.values[1].clone()), Some(Value::Text(body(*key))), "key {}", key);
+    }
+
+    let capacity = PAGE_SIZE - PAGE_HEADER_SIZE;
+    let mut leaves = 0;
+    let mut used_bytes = 0;
+    for page_id in btree.page_ids(None).unwrap() {
+        let page = btree.load_page(page_id, None).unwrap();
+        if page.page_type == PageType::LeafTable {
+            leaves += 1;
+            used_bytes += capacity - page.available_space();
+        }
+    }
+    assert_eq!(used_bytes, data_bytes);
+    let utilization = used_bytes as f64 / (leaves * capacity) as f64;
+    assert!(utilization >= min_utilization, "leaf utilization {:.2} over {} leaves", utilization, leaves);
+    assert!(leaves <= (data_bytes as f64 / (capacity as f64 * min_utilization / 2.0)).ceil() as usize);
+}
+
+#[test]
+fn test_interleaved_row_sizes_keep_leaves_full() {
+    let alternating = |key: i64| if key % 2 == 0 { 10 } else { 1900 };
+    let ascending: Vec<i64> = (0..400).collect();
+    let descending: Vec<i64> = (0..400).rev().collect();
+    let shuffled: Vec<i64> = (0..400).map(|i| i * 7919 % 400).collect();
+    let clustered: Vec<i64> = (0..400).map(|i| (i % 20) * 20 + i / 20).collect();
+    assert_packed_leaves(&ascending, alternating, 0.9);
+    assert_packed_leaves(&descending, alternating, 0.9);
+    assert_packed_leaves(&shuffled, alternating, 0.6);
+    assert_packed_leaves(&clustered, alternating, 0.6);
+
+    // Runs of small rows between large ones of two sizes
+    let runs = |key: i64| match key % 7 {
+        0 => 1900,
+        3 => 1300,
+        _ => 10,
+    };
+    assert_packed_leaves(&ascending, runs, 0.8);
+    assert_packed_leaves(&shuffled, runs, 0.6);
+    assert_packed_leaves(&clustered, runs, 0.6);
+}
@@ -0,0 +1,112 @@
+use std::fs;
+
+use bambang::{
+    executor::{predicate::Predicate, statement::execute_statement},
+    storage::{column_usage::ColumnUsage, options::StorageManagerOptions, storage_manager::StorageManager},
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::create_temp_db_path_with_prefix,
+};
+
+fn seed_users(storage: &mut StorageManager) -> Result<(), DatabaseError> {
+    storage.create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT, nickname TEXT)")?;
+    let rows = (1..=10)
+        .map(|id| {
+            Row::new(vec![
+                Value::Integer(id),
+                Value::Text(format!("user_{}", id)),
+                Value::Text(format!("user{}@example.com", id)),
+                if id % 4 == 0 { Value::Text(format!("nick{}", id)) } else { Value::Null },
+            ])
+        })
+        .collect();
+    storage.insert_batch_into_table("users", rows)?;
+    Ok(())
+}
+
+/// `(name, reads, writes)` of each column
+fn counts(usage: &[ColumnUsage]) -> Vec<(&str, u64, u64)> {
+    usage.iter().map(|column| (column.name.as_str(), column.reads, column.writes)).collect()
+}
+
+#[test]
+fn test_column_usage_counts_each_projection_and_predicate_once_per_query() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("column_usage");
+    let mut storage = StorageManager::new(&path)?;
+    seed_users(&mut storage)?;
+
+    for _ in 0..3 {
+        storage.scan_table_projected("users", &["id", "name"], None)?;
+    }
+    let by_email = Predicate::eq("email".to_string(), Value::Text("user3@example.com".to_string()));
+    assert_eq!(storage.scan_table_projected("users", &["name"], Some(by_email))?.len(), 1);
+    storage.count_rows("users", Some(Predicate::gt("id".to_string(), Value::Integer(5))))?;
+    execute_statement(&mut storage, "SELECT name, name FROM users WHERE id = 2 OR id = 4")?;
+    execute_statement(&mut storage, "SELECT * FROM users")?;
+    storage.insert_into_table(
+        "users",
+        Row::new(vec![Value::Integer(11), Value::Text("user_11".to_string()), Value::Null, Value::Null]),
+    )?;
+    storage.update_table(
+        "users",
+        Some(Predicate::lt("id".to_string(), Value::Integer(3))),
+        &[("nickname".to_string(), Value::Text("early".to_string()))],
+    )?;
+
+    // Reads come from the projections and predicates, not from the rows they went over
+    let usage = storage.column_usage("users")?;
+    assert_eq!(
+        counts(&usage),
+        vec![("id", 7, 11), ("name", 6, 11), ("email", 2, 11), ("nickname", 1, 13)]
+    );
+    // Two seeded nicknames, a NULL insert, then two assigned by the update
+    assert_eq!(usage[3].non_null_ratio, 4.0 / 13.0);
+    assert_eq!(usage[2].non_null_ratio, 10.0 / 11.0);
+
+    assert!(matches!(storage.column_usage("missing"), Err(DatabaseError::TableNotFound { .. })));
+    storage.close()?;
+
+    // Counters are per session unless persisted
+    let storage = StorageManager::new(&path)?;
+    assert!(storage.column_usage("users")?.iter().all(|column| column.reads == 0 && column.writes == 0));
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_persisted_column_usage_accumulates_across_sessions() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("column_usage_persist");
+    let options = || StorageManagerOptions::new().with_persist_column_usage(true);
+    {
+        let mut storage = StorageManager::open_with_options(&path, options())?;
+        seed_users(&mut storage)?;
+        storage.scan_table_projected("users", &["email"], None)?;
+        storage.close()?;
+    }
+    for session in 1..=2 {
+        let storage = StorageManager::open_with_options(&path, options())?;
+        assert_eq!(
+            counts(&storage.column_usage("users")?),
+            vec![("id", 0, 10), ("name", 0, 10), ("email", session, 10), ("nickname", 0, 10)]
+        );
+        storage.scan_table_projected("users", &["email"], None)?;
+        storage.close()?;
+    }
+
+    // A table added since the last save gets an entry of its own, and a dropped one
+    // takes its entry with it
+    {
+        let mut storage = StorageManager::open_with_options(&path, options())?;
+        storage.create_table("tags", "CREATE TABLE tags(id INTEGER, label TEXT)")?;
+        storage.insert_into_table("tags", Row::new(vec![Value::Integer(1), Value::Text("a".to_string())]))?;
+        storage.drop_table("users")?;
+        storage.create_table("users", "CREATE TABLE users(id INTEGER, name TEXT)")?;
+        storage.close()?;
+    }
+    let storage = StorageManager::open_with_options(&path, options())?;
+    assert_eq!(counts(&storage.column_usage("tags")?), vec![("id", 0, 1), ("label", 0, 1)]);
+    assert_eq!(counts(&storage.column_usage("users")?), vec![("id", 0, 0), ("name", 0, 0)]);
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
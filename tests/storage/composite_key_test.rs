@@ -0,0 +1,105 @@
+use std::fs;
+
+use bambang::{
+    executor::statement::execute_statement,
+    storage::{
+        options::{OpenMode, StorageManagerOptions},
+        storage_manager::StorageManager,
+    },
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::create_temp_db_path_with_prefix,
+};
+
+const TENANTS: i64 = 3;
+const USERS: i64 = 120;
+
+fn member(tenant_id: i64, user_id: i64) -> Row {
+    Row::new(vec![
+        Value::Integer(tenant_id),
+        Value::Integer(user_id),
+        Value::Text(format!("member {tenant_id}/{user_id} with a name long enough to split pages")),
+    ])
+}
+
+/// The `(tenant_id, user_id)` pairs of `rows`, in the order given
+fn pairs(rows: &[Row]) -> Vec<(i64, i64)> {
+    rows.iter()
+        .map(|row| match row.values[..2] {
+            [Value::Integer(tenant_id), Value::Integer(user_id)] => (tenant_id, user_id),
+            ref other => panic!("unexpected key {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_composite_key_orders_and_finds_rows_by_the_whole_tuple() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("composite_key");
+    {
+        let mut storage_manager = StorageManager::new(&path)?;
+        execute_statement(
+            &mut storage_manager,
+            "CREATE TABLE members (tenant_id INTEGER, user_id INTEGER, name TEXT, PRIMARY KEY (tenant_id, user_id))",
+        )?;
+        let schema = storage_manager.get_table_schema("members").unwrap();
+        assert_eq!(schema.primary_key, vec![0, 1]);
+
+        // Rows share their first component and arrive out of order, with negative ids
+        // to sort ahead of the rest
+        let rows = (0..TENANTS)
+            .rev()
+            .flat_map(|tenant_id| (0..USERS).rev().map(move |user_id| member(tenant_id, user_id - 10)))
+            .collect();
+        storage_manager.insert_batch_into_table("members", rows)?;
+        // A bulk load writes each leaf in key order, so the scan follows the tuples
+        let loaded: Vec<(i64, i64)> = (0..TENANTS)
+            .flat_map(|tenant_id| (0..USERS).map(move |user_id| (tenant_id, user_id - 10)))
+            .collect();
+        assert_eq!(pairs(&storage_manager.scan_table("members", None)?), loaded);
+        assert!(storage_manager.table_page_stats("members")?.leaf_pages > 1);
+
+        storage_manager.insert_into_table("members", member(1, 500))?;
+        storage_manager.insert_into_table("members", member(1, -500))?;
+
+        // Taken tuples are rejected on their own and within a batch, but a component
+        // may repeat in another tuple
+        let taken = storage_manager.insert_into_table("members", member(2, 7));
+        assert!(matches!(
+            taken,
+            Err(DatabaseError::UniqueConstraintViolation { ref column, ref value, .. })
+                if column == "(tenant_id, user_id)" && *value == Value::Text("(2, 7)".to_string())
+        ));
+        let batch = storage_manager.insert_batch_into_table("members", vec![member(3, 1), member(3, 2), member(3, 1)]);
+        assert!(matches!(batch, Err(DatabaseError::UniqueConstraintViolation { .. })));
+        assert!(storage_manager.insert_batch_into_table("members", vec![member(4, 0), member(0, 0)]).is_err());
+    }
+
+    let mut expected: Vec<(i64, i64)> = (0..TENANTS)
+        .flat_map(|tenant_id| (0..USERS).map(move |user_id| (tenant_id, user_id - 10)))
+        .chain([(1, 500), (1, -500), (3, 1), (3, 2), (4, 0)])
+        .collect();
+    expected.sort_unstable();
+
+    for mode in [OpenMode::Eager, OpenMode::Lazy] {
+        let storage_manager =
+            StorageManager::open_with_options(&path, StorageManagerOptions::new().with_open_mode(mode))?;
+        let mut stored = pairs(&storage_manager.scan_table("members", None)?);
+        stored.sort_unstable();
+        assert_eq!(stored, expected);
+
+        let found = storage_manager.get_by_primary_key("members", &[Value::Integer(1), Value::Integer(-500)])?;
+        assert_eq!(found.map(|row| row.values), Some(member(1, -500).values));
+        for (tenant_id, user_id) in [(0, -10), (1, 42), (2, 109), (4, 0)] {
+            let row = storage_manager
+                .get_by_primary_key("members", &[Value::Integer(tenant_id), Value::Integer(user_id)])?
+                .unwrap();
+            assert_eq!(pairs(&[row]), vec![(tenant_id, user_id)]);
+        }
+        assert!(storage_manager
+            .get_by_primary_key("members", &[Value::Integer(1), Value::Integer(110)])?
+            .is_none());
+        assert!(storage_manager.get_by_primary_key("members", &[Value::Integer(1)]).is_err());
+    }
+
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
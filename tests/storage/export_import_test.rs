@@ -0,0 +1,154 @@
+use bambang::{
+    executor::predicate::Predicate,
+    storage::{export::JsonLayout, storage_manager::StorageManager},
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::TempDatabase,
+};
+
+const SAMPLES_SQL: &str =
+    "CREATE TABLE {name} (id INTEGER, label TEXT, score REAL, done BOOLEAN, payload BLOB, at TIMESTAMP, amount DECIMAL)";
+
+fn create_samples(storage: &mut StorageManager, name: &str) -> Result<(), DatabaseError> {
+    storage.create_table(name, &SAMPLES_SQL.replace("{name}", name))?;
+    Ok(())
+}
+
+fn sample_rows() -> Vec<Row> {
+    let decimal = |text: &str| Value::Decimal(text.parse().unwrap());
+    vec![
+        Row::new(vec![
+            Value::Integer(1),
+            Value::Text("plain".to_string()),
+            Value::Real(0.1),
+            Value::Boolean(true),
+            Value::Blob(vec![0xde, 0xad, 0xbe, 0xef]),
+            Value::Timestamp(1_700_000_000),
+            decimal("12345.6789"),
+        ]),
+        Row::new(vec![
+            Value::Integer(i64::MIN),
+            Value::Text("comma, \"quotes\"\nand a line break — ünïcode".to_string()),
+            Value::Real(-1.0e300),
+            Value::Boolean(false),
+            Value::Blob(Vec::new()),
+            Value::Timestamp(-86_400),
+            decimal("-0.001"),
+        ]),
+        Row::new(vec![
+            Value::Integer(i64::MAX),
+            Value::Text(String::new()),
+            Value::Real(f64::MIN_POSITIVE),
+            Value::Boolean(true),
+            Value::Blob(vec![0, 1, 2]),
+            Value::Timestamp(0),
+            decimal("99999999999999999999.5"),
+        ]),
+        Row::new(vec![
+            Value::Integer(4),
+            Value::Text("NULL".to_string()),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+        ]),
+    ]
+}
+
+fn setup(temp_db: &mut TempDatabase) -> Result<&mut StorageManager, DatabaseError> {
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_samples(storage, "samples")?;
+    for row in sample_rows() {
+        storage.insert_into_table("samples", row)?;
+    }
+    Ok(storage)
+}
+
+#[test]
+fn test_export_then_import_round_trips_every_value_type() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("export_round_trip");
+    let storage = setup(&mut temp_db)?;
+    // Imported rows get row ids in the order they are read, so only values are compared
+    let values = |rows: Vec<Row>| rows.into_iter().map(|row| row.values).collect::<Vec<_>>();
+    let original = values(storage.scan_table("samples", None)?);
+    assert_eq!(original.len(), 4);
+
+    let mut csv = Vec::new();
+    assert_eq!(storage.export_csv("samples", None, &mut csv)?, 4);
+    create_samples(storage, "from_csv")?;
+    assert_eq!(storage.import_csv("from_csv", &mut csv.as_slice())?, 4);
+    assert_eq!(values(storage.scan_table("from_csv", None)?), original);
+
+    for (name, layout) in [("from_json", JsonLayout::Array), ("from_lines", JsonLayout::Lines)] {
+        let mut json = Vec::new();
+        assert_eq!(storage.export_json("samples", None, &mut json, layout)?, 4);
+        create_samples(storage, name)?;
+        assert_eq!(storage.import_json(name, &mut json.as_slice())?, 4);
+        assert_eq!(values(storage.scan_table(name, None)?), original, "{:?}", layout);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_export_formats_quote_and_encode_values() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("export_formats");
+    let storage = setup(&mut temp_db)?;
+
+    let mut csv = Vec::new();
+    storage.export_csv("samples", None, &mut csv)?;
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("id,label,score,done,payload,at,amount"));
+    // Rows are written in key order
+    assert_eq!(lines.next(), Some("-9223372036854775808,\"comma, \"\"quotes\"\""));
+    assert!(csv.contains("\"comma, \"\"quotes\"\"\nand a line break — ünïcode\""), "{}", csv);
+    assert!(csv.contains("\n1,plain,0.1,true,deadbeef,2023-11-14T22:13:20Z,12345.6789\n"), "{}", csv);
+    // Empty text and an empty blob are quoted; NULL is left empty
+    assert!(csv.contains(",false,\"\",1969-12-31T00:00:00Z,-0.001\n"), "{}", csv);
+    assert!(csv.contains("\n4,NULL,,,,,\n"), "{}", csv);
+    assert!(csv.contains("\n9223372036854775807,\"\","), "{}", csv);
+
+    let mut lines = Vec::new();
+    let predicate = Predicate::gt("id".to_string(), Value::Integer(1));
+    assert_eq!(storage.export_json("samples", Some(predicate), &mut lines, JsonLayout::Lines)?, 2);
+    let lines = String::from_utf8(lines).unwrap();
+    let objects: Vec<serde_json::Value> = lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(objects.len(), 2);
+    assert_eq!(objects[0]["score"], serde_json::Value::Null);
+    assert_eq!(objects[1]["payload"], "000102");
+    assert_eq!(objects[1]["at"], "1970-01-01T00:00:00Z");
+    Ok(())
+}
+
+#[test]
+fn test_import_rejects_unknown_columns_and_bad_values() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("import_errors");
+    let storage = setup(&mut temp_db)?;
+
+    let unknown = b"id,colour\n1,red\n";
+    assert!(matches!(
+        storage.import_csv("samples", &mut unknown.as_slice()),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    let bad_integer = b"id,label\nseven,x\n";
+    assert!(storage.import_csv("samples", &mut bad_integer.as_slice()).is_err());
+    let ragged = b"id,label\n5\n";
+    assert!(matches!(
+        storage.import_csv("samples", &mut ragged.as_slice()),
+        Err(DatabaseError::InvalidData { .. })
+    ));
+    let unterminated = b"id,label\n5,\"open\n";
+    assert!(storage.import_csv("samples", &mut unterminated.as_slice()).is_err());
+
+    // Columns missing from the input are NULL, in whatever order the header lists them
+    let partial = b"label,id\n\"x,y\",9\n";
+    assert_eq!(storage.import_csv("samples", &mut partial.as_slice())?, 1);
+    let rows = storage.scan_table("samples", Some(Predicate::eq("id".to_string(), Value::Integer(9))))?;
+    assert_eq!(rows[0].values[1], Value::Text("x,y".to_string()));
+    assert_eq!(rows[0].values[6], Value::Null);
+
+    let not_objects = b"[1, 2]";
+    assert!(storage.import_json("samples", &mut not_objects.as_slice()).is_err());
+    assert_eq!(storage.scan_table("samples", None)?.len(), 5);
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::fs;
+
+use bambang::{
+    executor::predicate::Predicate,
+    planner::types::SortOrder,
+    storage::{BAMBANG_HEADER_SIZE, bplus_tree::BPlusTree, storage_manager::StorageManager},
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::create_temp_db_path_with_prefix,
+};
+
+fn reading(key: f64) -> Row {
+    Row::new(vec![Value::Real(key), Value::Text(format!("reading {:0>60}", key))])
+}
+
+fn search(storage: &StorageManager, key: f64) -> Result<Option<Row>, DatabaseError> {
+    let file = fs::OpenOptions::new().read(true).write(true).open(&storage.db_info.path)?;
+    let extras = Some(BAMBANG_HEADER_SIZE as u64);
+    let mut btree = BPlusTree::new_with_extras(file, storage.table_roots["readings"], extras)?;
+    btree.search(&Value::Real(key), extras)
+}
+
+fn leaf_of(storage: &mut StorageManager, key: f64) -> Result<u64, DatabaseError> {
+    Ok(*storage.trace_key("readings", &Value::Real(key))?.last().unwrap())
+}
+
+fn assert_infinities_in_place(storage: &mut StorageManager) -> Result<(), DatabaseError> {
+    let rows = storage.scan_table_sorted("readings", None, vec![("key".to_string(), SortOrder::Ascending)])?;
+    assert_eq!(rows.len(), 302);
+    assert_eq!(rows.first().unwrap().values[0], Value::Real(f64::NEG_INFINITY));
+    assert_eq!(rows.last().unwrap().values[0], Value::Real(f64::INFINITY));
+
+    // The infinities route to the leaves at either end of the tree
+    assert_eq!(leaf_of(storage, f64::NEG_INFINITY)?, leaf_of(storage, 0.0)?);
+    assert_eq!(leaf_of(storage, f64::INFINITY)?, leaf_of(storage, 299.0)?);
+    assert_ne!(leaf_of(storage, 0.0)?, leaf_of(storage, 299.0)?);
+
+    for key in [f64::NEG_INFINITY, f64::INFINITY, 150.0] {
+        let found = search(storage, key)?.expect("key is stored");
+        assert_eq!(found.values[0], Value::Real(key));
+    }
+
+    // Range scans put the infinities past every finite bound, integer ones included
+    let above = storage.scan_table("readings", Some(Predicate::gt("key".to_string(), Value::Integer(i64::MAX))))?;
+    assert_eq!(above.len(), 1);
+    assert_eq!(above[0].values[0], Value::Real(f64::INFINITY));
+    let below = storage.scan_table("readings", Some(Predicate::lt("key".to_string(), Value::Real(f64::MIN))))?;
+    assert_eq!(below.len(), 1);
+    assert_eq!(below[0].values[0], Value::Real(f64::NEG_INFINITY));
+    Ok(())
+}
+
+#[test]
+fn test_infinite_keys_are_ordered_and_findable_after_splits_and_reopen() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("infinite_keys");
+    {
+        let mut storage = StorageManager::new(&path)?;
+        storage.create_table("readings", "CREATE TABLE readings(key REAL PRIMARY KEY, label TEXT)")?;
+        storage.insert_into_table("readings", reading(f64::INFINITY))?;
+        for key in 0..300 {
+            storage.insert_into_table("readings", reading(key as f64))?;
+        }
+        storage.insert_into_table("readings", reading(f64::NEG_INFINITY))?;
+        assert!(storage.table_page_stats("readings")?.interior_pages > 0, "the tree split");
+        assert_infinities_in_place(&mut storage)?;
+    }
+
+    let mut storage = StorageManager::new(&path)?;
+    assert_infinities_in_place(&mut storage)?;
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_nan_is_rejected_as_key_and_unique_value() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("nan_keys");
+    {
+        let mut storage = StorageManager::new(&path)?;
+        storage.create_table("readings", "CREATE TABLE readings(key REAL PRIMARY KEY, label TEXT)")?;
+        storage.create_table("samples", "CREATE TABLE samples(id INTEGER, value REAL, code REAL UNIQUE)")?;
+        for key in 0..300 {
+            storage.insert_into_table("readings", reading(key as f64))?;
+        }
+
+        assert!(matches!(
+            storage.insert_into_table("readings", reading(f64::NAN)),
+            Err(DatabaseError::InvalidData { .. })
+        ));
+        assert!(matches!(
+            storage.insert_into_table(
+                "samples",
+                Row::new(vec![Value::Integer(1), Value::Real(1.0), Value::Real(f64::NAN)])
+            ),
+            Err(DatabaseError::InvalidData { details }) if details.contains("samples.code")
+        ));
+        // Outside keys and UNIQUE columns NaN is an ordinary value
+        storage.insert_into_table(
+            "samples",
+            Row::new(vec![Value::Integer(2), Value::Real(f64::NAN), Value::Real(2.0)]),
+        )?;
+    }
+
+    let storage = StorageManager::new(&path)?;
+    assert_eq!(storage.scan_table("readings", None)?.len(), 300);
+    let samples = storage.scan_table("samples", None)?;
+    assert_eq!(samples.len(), 1);
+    assert!(samples[0].values[1].is_nan());
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
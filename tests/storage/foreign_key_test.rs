@@ -0,0 +1,159 @@
+use std::fs;
+
+use bambang::{
+    executor::{predicate::Predicate, statement::execute_statement},
+    storage::{
+        options::{OpenMode, StorageManagerOptions},
+        schema::ForeignKey,
+        storage_manager::StorageManager,
+    },
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::create_temp_db_path_with_prefix,
+};
+
+fn order(id: i64, customer_id: Value) -> Row {
+    Row::new(vec![Value::Integer(id), customer_id, Value::Real(9.5)])
+}
+
+fn order_ids(storage: &StorageManager) -> Result<Vec<Value>, DatabaseError> {
+    let mut ids: Vec<Value> = storage
+        .scan_table_projected("orders", &["id"], None)?
+        .into_iter()
+        .map(|row| row.values[0].clone())
+        .collect();
+    ids.sort_by(|a, b| a.total_cmp(b));
+    Ok(ids)
+}
+
+#[test]
+fn test_foreign_keys_restrict_orders_to_existing_customers() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("foreign_key");
+    {
+        let mut storage = StorageManager::new(&path)?;
+        execute_statement(&mut storage, "CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT)")?;
+        execute_statement(
+            &mut storage,
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, customer_id INTEGER REFERENCES customers(id), total REAL)",
+        )?;
+        execute_statement(&mut storage, "INSERT INTO customers VALUES (1, 'Ann'), (2, 'Bo')")?;
+
+        // Orders of existing customers, and ones with no customer, go in
+        execute_statement(&mut storage, "INSERT INTO orders VALUES (10, 1, 20.0), (11, NULL, 5.0)")?;
+        match storage.insert_into_table("orders", order(12, Value::Integer(99))) {
+            Err(DatabaseError::ForeignKeyViolation { column, reference, value }) => {
+                assert_eq!((column.as_str(), reference.as_str()), ("orders.customer_id", "customers.id"));
+                assert_eq!(value, Value::Integer(99));
+            }
+            other => panic!("expected ForeignKeyViolation, got {:?}", other),
+        }
+
+        // A batch stops at its first dangling reference, keeping the rows ahead of it
+        let batch = vec![
+            order(13, Value::Integer(2)),
+            order(14, Value::Integer(98)),
+            order(15, Value::Integer(1)),
+        ];
+        assert!(matches!(
+            storage.insert_batch_into_table("orders", batch),
+            Err(DatabaseError::ForeignKeyViolation { .. })
+        ));
+        assert_eq!(order_ids(&storage)?, vec![Value::Integer(10), Value::Integer(11), Value::Integer(13)]);
+
+        let by_id = |id| Some(Predicate::eq("id".to_string(), Value::Integer(id)));
+        let update = storage.update_table("orders", by_id(10), &[("customer_id".to_string(), Value::Integer(77))]);
+        assert!(matches!(update, Err(DatabaseError::ForeignKeyViolation { .. })));
+        assert_eq!(storage.update_table("orders", by_id(10), &[("customer_id".to_string(), Value::Integer(2))])?, 1);
+
+        // Customer 2 has orders, so it can be neither deleted nor renumbered; customer 1
+        // no longer has any
+        match execute_statement(&mut storage, "DELETE FROM customers WHERE id = 2") {
+            Err(DatabaseError::ForeignKeyViolation { column, reference, value }) => {
+                assert_eq!((column.as_str(), reference.as_str()), ("orders.customer_id", "customers.id"));
+                assert_eq!(value, Value::Integer(2));
+            }
+            other => panic!("expected ForeignKeyViolation, got {:?}", other.map(|_| ())),
+        }
+        let renumber = storage.update_table("customers", by_id(2), &[("id".to_string(), Value::Integer(3))]);
+        assert!(matches!(renumber, Err(DatabaseError::ForeignKeyViolation { .. })));
+        assert_eq!(storage.scan_table("customers", None)?.len(), 2);
+        assert_eq!(storage.delete_from_table("customers", by_id(1))?, 1);
+        assert!(matches!(storage.drop_table("customers"), Err(DatabaseError::ExecutionError { .. })));
+        storage.close()?;
+    }
+
+    // The definition is persisted and still enforced after a reopen
+    for mode in [OpenMode::Eager, OpenMode::Lazy] {
+        let mut storage = StorageManager::open_with_options(&path, StorageManagerOptions::new().with_open_mode(mode))?;
+        assert_eq!(
+            storage.load_table_schema("orders")?.unwrap().foreign_keys,
+            vec![ForeignKey::new("customer_id".to_string(), "customers".to_string(), "id".to_string())]
+        );
+        assert!(matches!(
+            storage.insert_into_table("orders", order(16, Value::Integer(1))),
+            Err(DatabaseError::ForeignKeyViolation { .. })
+        ));
+        assert!(matches!(
+            storage.delete_from_table("customers", None),
+            Err(DatabaseError::ForeignKeyViolation { .. })
+        ));
+    }
+
+    // Once the orders are gone, so may the customers be
+    let mut storage = StorageManager::new(&path)?;
+    storage.drop_table("orders")?;
+    storage.drop_table("customers")?;
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_foreign_key_definitions_and_self_references() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("foreign_key_definitions");
+    let mut storage = StorageManager::new(&path)?;
+    execute_statement(&mut storage, "CREATE TABLE customers (id INTEGER PRIMARY KEY, email TEXT UNIQUE, name TEXT)")?;
+
+    // A referenced table and column must exist, and the column must identify one row
+    let rejected = [
+        "CREATE TABLE a (x INTEGER REFERENCES missing(id))",
+        "CREATE TABLE a (x INTEGER REFERENCES customers(nope))",
+        "CREATE TABLE a (x TEXT REFERENCES customers(name))",
+        "CREATE TABLE a (x INTEGER REFERENCES customers(id) ON DELETE CASCADE)",
+        "CREATE TABLE a (x INTEGER, FOREIGN KEY (y) REFERENCES customers(id))",
+    ];
+    for sql in rejected {
+        assert!(execute_statement(&mut storage, sql).is_err(), "{}", sql);
+        assert!(!storage.table_exists("a"), "{}", sql);
+    }
+
+    // A table-level FOREIGN KEY on a UNIQUE column
+    execute_statement(
+        &mut storage,
+        "CREATE TABLE invoices (id INTEGER PRIMARY KEY, email TEXT, FOREIGN KEY (email) REFERENCES customers(email))",
+    )?;
+    execute_statement(&mut storage, "INSERT INTO customers VALUES (1, 'ann@example.com', 'Ann')")?;
+    execute_statement(&mut storage, "INSERT INTO invoices VALUES (1, 'ann@example.com')")?;
+    assert!(matches!(
+        execute_statement(&mut storage, "INSERT INTO invoices VALUES (2, 'bo@example.com')"),
+        Err(DatabaseError::ForeignKeyViolation { .. })
+    ));
+
+    // Rows of a self-referencing table may reference themselves or rows earlier in the
+    // same batch, and rows referencing only each other can be deleted together
+    execute_statement(
+        &mut storage,
+        "CREATE TABLE employees (id INTEGER PRIMARY KEY, manager_id INTEGER REFERENCES employees(id))",
+    )?;
+    execute_statement(&mut storage, "INSERT INTO employees VALUES (1, 1), (2, 1), (3, 2)")?;
+    assert!(matches!(
+        execute_statement(&mut storage, "DELETE FROM employees WHERE id = 2"),
+        Err(DatabaseError::ForeignKeyViolation { .. })
+    ));
+    execute_statement(&mut storage, "DELETE FROM employees WHERE id >= 2")?;
+    execute_statement(&mut storage, "DELETE FROM employees")?;
+    assert!(storage.is_table_empty("employees")?);
+
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use std::fs;
+
+use bambang::{
+    executor::predicate::Predicate,
+    storage::storage_manager::StorageManager,
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::create_temp_db_path_with_prefix,
+};
+
+const ROW_COUNT: i64 = 2000;
+/// Batch inserts pack pages densely, so the lookup test needs a larger table to compare
+/// an index lookup against
+const LOOKUP_ROW_COUNT: i64 = 8000;
+
+fn user(id: i64, email: Option<String>) -> Row {
+    Row::new(vec![
+        Value::Integer(id),
+        Value::Text(format!("user {id}")),
+        email.map(Value::Text).unwrap_or(Value::Null),
+    ])
+}
+
+/// Emails repeat every `row_count / 4` rows so each one is shared by four users
+fn create_users(storage_manager: &mut StorageManager, ids: std::ops::Range<i64>, row_count: i64) -> Result<(), DatabaseError> {
+    if !storage_manager.table_exists("users") {
+        storage_manager.create_table("users", "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT)")?;
+    }
+    let rows = ids.map(|id| user(id, Some(format!("user{}@example.com", id % (row_count / 4))))).collect();
+    storage_manager.insert_batch_into_table("users", rows)
+}
+
+fn ids(rows: &[Row]) -> Vec<i64> {
+    let mut ids: Vec<i64> = rows
+        .iter()
+        .map(|row| match row.values[0] {
+            Value::Integer(id) => id,
+            ref other => panic!("unexpected key {:?}", other),
+        })
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn test_lookup_by_index_avoids_a_full_scan() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("index_lookup");
+    {
+        let mut storage_manager = StorageManager::new(&path)?;
+        // Half the rows exist before the index is built, the rest are added through it
+        create_users(&mut storage_manager, 0..LOOKUP_ROW_COUNT / 2, LOOKUP_ROW_COUNT)?;
+        assert!(!storage_manager.is_column_indexed("users", "email"));
+        storage_manager.create_index("users", "email")?;
+        assert!(storage_manager.is_column_indexed("users", "email"));
+        create_users(&mut storage_manager, LOOKUP_ROW_COUNT / 2..LOOKUP_ROW_COUNT, LOOKUP_ROW_COUNT)?;
+        storage_manager.insert_into_table("users", user(LOOKUP_ROW_COUNT, None))?;
+    }
+
+    let storage_manager = StorageManager::new(&path)?;
+    let table_pages = storage_manager.table_page_stats("users")?.page_count as u64;
+    let target = Value::Text("user123@example.com".to_string());
+
+    let reads_before = storage_manager.io_stats().pages_read;
+    let found = storage_manager.lookup_by_index("users", "email", &target)?;
+    let lookup_reads = storage_manager.io_stats().pages_read - reads_before;
+    assert_eq!(ids(&found), vec![123, 2123, 4123, 6123]);
+    assert!(
+        lookup_reads * 4 < table_pages,
+        "lookup read {} pages of a {}-page table",
+        lookup_reads,
+        table_pages
+    );
+
+    let scanned = storage_manager.scan_table("users", Some(Predicate::eq("email".to_string(), target)))?;
+    assert_eq!(ids(&scanned), ids(&found));
+    assert!(storage_manager
+        .lookup_by_index("users", "email", &Value::Text("nobody@example.com".to_string()))?
+        .is_empty());
+    assert!(storage_manager.lookup_by_index("users", "email", &Value::Null)?.is_empty());
+
+    drop(storage_manager);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_index_follows_updates_deletes_and_drops() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("index_maintenance");
+    let mut storage_manager = StorageManager::new(&path)?;
+    create_users(&mut storage_manager, 0..ROW_COUNT, ROW_COUNT)?;
+    storage_manager.create_index("users", "email")?;
+
+    assert!(matches!(
+        storage_manager.create_index("users", "email"),
+        Err(DatabaseError::IndexAlreadyExists { .. })
+    ));
+    assert!(matches!(
+        storage_manager.create_index("users", "phone"),
+        Err(DatabaseError::ColumnNotFound { .. })
+    ));
+    assert!(matches!(
+        storage_manager.lookup_by_index("users", "name", &Value::Text("user 1".to_string())),
+        Err(DatabaseError::IndexNotFound { .. })
+    ));
+
+    let moved = Value::Text("moved@example.com".to_string());
+    let updated = storage_manager.update_table(
+        "users",
+        Some(Predicate::eq("email".to_string(), Value::Text("user7@example.com".to_string()))),
+        &[("email".to_string(), moved.clone())],
+    )?;
+    assert_eq!(updated, 4);
+    assert!(storage_manager
+        .lookup_by_index("users", "email", &Value::Text("user7@example.com".to_string()))?
+        .is_empty());
+    assert_eq!(ids(&storage_manager.lookup_by_index("users", "email", &moved)?), vec![7, 507, 1007, 1507]);
+
+    let deleted = storage_manager.delete_from_table("users", Some(Predicate::lt("id".to_string(), Value::Integer(1000))))?;
+    assert_eq!(deleted, 1000);
+    assert_eq!(ids(&storage_manager.lookup_by_index("users", "email", &moved)?), vec![1007, 1507]);
+    assert_eq!(
+        ids(&storage_manager.lookup_by_index("users", "email", &Value::Text("user42@example.com".to_string()))?),
+        vec![1042, 1542]
+    );
+
+    let free_before = storage_manager.free_page_ids()?.len();
+    let table_pages = storage_manager.table_page_stats("users")?.page_count;
+    storage_manager.drop_table("users")?;
+    assert!(storage_manager.free_page_ids()?.len() > free_before + table_pages);
+    assert!(!storage_manager.is_column_indexed("users", "email"));
+
+    // The index goes with the table, so a new table of the same name starts without one
+    drop(storage_manager);
+    let mut storage_manager = StorageManager::new(&path)?;
+    create_users(&mut storage_manager, 0..10, ROW_COUNT)?;
+    assert!(matches!(
+        storage_manager.lookup_by_index("users", "email", &moved),
+        Err(DatabaseError::IndexNotFound { .. })
+    ));
+    storage_manager.create_index("users", "email")?;
+    assert_eq!(
+        ids(&storage_manager.lookup_by_index("users", "email", &Value::Text("user3@example.com".to_string()))?),
+        vec![3]
+    );
+
+    drop(storage_manager);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
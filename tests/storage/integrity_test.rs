@@ -0,0 +1,145 @@
+use std::{
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use bambang::{
+    storage::{page_offset, schema::TableKind, storage_manager::StorageManager},
+    types::{
+        PageId,
+        error::DatabaseError,
+        page::{Page, PageType},
+        row::Row,
+        value::Value,
+    },
+    utils::mock::TempDatabase,
+};
+
+fn create_notes(storage: &mut StorageManager, count: i64) -> Result<(), DatabaseError> {
+    storage.create_table("notes", "CREATE TABLE notes(id INTEGER PRIMARY KEY, body TEXT)")?;
+    let rows = (0..count)
+        .map(|id| Row::new(vec![Value::Integer(id), Value::Text(format!("{:0>200}", id))]))
+        .collect();
+    storage.insert_batch_into_table("notes", rows)
+}
+
+/// Leaf pages of `table`, decoded as they are on disk
+fn leaf_pages(storage: &StorageManager, table: &str) -> Result<Vec<Page>, DatabaseError> {
+    let mut leaves = Vec::new();
+    for page in storage.iter_table_pages(table)? {
+        let (_, bytes) = page?;
+        let page = Page::from_bytes(&bytes)?;
+        if page.page_type == PageType::LeafTable {
+            leaves.push(page);
+        }
+    }
+    Ok(leaves)
+}
+
+fn overwrite(path: &Path, offset: u64, bytes: &[u8]) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(bytes)
+}
+
+#[test]
+fn test_healthy_database_has_no_findings() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("integrity_healthy");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_notes(storage, 300)?;
+    storage.create_index("notes", "body")?;
+    storage.create_table_with_kind(
+        "events",
+        "CREATE TABLE events(at TIMESTAMP, body TEXT)",
+        TableKind::AppendLog,
+    )?;
+    let events = (0..100)
+        .map(|second| Row::new(vec![Value::Timestamp(second * 1000), Value::Text(format!("{:0>200}", second))]))
+        .collect();
+    storage.insert_batch_into_table("events", events)?;
+    storage.create_table("blobs", "CREATE TABLE blobs(id INTEGER PRIMARY KEY, data BLOB)")?;
+    for id in 0..5 {
+        storage.insert_into_table("blobs", Row::new(vec![Value::Integer(id), Value::Blob(vec![id as u8; 3000])]))?;
+    }
+
+    let report = storage.check_integrity()?;
+    assert!(report.is_ok(), "{:?}", report.findings);
+    // sqlite_schema, three tables and one index
+    assert_eq!(report.trees_checked, 5);
+    assert!(report.pages_checked as u64 <= storage.db_info.page_count);
+    assert!(report.pages_checked > 10);
+    Ok(())
+}
+
+#[test]
+fn test_damaged_pages_are_each_reported() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("integrity_damaged");
+    let path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_notes(storage, 300)?;
+    let leaves: Vec<PageId> = leaf_pages(storage, "notes")?.iter().map(|page| page.page_id).collect();
+    assert!(leaves.len() > 3);
+
+    let (first, second) = (leaves[1], leaves[leaves.len() - 2]);
+    for page_id in [first, second] {
+        overwrite(&path, page_offset(page_id) + 2000, &[0xAB; 64])?;
+    }
+
+    let report = storage.check_integrity()?;
+    assert!(!report.is_ok());
+    for page_id in [first, second] {
+        let findings: Vec<_> = report.findings_for(page_id).collect();
+        assert_eq!(findings.len(), 1, "{:?}", report.findings);
+        assert_eq!(findings[0].tree.as_deref(), Some("notes"));
+        assert!(findings[0].details.contains("Checksum"), "{}", findings[0]);
+    }
+    // The leaf chain cannot be followed past the first damaged leaf
+    assert!(report.findings.iter().any(|finding| finding.page_id.is_none() && finding.details.contains("Leaf chain")));
+    Ok(())
+}
+
+#[test]
+fn test_leaf_chain_that_skips_a_leaf_is_reported() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("integrity_chain");
+    let path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_notes(storage, 300)?;
+    let mut leaves = leaf_pages(storage, "notes")?;
+    assert!(leaves.len() > 3);
+
+    // Link the first leaf past the second; the page itself is still well formed
+    let mut first = leaves.remove(0);
+    first.next_leaf_page_id = Some(leaves[1].page_id);
+    first.update_checksum();
+    overwrite(&path, page_offset(first.page_id), &first.to_bytes()?)?;
+
+    let report = storage.check_integrity()?;
+    assert!(!report.is_ok());
+    assert!(report.findings.iter().all(|finding| finding.tree.as_deref() == Some("notes")), "{:?}", report.findings);
+    assert!(report.findings_for(leaves[1].page_id).any(|finding| finding.details.contains("links back")));
+    assert!(report.findings.iter().any(|finding| finding.details.contains("Leaf chain")));
+    Ok(())
+}
+
+#[test]
+fn test_file_size_disagreeing_with_header_is_reported() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("integrity_header");
+    let path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_notes(storage, 10)?;
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+    file.write_all(&[0u8; 100])?;
+    drop(file);
+
+    let report = storage.check_integrity()?;
+    assert_eq!(report.findings.len(), 1, "{:?}", report.findings);
+    assert_eq!(report.findings[0].page_id, None);
+    assert!(report.findings[0].details.contains("but the file is"), "{}", report.findings[0]);
+
+    // A bad magic number is reported and the pages are still walked
+    overwrite(&path, 0, b"NOT A BAMBANG DB")?;
+    let report = storage.check_integrity()?;
+    assert!(report.findings.iter().any(|finding| finding.details.contains("magic")));
+    assert_eq!(report.trees_checked, 2);
+    Ok(())
+}
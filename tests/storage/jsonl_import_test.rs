@@ -0,0 +1,197 @@
+use std::fs;
+
+use bambang::{
+    storage::{
+        export::JsonLayout,
+        import::{JsonlImportOptions, NestedJson},
+        storage_manager::StorageManager,
+    },
+    types::{
+        error::DatabaseError,
+        row::Row,
+        value::{DataType, Value},
+    },
+    utils::mock::TempDatabase,
+};
+
+fn creating() -> JsonlImportOptions {
+    JsonlImportOptions::new().with_create_table(true)
+}
+
+fn values(rows: Vec<Row>) -> Vec<Vec<Value>> {
+    rows.into_iter().map(|row| row.values).collect()
+}
+
+/// Name, type and nullability of each column of the table
+fn columns(storage: &StorageManager, table: &str) -> Vec<(String, DataType, bool)> {
+    storage
+        .get_table_schema(table)
+        .unwrap()
+        .columns
+        .iter()
+        .map(|column| (column.name.clone(), column.data_type.clone(), column.nullable))
+        .collect()
+}
+
+#[test]
+fn test_inferred_schema_widens_types_and_makes_missing_keys_nullable() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("jsonl_infer");
+    let storage = temp_db.create_storage_manager().unwrap();
+    let input = concat!(
+        r#"{"id": 1, "score": 3, "code": 7, "done": true, "note": "first"}"#, "\n",
+        "\n",
+        r#"{"id": 2, "score": 2.5, "code": "x7", "done": false, "note": null}"#, "\n",
+        r#"{"id": 3, "score": 4, "code": 9, "done": true}"#, "\n",
+        // Past the sample: the schema is fixed by now
+        r#"{"id": 4, "score": 1, "code": 10, "done": false, "note": "last"}"#, "\n",
+    );
+
+    let summary = storage.import_jsonl("events", input.as_bytes(), &creating().with_sample_size(3))?;
+    assert!(summary.created_table);
+    assert_eq!(summary.imported, 4);
+    assert!(summary.rejected.is_empty());
+    let text = |s: &str| Value::Text(s.to_string());
+    assert_eq!(
+        columns(storage, "events"),
+        vec![
+            ("id".to_string(), DataType::Integer, false),
+            ("score".to_string(), DataType::Real, false),
+            ("code".to_string(), DataType::Text, false),
+            ("done".to_string(), DataType::Boolean, false),
+            ("note".to_string(), DataType::Text, true),
+        ]
+    );
+    assert_eq!(
+        values(storage.scan_table("events", None)?),
+        vec![
+            vec![Value::Integer(1), Value::Real(3.0), text("7"), Value::Boolean(true), text("first")],
+            vec![Value::Integer(2), Value::Real(2.5), text("x7"), Value::Boolean(false), Value::Null],
+            vec![Value::Integer(3), Value::Real(4.0), text("9"), Value::Boolean(true), Value::Null],
+            vec![Value::Integer(4), Value::Real(1.0), text("10"), Value::Boolean(false), text("last")],
+        ]
+    );
+
+    // A later import maps keys by name into the existing table; a key missing from a
+    // NOT NULL column refuses the line, reported with its number
+    let more = "{\"note\": \"reordered\", \"done\": true, \"code\": 1, \"score\": 1, \"id\": 5}\n{\"id\": 6}\n";
+    let result = storage.import_jsonl("events", more.as_bytes(), &creating());
+    assert!(matches!(result, Err(DatabaseError::ImportLine { line: 2, .. })), "{:?}", result);
+    assert_eq!(storage.scan_table("events", None)?.len(), 5);
+    Ok(())
+}
+
+#[test]
+fn test_nested_values_are_refused_or_kept_as_json_text() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("jsonl_nested");
+    let storage = temp_db.create_storage_manager().unwrap();
+    let input = "{\"id\": 1, \"tags\": \"none\"}\n{\"id\": 2, \"tags\": [\"a\", {\"b\": 1}]}\n";
+
+    let result = storage.import_jsonl("docs", input.as_bytes(), &creating());
+    match result {
+        Err(DatabaseError::ImportLine { line: 2, source }) => assert!(source.to_string().contains("tags"), "{}", source),
+        other => panic!("expected line 2 to be refused, got {:?}", other),
+    }
+    assert!(!storage.table_exists("docs"));
+    assert!(matches!(
+        storage.import_jsonl("docs", input.as_bytes(), &JsonlImportOptions::new()),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+
+    let summary = storage.import_jsonl("docs", input.as_bytes(), &creating().with_nested(NestedJson::AsText))?;
+    assert_eq!(summary.imported, 2);
+    assert_eq!(storage.get_table_schema("docs").unwrap().columns[1].data_type, DataType::Text);
+    assert_eq!(
+        storage.scan_table("docs", None)?[1].values[1],
+        Value::Text(r#"["a",{"b":1}]"#.to_string())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_lenient_import_of_a_long_stream_skips_malformed_lines() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("jsonl_lenient");
+    let rejects_path = temp_db.path.with_extension("rejects.jsonl");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("readings", "CREATE TABLE readings (id INTEGER PRIMARY KEY, sensor TEXT NOT NULL, value REAL)")?;
+
+    let mut input = String::new();
+    for id in 1..=50_000 {
+        let line = match id {
+            1_234 => "{\"id\": 1234, \"sensor\": \"s1\", \"value\": ".to_string(),
+            20_000 => "[20000, \"s0\", 1.5]".to_string(),
+            // Takes the key of an earlier line
+            45_678 => "{\"id\": 7, \"sensor\": \"dup\", \"value\": 0}".to_string(),
+            _ => format!("{{\"id\": {}, \"sensor\": \"s{}\", \"value\": {}}}", id, id % 10, id as f64 / 4.0),
+        };
+        input.push_str(&line);
+        input.push('\n');
+    }
+    let options = JsonlImportOptions::new().with_lenient(true).with_rejects_path(&rejects_path);
+    let summary = storage.import_jsonl("readings", input.as_bytes(), &options)?;
+
+    assert_eq!(summary.imported, 49_997);
+    assert_eq!(summary.rejected.iter().map(|reject| reject.line).collect::<Vec<_>>(), vec![1_234, 20_000, 45_678]);
+    assert!(summary.rejected[2].reason.contains("UNIQUE") || summary.rejected[2].reason.contains("PRIMARY KEY"), "{:?}", summary.rejected[2]);
+    let rejects = fs::read_to_string(&rejects_path)?;
+    assert_eq!(rejects.lines().collect::<Vec<_>>(), vec![
+        "{\"id\": 1234, \"sensor\": \"s1\", \"value\": ",
+        "[20000, \"s0\", 1.5]",
+        "{\"id\": 7, \"sensor\": \"dup\", \"value\": 0}",
+    ]);
+
+    let rows = storage.scan_table("readings", None)?;
+    assert_eq!(rows.len(), 49_997);
+    assert_eq!(rows[6].values, vec![Value::Integer(7), Value::Text("s7".to_string()), Value::Real(1.75)]);
+    assert_eq!(rows.last().unwrap().values[0], Value::Integer(50_000));
+    let _ = fs::remove_file(&rejects_path);
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_import_round_trips_the_json_export() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("jsonl_round_trip");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table(
+        "original",
+        "CREATE TABLE original (id INTEGER, label TEXT, score REAL, done BOOLEAN, payload BLOB, at TIMESTAMP)",
+    )?;
+    let rows = [
+        vec![Value::Integer(1), Value::Text("plain".to_string()), Value::Real(0.1), Value::Boolean(true), Value::Blob(vec![0xde, 0xad]), Value::Timestamp(1_700_000_000)],
+        vec![Value::Integer(-5), Value::Text("quote \" and\nbreak — ü".to_string()), Value::Real(-2.0), Value::Boolean(false), Value::Blob(Vec::new()), Value::Timestamp(0)],
+        vec![Value::Integer(i64::MAX), Value::Null, Value::Null, Value::Null, Value::Null, Value::Null],
+    ];
+    for values in rows {
+        storage.insert_into_table("original", Row::new(values))?;
+    }
+    let export = |storage: &StorageManager, table: &str| -> Result<String, DatabaseError> {
+        let mut out = Vec::new();
+        storage.export_json(table, None, &mut out, JsonLayout::Lines)?;
+        Ok(String::from_utf8(out).unwrap())
+    };
+    let exported = export(storage, "original")?;
+
+    // Into a table of the same schema every type survives
+    storage.create_table(
+        "copy",
+        "CREATE TABLE copy (id INTEGER, label TEXT, score REAL, done BOOLEAN, payload BLOB, at TIMESTAMP)",
+    )?;
+    assert_eq!(storage.import_jsonl("copy", exported.as_bytes(), &JsonlImportOptions::new())?.imported, 3);
+    assert_eq!(export(storage, "copy")?, exported);
+    assert_eq!(values(storage.scan_table("copy", None)?), values(storage.scan_table("original", None)?));
+
+    // An inferred table reads blobs and timestamps as their text, so its export matches too
+    assert!(storage.import_jsonl("inferred", exported.as_bytes(), &creating())?.created_table);
+    assert_eq!(export(storage, "inferred")?, exported);
+    assert_eq!(
+        columns(storage, "inferred").into_iter().map(|(_, data_type, nullable)| (data_type, nullable)).collect::<Vec<_>>(),
+        vec![
+            (DataType::Integer, false),
+            (DataType::Text, true),
+            (DataType::Real, true),
+            (DataType::Boolean, true),
+            (DataType::Text, true),
+            (DataType::Text, true),
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,295 @@
+use std::fs;
+
+use bambang::{
+    executor::predicate::Predicate,
+    storage::{
+        SCHEMA_ROOT_PAGE_ID,
+        options::{OpenMode, StorageManagerOptions},
+        page_offset,
+        schema::{ColumnSchema, TableSchema},
+        storage_manager::StorageManager,
+    },
+    types::{
+        PAGE_SIZE,
+        error::DatabaseError,
+        page::{Page, PageType},
+        row::Row,
+        value::{DataType, Value},
+    },
+    utils::mock::create_temp_db_path_with_prefix,
+};
+
+const TABLE_COUNT: usize = 500;
+
+fn open(path: &std::path::Path, mode: OpenMode) -> Result<StorageManager, DatabaseError> {
+    StorageManager::open_with_options(path, StorageManagerOptions::default().with_open_mode(mode))
+}
+
+fn create_many_tables(prefix: &str) -> std::path::PathBuf {
+    let path = create_temp_db_path_with_prefix(prefix);
+    let mut storage_manager = StorageManager::new(&path).unwrap();
+    for i in 0..TABLE_COUNT {
+        let sql = format!("CREATE TABLE t{i} (id INTEGER PRIMARY KEY, name TEXT NOT NULL)");
+        storage_manager.create_table(&format!("t{i}"), &sql).unwrap();
+    }
+    path
+}
+
+#[test]
+fn test_lazy_open_reads_only_the_schema_leaves() {
+    let path = create_many_tables("lazy_open_reads");
+
+    let eager = open(&path, OpenMode::Eager).unwrap();
+    let eager_reads = eager.io_stats().pages_read;
+    assert_eq!(eager.get_table_names().len(), TABLE_COUNT + 1);
+    assert_eq!(eager.schema_manager.parsed_schema_count(), TABLE_COUNT + 1);
+    drop(eager);
+
+    let lazy = open(&path, OpenMode::Lazy).unwrap();
+    let lazy_reads = lazy.io_stats().pages_read;
+    assert!(lazy_reads <= eager_reads);
+    assert!(lazy_reads < (TABLE_COUNT / 10) as u64, "lazy open read {} pages", lazy_reads);
+    assert_eq!(lazy.get_table_names().len(), TABLE_COUNT + 1);
+    assert!(lazy.table_exists("t499"));
+    assert_eq!(lazy.schema_manager.parsed_schema_count(), 0);
+
+    // Touching one table parses only its schema, from the page its entry lives on
+    let schema = lazy.get_table_schema("t250").unwrap();
+    assert_eq!(schema.column_names(), vec!["id", "name"]);
+    assert_eq!(lazy.io_stats().pages_read, lazy_reads + 1);
+    assert_eq!(lazy.schema_manager.parsed_schema_count(), 1);
+    lazy.get_table_schema("t250").unwrap();
+    assert_eq!(lazy.io_stats().pages_read, lazy_reads + 1);
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_lazy_schemas_behave_like_eager_ones() {
+    let path = create_many_tables("lazy_open_behaviour");
+    {
+        let mut storage_manager = StorageManager::new(&path).unwrap();
+        let root_page_id = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+        let schema = TableSchema::new(
+            "accounts".to_string(),
+            vec![
+                ColumnSchema::new("id".to_string(), DataType::Integer, 0).primary_key(),
+                ColumnSchema::new("owner".to_string(), DataType::Text, 1).not_null(),
+                ColumnSchema::new("balance".to_string(), DataType::Integer, 2).with_default(Value::Integer(0)),
+            ],
+            root_page_id,
+            "CREATE TABLE accounts(id INTEGER PRIMARY KEY, owner TEXT NOT NULL, balance INTEGER DEFAULT 0)"
+                .to_string(),
+        );
+        storage_manager.add_table_schema(schema).unwrap();
+        for (id, owner, balance) in [(1, "ana", 10), (2, "budi", 250), (3, "citra", 40)] {
+            let row = Row::new(vec![
+                Value::Integer(id),
+                Value::Text(owner.to_string()),
+                Value::Integer(balance),
+            ]);
+            storage_manager.insert_into_table("accounts", row).unwrap();
+        }
+    }
+
+    let eager = open(&path, OpenMode::Eager).unwrap();
+    let eager_schema = eager.get_table_schema("accounts").unwrap().clone();
+    let eager_t7 = eager.get_table_schema("t7").unwrap().clone();
+    drop(eager);
+
+    let mut lazy = open(&path, OpenMode::Lazy).unwrap();
+    assert_eq!(lazy.get_table_schema("accounts"), Some(&eager_schema));
+    assert_eq!(lazy.get_table_schema("t7"), Some(&eager_t7));
+
+    let null_owner = Row::new(vec![Value::Integer(4), Value::Null, Value::Integer(1)]);
+    assert!(matches!(lazy.validate_row("accounts", &null_owner), Err(DatabaseError::InvalidData { .. })));
+    let mut defaulted = Row::new(vec![Value::Integer(4), Value::Text("dewi".to_string()), Value::Null]);
+    lazy.apply_defaults("accounts", &mut defaulted).unwrap();
+    assert_eq!(defaulted.values[2], Value::Integer(0));
+
+    let rich = lazy
+        .scan_table("accounts", Some(Predicate::gt("balance".to_string(), Value::Integer(30))))
+        .unwrap();
+    let owners: Vec<_> = rich.iter().map(|row| row.values[1].clone()).collect();
+    assert_eq!(owners, vec![Value::Text("budi".to_string()), Value::Text("citra".to_string())]);
+
+    // Tables that were never touched still take inserts and scans
+    lazy.insert_into_table("t499", Row::new(vec![Value::Integer(1), Value::Text("x".to_string())]))
+        .unwrap();
+    assert_eq!(lazy.scan_table("t499", None).unwrap().len(), 1);
+    let missing_name = Row::new(vec![Value::Integer(2), Value::Null]);
+    assert!(lazy.validate_row("t498", &missing_name).is_err());
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_lazy_open_defers_schema_errors_to_first_use() {
+    let path = create_temp_db_path_with_prefix("lazy_open_errors");
+    {
+        let mut storage_manager = StorageManager::new(&path).unwrap();
+        storage_manager
+            .create_table("healthy", "CREATE TABLE healthy (id INTEGER, name TEXT)")
+            .unwrap();
+        let root_page_id = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+        let schema = TableSchema::new(
+            "broken".to_string(),
+            vec![
+                ColumnSchema::new("id".to_string(), DataType::Integer, 0),
+                ColumnSchema::new("name".to_string(), DataType::Text, 2),
+            ],
+            root_page_id,
+            "CREATE TABLE broken(id INTEGER, name TEXT)".to_string(),
+        );
+        storage_manager.add_table_schema(schema).unwrap();
+    }
+
+    // An eager open leaves the broken table without a schema and says why
+    let eager = open(&path, OpenMode::Eager).unwrap();
+    assert!(eager.get_table_schema("healthy").is_some());
+    assert!(eager.get_table_schema("broken").is_none());
+    match eager.schema_warnings() {
+        [warning] => {
+            assert_eq!(warning.table, "broken");
+            assert!(warning.details.contains("position"), "{}", warning);
+        }
+        other => panic!("expected one warning, got {:?}", other),
+    }
+    drop(eager);
+
+    let mut lazy = open(&path, OpenMode::Lazy).unwrap();
+    lazy.insert_into_table("healthy", Row::new(vec![Value::Integer(1), Value::Text("a".to_string())]))
+        .unwrap();
+    assert_eq!(lazy.scan_table("healthy", None).unwrap().len(), 1);
+
+    assert!(lazy.table_exists("broken"));
+    assert!(lazy.get_table_schema("broken").is_none());
+    match lazy.scan_table("broken", None) {
+        Err(DatabaseError::InvalidTableSchema { table, reason }) => {
+            assert_eq!(table, "broken");
+            assert!(reason.contains("position"), "{}", reason);
+        }
+        other => panic!("expected InvalidTableSchema, got {:?}", other),
+    }
+    let row = Row::new(vec![Value::Integer(1), Value::Text("b".to_string())]);
+    assert!(matches!(
+        lazy.insert_into_table("broken", row),
+        Err(DatabaseError::InvalidTableSchema { .. })
+    ));
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_unreadable_create_table_statement_is_an_error() {
+    let path = create_temp_db_path_with_prefix("unreadable_create_table");
+    {
+        let mut storage_manager = StorageManager::new(&path).unwrap();
+        storage_manager
+            .create_table("healthy", "CREATE TABLE healthy (id INTEGER, name TEXT)")
+            .unwrap();
+        storage_manager
+            .create_table("garbled", "CREATE TABLE garbled (id INTEGER, label TEXT)")
+            .unwrap();
+        // A statement that cannot be read is refused before anything is stored
+        assert!(matches!(
+            storage_manager.create_table("refused", "CREATE TABLE refused (id INTEGER"),
+            Err(DatabaseError::InvalidTableSchema { .. })
+        ));
+        assert!(!storage_manager.table_exists("refused"));
+    }
+    rewrite_stored_type(&path, "garbled (id", "garbled )id");
+
+    // An eager open leaves the table without a schema and says why
+    let eager = open(&path, OpenMode::Eager).unwrap();
+    assert!(eager.get_table_schema("healthy").is_some());
+    assert!(eager.get_table_schema("garbled").is_none());
+    match eager.schema_warnings() {
+        [warning] => {
+            assert_eq!(warning.table, "garbled");
+            assert!(warning.details.contains("CREATE TABLE"), "{}", warning);
+        }
+        other => panic!("expected one warning, got {:?}", other),
+    }
+    drop(eager);
+
+    let lazy = open(&path, OpenMode::Lazy).unwrap();
+    assert_eq!(lazy.scan_table("healthy", None).unwrap().len(), 0);
+    match lazy.scan_table("garbled", None) {
+        Err(DatabaseError::InvalidTableSchema { table, reason }) => {
+            assert_eq!(table, "garbled");
+            assert!(reason.contains("CREATE TABLE"), "{}", reason);
+        }
+        other => panic!("expected InvalidTableSchema, got {:?}", other),
+    }
+    drop(lazy);
+    let _ = fs::remove_file(&path);
+}
+
+/// Rewrite every `from` stored in the schema root page to `to`, which must be as long,
+/// the way a newer version could have stored a type this one does not know
+fn rewrite_stored_type(path: &std::path::Path, from: &str, to: &str) {
+    let mut bytes = fs::read(path).unwrap();
+    let offset = page_offset(SCHEMA_ROOT_PAGE_ID) as usize;
+    let mut page = Page::from_bytes(&bytes[offset..offset + PAGE_SIZE]).unwrap();
+    let data = page.data.as_mut().unwrap();
+    let positions: Vec<usize> = (0..data.len() - from.len())
+        .filter(|&i| &data[i..i + from.len()] == from.as_bytes())
+        .collect();
+    assert!(!positions.is_empty());
+    for position in positions {
+        data[position..position + to.len()].copy_from_slice(to.as_bytes());
+    }
+    page.update_checksum();
+    bytes[offset..offset + PAGE_SIZE].copy_from_slice(&page.to_bytes().unwrap());
+    fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn test_unknown_column_types_are_read_as_blob() {
+    let path = create_temp_db_path_with_prefix("unknown_column_type");
+    {
+        let mut storage_manager = StorageManager::new(&path).unwrap();
+        storage_manager
+            .create_table("healthy", "CREATE TABLE healthy (id INTEGER, name TEXT)")
+            .unwrap();
+        storage_manager
+            .insert_into_table("healthy", Row::new(vec![Value::Integer(1), Value::Text("a".to_string())]))
+            .unwrap();
+        let root_page_id = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+        let schema = TableSchema::new(
+            "future".to_string(),
+            vec![
+                ColumnSchema::new("id".to_string(), DataType::Integer, 0),
+                ColumnSchema::new("reading".to_string(), DataType::Decimal, 1),
+            ],
+            root_page_id,
+            "CREATE TABLE future(id INTEGER, reading DECIMAL)".to_string(),
+        );
+        storage_manager.add_table_schema(schema).unwrap();
+        storage_manager
+            .insert_into_table("future", Row::new(vec![Value::Integer(7), Value::Null]))
+            .unwrap();
+    }
+    rewrite_stored_type(&path, "DECIMAL", "VECTOR7");
+
+    for mode in [OpenMode::Eager, OpenMode::Lazy] {
+        let storage_manager = open(&path, mode).unwrap();
+        assert_eq!(storage_manager.scan_table("healthy", None).unwrap().len(), 1);
+        let schema = storage_manager.get_table_schema("future").unwrap();
+        assert_eq!(schema.columns[1].name, "reading");
+        assert_eq!(schema.columns[1].data_type, DataType::Blob);
+        assert_eq!(storage_manager.scan_table("future", None).unwrap()[0].values[0], Value::Integer(7));
+    }
+
+    let storage_manager = open(&path, OpenMode::Eager).unwrap();
+    match storage_manager.schema_warnings() {
+        [warning] => {
+            assert_eq!(warning.table, "future");
+            assert!(warning.details.contains("VECTOR7"), "{}", warning);
+        }
+        other => panic!("expected one warning, got {:?}", other),
+    }
+    drop(storage_manager);
+    let _ = fs::remove_file(&path);
+}
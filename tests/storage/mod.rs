@@ -1,2 +1,20 @@
+pub mod append_log_test;
 pub mod bplus_tree_test;
-pub mod storage_manager_test;
\ No newline at end of file
+pub mod column_usage_test;
+pub mod composite_key_test;
+pub mod export_import_test;
+pub mod float_key_test;
+pub mod foreign_key_test;
+pub mod index_test;
+pub mod integrity_test;
+pub mod jsonl_import_test;
+pub mod lazy_open_test;
+pub mod page_image_test;
+pub mod schema_tree_test;
+pub mod schema_watch_test;
+pub mod sequence_test;
+pub mod storage_manager_test;
+pub mod transaction_test;
+pub mod vacuum_test;
+pub mod wide_table_test;
+pub mod workload_test;
@@ -0,0 +1,205 @@
+use std::{cell::Cell, collections::HashMap};
+
+use bambang::{
+    executor::predicate::Predicate,
+    storage::{
+        page_image::{self, PAGE_IMAGE_FORMAT_VERSION, PageImageManifest},
+        storage_manager::StorageManager,
+    },
+    types::{PAGE_SIZE, error::DatabaseError, row::Row, value::Value},
+    utils::{
+        mock::TempDatabase,
+        progress::{ChannelProgress, ProgressEvent, ProgressSink, TerminalProgress, report_progress},
+    },
+};
+
+fn populate_orders(storage: &mut StorageManager, count: i64) -> Result<(), DatabaseError> {
+    storage.create_table(
+        "orders",
+        "CREATE TABLE orders (id INTEGER PRIMARY KEY, customer TEXT, total REAL)",
+    )?;
+    for i in 0..count {
+        storage.insert_into_table(
+            "orders",
+            Row::new(vec![
+                Value::Integer(i),
+                Value::Text(format!("customer_{:04}_{}", i % 97, "x".repeat(40))),
+                Value::Real(i as f64 * 1.25),
+            ]),
+        )?;
+    }
+    Ok(())
+}
+
+fn rows_by_id(storage: &StorageManager) -> Result<HashMap<i64, Vec<Value>>, DatabaseError> {
+    Ok(storage
+        .scan_table("orders", None)?
+        .into_iter()
+        .map(|row| match row.values[0] {
+            Value::Integer(id) => (id, row.values),
+            _ => panic!("unexpected id"),
+        })
+        .collect())
+}
+
+#[test]
+fn test_physical_copy_of_multi_level_table() -> Result<(), DatabaseError> {
+    let mut source_db = TempDatabase::with_prefix("page_image_source");
+    let source = source_db.create_storage_manager().unwrap();
+    populate_orders(source, 1500)?;
+    assert!(source.trace_key("orders", &Value::Integer(700))?.len() >= 2);
+
+    let mut dest_db = TempDatabase::with_prefix("page_image_dest");
+    let dest = dest_db.create_storage_manager().unwrap();
+    // Occupy some pages first so the copied ids cannot line up with the source
+    dest.create_table("other", "CREATE TABLE other (id INTEGER)")?;
+    dest.create_table("another", "CREATE TABLE another (id INTEGER)")?;
+
+    let mut image = Vec::new();
+    let exported = source.export_table_pages("orders", &mut image)?;
+    assert_eq!(exported.page_count, source.iter_table_pages("orders")?.count());
+    let imported = dest.import_table_pages(&mut image.as_slice())?;
+    assert_eq!(imported, exported);
+
+    assert_eq!(rows_by_id(dest)?, rows_by_id(source)?);
+    for id in [0, 1, 700, 1499] {
+        let key = Predicate::eq("id".to_string(), Value::Integer(id));
+        assert_eq!(
+            dest.scan_table("orders", Some(key.clone()))?,
+            source.scan_table("orders", Some(key))?
+        );
+        let path = dest.trace_key("orders", &Value::Integer(id))?;
+        assert_eq!(path.len(), source.trace_key("orders", &Value::Integer(id))?.len());
+    }
+    assert!(dest.check_page_consistency()?.is_empty());
+
+    // The copy survives a reopen and keeps accepting inserts
+    let dest_path = dest_db.path.clone();
+    let mut reopened = StorageManager::new(&dest_path)?;
+    reopened.insert_into_table(
+        "orders",
+        Row::new(vec![Value::Integer(5000), Value::Text("late".to_string()), Value::Real(1.0)]),
+    )?;
+    assert_eq!(reopened.count_rows("orders", None)?, 1501);
+    Ok(())
+}
+
+#[test]
+fn test_page_image_rejects_corruption_and_incompatible_formats() -> Result<(), DatabaseError> {
+    let mut source_db = TempDatabase::with_prefix("page_image_corrupt_source");
+    let source = source_db.create_storage_manager().unwrap();
+    populate_orders(source, 200)?;
+    let mut image = Vec::new();
+    source.export_table_pages("orders", &mut image)?;
+
+    let mut dest_db = TempDatabase::with_prefix("page_image_corrupt_dest");
+    let dest_path = dest_db.path.clone();
+    let dest = dest_db.create_storage_manager().unwrap();
+    let pages_before = dest.db_info.page_count;
+
+    // Flip a byte in the trailing digest: the pages parse but the copy is refused
+    let last = image.len() - 1;
+    image[last] ^= 0xFF;
+    assert!(matches!(
+        dest.import_table_pages(&mut image.as_slice()),
+        Err(DatabaseError::CorruptedDatabase { .. })
+    ));
+    assert!(!dest.table_exists("orders"));
+    assert_eq!(dest.db_info.page_count, pages_before);
+    assert_eq!(dest.db_info.file_size, std::fs::metadata(&dest_path)?.len());
+
+    let mut foreign = Vec::new();
+    page_image::write_manifest(
+        &mut foreign,
+        &PageImageManifest {
+            format_version: PAGE_IMAGE_FORMAT_VERSION,
+            page_size: PAGE_SIZE * 2,
+            schema_format: dest.db_info.header.schema_format_number,
+            schema: source.get_table_schema("orders").unwrap().clone(),
+            page_ids: vec![2],
+        },
+    )?;
+    let error = dest.import_table_pages(&mut foreign.as_slice()).unwrap_err();
+    assert!(matches!(error, DatabaseError::IncompatiblePageImage { .. }));
+    assert!(error.to_string().contains("copy the table logically"));
+    Ok(())
+}
+
+/// Cancels once it has seen `cancel_after` reports
+struct CountingSink {
+    reports: Cell<u64>,
+    cancel_after: u64,
+}
+
+impl ProgressSink for CountingSink {
+    fn report(&self, _done: u64, _total: Option<u64>, _phase: &str) {
+        self.reports.set(self.reports.get() + 1);
+    }
+
+    fn should_cancel(&self) -> bool {
+        self.reports.get() >= self.cancel_after
+    }
+}
+
+#[test]
+fn test_cancelled_import_leaves_database_unchanged() -> Result<(), DatabaseError> {
+    let mut source_db = TempDatabase::with_prefix("page_image_cancel_source");
+    let source = source_db.create_storage_manager().unwrap();
+    populate_orders(source, 300)?;
+    let mut image = Vec::new();
+    let summary = source.export_table_pages("orders", &mut image)?;
+    assert!(summary.page_count > 3);
+
+    let mut dest_db = TempDatabase::with_prefix("page_image_cancel_dest");
+    let dest_path = dest_db.path.clone();
+    let dest = dest_db.create_storage_manager().unwrap();
+    let file_len = std::fs::metadata(&dest_path)?.len();
+
+    let sink = CountingSink { reports: Cell::new(0), cancel_after: 3 };
+    assert!(matches!(
+        dest.import_table_pages_with_progress(&mut image.as_slice(), Some(&sink)),
+        Err(DatabaseError::Cancelled { .. })
+    ));
+    assert_eq!(sink.reports.get(), 3);
+    assert!(!dest.table_exists("orders"));
+    assert_eq!(std::fs::metadata(&dest_path)?.len(), file_len);
+    assert!(dest.check_page_consistency()?.is_empty());
+
+    // The same image imports cleanly afterwards, reporting every page over a channel
+    let (sink, events) = ChannelProgress::new();
+    dest.import_table_pages_with_progress(&mut image.as_slice(), Some(&sink))?;
+    let events: Vec<ProgressEvent> = events.try_iter().collect();
+    assert_eq!(events.len(), summary.page_count + 1);
+    assert!(events.windows(2).all(|pair| pair[0].done + 1 == pair[1].done));
+    assert_eq!(events.last().unwrap().total, Some(summary.page_count as u64));
+    assert_eq!(rows_by_id(dest)?, rows_by_id(source)?);
+    assert!(dest.check_page_consistency()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_progress_sinks_render_and_cancel() {
+    let (sink, events) = ChannelProgress::new();
+    assert!(!sink.should_cancel());
+    sink.cancel_handle().store(true, std::sync::atomic::Ordering::SeqCst);
+    assert!(sink.should_cancel());
+    assert!(matches!(
+        report_progress(Some(&sink), "Vacuum", 1, None, "scanning"),
+        Err(DatabaseError::Cancelled { ref operation }) if operation == "Vacuum"
+    ));
+    assert_eq!(
+        events.try_recv().unwrap(),
+        ProgressEvent { done: 1, total: None, phase: "scanning".to_string() }
+    );
+    assert!(report_progress(None, "Vacuum", 1, None, "scanning").is_ok());
+
+    let terminal = TerminalProgress::new(Vec::new());
+    terminal.report(15, Some(30), "copying pages");
+    terminal.report(7, None, "rows");
+    terminal.finish();
+    let output = String::from_utf8(terminal.into_inner()).unwrap();
+    assert_eq!(
+        output,
+        format!("\rcopying pages [{}{}] 15/30\rrows 7\n", "#".repeat(15), " ".repeat(15))
+    );
+}
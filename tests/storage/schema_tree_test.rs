@@ -0,0 +1,158 @@
+use std::fs;
+
+use bambang::{
+    storage::{
+        BAMBANG_HEADER_SIZE, SCHEMA_ROOT_PAGE_ID,
+        bplus_tree::BPlusTree,
+        options::{OpenMode, StorageManagerOptions},
+        schema::{ColumnSchema, TableSchema},
+        storage_manager::StorageManager,
+    },
+    types::{
+        error::DatabaseError,
+        page::PageType,
+        row::Row,
+        value::{DataType, Value},
+    },
+    utils::mock::create_temp_db_path_with_prefix,
+};
+
+const PAGE_SIZE: usize = 1024;
+const TABLE_COUNT: usize = 300;
+const COLUMN_COUNT: usize = 6;
+
+fn open(path: &std::path::Path, mode: OpenMode) -> Result<StorageManager, DatabaseError> {
+    let options = StorageManagerOptions::new().with_page_size(PAGE_SIZE).with_open_mode(mode);
+    StorageManager::open_with_options(path, options)
+}
+
+fn add_wide_table(storage_manager: &mut StorageManager, name: &str) -> Result<(), DatabaseError> {
+    let root_page_id = storage_manager.allocate_new_page(PageType::LeafTable)?;
+    let columns: Vec<ColumnSchema> = (0..COLUMN_COUNT)
+        .map(|i| {
+            let column = ColumnSchema::new(format!("c{i}"), DataType::Integer, i);
+            if i == 0 { column.primary_key() } else { column }
+        })
+        .collect();
+    let definitions: Vec<String> = (0..COLUMN_COUNT).map(|i| format!("c{i} INTEGER")).collect();
+    let sql = format!("CREATE TABLE {name}({})", definitions.join(", "));
+    storage_manager.add_table_schema(TableSchema::new(name.to_string(), columns, root_page_id, sql))
+}
+
+/// Levels of the schema tree and the type of its root page
+fn schema_tree_shape(path: &std::path::Path) -> (usize, PageType) {
+    let file = fs::OpenOptions::new().read(true).write(true).open(path).unwrap();
+    let extras = Some(BAMBANG_HEADER_SIZE as u64);
+    let mut btree = BPlusTree::new_with_page_size(file, SCHEMA_ROOT_PAGE_ID, extras, PAGE_SIZE).unwrap();
+    let root_type = btree.load_page(SCHEMA_ROOT_PAGE_ID, extras).unwrap().page_type.clone();
+    let mut height = 1;
+    let mut page_id = SCHEMA_ROOT_PAGE_ID;
+    loop {
+        let page = btree.load_page(page_id, extras).unwrap();
+        if page.page_type == PageType::LeafTable {
+            return (height, root_type);
+        }
+        let entry = page.get_cell(0).unwrap();
+        page_id = u64::from_le_bytes(entry[0..8].try_into().unwrap());
+        height += 1;
+    }
+}
+
+/// Names of the tables whose column entries are still stored in `sqlite_schema`
+fn tables_with_column_entries(storage_manager: &StorageManager) -> Vec<String> {
+    let mut owners: Vec<String> = storage_manager
+        .scan_table("sqlite_schema", None)
+        .unwrap()
+        .into_iter()
+        .filter_map(|row| match &row.values[..] {
+            [Value::Text(entry_type), _, Value::Text(owner), ..] if entry_type == "column" => Some(owner.clone()),
+            _ => None,
+        })
+        .collect();
+    owners.dedup();
+    owners
+}
+
+#[test]
+fn test_schema_tree_grows_under_a_fixed_root_on_page_one() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("schema_tree_growth");
+    {
+        let mut storage_manager = open(&path, OpenMode::Eager)?;
+        for i in 0..TABLE_COUNT {
+            add_wide_table(&mut storage_manager, &format!("t{i}"))?;
+        }
+        storage_manager.check_schema_anchor()?;
+    }
+
+    let (height, root_type) = schema_tree_shape(&path);
+    assert!(height >= 3, "schema tree has {} levels", height);
+    assert_eq!(root_type, PageType::InteriorTable);
+
+    for mode in [OpenMode::Eager, OpenMode::Lazy] {
+        let mut storage_manager = open(&path, mode)?;
+        assert_eq!(storage_manager.get_table_names().len(), TABLE_COUNT + 1);
+        assert_eq!(storage_manager.table_roots["sqlite_schema"], SCHEMA_ROOT_PAGE_ID);
+        let schema = storage_manager.get_table_schema("t257").unwrap();
+        assert_eq!(schema.columns.len(), COLUMN_COUNT);
+        let schema_rows = storage_manager.scan_table("sqlite_schema", None)?;
+        assert_eq!(schema_rows.len(), 1 + TABLE_COUNT * (1 + COLUMN_COUNT));
+        storage_manager.check_schema_anchor()?;
+    }
+
+    // DDL after reopening keeps splitting under page 1 and drops reach entries on any leaf
+    {
+        let mut storage_manager = open(&path, OpenMode::Lazy)?;
+        for i in TABLE_COUNT..TABLE_COUNT + 20 {
+            add_wide_table(&mut storage_manager, &format!("t{i}"))?;
+        }
+        storage_manager
+            .create_table("notes", "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)")?;
+        for id in 0..50 {
+            storage_manager
+                .insert_into_table("notes", Row::new(vec![Value::Integer(id), Value::Text(format!("note {id}"))]))?;
+        }
+        for name in ["t0", "t150", "t299", "t310"] {
+            storage_manager.drop_table(name)?;
+        }
+        storage_manager.check_schema_anchor()?;
+    }
+
+    for mode in [OpenMode::Eager, OpenMode::Lazy] {
+        let mut storage_manager = open(&path, mode)?;
+        let names = storage_manager.get_table_names();
+        assert_eq!(names.len(), TABLE_COUNT + 20 - 4 + 2);
+        for name in ["t0", "t150", "t299", "t310"] {
+            assert!(!storage_manager.table_exists(name), "{} survived its drop", name);
+        }
+        let owners = tables_with_column_entries(&storage_manager);
+        assert!(!owners.iter().any(|owner| ["t0", "t150", "t299", "t310"].contains(&owner.as_str())));
+        assert!(owners.iter().any(|owner| owner == "t319"));
+        assert_eq!(storage_manager.scan_table("notes", None)?.len(), 50);
+        assert_eq!(storage_manager.get_table_schema("t1").unwrap().columns.len(), COLUMN_COUNT);
+        storage_manager.check_schema_anchor()?;
+    }
+    assert_eq!(schema_tree_shape(&path).1, PageType::InteriorTable);
+
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_schema_root_page_is_never_freed() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("schema_root_free");
+    let mut storage_manager = open(&path, OpenMode::Eager)?;
+    storage_manager.create_table("items", "CREATE TABLE items (id INTEGER PRIMARY KEY)")?;
+
+    assert!(storage_manager.free_page(SCHEMA_ROOT_PAGE_ID).is_err());
+    assert!(!storage_manager.free_page_ids()?.contains(&SCHEMA_ROOT_PAGE_ID));
+
+    // Freed pages are reused without ever handing out page 1
+    storage_manager.drop_table("items")?;
+    let reused = storage_manager.allocate_new_page(PageType::LeafTable)?;
+    assert_ne!(reused, SCHEMA_ROOT_PAGE_ID);
+    storage_manager.check_schema_anchor()?;
+
+    drop(storage_manager);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
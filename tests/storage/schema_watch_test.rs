@@ -0,0 +1,77 @@
+use bambang::{
+    storage::{
+        schema::ColumnSchema,
+        schema_watch::SchemaChange,
+        storage_manager::StorageManager,
+    },
+    types::{error::DatabaseError, row::Row, value::{DataType, Value}},
+    utils::mock::TempDatabase,
+};
+
+#[test]
+fn test_ddl_emits_versioned_schema_events() {
+    let mut temp_db = TempDatabase::with_prefix("schema_watch_events_test");
+    let path = temp_db.path.clone();
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    let mut watcher = storage_manager.subscribe_schema_changes();
+    let initial_cookie = storage_manager.schema_cookie();
+
+    storage_manager
+        .create_table("users", "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)")
+        .unwrap();
+    storage_manager
+        .insert_into_table("users", Row::new(vec![Value::Integer(1), Value::Text("Ana".to_string())]))
+        .unwrap();
+    storage_manager
+        .add_column("users", ColumnSchema::new("email".to_string(), DataType::Text, 2))
+        .unwrap();
+    storage_manager.drop_table("users").unwrap();
+    storage_manager.reload_schemas().unwrap();
+
+    let events = watcher.drain();
+    let changes: Vec<_> = events.iter().map(|event| event.change.clone()).collect();
+    assert_eq!(
+        changes,
+        vec![
+            SchemaChange::TableCreated("users".to_string()),
+            SchemaChange::TableAltered("users".to_string()),
+            SchemaChange::TableDropped("users".to_string()),
+            SchemaChange::SchemasReloaded,
+        ]
+    );
+    let cookies: Vec<_> = events.iter().map(|event| event.schema_cookie).collect();
+    assert_eq!(
+        cookies,
+        vec![initial_cookie + 1, initial_cookie + 2, initial_cookie + 3, initial_cookie + 3]
+    );
+    assert!(!watcher.missed_events());
+
+    assert!(!storage_manager.table_exists("users"));
+    assert!(matches!(
+        storage_manager.drop_table("users"),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+    let reopened = StorageManager::new(&path).unwrap();
+    assert!(!reopened.table_exists("users"));
+    assert_eq!(reopened.schema_cookie(), initial_cookie + 3);
+}
+
+#[test]
+fn test_dropped_watchers_and_unwatched_ddl_hold_no_memory() {
+    let mut temp_db = TempDatabase::with_prefix("schema_watch_no_subscribers_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    drop(storage_manager.subscribe_schema_changes());
+    assert_eq!(storage_manager.schema_subscriber_count(), 1);
+
+    // 10k DDL operations: create and drop the same table 5k times
+    for _ in 0..5_000 {
+        storage_manager
+            .create_table("scratch", "CREATE TABLE scratch (id INTEGER)")
+            .unwrap();
+        storage_manager.drop_table("scratch").unwrap();
+        assert_eq!(storage_manager.schema_subscriber_count(), 0);
+    }
+    // Dropped tables give their pages back, so the file stops growing after the first cycle
+    assert_eq!(storage_manager.db_info.page_count, 2);
+    assert_eq!(storage_manager.get_table_names(), vec!["sqlite_schema".to_string()]);
+}
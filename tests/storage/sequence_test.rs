@@ -0,0 +1,131 @@
+use bambang::{
+    storage::{options::StorageManagerOptions, storage_manager::StorageManager},
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::TempDatabase,
+};
+
+#[test]
+fn test_sequence_is_shared_across_tables() {
+    let mut temp_db = TempDatabase::with_prefix("sequence_shared_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager
+        .create_sequence("invoice_number", 1000, 1, None, None, false)
+        .unwrap();
+    storage_manager
+        .create_table("sales", "CREATE TABLE sales (invoice INTEGER, amount REAL)")
+        .unwrap();
+    storage_manager
+        .create_table("refunds", "CREATE TABLE refunds (invoice INTEGER, amount REAL)")
+        .unwrap();
+    assert_eq!(storage_manager.current_val("invoice_number").unwrap(), None);
+
+    for (table, amount) in [("sales", 10.0), ("refunds", -4.0), ("sales", 7.5)] {
+        let invoice = storage_manager.next_val("invoice_number").unwrap();
+        storage_manager
+            .insert_into_table(table, Row::new(vec![Value::Integer(invoice), Value::Real(amount)]))
+            .unwrap();
+    }
+    assert_eq!(storage_manager.current_val("invoice_number").unwrap(), Some(1002));
+
+    let invoices = |table: &str| -> Vec<Value> {
+        storage_manager
+            .scan_table(table, None)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.values[0].clone())
+            .collect()
+    };
+    assert_eq!(invoices("sales"), vec![Value::Integer(1000), Value::Integer(1002)]);
+    assert_eq!(invoices("refunds"), vec![Value::Integer(1001)]);
+}
+
+#[test]
+fn test_sequence_cycles_and_exhausts() {
+    let mut temp_db = TempDatabase::with_prefix("sequence_bounds_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager.create_sequence("slot", 2, 1, Some(1), Some(3), true).unwrap();
+    storage_manager.create_sequence("countdown", -2, -2, Some(-6), Some(-1), true).unwrap();
+    storage_manager.create_sequence("ticket", 1, 1, None, Some(2), false).unwrap();
+
+    let values = |sm: &mut StorageManager, name: &str, n: usize| -> Vec<i64> {
+        (0..n).map(|_| sm.next_val(name).unwrap()).collect()
+    };
+    assert_eq!(values(storage_manager, "slot", 5), vec![2, 3, 1, 2, 3]);
+    assert_eq!(values(storage_manager, "countdown", 4), vec![-2, -4, -6, -1]);
+    assert_eq!(values(storage_manager, "ticket", 2), vec![1, 2]);
+    assert!(matches!(
+        storage_manager.next_val("ticket"),
+        Err(DatabaseError::SequenceExhausted { name }) if name == "ticket"
+    ));
+    assert_eq!(storage_manager.current_val("ticket").unwrap(), Some(2));
+}
+
+#[test]
+fn test_sequence_values_are_not_reused_after_crash() {
+    let mut temp_db = TempDatabase::with_prefix("sequence_recovery_test");
+    let path = temp_db.path.clone();
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager.options.sequence_cache_size = 10;
+    storage_manager.create_sequence("order_id", 1, 1, None, None, false).unwrap();
+    let handed_out: Vec<i64> = (0..3).map(|_| storage_manager.next_val("order_id").unwrap()).collect();
+    assert_eq!(handed_out, vec![1, 2, 3]);
+
+    // The first handle never records how far into its block it got, as if it crashed
+    let options = StorageManagerOptions::new().with_sequence_cache_size(10);
+    let mut recovered = StorageManager::open_with_options(&path, options.clone()).unwrap();
+    assert_eq!(recovered.current_val("order_id").unwrap(), None);
+    assert_eq!(recovered.next_val("order_id").unwrap(), 11);
+    assert_eq!(recovered.next_val("order_id").unwrap(), 12);
+    drop(recovered);
+
+    let mut recovered = StorageManager::open_with_options(&path, options).unwrap();
+    assert_eq!(recovered.next_val("order_id").unwrap(), 21);
+
+    // A block that runs into MAXVALUE leaves the sequence exhausted after recovery
+    recovered.create_sequence("short", 1, 1, None, Some(5), false).unwrap();
+    assert_eq!(recovered.next_val("short").unwrap(), 1);
+    drop(recovered);
+    let mut recovered = StorageManager::new(&path).unwrap();
+    assert!(matches!(
+        recovered.next_val("short"),
+        Err(DatabaseError::SequenceExhausted { .. })
+    ));
+}
+
+#[test]
+fn test_sequence_definition_errors() {
+    let mut temp_db = TempDatabase::with_prefix("sequence_errors_test");
+    let path = temp_db.path.clone();
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager.create_sequence("seq", 1, 1, None, None, false).unwrap();
+
+    assert!(matches!(
+        storage_manager.create_sequence("seq", 1, 1, None, None, false),
+        Err(DatabaseError::SequenceAlreadyExists { .. })
+    ));
+    assert!(matches!(
+        storage_manager.create_sequence("zero", 1, 0, None, None, false),
+        Err(DatabaseError::InvalidData { .. })
+    ));
+    assert!(matches!(
+        storage_manager.create_sequence("outside", 10, 1, Some(1), Some(5), false),
+        Err(DatabaseError::InvalidData { .. })
+    ));
+    assert!(matches!(
+        storage_manager.next_val("missing"),
+        Err(DatabaseError::SequenceNotFound { .. })
+    ));
+
+    storage_manager.drop_sequence("seq").unwrap();
+    assert!(matches!(
+        storage_manager.drop_sequence("seq"),
+        Err(DatabaseError::SequenceNotFound { .. })
+    ));
+    let mut reopened = StorageManager::new(&path).unwrap();
+    assert!(matches!(
+        reopened.current_val("seq"),
+        Err(DatabaseError::SequenceNotFound { .. })
+    ));
+    reopened.create_sequence("seq", 5, 1, None, None, false).unwrap();
+    assert_eq!(reopened.next_val("seq").unwrap(), 5);
+}
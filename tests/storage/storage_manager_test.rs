@@ -1,9 +1,31 @@
-use std::fs;
+use std::{
+    env, fs,
+    process::Command,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
 
 use bambang::{
-    executor::predicate::Predicate,
-    storage::{schema::ColumnSchema, storage_manager::StorageManager},
-    types::{row::Row, value::{DataType, Value}},
+    executor::{create_table::CreateTableExecutor, predicate::Predicate, sequential_scan::SequentialScanner},
+    storage::{
+        BAMBANG_HEADER_SIZE,
+        bplus_tree::BPlusTree,
+        export::JsonLayout,
+        options::StorageManagerOptions,
+        page_offset, page_offset_with_size,
+        page_store::{IoLog, assert_header_written_before},
+        schema::{ColumnSchema, TableSchema},
+        storage_manager::StorageManager,
+    },
+    types::{
+        PAGE_SIZE, SLOT_DIRECTORY_ENTRY_SIZE,
+        error::DatabaseError,
+        page::{Page, PageType},
+        row::Row,
+        value::{DataType, Value},
+    },
     utils::mock::{TempDatabase, create_temp_db_path_with_prefix},
 };
 
@@ -47,6 +69,26 @@ fn test_storage_manager_open_existing_database() {
     let _ = fs::remove_file(&temp_path);
 }
 
+#[test]
+fn test_open_rejects_older_slot_format() {
+    let temp_path = create_temp_db_path_with_prefix("older_slot_format");
+    {
+        let _ = StorageManager::new(&temp_path).unwrap();
+    }
+    // schema_format_number sits after the magic, page size, six single-byte fields and
+    // five u32 counters
+    let mut bytes = fs::read(&temp_path).unwrap();
+    bytes[44..48].copy_from_slice(&4u32.to_be_bytes());
+    fs::write(&temp_path, &bytes).unwrap();
+
+    let result = StorageManager::new(&temp_path);
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidHeader { ref reason }) if reason.contains("schema format 4")
+    ));
+    let _ = fs::remove_file(&temp_path);
+}
+
 #[test]
 fn test_multiple_tables() {
     let mut temp_db = TempDatabase::with_prefix("multi_table_test");
@@ -159,6 +201,81 @@ fn test_scan_table_without_predicate() {
     assert_eq!(all_rows.len(), 4);
 }
 
+#[test]
+fn test_fold_rows_matches_summed_scan() {
+    let mut temp_db = TempDatabase::with_prefix("fold_rows_test");
+    let storage_manager = setup_test_table_with_schema(&mut temp_db);
+
+    let age_sum = |rows: &[Row]| -> i64 {
+        rows.iter()
+            .map(|row| match row.values[2] {
+                Value::Integer(age) => age,
+                _ => 0,
+            })
+            .sum()
+    };
+    let folded = storage_manager
+        .fold_rows("users", None, 0i64, |acc, row| match row.values[2] {
+            Value::Integer(age) => acc + age,
+            _ => acc,
+        })
+        .unwrap();
+    let scanned = storage_manager.scan_table("users", None).unwrap();
+    assert_eq!(folded, age_sum(&scanned));
+    assert_eq!(folded, 118);
+
+    let active = Predicate::eq("active".to_string(), Value::Boolean(true));
+    let (count, sum) = storage_manager
+        .fold_rows("users", Some(active.clone()), (0usize, 0i64), |(count, sum), row| {
+            match row.values[2] {
+                Value::Integer(age) => (count + 1, sum + age),
+                _ => (count + 1, sum),
+            }
+        })
+        .unwrap();
+    let active_rows = storage_manager.scan_table("users", Some(active)).unwrap();
+    assert_eq!(count, active_rows.len());
+    assert_eq!(sum, age_sum(&active_rows));
+}
+
+#[test]
+fn test_count_rows_matches_scan() {
+    let mut temp_db = TempDatabase::with_prefix("count_rows_test");
+    setup_test_table_with_schema(&mut temp_db);
+    let storage_manager = temp_db.get_storage_manager().unwrap();
+
+    assert_eq!(storage_manager.count_rows("users", None).unwrap(), 4);
+    let active = Predicate::eq("active".to_string(), Value::Boolean(true));
+    assert_eq!(
+        storage_manager.count_rows("users", Some(active.clone())).unwrap(),
+        storage_manager.scan_table("users", Some(active)).unwrap().len()
+    );
+
+    // The metadata-only path must skip deleted slots and follow the whole leaf chain
+    for id in 5..=300 {
+        storage_manager
+            .insert_into_table(
+                "users",
+                Row::new(vec![
+                    Value::Integer(id),
+                    Value::Text(format!("user_{:0>40}", id)),
+                    Value::Integer(20),
+                    Value::Boolean(false),
+                ]),
+            )
+            .unwrap();
+    }
+    let deleted = storage_manager
+        .delete_from_table("users", Some(Predicate::gt("id".to_string(), Value::Integer(250))))
+        .unwrap();
+    assert_eq!(deleted, 50);
+    assert_eq!(storage_manager.count_rows("users", None).unwrap(), 250);
+    assert_eq!(
+        storage_manager.count_rows("users", None).unwrap(),
+        storage_manager.scan_table("users", None).unwrap().len()
+    );
+}
+
 #[test]
 fn test_scan_table_with_predicate_functionality() {
     let mut temp_db = TempDatabase::with_prefix("scan_predicate_test");
@@ -205,10 +322,1089 @@ fn test_multiple_inserts() {
         storage_manager.insert_into_table("users", user).unwrap();
         assert!(storage_manager.table_roots.contains_key("users"));
     }
-    drop(storage_manager);
+    temp_db.storage_manager = None;
     let reopened_storage = StorageManager::new(&db_path).unwrap();
     assert!(reopened_storage.table_roots.contains_key("users"));
     assert!(reopened_storage.table_roots.contains_key("sqlite_schema"));
     drop(reopened_storage);
     drop(temp_db);
-}
\ No newline at end of file
+}
+#[test]
+fn test_freed_pages_are_reused_after_reopen() {
+    let mut temp_db = TempDatabase::with_prefix("freelist_reuse_test");
+    let db_path = temp_db.path.clone();
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    let first = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+    let second = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+    let third = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+    storage_manager.free_page(first).unwrap();
+    storage_manager.free_page(third).unwrap();
+    assert_eq!(storage_manager.db_info.header.freelist_pages_count, 2);
+    assert_eq!(storage_manager.db_info.header.freelist_trunk_page as u64, third);
+    temp_db.storage_manager = None;
+
+    let mut reopened = StorageManager::new(&db_path).unwrap();
+    assert_eq!(reopened.db_info.header.freelist_pages_count, 2);
+    let free_pages = reopened.free_page_ids().unwrap();
+    assert_eq!(free_pages, vec![third, first]);
+    assert!(!free_pages.contains(&second));
+    let page_count = reopened.db_info.page_count;
+    assert_eq!(reopened.allocate_new_page(PageType::LeafTable).unwrap(), third);
+    assert_eq!(reopened.allocate_new_page(PageType::LeafTable).unwrap(), first);
+    assert_eq!(reopened.db_info.header.freelist_pages_count, 0);
+    assert_eq!(reopened.db_info.page_count, page_count);
+    assert_eq!(reopened.allocate_new_page(PageType::LeafTable).unwrap(), page_count + 1);
+}
+
+#[test]
+fn test_freeing_a_page_twice_is_rejected() {
+    let mut temp_db = TempDatabase::with_prefix("freelist_double_free_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    let first = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+    let second = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+    storage_manager.free_page(first).unwrap();
+    storage_manager.free_page(second).unwrap();
+    for page_id in [first, second] {
+        assert!(matches!(storage_manager.free_page(page_id), Err(DatabaseError::InvalidData { .. })));
+    }
+    assert_eq!(storage_manager.db_info.header.freelist_pages_count, 2);
+    assert_eq!(storage_manager.free_page_ids().unwrap(), vec![second, first]);
+    assert_eq!(storage_manager.allocate_new_page(PageType::LeafTable).unwrap(), second);
+    assert_eq!(storage_manager.allocate_new_page(PageType::LeafTable).unwrap(), first);
+    assert!(storage_manager.free_page_ids().unwrap().is_empty());
+}
+
+#[test]
+fn test_btree_splits_reuse_freed_pages() {
+    let mut temp_db = TempDatabase::with_prefix("freelist_split_test");
+    let db_path = temp_db.path.clone();
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager
+        .create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT)")
+        .unwrap();
+    let spare = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+    storage_manager.free_page(spare).unwrap();
+    let page_count = storage_manager.db_info.page_count;
+    let padding = "x".repeat(400);
+    for i in 1..=12 {
+        let row = create_user_row(i, &format!("User{}{}", i, padding), "user@example.com");
+        storage_manager.insert_into_table("users", row).unwrap();
+    }
+    assert_eq!(storage_manager.db_info.header.freelist_pages_count, 0);
+    assert!(storage_manager.db_info.page_count > page_count);
+    temp_db.storage_manager = None;
+
+    let reopened = StorageManager::new(&db_path).unwrap();
+    assert_eq!(reopened.db_info.header.freelist_trunk_page, 0);
+}
+
+#[test]
+fn test_root_split_records_new_pages_in_header_before_writing_them() {
+    let mut temp_db = TempDatabase::with_prefix("split_order_test");
+    let io_log = IoLog::new();
+    let options = StorageManagerOptions::default().with_io_log(io_log.clone());
+    let storage_manager = temp_db.create_storage_manager_with_options(options).unwrap();
+    storage_manager
+        .create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT)")
+        .unwrap();
+    let root_page_id = storage_manager.table_roots["users"];
+    let padding = "x".repeat(400);
+    let mut split_seq = None;
+    for i in 1..=12 {
+        let page_count = storage_manager.db_info.page_count;
+        let seq = io_log.next_seq();
+        let row = create_user_row(i, &format!("User{}{}", i, padding), "user@example.com");
+        storage_manager.insert_into_table("users", row).unwrap();
+        if storage_manager.table_roots["users"] != root_page_id {
+            split_seq = Some((seq, page_count));
+            break;
+        }
+    }
+    let (split_seq, page_count) = split_seq.expect("root never split");
+
+    // The sibling and the new root are each counted in the header before their first
+    // write, and the old root is rewritten with its half of the cells after both exist
+    let new_root = storage_manager.table_roots["users"];
+    let new_pages: Vec<u64> = io_log
+        .pages_written_since(split_seq)
+        .into_iter()
+        .filter(|page_id| *page_id > page_count)
+        .collect();
+    assert_eq!(new_pages.len(), 2);
+    assert!(new_pages.contains(&new_root));
+    for page_id in &new_pages {
+        assert_header_written_before(&io_log, *page_id, split_seq);
+    }
+    let last_root_write = *io_log.page_writes(root_page_id).last().unwrap();
+    assert!(new_pages
+        .iter()
+        .all(|page_id| io_log.page_writes(*page_id)[0] < last_root_write));
+}
+
+#[test]
+fn test_quota_blocks_page_allocation_and_persists() {
+    let mut temp_db = TempDatabase::with_prefix("quota_alloc_test");
+    let limit = (BAMBANG_HEADER_SIZE + 4 * PAGE_SIZE) as u64;
+    let options = StorageManagerOptions::new().with_max_database_size(limit);
+    let mut storage_manager = StorageManager::open_with_options(&temp_db.path, options).unwrap();
+    let mut allocated = Vec::new();
+    let err = loop {
+        match storage_manager.allocate_new_page(PageType::LeafTable) {
+            Ok(page_id) => allocated.push(page_id),
+            Err(e) => break e,
+        }
+    };
+    assert_eq!(allocated.len(), 3);
+    match err {
+        DatabaseError::QuotaExceeded { limit: reported, current } => {
+            assert_eq!(reported, limit);
+            assert_eq!(current, limit);
+        }
+        other => panic!("expected QuotaExceeded, got {:?}", other),
+    }
+    assert_eq!(storage_manager.db_info.page_count, 4);
+    assert_eq!(fs::metadata(&temp_db.path).unwrap().len(), limit);
+    drop(storage_manager);
+
+    // The quota is stored in the header, so reopening without options keeps it
+    let mut reopened = StorageManager::new(&temp_db.path).unwrap();
+    assert_eq!(reopened.quota_usage().limit_bytes, Some(limit));
+    assert!(matches!(
+        reopened.allocate_new_page(PageType::LeafTable),
+        Err(DatabaseError::QuotaExceeded { .. })
+    ));
+    reopened.free_page(allocated[1]).unwrap();
+    assert_eq!(reopened.allocate_new_page(PageType::LeafTable).unwrap(), allocated[1]);
+    reopened.set_max_database_size(None).unwrap();
+    assert_eq!(reopened.allocate_new_page(PageType::LeafTable).unwrap(), 5);
+    temp_db.storage_manager = Some(reopened);
+}
+
+#[test]
+fn test_quota_enforced_for_inserts_with_warning() {
+    let mut temp_db = TempDatabase::with_prefix("quota_insert_test");
+    let limit = (BAMBANG_HEADER_SIZE + 8 * PAGE_SIZE) as u64;
+    let warnings = Arc::new(AtomicUsize::new(0));
+    let counter = warnings.clone();
+    let options = StorageManagerOptions::new()
+        .with_max_database_size(limit)
+        .with_quota_warning(0.75, move |usage| {
+            assert!(usage.fraction().unwrap() >= 0.75);
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    let mut storage_manager = StorageManager::open_with_options(&temp_db.path, options).unwrap();
+    storage_manager
+        .create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT)")
+        .unwrap();
+    let padding = "x".repeat(600);
+    let user = |i: i64| create_user_row(i, &format!("User{}{}", i, padding), "user@example.com");
+    let mut result = Ok(());
+    let mut failed_id = 0;
+    for i in 1..=200 {
+        result = storage_manager.insert_into_table("users", user(i));
+        if result.is_err() {
+            failed_id = i;
+            break;
+        }
+    }
+    assert!(matches!(result, Err(DatabaseError::QuotaExceeded { .. })));
+    assert_eq!(warnings.load(Ordering::SeqCst), 1);
+    assert!(fs::metadata(&temp_db.path).unwrap().len() <= limit);
+
+    // The refused row left nothing behind
+    let ids = |storage_manager: &StorageManager| -> Vec<Value> {
+        let mut ids: Vec<Value> = storage_manager
+            .scan_table("users", None)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.values[0].clone())
+            .collect();
+        ids.sort_by(|a, b| a.total_cmp(b));
+        ids
+    };
+    assert_eq!(ids(&storage_manager), (1..failed_id).map(Value::Integer).collect::<Vec<_>>());
+    assert!(storage_manager.check_integrity().unwrap().is_ok());
+
+    // Deleting and vacuuming frees room, and the refused row then fits within the quota
+    let kept_from = failed_id / 2;
+    storage_manager
+        .delete_from_table("users", Some(Predicate::lt("id".to_string(), Value::Integer(kept_from))))
+        .unwrap();
+    let vacuumed = storage_manager.vacuum().unwrap();
+    assert!(vacuumed.bytes_after < vacuumed.bytes_before);
+    assert_eq!(storage_manager.quota_usage().limit_bytes, Some(limit));
+    storage_manager.insert_into_table("users", user(failed_id)).unwrap();
+    assert!(fs::metadata(&temp_db.path).unwrap().len() <= limit);
+    assert_eq!(ids(&storage_manager), (kept_from..=failed_id).map(Value::Integer).collect::<Vec<_>>());
+    assert!(storage_manager.check_integrity().unwrap().is_ok());
+    drop(storage_manager);
+
+    let reopened = StorageManager::new(&temp_db.path).unwrap();
+    assert!(reopened.quota_usage().used_bytes <= limit);
+    assert_eq!(ids(&reopened), (kept_from..=failed_id).map(Value::Integer).collect::<Vec<_>>());
+    temp_db.storage_manager = Some(reopened);
+}
+
+#[test]
+fn test_add_column_backfills_rows_and_survives_reopen() {
+    let temp_path = create_temp_db_path_with_prefix("add_column_test");
+    {
+        let mut storage_manager = StorageManager::new(&temp_path).unwrap();
+        storage_manager
+            .create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT)")
+            .unwrap();
+        for i in 1..=40 {
+            storage_manager
+                .insert_into_table("users", create_user_row(i, &format!("User{}", i), "user@example.com"))
+                .unwrap();
+        }
+        let status = ColumnSchema::new("status".to_string(), DataType::Text, 3)
+            .with_default(Value::Text("active".to_string()));
+        storage_manager.add_column("users", status).unwrap();
+        let score = ColumnSchema::new("score".to_string(), DataType::Integer, 4);
+        storage_manager.add_column("users", score).unwrap();
+    }
+
+    let storage_manager = StorageManager::new(&temp_path).unwrap();
+    let schema = storage_manager.get_table_schema("users").unwrap();
+    assert_eq!(
+        schema.column_names(),
+        vec!["id", "name", "email", "status", "score"]
+    );
+    CreateTableExecutor::new()
+        .validate_columns(&schema.columns)
+        .unwrap();
+    assert_eq!(
+        schema.get_column("status").unwrap().default_value,
+        Some(Value::Text("active".to_string()))
+    );
+
+    let rows = storage_manager.scan_table("users", None).unwrap();
+    assert_eq!(rows.len(), 40);
+    for row in &rows {
+        assert_eq!(row.values.len(), 5);
+        assert_eq!(row.values[3], Value::Text("active".to_string()));
+        assert_eq!(row.values[4], Value::Null);
+    }
+    let active = Predicate::eq("status".to_string(), Value::Text("active".to_string()));
+    assert_eq!(storage_manager.scan_table("users", Some(active)).unwrap().len(), 40);
+    drop(storage_manager);
+    let _ = fs::remove_file(&temp_path);
+}
+
+#[test]
+fn test_add_column_rejects_invalid_columns() {
+    let mut temp_db = TempDatabase::with_prefix("add_column_invalid_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager
+        .create_table("users", "CREATE TABLE users(id INTEGER, name TEXT)")
+        .unwrap();
+    storage_manager
+        .insert_into_table("users", Row::new(vec![Value::Integer(1), Value::Text("a".to_string())]))
+        .unwrap();
+
+    let wrong_position = ColumnSchema::new("age".to_string(), DataType::Integer, 5);
+    assert!(matches!(
+        storage_manager.add_column("users", wrong_position),
+        Err(DatabaseError::InvalidData { .. })
+    ));
+    let duplicate = ColumnSchema::new("name".to_string(), DataType::Text, 2);
+    assert!(matches!(
+        storage_manager.add_column("users", duplicate),
+        Err(DatabaseError::InvalidData { .. })
+    ));
+    let not_null = ColumnSchema::new("age".to_string(), DataType::Integer, 2).not_null();
+    assert!(matches!(
+        storage_manager.add_column("users", not_null),
+        Err(DatabaseError::InvalidData { .. })
+    ));
+    let bad_default = ColumnSchema::new("age".to_string(), DataType::Integer, 2)
+        .with_default(Value::Text("old".to_string()));
+    assert!(matches!(
+        storage_manager.add_column("users", bad_default),
+        Err(DatabaseError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        storage_manager.add_column("missing", ColumnSchema::new("x".to_string(), DataType::Integer, 0)),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+
+    assert_eq!(storage_manager.get_table_schema("users").unwrap().columns.len(), 2);
+    let rows = storage_manager.scan_table("users", None).unwrap();
+    assert_eq!(rows[0].values.len(), 2);
+}
+
+fn reopen_with_columns(prefix: &str, columns: Vec<ColumnSchema>) -> Result<StorageManager, DatabaseError> {
+    let temp_path = create_temp_db_path_with_prefix(prefix);
+    {
+        let mut storage_manager = StorageManager::new(&temp_path).unwrap();
+        let root_page_id = storage_manager.allocate_new_page(PageType::LeafTable).unwrap();
+        let schema = TableSchema::new(
+            "broken".to_string(),
+            columns,
+            root_page_id,
+            "CREATE TABLE broken(id INTEGER, name TEXT)".to_string(),
+        );
+        storage_manager.add_table_schema(schema).unwrap();
+    }
+    let reopened = StorageManager::new(&temp_path);
+    let _ = fs::remove_file(&temp_path);
+    reopened
+}
+
+#[test]
+fn test_load_rejects_column_position_gap() {
+    let result = reopen_with_columns(
+        "position_gap_test",
+        vec![
+            ColumnSchema::new("id".to_string(), DataType::Integer, 0),
+            ColumnSchema::new("name".to_string(), DataType::Text, 2),
+        ],
+    );
+    // The table stays, without a schema, and the open reports why
+    let storage_manager = result.unwrap();
+    assert!(storage_manager.table_roots.contains_key("broken"));
+    assert!(storage_manager.get_table_schema("broken").is_none());
+    match storage_manager.schema_warnings() {
+        [warning] => assert!(warning.details.contains("position"), "{}", warning),
+        other => panic!("expected one warning, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_load_rejects_duplicate_column_position() {
+    let result = reopen_with_columns(
+        "position_duplicate_test",
+        vec![
+            ColumnSchema::new("id".to_string(), DataType::Integer, 0),
+            ColumnSchema::new("name".to_string(), DataType::Text, 0),
+        ],
+    );
+    let storage_manager = result.unwrap();
+    assert!(storage_manager.get_table_schema("broken").is_none());
+    assert_eq!(storage_manager.schema_warnings().len(), 1);
+
+    let valid = reopen_with_columns(
+        "position_valid_test",
+        vec![
+            ColumnSchema::new("name".to_string(), DataType::Text, 1),
+            ColumnSchema::new("id".to_string(), DataType::Integer, 0),
+        ],
+    )
+    .unwrap();
+    assert_eq!(valid.get_table_schema("broken").unwrap().column_names(), vec!["id", "name"]);
+    assert!(valid.schema_warnings().is_empty());
+}
+
+fn tree_height(path: &std::path::Path, root_page_id: u64) -> usize {
+    let file = fs::OpenOptions::new().read(true).write(true).open(path).unwrap();
+    let extras = Some(BAMBANG_HEADER_SIZE as u64);
+    let mut btree = BPlusTree::new_with_extras(file, root_page_id, extras).unwrap();
+    let mut height = 1;
+    let mut page_id = root_page_id;
+    loop {
+        let page = btree.load_page(page_id, extras).unwrap();
+        if page.page_type == PageType::LeafTable {
+            return height;
+        }
+        let entry = page.get_cell(0).unwrap();
+        page_id = u64::from_le_bytes(entry[0..8].try_into().unwrap());
+        height += 1;
+    }
+}
+
+#[test]
+fn test_trace_key_follows_tree_height() {
+    let mut temp_db = TempDatabase::with_prefix("trace_key_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager
+        .create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT)")
+        .unwrap();
+    let single_leaf_root = storage_manager.table_roots["users"];
+    assert_eq!(
+        storage_manager.trace_key("users", &Value::Integer(1)).unwrap(),
+        vec![single_leaf_root]
+    );
+
+    let padding = "x".repeat(500);
+    for i in 1..=60 {
+        storage_manager
+            .insert_into_table("users", create_user_row(i, &padding, "user@example.com"))
+            .unwrap();
+    }
+    let root_page_id = storage_manager.table_roots["users"];
+    let height = tree_height(&temp_db.path, root_page_id);
+    assert!(height > 1);
+
+    let storage_manager = temp_db.get_storage_manager().unwrap();
+    let mut leaves = std::collections::HashSet::new();
+    for key in [1, 15, 30, 45, 60, 1000] {
+        let path = storage_manager.trace_key("users", &Value::Integer(key)).unwrap();
+        assert_eq!(path.len(), height);
+        assert_eq!(path[0], root_page_id);
+        leaves.insert(*path.last().unwrap());
+    }
+    assert!(leaves.len() > 1);
+    assert!(matches!(
+        storage_manager.trace_key("missing", &Value::Integer(1)),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_user_version_persists_across_reopen() {
+    let mut temp_db = TempDatabase::with_prefix("user_version_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    assert_eq!(storage_manager.user_version(), 0);
+
+    storage_manager.set_user_version(42).unwrap();
+    assert_eq!(storage_manager.user_version(), 42);
+    // Later header writes must not clobber it
+    storage_manager
+        .create_table("users", "CREATE TABLE users (id INTEGER, name TEXT)")
+        .unwrap();
+
+    let reopened = StorageManager::new(&temp_db.path).unwrap();
+    assert_eq!(reopened.user_version(), 42);
+}
+
+#[test]
+fn test_change_counter_advances_on_writes() {
+    let mut temp_db = TempDatabase::with_prefix("change_counter_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    let initial = storage_manager.change_counter();
+    storage_manager
+        .create_table("users", "CREATE TABLE users (id INTEGER, name TEXT)")
+        .unwrap();
+    let after_create = storage_manager.change_counter();
+    assert!(after_create > initial);
+
+    storage_manager
+        .insert_into_table("users", Row::new(vec![Value::Integer(1), Value::Text("a".to_string())]))
+        .unwrap();
+    let after_first = storage_manager.change_counter();
+    storage_manager
+        .insert_into_table("users", Row::new(vec![Value::Integer(2), Value::Text("b".to_string())]))
+        .unwrap();
+    let after_second = storage_manager.change_counter();
+    assert!(after_first > after_create);
+    assert!(after_second > after_first);
+
+    // A read leaves the counter alone, and a delete that matches nothing is not a write
+    storage_manager.scan_table("users", None).unwrap();
+    storage_manager
+        .delete_from_table("users", Some(Predicate::eq("id".to_string(), Value::Integer(99))))
+        .unwrap();
+    assert_eq!(storage_manager.change_counter(), after_second);
+
+    let reopened = StorageManager::new(&temp_db.path).unwrap();
+    assert_eq!(reopened.change_counter(), after_second);
+}
+
+#[test]
+fn test_export_json_writes_typed_objects() {
+    let mut temp_db = TempDatabase::with_prefix("export_json_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager
+        .create_table(
+            "events",
+            "CREATE TABLE events (id INTEGER, label TEXT, score REAL, done BOOLEAN, payload BLOB, at TIMESTAMP)",
+        )
+        .unwrap();
+    storage_manager
+        .insert_into_table(
+            "events",
+            Row::new(vec![
+                Value::Integer(1),
+                Value::Text("launch \"v1\"".to_string()),
+                Value::Real(2.5),
+                Value::Boolean(true),
+                Value::Blob(vec![0xde, 0xad, 0x01]),
+                Value::Timestamp(1_700_000_000),
+            ]),
+        )
+        .unwrap();
+    storage_manager
+        .insert_into_table(
+            "events",
+            Row::new(vec![Value::Integer(2), Value::Null, Value::Null, Value::Null, Value::Null, Value::Null]),
+        )
+        .unwrap();
+
+    let mut output = Vec::new();
+    storage_manager.export_json("events", None, &mut output, JsonLayout::Array).unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        parsed,
+        serde_json::json!([
+            {
+                "id": 1,
+                "label": "launch \"v1\"",
+                "score": 2.5,
+                "done": true,
+                "payload": "dead01",
+                "at": "2023-11-14T22:13:20Z"
+            },
+            { "id": 2, "label": null, "score": null, "done": null, "payload": null, "at": null }
+        ])
+    );
+
+    let mut empty = Vec::new();
+    storage_manager.create_table("empty", "CREATE TABLE empty (id INTEGER)").unwrap();
+    storage_manager.export_json("empty", None, &mut empty, JsonLayout::Array).unwrap();
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&empty).unwrap(), serde_json::json!([]));
+    assert!(matches!(
+        storage_manager.export_json("missing", None, &mut Vec::new(), JsonLayout::Array),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_decimal_values_compare_numerically() {
+    let mut temp_db = TempDatabase::with_prefix("decimal_values_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager
+        .create_table("prices", "CREATE TABLE prices (amount DECIMAL(10, 2), label TEXT)")
+        .unwrap();
+    let schema = storage_manager.get_table_schema("prices").unwrap();
+    assert_eq!(schema.columns[0].data_type, DataType::Decimal);
+
+    let decimal = |s: &str| Value::from_string(s, &DataType::Decimal).unwrap();
+    for amount in ["10.05", "9.99", "100.00", "-0.01", "10.5"] {
+        storage_manager
+            .insert_into_table("prices", Row::new(vec![decimal(amount), Value::Text(amount.to_string())]))
+            .unwrap();
+    }
+    let amounts = |predicate: Option<Predicate>| -> Vec<String> {
+        let mut values: Vec<Value> = storage_manager
+            .scan_table("prices", predicate)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.values[0].clone())
+            .collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values.iter().map(|value| value.to_string()).collect()
+    };
+    assert_eq!(amounts(None), vec!["-0.01", "9.99", "10.05", "10.5", "100.00"]);
+    assert_eq!(
+        amounts(Some(Predicate::gt("amount".to_string(), decimal("10.05")))),
+        vec!["10.5", "100.00"]
+    );
+    assert_eq!(
+        amounts(Some(Predicate::eq("amount".to_string(), Value::Integer(100)))),
+        vec!["100.00"]
+    );
+}
+
+#[test]
+fn test_inconsistent_leaf_is_reported_and_repaired() {
+    let mut temp_db = TempDatabase::with_prefix("page_reconciliation_test");
+    let storage_manager = temp_db.create_storage_manager().unwrap();
+    storage_manager
+        .create_table("users", "CREATE TABLE users (id INTEGER, name TEXT)")
+        .unwrap();
+    for i in 1..=5 {
+        storage_manager
+            .insert_into_table("users", Row::new(vec![Value::Integer(i), Value::Text(format!("user_{}", i))]))
+            .unwrap();
+    }
+    assert!(storage_manager.check_page_consistency().unwrap().is_empty());
+
+    // Simulate an older writer that stored a free_space_offset above the live cells
+    let root_page_id = storage_manager.table_roots["users"];
+    let offset = BAMBANG_HEADER_SIZE as u64 + (root_page_id - 1) * PAGE_SIZE as u64;
+    let db_path = storage_manager.db_info.path.clone();
+    let mut bytes = fs::read(&db_path).unwrap();
+    let page_range = offset as usize..offset as usize + PAGE_SIZE;
+    let mut buggy = Page::from_bytes(&bytes[page_range.clone()]).unwrap();
+    buggy.free_space_offset += 20;
+    buggy.update_checksum();
+    let mut page_bytes = bytes[page_range.clone()].to_vec();
+    buggy.write_header(&mut std::io::Cursor::new(&mut page_bytes));
+    bytes[page_range].copy_from_slice(&page_bytes);
+    fs::write(&db_path, &bytes).unwrap();
+
+    let reports = storage_manager.check_page_consistency().unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].0, root_page_id);
+
+    for i in 6..=8 {
+        storage_manager
+            .insert_into_table("users", Row::new(vec![Value::Integer(i), Value::Text(format!("user_{}", i))]))
+            .unwrap();
+    }
+    let mut names: Vec<String> = storage_manager
+        .scan_table("users", None)
+        .unwrap()
+        .into_iter()
+        .map(|row| row.values[1].to_string())
+        .collect();
+    names.sort();
+    let expected: Vec<String> = (1..=8).map(|i| format!("user_{}", i)).collect();
+    assert_eq!(names, expected);
+    assert!(storage_manager.check_page_consistency().unwrap().is_empty());
+}
+
+#[test]
+fn test_small_inserts_write_only_modified_extents() -> Result<(), DatabaseError> {
+    let insert_rows = |options: StorageManagerOptions| -> Result<(u64, u64, Vec<Row>), DatabaseError> {
+        let path = create_temp_db_path_with_prefix("partial_writes");
+        let mut storage = StorageManager::open_with_options(&path, options)?;
+        storage.create_table("events", "CREATE TABLE events(id INTEGER, kind TEXT)")?;
+        let before = storage.io_stats();
+        for id in 0..50 {
+            storage.insert_into_table("events", Row::new(vec![Value::Integer(id), Value::Text("tick".to_string())]))?;
+        }
+        let after = storage.io_stats();
+        let rows = storage.scan_table("events", None)?;
+        drop(storage);
+        let _ = fs::remove_file(&path);
+        Ok((after.bytes_written - before.bytes_written, after.partial_page_writes, rows))
+    };
+
+    let (partial_bytes, partial_writes, partial_rows) = insert_rows(StorageManagerOptions::new())?;
+    let (full_bytes, full_mode_partial_writes, full_rows) =
+        insert_rows(StorageManagerOptions::new().with_partial_page_writes(false))?;
+
+    assert_eq!(partial_rows, full_rows);
+    assert_eq!(full_mode_partial_writes, 0);
+    assert!(partial_writes >= 50, "{} partial writes", partial_writes);
+    assert!(full_bytes >= 50 * PAGE_SIZE as u64);
+    // Header, one slot entry and a small cell per insert
+    assert!(partial_bytes * 10 < full_bytes, "{} vs {} bytes", partial_bytes, full_bytes);
+    Ok(())
+}
+
+#[test]
+fn test_row_storage_cost_matches_space_consumed() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("row_storage_cost");
+    let mut storage = StorageManager::new(&path)?;
+    let read_page = |page_id: u64| -> Page {
+        let bytes = fs::read(&path).unwrap();
+        let offset = page_offset(page_id) as usize;
+        Page::from_bytes(&bytes[offset..offset + PAGE_SIZE]).unwrap()
+    };
+    // Rowid flag, rowid, value count, INTEGER id and the TEXT header
+    let fixed_bytes = 1 + 8 + 4 + 9 + 5;
+    let threshold = PAGE_SIZE / 2;
+    let body_lengths = [0, 100, 1500, threshold - fixed_bytes - 1, threshold - fixed_bytes, 3000];
+
+    for (i, &body_length) in body_lengths.iter().enumerate() {
+        let table = format!("sized_{}", i);
+        storage.create_table(&table, &format!("CREATE TABLE {}(id INTEGER, body TEXT)", table))?;
+        let row = Row::new(vec![Value::Integer(1), Value::Text("x".repeat(body_length))]);
+        let cost = storage.row_storage_cost(&table, &row)?;
+        assert_eq!(cost.needs_overflow, fixed_bytes + body_length >= threshold, "body {}", body_length);
+        assert!(read_page(storage.table_roots[&table]).would_fit(&cost));
+
+        let root = storage.table_roots[&table];
+        let free_before = read_page(root).available_space();
+        let file_before = fs::metadata(&path)?.len();
+        storage.insert_into_table(&table, row)?;
+        let free_after = read_page(root).available_space();
+        let pages_added = (fs::metadata(&path)?.len() - file_before) / PAGE_SIZE as u64;
+
+        assert_eq!(free_before - free_after, cost.page_bytes(), "body {}", body_length);
+        assert_eq!(pages_added as usize, cost.overflow_pages_estimate, "body {}", body_length);
+    }
+
+    assert!(matches!(
+        storage.row_storage_cost("missing", &Row::new(vec![Value::Integer(1)])),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_insert_into_table_exact_fit_boundaries() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("exact_fit");
+    let mut storage = StorageManager::new(&path)?;
+    let read_page = |page_id: u64| -> Page {
+        let bytes = fs::read(&path).unwrap();
+        let offset = page_offset(page_id) as usize;
+        Page::from_bytes(&bytes[offset..offset + PAGE_SIZE]).unwrap()
+    };
+    let row = |id: i64, body_len: usize| Row::new(vec![Value::Integer(id), Value::Text("x".repeat(body_len))]);
+    // Rowid flag, rowid, value count, INTEGER id and the TEXT header
+    let fixed_bytes = 1 + 8 + 4 + 9 + 5;
+
+    for (i, leftover) in [0, 1, 3, 4].into_iter().enumerate() {
+        let table = format!("fit_{}", i);
+        storage.create_table(&table, &format!("CREATE TABLE {}(id INTEGER, body TEXT)", table))?;
+        for id in 0..3 {
+            storage.insert_into_table(&table, row(id, 1000))?;
+        }
+        let root = storage.table_roots[&table];
+        let free = read_page(root).available_space();
+        let body_len = free - SLOT_DIRECTORY_ENTRY_SIZE - leftover - fixed_bytes;
+        let file_len = fs::metadata(&path)?.len();
+
+        storage.insert_into_table(&table, row(3, body_len))?;
+        assert_eq!(storage.table_roots[&table], root, "leftover {} split the leaf", leftover);
+        assert_eq!(fs::metadata(&path)?.len(), file_len, "leftover {} allocated a page", leftover);
+        assert_eq!(read_page(root).available_space(), leftover);
+        assert_eq!(storage.scan_table(&table, None)?.len(), 4);
+    }
+
+    // One byte more than the leaf holds must split
+    storage.create_table("too_big", "CREATE TABLE too_big(id INTEGER, body TEXT)")?;
+    for id in 0..3 {
+        storage.insert_into_table("too_big", row(id, 1000))?;
+    }
+    let root = storage.table_roots["too_big"];
+    let free = read_page(root).available_space();
+    storage.insert_into_table("too_big", row(3, free - SLOT_DIRECTORY_ENTRY_SIZE - fixed_bytes + 1))?;
+    assert_ne!(storage.table_roots["too_big"], root);
+    assert_eq!(storage.scan_table("too_big", None)?.len(), 4);
+
+    // Space held by deleted rows is reclaimed before splitting
+    storage.create_table("reuse", "CREATE TABLE reuse(id INTEGER, body TEXT)")?;
+    for id in 0..3 {
+        storage.insert_into_table("reuse", row(id, 1000))?;
+    }
+    storage.delete_from_table("reuse", Some(Predicate::eq("id".to_string(), Value::Integer(1))))?;
+    let root = storage.table_roots["reuse"];
+    storage.insert_into_table("reuse", row(3, 1500))?;
+    assert_eq!(storage.table_roots["reuse"], root);
+    let ids: Vec<Value> = storage.scan_table("reuse", None)?.into_iter().map(|r| r.values[0].clone()).collect();
+    assert_eq!(ids, vec![Value::Integer(0), Value::Integer(2), Value::Integer(3)]);
+
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_databases_with_non_default_page_sizes() -> Result<(), DatabaseError> {
+    for page_size in [512, 8192, 16384, 65536] {
+        let path = create_temp_db_path_with_prefix(&format!("page_size_{}", page_size));
+        let options = StorageManagerOptions::new().with_page_size(page_size);
+        let mut storage = StorageManager::open_with_options(&path, options)?;
+        assert_eq!(storage.page_size(), page_size);
+        storage.create_table("items", "CREATE TABLE items(id INTEGER, body TEXT)")?;
+        // Rows an eighth of a page long split the tree a few times over
+        for id in 0..40 {
+            storage.insert_into_table("items", Row::new(vec![Value::Integer(id), Value::Text("x".repeat(page_size / 8))]))?;
+        }
+        let file_len = fs::metadata(&path)?.len();
+        assert_eq!((file_len - BAMBANG_HEADER_SIZE as u64) % page_size as u64, 0);
+        assert!(file_len > BAMBANG_HEADER_SIZE as u64 + 2 * page_size as u64);
+        drop(storage);
+
+        // An existing file keeps its own page size whatever the options ask for
+        let options = StorageManagerOptions::new().with_page_size(PAGE_SIZE);
+        let storage = StorageManager::open_with_options(&path, options)?;
+        assert_eq!(storage.page_size(), page_size);
+        assert_eq!(storage.scan_table("items", None)?.len(), 40);
+        assert_eq!(storage.count_rows("items", Some(Predicate::lt("id".to_string(), Value::Integer(10))))?, 10);
+        let root = storage.table_roots["items"];
+        let bytes = fs::read(&path)?;
+        let offset = page_offset_with_size(root, page_size) as usize;
+        assert_eq!(Page::from_bytes(&bytes[offset..offset + page_size])?.page_size, page_size);
+
+        drop(storage);
+        let _ = fs::remove_file(&path);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_larger_pages_keep_big_rows_inline() -> Result<(), DatabaseError> {
+    let row = |id: i64| Row::new(vec![Value::Integer(id), Value::Text("x".repeat(3000))]);
+
+    let mut temp_db = TempDatabase::with_prefix("page_size_default_big_rows");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("docs", "CREATE TABLE docs(id INTEGER, body TEXT)")?;
+    assert!(storage.row_storage_cost("docs", &row(1))?.needs_overflow);
+
+    let path = create_temp_db_path_with_prefix("page_size_8k_big_rows");
+    let options = StorageManagerOptions::new().with_page_size(8192);
+    let mut storage = StorageManager::open_with_options(&path, options)?;
+    storage.create_table("docs", "CREATE TABLE docs(id INTEGER, body TEXT)")?;
+    assert!(!storage.row_storage_cost("docs", &row(1))?.needs_overflow);
+    for id in 0..6 {
+        storage.insert_into_table("docs", row(id))?;
+    }
+    let rows = storage.scan_table("docs", None)?;
+    assert_eq!(rows.len(), 6);
+    assert!(rows.iter().all(|r| r.values[1] == Value::Text("x".repeat(3000))));
+
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_overflow_rows_survive_leaf_splits() -> Result<(), DatabaseError> {
+    let body = |id: i64| match id % 5 {
+        0 => "o".repeat(3000),
+        1 => "m".repeat(1900),
+        _ => format!("small {}", id),
+    };
+    let mut temp_db = TempDatabase::with_prefix("overflow_splits");
+    let storage = temp_db
+        .create_storage_manager_with_options(StorageManagerOptions::default().with_paranoid_checks(true))
+        .unwrap();
+    storage.create_table("docs", "CREATE TABLE docs(id INTEGER, body TEXT)")?;
+    // Out of key order, so overflow cells are moved by splits of the leaves holding them
+    for id in (0..200).map(|i| i * 7919 % 200) {
+        storage.insert_into_table("docs", Row::new(vec![Value::Integer(id), Value::Text(body(id))]))?;
+    }
+    assert!(storage.table_page_stats("docs")?.leaf_pages > 1);
+
+    let mut rows = storage.scan_table("docs", None)?;
+    rows.sort_by(|a, b| a.values[0].total_cmp(&b.values[0]));
+    assert_eq!(rows.len(), 200);
+    for (id, row) in (0..200).zip(&rows) {
+        assert_eq!(row.values, vec![Value::Integer(id), Value::Text(body(id))]);
+    }
+    Ok(())
+}
+
+fn create_files_with_overflow_row(storage: &mut StorageManager) -> Result<(), DatabaseError> {
+    storage.create_table("files", "CREATE TABLE files(id INTEGER PRIMARY KEY, name TEXT, data BLOB)")?;
+    for id in 0..10 {
+        let size = if id == 3 { 3000 } else { 10 };
+        let row = Row::new(vec![Value::Integer(id), Value::Text(format!("f{}", id)), Value::Blob(vec![id as u8; size])]);
+        storage.insert_into_table("files", row)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_point_lookup_in_table_with_overflow_row() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("overflow_lookup");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_files_with_overflow_row(storage)?;
+
+    let row = storage.get_by_primary_key("files", &[Value::Integer(3)])?.unwrap();
+    assert_eq!(row.values[2], Value::Blob(vec![3; 3000]));
+    let row = storage.get_by_primary_key("files", &[Value::Integer(7)])?.unwrap();
+    assert_eq!(row.values[1], Value::Text("f7".to_string()));
+    assert!(storage.get_by_primary_key("files", &[Value::Integer(10)])?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_update_in_table_with_overflow_row() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("overflow_update");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_files_with_overflow_row(storage)?;
+
+    let other = Predicate::eq("id".to_string(), Value::Integer(5));
+    assert_eq!(storage.update_table("files", Some(other), &[("name".to_string(), Value::Text("five".to_string()))])?, 1);
+    let overflow = Predicate::eq("id".to_string(), Value::Integer(3));
+    assert_eq!(storage.update_table("files", Some(overflow), &[("name".to_string(), Value::Text("three".to_string()))])?, 1);
+
+    let row = storage.get_by_primary_key("files", &[Value::Integer(5)])?.unwrap();
+    assert_eq!(row.values[1], Value::Text("five".to_string()));
+    let row = storage.get_by_primary_key("files", &[Value::Integer(3)])?.unwrap();
+    assert_eq!(row.values[1], Value::Text("three".to_string()));
+    assert_eq!(row.values[2], Value::Blob(vec![3; 3000]));
+
+    // Rows move onto and off overflow pages as their size changes
+    let shrink = Predicate::eq("id".to_string(), Value::Integer(3));
+    assert_eq!(storage.update_table("files", Some(shrink), &[("data".to_string(), Value::Blob(vec![3; 10]))])?, 1);
+    let grow = Predicate::eq("id".to_string(), Value::Integer(8));
+    assert_eq!(storage.update_table("files", Some(grow), &[("data".to_string(), Value::Blob(vec![8; 3000]))])?, 1);
+    let row = storage.get_by_primary_key("files", &[Value::Integer(3)])?.unwrap();
+    assert_eq!(row.values[2], Value::Blob(vec![3; 10]));
+    let row = storage.get_by_primary_key("files", &[Value::Integer(8)])?.unwrap();
+    assert_eq!(row.values[2], Value::Blob(vec![8; 3000]));
+    assert_eq!(storage.scan_table("files", None)?.len(), 10);
+    Ok(())
+}
+
+#[test]
+fn test_delete_in_table_with_overflow_row() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("overflow_delete");
+    let storage = temp_db.create_storage_manager().unwrap();
+    create_files_with_overflow_row(storage)?;
+
+    let others = Predicate::gt("id".to_string(), Value::Integer(6));
+    assert_eq!(storage.delete_from_table("files", Some(others))?, 3);
+    assert_eq!(storage.scan_table("files", None)?.len(), 7);
+
+    // The overflow page goes back to the freelist with its row
+    let free_before = storage.free_page_ids()?.len();
+    let overflow = Predicate::eq("id".to_string(), Value::Integer(3));
+    assert_eq!(storage.delete_from_table("files", Some(overflow))?, 1);
+    assert_eq!(storage.free_page_ids()?.len(), free_before + 1);
+    assert!(storage.get_by_primary_key("files", &[Value::Integer(3)])?.is_none());
+    assert_eq!(storage.scan_table("files", None)?.len(), 6);
+    Ok(())
+}
+
+#[test]
+fn test_unsupported_page_sizes_are_rejected() {
+    for page_size in [0, 256, 1000, 4095, 131072] {
+        let path = create_temp_db_path_with_prefix(&format!("bad_page_size_{}", page_size));
+        let options = StorageManagerOptions::new().with_page_size(page_size);
+        assert!(matches!(
+            StorageManager::open_with_options(&path, options),
+            Err(DatabaseError::UnsupportedPageSize { size }) if size == page_size
+        ));
+        assert!(!path.exists());
+    }
+}
+
+#[test]
+fn test_table_page_stats_cover_every_page() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("table_page_stats");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT)")?;
+    for id in 0..300 {
+        storage.insert_into_table("users", create_user_row(id, &format!("user_{}", id), "user@example.com"))?;
+    }
+
+    let stats = storage.table_page_stats("users")?;
+    assert_eq!(stats.page_count, storage.iter_table_pages("users")?.page_ids().len());
+    assert!(stats.page_count > 1);
+    assert_eq!(stats.leaf_pages + stats.interior_pages, stats.page_count);
+    // Leaf cells hold the rows; interior cells point at children
+    assert_eq!(stats.active_slots, 300 + stats.page_count - 1);
+    assert_eq!(stats.deleted_slots, 0);
+    assert!(stats.utilization_ratio() > 0.0 && stats.utilization_ratio() <= 1.0);
+    assert_eq!(stats.fragmentation_ratio(), 0.0);
+
+    assert!(matches!(
+        storage.table_page_stats("missing"),
+        Err(DatabaseError::TableNotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_deletes_merge_underfull_leaves() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("delete_merge_leaves");
+    let db_path = temp_db.path.clone();
+    let storage = temp_db
+        .create_storage_manager_with_options(StorageManagerOptions::default().with_paranoid_checks(true))
+        .unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, keep INTEGER, note TEXT)")?;
+    let note = "n".repeat(250);
+    for id in 0..2000 {
+        let keep = (id % 10 == 0) as i64;
+        storage.insert_into_table(
+            "items",
+            Row::new(vec![Value::Integer(id), Value::Integer(keep), Value::Text(note.clone())]),
+        )?;
+    }
+    let leaves_before = storage.table_page_stats("items")?.leaf_pages;
+    let height_before = tree_height(&db_path, storage.table_roots["items"]);
+    assert!(height_before > 2);
+
+    let deleted = storage.delete_from_table("items", Some(Predicate::eq("keep".to_string(), Value::Integer(0))))?;
+    assert_eq!(deleted, 1800);
+    let survivors: Vec<Value> = (0..200).map(|i| Value::Integer(i * 10)).collect();
+    let ids = |rows: Vec<Row>| rows.into_iter().map(|row| row.values[0].clone()).collect::<Vec<_>>();
+    assert_eq!(ids(storage.scan_table("items", None)?), survivors);
+    let mut reversed = survivors.clone();
+    reversed.reverse();
+    let mut scanner = SequentialScanner::new(storage, "items".to_string(), None)?;
+    assert_eq!(ids(scanner.scan_reverse()?), reversed);
+
+    let stats = storage.table_page_stats("items")?;
+    assert!(
+        stats.leaf_pages * 3 <= leaves_before,
+        "{} leaves left of {}",
+        stats.leaf_pages,
+        leaves_before
+    );
+    assert_eq!(stats.deleted_slots, 0);
+    let freed = storage.db_info.header.freelist_pages_count as usize;
+    assert!(freed >= leaves_before - stats.leaf_pages);
+    // Every survivor is still reachable by routing its key from the root
+    let height = tree_height(&db_path, storage.table_roots["items"]);
+    for id in [0, 990, 1990] {
+        let path = storage.trace_key("items", &Value::Integer(id))?;
+        assert_eq!(path.len(), height);
+        let rows = storage.scan_table("items", Some(Predicate::eq("id".to_string(), Value::Integer(id))))?;
+        assert_eq!(rows.len(), 1);
+    }
+
+    // New pages come off the freelist instead of growing the file
+    let page_count = storage.db_info.page_count;
+    for id in 2000..2100 {
+        storage.insert_into_table(
+            "items",
+            Row::new(vec![Value::Integer(id), Value::Integer(1), Value::Text(note.clone())]),
+        )?;
+    }
+    assert_eq!(storage.db_info.page_count, page_count);
+
+    // Emptying all but one leaf collapses the tree to a single leaf root
+    storage.delete_from_table("items", Some(Predicate::gt("id".to_string(), Value::Integer(0))))?;
+    let root_page_id = storage.table_roots["items"];
+    assert_eq!(tree_height(&db_path, root_page_id), 1);
+    temp_db.storage_manager = None;
+
+    let reopened = StorageManager::new(&db_path)?;
+    assert_eq!(reopened.table_roots["items"], root_page_id);
+    assert_eq!(ids(reopened.scan_table("items", None)?), vec![Value::Integer(0)]);
+    Ok(())
+}
+
+const QUIET_CHILD_ENV: &str = "BAMBANG_QUIET_CHILD";
+const QUIET_START: &str = "--- quiet workload start ---";
+const QUIET_END: &str = "--- quiet workload end ---";
+
+/// Create a database, grow a table past a root split and reopen it
+fn logged_workload(options: StorageManagerOptions) -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("quiet");
+    let mut storage = StorageManager::open_with_options(&path, options.clone())?;
+    storage.create_table("notes", "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)")?;
+    for id in 0..200 {
+        storage.insert_into_table("notes", Row::new(vec![Value::Integer(id), Value::Text("n".repeat(100))]))?;
+    }
+    drop(storage);
+    let storage = StorageManager::open_with_options(&path, options)?;
+    assert_eq!(storage.scan_table("notes", None)?.len(), 200);
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+/// Runs the workload with the real stdout when started by
+/// `test_storage_manager_prints_nothing_by_default`, and does nothing otherwise
+#[test]
+fn test_quiet_workload_child() -> Result<(), DatabaseError> {
+    if env::var_os(QUIET_CHILD_ENV).is_none() {
+        return Ok(());
+    }
+    println!("{}", QUIET_START);
+    logged_workload(StorageManagerOptions::default())?;
+    println!("{}", QUIET_END);
+    Ok(())
+}
+
+#[test]
+fn test_storage_manager_prints_nothing_by_default() {
+    // The test harness captures prints from test threads, so the workload runs in a
+    // child copy of this binary whose stdout is not captured
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "storage::storage_manager_test::test_quiet_workload_child", "--nocapture", "--test-threads=1"])
+        .env(QUIET_CHILD_ENV, "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+    let start = stdout.find(QUIET_START).expect("the child should run the workload") + QUIET_START.len();
+    let end = stdout.find(QUIET_END).unwrap();
+    assert_eq!(stdout[start..end].trim(), "");
+}
+
+#[test]
+fn test_log_hook_receives_storage_messages() -> Result<(), DatabaseError> {
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&messages);
+    logged_workload(StorageManagerOptions::default().with_log(move |message| sink.lock().unwrap().push(message.to_string())))?;
+
+    let messages = messages.lock().unwrap();
+    assert!(messages[0].starts_with("Creating new database at path"), "{:?}", messages);
+    assert!(messages.iter().any(|message| message.starts_with("Updated root page for table 'notes'")), "{:?}", messages);
+    assert!(messages.last().unwrap().starts_with("Opening existing database at path"), "{:?}", messages);
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use bambang::{
+    executor::predicate::Predicate,
+    storage::{
+        options::{StorageManagerOptions, SyncMode},
+        page_store::{IoLog, IoOp, assert_ends_with_sync, assert_sync_after_writes},
+        storage_manager::StorageManager,
+    },
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::TempDatabase,
+};
+
+fn item(id: i64) -> Row {
+    Row::new(vec![Value::Integer(id), Value::Text(format!("item_{}_{}", id, "x".repeat(80)))])
+}
+
+/// Open `temp_db` with every write and sync to its file recorded
+fn recorded(temp_db: &mut TempDatabase) -> (&mut StorageManager, IoLog) {
+    let io_log = IoLog::new();
+    let options = StorageManagerOptions::default().with_io_log(io_log.clone());
+    (temp_db.create_storage_manager_with_options(options).unwrap(), io_log)
+}
+
+fn ids(storage: &StorageManager, table: &str) -> Result<Vec<i64>, DatabaseError> {
+    let mut ids: Vec<i64> = storage
+        .scan_table(table, None)?
+        .iter()
+        .map(|row| match row.values[0] {
+            Value::Integer(id) => id,
+            _ => panic!("Expected integer ID"),
+        })
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+#[test]
+fn test_rollback_discards_inserted_rows() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("txn_rollback_insert");
+    let path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+    storage.insert_into_table("items", item(1))?;
+    let file_len = std::fs::metadata(&path)?.len();
+    let page_count = storage.db_info.page_count;
+    let root_page_id = storage.table_roots["items"];
+
+    storage.begin_transaction()?;
+    assert!(storage.in_transaction());
+    // Enough rows to split the root and allocate new pages
+    for id in 2..=200 {
+        storage.insert_into_table("items", item(id))?;
+    }
+    assert_eq!(ids(storage, "items")?.len(), 200);
+    assert_ne!(storage.table_roots["items"], root_page_id);
+    storage.rollback()?;
+
+    assert!(!storage.in_transaction());
+    assert_eq!(ids(storage, "items")?, vec![1]);
+    assert_eq!(storage.table_roots["items"], root_page_id);
+    assert_eq!(storage.db_info.page_count, page_count);
+    assert_eq!(std::fs::metadata(&path)?.len(), file_len);
+
+    // Rowids pick up where they were before the transaction
+    storage.insert_into_table("items", item(2))?;
+    let rows = storage.scan_table("items", Some(Predicate::eq("id".to_string(), Value::Integer(2))))?;
+    assert_eq!(rows[0].row_id, Some(2));
+    temp_db.storage_manager = None;
+
+    let reopened = StorageManager::new(&path)?;
+    assert_eq!(ids(&reopened, "items")?, vec![1, 2]);
+    Ok(())
+}
+
+#[test]
+fn test_commit_keeps_changes_across_reopen() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("txn_commit");
+    let path = temp_db.path.clone();
+    let (storage, io_log) = recorded(&mut temp_db);
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+
+    storage.begin_transaction()?;
+    let begin_seq = io_log.next_seq();
+    storage.insert_batch_into_table("items", (1..=50).map(item).collect())?;
+    storage.delete_from_table("items", Some(Predicate::gt("id".to_string(), Value::Integer(40))))?;
+    assert!(io_log.syncs().iter().all(|seq| *seq < begin_seq), "synced before commit");
+    storage.commit()?;
+    assert!(!storage.in_transaction());
+
+    // Commit is a single sync barrier behind every page the transaction wrote
+    let written = io_log.pages_written_since(begin_seq);
+    assert!(!written.is_empty());
+    let commit_seq = *io_log.syncs().last().unwrap();
+    assert_sync_after_writes(&io_log, &written, commit_seq);
+    assert_ends_with_sync(&io_log, begin_seq);
+    temp_db.storage_manager = None;
+
+    let reopened = StorageManager::new(&path)?;
+    assert_eq!(ids(&reopened, "items")?, (1..=40).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn test_rollback_undoes_ddl_updates_and_deletes() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("txn_rollback_mixed");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+    storage.insert_batch_into_table("items", (1..=100).map(item).collect())?;
+    let before = storage.scan_table("items", None)?;
+    let schema_cookie = storage.schema_cookie();
+
+    storage.begin_transaction()?;
+    storage.create_table("scratch", "CREATE TABLE scratch(id INTEGER)")?;
+    storage.insert_into_table("scratch", Row::new(vec![Value::Integer(7)]))?;
+    storage.update_table(
+        "items",
+        Some(Predicate::lt("id".to_string(), Value::Integer(10))),
+        &[("name".to_string(), Value::Text("renamed".to_string()))],
+    )?;
+    storage.delete_from_table("items", Some(Predicate::gt("id".to_string(), Value::Integer(50))))?;
+    storage.rollback()?;
+
+    assert!(!storage.table_exists("scratch"));
+    assert!(storage.get_table_schema("scratch").is_none());
+    assert_eq!(storage.schema_cookie(), schema_cookie);
+    assert_eq!(storage.scan_table("items", None)?, before);
+
+    // The table name is free again
+    storage.create_table("scratch", "CREATE TABLE scratch(id INTEGER)")?;
+    assert!(ids(storage, "scratch")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_failed_batch_rolled_back_leaves_table_unchanged() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("txn_failed_batch");
+    let storage = temp_db.create_storage_manager().unwrap();
+    storage.create_table("users", "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)")?;
+    storage.insert_into_table("users", item(1))?;
+
+    let mut rows: Vec<Row> = (2..=150).map(item).collect();
+    rows.push(item(75));
+    storage.begin_transaction()?;
+    assert!(matches!(
+        storage.insert_batch_into_table("users", rows),
+        Err(DatabaseError::UniqueConstraintViolation { .. })
+    ));
+    storage.rollback()?;
+    assert_eq!(ids(storage, "users")?, vec![1]);
+    Ok(())
+}
+
+#[test]
+fn test_transaction_misuse_is_rejected() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("txn_misuse");
+    let storage = temp_db.create_storage_manager().unwrap();
+    assert!(matches!(storage.commit(), Err(DatabaseError::ExecutionError { .. })));
+    assert!(matches!(storage.rollback(), Err(DatabaseError::ExecutionError { .. })));
+
+    storage.begin_transaction()?;
+    assert!(matches!(storage.begin_transaction(), Err(DatabaseError::ExecutionError { .. })));
+    storage.commit()?;
+    assert!(matches!(storage.commit(), Err(DatabaseError::ExecutionError { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_close_rolls_back_an_open_transaction() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("txn_close");
+    let path = temp_db.path.clone();
+    let (storage, io_log) = recorded(&mut temp_db);
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+    storage.insert_into_table("items", item(1))?;
+    storage.begin_transaction()?;
+    storage.insert_into_table("items", item(2))?;
+
+    let close_seq = io_log.next_seq();
+    temp_db.storage_manager.take().unwrap().close()?;
+    // The rollback's own barrier comes first, then close syncs once more with nothing
+    // written in between
+    let ops: Vec<IoOp> = io_log.events_since(close_seq).iter().map(|event| event.op).collect();
+    assert!(matches!(ops[ops.len() - 2..], [IoOp::Sync, IoOp::Sync]), "{:?}", ops);
+    let reopened = StorageManager::new(&path)?;
+    assert_eq!(ids(&reopened, "items")?, vec![1]);
+    Ok(())
+}
+
+#[test]
+fn test_rollback_restores_pages_then_header_then_truncates_and_syncs() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("txn_rollback_order");
+    let path = temp_db.path.clone();
+    let (storage, io_log) = recorded(&mut temp_db);
+    storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+    storage.insert_into_table("items", item(1))?;
+    let file_len = std::fs::metadata(&path)?.len();
+    let page_count = storage.db_info.page_count;
+
+    storage.begin_transaction()?;
+    let begin_seq = io_log.next_seq();
+    for id in 2..=200 {
+        storage.insert_into_table("items", item(id))?;
+    }
+    let modified: Vec<u64> = io_log
+        .pages_written_since(begin_seq)
+        .into_iter()
+        .filter(|page_id| *page_id <= page_count)
+        .collect();
+    let rollback_seq = io_log.next_seq();
+    storage.rollback()?;
+
+    // Saved pages go back first, then the original header, then the file shrinks to its
+    // old length, and only then is the result synced
+    let ops: Vec<IoOp> = io_log.events_since(rollback_seq).iter().map(|event| event.op).collect();
+    let restored = ops.iter().take_while(|op| matches!(op, IoOp::PageWrite { .. })).count();
+    assert_eq!(restored, modified.len());
+    let mut restored_pages: Vec<u64> = ops[..restored]
+        .iter()
+        .map(|op| match op {
+            IoOp::PageWrite { page_id, .. } => *page_id,
+            _ => unreachable!(),
+        })
+        .collect();
+    restored_pages.sort();
+    let mut expected = modified.clone();
+    expected.sort();
+    assert_eq!(restored_pages, expected);
+    assert!(matches!(
+        ops[restored..],
+        [IoOp::HeaderWrite { offset: 0, .. }, IoOp::SetLen { len }, IoOp::Sync] if len == file_len
+    ), "{:?}", &ops[restored..]);
+    Ok(())
+}
+
+#[test]
+fn test_sync_mode_decides_how_often_inserts_sync() -> Result<(), DatabaseError> {
+    // Syncs after three single inserts and after a batch of 50 rows
+    for (mode, single_syncs, batch_syncs) in [(SyncMode::Off, 0, 0), (SyncMode::OnBatch, 3, 1), (SyncMode::Always, 3, 50)] {
+        let mut temp_db = TempDatabase::with_prefix("sync_mode");
+        let path = temp_db.path.clone();
+        let io_log = IoLog::new();
+        let options = StorageManagerOptions::default().with_io_log(io_log.clone()).with_sync_mode(mode);
+        let storage = temp_db.create_storage_manager_with_options(options).unwrap();
+        storage.create_table("items", "CREATE TABLE items(id INTEGER, name TEXT)")?;
+
+        let start_seq = io_log.next_seq();
+        for id in 1..=3 {
+            storage.insert_into_table("items", item(id))?;
+        }
+        assert_eq!(io_log.syncs().iter().filter(|seq| **seq >= start_seq).count(), single_syncs, "{:?}", mode);
+
+        let batch_seq = io_log.next_seq();
+        storage.insert_batch_into_table("items", (4..=53).map(item).collect())?;
+        let syncs: Vec<usize> = io_log.syncs().into_iter().filter(|seq| *seq >= batch_seq).collect();
+        assert_eq!(syncs.len(), batch_syncs, "{:?}", mode);
+        if mode == SyncMode::Off {
+            storage.sync()?;
+        }
+        // Whatever the mode, the last sync covers every page the batch wrote, the
+        // schema entry holding the new root and rowid counter included
+        assert_sync_after_writes(&io_log, &io_log.pages_written_since(batch_seq), *io_log.syncs().last().unwrap());
+        assert_ends_with_sync(&io_log, batch_seq);
+
+        // Inside a transaction only the commit syncs
+        storage.begin_transaction()?;
+        let begin_seq = io_log.next_seq();
+        storage.insert_batch_into_table("items", (54..=60).map(item).collect())?;
+        storage.insert_into_table("items", item(61))?;
+        assert!(io_log.syncs().iter().all(|seq| *seq < begin_seq), "{:?} synced before commit", mode);
+        storage.commit()?;
+        assert_ends_with_sync(&io_log, begin_seq);
+        temp_db.storage_manager = None;
+
+        let reopened = StorageManager::new(&path)?;
+        assert_eq!(ids(&reopened, "items")?, (1..=61).collect::<Vec<_>>());
+    }
+    Ok(())
+}
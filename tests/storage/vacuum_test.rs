@@ -0,0 +1,198 @@
+use std::{
+    ffi::OsString,
+    io::{Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use bambang::{
+    executor::predicate::Predicate,
+    storage::{
+        append_log::LogPosition, options::StorageManagerOptions, page_offset, schema::TableKind,
+        storage_manager::StorageManager,
+    },
+    types::{PAGE_SIZE, error::DatabaseError, row::Row, value::Value},
+    utils::mock::TempDatabase,
+};
+
+/// 300 rows with long notes, then every `step`th note cut down to a few bytes, which
+/// leaves the bytes of the old notes on their pages
+fn shrink_notes(storage: &mut StorageManager, step: usize) -> Result<(), DatabaseError> {
+    storage.create_table("notes", "CREATE TABLE notes(id INTEGER PRIMARY KEY, body TEXT)")?;
+    let rows = (0..300)
+        .map(|id| Row::new(vec![Value::Integer(id), Value::Text(format!("{:0>200}", id))]))
+        .collect();
+    storage.insert_batch_into_table("notes", rows)?;
+    storage.update_table(
+        "notes",
+        Some(Predicate::in_list("id".to_string(), (0..300).step_by(step).map(Value::Integer).collect())),
+        &[("body".to_string(), Value::Text("short".to_string()))],
+    )?;
+    Ok(())
+}
+
+fn without_compaction() -> StorageManagerOptions {
+    StorageManagerOptions::default().with_compaction_threshold(None)
+}
+
+#[test]
+fn test_vacuum_reclaims_wasted_space_and_keeps_rows() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("vacuum_reclaims");
+    let storage = temp_db.create_storage_manager_with_options(without_compaction()).unwrap();
+    shrink_notes(storage, 2)?;
+    let rows = storage.scan_table("notes", None)?;
+    let page_count = storage.table_page_stats("notes")?.page_count;
+
+    let report = storage.vacuum_table("notes", false)?;
+    assert!(report.before.wasted_space > 0);
+    assert_eq!(report.after.wasted_space, 0);
+    assert_eq!(report.reclaimed_bytes(), report.before.wasted_space);
+    assert_eq!(report.after.page_count, page_count);
+    assert_eq!(report.after, storage.table_page_stats("notes")?);
+    assert_eq!(storage.scan_table("notes", None)?, rows);
+
+    // Nothing is left to reclaim the second time
+    let again = storage.vacuum_table("notes", false)?;
+    assert_eq!(again.before, again.after);
+    assert_eq!(again.reclaimed_bytes(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_vacuum_merges_underfull_leaves() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("vacuum_merges");
+    let storage = temp_db.create_storage_manager_with_options(without_compaction()).unwrap();
+    shrink_notes(storage, 1)?;
+    let rows = storage.scan_table("notes", None)?;
+
+    let report = storage.vacuum_table("notes", true)?;
+    assert!(report.freed_pages() > 0);
+    assert!(report.after.leaf_pages < report.before.leaf_pages);
+    assert_eq!(report.after.wasted_space, 0);
+    assert_eq!(storage.scan_table("notes", None)?, rows);
+
+    // The vacuumed table survives a reopen and takes new rows
+    let storage = StorageManager::open_with_options(&temp_db.path, without_compaction())?;
+    assert_eq!(storage.scan_table("notes", None)?, rows);
+    let mut storage = storage;
+    storage.insert_into_table("notes", Row::new(vec![Value::Integer(300), Value::Text("new".to_string())]))?;
+    assert_eq!(storage.scan_table("notes", None)?.len(), 301);
+    Ok(())
+}
+
+#[test]
+fn test_compaction_threshold_compacts_pages_on_update() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("vacuum_threshold");
+    let options = StorageManagerOptions::default().with_compaction_threshold(Some(0.1));
+    let storage = temp_db.create_storage_manager_with_options(options).unwrap();
+    shrink_notes(storage, 2)?;
+
+    let stats = storage.table_page_stats("notes")?;
+    assert!(stats.fragmentation_ratio() <= 0.1, "fragmentation {}", stats.fragmentation_ratio());
+    let report = storage.vacuum_table("notes", false)?;
+    assert!(report.reclaimed_bytes() < report.before.cell_area_bytes / 10);
+    Ok(())
+}
+
+/// Tables of every shape, churned by deletes: keyed rows with an index, rows big enough
+/// for overflow pages, an append log trimmed at its head, and a table left empty
+fn churn_database(storage: &mut StorageManager) -> Result<(), DatabaseError> {
+    shrink_notes(storage, 2)?;
+    storage.create_index("notes", "body")?;
+    storage.delete_from_table(
+        "notes",
+        Some(Predicate::in_list("id".to_string(), (0..300).filter(|id| id % 3 != 0).map(Value::Integer).collect())),
+    )?;
+
+    storage.create_table("blobs", "CREATE TABLE blobs(id INTEGER PRIMARY KEY, data BLOB)")?;
+    let blobs = (0..10).map(|id| Row::new(vec![Value::Integer(id), Value::Blob(vec![id as u8; 3000])])).collect();
+    storage.insert_batch_into_table("blobs", blobs)?;
+    storage.delete_from_table("blobs", Some(Predicate::lt("id".to_string(), Value::Integer(4))))?;
+
+    storage.create_table_with_kind("events", "CREATE TABLE events(at TIMESTAMP, body TEXT)", TableKind::AppendLog)?;
+    let events = (0..400).map(|second| Row::new(vec![Value::Timestamp(second * 1000), Value::Text(format!("{:0>100}", second))]));
+    storage.insert_batch_into_table("events", events.collect())?;
+    storage.drop_before("events", LogPosition::Timestamp(200_000))?;
+
+    storage.create_table("empty", "CREATE TABLE empty(id INTEGER)")?;
+    storage.create_sequence("ticket", 1, 1, None, None, false)?;
+    storage.next_val("ticket")?;
+    Ok(())
+}
+
+fn all_rows(storage: &StorageManager) -> Result<Vec<Vec<Row>>, DatabaseError> {
+    ["blobs", "empty", "events", "notes"]
+        .iter()
+        .map(|table| storage.scan_table(table, None))
+        .collect()
+}
+
+fn vacuum_path(db_path: &Path) -> PathBuf {
+    let mut path = OsString::from(db_path);
+    path.push("-vacuum");
+    PathBuf::from(path)
+}
+
+#[test]
+fn test_database_vacuum_rewrites_file_compactly() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("vacuum_database");
+    let path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    churn_database(storage)?;
+    let rows = all_rows(storage)?;
+    let indexed = storage.lookup_by_index("notes", "body", &Value::Text("short".to_string()))?;
+    assert!(!storage.free_page_ids()?.is_empty());
+
+    let report = storage.vacuum()?;
+    assert!(report.pages_after < report.pages_before);
+    assert_eq!(report.reclaimed_bytes(), report.freed_pages() * PAGE_SIZE as u64);
+    assert_eq!(std::fs::metadata(&path)?.len(), report.bytes_after);
+    assert!(!vacuum_path(&path).exists());
+    assert!(storage.free_page_ids()?.is_empty());
+    assert_eq!(all_rows(storage)?, rows);
+    assert_eq!(storage.lookup_by_index("notes", "body", &Value::Text("short".to_string()))?, indexed);
+    storage.check_append_log("events")?;
+
+    // The rebuilt file opens on its own and keeps every table's rowids and counters
+    let mut storage = StorageManager::new(&path)?;
+    assert_eq!(all_rows(&storage)?, rows);
+    assert!(storage.check_page_consistency()?.is_empty());
+    let cache_size = StorageManagerOptions::default().sequence_cache_size as i64;
+    assert_eq!(storage.next_val("ticket")?, 1 + cache_size);
+    storage.insert_into_table("notes", Row::new(vec![Value::Integer(300), Value::Text("short".to_string())]))?;
+    assert_eq!(storage.lookup_by_index("notes", "body", &Value::Text("short".to_string()))?.len(), indexed.len() + 1);
+    storage.insert_into_table("events", Row::new(vec![Value::Timestamp(400_000), Value::Text("late".to_string())]))?;
+    let last = storage.scan_table("events", None)?.pop().unwrap();
+    assert_eq!(last.row_id, Some(401));
+
+    // A second vacuum has nothing left to reclaim
+    let again = storage.vacuum()?;
+    assert_eq!(again.pages_after, again.pages_before);
+    Ok(())
+}
+
+#[test]
+fn test_failed_database_vacuum_leaves_original_file() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("vacuum_database_failure");
+    let path = temp_db.path.clone();
+    let storage = temp_db.create_storage_manager().unwrap();
+    churn_database(storage)?;
+    let root_page_id = storage.table_roots["blobs"];
+
+    storage.begin_transaction()?;
+    assert!(matches!(storage.vacuum(), Err(DatabaseError::ExecutionError { .. })));
+    storage.rollback()?;
+
+    // A leftover copy from an interrupted vacuum is discarded, never opened
+    std::fs::write(vacuum_path(&path), b"not a database")?;
+    let mut file = std::fs::OpenOptions::new().write(true).open(&path)?;
+    file.seek(SeekFrom::Start(page_offset(root_page_id)))?;
+    file.write_all(&[0xAB; 64])?;
+    drop(file);
+    let original = std::fs::read(&path)?;
+
+    assert!(storage.vacuum().is_err());
+    assert_eq!(std::fs::read(&path)?, original);
+    assert!(!vacuum_path(&path).exists());
+    assert_eq!(storage.scan_table("notes", None)?.len(), 100);
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use bambang::{
+    executor::statement::execute_statement,
+    storage::{
+        options::{OpenMode, StorageManagerOptions},
+        page_store::{IoLog, IoOp},
+        schema::{ColumnSchema, MAX_COLUMNS, TableSchema},
+        storage_manager::StorageManager,
+    },
+    types::{
+        error::DatabaseError,
+        row::Row,
+        value::{DataType, Value},
+    },
+    utils::mock::create_temp_db_path_with_prefix,
+};
+
+const WIDE_COLUMNS: usize = 1000;
+/// A table's CREATE TABLE text and each of its rows have to stay inline in a leaf, under
+/// half a page, which for this many columns takes the largest page size
+const WIDE_PAGE_SIZE: usize = 65536;
+
+/// `CREATE TABLE` with an INTEGER key followed by alternating TEXT and INTEGER columns
+fn wide_table_sql(table: &str, columns: usize) -> String {
+    let definitions: Vec<String> = (0..columns)
+        .map(|i| match i {
+            0 => "c0 INTEGER PRIMARY KEY".to_string(),
+            _ if i % 2 == 1 => format!("c{} TEXT", i),
+            _ => format!("c{} INTEGER NOT NULL", i),
+        })
+        .collect();
+    format!("CREATE TABLE {} ({})", table, definitions.join(", "))
+}
+
+fn wide_row(id: i64, columns: usize) -> Row {
+    Row::new(
+        (0..columns)
+            .map(|i| match i {
+                0 => Value::Integer(id),
+                _ if i % 2 == 1 => Value::Text(format!("v{}", i)),
+                _ => Value::Integer(i as i64),
+            })
+            .collect(),
+    )
+}
+
+#[test]
+fn test_wide_tables_create_validate_and_reopen() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("wide_table");
+    {
+        let options = StorageManagerOptions::new().with_page_size(WIDE_PAGE_SIZE);
+        let mut storage = StorageManager::open_with_options(&path, options)?;
+        let started = Instant::now();
+        execute_statement(&mut storage, &wide_table_sql("wide", WIDE_COLUMNS))?;
+        assert!(started.elapsed() < Duration::from_secs(10), "creation took {:?}", started.elapsed());
+        assert_eq!(storage.get_table_schema("wide").unwrap().columns.len(), WIDE_COLUMNS);
+
+        let row = wide_row(1, WIDE_COLUMNS);
+        storage.validate_row("wide", &row)?;
+        storage.insert_into_table("wide", row.clone())?;
+        assert_eq!(storage.scan_table("wide", None)?[0].values, row.values);
+        let mut missing = wide_row(2, WIDE_COLUMNS);
+        missing.values[WIDE_COLUMNS - 2] = Value::Null;
+        assert!(storage.validate_row("wide", &missing).is_err());
+    }
+
+    for mode in [OpenMode::Eager, OpenMode::Lazy] {
+        let storage = StorageManager::open_with_options(&path, StorageManagerOptions::new().with_open_mode(mode))?;
+        let schema = storage.load_table_schema("wide")?.unwrap();
+        assert_eq!(schema.column_names(), (0..WIDE_COLUMNS).map(|i| format!("c{}", i)).collect::<Vec<_>>());
+        assert_eq!(storage.scan_table("wide", None)?[0].values, wide_row(1, WIDE_COLUMNS).values);
+    }
+
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_widest_schema_validates_in_linear_time() -> Result<(), DatabaseError> {
+    let columns = (0..MAX_COLUMNS)
+        .map(|i| match i {
+            0 => ColumnSchema::new("c0".to_string(), DataType::Integer, i).primary_key(),
+            _ if i % 2 == 1 => ColumnSchema::new(format!("c{}", i), DataType::Text, i),
+            _ => ColumnSchema::new(format!("c{}", i), DataType::Integer, i).not_null(),
+        })
+        .collect();
+    let schema = TableSchema::new("widest".to_string(), columns, 2, wide_table_sql("widest", MAX_COLUMNS));
+    let row = wide_row(1, MAX_COLUMNS);
+
+    // A column lookup per value would take seconds for this many rows
+    let started = Instant::now();
+    for _ in 0..500 {
+        schema.validate_row(&row)?;
+    }
+    assert!(started.elapsed() < Duration::from_secs(2), "validation took {:?}", started.elapsed());
+    Ok(())
+}
+
+#[test]
+fn test_wide_table_beyond_the_page_size_fails_cleanly() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("wide_table_small_pages");
+    let mut storage = StorageManager::new(&path)?;
+    let page_count = storage.db_info.page_count;
+    let result = execute_statement(&mut storage, &wide_table_sql("wide", WIDE_COLUMNS));
+    assert!(matches!(result, Err(DatabaseError::RowTooLarge { .. })), "{:?}", result.map(|_| ()));
+    // Only the table's root page was allocated before its entry was refused
+    assert_eq!(storage.db_info.page_count, page_count + 1);
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_too_many_columns_is_rejected_before_anything_is_written() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("too_wide_table");
+    let mut storage = StorageManager::new(&path)?;
+    let page_count = storage.db_info.page_count;
+    let sql = wide_table_sql("too_wide", MAX_COLUMNS + 1);
+
+    let result = execute_statement(&mut storage, &sql);
+    assert!(
+        matches!(result, Err(DatabaseError::TooManyColumns { count, max }) if count == MAX_COLUMNS + 1 && max == MAX_COLUMNS),
+        "{:?}",
+        result.map(|_| ())
+    );
+    assert!(matches!(storage.create_table("too_wide", &sql), Err(DatabaseError::TooManyColumns { .. })));
+    assert!(!storage.table_exists("too_wide"));
+    assert_eq!(storage.db_info.page_count, page_count);
+
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+#[test]
+fn test_wide_table_schema_rows_write_each_schema_page_once() -> Result<(), DatabaseError> {
+    let path = create_temp_db_path_with_prefix("wide_table_writes");
+    let io_log = IoLog::new();
+    let mut storage = StorageManager::open_with_options(&path, StorageManagerOptions::new().with_io_log(io_log.clone()))?;
+    let first_seq = io_log.next_seq();
+    execute_statement(&mut storage, &wide_table_sql("wide", 20))?;
+
+    // The table row and its 20 column rows share a leaf, which is written once rather than
+    // once per row, alongside the new table's root page
+    let page_writes: Vec<_> = io_log
+        .events_since(first_seq)
+        .into_iter()
+        .filter_map(|event| match event.op {
+            IoOp::PageWrite { page_id, .. } => Some(page_id),
+            _ => None,
+        })
+        .collect();
+    let root_page_id = storage.table_roots["wide"];
+    let schema_writes = page_writes.iter().filter(|&&page_id| page_id != root_page_id).count();
+    assert_eq!(schema_writes, 1, "{:?}", page_writes);
+    assert_eq!(storage.get_table_schema("wide").unwrap().columns.len(), 20);
+    drop(storage);
+
+    let storage = StorageManager::new(&path)?;
+    assert_eq!(storage.get_table_schema("wide").unwrap().column_names()[19], "c19");
+    drop(storage);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use bambang::{
+    executor::predicate::Predicate,
+    storage::{storage_manager::StorageManager, workload::MIN_SCANS_FOR_RECOMMENDATION},
+    types::{error::DatabaseError, row::Row, value::Value},
+    utils::mock::TempDatabase,
+};
+
+const WINDOW: Duration = Duration::from_secs(60 * 60);
+
+fn seed_users(storage: &mut StorageManager) -> Result<(), DatabaseError> {
+    storage.create_table("users", "CREATE TABLE users(id INTEGER, name TEXT, email TEXT)")?;
+    let rows = (1..=300)
+        .map(|id| {
+            Row::new(vec![
+                Value::Integer(id),
+                Value::Text(format!("user_{}", id % 3)),
+                Value::Text(format!("user{}@example.com", id)),
+            ])
+        })
+        .collect();
+    storage.insert_batch_into_table("users", rows)?;
+    Ok(())
+}
+
+fn lookup_email(storage: &StorageManager, id: i64) -> Result<usize, DatabaseError> {
+    let predicate = Predicate::eq("email".to_string(), Value::Text(format!("user{}@example.com", id)));
+    Ok(storage.scan_table("users", Some(predicate))?.len())
+}
+
+#[test]
+fn test_repeated_selective_scans_recommend_an_index() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("workload_email");
+    let storage = temp_db.create_storage_manager().unwrap();
+    seed_users(storage)?;
+    assert!(storage.recommend_indexes(WINDOW).is_empty());
+
+    for id in 1..=20 {
+        assert_eq!(lookup_email(storage, id)?, 1);
+    }
+    // Lookups on the key column and unselective filters are left alone
+    for id in 1..=20 {
+        storage.count_rows("users", Some(Predicate::eq("id".to_string(), Value::Integer(id))))?;
+        storage.count_rows("users", Some(Predicate::is_not_null("name".to_string())))?;
+    }
+
+    let recommendations = storage.recommend_indexes(WINDOW);
+    assert_eq!(recommendations.len(), 1);
+    let top = &recommendations[0];
+    assert_eq!(top.table, "users");
+    assert_eq!(top.columns, vec!["email".to_string()]);
+    assert_eq!(top.create_statement, "CREATE INDEX idx_users_email ON users(email)");
+    assert!(top.estimated_pages_saved_per_day > 0.0);
+
+    let scans = storage.workload_log().recent(WINDOW);
+    assert_eq!(scans.len(), 60);
+    assert_eq!(scans[0].rows_examined, 300);
+    assert_eq!(scans[0].rows_returned, 1);
+    Ok(())
+}
+
+#[test]
+fn test_recommendations_need_enough_scans_and_rank_by_pages_saved() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("workload_ranking");
+    let storage = temp_db.create_storage_manager().unwrap();
+    seed_users(storage)?;
+
+    for id in 1..MIN_SCANS_FOR_RECOMMENDATION as i64 {
+        lookup_email(storage, id)?;
+    }
+    assert!(storage.recommend_indexes(WINDOW).is_empty());
+
+    lookup_email(storage, 1)?;
+    let name = Predicate::eq("name".to_string(), Value::Text("nobody".to_string()));
+    for _ in 0..MIN_SCANS_FOR_RECOMMENDATION * 2 {
+        storage.scan_table("users", Some(name.clone()))?;
+    }
+    let columns: Vec<Vec<String>> = storage
+        .recommend_indexes(WINDOW)
+        .into_iter()
+        .map(|recommendation| recommendation.columns)
+        .collect();
+    assert_eq!(columns, vec![vec!["name".to_string()], vec!["email".to_string()]]);
+
+    storage.workload_log().clear();
+    assert!(storage.recommend_indexes(WINDOW).is_empty());
+    Ok(())
+}
@@ -1,7 +1,9 @@
-use std::time::Instant;
+#![allow(unused_variables, clippy::bool_assert_comparison)]
+
+use std::{io::Cursor, time::Instant};
 
 use bambang::types::{
-    error::DatabaseError, page::{Page, PageType}, PAGE_HEADER_SIZE, PAGE_SIZE, SLOT_DIRECTORY_ENTRY_SIZE
+    error::DatabaseError, page::{OVERFLOW_PAGE_CAPACITY, OVERFLOW_THRESHOLD, Page, PageType, SlotPos, StorageCost}, row::Row, value::Value, PAGE_HEADER_SIZE, PAGE_SIZE, SLOT_DIRECTORY_ENTRY_SIZE
 };
 
 // Test utilities
@@ -13,6 +15,15 @@ fn create_sample_row_data(id: u32) -> Vec<u8> {
     format!("row_data_{:06}", id).into_bytes()
 }
 
+/// Run the paranoid-mode checks a B+ tree applies before writing `page`
+fn assert_invariants(page: &Page) {
+    let mut page = page.clone();
+    page.update_checksum();
+    if let Err(error) = page.validate_invariants() {
+        panic!("{}", error);
+    }
+}
+
 #[test]
 fn test_page_creation_and_basic_properties() {
     let page = Page::new(1, PageType::LeafTable);
@@ -20,7 +31,7 @@ fn test_page_creation_and_basic_properties() {
     assert_eq!(page.page_id, 1);
     assert_eq!(page.page_type, PageType::LeafTable);
     assert_eq!(page.cell_count, 0);
-    assert_eq!(page.free_space_offset, PAGE_SIZE as u16);
+    assert_eq!(page.free_space_offset, PAGE_SIZE as u32);
     assert!(!page.is_metadata_only());
     assert!(page.verify_checksum());
     assert_eq!(page.slot_directory.slots.len(), 0);
@@ -29,7 +40,7 @@ fn test_page_creation_and_basic_properties() {
 #[test]
 fn test_metadata_only_mode() {
     // Create a minimal header for testing
-    let mut header_bytes = vec![0u8; PAGE_HEADER_SIZE + 16]; // Header + space for 4 slots
+    let mut header_bytes = vec![0u8; PAGE_HEADER_SIZE + 4 * SLOT_DIRECTORY_ENTRY_SIZE]; // Header + space for 4 slots
 
     // Manually construct header: page_id=42, page_type=13 (LeafTable), no parent/next, cell_count=2
     header_bytes[0..8].copy_from_slice(&42u64.to_le_bytes());
@@ -40,15 +51,17 @@ fn test_metadata_only_mode() {
     header_bytes[27..29].copy_from_slice(&(PAGE_SIZE as u16 - 100).to_le_bytes()); // free_space_offset
     header_bytes[29..33].copy_from_slice(&0u32.to_le_bytes()); // checksum (we'll ignore for this test)
 
-    // Add slot directory entries
+    // Add slot directory entries: offset, length, flags (0b10 = has row_id), row_id
     let slot_offset = PAGE_HEADER_SIZE;
     header_bytes[slot_offset..slot_offset + 2].copy_from_slice(&1000u16.to_le_bytes()); // slot 0 offset
     header_bytes[slot_offset + 2..slot_offset + 4].copy_from_slice(&50u16.to_le_bytes()); // slot 0 length
-    header_bytes[slot_offset + 4..slot_offset + 6].copy_from_slice(&1050u16.to_le_bytes()); // slot 1 offset
-    header_bytes[slot_offset + 6..slot_offset + 8].copy_from_slice(&30u16.to_le_bytes()); // slot 1 length
+    header_bytes[slot_offset + 4] = 0b10; // slot 0 flags
+    header_bytes[slot_offset + 5..slot_offset + 13].copy_from_slice(&7u64.to_le_bytes()); // slot 0 row_id
+    header_bytes[slot_offset + 13..slot_offset + 15].copy_from_slice(&1050u16.to_le_bytes()); // slot 1 offset
+    header_bytes[slot_offset + 15..slot_offset + 17].copy_from_slice(&30u16.to_le_bytes()); // slot 1 length
 
     let metadata_size = Page::calculate_metadata_size(&header_bytes).unwrap();
-    assert_eq!(metadata_size, PAGE_HEADER_SIZE + 8); // 2 slots * 4 bytes each
+    assert_eq!(metadata_size, PAGE_HEADER_SIZE + 26); // 2 slots * 13 bytes each
 
     let page = Page::from_header_bytes(&header_bytes[..metadata_size]).unwrap();
 
@@ -59,8 +72,10 @@ fn test_metadata_only_mode() {
     assert_eq!(page.slot_directory.slots.len(), 2);
     assert_eq!(page.slot_directory.slots[0].offset, 1000);
     assert_eq!(page.slot_directory.slots[0].length, 50);
+    assert_eq!(page.slot_directory.slots[0].row_id, Some(7));
     assert_eq!(page.slot_directory.slots[1].offset, 1050);
     assert_eq!(page.slot_directory.slots[1].length, 30);
+    assert_eq!(page.slot_directory.slots[1].row_id, None);
 
     println!("Metadata size: {}", metadata_size);
     println!("Memory footprint: {}", page.memory_footprint());
@@ -78,6 +93,7 @@ fn test_cell_insertion_and_retrieval() {
 
     // Insert first cell
     let slot1 = page.insert_cell(&test_data1, Some(1)).unwrap();
+    assert_invariants(&page);
     assert_eq!(slot1, 0);
     assert_eq!(page.cell_count, 1);
     assert_eq!(page.active_cell_count(), 1);
@@ -85,6 +101,7 @@ fn test_cell_insertion_and_retrieval() {
 
     // Insert second cell
     let slot2 = page.insert_cell(&test_data2, Some(2)).unwrap();
+    assert_invariants(&page);
     assert_eq!(slot2, 1);
     assert_eq!(page.cell_count, 2);
     assert_eq!(page.active_cell_count(), 2);
@@ -115,10 +132,12 @@ fn test_cell_deletion() {
     let test_data = create_sample_row_data(1);
 
     let slot = page.insert_cell(&test_data, Some(1)).unwrap();
+    assert_invariants(&page);
     assert_eq!(page.active_cell_count(), 1);
 
     // Delete the cell
     page.delete_cell(slot).unwrap();
+    assert_invariants(&page);
     assert_eq!(page.active_cell_count(), 0);
     assert!(page.is_slot_deleted(slot));
 
@@ -138,10 +157,12 @@ fn test_cell_update_same_size() {
     let new_data = create_test_data(50); // Same size
 
     let slot = page.insert_cell(&original_data, Some(1)).unwrap();
+    assert_invariants(&page);
     let original_offset = page.slot_directory.slots[slot].offset;
 
     // Update with same size should work in-place
     page.update_cell(slot, &new_data, Some(2)).unwrap();
+    assert_invariants(&page);
 
     assert_eq!(page.slot_directory.slots[slot].offset, original_offset);
     assert_eq!(page.slot_directory.slots[slot].length, 50);
@@ -158,9 +179,11 @@ fn test_cell_update_smaller_size() {
     let new_data = create_test_data(50); // Smaller
 
     let slot = page.insert_cell(&original_data, Some(1)).unwrap();
+    assert_invariants(&page);
     let original_offset = page.slot_directory.slots[slot].offset;
 
     page.update_cell(slot, &new_data, Some(2)).unwrap();
+    assert_invariants(&page);
 
     assert_eq!(page.slot_directory.slots[slot].offset, original_offset);
     assert_eq!(page.slot_directory.slots[slot].length, 50);
@@ -177,9 +200,11 @@ fn test_cell_update_larger_size() {
     let new_data = create_test_data(100); // Larger
 
     let slot = page.insert_cell(&original_data, Some(1)).unwrap();
+    assert_invariants(&page);
 
     // This should trigger compaction and relocation
     page.update_cell(slot, &new_data, Some(2)).unwrap();
+    assert_invariants(&page);
 
     assert_eq!(page.slot_directory.slots[slot].length, 100);
     assert_eq!(page.slot_directory.slots[slot].row_id, Some(2));
@@ -198,16 +223,21 @@ fn test_page_compaction() {
     let data3 = create_test_data(100);
 
     let slot1 = page.insert_cell(&data1, Some(1)).unwrap();
+    assert_invariants(&page);
     let slot2 = page.insert_cell(&data2, Some(2)).unwrap();
+    assert_invariants(&page);
     let slot3 = page.insert_cell(&data3, Some(3)).unwrap();
+    assert_invariants(&page);
 
     let initial_free_space = page.available_space();
 
     // Delete middle cell to create fragmentation
     page.delete_cell(slot2).unwrap();
+    assert_invariants(&page);
 
     // Force compaction
     page.compact().unwrap();
+    assert_invariants(&page);
 
     // Should have more free space and fewer slots
     assert!(page.available_space() > initial_free_space);
@@ -232,8 +262,11 @@ fn test_fragmentation_calculation() {
     // Insert and delete to create fragmentation
     let data = create_test_data(100);
     let slot1 = page.insert_cell(&data, Some(1)).unwrap();
+    assert_invariants(&page);
     let slot2 = page.insert_cell(&data, Some(2)).unwrap();
+    assert_invariants(&page);
     let slot3 = page.insert_cell(&data, Some(3)).unwrap();
+    assert_invariants(&page);
 
     // Should still be low fragmentation
     let frag_before = page.get_fragmentation_ratio();
@@ -241,6 +274,7 @@ fn test_fragmentation_calculation() {
 
     // Delete middle cell
     page.delete_cell(slot2).unwrap();
+    assert_invariants(&page);
 
     // Should have increased fragmentation
     let frag_after = page.get_fragmentation_ratio();
@@ -259,8 +293,11 @@ fn test_page_statistics() {
     // Add some cells
     let data = create_test_data(100);
     page.insert_cell(&data, Some(1)).unwrap();
+    assert_invariants(&page);
     page.insert_cell(&data, Some(2)).unwrap();
+    assert_invariants(&page);
     page.insert_cell(&data, Some(3)).unwrap();
+    assert_invariants(&page);
 
     let stats_full = page.get_page_stats();
     assert_eq!(stats_full.total_slots, 3);
@@ -270,6 +307,7 @@ fn test_page_statistics() {
 
     // Delete one cell
     page.delete_cell(1).unwrap();
+    assert_invariants(&page);
 
     let stats_deleted = page.get_page_stats();
     assert_eq!(stats_deleted.total_slots, 3);
@@ -288,7 +326,9 @@ fn test_serialization_roundtrip() {
     let data1 = create_sample_row_data(1);
     let data2 = create_sample_row_data(2);
     page.insert_cell(&data1, Some(1)).unwrap();
+    assert_invariants(&page);
     page.insert_cell(&data2, Some(2)).unwrap();
+    assert_invariants(&page);
 
     // Serialize
     let bytes = page.to_bytes().unwrap();
@@ -317,6 +357,30 @@ fn test_serialization_roundtrip() {
     assert!(reconstructed.verify_checksum());
 }
 
+#[test]
+fn test_slot_entries_keep_row_id_and_overflow_flag_across_reload() {
+    let mut page = Page::new(7, PageType::LeafTable);
+    // Exactly as long as an overflow pointer, but an ordinary cell
+    let twelve_bytes = create_test_data(12);
+    page.insert_cell(&twelve_bytes, Some(41)).unwrap();
+    let large_data = create_test_data(PAGE_SIZE / 2);
+    page.insert_cell_with_overflow(&large_data, Some(42), Some(100)).unwrap();
+    page.insert_cell(&create_test_data(20), None).unwrap();
+    assert_invariants(&page);
+
+    let reloaded = Page::from_bytes(&page.to_bytes().unwrap()).unwrap();
+    assert_eq!(reloaded.slot_directory, page.slot_directory);
+    assert!(!reloaded.slot_directory.slots[0].is_overflow);
+    assert_eq!(reloaded.get_cell(0).unwrap(), twelve_bytes);
+    assert_eq!(reloaded.slot_directory.slots[1].row_id, Some(42));
+    assert_eq!(
+        reloaded.slot_directory.slots[1].overflow_pointer.as_ref().map(|pointer| pointer.page_id),
+        Some(100)
+    );
+    assert_eq!(reloaded.overflow_pages, vec![100]);
+    assert_eq!(reloaded.slot_directory.slots[2].row_id, None);
+}
+
 #[test]
 fn test_page_capacity_limits() {
     let mut page = Page::new(1, PageType::LeafTable);
@@ -394,15 +458,77 @@ fn test_error_conditions() {
 
     assert!(matches!(
         meta_page.insert_cell(&[1, 2, 3], Some(1)),
-        Err(DatabaseError::SerializationError { .. })
+        Err(DatabaseError::MetadataOnlyPage { ref operation, .. }) if operation == "insertion"
     ));
 
     assert!(matches!(
         meta_page.to_bytes(),
-        Err(DatabaseError::SerializationError { .. })
+        Err(DatabaseError::MetadataOnlyPage { ref operation, .. }) if operation == "serialization"
     ));
 }
 
+#[test]
+fn test_metadata_only_page_upgrades_for_mutation() {
+    // Build a page as a writer would have left it on disk
+    let mut page = Page::new(7, PageType::LeafTable);
+    for i in 0..5 {
+        page.insert_cell(&create_sample_row_data(i), Some(i as u64)).unwrap();
+    }
+    let disk_bytes = page.to_bytes().unwrap();
+
+    // A scanner reads only the header and slot directory
+    let metadata_size = Page::calculate_metadata_size(&disk_bytes[..PAGE_HEADER_SIZE]).unwrap();
+    let mut meta_page = Page::from_header_bytes(&disk_bytes[..metadata_size]).unwrap();
+    assert!(meta_page.is_metadata_only());
+    assert_eq!(meta_page.active_cell_count(), 5);
+    for (result, operation) in [
+        (meta_page.delete_cell(1), "deletion"),
+        (meta_page.update_cell(0, &[9], Some(0)), "update"),
+        (meta_page.compact(), "compaction"),
+    ] {
+        match result {
+            Err(DatabaseError::MetadataOnlyPage { page_id, operation: actual }) => {
+                assert_eq!(page_id, 7);
+                assert_eq!(actual, operation);
+            }
+            other => panic!("Expected MetadataOnlyPage for {}, got {:?}", operation, other),
+        }
+    }
+
+    // Turning the scan into a mutation upgrades the page on demand
+    let mut requested = None;
+    meta_page
+        .ensure_full_data(|page_id| {
+            requested = Some(page_id);
+            Ok(disk_bytes.clone())
+        })
+        .unwrap();
+    assert_eq!(requested, Some(7));
+    assert!(!meta_page.is_metadata_only());
+    assert_eq!(meta_page.get_cell(2), Some(create_sample_row_data(2).as_slice()));
+
+    meta_page.delete_cell(1).unwrap();
+    meta_page.insert_cell(&create_sample_row_data(42), Some(42)).unwrap();
+    let reloaded = Page::from_bytes(&meta_page.to_bytes().unwrap()).unwrap();
+    assert_eq!(reloaded.active_cell_count(), 5);
+
+    // Already-full pages never call the loader
+    meta_page
+        .ensure_full_data(|_| panic!("loader must not run for a full page"))
+        .unwrap();
+
+    // Loader failures propagate and leave the page metadata-only
+    let mut other = Page::from_header_bytes(&disk_bytes[..metadata_size]).unwrap();
+    let result = other.ensure_full_data(|page_id| {
+        Err(DatabaseError::CorruptedPage {
+            page_id,
+            reason: "unreadable".to_string(),
+        })
+    });
+    assert!(matches!(result, Err(DatabaseError::CorruptedPage { page_id: 7, .. })));
+    assert!(other.is_metadata_only());
+}
+
 #[test]
 fn test_checksum_validation() {
     let mut page = Page::new(1, PageType::LeafTable);
@@ -569,3 +695,482 @@ fn bench_page_operations() {
     assert!(retrieve_duration.as_micros() < 1000); // Should be very fast
     assert!(metadata_duration.as_millis() < 10); // Should be very fast
 }
+
+/// Rewrite the header of `bytes` from `page`, as a buggy writer would have left it
+fn write_buggy_header(bytes: &[u8], mut page: Page) -> Vec<u8> {
+    page.update_checksum();
+    let mut bytes = bytes.to_vec();
+    page.write_header(&mut Cursor::new(&mut bytes));
+    bytes
+}
+
+fn page_with_cells(count: u32) -> Page {
+    let mut page = Page::new(3, PageType::LeafTable);
+    for i in 0..count {
+        page.insert_cell(&create_sample_row_data(i), Some(i as u64)).unwrap();
+    }
+    page
+}
+
+#[test]
+fn test_load_clamps_free_space_offset_over_live_cells() {
+    let page = page_with_cells(5);
+    let bytes = page.to_bytes().unwrap();
+    let lowest_cell = page.free_space_offset;
+
+    let mut buggy = page.clone();
+    buggy.free_space_offset = lowest_cell + 40;
+    let mut loaded = Page::from_bytes(&write_buggy_header(&bytes, buggy)).unwrap();
+
+    assert_eq!(loaded.free_space_offset, lowest_cell);
+    assert!(loaded.is_dirty);
+    assert_eq!(loaded.reconciliation_warnings.len(), 1);
+    assert!(loaded.reconciliation_warnings[0].contains("free_space_offset"));
+
+    // New cells land below the existing ones instead of overwriting them
+    loaded.insert_cell(&create_sample_row_data(99), Some(99)).unwrap();
+    for i in 0..5 {
+        assert_eq!(loaded.get_cell(i as usize), Some(create_sample_row_data(i).as_slice()));
+    }
+    assert_eq!(loaded.get_cell(5), Some(create_sample_row_data(99).as_slice()));
+
+    // Once written back the page loads cleanly
+    let reloaded = Page::from_bytes(&loaded.to_bytes().unwrap()).unwrap();
+    assert!(reloaded.reconciliation_warnings.is_empty());
+    assert_eq!(reloaded.active_cell_count(), 6);
+}
+
+#[test]
+fn test_load_recovers_slots_beyond_cell_count() {
+    let mut page = page_with_cells(5);
+    page.delete_cell(3).unwrap();
+    let bytes = page.to_bytes().unwrap();
+
+    let mut buggy = page.clone();
+    buggy.slot_directory.slots.truncate(2);
+    buggy.cell_count = 2;
+    let mut loaded = Page::from_bytes(&write_buggy_header(&bytes, buggy)).unwrap();
+
+    assert_eq!(loaded.cell_count, 5);
+    assert_eq!(loaded.slot_directory.slots.len(), 5);
+    assert!(loaded.is_dirty);
+    assert!(loaded.reconciliation_warnings[0].contains("cell_count 2"));
+    assert!(loaded.get_cell(3).is_none());
+    for i in [0, 1, 2, 4] {
+        assert_eq!(loaded.get_cell(i as usize), Some(create_sample_row_data(i).as_slice()));
+    }
+
+    // The recovered page is no longer known to be in key order, so the new cell fills
+    // the deleted slot and the recovered cell after it is left alone
+    let new_slot = loaded.insert_cell(&create_sample_row_data(7), Some(7)).unwrap();
+    assert_eq!(new_slot, 3);
+    assert_eq!(loaded.slot_directory.slots.len(), 5);
+    assert_eq!(loaded.get_cell(4), Some(create_sample_row_data(4).as_slice()));
+}
+
+/// Bytes of `page` after `corrupt` rewrote its slots, with a checksum that still matches,
+/// as a torn write that updated the checksum last would leave them
+fn bytes_with_corrupted_slots(page: &Page, corrupt: impl FnOnce(&mut Page)) -> Vec<u8> {
+    let mut page = page.clone();
+    corrupt(&mut page);
+    page.update_checksum();
+    page.to_bytes().unwrap()
+}
+
+#[test]
+fn test_load_rejects_overlapping_slots() {
+    let page = page_with_cells(5);
+    assert!(page.validate_structure().is_ok());
+    let cell_len = create_sample_row_data(0).len() as u16;
+
+    // Slot 1 shifted up into slot 0
+    let bytes = bytes_with_corrupted_slots(&page, |page| page.slot_directory.slots[1].offset += 5);
+    match Page::from_bytes(&bytes) {
+        Err(DatabaseError::CorruptedPage { page_id: 3, reason }) => {
+            assert!(reason.contains("Slots 1 and 0 overlap"), "{}", reason)
+        }
+        other => panic!("Expected a corrupted page, got {:?}", other),
+    }
+
+    // Two slots claiming the same bytes
+    let bytes = bytes_with_corrupted_slots(&page, |page| {
+        page.slot_directory.slots[4].offset = page.slot_directory.slots[2].offset
+    });
+    assert!(matches!(Page::from_bytes(&bytes), Err(DatabaseError::CorruptedPage { .. })));
+
+    // A slot reaching back into the slot directory
+    let bytes = bytes_with_corrupted_slots(&page, |page| {
+        page.slot_directory.slots[2].offset = PAGE_HEADER_SIZE as u16
+    });
+    match Page::from_bytes(&bytes) {
+        Err(DatabaseError::CorruptedPage { reason, .. }) => assert!(reason.contains("Slot directory"), "{}", reason),
+        other => panic!("Expected a corrupted page, got {:?}", other),
+    }
+
+    // Adjacent cells and the gaps deleted cells leave are fine
+    let mut fragmented = page.clone();
+    fragmented.delete_cell(1).unwrap();
+    fragmented.update_cell(3, &create_sample_row_data(3)[..cell_len as usize - 4], Some(3)).unwrap();
+    assert!(fragmented.validate_structure().is_ok());
+    let loaded = Page::from_bytes(&fragmented.to_bytes().unwrap()).unwrap();
+    assert_eq!(loaded.active_cell_count(), 4);
+
+    // Checked on in-memory pages too
+    let mut overlapping = page.clone();
+    overlapping.slot_directory.slots[0].length = cell_len * 2;
+    overlapping.slot_directory.slots[0].offset -= cell_len;
+    assert!(matches!(overlapping.validate_structure(), Err(DatabaseError::CorruptedPage { .. })));
+}
+
+#[test]
+fn test_consistent_page_loads_without_reconciliation() {
+    let page = page_with_cells(3);
+    let loaded = Page::from_bytes(&page.to_bytes().unwrap()).unwrap();
+    assert!(loaded.reconciliation_warnings.is_empty());
+    assert!(!loaded.is_dirty);
+}
+
+#[test]
+fn test_storage_cost_and_would_fit() {
+    let small = StorageCost::for_cell(100);
+    assert_eq!(small.inline_bytes, 100);
+    assert_eq!(small.page_bytes(), 100 + SLOT_DIRECTORY_ENTRY_SIZE);
+    assert!(!small.needs_overflow);
+    assert_eq!(small.overflow_pages_estimate, 0);
+
+    let large = StorageCost::for_cell(OVERFLOW_THRESHOLD);
+    assert!(large.needs_overflow);
+    assert_eq!(large.inline_bytes, 12);
+    assert_eq!(large.overflow_pages_estimate, 1);
+    assert_eq!(StorageCost::for_cell(OVERFLOW_PAGE_CAPACITY + 1).overflow_pages_estimate, 2);
+
+    // Fill a page until only a few dozen bytes remain
+    let mut page = Page::new(1, PageType::LeafTable);
+    while page.available_space() > 200 {
+        page.insert_cell(&create_test_data(100), None).unwrap();
+    }
+    let remaining = page.available_space();
+    let exact = StorageCost::for_cell(remaining - SLOT_DIRECTORY_ENTRY_SIZE);
+    assert!(page.would_fit(&exact));
+    assert!(!page.would_fit(&StorageCost::for_cell(remaining - SLOT_DIRECTORY_ENTRY_SIZE + 1)));
+    // A cell headed for an overflow page only needs room for its pointer
+    assert!(page.would_fit(&large));
+
+    page.insert_cell(&create_test_data(exact.inline_bytes), None).unwrap();
+    assert_eq!(page.available_space(), 0);
+}
+
+/// A leaf page whose free space is exactly `free` bytes
+fn page_with_free_space(free: usize) -> Page {
+    let mut page = Page::new(1, PageType::LeafTable);
+    let filler = page.available_space() - SLOT_DIRECTORY_ENTRY_SIZE - free;
+    page.insert_cell(&create_test_data(filler), None).unwrap();
+    assert_eq!(page.available_space(), free);
+    page
+}
+
+fn assert_round_trips(page: &Page) {
+    let restored = Page::from_bytes(&page.to_bytes().unwrap()).unwrap();
+    assert_eq!(restored.free_space_offset, page.free_space_offset);
+    assert_eq!(restored.slot_directory.slots.len(), page.slot_directory.slots.len());
+    for i in 0..page.slot_directory.slots.len() {
+        assert_eq!(restored.get_cell(i), page.get_cell(i));
+    }
+}
+
+#[test]
+fn test_exact_fit_boundaries_through_the_page_api() {
+    let cell_len = 100;
+    for leftover in [0, 1, SLOT_DIRECTORY_ENTRY_SIZE - 1, SLOT_DIRECTORY_ENTRY_SIZE] {
+        // Room for the cell, its slot entry and `leftover` more bytes
+        let mut page = page_with_free_space(cell_len + SLOT_DIRECTORY_ENTRY_SIZE + leftover);
+        assert!(page.can_fit(cell_len + leftover), "leftover {}", leftover);
+        assert!(!page.can_fit(cell_len + leftover + 1), "leftover {}", leftover);
+
+        page.insert_cell(&create_test_data(cell_len), None).unwrap();
+        assert_eq!(page.available_space(), leftover);
+        // Only a whole slot entry makes room for another (empty) cell
+        assert_eq!(page.can_fit(0), leftover >= SLOT_DIRECTORY_ENTRY_SIZE, "leftover {}", leftover);
+        let before = page.to_bytes().unwrap();
+        assert!(matches!(
+            page.insert_cell(&create_test_data(leftover + 1), None),
+            Err(DatabaseError::PageFull { .. })
+        ));
+        assert_eq!(page.to_bytes().unwrap(), before, "a rejected insert must not touch the page");
+        assert_round_trips(&page);
+    }
+
+    // The last byte of the page is usable: a cell can end exactly at PAGE_SIZE and one can
+    // start right after the slot directory
+    let mut page = Page::new(1, PageType::LeafTable);
+    let whole = page.available_space() - SLOT_DIRECTORY_ENTRY_SIZE;
+    page.insert_cell(&create_test_data(whole), None).unwrap();
+    assert_eq!(page.available_space(), 0);
+    assert_eq!(page.free_space_offset as usize, PAGE_HEADER_SIZE + SLOT_DIRECTORY_ENTRY_SIZE);
+    assert_round_trips(&page);
+}
+
+#[test]
+fn test_exact_fit_boundaries_for_overflow_pointers() {
+    let large = create_test_data(OVERFLOW_THRESHOLD);
+    for leftover in [0, 1, SLOT_DIRECTORY_ENTRY_SIZE - 1, SLOT_DIRECTORY_ENTRY_SIZE] {
+        let cost = StorageCost::for_cell(large.len());
+        let mut page = page_with_free_space(cost.page_bytes() + leftover);
+        assert!(page.would_fit(&cost));
+        page.insert_cell_with_overflow(&large, Some(1), Some(99)).unwrap();
+        assert_eq!(page.available_space(), leftover);
+        assert!(matches!(
+            page.insert_cell_with_overflow(&large, Some(2), Some(100)),
+            Err(DatabaseError::PageFull { .. })
+        ));
+        assert_round_trips(&page);
+    }
+}
+
+#[test]
+fn test_reclaimable_space_counts_deleted_cells() {
+    let mut page = Page::new(1, PageType::LeafTable);
+    let first = page.insert_cell(&create_test_data(1000), None).unwrap();
+    page.insert_cell(&create_test_data(2900), None).unwrap();
+    assert_eq!(page.reclaimable_space(), 0);
+    page.delete_cell(first).unwrap();
+    assert_eq!(page.reclaimable_space(), 1000);
+
+    let cost = StorageCost::for_cell(900);
+    assert!(!page.would_fit(&cost));
+    assert!(page.fits_after_compaction(&cost));
+    page.compact().unwrap();
+    assert!(page.would_fit(&cost));
+
+    // Growing a cell may use space only compaction can free
+    let mut page = Page::new(1, PageType::LeafTable);
+    let doomed = page.insert_cell(&create_test_data(1500), None).unwrap();
+    let grown = page.insert_cell(&create_test_data(100), Some(7)).unwrap();
+    page.insert_cell(&create_test_data(2300), None).unwrap();
+    page.delete_cell(doomed).unwrap();
+    page.update_cell(grown, &create_test_data(1200), Some(7)).unwrap();
+    assert_eq!(page.get_cell(grown).unwrap().len(), 1200);
+    assert_round_trips(&page);
+}
+
+#[test]
+fn test_sized_pages_round_trip() {
+    for page_size in [512, 8192, 65536] {
+        let mut page = Page::new_with_size(7, PageType::LeafTable, page_size);
+        assert_eq!(page.free_space_offset as usize, page_size);
+        assert_eq!(page.available_space(), page_size - PAGE_HEADER_SIZE);
+
+        // An empty page of the largest size stores its offset as 0
+        let restored = Page::from_bytes(&page.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.page_size, page_size);
+        assert_eq!(restored.free_space_offset, page.free_space_offset);
+
+        let cell = vec![0xAB; page_size / 4];
+        page.insert_cell(&cell, Some(1)).unwrap();
+        let bytes = page.to_bytes().unwrap();
+        assert_eq!(bytes.len(), page_size);
+        let restored = Page::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.get_cell(0).unwrap(), &cell[..]);
+        assert_eq!(restored.free_space_offset as usize, page_size - cell.len());
+
+        let metadata_size = Page::calculate_metadata_size(&bytes).unwrap();
+        let meta = Page::from_header_bytes_with_size(&bytes[..metadata_size], page_size).unwrap();
+        assert_eq!(meta.free_space_offset, restored.free_space_offset);
+        assert_eq!(meta.available_space(), restored.available_space());
+    }
+    assert!(matches!(
+        Page::from_bytes(&vec![0; 3000]),
+        Err(DatabaseError::InvalidPageSize { actual: 3000, .. })
+    ));
+}
+
+#[test]
+fn test_validate_invariants_names_the_broken_page() {
+    let mut page = Page::new(7, PageType::LeafTable);
+    page.insert_cell(&create_sample_row_data(1), Some(1)).unwrap();
+    page.insert_cell(&create_sample_row_data(2), Some(2)).unwrap();
+    assert_invariants(&page);
+
+    page.update_checksum();
+    page.cell_count = 1;
+    match page.validate_invariants() {
+        Err(DatabaseError::InternalInvariant { details }) => {
+            assert!(details.starts_with("page 7 (LeafTable)"), "{}", details);
+            assert!(details.contains("cell_count 1 but 2 slot entries"), "{}", details);
+        }
+        other => panic!("expected an invariant violation, got {:?}", other),
+    }
+
+    page.cell_count = 2;
+    page.slot_directory.slots[0].offset -= 4;
+    page.update_checksum();
+    assert!(matches!(page.validate_invariants(), Err(DatabaseError::InternalInvariant { .. })));
+}
+
+#[test]
+fn test_metadata_only_load_rejects_out_of_range_header() {
+    let mut page = Page::new(9, PageType::LeafTable);
+    page.insert_cell(&create_sample_row_data(1), Some(1)).unwrap();
+    let bytes = page.to_bytes().unwrap();
+    let metadata_size = Page::calculate_metadata_size(&bytes).unwrap();
+
+    let mut header_bytes = bytes[..metadata_size].to_vec();
+    header_bytes[27..29].copy_from_slice(&0xFFFFu16.to_le_bytes());
+    match Page::from_header_bytes(&header_bytes) {
+        Err(DatabaseError::CorruptedPage { page_id, reason }) => {
+            assert_eq!(page_id, 9);
+            assert!(reason.contains("free_space_offset"), "{}", reason);
+        }
+        other => panic!("expected a corrupted page, got {:?}", other),
+    }
+
+    let mut header_bytes = bytes[..metadata_size].to_vec();
+    let slot = PAGE_HEADER_SIZE;
+    header_bytes[slot..slot + 2].copy_from_slice(&(PAGE_SIZE as u16 - 4).to_le_bytes());
+    assert!(matches!(
+        Page::from_header_bytes(&header_bytes),
+        Err(DatabaseError::CorruptedPage { page_id: 9, .. })
+    ));
+}
+
+fn keyed_cell(key: i64) -> Vec<u8> {
+    Row::new(vec![Value::Integer(key), Value::Text(format!("row_{}", key))]).to_bytes()
+}
+
+fn cell_key(cell: &[u8]) -> Result<Value, DatabaseError> {
+    Ok(Row::from_bytes(cell)?.values[0].clone())
+}
+
+#[test]
+fn test_binary_search_key_keeps_cells_in_order_around_deleted_slots() {
+    let mut page = Page::new(4, PageType::LeafTable);
+    for key in [5, 1, 9, 5, 3, 7] {
+        let position = page.binary_search_key(&Value::Integer(key), cell_key).unwrap();
+        page.insert_cell_at(position.end, &keyed_cell(key), None).unwrap();
+    }
+    let keys: Vec<Value> = (0..6).map(|slot| cell_key(page.get_cell(slot).unwrap()).unwrap()).collect();
+    assert_eq!(keys, [1, 3, 5, 5, 7, 9].map(Value::Integer));
+    assert_invariants(&page);
+
+    // Deleted slots are stepped over, both when probed and at either end of the page
+    page.delete_cell(1).unwrap();
+    page.delete_cell(5).unwrap();
+    let search = |key: i64| page.binary_search_key(&Value::Integer(key), cell_key).unwrap();
+    assert_eq!(search(3), SlotPos { start: 2, end: 2 });
+    assert_eq!(search(5), SlotPos { start: 2, end: 4 });
+    assert!(search(5).found() && search(7).found() && !search(9).found());
+    assert_eq!(search(0), SlotPos { start: 0, end: 0 });
+    assert_eq!(search(8), SlotPos { start: 6, end: 6 });
+
+    // On interior pages the NULL bound of the rightmost child comes after every key
+    let mut interior = Page::new(5, PageType::InteriorTable);
+    for bound in [Value::Integer(10), Value::Integer(20), Value::Null] {
+        interior.insert_cell(&bound.to_bytes(), None).unwrap();
+    }
+    let bound = |cell: &[u8]| Value::from_bytes(cell);
+    assert_eq!(interior.binary_search_key(&Value::Integer(20), bound).unwrap(), SlotPos { start: 1, end: 2 });
+    assert_eq!(interior.binary_search_key(&Value::Integer(25), bound).unwrap(), SlotPos { start: 2, end: 2 });
+}
+
+/// Keys of the live cells in slot order
+fn live_keys(page: &Page) -> Vec<i64> {
+    (0..page.slot_directory.slots.len())
+        .filter_map(|slot| page.get_cell(slot))
+        .map(|cell| match cell_key(cell).unwrap() {
+            Value::Integer(key) => key,
+            other => panic!("unexpected key {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_churn_reuses_deleted_slots_and_keeps_key_order() {
+    let mut page = Page::new(6, PageType::LeafTable);
+    let mut keys: Vec<i64> = (0..40).map(|key| key * 1000).collect();
+    for &key in &keys {
+        page.insert_cell(&keyed_cell(key), None).unwrap();
+    }
+    let initial_slots = page.slot_directory.slots.len();
+
+    let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        seed >> 33
+    };
+    for round in 0..10_000 {
+        let victim = keys.remove(next() as usize % keys.len());
+        let position = page.binary_search_key(&Value::Integer(victim), cell_key).unwrap();
+        page.delete_cell(position.start).unwrap();
+
+        let key = (next() % 40_000) as i64;
+        let cell = keyed_cell(key);
+        if !page.can_fit(cell.len()) {
+            page.compact().unwrap();
+        }
+        let position = page.binary_search_key(&Value::Integer(key), cell_key).unwrap();
+        page.insert_cell_at(position.end, &cell, None).unwrap();
+        keys.insert(keys.partition_point(|&existing| existing <= key), key);
+
+        assert!(page.slot_directory.slots.len() <= initial_slots, "round {}", round);
+        assert_eq!(page.cell_count as usize, page.slot_directory.slots.len());
+    }
+    assert_eq!(live_keys(&page), keys);
+    assert_eq!(page.active_cell_count(), 40);
+    assert_invariants(&page);
+    assert_eq!(live_keys(&Page::from_bytes(&page.to_bytes().unwrap()).unwrap()), keys);
+}
+
+#[test]
+fn test_compaction_can_truncate_trailing_deleted_slots() {
+    let mut page = Page::new(7, PageType::LeafTable);
+    for key in 0..6 {
+        page.insert_cell(&keyed_cell(key), None).unwrap();
+    }
+    for slot in [1, 4, 5] {
+        page.delete_cell(slot).unwrap();
+    }
+
+    // Plain compaction keeps every slot so held indices stay valid
+    page.compact().unwrap();
+    assert_eq!(page.slot_directory.slots.len(), 6);
+    page.compact_with(true).unwrap();
+    assert_eq!(page.slot_directory.slots.len(), 4);
+    assert_eq!(page.cell_count, 4);
+    assert_eq!(live_keys(&page), vec![0, 2, 3]);
+    assert!(page.is_slot_deleted(1));
+
+    // An unsorted page fills the remaining gap in place
+    page.sorted = false;
+    assert_eq!(page.insert_cell(&keyed_cell(9), None).unwrap(), 1);
+    assert_eq!(live_keys(&page), vec![0, 9, 2, 3]);
+    assert_invariants(&page);
+}
+
+#[test]
+fn test_sorted_flag_round_trips_and_older_pages_lack_it() {
+    let mut page = page_with_cells(3);
+    assert!(page.sorted);
+    assert!(Page::from_bytes(&page.to_bytes().unwrap()).unwrap().sorted);
+
+    // Pages written before the flag existed have a zero flags byte and a checksum that
+    // never covered it
+    page.sorted = false;
+    page.update_checksum();
+    let bytes = page.to_bytes().unwrap();
+    assert_eq!(bytes[41], 0);
+    let loaded = Page::from_bytes(&bytes).unwrap();
+    assert!(!loaded.sorted);
+    assert!(matches!(
+        loaded.binary_search_key(&Value::Integer(1), cell_key),
+        Err(DatabaseError::InternalInvariant { .. })
+    ));
+
+    let mut unknown = bytes.clone();
+    unknown[41] = 0x80;
+    assert!(matches!(Page::from_bytes(&unknown), Err(DatabaseError::CorruptedPage { page_id: 3, .. })));
+
+    // An overflow pointer carries no key, so the page falls back to linear search
+    let mut page = Page::new(6, PageType::LeafTable);
+    page.insert_cell_with_overflow(&create_test_data(PAGE_SIZE / 2), None, Some(100)).unwrap();
+    assert!(!page.sorted);
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::approx_constant)]
+
 use bambang::types::{error::DatabaseError, row::Row, value::Value};
 
 #[test]
@@ -506,3 +508,71 @@ fn test_serialization_performance_characteristics() {
     );
     assert!(!bytes.is_empty());
 }
+
+#[test]
+fn test_from_bytes_projected_skips_unrequested_values() {
+    let row = Row::with_row_id(
+        7,
+        vec![
+            Value::Integer(1),
+            Value::Blob(vec![0xAB; 2048]),
+            Value::Text("kept".to_string()),
+            Value::Null,
+            Value::Real(2.5),
+        ],
+    );
+    let bytes = row.to_bytes();
+
+    let projected = Row::from_bytes_projected(&bytes, &[4, 2, 0, 2]).unwrap();
+    assert_eq!(projected.row_id, Some(7));
+    assert_eq!(
+        projected.values,
+        vec![
+            Value::Real(2.5),
+            Value::Text("kept".to_string()),
+            Value::Integer(1),
+            Value::Text("kept".to_string()),
+        ]
+    );
+    assert!(Row::from_bytes_projected(&bytes, &[]).unwrap().values.is_empty());
+    assert!(matches!(
+        Row::from_bytes_projected(&bytes, &[5]),
+        Err(DatabaseError::ColumnIndexOutOfBounds { index: 5 })
+    ));
+}
+
+#[test]
+fn test_row_version_round_trip() {
+    let mut row = Row::with_row_id(3, vec![Value::Integer(1), Value::Text("a".to_string())]);
+    let unversioned = row.to_bytes();
+    assert_eq!(Row::from_bytes(&unversioned).unwrap().version, 0);
+
+    // A version only costs space once the row has been updated
+    row.version = 42;
+    let bytes = row.to_bytes();
+    assert_eq!(bytes.len(), unversioned.len() + 8);
+    assert_eq!(bytes.len(), row.size());
+    assert_eq!(Row::from_bytes(&bytes).unwrap(), row);
+    assert_eq!(Row::from_bytes_projected(&bytes, &[1]).unwrap().version, 42);
+
+    let mut without_id = Row::new(vec![Value::Null]);
+    without_id.version = 1;
+    assert_eq!(Row::from_bytes(&without_id.to_bytes()).unwrap(), without_id);
+    assert!(matches!(
+        Row::from_bytes(&bytes[..12]),
+        Err(DatabaseError::SerializationError { .. })
+    ));
+}
+
+#[test]
+fn test_value_count_larger_than_the_bytes_left_is_rejected() {
+    let mut bytes = Row::new(vec![Value::Integer(1), Value::Null]).to_bytes();
+    // Flags byte, then the value count
+    bytes[1..5].copy_from_slice(&u32::MAX.to_le_bytes());
+    for result in [Row::from_bytes(&bytes), Row::from_bytes_projected(&bytes, &[0])] {
+        assert!(matches!(
+            result,
+            Err(DatabaseError::SerializationError { details }) if details.contains("Value count")
+        ));
+    }
+}
@@ -1,4 +1,17 @@
-use bambang::types::value::{DataType, Value};
+#![allow(clippy::approx_constant, clippy::redundant_closure, clippy::useless_vec)]
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+};
+
+use bambang::types::{
+    decimal::Decimal,
+    error::DatabaseError,
+    row::Row,
+    value::{DataType, HashableValue, Value, ValueComparison},
+};
 
 #[test]
 fn test_value_creation_and_data_types() {
@@ -236,3 +249,225 @@ fn test_timestamp_operations() {
     let formatted = ts.format_timestamp("%Y-%m-%d %H:%M:%S");
     assert_eq!(formatted, Some("2022-01-01 00:00:00".to_string()));
 }
+
+#[test]
+fn test_cast_only_allows_lossless_conversions() {
+    assert_eq!(Value::Text(" 42 ".to_string()).cast(&DataType::Integer).unwrap(), Value::Integer(42));
+    assert_eq!(Value::Real(3.0).cast(&DataType::Integer).unwrap(), Value::Integer(3));
+    assert_eq!(Value::Integer(7).cast(&DataType::Real).unwrap(), Value::Real(7.0));
+    assert_eq!(Value::Text("yes".to_string()).cast(&DataType::Boolean).unwrap(), Value::Boolean(true));
+    assert_eq!(
+        Value::Text("2022-01-01".to_string()).cast(&DataType::Timestamp).unwrap(),
+        Value::Timestamp(1640995200)
+    );
+    assert_eq!(Value::Null.cast(&DataType::Integer).unwrap(), Value::Null);
+
+    assert!(Value::Real(1.5).cast(&DataType::Integer).is_err());
+    assert!(Value::Text("42abc".to_string()).cast(&DataType::Integer).is_err());
+    assert!(Value::Integer(2).cast(&DataType::Boolean).is_err());
+    assert!(Value::Integer(i64::MAX).cast(&DataType::Real).is_err());
+    assert!(Value::Blob(vec![0xff, 0xfe]).cast(&DataType::Text).is_err());
+}
+
+#[test]
+fn test_decimal_round_trips_and_orders_exactly() {
+    let parse = |s: &str| Value::from_string(s, &DataType::Decimal).unwrap();
+    for text in ["0", "-0.5", "19.99", "0.10", "12345678901234567890.123456789", "-.25"] {
+        let value = parse(text);
+        let bytes = value.to_bytes();
+        assert_eq!(bytes[0], 7);
+        assert_eq!(bytes.len(), value.serialized_size());
+        let decoded = Value::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_string(), value.to_string());
+    }
+    assert_eq!(parse("0.10").to_string(), "0.10");
+    assert_eq!(parse("-.25").to_string(), "-0.25");
+
+    // 0.1 + 0.2 style sums stay exact where f64 would not
+    assert_eq!(parse("0.30"), parse("0.3"));
+    assert_ne!(parse("0.30000000000000001"), parse("0.3"));
+    assert_eq!(parse("2.00"), Value::Integer(2));
+
+    let mut values = [parse("10.5"), parse("-3"), parse("10.49"), Value::Integer(10), parse("0.001")];
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let rendered: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    assert_eq!(rendered, vec!["-3", "0.001", "10", "10.49", "10.5"]);
+
+    // Rescaling a huge mantissa overflows i128; ordering must still follow magnitude
+    let huge = parse("99999999999999999999999999999999999999");
+    let tiny = parse("0.00000000000000000000000000000000000001");
+    assert!(huge > tiny);
+    assert!(parse("-99999999999999999999999999999999999999") < tiny);
+
+    assert!(Value::from_string("12.3.4", &DataType::Decimal).is_err());
+    assert!(Value::from_string("1e5", &DataType::Decimal).is_err());
+    assert_eq!(DataType::from_string("numeric").unwrap(), DataType::Decimal);
+    assert_eq!(DataType::from_string("DECIMAL").unwrap(), DataType::Decimal);
+    assert_eq!(parse("7.50").cast(&DataType::Integer).ok(), None);
+    assert_eq!(parse("7.00").cast(&DataType::Integer).unwrap(), Value::Integer(7));
+    assert_eq!(Value::Real(0.1).cast(&DataType::Decimal).unwrap().to_string(), "0.1");
+}
+
+#[test]
+fn test_cross_type_comparison_matrix_by_mode() {
+    let text = |s: &str| Value::Text(s.to_string());
+    // (left, right, coercive equal, strict equal, strict ordering)
+    let matrix = [
+        (Value::Integer(1), Value::Integer(1), true, true, Some(Ordering::Equal)),
+        (Value::Integer(1), Value::Real(1.0), true, true, Some(Ordering::Equal)),
+        (Value::Real(2.5), Value::Integer(2), false, false, Some(Ordering::Greater)),
+        (Value::Integer(1), "1.0".parse().map(Value::Decimal).unwrap(), true, true, Some(Ordering::Equal)),
+        (Value::Integer(1), Value::Boolean(true), true, false, None),
+        (Value::Integer(0), Value::Boolean(false), true, false, None),
+        (Value::Integer(1), text("1"), true, false, None),
+        (text("5"), Value::Integer(4), false, false, None),
+        (Value::Real(3.0), text("3"), true, false, None),
+        (Value::Timestamp(10), Value::Integer(10), true, false, None),
+        (text("a"), text("a"), true, true, Some(Ordering::Equal)),
+        (text("a"), Value::Blob(b"a".to_vec()), false, false, None),
+        (Value::Boolean(true), Value::Boolean(true), true, true, Some(Ordering::Equal)),
+        (Value::Null, Value::Null, true, true, Some(Ordering::Equal)),
+        (Value::Null, Value::Integer(0), false, false, None),
+    ];
+    for (left, right, coercive, strict, strict_order) in matrix {
+        assert_eq!(ValueComparison::Coercive.equals(&left, &right), coercive, "{:?} = {:?}", left, right);
+        assert_eq!(ValueComparison::Coercive.equals(&right, &left), coercive, "{:?} = {:?}", right, left);
+        assert_eq!(ValueComparison::Strict.equals(&left, &right), strict, "{:?} = {:?}", left, right);
+        assert_eq!(ValueComparison::Strict.equals(&right, &left), strict, "{:?} = {:?}", right, left);
+        assert_eq!(ValueComparison::Strict.compare(&left, &right), strict_order, "{:?} <> {:?}", left, right);
+    }
+    // Coercive ordering still relates numeric text to numbers
+    assert_eq!(ValueComparison::Coercive.compare(&text("5"), &Value::Integer(4)), Some(Ordering::Greater));
+    assert_eq!(ValueComparison::default(), ValueComparison::Coercive);
+}
+
+fn hash_of(value: Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    HashableValue(value).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn test_hashable_value_canonicalizes_numerics() {
+    let one = HashableValue(Value::Integer(1));
+    assert_eq!(one, HashableValue(Value::Real(1.0)));
+    assert_eq!(one, HashableValue(Value::Decimal(Decimal::new(100, 2).unwrap())));
+    assert_eq!(hash_of(Value::Integer(1)), hash_of(Value::Real(1.0)));
+    assert_eq!(hash_of(Value::Integer(1)), hash_of(Value::Decimal(Decimal::new(100, 2).unwrap())));
+    assert_eq!(hash_of(Value::Real(0.0)), hash_of(Value::Real(-0.0)));
+    assert_eq!(
+        HashableValue(Value::Real(2.5)),
+        HashableValue(Value::Decimal(Decimal::new(25, 1).unwrap()))
+    );
+
+    assert_eq!(HashableValue(Value::Real(f64::NAN)), HashableValue(Value::Real(f64::NAN)));
+    assert_eq!(hash_of(Value::Real(f64::NAN)), hash_of(Value::Real(-f64::NAN)));
+    assert_ne!(HashableValue(Value::Real(1.5)), HashableValue(Value::Integer(1)));
+    // Unlike `Value`'s own `==`, only numerics meet across types
+    assert_ne!(HashableValue(Value::Integer(1)), HashableValue(Value::Boolean(true)));
+    assert_ne!(HashableValue(Value::Integer(5)), HashableValue(Value::Text("5".to_string())));
+    assert_ne!(HashableValue(Value::Integer(7)), HashableValue(Value::Timestamp(7)));
+    assert_eq!(HashableValue(Value::Null), HashableValue(Value::Null));
+    // Exact for integers beyond f64 precision
+    assert_ne!(
+        HashableValue(Value::Integer((1 << 53) + 1)),
+        HashableValue(Value::Real((1u64 << 53) as f64))
+    );
+}
+
+#[test]
+fn test_hashable_value_in_collections() {
+    let values = vec![
+        Value::Integer(3),
+        Value::Real(3.0),
+        Value::Null,
+        Value::Null,
+        Value::Text("a".to_string()),
+        Value::Text("a".to_string()),
+        Value::Blob(vec![1, 2]),
+        Value::Real(f64::NAN),
+        Value::Real(f64::NAN),
+        Value::Boolean(true),
+    ];
+    let distinct: HashSet<HashableValue> = values.iter().cloned().map(HashableValue::from).collect();
+    assert_eq!(distinct.len(), 6);
+
+    let mut counts: HashMap<HashableValue, usize> = HashMap::new();
+    for value in values {
+        *counts.entry(value.into()).or_default() += 1;
+    }
+    assert_eq!(counts[&HashableValue(Value::Integer(3))], 2);
+    assert_eq!(counts[&HashableValue(Value::Null)], 2);
+    assert_eq!(counts[&HashableValue(Value::Real(f64::NAN))], 2);
+    assert_eq!(counts[&HashableValue(Value::Boolean(true))], 1);
+}
+
+#[test]
+fn test_total_cmp_places_infinities_and_nan() {
+    let mut values = [
+        Value::Real(f64::NAN),
+        Value::Integer(i64::MAX),
+        Value::Real(f64::INFINITY),
+        Value::Text("a".to_string()),
+        Value::Real(f64::NEG_INFINITY),
+        Value::Integer(i64::MIN),
+        Value::Null,
+    ];
+    values.sort_by(Value::total_cmp);
+    assert!(values[0].is_null());
+    assert_eq!(values[1], Value::Real(f64::NEG_INFINITY));
+    assert_eq!(values[2], Value::Integer(i64::MIN));
+    assert_eq!(values[3], Value::Integer(i64::MAX));
+    assert_eq!(values[4], Value::Real(f64::INFINITY));
+    assert!(values[5].is_nan());
+    assert_eq!(values[6], Value::Text("a".to_string()));
+    assert_eq!(Value::Real(f64::NAN).total_cmp(&Value::Real(f64::NAN)), Ordering::Equal);
+}
+
+#[test]
+fn test_values_from_rust_types() {
+    assert!(matches!(Value::from(42i64), Value::Integer(42)));
+    assert!(matches!(Value::from(2.5f64), Value::Real(r) if r == 2.5));
+    assert!(matches!(Value::from(true), Value::Boolean(true)));
+    assert!(matches!(Value::from("Alice".to_string()), Value::Text(s) if s == "Alice"));
+    assert!(matches!(Value::from("Bob"), Value::Text(s) if s == "Bob"));
+    assert!(matches!(Value::from(vec![1u8, 2, 3]), Value::Blob(b) if b == [1, 2, 3]));
+
+    let row = Row::new(vec![1i64.into(), "Alice".into()]);
+    assert_eq!(row.values, vec![Value::Integer(1), Value::Text("Alice".to_string())]);
+}
+
+#[test]
+fn test_rust_types_try_from_values() -> Result<(), DatabaseError> {
+    assert_eq!(i64::try_from(Value::Integer(-7))?, -7);
+    assert_eq!(f64::try_from(Value::Real(0.25))?, 0.25);
+    assert!(bool::try_from(Value::Boolean(true))?);
+    assert_eq!(String::try_from(Value::Text("hi".to_string()))?, "hi");
+    assert_eq!(Vec::<u8>::try_from(Value::Blob(vec![9, 8]))?, vec![9, 8]);
+
+    // Round trips through `into`
+    let name: String = Value::from("Carol").try_into()?;
+    assert_eq!(name, "Carol");
+    let id: i64 = Value::from(11i64).try_into()?;
+    assert_eq!(id, 11);
+    Ok(())
+}
+
+#[test]
+fn test_try_from_value_rejects_other_types() {
+    let err = i64::try_from(Value::Text("1".to_string())).unwrap_err();
+    match err {
+        DatabaseError::TypeMismatch { expected, actual } => {
+            assert_eq!(expected, "INTEGER");
+            assert_eq!(actual, "TEXT");
+        }
+        other => panic!("Expected a type mismatch, got {:?}", other),
+    }
+    // Conversions are exact, never casts
+    assert!(i64::try_from(Value::Real(1.0)).is_err());
+    assert!(f64::try_from(Value::Integer(1)).is_err());
+    assert!(i64::try_from(Value::Timestamp(1)).is_err());
+    assert!(String::try_from(Value::Null).is_err());
+    assert!(bool::try_from(Value::Integer(1)).is_err());
+    assert!(Vec::<u8>::try_from(Value::Text("bytes".to_string())).is_err());
+}
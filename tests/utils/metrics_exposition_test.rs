@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bambang::{
+    executor::statement::execute_statement,
+    types::error::DatabaseError,
+    utils::{
+        metrics_exposition::{EXPOSITION_CONTENT_TYPE, QueryMetrics, render_metrics, serve_metrics},
+        mock::TempDatabase,
+    },
+};
+
+/// Samples of an exposition keyed by name and labels, checking the format on the way:
+/// every sample belongs to a family declared with HELP and TYPE, and has a number value
+fn parse_exposition(text: &str) -> HashMap<String, f64> {
+    let mut families = HashMap::new();
+    let mut samples = HashMap::new();
+    for line in text.lines() {
+        if let Some(help) = line.strip_prefix("# HELP ") {
+            let (name, description) = help.split_once(' ').expect("HELP has a description");
+            assert!(!description.is_empty(), "{}", line);
+            families.insert(name.to_string(), None);
+        } else if let Some(kind) = line.strip_prefix("# TYPE ") {
+            let (name, kind) = kind.split_once(' ').expect("TYPE has a kind");
+            assert!(["counter", "gauge", "histogram"].contains(&kind), "{}", line);
+            assert!(families.insert(name.to_string(), Some(kind.to_string())).is_some(), "TYPE before HELP: {}", line);
+        } else {
+            let (series, value) = line.rsplit_once(' ').expect("sample has a value");
+            let name = series.split('{').next().unwrap();
+            assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'), "{}", line);
+            if let Some(labels) = series.strip_prefix(name) {
+                assert!(labels.is_empty() || (labels.starts_with('{') && labels.ends_with('}')), "{}", line);
+            }
+            let family = ["_bucket", "_sum", "_count"]
+                .iter()
+                .find_map(|suffix| name.strip_suffix(suffix).filter(|family| families.contains_key(*family)))
+                .unwrap_or(name);
+            assert!(matches!(families.get(family), Some(Some(_))), "undeclared family: {}", line);
+            samples.insert(series.to_string(), value.parse::<f64>().expect("sample value is a number"));
+        }
+    }
+    samples
+}
+
+#[test]
+fn test_metrics_render_in_exposition_format_after_workload() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("metrics_exposition");
+    let storage = temp_db.create_storage_manager().unwrap();
+    let mut queries = QueryMetrics::default();
+    let before = parse_exposition(&render_metrics(storage, &queries)?);
+    for sql in [
+        "CREATE TABLE users(id INTEGER PRIMARY KEY, name TEXT)",
+        "INSERT INTO users VALUES (1, 'Ann'), (2, 'Bob'), (3, 'Cy')",
+        "INSERT INTO users VALUES (4, 'Di')",
+        "DELETE FROM users WHERE id = 2",
+        "select * from users",
+    ] {
+        let started = Instant::now();
+        execute_statement(storage, sql)?;
+        queries.record(sql, started.elapsed());
+    }
+    let after = parse_exposition(&render_metrics(storage, &queries)?);
+
+    assert_eq!(after["bambang_queries_total{kind=\"create\"}"], 1.0);
+    assert_eq!(after["bambang_queries_total{kind=\"insert\"}"], 2.0);
+    assert_eq!(after["bambang_queries_total{kind=\"delete\"}"], 1.0);
+    assert_eq!(after["bambang_queries_total{kind=\"select\"}"], 1.0);
+    assert_eq!(after["bambang_table_rows{table=\"users\"}"], 3.0);
+    assert!(after["bambang_pages_read_total"] > before["bambang_pages_read_total"]);
+    assert!(after["bambang_bytes_written_total"] > before["bambang_bytes_written_total"]);
+    assert!(after["bambang_database_size_bytes"] > before["bambang_database_size_bytes"]);
+    assert!(after.contains_key("bambang_freelist_pages"));
+    assert!(after.contains_key("bambang_page_writes_total{kind=\"partial\"}"));
+
+    // Buckets are cumulative and end with every statement
+    let mut buckets: Vec<f64> = after
+        .iter()
+        .filter(|(series, _)| series.starts_with("bambang_query_duration_seconds_bucket"))
+        .map(|(_, count)| *count)
+        .collect();
+    buckets.sort_by(|a, b| a.total_cmp(b));
+    assert_eq!(buckets.len(), 9);
+    assert_eq!(after["bambang_query_duration_seconds_bucket{le=\"+Inf\"}"], 5.0);
+    assert_eq!(*buckets.last().unwrap(), 5.0);
+    assert_eq!(after["bambang_query_duration_seconds_count"], 5.0);
+    assert!(after["bambang_query_duration_seconds_sum"] > 0.0);
+    Ok(())
+}
+
+#[test]
+fn test_query_duration_buckets_are_cumulative() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("metrics_buckets");
+    let storage = temp_db.create_storage_manager().unwrap();
+    let mut queries = QueryMetrics::default();
+    queries.record("SELECT 1", Duration::from_micros(500));
+    queries.record("SELECT 2", Duration::from_millis(20));
+    queries.record("UPDATE t SET a = 1", Duration::from_secs(10));
+
+    let samples = parse_exposition(&render_metrics(storage, &queries)?);
+    assert_eq!(samples["bambang_query_duration_seconds_bucket{le=\"0.001\"}"], 1.0);
+    assert_eq!(samples["bambang_query_duration_seconds_bucket{le=\"0.01\"}"], 1.0);
+    assert_eq!(samples["bambang_query_duration_seconds_bucket{le=\"0.05\"}"], 2.0);
+    assert_eq!(samples["bambang_query_duration_seconds_bucket{le=\"5\"}"], 2.0);
+    assert_eq!(samples["bambang_query_duration_seconds_bucket{le=\"+Inf\"}"], 3.0);
+    assert_eq!(samples["bambang_queries_total{kind=\"update\"}"], 1.0);
+    Ok(())
+}
+
+#[test]
+fn test_metrics_endpoint_serves_latest_snapshot() -> Result<(), DatabaseError> {
+    let mut temp_db = TempDatabase::with_prefix("metrics_endpoint");
+    let storage = temp_db.create_storage_manager().unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let snapshot = Arc::new(Mutex::new(String::new()));
+    serve_metrics(listener, snapshot.clone());
+
+    let scrape = || -> std::io::Result<String> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    };
+
+    let mut queries = QueryMetrics::default();
+    execute_statement(storage, "CREATE TABLE t(id INTEGER)")?;
+    queries.record("CREATE TABLE t(id INTEGER)", Duration::from_millis(1));
+    *snapshot.lock().unwrap() = render_metrics(storage, &queries)?;
+    let response = scrape()?;
+    let (head, body) = response.split_once("\r\n\r\n").expect("response has a body");
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{}", head);
+    assert!(head.contains(&format!("Content-Type: {}", EXPOSITION_CONTENT_TYPE)), "{}", head);
+    assert!(head.contains(&format!("Content-Length: {}", body.len())), "{}", head);
+    assert_eq!(parse_exposition(body)["bambang_table_rows{table=\"t\"}"], 0.0);
+
+    execute_statement(storage, "INSERT INTO t VALUES (1), (2)")?;
+    *snapshot.lock().unwrap() = render_metrics(storage, &queries)?;
+    let response = scrape()?;
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    assert_eq!(parse_exposition(body)["bambang_table_rows{table=\"t\"}"], 2.0);
+    Ok(())
+}
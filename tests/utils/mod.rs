@@ -0,0 +1,2 @@
+pub mod metrics_exposition_test;
+pub mod result_format_test;
@@ -0,0 +1,106 @@
+use bambang::{
+    types::{row::Row, value::Value},
+    utils::result_format::{OutputMode, ResultFormatter},
+};
+
+fn columns(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn sample_rows() -> Vec<Row> {
+    vec![
+        Row::new(vec![
+            Value::Integer(1),
+            Value::Text("Zoë".to_string()),
+            Value::Null,
+        ]),
+        Row::new(vec![
+            Value::Integer(200),
+            Value::Text("日本".to_string()),
+            Value::Blob(vec![0xde, 0xad, 0xbe, 0xef]),
+        ]),
+    ]
+}
+
+#[test]
+fn test_table_aligns_columns_and_shows_null_and_blobs() {
+    let output = ResultFormatter::new(OutputMode::Table)
+        .format(&columns(&["id", "name", "data"]), &sample_rows());
+    let expected = "\
++-----+------+---------------+
+| id  | name | data          |
++-----+------+---------------+
+|   1 | Zoë  | NULL          |
+| 200 | 日本 | BLOB(4 bytes) |
++-----+------+---------------+
+";
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_table_truncates_long_cells_on_character_boundaries() {
+    let rows = vec![Row::new(vec![Value::Text("ééééééééééé\nnext".to_string())])];
+    let output = ResultFormatter::new(OutputMode::Table)
+        .with_max_cell_width(6)
+        .format(&columns(&["note"]), &rows);
+    assert!(output.contains("| ééééé… |"), "{}", output);
+    assert!(!output.contains("next"));
+
+    let short = vec![Row::new(vec![Value::Text("a\nb".to_string())])];
+    let output = ResultFormatter::new(OutputMode::Table).format(&columns(&["note"]), &short);
+    assert!(output.contains("| a\\nb |"), "{}", output);
+
+    // Wide characters count two columns each, so only two fit before the ellipsis
+    let wide = vec![Row::new(vec![Value::Text("日本語のテキスト".to_string())])];
+    let output = ResultFormatter::new(OutputMode::Table)
+        .with_max_cell_width(6)
+        .format(&columns(&["note"]), &wide);
+    assert!(output.contains("| 日本… |"), "{}", output);
+}
+
+#[test]
+fn test_table_without_rows_prints_only_the_header() {
+    let output = ResultFormatter::new(OutputMode::Table).format(&columns(&["id"]), &[]);
+    assert_eq!(output, "+----+\n| id |\n+----+\n");
+}
+
+#[test]
+fn test_csv_quotes_and_escapes_fields() {
+    let rows = vec![
+        Row::new(vec![
+            Value::Text("plain".to_string()),
+            Value::Text("a,b".to_string()),
+            Value::Text("say \"hi\"".to_string()),
+        ]),
+        Row::new(vec![
+            Value::Null,
+            Value::Text(String::new()),
+            Value::Text("line\nbreak".to_string()),
+        ]),
+    ];
+    let output = ResultFormatter::new(OutputMode::Csv).format(&columns(&["x", "y,z", "w"]), &rows);
+    let expected = "x,\"y,z\",w\nplain,\"a,b\",\"say \"\"hi\"\"\"\n,\"\",\"line\nbreak\"\n";
+    assert_eq!(output, expected);
+
+    let output = ResultFormatter::new(OutputMode::Csv).format(&columns(&["id", "name", "data"]), &sample_rows());
+    assert_eq!(output, "id,name,data\n1,Zoë,\n200,日本,deadbeef\n");
+}
+
+#[test]
+fn test_json_emits_one_object_per_row_in_column_order() {
+    let output = ResultFormatter::new(OutputMode::Json).format(&columns(&["id", "name", "data"]), &sample_rows());
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], r#"{"id": 1, "name": "Zoë", "data": null}"#);
+    let second: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+    assert_eq!(second["name"], "日本");
+    assert_eq!(second["data"], "deadbeef");
+}
+
+#[test]
+fn test_output_mode_parses_names() {
+    assert_eq!("CSV".parse::<OutputMode>(), Ok(OutputMode::Csv));
+    assert_eq!("json".parse::<OutputMode>(), Ok(OutputMode::Json));
+    assert_eq!("table".parse::<OutputMode>().map(|mode| mode.name()), Ok("table"));
+    assert!("xml".parse::<OutputMode>().is_err());
+}